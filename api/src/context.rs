@@ -11,7 +11,7 @@ use anyhow::{ensure, format_err, Context as AnyhowContext, Result};
 use aptos_api_types::{
     AptosErrorCode, AsConverter, BcsBlock, GasEstimation, LedgerInfo, TransactionOnChainData,
 };
-use aptos_config::config::{NodeConfig, RoleType};
+use aptos_config::config::{ApiConfig, NodeConfig, RoleType};
 use aptos_crypto::HashValue;
 use aptos_gas::{AptosGasParameters, FromOnChainGasSchedule};
 use aptos_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
@@ -47,10 +47,47 @@ pub struct Context {
     pub db: Arc<dyn DbReader>,
     mp_sender: MempoolClientSender,
     pub node_config: NodeConfig,
+    api_limits: ApiLimits,
     gas_estimation: Arc<RwLock<GasEstimationCache>>,
     gas_schedule_cache: Arc<RwLock<GasScheduleCache>>,
 }
 
+/// The limits of the API that are reloaded with the node config. They're shared by all the clones
+/// of the context and with the node, so they take effect without restarting the API.
+#[derive(Clone, Debug)]
+pub struct ApiLimits(Arc<RwLock<ApiLimitValues>>);
+
+#[derive(Clone, Copy, Debug)]
+struct ApiLimitValues {
+    max_submit_transaction_batch_size: usize,
+    max_transactions_page_size: u16,
+    max_events_page_size: u16,
+}
+
+impl ApiLimits {
+    pub fn new(config: &ApiConfig) -> Self {
+        Self(Arc::new(RwLock::new(ApiLimitValues::from(config))))
+    }
+
+    pub fn reload(&self, config: &ApiConfig) {
+        *self.0.write().unwrap() = ApiLimitValues::from(config);
+    }
+
+    fn get(&self) -> ApiLimitValues {
+        *self.0.read().unwrap()
+    }
+}
+
+impl From<&ApiConfig> for ApiLimitValues {
+    fn from(config: &ApiConfig) -> Self {
+        Self {
+            max_submit_transaction_batch_size: config.max_submit_transaction_batch_size,
+            max_transactions_page_size: config.max_transactions_page_size,
+            max_events_page_size: config.max_events_page_size,
+        }
+    }
+}
+
 impl std::fmt::Debug for Context {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "Context<chain_id: {}>", self.chain_id)
@@ -68,6 +105,7 @@ impl Context {
             chain_id,
             db,
             mp_sender,
+            api_limits: ApiLimits::new(&node_config.api),
             node_config,
             gas_estimation: Arc::new(RwLock::new(GasEstimationCache {
                 last_updated_version: None,
@@ -83,12 +121,18 @@ impl Context {
         }
     }
 
+    /// Shares the given limits instead of those of the node config
+    pub fn with_api_limits(mut self, api_limits: ApiLimits) -> Self {
+        self.api_limits = api_limits;
+        self
+    }
+
     pub fn max_transactions_page_size(&self) -> u16 {
-        self.node_config.api.max_transactions_page_size
+        self.api_limits.get().max_transactions_page_size
     }
 
     pub fn max_events_page_size(&self) -> u16 {
-        self.node_config.api.max_events_page_size
+        self.api_limits.get().max_events_page_size
     }
 
     pub fn move_resolver(&self) -> Result<StorageAdapterOwned<DbStateView>> {
@@ -127,7 +171,7 @@ impl Context {
     }
 
     pub fn max_submit_transaction_batch_size(&self) -> usize {
-        self.node_config.api.max_submit_transaction_batch_size
+        self.api_limits.get().max_submit_transaction_batch_size
     }
 
     pub async fn submit_transaction(&self, txn: SignedTransaction) -> Result<SubmissionStatus> {
@@ -349,7 +393,7 @@ impl Context {
 
         // We can only get the max_transactions page size
         let max_txns = std::cmp::min(
-            self.node_config.api.max_transactions_page_size,
+            self.max_transactions_page_size(),
             (last_version - first_version + 1) as u16,
         );
         let txns = if with_transactions {
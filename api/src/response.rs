@@ -549,6 +549,23 @@ pub fn api_disabled<S: Display, E: ForbiddenError>(identifier: S) -> E {
     )
 }
 
+pub fn node_draining<S: Display, E: ServiceUnavailableError>(identifier: S) -> E {
+    E::service_unavailable_with_code_no_info(
+        &format!("{} is disabled while the node shuts down", identifier),
+        AptosErrorCode::ApiDisabled,
+    )
+}
+
+pub fn storage_low_on_space<S: Display, E: InsufficientStorageError>(identifier: S) -> E {
+    E::insufficient_storage_with_code_no_info(
+        &format!(
+            "{} is disabled while the storage of the node is low on space",
+            identifier
+        ),
+        AptosErrorCode::ApiDisabled,
+    )
+}
+
 pub fn version_not_found<E: NotFoundError>(ledger_version: u64, ledger_info: &LedgerInfo) -> E {
     build_not_found(
         "Ledger version",
@@ -13,9 +13,10 @@ use crate::{
     generate_error_response, generate_success_response,
     page::Page,
     response::{
-        api_disabled, transaction_not_found_by_hash, transaction_not_found_by_version,
-        BadRequestError, BasicError, BasicErrorWith404, BasicResponse, BasicResponseStatus,
-        BasicResult, BasicResultWith404, InsufficientStorageError, InternalError,
+        api_disabled, node_draining, storage_low_on_space, transaction_not_found_by_hash,
+        transaction_not_found_by_version, BadRequestError, BasicError, BasicErrorWith404,
+        BasicResponse, BasicResponseStatus, BasicResult, BasicResultWith404,
+        InsufficientStorageError, InternalError,
     },
     ApiTags,
 };
@@ -29,6 +30,7 @@ use aptos_api_types::{
     MAX_RECURSIVE_TYPES_ALLOWED, U64,
 };
 use aptos_crypto::{hash::CryptoHash, signing_message};
+use aptos_tracing::{Stage, TraceContext};
 use aptos_types::{
     account_config::CoinStoreResource,
     account_view::AccountView,
@@ -40,12 +42,13 @@ use aptos_types::{
     vm_status::StatusCode,
 };
 use aptos_vm::AptosVM;
+use poem::Request;
 use poem_openapi::{
     param::{Path, Query},
     payload::Json,
     ApiRequest, OpenApi,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::SystemTime};
 
 generate_success_response!(SubmitTransactionResponse, (202, Accepted));
 
@@ -293,7 +296,9 @@ impl TransactionsApi {
         &self,
         accept_type: AcceptType,
         data: SubmitTransactionPost,
+        request: &Request,
     ) -> SubmitTransactionResult<PendingTransaction> {
+        let start = SystemTime::now();
         data.verify()
             .context("Submitted transaction invalid'")
             .map_err(|err| {
@@ -308,10 +313,41 @@ impl TransactionsApi {
         if !self.context.node_config.api.transaction_submission_enabled {
             return Err(api_disabled("Submit transaction"));
         }
+        if aptos_drain::is_draining() {
+            return Err(node_draining("Submit transaction"));
+        }
+        if aptos_storage_watchdog::api_writes_rejected() {
+            return Err(storage_low_on_space("Submit transaction"));
+        }
         let ledger_info = self.context.get_latest_ledger_info()?;
         let signed_transaction = self.get_signed_transaction(&ledger_info, data)?;
-        self.create(&accept_type, &ledger_info, signed_transaction)
-            .await
+
+        // Trace the transaction if it's sampled, continuing the trace of the request if any
+        let parent = request
+            .headers()
+            .get(aptos_tracing::TRACEPARENT_HEADER)
+            .and_then(|header| header.to_str().ok())
+            .and_then(TraceContext::from_traceparent);
+        let traced_txn = aptos_tracing::start_trace(
+            || signed_transaction.clone().committed_hash(),
+            parent,
+            start,
+        );
+        let result = self
+            .create(&accept_type, &ledger_info, signed_transaction)
+            .await;
+        if let Some(txn_hash) = traced_txn {
+            let end = SystemTime::now();
+            aptos_tracing::record_span(&txn_hash, Stage::Api, start, end, vec![]);
+            if result.is_err() {
+                aptos_tracing::end_trace(
+                    &txn_hash,
+                    end,
+                    Some("Transaction was rejected".to_string()),
+                );
+            }
+        }
+        result
     }
 
     /// Submit batch transactions
@@ -361,6 +397,12 @@ impl TransactionsApi {
         if !self.context.node_config.api.transaction_submission_enabled {
             return Err(api_disabled("Submit batch transaction"));
         }
+        if aptos_drain::is_draining() {
+            return Err(node_draining("Submit batch transaction"));
+        }
+        if aptos_storage_watchdog::api_writes_rejected() {
+            return Err(storage_low_on_space("Submit batch transaction"));
+        }
         let ledger_info = self.context.get_latest_ledger_info()?;
         let signed_transactions_batch = self.get_signed_transactions_batch(&ledger_info, data)?;
         if self.context.max_submit_transaction_batch_size() < signed_transactions_batch.len() {
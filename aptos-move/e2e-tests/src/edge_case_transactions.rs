@@ -0,0 +1,369 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structurally valid but unusual transactions, to check how the VM handles edge cases.
+//!
+//! Each transaction is generated from a mix of edge cases (gas amounts and prices around their
+//! bounds, sequence numbers around the one of the sender, expired transactions, wrong chain ids,
+//! bad signatures, huge payloads and exotic type arguments). The transactions are validated and
+//! executed on two executors, and the results are checked against a model of the checks the VM
+//! runs before executing a transaction:
+//!
+//! * the VM doesn't panic, and both executors return the same results;
+//! * a transaction rejected by the validator is rejected with the status of the first check it
+//!   fails, and is discarded by the execution with the same status;
+//! * a transaction accepted by the validator isn't discarded by the execution for a validation
+//!   error, except for sequence numbers that are too new;
+//! * a kept transaction doesn't use more than its maximum amount of gas.
+
+use crate::{account::Account, common_transactions::EMPTY_SCRIPT, executor::FakeExecutor};
+use aptos_crypto::SigningKey;
+use aptos_gas::{FeePerGasUnit, Gas, InitialGasSchedule, TransactionGasParameters};
+use aptos_keygen::KeyGen;
+use aptos_types::{
+    account_address::AccountAddress,
+    chain_id::ChainId,
+    transaction::{
+        RawTransaction, Script, SignedTransaction, TransactionArgument, TransactionPayload,
+        TransactionStatus,
+    },
+    utility_coin::APTOS_COIN_TYPE,
+    vm_status::{StatusCode, StatusType},
+};
+use cached_packages::aptos_stdlib;
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{StructTag, TypeTag},
+};
+use proptest::{collection::vec, prelude::*};
+use proptest_derive::Arbitrary;
+
+/// The sequence number of a transaction, relative to the one of its sender
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum SequenceNumberCase {
+    Old,
+    Current,
+    Next,
+    Max,
+}
+
+/// The maximum amount of gas of a transaction, relative to the bounds of the gas schedule
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum GasAmountCase {
+    Zero,
+    BelowIntrinsic,
+    Intrinsic,
+    Maximum,
+    AboveMaximum,
+    Max,
+    Any(#[proptest(strategy = "0u64..4_000_000")] u64),
+}
+
+/// The gas unit price of a transaction, relative to the bounds of the gas schedule
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum GasPriceCase {
+    Zero,
+    Minimum,
+    Maximum,
+    AboveMaximum,
+    Max,
+}
+
+/// How a transaction is signed
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum SignatureCase {
+    Valid,
+    /// The signature of another transaction
+    Invalid,
+    /// A valid signature, by a key which isn't the one of the sender
+    OtherKey,
+}
+
+/// A type argument of `0x1::coin::transfer`
+#[derive(Arbitrary, Clone, Debug)]
+pub enum TypeArgCase {
+    AptosCoin,
+    Signer,
+    NestedVector(#[proptest(strategy = "1usize..=128")] usize),
+    /// A struct of a module which doesn't exist, with many type arguments
+    MissingStruct(#[proptest(strategy = "0usize..=64")] usize),
+}
+
+impl TypeArgCase {
+    fn type_tag(&self) -> TypeTag {
+        match self {
+            TypeArgCase::AptosCoin => APTOS_COIN_TYPE.clone(),
+            TypeArgCase::Signer => TypeTag::Signer,
+            TypeArgCase::NestedVector(depth) => {
+                (0..*depth).fold(TypeTag::U8, |type_tag| TypeTag::Vector(Box::new(type_tag)))
+            }
+            TypeArgCase::MissingStruct(num_type_params) => TypeTag::Struct(Box::new(StructTag {
+                address: AccountAddress::from_hex_literal("0xcafe").unwrap(),
+                module: Identifier::new("missing").unwrap(),
+                name: Identifier::new("Coin").unwrap(),
+                type_params: vec![TypeTag::U64; *num_type_params],
+            })),
+        }
+    }
+}
+
+#[derive(Arbitrary, Clone, Debug)]
+pub enum PayloadCase {
+    EmptyScript,
+    /// A script which isn't a valid module
+    Garbage(#[proptest(strategy = "vec(any::<u8>(), 0..256)")] Vec<u8>),
+    Transfer(TypeArgCase, u64),
+    /// An argument whose size is the maximum size of a transaction, plus an offset
+    Large(#[proptest(strategy = "-1024i64..1024")] i64),
+    /// An argument much larger than the maximum size of a transaction
+    Huge,
+}
+
+impl PayloadCase {
+    fn payload(&self, sender: AccountAddress, max_size: u64) -> TransactionPayload {
+        let empty_script =
+            |args| TransactionPayload::Script(Script::new(EMPTY_SCRIPT.to_vec(), vec![], args));
+        match self {
+            PayloadCase::EmptyScript => empty_script(vec![]),
+            PayloadCase::Garbage(code) => {
+                TransactionPayload::Script(Script::new(code.clone(), vec![], vec![]))
+            }
+            PayloadCase::Transfer(type_arg, amount) => {
+                aptos_stdlib::coin_transfer(type_arg.type_tag(), sender, *amount)
+            }
+            PayloadCase::Large(offset) => {
+                let len = (max_size as i64 + offset).max(0) as usize;
+                empty_script(vec![TransactionArgument::U8Vector(vec![0; len])])
+            }
+            PayloadCase::Huge => {
+                let len = 16 * max_size as usize;
+                empty_script(vec![TransactionArgument::U8Vector(vec![0; len])])
+            }
+        }
+    }
+}
+
+/// A transaction made of edge cases, sent by a fresh account
+#[derive(Arbitrary, Clone, Debug)]
+pub struct EdgeCaseTransactionGen {
+    #[proptest(strategy = "prop_oneof![Just(0), 1u64..1_000, Just(u64::MAX)]")]
+    account_sequence_number: u64,
+    #[proptest(strategy = "prop_oneof![Just(0), 1u64..1_000_000, Just(u64::MAX)]")]
+    balance: u64,
+    sequence_number: SequenceNumberCase,
+    max_gas_amount: GasAmountCase,
+    gas_unit_price: GasPriceCase,
+    expired: bool,
+    wrong_chain_id: bool,
+    signature: SignatureCase,
+    payload: PayloadCase,
+}
+
+impl EdgeCaseTransactionGen {
+    fn sign(
+        &self,
+        sender: &Account,
+        txn_gas_params: &TransactionGasParameters,
+    ) -> SignedTransaction {
+        let sequence_number = match self.sequence_number {
+            SequenceNumberCase::Old => self.account_sequence_number.wrapping_sub(1),
+            SequenceNumberCase::Current => self.account_sequence_number,
+            SequenceNumberCase::Next => self.account_sequence_number.wrapping_add(1),
+            SequenceNumberCase::Max => u64::MAX,
+        };
+        let payload = self.payload.payload(
+            *sender.address(),
+            u64::from(txn_gas_params.max_transaction_size_in_bytes),
+        );
+        let expiration_timestamp_secs = if self.expired { 0 } else { u64::MAX };
+        let chain_id = if self.wrong_chain_id {
+            ChainId::new(ChainId::test().id().wrapping_add(1))
+        } else {
+            ChainId::test()
+        };
+        let raw_txn = |max_gas_amount| {
+            RawTransaction::new(
+                *sender.address(),
+                sequence_number,
+                payload.clone(),
+                max_gas_amount,
+                self.gas_unit_price(txn_gas_params),
+                expiration_timestamp_secs,
+                chain_id,
+            )
+        };
+
+        // The size of a transaction doesn't depend on its amount of gas
+        let size = bcs::to_bytes(&raw_txn(0)).unwrap().len() as u64;
+        let intrinsic_gas = u64::from(intrinsic_gas(txn_gas_params, size));
+        let maximum = u64::from(txn_gas_params.maximum_number_of_gas_units);
+        let max_gas_amount = match self.max_gas_amount {
+            GasAmountCase::Zero => 0,
+            GasAmountCase::BelowIntrinsic => intrinsic_gas.saturating_sub(1),
+            GasAmountCase::Intrinsic => intrinsic_gas,
+            GasAmountCase::Maximum => maximum,
+            GasAmountCase::AboveMaximum => maximum + 1,
+            GasAmountCase::Max => u64::MAX,
+            GasAmountCase::Any(max_gas_amount) => max_gas_amount,
+        };
+
+        match self.signature {
+            SignatureCase::Valid => raw_txn(max_gas_amount)
+                .sign(&sender.privkey, sender.pubkey.clone())
+                .unwrap()
+                .into_inner(),
+            SignatureCase::Invalid => {
+                let other_raw_txn = raw_txn(max_gas_amount.wrapping_add(1));
+                let signature = sender.privkey.sign(&other_raw_txn).unwrap();
+                SignedTransaction::new(raw_txn(max_gas_amount), sender.pubkey.clone(), signature)
+            }
+            SignatureCase::OtherKey => {
+                let other = Account::new_from_seed(&mut KeyGen::from_seed([1; 32]));
+                raw_txn(max_gas_amount)
+                    .sign(&other.privkey, other.pubkey)
+                    .unwrap()
+                    .into_inner()
+            }
+        }
+    }
+
+    fn gas_unit_price(&self, txn_gas_params: &TransactionGasParameters) -> u64 {
+        let maximum = u64::from(txn_gas_params.max_price_per_gas_unit);
+        match self.gas_unit_price {
+            GasPriceCase::Zero => 0,
+            GasPriceCase::Minimum => u64::from(txn_gas_params.min_price_per_gas_unit),
+            GasPriceCase::Maximum => maximum,
+            GasPriceCase::AboveMaximum => maximum + 1,
+            GasPriceCase::Max => u64::MAX,
+        }
+    }
+
+    /// The status of the first check the transaction fails, in the order the VM runs them: the
+    /// signature, the gas checks of the adapter and then the checks of the Move prologue.
+    fn expected_status(
+        &self,
+        txn: &SignedTransaction,
+        txn_gas_params: &TransactionGasParameters,
+    ) -> Option<StatusCode> {
+        let size = txn.raw_txn_bytes_len() as u64;
+        let max_gas_amount = Gas::from(txn.max_gas_amount());
+        let gas_unit_price = FeePerGasUnit::from(txn.gas_unit_price());
+        let status = if let SignatureCase::Invalid = self.signature {
+            StatusCode::INVALID_SIGNATURE
+        } else if size > u64::from(txn_gas_params.max_transaction_size_in_bytes) {
+            StatusCode::EXCEEDED_MAX_TRANSACTION_SIZE
+        } else if max_gas_amount > txn_gas_params.maximum_number_of_gas_units {
+            StatusCode::MAX_GAS_UNITS_EXCEEDS_MAX_GAS_UNITS_BOUND
+        } else if max_gas_amount < intrinsic_gas(txn_gas_params, size) {
+            StatusCode::MAX_GAS_UNITS_BELOW_MIN_TRANSACTION_GAS_UNITS
+        } else if gas_unit_price < txn_gas_params.min_price_per_gas_unit {
+            StatusCode::GAS_UNIT_PRICE_BELOW_MIN_BOUND
+        } else if gas_unit_price > txn_gas_params.max_price_per_gas_unit {
+            StatusCode::GAS_UNIT_PRICE_ABOVE_MAX_BOUND
+        } else if self.expired {
+            StatusCode::TRANSACTION_EXPIRED
+        } else if self.wrong_chain_id {
+            StatusCode::BAD_CHAIN_ID
+        } else if let SignatureCase::OtherKey = self.signature {
+            StatusCode::INVALID_AUTH_KEY
+        } else if txn.sequence_number() == u64::MAX {
+            StatusCode::SEQUENCE_NUMBER_TOO_BIG
+        } else if txn.sequence_number() < self.account_sequence_number {
+            StatusCode::SEQUENCE_NUMBER_TOO_OLD
+        } else if txn.sequence_number() > self.account_sequence_number {
+            StatusCode::SEQUENCE_NUMBER_TOO_NEW
+        } else if (txn.gas_unit_price() as u128) * (txn.max_gas_amount() as u128)
+            > self.balance as u128
+        {
+            StatusCode::INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE
+        } else {
+            return None;
+        };
+        Some(status)
+    }
+}
+
+fn intrinsic_gas(txn_gas_params: &TransactionGasParameters, size: u64) -> Gas {
+    txn_gas_params
+        .calculate_intrinsic_gas(size.into())
+        .to_unit_round_up_with_params(txn_gas_params)
+}
+
+pub fn edge_case_transactions_strategy() -> impl Strategy<Value = Vec<EdgeCaseTransactionGen>> {
+    vec(any::<EdgeCaseTransactionGen>(), 1..20)
+}
+
+/// Validates and executes the transactions, and checks the invariants in the module
+/// documentation. Returns the transactions accepted by the validator, with the sequence numbers
+/// of their senders.
+pub fn run_and_assert_edge_cases(
+    transaction_gens: &[EdgeCaseTransactionGen],
+) -> Result<Vec<(SignedTransaction, u64)>, TestCaseError> {
+    let txn_gas_params = TransactionGasParameters::initial();
+    let mut executor = FakeExecutor::from_head_genesis();
+    let mut other_executor = FakeExecutor::from_head_genesis();
+
+    let mut txns = vec![];
+    let mut expected_statuses = vec![];
+    for transaction_gen in transaction_gens {
+        // Every transaction has its own sender, so they don't depend on each other
+        let sender = executor.create_raw_account_data(
+            transaction_gen.balance,
+            transaction_gen.account_sequence_number,
+        );
+        executor.add_account_data(&sender);
+        other_executor.add_account_data(&sender);
+        let txn = transaction_gen.sign(sender.account(), &txn_gas_params);
+        expected_statuses.push(transaction_gen.expected_status(&txn, &txn_gas_params));
+        txns.push(txn);
+    }
+
+    let mut accepted = vec![];
+    for ((txn, expected), transaction_gen) in
+        txns.iter().zip(&expected_statuses).zip(transaction_gens)
+    {
+        let result = executor.verify_transaction(txn.clone());
+        prop_assert_eq!(&result, &other_executor.verify_transaction(txn.clone()));
+        let expected = expected.filter(|status| *status != StatusCode::SEQUENCE_NUMBER_TOO_NEW);
+        prop_assert_eq!(
+            result.status(),
+            expected,
+            "unexpected validation of {:?}",
+            txn
+        );
+        match result.status() {
+            Some(status) => prop_assert_eq!(status.status_type(), StatusType::Validation),
+            None => accepted.push((txn.clone(), transaction_gen.account_sequence_number)),
+        }
+    }
+
+    // Executing the block also checks that the sequential and parallel executions match
+    let outputs = executor.execute_block(txns.clone()).unwrap();
+    prop_assert_eq!(
+        &outputs,
+        &other_executor.execute_block(txns.clone()).unwrap()
+    );
+    prop_assert_eq!(outputs.len(), txns.len());
+    for ((txn, expected), output) in txns.iter().zip(&expected_statuses).zip(&outputs) {
+        match (expected, output.status()) {
+            (Some(expected), status) => prop_assert_eq!(
+                status,
+                &TransactionStatus::Discard(*expected),
+                "unexpected execution of {:?}",
+                txn
+            ),
+            (None, TransactionStatus::Discard(status)) => prop_assert_ne!(
+                status.status_type(),
+                StatusType::Validation,
+                "{:?} was validated but discarded",
+                txn
+            ),
+            (None, TransactionStatus::Keep(_)) => {
+                prop_assert!(output.gas_used() <= txn.max_gas_amount())
+            }
+            (None, TransactionStatus::Retry) => {}
+        }
+    }
+
+    Ok(accepted)
+}
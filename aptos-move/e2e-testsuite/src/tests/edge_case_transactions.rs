@@ -0,0 +1,17 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use language_e2e_tests::edge_case_transactions::{
+    edge_case_transactions_strategy, run_and_assert_edge_cases,
+};
+use proptest::prelude::*;
+
+proptest! {
+    // Every case sets up two executors, so run a small number of them.
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn edge_case_transactions(transaction_gens in edge_case_transactions_strategy()) {
+        run_and_assert_edge_cases(&transaction_gens)?;
+    }
+}
@@ -14,6 +14,7 @@
 mod account_universe;
 mod create_account;
 mod data_store;
+mod edge_case_transactions;
 mod execution_strategies;
 mod failed_transaction_tests;
 mod genesis;
@@ -0,0 +1,88 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The config watcher checks the watched on-chain configs and resources at the version of every
+//! new epoch, as soon as it's notified of the reconfiguration, and at the latest state
+//! checkpoint every `check_interval_ms` in between, for the resources that change within an
+//! epoch. The first check, usually that of the initial configs, only records the resources.
+
+use aptos_config::config::NodeConfig;
+use aptos_config_watcher::ConfigWatcher;
+use aptos_logger::prelude::*;
+use aptos_state_view::StateView;
+use aptos_types::transaction::Version;
+use event_notifications::ReconfigNotificationListener;
+use futures::StreamExt;
+use std::{thread, time::Duration};
+use storage_interface::{state_view::DbStateViewAtVersion, DbReaderWriter};
+
+/// How often the node warns while the watched resources can't be checked
+const WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Starts checking the watched resources in the background
+pub fn start_config_watcher(
+    node_config: &NodeConfig,
+    db_rw: DbReaderWriter,
+    reconfig_listener: ReconfigNotificationListener,
+) -> anyhow::Result<()> {
+    let mut watcher = ConfigWatcher::from_config(&node_config.config_watcher)?;
+    let check_interval = Duration::from_millis(node_config.config_watcher.check_interval_ms);
+    // The reconfigurations are awaited on a runtime of the thread, and the webhooks are posted
+    // outside of it, as their client blocks
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()?;
+    thread::Builder::new()
+        .name("config-watcher".to_string())
+        .spawn(move || {
+            let mut reconfig_listener = Some(reconfig_listener);
+            loop {
+                let reconfig_version = match reconfig_listener.as_mut() {
+                    Some(listener) => {
+                        let notification = runtime.block_on(async {
+                            tokio::time::timeout(check_interval, listener.next()).await
+                        });
+                        match notification {
+                            Ok(Some(notification)) => Some(notification.version),
+                            Ok(None) => {
+                                warn!("The config watcher stopped receiving the reconfigurations");
+                                reconfig_listener = None;
+                                None
+                            }
+                            Err(_) => None,
+                        }
+                    }
+                    None => {
+                        thread::sleep(check_interval);
+                        None
+                    }
+                };
+                let version = match reconfig_version {
+                    Some(version) => Ok(Some(version)),
+                    None => db_rw.reader.get_latest_state_checkpoint_version(),
+                };
+                let result = match version {
+                    Ok(Some(version)) => check(&mut watcher, &db_rw, version),
+                    Ok(None) => Ok(()),
+                    Err(error) => Err(error),
+                };
+                if let Err(error) = result {
+                    sample!(
+                        SampleRate::Duration(WARNING_INTERVAL),
+                        warn!("Failed to check the watched resources: {}", error)
+                    );
+                }
+            }
+        })?;
+    Ok(())
+}
+
+fn check(
+    watcher: &mut ConfigWatcher,
+    db_rw: &DbReaderWriter,
+    version: Version,
+) -> anyhow::Result<()> {
+    let state_view = db_rw.reader.state_view_at_version(Some(version))?;
+    watcher.check(version, |state_key| state_view.get_state_value(state_key))?;
+    Ok(())
+}
@@ -0,0 +1,180 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The drain sequence of a graceful shutdown. Once the drain is requested, the API and mempool
+//! refuse new transactions, and the node waits for the commits in flight, persists the
+//! transactions of mempool, flushes the storage and only then exits, within the configured
+//! deadline unless persisting or flushing outlasts it.
+//! The transactions of mempool are submitted again on the next start.
+
+use aptos_config::config::DrainConfig;
+use aptos_logger::prelude::*;
+use aptos_mempool::{MempoolClientRequest, MempoolClientSender};
+use aptos_types::{mempool_status::MempoolStatusCode, transaction::SignedTransaction};
+use aptosdb::AptosDB;
+use futures::{channel::oneshot, executor::block_on, SinkExt};
+use std::{
+    fs,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use storage_interface::DbReader;
+use tokio::runtime::Handle;
+
+/// The file in the data directory the transactions of mempool are persisted to
+const MEMPOOL_SNAPSHOT_FILE: &str = "mempool_snapshot.bcs";
+
+/// How often the ledger version is checked while waiting for the commits in flight
+const COMMIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Starts the drain on the first SIGTERM or SIGINT, so the node takes up to the drain deadline to
+/// exit, e.g. 30 seconds after Ctrl-C by default. A second signal exits right away.
+#[cfg(unix)]
+pub fn handle_termination_signals() -> anyhow::Result<()> {
+    use signal_hook::{
+        consts::{SIGINT, SIGTERM},
+        iterator::Signals,
+    };
+
+    let mut signals = Signals::new(&[SIGTERM, SIGINT])?;
+    thread::Builder::new()
+        .name("signals".to_string())
+        .spawn(move || {
+            for signal in signals.forever() {
+                if aptos_drain::start_draining(&format!("received signal {}", signal)) {
+                    info!("Send the signal again to exit without draining");
+                } else {
+                    warn!("Received signal {} while draining, exiting now", signal);
+                    std::process::exit(1);
+                }
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn handle_termination_signals() -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Runs the drain sequence. The commits in flight are only waited for up to half of the deadline,
+/// since a validator keeps committing the blocks of the other validators, so that mempool is
+/// persisted and the storage flushed within the rest of it. Those always run to completion.
+pub fn drain(
+    config: &DrainConfig,
+    data_dir: &Path,
+    db: Arc<AptosDB>,
+    mempool_client: MempoolClientSender,
+) {
+    let start = Instant::now();
+    let deadline = Duration::from_millis(config.deadline_ms);
+    let commit_settle = Duration::from_millis(config.commit_settle_ms);
+
+    wait_for_commits(&db, commit_settle, start + deadline / 2);
+    if config.persist_mempool {
+        let snapshot_path = data_dir.join(MEMPOOL_SNAPSHOT_FILE);
+        match persist_mempool(&snapshot_path, mempool_client) {
+            Ok(num_txns) => info!(
+                "Persisted the {} transactions of mempool to {:?}",
+                num_txns, snapshot_path
+            ),
+            Err(error) => error!("Failed to persist the transactions of mempool: {}", error),
+        }
+    }
+    if let Err(error) = db.flush() {
+        error!("Failed to flush the storage: {}", error);
+    }
+
+    if start.elapsed() <= deadline {
+        info!("Drained in {} ms", start.elapsed().as_millis());
+    } else {
+        warn!(
+            "The drain took {} ms, past its deadline of {} ms",
+            start.elapsed().as_millis(),
+            config.deadline_ms
+        );
+    }
+}
+
+/// The commits in flight are done once the ledger version hasn't changed for `commit_settle`
+fn wait_for_commits(db: &AptosDB, commit_settle: Duration, deadline: Instant) {
+    let mut version = db.get_latest_version().ok();
+    let mut last_change = Instant::now();
+    while Instant::now() < deadline {
+        thread::sleep(COMMIT_POLL_INTERVAL);
+        let latest_version = db.get_latest_version().ok();
+        if latest_version != version {
+            version = latest_version;
+            last_change = Instant::now();
+        } else if last_change.elapsed() >= commit_settle {
+            info!("The commits in flight are done, at version {:?}", version);
+            return;
+        }
+    }
+    warn!(
+        "The node was still committing when it stopped waiting, at version {:?}",
+        version
+    );
+}
+
+fn persist_mempool(path: &Path, mut mempool_client: MempoolClientSender) -> anyhow::Result<usize> {
+    let (callback, callback_rcv) = oneshot::channel();
+    block_on(mempool_client.send(MempoolClientRequest::GetAllTransactions(callback)))?;
+    let txns = block_on(callback_rcv)?;
+    fs::write(path, bcs::to_bytes(&txns)?)?;
+    Ok(txns.len())
+}
+
+fn read_mempool_snapshot(path: &Path) -> anyhow::Result<Vec<SignedTransaction>> {
+    Ok(bcs::from_bytes(&fs::read(path)?)?)
+}
+
+/// Submits the transactions persisted by the last drain again, if any. Those that expired or were
+/// committed in the meantime are rejected by mempool.
+pub fn restore_mempool(data_dir: &Path, mut mempool_client: MempoolClientSender, runtime: &Handle) {
+    let path = data_dir.join(MEMPOOL_SNAPSHOT_FILE);
+    if !path.exists() {
+        return;
+    }
+    let txns = read_mempool_snapshot(&path);
+    // The snapshot is only submitted once, even if the node stops before it's done
+    if let Err(error) = fs::remove_file(&path) {
+        warn!(
+            "Failed to remove the mempool snapshot {:?}: {}",
+            path, error
+        );
+    }
+    let txns = match txns {
+        Ok(txns) => txns,
+        Err(error) => {
+            error!("Failed to read the mempool snapshot {:?}: {}", path, error);
+            return;
+        }
+    };
+
+    runtime.spawn(async move {
+        let num_txns = txns.len();
+        let mut num_accepted = 0;
+        for txn in txns {
+            let (callback, callback_rcv) = oneshot::channel();
+            if mempool_client
+                .send(MempoolClientRequest::SubmitTransaction(txn, callback))
+                .await
+                .is_err()
+            {
+                break;
+            }
+            if let Ok(Ok((status, _))) = callback_rcv.await {
+                if status.code == MempoolStatusCode::Accepted {
+                    num_accepted += 1;
+                }
+            }
+        }
+        info!(
+            "Submitted the {} transactions persisted by the last drain, {} were accepted",
+            num_txns, num_accepted
+        );
+    });
+}
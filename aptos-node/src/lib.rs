@@ -3,10 +3,15 @@
 
 #![forbid(unsafe_code)]
 
+mod config_watcher;
+mod drain;
 mod log_build_information;
+mod reload;
+mod storage_watchdog;
 
 use anyhow::anyhow;
-use aptos_api::bootstrap as bootstrap_api;
+use aptos_admin_service::{start_admin_service, AdminServiceContext};
+use aptos_api::{bootstrap as bootstrap_api, ApiLimits};
 use aptos_build_info::build_information;
 use aptos_config::{
     config::{
@@ -18,16 +23,18 @@ use aptos_config::{
 };
 use aptos_data_client::aptosnet::AptosNetDataClient;
 use aptos_fh_stream::runtime::bootstrap as bootstrap_fh_stream;
-use aptos_infallible::RwLock;
+use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::{prelude::*, telemetry_log_writer::TelemetryLog, Level, LoggerFilterUpdater};
 use aptos_state_view::account_with_state_view::AsAccountWithStateView;
 use aptos_time_service::TimeService;
 use aptos_types::{
     account_config::CORE_CODE_ADDRESS, account_view::AccountView, chain_id::ChainId,
-    on_chain_config::ON_CHAIN_CONFIG_REGISTRY, waypoint::Waypoint,
+    on_chain_config::ON_CHAIN_CONFIG_REGISTRY, state_store::state_key::StateKey,
+    waypoint::Waypoint,
 };
 use aptos_vm::AptosVM;
 use aptosdb::AptosDB;
+use backup_data_client::BackupDataClient;
 use backup_service::start_backup_service;
 use clap::Parser;
 use consensus::consensus_provider::start_consensus;
@@ -43,9 +50,10 @@ use futures::channel::mpsc;
 use hex::FromHex;
 use log_build_information::log_build_information;
 use mempool_notifications::MempoolNotificationSender;
-use network::application::storage::PeerMetadataStorage;
+use network::{application::storage::PeerMetadataStorage, protocols::network::AppConfig};
 use network_builder::builder::NetworkBuilder;
 use rand::{rngs::StdRng, SeedableRng};
+use reload::{ConfigReloader, StateSyncReloadHandles};
 use state_sync_driver::{
     driver_factory::{DriverFactory, StateSyncRuntimes},
     metadata_storage::PersistentMetadataStorage,
@@ -54,9 +62,9 @@ use std::{
     boxed::Box,
     collections::{HashMap, HashSet},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering},
+        atomic::{AtomicUsize, Ordering},
         Arc,
     },
     thread,
@@ -65,7 +73,8 @@ use std::{
 use storage_interface::{state_view::LatestDbStateCheckpointView, DbReader, DbReaderWriter};
 use storage_service_client::{StorageServiceClient, StorageServiceMultiSender};
 use storage_service_server::{
-    network::StorageServiceNetworkEvents, StorageReader, StorageServiceServer,
+    network::{BandwidthLimiter, StorageServiceNetworkEvents},
+    StorageReader, StorageServiceServer,
 };
 use tokio::runtime::{Builder, Runtime};
 
@@ -112,10 +121,62 @@ pub struct AptosNodeArgs {
     /// only commit a block when there is user transaction in mempool.
     #[clap(long, requires("test"))]
     lazy: bool,
+
+    /// Validate the node configuration file and exit, instead of starting the node
+    ///
+    /// Every problem found is printed along with how to fix it, and the exit code is non-zero
+    /// if any of them is an error.
+    #[clap(long, conflicts_with("test"), requires("config"))]
+    validate_config: bool,
+
+    /// Paths to files overriding the settings of the node configuration file, in order
+    ///
+    /// Their sections are merged into the configuration setting by setting, and their other
+    /// values, lists included, replace those of the files before them.
+    #[clap(
+        long = "config-override",
+        parse(from_os_str),
+        multiple_occurrences(true),
+        conflicts_with("test")
+    )]
+    config_overrides: Vec<PathBuf>,
+
+    /// Print the configuration with its override files and environment overrides merged, and
+    /// exit, instead of starting the node. The secrets are redacted.
+    #[clap(
+        long,
+        conflicts_with_all(&["test", "validate-config"]),
+        requires("config")
+    )]
+    print_effective_config: bool,
+}
+
+/// The config file of a node and the files overriding it, from which the config is reloaded
+#[derive(Clone, Debug)]
+pub struct ConfigFiles {
+    pub config_path: PathBuf,
+    pub override_paths: Vec<PathBuf>,
 }
 
 impl AptosNodeArgs {
     pub fn run(self) {
+        // The config is required along with the flag
+        if let (true, Some(config_path)) = (self.validate_config, &self.config) {
+            std::process::exit(validate_config(config_path, &self.config_overrides));
+        }
+        if let (true, Some(config_path)) = (self.print_effective_config, &self.config) {
+            match NodeConfig::effective_config(config_path, &self.config_overrides) {
+                Ok(config) => println!("{}", config),
+                Err(error) => {
+                    eprintln!(
+                        "Failed to load the node config {:?}: {}",
+                        config_path, error
+                    );
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
         if self.test {
             println!("Entering test mode, this should never be used in production!");
             let rng = self
@@ -133,7 +194,9 @@ impl AptosNodeArgs {
                 self.random_ports,
                 self.lazy,
                 &genesis_framework,
+                Vec::new(),
                 rng,
+                |_| {},
             )
             .expect("Test mode should start correctly");
         } else {
@@ -147,23 +210,52 @@ impl AptosNodeArgs {
             }
 
             // A config file exists, attempt to parse the config
-            let config = NodeConfig::load(config_path.clone()).unwrap_or_else(|error| {
-                panic!(
-                    "Failed to parse node config file! Given file path: {:?}. Error: {:?}",
-                    config_path.display(),
-                    error
-                )
-            });
+            let config = NodeConfig::load_with_overrides(&config_path, &self.config_overrides)
+                .unwrap_or_else(|error| {
+                    panic!(
+                        "Failed to parse node config file! Given file path: {:?}. Error: {:?}",
+                        config_path.display(),
+                        error
+                    )
+                });
 
             // Start the node
             println!("Using node config {:?}", &config);
-            start(config, None, true).expect("Node should start correctly");
+            let config_files = ConfigFiles {
+                config_path,
+                override_paths: self.config_overrides,
+            };
+            start(config, Some(config_files), None, true).expect("Node should start correctly");
         };
     }
 }
 
+/// Prints the problems with the config files, returning the exit code
+fn validate_config(config_path: &Path, override_paths: &[PathBuf]) -> i32 {
+    let problems = NodeConfig::validate_file(config_path, override_paths);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    let num_errors = problems.iter().filter(|problem| problem.is_error()).count();
+    println!(
+        "{:?}: {} errors, {} warnings",
+        config_path,
+        num_errors,
+        problems.len() - num_errors
+    );
+    if num_errors > 0 {
+        1
+    } else {
+        0
+    }
+}
+
 /// Runtime handle to ensure that all inner runtimes stay in scope
 pub struct AptosHandle {
+    db: Arc<AptosDB>,
+    mempool_client: MempoolClientSender,
+    config_reloader: Option<Arc<ConfigReloader>>,
+    _admin_service: Option<Runtime>,
     _api: Runtime,
     _backup: Runtime,
     _consensus_runtime: Option<Runtime>,
@@ -175,9 +267,10 @@ pub struct AptosHandle {
     _telemetry_runtime: Option<Runtime>,
 }
 
-/// Start an aptos node
+/// Start an aptos node. The config is only reloadable if the paths of its files are given.
 pub fn start(
     config: NodeConfig,
+    config_files: Option<ConfigFiles>,
     log_file: Option<PathBuf>,
     create_global_rayon_pool: bool,
 ) -> anyhow::Result<()> {
@@ -202,9 +295,21 @@ pub fn start(
     if config.logger.enable_backtrace {
         logger_builder.enable_backtrace();
     }
+    if let Some(limits) = &config.logger.limits {
+        logger_builder.limits(limits.clone());
+    }
+    if config.crash_report.enabled {
+        logger_builder.recent_logs(config.crash_report.num_recent_logs);
+    }
     if let Some(log_file) = log_file {
         logger_builder.printer(Box::new(FileWriter::new(log_file)));
     }
+    for sink in &config.logger.sinks {
+        let log_sink = sink
+            .build()
+            .map_err(|err| anyhow!("Failed to create log sink {:?}: {}", sink, err))?;
+        logger_builder.sink(sink.level(), log_sink);
+    }
     let mut remote_log_rx = None;
     if config.logger.enable_telemetry_remote_log {
         let (tx, rx) = mpsc::channel(TELEMETRY_LOG_INGEST_BUFFER_SIZE);
@@ -216,6 +321,11 @@ pub fn start(
 
     // Print out build information.
     log_build_information();
+    crash_handler::setup_crash_reporting(
+        &config.crash_report,
+        config.data_dir(),
+        build_information!(),
+    );
 
     // Let's now log some important information, since the logger is set up
     info!(config = config, "Loaded AptosNode config");
@@ -231,28 +341,53 @@ pub fn start(
         warn!("failpoints is set in config, but the binary doesn't compile with this feature");
     }
 
-    let _node_handle = setup_environment(config, remote_log_rx, Some(logger_filter_update_job))?;
+    let drain_config = config.drain.clone();
+    let data_dir = config.base.data_dir.clone();
+    let node_handle = setup_environment(
+        config,
+        config_files,
+        remote_log_rx,
+        Some(logger_filter_update_job),
+    )?;
+    if let Some(config_reloader) = &node_handle.config_reloader {
+        reload::handle_reload_signal(config_reloader.clone())?;
+    }
 
-    let term = Arc::new(AtomicBool::new(false));
+    // Run until the drain is requested, e.g. on SIGTERM or from the admin service
+    drain::handle_termination_signals()?;
+    aptos_drain::wait_for_drain();
+    drain::drain(
+        &drain_config,
+        &data_dir,
+        node_handle.db.clone(),
+        node_handle.mempool_client.clone(),
+    );
 
-    while !term.load(Ordering::Acquire) {
-        std::thread::park();
-    }
-    Ok(())
+    // Exit right away, the runtimes have nothing left worth waiting for
+    info!("Shutting down");
+    std::process::exit(0)
 }
 
 const EPOCH_LENGTH_SECS: u64 = 60;
 
-pub fn load_test_environment<R>(
+/// Starts a single validator test network out of `test_dir`, generating genesis on first use.
+///
+/// `config_overrides` is applied to the node config last, both when the network is first created
+/// and when an existing network is restarted from `test_dir`.  `extra_genesis_state` is only
+/// written when genesis is generated, i.e. when the network is first created.
+pub fn load_test_environment<R, F>(
     config_path: Option<PathBuf>,
     test_dir: Option<PathBuf>,
     random_ports: bool,
     lazy: bool,
     framework: &ReleaseBundle,
+    extra_genesis_state: Vec<(StateKey, Vec<u8>)>,
     rng: R,
+    config_overrides: F,
 ) -> anyhow::Result<()>
 where
     R: ::rand::RngCore + ::rand::CryptoRng,
+    F: FnOnce(&mut NodeConfig),
 {
     // If there wasn't a testnet directory given, create a temp one
     let test_dir = if let Some(test_dir) = test_dir {
@@ -273,8 +408,10 @@ where
 
     // If there's already a config, use it
     let config = if validator_config_path.exists() {
-        NodeConfig::load(&validator_config_path)
-            .map_err(|err| anyhow!("Unable to load config: {}", err))?
+        let mut config = NodeConfig::load(&validator_config_path)
+            .map_err(|err| anyhow!("Unable to load config: {}", err))?;
+        config_overrides(&mut config);
+        config
     } else {
         // Build a single validator network with a generated config
         let mut template = NodeConfig::default_for_validator();
@@ -312,6 +449,7 @@ where
         if lazy {
             template.consensus.quorum_store_poll_count = u64::MAX;
         }
+        config_overrides(&mut template);
 
         // Build genesis and validator node
         let builder = aptos_genesis::builder::Builder::new(&test_dir, framework.clone())?
@@ -323,7 +461,8 @@ where
                 genesis_config.epoch_duration_secs = EPOCH_LENGTH_SECS;
                 genesis_config.recurring_lockup_duration_secs = 7200;
             })))
-            .with_randomize_first_validator_ports(random_ports);
+            .with_randomize_first_validator_ports(random_ports)
+            .with_extra_genesis_state(extra_genesis_state);
 
         let (root_key, _genesis, genesis_waypoint, validators) = builder.build(rng)?;
 
@@ -366,7 +505,8 @@ where
 
     println!("\nAptos is running, press ctrl-c to exit\n");
 
-    start(config, Some(log_file), false)
+    // The config overrides aren't in the config file, so the config can't be reloaded from it
+    start(config, None, Some(log_file), false)
 }
 
 // Fetch chain ID from on-chain resource
@@ -385,7 +525,7 @@ fn fetch_chain_id(db: &DbReaderWriter) -> anyhow::Result<ChainId> {
 
 fn create_state_sync_runtimes<M: MempoolNotificationSender + 'static>(
     node_config: &NodeConfig,
-    storage_service_server_network_handles: Vec<StorageServiceNetworkEvents>,
+    storage_service_server_network_handles: Vec<(NetworkId, StorageServiceNetworkEvents)>,
     storage_service_client_network_handles: HashMap<
         NetworkId,
         storage_service_client::StorageServiceNetworkSender,
@@ -396,13 +536,14 @@ fn create_state_sync_runtimes<M: MempoolNotificationSender + 'static>(
     waypoint: Waypoint,
     event_subscription_service: EventSubscriptionService,
     db_rw: DbReaderWriter,
-) -> anyhow::Result<StateSyncRuntimes> {
+) -> anyhow::Result<(StateSyncRuntimes, StateSyncReloadHandles)> {
     // Start the state sync storage service
-    let storage_service_runtime = setup_state_sync_storage_service(
-        node_config.state_sync.storage_service,
-        storage_service_server_network_handles,
-        &db_rw,
-    )?;
+    let (storage_service_runtime, storage_service_bandwidth_limiters) =
+        setup_state_sync_storage_service(
+            node_config.state_sync.storage_service,
+            storage_service_server_network_handles,
+            &db_rw,
+        )?;
 
     // Start the data client
     let (aptos_data_client, aptos_data_client_runtime) = setup_aptos_data_client(
@@ -413,10 +554,20 @@ fn create_state_sync_runtimes<M: MempoolNotificationSender + 'static>(
         peer_metadata_storage,
     )?;
 
+    // Read the ledger history from the backups (if configured)
+    let (backup_data_client, backup_loader) = BackupDataClient::new(
+        node_config.state_sync.backup_data_client.clone(),
+        node_config.state_sync.storage_service,
+        aptos_data_client.clone(),
+    );
+    if let Some(backup_loader) = backup_loader {
+        aptos_data_client_runtime.spawn(backup_loader.start_loader());
+    }
+
     // Start the data streaming service
     let (streaming_service_client, streaming_service_runtime) = setup_data_streaming_service(
         node_config.state_sync.data_streaming_service,
-        aptos_data_client.clone(),
+        backup_data_client.clone(),
     )?;
 
     // Create the chunk executor and persistent storage
@@ -434,22 +585,27 @@ fn create_state_sync_runtimes<M: MempoolNotificationSender + 'static>(
         metadata_storage,
         consensus_listener,
         event_subscription_service,
-        aptos_data_client,
+        backup_data_client,
         streaming_service_client,
     );
 
     // Create and return the new state sync handle
-    Ok(StateSyncRuntimes::new(
+    let state_sync_runtimes = StateSyncRuntimes::new(
         aptos_data_client_runtime,
         state_sync,
         storage_service_runtime,
         streaming_service_runtime,
-    ))
+    );
+    let reload_handles = StateSyncReloadHandles {
+        storage_service_bandwidth_limiters,
+        aptos_data_client,
+    };
+    Ok((state_sync_runtimes, reload_handles))
 }
 
 fn setup_data_streaming_service(
     config: DataStreamingServiceConfig,
-    aptos_data_client: AptosNetDataClient,
+    aptos_data_client: BackupDataClient<AptosNetDataClient>,
 ) -> anyhow::Result<(StreamingServiceClient, Runtime)> {
     // Create the data streaming service
     let (streaming_service_client, streaming_service_listener) =
@@ -514,9 +670,9 @@ fn setup_aptos_data_client(
 
 fn setup_state_sync_storage_service(
     config: StorageServiceConfig,
-    network_handles: Vec<StorageServiceNetworkEvents>,
+    network_handles: Vec<(NetworkId, StorageServiceNetworkEvents)>,
     db_rw: &DbReaderWriter,
-) -> anyhow::Result<Runtime> {
+) -> anyhow::Result<(Runtime, Vec<Arc<BandwidthLimiter>>)> {
     // Create a new state sync storage service runtime
     let storage_service_runtime = Builder::new_multi_thread()
         .thread_name_fn(|| {
@@ -531,18 +687,21 @@ fn setup_state_sync_storage_service(
 
     // Spawn all state sync storage service servers on the same runtime
     let storage_reader = StorageReader::new(config, Arc::clone(&db_rw.reader));
-    for events in network_handles {
+    let mut bandwidth_limiters = vec![];
+    for (network_id, events) in network_handles {
         let service = StorageServiceServer::new(
             config,
             storage_service_runtime.handle().clone(),
             storage_reader.clone(),
             TimeService::real(),
+            network_id,
             events,
         );
+        bandwidth_limiters.push(service.bandwidth_limiter());
         storage_service_runtime.spawn(service.start());
     }
 
-    Ok(storage_service_runtime)
+    Ok((storage_service_runtime, bandwidth_limiters))
 }
 
 #[cfg(feature = "indexer")]
@@ -572,9 +731,20 @@ fn bootstrap_indexer(
 
 pub fn setup_environment(
     node_config: NodeConfig,
+    config_files: Option<ConfigFiles>,
     remote_log_rx: Option<mpsc::Receiver<TelemetryLog>>,
     logger_filter_update_job: Option<LoggerFilterUpdater>,
 ) -> anyhow::Result<AptosHandle> {
+    // Start recording sensitive actions, before anything accesses secure storage
+    if let Some(audit_path) = &node_config.audit.path {
+        let signing_key_path = node_config
+            .audit
+            .signing_key_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("audit.signing_key_path must be set to enable auditing"))?;
+        aptos_audit::init(audit_path, signing_key_path)?;
+    }
+
     // Start the node inspection service
     let node_config_clone = node_config.clone();
     thread::spawn(move || {
@@ -595,10 +765,14 @@ pub fn setup_environment(
         )
         .map_err(|err| anyhow!("DB failed to open {}", err))?,
     );
+    inspection_service::runtime_configuration::set_db_reader(aptos_db.clone());
     let backup_service = start_backup_service(
         node_config.storage.backup_service_address,
         Arc::clone(&aptos_db),
     );
+    if node_config.storage_watchdog.enabled {
+        storage_watchdog::start_storage_watchdog(&node_config, Arc::clone(&aptos_db))?;
+    }
 
     let genesis_waypoint = node_config.base.waypoint.genesis_waypoint();
     // if there's genesis txn and waypoint, commit it if the result matches.
@@ -633,6 +807,8 @@ pub fn setup_environment(
     let mut consensus_network_handles = None;
     let mut storage_service_server_network_handles = vec![];
     let mut storage_service_client_network_handles = HashMap::new();
+    let mut admin_network_senders = HashMap::new();
+    let mut connectivity_managers = HashMap::new();
 
     // Create an event subscription service so that components can be notified of events and reconfigs
     let mut event_subscription_service = EventSubscriptionService::new(
@@ -649,6 +825,15 @@ pub fn setup_environment(
         None
     };
 
+    // Watch the on-chain configs and resources, from the reconfigurations and the latest state
+    if node_config.config_watcher.enabled {
+        config_watcher::start_config_watcher(
+            &node_config,
+            db_rw.clone(),
+            event_subscription_service.subscribe_to_reconfigurations()?,
+        )?;
+    }
+
     // Gather all network configs into a single vector.
     let mut network_configs: Vec<&NetworkConfig> = node_config.full_node_networks.iter().collect();
     if let Some(network_config) = node_config.validator_network.as_ref() {
@@ -678,6 +863,9 @@ pub fn setup_environment(
     let chain_id = fetch_chain_id(&db_rw)?;
 
     let build_info = build_information!();
+    let logger_reload_handle = logger_filter_update_job
+        .as_ref()
+        .map(LoggerFilterUpdater::reload_handle);
     // Start the telemetry service as early as possible and before any blocking calls
     // We have all the necesary info here to start the telemetry service
     let telemetry_runtime = aptos_telemetry::service::start_telemetry_service(
@@ -687,6 +875,7 @@ pub fn setup_environment(
         remote_log_rx,
         logger_filter_update_job,
     );
+    aptos_tracing::init(&node_config.tracing);
 
     for network_config in network_configs.into_iter() {
         let network_id = network_config.network_id;
@@ -737,13 +926,21 @@ pub fn setup_environment(
             network_builder.add_service(&storage_service_server::network::network_endpoint_config(
                 node_config.state_sync.storage_service,
             ));
-        storage_service_server_network_handles.push(storage_service_events);
+        storage_service_server_network_handles.push((network_id, storage_service_events));
 
         // Register the storage-service clients with Network
         let storage_service_sender =
             network_builder.add_client(&storage_service_client::network_endpoint_config());
         storage_service_client_network_handles.insert(network_id, storage_service_sender);
 
+        // Register the client the admin service resets the connections to peers with
+        if node_config.admin_service.enabled {
+            admin_network_senders.insert(
+                network_id,
+                network_builder.add_client(&AppConfig::client([])),
+            );
+        }
+
         // Create the endpoints to connect the Network to mempool.
         let (mempool_sender, mempool_events) = network_builder.add_p2p_service(
             &aptos_mempool::network::network_endpoint_config(MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE),
@@ -764,6 +961,11 @@ pub fn setup_environment(
             );
         }
 
+        // Keep the connectivity manager requests, to update the seeds on a config reload
+        if let Some(conn_mgr_reqs_tx) = network_builder.conn_mgr_reqs_tx() {
+            connectivity_managers.insert(network_id, conn_mgr_reqs_tx);
+        }
+
         let network_context = network_builder.network_context();
         network_builder.build(runtime.handle().clone());
         network_builder.start();
@@ -786,7 +988,7 @@ pub fn setup_environment(
         );
 
     // Create the state sync runtimes
-    let state_sync_runtimes = create_state_sync_runtimes(
+    let (state_sync_runtimes, state_sync_reload_handles) = create_state_sync_runtimes(
         &node_config,
         storage_service_server_network_handles,
         storage_service_client_network_handles,
@@ -800,11 +1002,13 @@ pub fn setup_environment(
 
     let (mp_client_sender, mp_client_events) = mpsc::channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
+    let api_limits = ApiLimits::new(&node_config.api);
     let api_runtime = bootstrap_api(
         &node_config,
         chain_id,
         aptos_db.clone(),
         mp_client_sender.clone(),
+        api_limits.clone(),
     )?;
     let sf_runtime = match bootstrap_fh_stream(
         &node_config,
@@ -816,7 +1020,12 @@ pub fn setup_environment(
         Some(res) => Some(res?),
     };
 
-    let index_runtime = bootstrap_indexer(&node_config, chain_id, aptos_db, mp_client_sender)?;
+    let index_runtime = bootstrap_indexer(
+        &node_config,
+        chain_id,
+        aptos_db.clone(),
+        mp_client_sender.clone(),
+    )?;
 
     let mut consensus_runtime = None;
     let (consensus_to_mempool_sender, consensus_to_mempool_receiver) =
@@ -834,6 +1043,40 @@ pub fn setup_environment(
         peer_metadata_storage.clone(),
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
+    drain::restore_mempool(
+        &node_config.base.data_dir,
+        mp_client_sender.clone(),
+        mempool.handle(),
+    );
+
+    let config_reloader = config_files.map(|config_files| {
+        Arc::new(ConfigReloader {
+            config_files,
+            config: Mutex::new(node_config.clone()),
+            logger: logger_reload_handle,
+            api_limits,
+            mempool_client: mp_client_sender.clone(),
+            state_sync: state_sync_reload_handles,
+            connectivity_managers,
+        })
+    });
+
+    // Start the admin service, once the components it acts on are up
+    let admin_service = if node_config.admin_service.enabled {
+        Some(start_admin_service(AdminServiceContext {
+            config: node_config.admin_service.clone(),
+            db: aptos_db.clone(),
+            network_senders: admin_network_senders,
+            mempool_client: mp_client_sender.clone(),
+            config_reloader: config_reloader.clone().map(|config_reloader| {
+                Arc::new(move || config_reloader.reload()) as aptos_admin_service::ConfigReloader
+            }),
+            node_config: node_config.clone(),
+            peer_metadata_storage: peer_metadata_storage.clone(),
+        })?)
+    } else {
+        None
+    };
 
     assert!(
         !node_config.consensus.use_quorum_store,
@@ -874,6 +1117,10 @@ pub fn setup_environment(
     }
 
     Ok(AptosHandle {
+        db: aptos_db,
+        mempool_client: mp_client_sender,
+        config_reloader,
+        _admin_service: admin_service,
         _api: api_runtime,
         _backup: backup_service,
         _consensus_runtime: consensus_runtime,
@@ -907,7 +1154,7 @@ mod tests {
         validator_network.mutual_authentication = false;
 
         // Starting the node should panic
-        setup_environment(node_config, None, None).unwrap();
+        setup_environment(node_config, None, None, None).unwrap();
     }
 
     #[cfg(feature = "check-vm-features")]
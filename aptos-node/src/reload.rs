@@ -0,0 +1,151 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reloading the config files of a running node, on SIGHUP or from the admin service. Only the
+//! reloadable settings are applied (see [`NodeConfig::check_reload`]), and config files that
+//! change any other setting are rejected without applying anything.
+
+use crate::ConfigFiles;
+use anyhow::Result;
+use aptos_api::ApiLimits;
+use aptos_audit::{audit, AuditEvent};
+use aptos_config::{config::NodeConfig, network_id::NetworkId};
+use aptos_data_client::aptosnet::AptosNetDataClient;
+use aptos_infallible::Mutex;
+use aptos_logger::{prelude::*, LoggerReloadHandle};
+use aptos_mempool::{MempoolClientRequest, MempoolClientSender};
+use futures::{executor::block_on, SinkExt};
+use network::connectivity_manager::{ConnectivityRequest, DiscoverySource};
+use network_builder::builder::merge_seeds;
+use std::{collections::HashMap, sync::Arc};
+use storage_service_server::network::BandwidthLimiter;
+
+/// The state sync components the bandwidth limits are reloaded on
+pub struct StateSyncReloadHandles {
+    pub storage_service_bandwidth_limiters: Vec<Arc<BandwidthLimiter>>,
+    pub aptos_data_client: AptosNetDataClient,
+}
+
+/// Applies the reloadable settings of the config files to the running components
+pub struct ConfigReloader {
+    pub config_files: ConfigFiles,
+    pub config: Mutex<NodeConfig>,
+    pub logger: Option<LoggerReloadHandle>,
+    pub api_limits: ApiLimits,
+    pub mempool_client: MempoolClientSender,
+    pub state_sync: StateSyncReloadHandles,
+    pub connectivity_managers: HashMap<NetworkId, channel::Sender<ConnectivityRequest>>,
+}
+
+impl ConfigReloader {
+    /// Reloads the config files, returning the reloadable settings that changed
+    pub fn reload(&self) -> Result<Vec<String>> {
+        let mut config = self.config.lock();
+        let (new_config, changes) = config.reload(
+            &self.config_files.config_path,
+            &self.config_files.override_paths,
+        )?;
+        for network in new_config
+            .validator_network
+            .iter()
+            .chain(new_config.full_node_networks.iter())
+        {
+            network.verify_seeds()?;
+        }
+
+        self.apply(&mut config, &new_config)?;
+        *config = new_config;
+        info!(
+            "Reloaded the config {:?}, changed: {:?}",
+            self.config_files, changes
+        );
+        Ok(changes)
+    }
+
+    /// Applies `new_config` to the running components. Each section is stored in `config` once
+    /// it's applied, so that the stored config stays what the components run with if a later
+    /// section fails to apply.
+    fn apply(&self, config: &mut NodeConfig, new_config: &NodeConfig) -> Result<()> {
+        if let Some(logger) = &self.logger {
+            logger.reload(
+                new_config.logger.level,
+                new_config.logger.telemetry_level,
+                new_config.logger.limits.clone(),
+            );
+        }
+        config.logger = new_config.logger.clone();
+
+        self.api_limits.reload(&new_config.api);
+        config.api = new_config.api.clone();
+
+        block_on(
+            self.mempool_client
+                .clone()
+                .send(MempoolClientRequest::UpdateCapacity(
+                    new_config.mempool.clone(),
+                )),
+        )?;
+        config.mempool = new_config.mempool.clone();
+
+        for bandwidth_limiter in &self.state_sync.storage_service_bandwidth_limiters {
+            bandwidth_limiter.update_limits(&new_config.state_sync.storage_service);
+        }
+        self.state_sync
+            .aptos_data_client
+            .update_bandwidth_limits(&new_config.state_sync.aptos_data_client);
+        config.state_sync = new_config.state_sync.clone();
+
+        // The networks can't be added or removed by a reload, so they're in the same order
+        let networks = config
+            .validator_network
+            .iter_mut()
+            .chain(config.full_node_networks.iter_mut());
+        let new_networks = new_config
+            .validator_network
+            .iter()
+            .chain(new_config.full_node_networks.iter());
+        for (network, new_network) in networks.zip(new_networks) {
+            if let Some(connectivity_manager) =
+                self.connectivity_managers.get(&new_network.network_id)
+            {
+                connectivity_manager.clone().try_send(
+                    ConnectivityRequest::UpdateDiscoveredPeers(
+                        DiscoverySource::Config,
+                        merge_seeds(new_network),
+                    ),
+                )?;
+            }
+            *network = new_network.clone();
+        }
+        Ok(())
+    }
+}
+
+/// Reloads the config on every SIGHUP
+#[cfg(unix)]
+pub fn handle_reload_signal(config_reloader: Arc<ConfigReloader>) -> Result<()> {
+    use signal_hook::{consts::SIGHUP, iterator::Signals};
+
+    let mut signals = Signals::new(&[SIGHUP])?;
+    std::thread::Builder::new()
+        .name("reload-signal".to_string())
+        .spawn(move || {
+            for _ in signals.forever() {
+                let result = config_reloader.reload();
+                if let Err(error) = &result {
+                    error!("Failed to reload the config on SIGHUP: {}", error);
+                }
+                audit(
+                    AuditEvent::new("node.reload_config")
+                        .actor("SIGHUP")
+                        .outcome(&result),
+                );
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn handle_reload_signal(_config_reloader: Arc<ConfigReloader>) -> Result<()> {
+    Ok(())
+}
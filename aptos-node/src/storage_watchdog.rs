@@ -0,0 +1,162 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The storage watchdog checks the free space of the storage volume periodically. Below the
+//! warning threshold it warns, and below the critical one it alerts and applies the configured
+//! mitigations: it tightens the prune windows, pauses the internal indexer and rejects the
+//! transactions submitted to the API. The mitigations are lifted once the free space is back
+//! above the warning threshold, so they don't flap around the critical one.
+
+use aptos_config::config::{NodeConfig, PrunerConfig, StorageWatchdogConfig};
+use aptos_logger::prelude::*;
+use aptos_storage_watchdog::{DiskSpace, DiskSpaceLevel};
+use aptosdb::AptosDB;
+use std::{path::PathBuf, sync::Arc, thread, time::Duration};
+
+/// How often the node alerts while the storage volume stays low on space
+const ALERT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Starts checking the storage volume in the background
+pub fn start_storage_watchdog(node_config: &NodeConfig, db: Arc<AptosDB>) -> anyhow::Result<()> {
+    let watchdog = StorageWatchdog {
+        config: node_config.storage_watchdog.clone(),
+        storage_dir: node_config.storage.dir(),
+        pruner_config: node_config.storage.storage_pruner_config,
+        db,
+        level: DiskSpaceLevel::Ok,
+        mitigating: false,
+    };
+    thread::Builder::new()
+        .name("storage-watchdog".to_string())
+        .spawn(move || watchdog.run())?;
+    Ok(())
+}
+
+struct StorageWatchdog {
+    config: StorageWatchdogConfig,
+    storage_dir: PathBuf,
+    // The prune windows are restored to those of the config once the mitigations are lifted
+    pruner_config: PrunerConfig,
+    db: Arc<AptosDB>,
+    level: DiskSpaceLevel,
+    mitigating: bool,
+}
+
+impl StorageWatchdog {
+    fn run(mut self) {
+        let interval = Duration::from_millis(self.config.check_interval_ms);
+        loop {
+            match DiskSpace::of(&self.storage_dir) {
+                Ok(disk_space) => self.check(disk_space),
+                Err(error) => sample!(
+                    SampleRate::Duration(ALERT_INTERVAL),
+                    warn!(
+                        "Failed to check the space of the storage volume of {:?}: {}",
+                        self.storage_dir, error
+                    )
+                ),
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    fn check(&mut self, disk_space: DiskSpace) {
+        let level = DiskSpaceLevel::new(&disk_space, &self.config);
+        let level_changed = level != self.level;
+        self.level = level;
+        match level {
+            DiskSpaceLevel::Critical if level_changed => error!(
+                "The storage volume is critically low on space: {}",
+                disk_space
+            ),
+            DiskSpaceLevel::Critical => sample!(
+                SampleRate::Duration(ALERT_INTERVAL),
+                error!(
+                    "The storage volume is still critically low on space: {}",
+                    disk_space
+                )
+            ),
+            DiskSpaceLevel::Warning if level_changed => {
+                warn!("The storage volume is low on space: {}", disk_space)
+            }
+            DiskSpaceLevel::Warning => sample!(
+                SampleRate::Duration(ALERT_INTERVAL),
+                warn!("The storage volume is still low on space: {}", disk_space)
+            ),
+            DiskSpaceLevel::Ok if level_changed => {
+                info!("The storage volume has space again: {}", disk_space)
+            }
+            DiskSpaceLevel::Ok => {}
+        }
+
+        if level == DiskSpaceLevel::Critical && !self.mitigating {
+            self.apply_mitigations();
+            self.mitigating = true;
+        } else if level == DiskSpaceLevel::Ok && self.mitigating {
+            self.lift_mitigations();
+            self.mitigating = false;
+        }
+        aptos_storage_watchdog::record_check(&disk_space, level, self.mitigating);
+    }
+
+    fn apply_mitigations(&self) {
+        warn!("Applying the mitigations of the storage watchdog");
+        if self.config.critical_ledger_prune_window.is_some()
+            || self.config.critical_state_merkle_prune_window.is_some()
+        {
+            let tighten = |prune_window: u64, critical_prune_window: Option<u64>| {
+                critical_prune_window.map_or(prune_window, |critical_prune_window| {
+                    prune_window.min(critical_prune_window)
+                })
+            };
+            self.set_prune_windows(
+                tighten(
+                    self.pruner_config.ledger_pruner_config.prune_window,
+                    self.config.critical_ledger_prune_window,
+                ),
+                tighten(
+                    self.pruner_config.state_merkle_pruner_config.prune_window,
+                    self.config.critical_state_merkle_prune_window,
+                ),
+            );
+        }
+        if self.config.pause_indexer {
+            self.db.pause_indexer();
+        }
+        if self.config.reject_api_writes {
+            aptos_storage_watchdog::set_api_writes_rejected(true);
+        }
+    }
+
+    fn lift_mitigations(&self) {
+        info!("Lifting the mitigations of the storage watchdog");
+        if self.config.critical_ledger_prune_window.is_some()
+            || self.config.critical_state_merkle_prune_window.is_some()
+        {
+            self.set_prune_windows(
+                self.pruner_config.ledger_pruner_config.prune_window,
+                self.pruner_config.state_merkle_pruner_config.prune_window,
+            );
+        }
+        if self.config.pause_indexer {
+            if let Err(error) = self.db.resume_indexer() {
+                error!(
+                    "Failed to resume the indexer, which stays paused until its DB is rebuilt: {}",
+                    error
+                );
+            }
+        }
+        if self.config.reject_api_writes {
+            aptos_storage_watchdog::set_api_writes_rejected(false);
+        }
+    }
+
+    fn set_prune_windows(&self, ledger_prune_window: u64, state_merkle_prune_window: u64) {
+        if let Err(error) = self
+            .db
+            .set_prune_windows(ledger_prune_window, state_merkle_prune_window)
+        {
+            error!("Failed to change the prune windows: {}", error);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{secret::Secret, utils};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminServiceConfig {
+    // The service only starts if it's enabled
+    pub enabled: bool,
+    // Only a loopback address is allowed without mutual TLS
+    pub address: String,
+    pub port: u16,
+    // Requires the clients to present a certificate signed by the client CA
+    pub tls: Option<AdminServiceTlsConfig>,
+    // If set, the bearer token required by every action, on top of the transport
+    pub auth_token: Option<Secret<String>>,
+    // Each action is disabled unless it's enabled here
+    pub enable_pruning: bool,
+    pub enable_compaction: bool,
+    pub enable_backup_checkpoints: bool,
+    pub enable_peer_resets: bool,
+    pub enable_mempool_flush: bool,
+    pub enable_drain: bool,
+    pub enable_config_reload: bool,
+    pub enable_debug_bundle: bool,
+    // The directory the backup checkpoints are created in, one sub-directory each
+    pub checkpoint_dir: Option<PathBuf>,
+}
+
+impl Default for AdminServiceConfig {
+    fn default() -> AdminServiceConfig {
+        AdminServiceConfig {
+            enabled: false,
+            address: "127.0.0.1".to_string(),
+            port: 9102,
+            tls: None,
+            auth_token: None,
+            enable_pruning: false,
+            enable_compaction: false,
+            enable_backup_checkpoints: false,
+            enable_peer_resets: false,
+            enable_mempool_flush: false,
+            enable_drain: false,
+            enable_config_reload: false,
+            enable_debug_bundle: false,
+            checkpoint_dir: None,
+        }
+    }
+}
+
+impl AdminServiceConfig {
+    pub fn randomize_ports(&mut self) {
+        self.port = utils::get_available_port();
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminServiceTlsConfig {
+    // The PEM encoded certificate and private key of the service
+    pub certificate_path: PathBuf,
+    pub private_key_path: PathBuf,
+    // The PEM encoded CA the client certificates must be signed by
+    pub client_ca_path: PathBuf,
+}
@@ -0,0 +1,15 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    // The file signed audit records of sensitive actions are appended to.
+    // Auditing is disabled if it's not set.
+    pub path: Option<PathBuf>,
+    // The file holding the hex encoded Ed25519 private key the audit records are signed with
+    pub signing_key_path: Option<PathBuf>,
+}
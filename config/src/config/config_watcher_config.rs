@@ -0,0 +1,71 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::secret::Secret;
+use aptos_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigWatcherConfig {
+    // Watches the resources below, and notifies their changes to the logs, the metrics and the
+    // webhooks
+    pub enabled: bool,
+    // The watched resources, by default the on-chain configs changed by governance proposals
+    pub resources: Vec<WatchedResource>,
+    // How often the resources are checked at the latest state checkpoint. They're also checked
+    // at the version of every new epoch, when the on-chain configs take effect.
+    pub check_interval_ms: u64,
+    // The webhooks each change is posted to, as JSON
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+impl Default for ConfigWatcherConfig {
+    fn default() -> ConfigWatcherConfig {
+        let framework_config = |resource: &str| WatchedResource {
+            account: AccountAddress::ONE,
+            resource: resource.to_string(),
+        };
+        ConfigWatcherConfig {
+            enabled: true,
+            resources: vec![
+                framework_config("0x1::consensus_config::ConsensusConfig"),
+                framework_config("0x1::gas_schedule::GasScheduleV2"),
+                framework_config("0x1::staking_config::StakingConfig"),
+                framework_config("0x1::version::Version"),
+            ],
+            check_interval_ms: 60_000,
+            webhooks: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchedResource {
+    // The account the resource is stored at
+    pub account: AccountAddress,
+    // The type of the resource, e.g. "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>"
+    pub resource: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    // The endpoint the changes are posted to
+    pub url: String,
+    // The bearer token sent with each change, if any
+    pub auth_token: Option<Secret<String>>,
+    // The timeout of each post
+    pub timeout_ms: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> WebhookConfig {
+        WebhookConfig {
+            url: "http://localhost:8080/aptos/config-changes".to_string(),
+            auth_token: None,
+            timeout_ms: 10_000,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::secret::Secret;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CrashReportConfig {
+    // Writes a crash report when the node panics
+    pub enabled: bool,
+    // The directory the reports are written to, relative to the data directory unless absolute
+    pub dir: PathBuf,
+    // How many reports are kept, the oldest ones are deleted
+    pub max_reports: usize,
+    // How many of the last log lines are included in each report
+    pub num_recent_logs: usize,
+    // The reports are also posted as JSON to this endpoint, if it's set
+    pub endpoint: Option<String>,
+    // The bearer token sent with each post, if any
+    pub auth_token: Option<Secret<String>>,
+    // How long the node waits for a post before it exits anyway
+    pub post_timeout_ms: u64,
+}
+
+impl Default for CrashReportConfig {
+    fn default() -> CrashReportConfig {
+        CrashReportConfig {
+            enabled: true,
+            dir: PathBuf::from("crash_reports"),
+            max_reports: 10,
+            num_recent_logs: 1_000,
+            endpoint: None,
+            auth_token: None,
+            post_timeout_ms: 5_000,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Comparing node configs setting by setting, e.g. to audit the drift across a fleet. The
+//! configs are compared once their defaults are filled in, so a setting set to its default in
+//! one file and left out of the other doesn't differ, and neither does the order of the
+//! settings in the files. The full node networks are matched by their network ids rather than
+//! their positions in the list, and the values of the secrets are never shown.
+
+use crate::{
+    config::{migration::flatten, Error, NodeConfig, RoleType},
+    secret::{self, REDACTED},
+};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+/// A setting that differs between two configs, it's none in a config without it, e.g. the
+/// settings of a network the other config doesn't have
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ConfigDifference {
+    pub setting: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl NodeConfig {
+    /// The settings that differ from those of the other config, grouped by the section they're
+    /// in, e.g. `consensus`
+    pub fn diff(
+        &self,
+        other: &NodeConfig,
+    ) -> Result<BTreeMap<String, Vec<ConfigDifference>>, Error> {
+        let left = flatten(&self.to_comparable_value()?);
+        let right = flatten(&other.to_comparable_value()?);
+        // The secrets are the settings whose values are redacted
+        let redacted_left = flatten(&secret::redacted(|| self.to_comparable_value())?);
+        let redacted_right = flatten(&secret::redacted(|| other.to_comparable_value())?);
+
+        let mut differences: BTreeMap<String, Vec<ConfigDifference>> = BTreeMap::new();
+        let mut settings: Vec<&String> = left.keys().chain(right.keys()).collect();
+        settings.sort();
+        settings.dedup();
+        for setting in settings {
+            let (left, right) = (left.get(setting), right.get(setting));
+            if left == right {
+                continue;
+            }
+            let show = |value: Option<&String>, redacted: &BTreeMap<String, String>| {
+                value.map(|value| {
+                    if redacted.get(setting) == Some(value) {
+                        value.clone()
+                    } else {
+                        REDACTED.to_string()
+                    }
+                })
+            };
+            let section = setting.split('.').next().unwrap_or_default().to_string();
+            differences
+                .entry(section)
+                .or_default()
+                .push(ConfigDifference {
+                    setting: setting.clone(),
+                    left: show(left, &redacted_left),
+                    right: show(right, &redacted_right),
+                });
+        }
+        Ok(differences)
+    }
+
+    /// The default config of the role of this config, to compare it against
+    pub fn role_defaults(&self) -> NodeConfig {
+        match self.base.role {
+            RoleType::Validator => NodeConfig::default_for_validator(),
+            RoleType::FullNode
+                if self
+                    .full_node_networks
+                    .iter()
+                    .any(|network| network.network_id.is_vfn_network()) =>
+            {
+                NodeConfig::default_for_validator_full_node()
+            }
+            RoleType::FullNode => NodeConfig::default_for_public_full_node(),
+        }
+    }
+
+    fn to_comparable_value(&self) -> Result<Value, Error> {
+        let mut config = to_value(self)?;
+        let mut networks = Mapping::new();
+        for network in &self.full_node_networks {
+            networks.insert(
+                Value::String(network.network_id.to_string()),
+                to_value(network)?,
+            );
+        }
+        if let Value::Mapping(config) = &mut config {
+            config.insert(
+                Value::String("full_node_networks".to_string()),
+                Value::Mapping(networks),
+            );
+        }
+        Ok(config)
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, Error> {
+    serde_yaml::to_value(value).map_err(|e| Error::Yaml("config".to_string(), e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{NetworkConfig, PersistableConfig};
+    use crate::network_id::NetworkId;
+
+    #[test]
+    fn test_diff_configs() {
+        let left = NodeConfig::parse(
+            r#"
+base:
+    role: "full_node"
+mempool:
+    capacity: 2000000
+api:
+    enabled: false
+admin_service:
+    auth_token: "left token"
+"#,
+        )
+        .unwrap();
+        // The same settings in another order, one of which is left at its default
+        let right = NodeConfig::parse(
+            r#"
+admin_service:
+    auth_token: "right token"
+api:
+    enabled: true
+base:
+    role: "full_node"
+"#,
+        )
+        .unwrap();
+
+        let differences = left.diff(&right).unwrap();
+        assert_eq!(
+            differences.keys().collect::<Vec<_>>(),
+            vec!["admin_service", "api"]
+        );
+        assert_eq!(
+            differences["admin_service"],
+            vec![ConfigDifference {
+                setting: "admin_service.auth_token".to_string(),
+                left: Some(REDACTED.to_string()),
+                right: Some(REDACTED.to_string()),
+            }]
+        );
+        assert_eq!(
+            differences["api"],
+            vec![ConfigDifference {
+                setting: "api.enabled".to_string(),
+                left: Some("false".to_string()),
+                right: Some("true".to_string()),
+            }]
+        );
+        assert!(left.diff(&left).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_redacts_nested_secrets() {
+        let vault_config = |token: &str| {
+            NodeConfig::parse(&format!(
+                r#"
+base:
+    role: "validator"
+consensus:
+    safety_rules:
+        backend:
+            type: "vault"
+            server: "https://127.0.0.1:8200"
+            token:
+                from_config: "{}"
+"#,
+                token
+            ))
+            .unwrap()
+        };
+        let left = vault_config("left token");
+        let right = vault_config("right token");
+
+        let differences = left.diff(&right).unwrap();
+        assert_eq!(
+            differences["consensus"],
+            vec![ConfigDifference {
+                setting: "consensus.safety_rules.backend.token.from_config".to_string(),
+                left: Some(REDACTED.to_string()),
+                right: Some(REDACTED.to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_networks() {
+        let mut left = NodeConfig::default();
+        left.full_node_networks = vec![
+            NetworkConfig::network_with_id(NetworkId::Vfn),
+            NetworkConfig::network_with_id(NetworkId::Public),
+        ];
+        let mut right = left.clone();
+        right.full_node_networks.reverse();
+        right.full_node_networks[0].max_outbound_connections += 1;
+
+        // The networks are matched by their ids, whatever their order
+        let differences = left.diff(&right).unwrap();
+        let settings: Vec<_> = differences["full_node_networks"]
+            .iter()
+            .map(|difference| difference.setting.as_str())
+            .collect();
+        assert_eq!(
+            settings,
+            vec!["full_node_networks.Public.max_outbound_connections"]
+        );
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DrainConfig {
+    // How long the node should take to drain, e.g. after SIGTERM or Ctrl-C. The commits in flight
+    // are waited for up to half of it, and mempool is persisted and the storage flushed after
+    pub deadline_ms: u64,
+    // The commits in flight are done once the ledger version hasn't changed for this long, which
+    // may never happen on a validator
+    pub commit_settle_ms: u64,
+    // Saves the transactions of mempool when draining, and submits them again on the next start
+    pub persist_mempool: bool,
+}
+
+impl Default for DrainConfig {
+    fn default() -> DrainConfig {
+        DrainConfig {
+            deadline_ms: 30_000,
+            commit_settle_ms: 2_000,
+            persist_mempool: true,
+        }
+    }
+}
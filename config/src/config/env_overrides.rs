@@ -0,0 +1,182 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Overriding the settings of the config file with environment variables, so a deployment can
+//! change a few settings without templating the whole file. The variable
+//! `APTOS__<SECTION>__<FIELD>` overrides the setting `section.field`, at any depth: e.g.
+//! `APTOS__API__ADDRESS=0.0.0.0:8080` sets `api.address`. The names are lowercased, and a
+//! number picks the item of a list, e.g. `APTOS__FULL_NODE_NETWORKS__0__MAX_OUTBOUND_CONNECTIONS`.
+//!
+//! The values are parsed as YAML, so they can be numbers, booleans, lists or whole sections, and
+//! anything that isn't valid YAML is taken as a string. A string that looks like a number has to
+//! be quoted, e.g. `APTOS__BASE__DATA_DIR='"42"'`. The overrides are applied to the file before
+//! it's deserialized, so an unknown setting is rejected like it would be in the file.
+
+use crate::config::Error;
+use serde_yaml::{Mapping, Value};
+
+/// The prefix of the environment variables overriding the config
+pub const ENV_OVERRIDE_PREFIX: &str = "APTOS__";
+/// Separates the names of the sections from that of the setting
+const ENV_OVERRIDE_SEPARATOR: &str = "__";
+
+/// Applies the overrides among the variables to the config, and returns the overridden settings
+pub(crate) fn apply_env_overrides(
+    config: &mut Value,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<String>, Error> {
+    let mut overrides: Vec<_> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(ENV_OVERRIDE_PREFIX)?.to_lowercase();
+            Some((name, path, value))
+        })
+        .collect();
+    // A section is overridden before the settings in it, so the latter aren't lost
+    overrides.sort_by(|(_, path, _), (_, other_path, _)| path.cmp(other_path));
+
+    let mut settings = vec![];
+    for (name, path, value) in overrides {
+        let segments: Vec<&str> = path.split(ENV_OVERRIDE_SEPARATOR).collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(Error::Unexpected(format!(
+                "The environment variable {} doesn't name a setting, expected {}<SECTION>{}<FIELD>",
+                name, ENV_OVERRIDE_PREFIX, ENV_OVERRIDE_SEPARATOR
+            )));
+        }
+        let value = serde_yaml::from_str(&value).unwrap_or(Value::String(value));
+        set_value(config, &segments, value).map_err(|problem| {
+            Error::Unexpected(format!(
+                "Failed to override the config from the environment variable {}: {}",
+                name, problem
+            ))
+        })?;
+        settings.push(segments.join("."));
+    }
+    Ok(settings)
+}
+
+fn set_value(node: &mut Value, segments: &[&str], value: Value) -> Result<(), String> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            *node = value;
+            return Ok(());
+        }
+    };
+    // A section that isn't in the file, or is explicitly empty, is created
+    if node.is_null() {
+        *node = Value::Mapping(Mapping::new());
+    }
+    let child = match node {
+        Value::Mapping(mapping) => {
+            let key = Value::String(segment.to_string());
+            if !mapping.contains_key(&key) {
+                mapping.insert(key.clone(), Value::Null);
+            }
+            mapping.get_mut(&key).expect("The key was just inserted")
+        }
+        Value::Sequence(sequence) => {
+            let num_items = sequence.len();
+            let index: usize = segment
+                .parse()
+                .map_err(|_| format!("{} isn't the index of an item in a list", segment))?;
+            sequence.get_mut(index).ok_or_else(|| {
+                format!("there's no item {} in a list of {} items", index, num_items)
+            })?
+        }
+        _ => return Err(format!("{} is in a setting that isn't a section", segment)),
+    };
+    set_value(child, rest, value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::NodeConfig;
+
+    const CONFIG: &str = r#"
+base:
+    role: "full_node"
+api:
+    enabled: true
+full_node_networks:
+    - network_id: "public"
+      max_outbound_connections: 4
+"#;
+
+    fn apply(vars: &[(&str, &str)]) -> Result<(Value, Vec<String>), Error> {
+        let mut config: Value = serde_yaml::from_str(CONFIG).unwrap();
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()));
+        let settings = apply_env_overrides(&mut config, vars)?;
+        Ok((config, settings))
+    }
+
+    #[test]
+    fn test_override_settings() {
+        let (config, settings) = apply(&[
+            ("APTOS__API__ADDRESS", "0.0.0.0:8080"),
+            ("APTOS__API__ENABLED", "false"),
+            ("APTOS__MEMPOOL__CAPACITY", "1000"),
+            (
+                "APTOS__FULL_NODE_NETWORKS__0__MAX_OUTBOUND_CONNECTIONS",
+                "8",
+            ),
+            ("RUST_LOG", "debug"),
+        ])
+        .unwrap();
+        assert_eq!(
+            settings,
+            vec![
+                "api.address",
+                "api.enabled",
+                "full_node_networks.0.max_outbound_connections",
+                "mempool.capacity",
+            ]
+        );
+
+        let config: NodeConfig = serde_yaml::from_value(config).unwrap();
+        assert_eq!(config.api.address, "0.0.0.0:8080".parse().unwrap());
+        assert!(!config.api.enabled);
+        assert_eq!(config.mempool.capacity, 1000);
+        assert_eq!(config.full_node_networks[0].max_outbound_connections, 8);
+    }
+
+    #[test]
+    fn test_override_sections() {
+        // The settings in a section are applied after the section itself
+        let (config, _) = apply(&[
+            (
+                "APTOS__FULL_NODE_NETWORKS__0__MAX_OUTBOUND_CONNECTIONS",
+                "8",
+            ),
+            (
+                "APTOS__FULL_NODE_NETWORKS",
+                "[{network_id: public, max_outbound_connections: 2}]",
+            ),
+        ])
+        .unwrap();
+        let config: NodeConfig = serde_yaml::from_value(config).unwrap();
+        assert_eq!(config.full_node_networks.len(), 1);
+        assert_eq!(config.full_node_networks[0].max_outbound_connections, 8);
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        apply(&[("APTOS__", "1")]).unwrap_err();
+        apply(&[("APTOS__API____ADDRESS", "1")]).unwrap_err();
+        apply(&[(
+            "APTOS__FULL_NODE_NETWORKS__1__MAX_OUTBOUND_CONNECTIONS",
+            "8",
+        )])
+        .unwrap_err();
+        apply(&[("APTOS__FULL_NODE_NETWORKS__FIRST__NETWORK_ID", "public")]).unwrap_err();
+        apply(&[("APTOS__API__ENABLED__VALUE", "true")]).unwrap_err();
+
+        // Unknown settings are only rejected once the config is deserialized
+        let (config, _) = apply(&[("APTOS__API__ADRESS", "0.0.0.0:8080")]).unwrap();
+        serde_yaml::from_value::<NodeConfig>(config).unwrap_err();
+    }
+}
@@ -15,6 +15,8 @@ pub enum Error {
     Yaml(String, #[source] serde_yaml::Error),
     #[error("Config is missing expected value: {0}")]
     Missing(&'static str),
+    #[error("The settings {0} changed, they only take effect on a restart")]
+    RequiresRestart(String),
     #[error("Unexpected error: {0}")]
     Unexpected(String),
 }
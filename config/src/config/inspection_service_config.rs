@@ -1,8 +1,9 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
+use crate::{secret::Secret, utils};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -11,6 +12,12 @@ pub struct InspectionServiceConfig {
     pub port: u16,
     pub expose_configuration: bool,
     pub expose_system_information: bool,
+    // The endpoint to change the log levels at runtime
+    pub log_levels: LogLevelsEndpointConfig,
+    // Pushes the metrics to a Prometheus remote write endpoint, for nodes that can't be scraped
+    pub remote_write: Option<MetricsRemoteWriteConfig>,
+    // The criteria of the readiness and liveness probes
+    pub health_probes: HealthProbesConfig,
 }
 
 impl Default for InspectionServiceConfig {
@@ -20,6 +27,9 @@ impl Default for InspectionServiceConfig {
             port: 9101,
             expose_configuration: false,
             expose_system_information: true,
+            log_levels: LogLevelsEndpointConfig::default(),
+            remote_write: None,
+            health_probes: HealthProbesConfig::default(),
         }
     }
 }
@@ -29,3 +39,88 @@ impl InspectionServiceConfig {
         self.port = utils::get_available_port();
     }
 }
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthProbesConfig {
+    // The node is only ready once its latest ledger info is at most this old
+    pub max_sync_lag_secs: u64,
+    // The node is only ready once its API answers, if the API is enabled
+    pub require_api: bool,
+    // How long the API has to answer the readiness probe
+    pub api_timeout_ms: u64,
+    // The node is only live while none of its event loops stalled for longer than this
+    pub max_stall_secs: u64,
+    // Notifies systemd once the node is ready, and pings its watchdog while the node is live
+    pub systemd_notify: bool,
+}
+
+impl Default for HealthProbesConfig {
+    fn default() -> HealthProbesConfig {
+        HealthProbesConfig {
+            max_sync_lag_secs: 60,
+            require_api: true,
+            api_timeout_ms: 1_000,
+            max_stall_secs: 60,
+            systemd_notify: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsRemoteWriteConfig {
+    // The remote write endpoint, e.g. "https://prometheus.example.com/api/v1/write"
+    pub url: String,
+    // How often the metrics are pushed
+    pub push_interval_ms: u64,
+    // The timeout of each push
+    pub timeout_ms: u64,
+    // The credentials sent with each push, if any
+    pub credentials: Option<RemoteWriteCredentials>,
+    // Labels added to every pushed series, e.g. to tell the nodes apart
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for MetricsRemoteWriteConfig {
+    fn default() -> MetricsRemoteWriteConfig {
+        MetricsRemoteWriteConfig {
+            url: "http://localhost:9090/api/v1/write".to_string(),
+            push_interval_ms: 15_000,
+            timeout_ms: 10_000,
+            credentials: None,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum RemoteWriteCredentials {
+    BasicAuth {
+        username: String,
+        password: Secret<String>,
+    },
+    BearerToken(Secret<String>),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogLevelsEndpointConfig {
+    // The bearer token required by the endpoint, which is disabled if it's not set
+    pub auth_token: Option<Secret<String>>,
+    // How long a changed log level lasts if the request doesn't say
+    pub default_duration_secs: u64,
+    // How long a changed log level lasts at most, before it reverts
+    pub max_duration_secs: u64,
+}
+
+impl Default for LogLevelsEndpointConfig {
+    fn default() -> LogLevelsEndpointConfig {
+        LogLevelsEndpointConfig {
+            auth_token: None,
+            default_duration_secs: 600,
+            max_duration_secs: 3600,
+        }
+    }
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::utils;
-use aptos_logger::{Level, CHANNEL_SIZE};
+use aptos_logger::{sinks::LogSinkConfig, Level, CHANNEL_SIZE};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
@@ -21,6 +21,13 @@ pub struct LoggerConfig {
     pub enable_telemetry_remote_log: bool,
     pub enable_telemetry_flush: bool,
     pub telemetry_level: Level,
+    // Additional destinations of the logs (OTLP collectors, syslog, rotated files), each with
+    // its own level. They are only written to when logging is async.
+    pub sinks: Vec<LogSinkConfig>,
+    // Rate limits and sampling of the logs of modules e.g. `network@warn=10/60s,mempool@debug=1%`
+    // for at most 10 identical warnings per minute from network, and 1% of the mempool debug
+    // logs. Overridden by the RUST_LOG_LIMITS environment variable, which is reloaded at runtime.
+    pub limits: Option<String>,
 }
 
 impl Default for LoggerConfig {
@@ -37,6 +44,8 @@ impl Default for LoggerConfig {
             enable_telemetry_remote_log: true,
             enable_telemetry_flush: true,
             telemetry_level: Level::Error,
+            sinks: vec![],
+            limits: None,
         }
     }
 }
@@ -0,0 +1,441 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Migrating a config file written for an older release to the current settings. The settings
+//! that were renamed or moved keep their values under their new names, the removed ones are
+//! dropped, and the deprecated ones are kept but reported, since they still work for now. The
+//! migration works on the YAML of the file, so only the settings it sets are kept, and not the
+//! defaults of the others.
+
+use crate::config::{ConfigProblem, NodeConfig};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+enum Migration {
+    /// The setting moved to another name, with the same value
+    Renamed {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// The setting no longer exists, and is dropped
+    Removed {
+        setting: &'static str,
+        reason: &'static str,
+    },
+    /// The setting still works, but will be removed
+    Deprecated {
+        setting: &'static str,
+        fix: &'static str,
+    },
+}
+
+/// The changes to the settings, in the order they're applied. A `*` matches every item of a
+/// list, or the value of an optional section.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Renamed {
+        from: "storage.storage_pruner_config.enable_ledger_pruner",
+        to: "storage.storage_pruner_config.ledger_pruner_config.enable",
+    },
+    Migration::Renamed {
+        from: "storage.storage_pruner_config.ledger_prune_window",
+        to: "storage.storage_pruner_config.ledger_pruner_config.prune_window",
+    },
+    Migration::Renamed {
+        from: "storage.storage_pruner_config.ledger_pruning_batch_size",
+        to: "storage.storage_pruner_config.ledger_pruner_config.batch_size",
+    },
+    Migration::Renamed {
+        from: "storage.storage_pruner_config.enable_state_store_pruner",
+        to: "storage.storage_pruner_config.state_merkle_pruner_config.enable",
+    },
+    Migration::Renamed {
+        from: "storage.storage_pruner_config.state_store_prune_window",
+        to: "storage.storage_pruner_config.state_merkle_pruner_config.prune_window",
+    },
+    Migration::Renamed {
+        from: "storage.storage_pruner_config.state_store_pruning_batch_size",
+        to: "storage.storage_pruner_config.state_merkle_pruner_config.batch_size",
+    },
+    Migration::Removed {
+        setting: "storage.address",
+        reason: "the storage runs in the node process",
+    },
+    Migration::Removed {
+        setting: "storage.timeout_ms",
+        reason: "the storage runs in the node process",
+    },
+    Migration::Renamed {
+        from: "debug_interface.address",
+        to: "inspection_service.address",
+    },
+    Migration::Renamed {
+        from: "debug_interface.metrics_server_port",
+        to: "inspection_service.port",
+    },
+    Migration::Removed {
+        setting: "debug_interface",
+        reason: "the metrics and the debug endpoints are served by the inspection service",
+    },
+    Migration::Removed {
+        setting: "json_rpc",
+        reason: "the JSON-RPC API was replaced by the REST API, configured under `api`",
+    },
+    Migration::Removed {
+        setting: "upstream",
+        reason: "the upstream peers are those of the networks",
+    },
+    Migration::Removed {
+        setting: "state_sync.state_sync_driver.enable_state_sync_v2",
+        reason: "the new state sync is the only one",
+    },
+    Migration::Deprecated {
+        setting: "metrics",
+        fix: "Remove it, it's ignored and the metrics are configured under `inspection_service`",
+    },
+    Migration::Deprecated {
+        setting: "firehose_stream.starting_version",
+        fix: "Set `firehose_stream.starting_block` instead",
+    },
+    Migration::Deprecated {
+        setting: "validator_network.*.seed_addrs",
+        fix: "Set the peers in `seeds` instead, along with their keys and roles",
+    },
+    Migration::Deprecated {
+        setting: "full_node_networks.*.seed_addrs",
+        fix: "Set the peers in `seeds` instead, along with their keys and roles",
+    },
+    Migration::Deprecated {
+        setting: "validator_network.*.discovery_method",
+        fix: "Set the list `discovery_methods` instead",
+    },
+    Migration::Deprecated {
+        setting: "full_node_networks.*.discovery_method",
+        fix: "Set the list `discovery_methods` instead",
+    },
+];
+
+/// A config migrated to the current settings
+#[derive(Clone, Debug)]
+pub struct ConfigMigration {
+    pub original: Value,
+    pub config: Value,
+    /// The settings that were renamed or removed
+    pub changes: Vec<String>,
+    /// The settings to fix by hand, the migrated config only loads if none is an error
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl ConfigMigration {
+    /// The settings changed by the migration, as `-` and `+` lines of `setting: value`
+    pub fn diff(&self) -> Vec<String> {
+        let original = flatten(&self.original);
+        let config = flatten(&self.config);
+        let mut diff = vec![];
+        for (setting, value) in &original {
+            if config.get(setting) != Some(value) {
+                diff.push(format!("- {}: {}", setting, value));
+            }
+        }
+        for (setting, value) in &config {
+            if original.get(setting) != Some(value) {
+                diff.push(format!("+ {}: {}", setting, value));
+            }
+        }
+        diff
+    }
+}
+
+impl NodeConfig {
+    /// Migrates the YAML of a config written for an older release to the current settings
+    pub fn migrate(original: Value) -> ConfigMigration {
+        let mut config = original.clone();
+        let mut changes = vec![];
+        let mut problems = vec![];
+        for migration in MIGRATIONS {
+            match migration {
+                Migration::Renamed { from, to } => {
+                    let from: Vec<_> = from.split('.').map(str::to_string).collect();
+                    let to: Vec<_> = to.split('.').map(str::to_string).collect();
+                    let value = match take(&mut config, &from) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    if get(&config, &to).is_some() {
+                        problems.push(ConfigProblem::warning(
+                            from.join("."),
+                            format!("was renamed to {}, which is set as well", to.join(".")),
+                            format!(
+                                "Check the value of {}, the old setting is dropped",
+                                to.join(".")
+                            ),
+                        ));
+                    } else {
+                        insert(&mut config, &to, value);
+                        changes.push(format!("Renamed {} to {}", from.join("."), to.join(".")));
+                    }
+                }
+                Migration::Removed { setting, reason } => {
+                    for path in find(&config, &setting.split('.').collect::<Vec<_>>()) {
+                        take(&mut config, &path);
+                        changes.push(format!("Removed {}: {}", path.join("."), reason));
+                    }
+                }
+                Migration::Deprecated { setting, fix } => {
+                    for path in find(&config, &setting.split('.').collect::<Vec<_>>()) {
+                        problems.push(ConfigProblem::warning(
+                            path.join("."),
+                            "is deprecated, and will be removed",
+                            *fix,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // The settings that aren't known to have changed are checked against the current ones
+        if let Err(error) = serde_yaml::from_value::<NodeConfig>(config.clone()) {
+            problems.push(ConfigProblem::error(
+                "config file",
+                format!("doesn't match the current settings: {}", error),
+                "Fix or remove the setting by hand, it didn't change in any known way",
+            ));
+        }
+        ConfigMigration {
+            original,
+            config,
+            changes,
+            problems,
+        }
+    }
+}
+
+/// The paths of the settings matching the pattern
+fn find(node: &Value, pattern: &[&str]) -> Vec<Vec<String>> {
+    let (segment, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => return vec![vec![]],
+    };
+    let children: Vec<(String, &Value)> = match (node, *segment) {
+        (Value::Mapping(_), "*") => vec![(String::new(), node)],
+        (Value::Sequence(sequence), "*") => sequence
+            .iter()
+            .enumerate()
+            .map(|(index, child)| (index.to_string(), child))
+            .collect(),
+        (Value::Mapping(mapping), segment) => mapping
+            .get(&Value::String(segment.to_string()))
+            .map(|child| vec![(segment.to_string(), child)])
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+    let mut paths = vec![];
+    for (segment, child) in children {
+        for mut path in find(child, rest) {
+            // An optional section matched by `*` has no segment of its own
+            if !segment.is_empty() {
+                path.insert(0, segment.clone());
+            }
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+fn get<'a>(node: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(node, |node, segment| match node {
+        Value::Mapping(mapping) => mapping.get(&Value::String(segment.clone())),
+        Value::Sequence(sequence) => sequence.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Removes the setting, and the sections it leaves empty
+fn take(node: &mut Value, path: &[String]) -> Option<Value> {
+    let (segment, rest) = path.split_first()?;
+    match node {
+        Value::Mapping(mapping) => {
+            let key = Value::String(segment.clone());
+            if rest.is_empty() {
+                return mapping.remove(&key);
+            }
+            let child = mapping.get_mut(&key)?;
+            let value = take(child, rest)?;
+            if matches!(child, Value::Mapping(child) if child.is_empty()) {
+                mapping.remove(&key);
+            }
+            Some(value)
+        }
+        Value::Sequence(sequence) => {
+            let child = sequence.get_mut(segment.parse::<usize>().ok()?)?;
+            take(child, rest)
+        }
+        _ => None,
+    }
+}
+
+/// Sets the setting, creating the sections it's in
+fn insert(node: &mut Value, path: &[String], value: Value) {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *node = value;
+            return;
+        }
+    };
+    if !matches!(node, Value::Mapping(_)) {
+        *node = Value::Mapping(Mapping::new());
+    }
+    if let Value::Mapping(mapping) = node {
+        let key = Value::String(segment.clone());
+        if !mapping.contains_key(&key) {
+            mapping.insert(key.clone(), Value::Null);
+        }
+        insert(
+            mapping.get_mut(&key).expect("The key was just inserted"),
+            rest,
+            value,
+        );
+    }
+}
+
+/// The values of all the settings, by their paths
+pub(crate) fn flatten(config: &Value) -> BTreeMap<String, String> {
+    fn flatten_into(node: &Value, path: &str, settings: &mut BTreeMap<String, String>) {
+        let join = |segment: String| {
+            if path.is_empty() {
+                segment
+            } else {
+                format!("{}.{}", path, segment)
+            }
+        };
+        match node {
+            Value::Mapping(mapping) if !mapping.is_empty() => {
+                for (key, child) in mapping {
+                    let segment = match key {
+                        Value::String(key) => key.clone(),
+                        key => to_yaml(key),
+                    };
+                    flatten_into(child, &join(segment), settings);
+                }
+            }
+            Value::Sequence(sequence) if !sequence.is_empty() => {
+                for (index, child) in sequence.iter().enumerate() {
+                    flatten_into(child, &join(index.to_string()), settings);
+                }
+            }
+            node => {
+                settings.insert(path.to_string(), to_yaml(node));
+            }
+        }
+    }
+
+    let mut settings = BTreeMap::new();
+    flatten_into(config, "", &mut settings);
+    settings
+}
+
+/// The value as YAML on a single line
+fn to_yaml(value: &Value) -> String {
+    match value {
+        Value::Mapping(_) => "{}".to_string(),
+        Value::Sequence(_) => "[]".to_string(),
+        value => serde_yaml::to_string(value)
+            .map(|yaml| yaml.trim_start_matches("---").trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Severity;
+
+    const OLD_CONFIG: &str = r#"
+base:
+    role: "full_node"
+storage:
+    dir: "db"
+    timeout_ms: 30000
+    storage_pruner_config:
+        enable_ledger_pruner: true
+        ledger_prune_window: 1000000
+debug_interface:
+    address: "0.0.0.0"
+    metrics_server_port: 9101
+    public_metrics_server_port: 9102
+metrics: {}
+full_node_networks:
+    - network_id: "public"
+      discovery_method: "onchain"
+"#;
+
+    #[test]
+    fn test_migrate_config() {
+        let migration = NodeConfig::migrate(serde_yaml::from_str(OLD_CONFIG).unwrap());
+        assert_eq!(
+            migration.changes,
+            vec![
+                "Renamed storage.storage_pruner_config.enable_ledger_pruner to storage.storage_pruner_config.ledger_pruner_config.enable",
+                "Renamed storage.storage_pruner_config.ledger_prune_window to storage.storage_pruner_config.ledger_pruner_config.prune_window",
+                "Removed storage.timeout_ms: the storage runs in the node process",
+                "Renamed debug_interface.address to inspection_service.address",
+                "Renamed debug_interface.metrics_server_port to inspection_service.port",
+                "Removed debug_interface: the metrics and the debug endpoints are served by the inspection service",
+            ]
+        );
+        let deprecated: Vec<_> = migration
+            .problems
+            .iter()
+            .map(|problem| (problem.severity, problem.setting.as_str()))
+            .collect();
+        assert_eq!(
+            deprecated,
+            vec![
+                (Severity::Warning, "metrics"),
+                (Severity::Warning, "full_node_networks.0.discovery_method"),
+            ]
+        );
+
+        // The migrated config loads, with the values of the old settings
+        let config: NodeConfig = serde_yaml::from_value(migration.config.clone()).unwrap();
+        let ledger_pruner_config = config.storage.storage_pruner_config.ledger_pruner_config;
+        assert!(ledger_pruner_config.enable);
+        assert_eq!(ledger_pruner_config.prune_window, 1_000_000);
+        assert_eq!(config.inspection_service.address, "0.0.0.0");
+        assert_eq!(config.inspection_service.port, 9101);
+
+        assert_eq!(
+            migration.diff(),
+            vec![
+                "- debug_interface.address: 0.0.0.0",
+                "- debug_interface.metrics_server_port: 9101",
+                "- debug_interface.public_metrics_server_port: 9102",
+                "- storage.storage_pruner_config.enable_ledger_pruner: true",
+                "- storage.storage_pruner_config.ledger_prune_window: 1000000",
+                "- storage.timeout_ms: 30000",
+                "+ inspection_service.address: 0.0.0.0",
+                "+ inspection_service.port: 9101",
+                "+ storage.storage_pruner_config.ledger_pruner_config.enable: true",
+                "+ storage.storage_pruner_config.ledger_pruner_config.prune_window: 1000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrate_unknown_settings() {
+        let original = serde_yaml::from_str("storage:\n    unknown_setting: 1\n").unwrap();
+        let migration = NodeConfig::migrate(original);
+        assert!(migration.changes.is_empty());
+        assert_eq!(migration.problems.len(), 1);
+        assert!(migration.problems[0].is_error());
+    }
+
+    #[test]
+    fn test_migrate_current_config() {
+        let original = serde_yaml::from_str(OLD_CONFIG).unwrap();
+        let migrated = NodeConfig::migrate(original).config;
+        let migration = NodeConfig::migrate(migrated);
+        assert!(migration.changes.is_empty());
+        assert!(migration.diff().is_empty());
+    }
+}
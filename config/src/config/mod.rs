@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::network_id::NetworkId;
+use crate::{network_id::NetworkId, secret::Secret};
 use aptos_secure_storage::{KVStorage, Storage};
 use aptos_types::{waypoint::Waypoint, PeerId};
 use rand::{rngs::StdRng, SeedableRng};
@@ -16,20 +16,38 @@ use std::{
 };
 use thiserror::Error;
 
+mod admin_service_config;
+pub use admin_service_config::*;
+mod audit_config;
+pub use audit_config::*;
+mod config_watcher_config;
+pub use config_watcher_config::*;
 mod consensus_config;
 pub use consensus_config::*;
+mod crash_report_config;
+pub use crash_report_config::*;
+mod diff;
+pub use diff::*;
+mod drain_config;
+pub use drain_config::*;
+mod env_overrides;
+pub use env_overrides::*;
 mod error;
 pub use error::*;
 mod execution_config;
 pub use execution_config::*;
 mod inspection_service_config;
 pub use inspection_service_config::*;
+mod layers;
 mod logger_config;
 pub use logger_config::*;
 mod mempool_config;
 pub use mempool_config::*;
+mod migration;
+pub use migration::*;
 mod network_config;
 pub use network_config::*;
+mod reload;
 mod secure_backend_config;
 pub use secure_backend_config::*;
 mod state_sync_config;
@@ -40,10 +58,19 @@ mod indexer_config;
 pub use indexer_config::*;
 mod storage_config;
 pub use storage_config::*;
+mod storage_watchdog_config;
+pub use storage_watchdog_config::*;
 mod safety_rules_config;
 pub use safety_rules_config::*;
+mod secret_references;
+mod telemetry_config;
+pub use telemetry_config::*;
 mod test_config;
 pub use test_config::*;
+mod tracing_config;
+pub use tracing_config::*;
+mod validation;
+pub use validation::*;
 mod api_config;
 pub use api_config::*;
 use aptos_crypto::{bls12381, ed25519::Ed25519PrivateKey, x25519};
@@ -61,11 +88,19 @@ pub struct DeprecatedConfig {}
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct NodeConfig {
+    #[serde(default)]
+    pub admin_service: AdminServiceConfig,
     #[serde(default)]
     pub base: BaseConfig,
     #[serde(default)]
+    pub config_watcher: ConfigWatcherConfig,
+    #[serde(default)]
     pub consensus: ConsensusConfig,
     #[serde(default)]
+    pub crash_report: CrashReportConfig,
+    #[serde(default)]
+    pub drain: DrainConfig,
+    #[serde(default)]
     pub execution: ExecutionConfig,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub full_node_networks: Vec<NetworkConfig>,
@@ -82,6 +117,8 @@ pub struct NodeConfig {
     #[serde(default)]
     pub api: ApiConfig,
     #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
     pub state_sync: StateSyncConfig,
     #[serde(default)]
     pub firehose_stream: FirehoseStreamerConfig,
@@ -90,8 +127,14 @@ pub struct NodeConfig {
     #[serde(default)]
     pub storage: StorageConfig,
     #[serde(default)]
+    pub storage_watchdog: StorageWatchdogConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)]
     pub test: Option<TestConfig>,
     #[serde(default)]
+    pub tracing: TracingConfig,
+    #[serde(default)]
     pub validator_network: Option<NetworkConfig>,
     #[serde(default)]
     pub failpoints: Option<HashMap<String, String>>,
@@ -275,19 +318,44 @@ impl NodeConfig {
     /// Reads the config file and returns the configuration object in addition to doing some
     /// post-processing of the config.
     /// Paths used in the config are either absolute or relative to the config location.
+    /// The settings of the file are overridden by the `APTOS__` environment variables, and the
+    /// secrets they reference are resolved.
     pub fn load<P: AsRef<Path>>(input_path: P) -> Result<Self, Error> {
-        let mut config = Self::load_config(&input_path)?;
+        Self::load_with_overrides(input_path, &[] as &[PathBuf])
+    }
+
+    /// Like [`NodeConfig::load`], with the override files deep merged into the config file, in
+    /// order: their sections are merged setting by setting, and their other values replace those
+    /// of the files before them.
+    pub fn load_with_overrides<P: AsRef<Path>, O: AsRef<Path>>(
+        input_path: P,
+        override_paths: &[O],
+    ) -> Result<Self, Error> {
+        let mut config = Self::load_config_file(&input_path, override_paths)?;
 
         let input_dir = RootPath::new(input_path);
         config.execution.load(&input_dir)?;
 
         let mut config = config
             .validate_indexer_configs()?
-            .validate_network_configs()?;
+            .validate_network_configs()?
+            .validate_state_sync_configs()?
+            .validate_tracing_configs()?;
         config.set_data_dir(config.data_dir().to_path_buf());
         Ok(config)
     }
 
+    /// Reads the config file, merges the override files and the environment into it, and
+    /// resolves the secrets it references
+    fn load_config_file<P: AsRef<Path>, O: AsRef<Path>>(
+        input_path: P,
+        override_paths: &[O],
+    ) -> Result<Self, Error> {
+        let mut config = layers::merge_layers(input_path, override_paths)?;
+        secret_references::resolve_secret_references(&mut config)?;
+        serde_yaml::from_value(config).map_err(|e| Error::Yaml("config".to_string(), e))
+    }
+
     pub fn peer_id(&self) -> Option<PeerId> {
         match self.base.role {
             RoleType::Validator => self.validator_network.as_ref().map(NetworkConfig::peer_id),
@@ -322,9 +390,12 @@ impl NodeConfig {
 
         self.indexer.postgres_uri = env_or_default(
             "INDEXER_DATABASE_URL",
-            self.indexer.postgres_uri,
+            self.indexer
+                .postgres_uri
+                .map(|postgres_uri| postgres_uri.expose().clone()),
             must_be_set("postgres_uri", "INDEXER_DATABASE_URL"),
-        );
+        )
+        .map(Secret::new);
 
         self.indexer.processor = env_or_default(
             "PROCESSOR_NAME",
@@ -409,6 +480,44 @@ impl NodeConfig {
         Ok(self)
     }
 
+    /// Checks that the state sync driver modes are compatible, and that data streams can make
+    /// progress
+    fn validate_state_sync_configs(self) -> Result<NodeConfig, Error> {
+        let driver_config = &self.state_sync.state_sync_driver;
+        if driver_config.epoch_verification_mode == EpochVerificationMode::TrustWaypoint {
+            invariant(
+                driver_config.bootstrapping_mode == BootstrappingMode::DownloadLatestStates,
+                "The TrustWaypoint epoch verification mode requires the DownloadLatestStates bootstrapping mode".into(),
+            )?;
+        }
+        // A stream can't send any request without room for a pending notification
+        invariant(
+            self.state_sync
+                .data_streaming_service
+                .max_pending_notifications
+                > 0,
+            "The data streaming service requires max_pending_notifications to be positive".into(),
+        )?;
+        Ok(self)
+    }
+
+    /// Checks that the sampling ratio is a ratio, and that the spans are exported
+    fn validate_tracing_configs(self) -> Result<NodeConfig, Error> {
+        let sampling_ratio = self.tracing.sampling_ratio;
+        invariant(
+            (0.0..=1.0).contains(&sampling_ratio),
+            format!(
+                "The tracing sampling_ratio must be between 0 and 1, got {}",
+                sampling_ratio
+            ),
+        )?;
+        invariant(
+            self.tracing.export_interval_ms > 0,
+            "The tracing export_interval_ms must be positive".into(),
+        )?;
+        Ok(self)
+    }
+
     pub fn save<P: AsRef<Path>>(&mut self, output_path: P) -> Result<(), Error> {
         let output_dir = RootPath::new(&output_path);
         self.execution.save(&output_dir)?;
@@ -418,6 +527,7 @@ impl NodeConfig {
     }
 
     pub fn randomize_ports(&mut self) {
+        self.admin_service.randomize_ports();
         self.api.randomize_ports();
         self.inspection_service.randomize_ports();
         self.storage.randomize_ports();
@@ -0,0 +1,268 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reloading the node config while the node is running. Only a few settings take effect without
+//! a restart: the log levels and limits, the API limits, the capacities of mempool, the state sync
+//! bandwidth limits and the seed peers of each network. A reload that changes anything else is
+//! rejected as a whole, so the running node never diverges from its config file.
+
+use crate::config::{Error, Identity, NetworkConfig, NodeConfig, RootPath};
+use serde_yaml::Value;
+use std::path::Path;
+
+impl NodeConfig {
+    /// Loads the config file and its override files again, and checks them against the running
+    /// config. Returns the reloaded config and the reloadable settings that changed.
+    pub fn reload<P: AsRef<Path>, O: AsRef<Path>>(
+        &self,
+        input_path: P,
+        override_paths: &[O],
+    ) -> Result<(NodeConfig, Vec<String>), Error> {
+        let mut config = Self::load_config_file(&input_path, override_paths)?;
+
+        // A network without an identity gets a random one on every load, so it keeps the one
+        // the node was started with
+        for network in config.networks_mut() {
+            if network.identity == Identity::None {
+                if let Some(current) = self
+                    .networks()
+                    .find(|current| current.network_id == network.network_id)
+                {
+                    network.identity = current.identity.clone();
+                }
+            }
+        }
+
+        let input_dir = RootPath::new(input_path);
+        config.execution.load(&input_dir)?;
+        let mut config = config
+            .validate_indexer_configs()?
+            .validate_network_configs()?
+            .validate_state_sync_configs()?;
+        config.set_data_dir(config.data_dir().to_path_buf());
+
+        let changes = self.check_reload(&config)?;
+        Ok((config, changes))
+    }
+
+    /// Returns the reloadable settings that differ in the new config, or an error listing the
+    /// other settings that differ, which require a restart.
+    pub fn check_reload(&self, new_config: &NodeConfig) -> Result<Vec<String>, Error> {
+        let mut changes = Vec::new();
+        let mut patched = self.clone();
+        {
+            let mut reload = |name: &str, changed: bool| {
+                if changed {
+                    changes.push(name.to_string());
+                }
+            };
+
+            let (logger, new_logger) = (&mut patched.logger, &new_config.logger);
+            reload("logger.level", copy(&mut logger.level, &new_logger.level));
+            reload(
+                "logger.telemetry_level",
+                copy(&mut logger.telemetry_level, &new_logger.telemetry_level),
+            );
+            reload(
+                "logger.limits",
+                copy(&mut logger.limits, &new_logger.limits),
+            );
+
+            let (api, new_api) = (&mut patched.api, &new_config.api);
+            reload(
+                "api.max_submit_transaction_batch_size",
+                copy(
+                    &mut api.max_submit_transaction_batch_size,
+                    &new_api.max_submit_transaction_batch_size,
+                ),
+            );
+            reload(
+                "api.max_transactions_page_size",
+                copy(
+                    &mut api.max_transactions_page_size,
+                    &new_api.max_transactions_page_size,
+                ),
+            );
+            reload(
+                "api.max_events_page_size",
+                copy(&mut api.max_events_page_size, &new_api.max_events_page_size),
+            );
+
+            let (mempool, new_mempool) = (&mut patched.mempool, &new_config.mempool);
+            reload(
+                "mempool.capacity",
+                copy(&mut mempool.capacity, &new_mempool.capacity),
+            );
+            reload(
+                "mempool.capacity_bytes",
+                copy(&mut mempool.capacity_bytes, &new_mempool.capacity_bytes),
+            );
+            reload(
+                "mempool.capacity_per_user",
+                copy(
+                    &mut mempool.capacity_per_user,
+                    &new_mempool.capacity_per_user,
+                ),
+            );
+
+            let (state_sync, new_state_sync) = (&mut patched.state_sync, &new_config.state_sync);
+            reload(
+                "state_sync.storage_service.outbound_bandwidth_limits",
+                copy(
+                    &mut state_sync.storage_service.outbound_bandwidth_limits,
+                    &new_state_sync.storage_service.outbound_bandwidth_limits,
+                ),
+            );
+            reload(
+                "state_sync.aptos_data_client.inbound_bandwidth_limits",
+                copy(
+                    &mut state_sync.aptos_data_client.inbound_bandwidth_limits,
+                    &new_state_sync.aptos_data_client.inbound_bandwidth_limits,
+                ),
+            );
+
+            for network in patched.networks_mut() {
+                if let Some(new_network) = new_config
+                    .networks()
+                    .find(|new_network| new_network.network_id == network.network_id)
+                {
+                    let seeds_changed = copy(&mut network.seeds, &new_network.seeds);
+                    let seed_addrs_changed = copy(&mut network.seed_addrs, &new_network.seed_addrs);
+                    reload(
+                        &format!("{}.seeds", network.network_id),
+                        seeds_changed || seed_addrs_changed,
+                    );
+                }
+            }
+        }
+
+        if patched != *new_config {
+            return Err(Error::RequiresRestart(
+                changed_settings(&patched, new_config).join(", "),
+            ));
+        }
+        Ok(changes)
+    }
+
+    fn networks(&self) -> impl Iterator<Item = &NetworkConfig> {
+        self.validator_network
+            .iter()
+            .chain(self.full_node_networks.iter())
+    }
+
+    fn networks_mut(&mut self) -> impl Iterator<Item = &mut NetworkConfig> {
+        self.validator_network
+            .iter_mut()
+            .chain(self.full_node_networks.iter_mut())
+    }
+}
+
+/// Copies the new value of a setting, returning whether it changed
+fn copy<T: Clone + PartialEq>(value: &mut T, new_value: &T) -> bool {
+    if value == new_value {
+        false
+    } else {
+        *value = new_value.clone();
+        true
+    }
+}
+
+/// Names the settings (up to the second level, e.g. `consensus.max_block_size`) that differ
+/// between the configs
+fn changed_settings(config: &NodeConfig, new_config: &NodeConfig) -> Vec<String> {
+    let (value, new_value) = match (
+        serde_yaml::to_value(config),
+        serde_yaml::to_value(new_config),
+    ) {
+        (Ok(Value::Mapping(value)), Ok(Value::Mapping(new_value))) => (value, new_value),
+        _ => return vec!["<unknown>".to_string()],
+    };
+
+    let mut settings = Vec::new();
+    for (key, _) in value.iter().chain(new_value.iter()) {
+        let name = key.as_str().unwrap_or("<unknown>");
+        if settings.iter().any(|setting: &String| setting == name) {
+            continue;
+        }
+        let (current, new) = (value.get(key), new_value.get(key));
+        match (current, new) {
+            (Some(Value::Mapping(current)), Some(Value::Mapping(new))) => {
+                for (field, _) in current.iter().chain(new.iter()) {
+                    let field_name = format!("{}.{}", name, field.as_str().unwrap_or("<unknown>"));
+                    if !settings.contains(&field_name)
+                        && !values_eq(current.get(field), new.get(field))
+                    {
+                        settings.push(field_name);
+                    }
+                }
+            }
+            _ if !values_eq(current, new) => settings.push(name.to_string()),
+            _ => {}
+        }
+    }
+    settings
+}
+
+/// Compares the values regardless of the order of their maps and sequences, since the hash maps
+/// and hash sets of the config serialize in arbitrary orders
+fn values_eq(value: Option<&Value>, other: Option<&Value>) -> bool {
+    match (value, other) {
+        (Some(Value::Mapping(value)), Some(Value::Mapping(other))) => {
+            value.len() == other.len()
+                && value
+                    .iter()
+                    .all(|(key, field)| values_eq(Some(field), other.get(key)))
+        }
+        (Some(Value::Sequence(value)), Some(Value::Sequence(other))) => {
+            value.len() == other.len()
+                && value.iter().all(|element| {
+                    other
+                        .iter()
+                        .any(|other_element| values_eq(Some(element), Some(other_element)))
+                })
+        }
+        (value, other) => value == other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{Peer, PeerRole};
+    use aptos_logger::Level;
+    use aptos_types::PeerId;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_check_reload() {
+        let config = NodeConfig::default_for_public_full_node();
+        assert!(config.check_reload(&config).unwrap().is_empty());
+
+        let mut new_config = config.clone();
+        new_config.logger.level = Level::Debug;
+        new_config.mempool.capacity_per_user = 10;
+        new_config.full_node_networks[0].seeds = HashMap::from([(
+            PeerId::random(),
+            Peer::new(vec![], HashSet::new(), PeerRole::Upstream),
+        )]);
+        assert_eq!(
+            config.check_reload(&new_config).unwrap(),
+            vec![
+                "logger.level".to_string(),
+                "mempool.capacity_per_user".to_string(),
+                "Public.seeds".to_string(),
+            ]
+        );
+
+        // Any other change is rejected, along with the reloadable ones
+        new_config.consensus.max_sending_block_txns += 1;
+        new_config.mempool.shared_mempool_tick_interval_ms += 1;
+        match config.check_reload(&new_config) {
+            Err(Error::RequiresRestart(settings)) => assert_eq!(
+                settings,
+                "consensus.max_sending_block_txns, mempool.shared_mempool_tick_interval_ms"
+            ),
+            result => panic!("Expected the reload to be rejected, got {:?}", result),
+        }
+    }
+}
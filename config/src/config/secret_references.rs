@@ -0,0 +1,181 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resolving the secrets referenced by the config when it's loaded, so the keys and tokens
+//! don't have to be written in the config file itself. Any setting can be set to a reference:
+//!
+//! * `file:///run/secrets/x` is the contents of the file, without the trailing newline.
+//! * `env://VAR` is the value of the environment variable.
+//! * `vault://path#field` is the field of the secret at the path, in the KV store mounted at
+//!   `secret` of the Vault server at `VAULT_ADDR`. The token is read from `VAULT_TOKEN`, and the
+//!   CA certificate of the server from the file at `VAULT_CACERT`, if it's set.
+//!
+//! A secret is resolved as a string, e.g. for the keys of the identities, except the fields of
+//! Vault secrets that are numbers, lists or maps. The values of the secrets never end up in
+//! the errors.
+
+use crate::config::Error;
+use aptos_vault_client::Client as VaultClient;
+use serde_yaml::Value;
+use std::{env, fs};
+
+const FILE_SCHEME: &str = "file://";
+const ENV_SCHEME: &str = "env://";
+const VAULT_SCHEME: &str = "vault://";
+
+/// Replaces every reference to a secret in the config by the value of the secret
+pub(crate) fn resolve_secret_references(config: &mut Value) -> Result<(), Error> {
+    let mut vault_client = None;
+    resolve_in(config, "", &mut vault_client)
+}
+
+fn resolve_in(
+    node: &mut Value,
+    setting: &str,
+    vault_client: &mut Option<VaultClient>,
+) -> Result<(), Error> {
+    let join = |segment: &dyn std::fmt::Display| {
+        if setting.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}.{}", setting, segment)
+        }
+    };
+    match node {
+        Value::Mapping(mapping) => {
+            for (key, child) in mapping.iter_mut() {
+                let segment = key
+                    .as_str()
+                    .map_or_else(|| format!("{:?}", key), str::to_string);
+                resolve_in(child, &join(&segment), vault_client)?;
+            }
+        }
+        Value::Sequence(sequence) => {
+            for (index, child) in sequence.iter_mut().enumerate() {
+                resolve_in(child, &join(&index), vault_client)?;
+            }
+        }
+        Value::String(reference) => {
+            let secret = resolve(reference, vault_client).map_err(|problem| {
+                Error::Unexpected(format!(
+                    "Failed to resolve the secret {} of {}: {}",
+                    reference, setting, problem
+                ))
+            })?;
+            if let Some(secret) = secret {
+                *node = secret;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The value of the secret, or none if the string doesn't reference one
+fn resolve(
+    reference: &str,
+    vault_client: &mut Option<VaultClient>,
+) -> Result<Option<Value>, String> {
+    if let Some(path) = reference.strip_prefix(FILE_SCHEME) {
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let secret = contents.strip_suffix('\n').unwrap_or(&contents);
+        let secret = secret.strip_suffix('\r').unwrap_or(secret);
+        Ok(Some(Value::String(secret.to_string())))
+    } else if let Some(var) = reference.strip_prefix(ENV_SCHEME) {
+        let secret = env::var(var).map_err(|error| format!("{}: {}", var, error))?;
+        Ok(Some(Value::String(secret)))
+    } else if let Some(path) = reference.strip_prefix(VAULT_SCHEME) {
+        let (secret, field) = path
+            .split_once('#')
+            .ok_or_else(|| "expected vault://path#field".to_string())?;
+        // The client is only created for the first reference to a Vault secret
+        if vault_client.is_none() {
+            *vault_client = Some(vault_client_from_env()?);
+        }
+        let response = vault_client
+            .as_ref()
+            .expect("The client was just created")
+            .read_secret(secret, field)
+            .map_err(|error| error.to_string())?;
+        let secret = serde_yaml::to_value(response.value).map_err(|error| error.to_string())?;
+        Ok(Some(secret))
+    } else {
+        Ok(None)
+    }
+}
+
+fn vault_client_from_env() -> Result<VaultClient, String> {
+    let host = env::var("VAULT_ADDR").map_err(|error| format!("VAULT_ADDR: {}", error))?;
+    let token = env::var("VAULT_TOKEN").map_err(|error| format!("VAULT_TOKEN: {}", error))?;
+    let ca_certificate = match env::var_os("VAULT_CACERT") {
+        Some(path) => Some(
+            fs::read_to_string(&path)
+                .map_err(|error| format!("VAULT_CACERT {:?}: {}", path, error))?,
+        ),
+        None => None,
+    };
+    Ok(VaultClient::new(host, token, ca_certificate, None, None))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{NodeConfig, RemoteWriteCredentials},
+        secret::Secret,
+    };
+    use aptos_temppath::TempPath;
+
+    #[test]
+    fn test_resolve_secret_references() {
+        let secret_file = TempPath::new();
+        secret_file.create_as_file().unwrap();
+        fs::write(secret_file.path(), "file secret\n").unwrap();
+        env::set_var("TEST_RESOLVE_SECRET_REFERENCES", "env secret");
+
+        let mut config: Value = serde_yaml::from_str(&format!(
+            r#"
+admin_service:
+    auth_token: "file://{}"
+inspection_service:
+    remote_write:
+        url: "https://metrics.example.com/api/v1/write"
+        credentials:
+            bearer_token: "env://TEST_RESOLVE_SECRET_REFERENCES"
+"#,
+            secret_file.path().display()
+        ))
+        .unwrap();
+        resolve_secret_references(&mut config).unwrap();
+
+        let config: NodeConfig = serde_yaml::from_value(config).unwrap();
+        assert_eq!(
+            config.admin_service.auth_token,
+            Some(Secret::from("file secret"))
+        );
+        let remote_write = config.inspection_service.remote_write.unwrap();
+        assert_eq!(
+            remote_write.credentials,
+            Some(RemoteWriteCredentials::BearerToken(Secret::from(
+                "env secret"
+            )))
+        );
+        assert_eq!(remote_write.url, "https://metrics.example.com/api/v1/write");
+    }
+
+    #[test]
+    fn test_unresolved_secret_references() {
+        for reference in [
+            "file:///nonexistent/secret",
+            "env://TEST_UNRESOLVED_SECRET_REFERENCES",
+            "vault://secret/without/field",
+        ] {
+            let mut config: Value =
+                serde_yaml::from_str(&format!("api:\n    token: \"{}\"\n", reference)).unwrap();
+            let error = resolve_secret_references(&mut config)
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("api.token"), "{}", error);
+        }
+    }
+}
@@ -1,9 +1,9 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::config::Error;
+use crate::{config::Error, secret::Secret};
 use aptos_secure_storage::{
-    GitHubStorage, InMemoryStorage, Namespaced, OnDiskStorage, RocksDbStorage, Storage,
+    Audited, GitHubStorage, InMemoryStorage, Namespaced, OnDiskStorage, RocksDbStorage, Storage,
     VaultStorage, SECURE_STORAGE_DB_NAME,
 };
 use serde::{Deserialize, Serialize};
@@ -118,7 +118,7 @@ pub struct OnDiskStorageConfig {
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Token {
-    FromConfig(String),
+    FromConfig(Secret<String>),
     /// This is an absolute path and not relative to data_dir
     FromDisk(PathBuf),
 }
@@ -127,7 +127,7 @@ impl Token {
     pub fn read_token(&self) -> Result<String, Error> {
         match self {
             Token::FromDisk(path) => read_file(path),
-            Token::FromConfig(token) => Ok(token.clone()),
+            Token::FromConfig(token) => Ok(token.expose().clone()),
         }
     }
 }
@@ -216,7 +216,7 @@ fn read_file(path: &Path) -> Result<String, Error> {
 
 impl From<&SecureBackend> for Storage {
     fn from(backend: &SecureBackend) -> Self {
-        match backend {
+        let storage = match backend {
             SecureBackend::GitHub(config) => {
                 let storage = Storage::from(GitHubStorage::new(
                     config.repository_owner.clone(),
@@ -270,7 +270,9 @@ impl From<&SecureBackend> for Storage {
                     storage
                 }
             }
-        }
+        };
+        // Key accesses are recorded in the audit log, if auditing is enabled
+        Storage::from(Audited::new(Box::new(storage)))
     }
 }
 #[cfg(test)]
@@ -290,7 +292,7 @@ mod tests {
                 namespace: None,
                 server: "127.0.0.1:8200".to_string(),
                 ca_certificate: None,
-                token: Token::FromConfig("test".to_string()),
+                token: Token::FromConfig("test".into()),
                 renew_ttl_secs: None,
                 disable_cas: None,
                 connection_timeout_ms: None,
@@ -318,7 +320,7 @@ vault:
                 namespace: None,
                 server: "127.0.0.1:8200".to_string(),
                 ca_certificate: None,
-                token: Token::FromConfig("test".to_string()),
+                token: Token::FromConfig("test".into()),
                 renew_ttl_secs: None,
                 disable_cas: None,
                 connection_timeout_ms: Some(3000),
@@ -379,7 +381,7 @@ vault:
         let disk = Token::FromDisk(temppath.path().to_path_buf());
         assert_eq!("disk_token", disk.read_token().unwrap());
 
-        let config = Token::FromConfig("config_token".to_string());
+        let config = Token::FromConfig("config_token".into());
         assert_eq!("config_token", config.read_token().unwrap());
     }
 }
@@ -1,16 +1,22 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::network_id::NetworkId;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 // The maximum message size per state sync message
 pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024; /* 16 MiB */
 
+// The number of bytes in a MiB
+const BYTES_PER_MIB: u64 = 1024 * 1024;
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StateSyncConfig {
     pub data_streaming_service: DataStreamingServiceConfig,
     pub aptos_data_client: AptosDataClientConfig,
+    pub backup_data_client: Option<BackupDataClientConfig>,
     pub state_sync_driver: StateSyncDriverConfig,
     pub storage_service: StorageServiceConfig,
 }
@@ -38,6 +44,28 @@ impl BootstrappingMode {
     }
 }
 
+/// The epoch verification mode determines which epoch ending ledger infos the
+/// node verifies when bootstrapping, e.g., every epoch change since genesis.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum EpochVerificationMode {
+    // Verifies the signatures of every epoch change (starting at genesis)
+    VerifyAllEpochs,
+    // Trusts the epoch ending ledger info of the waypoint and only verifies the
+    // epoch changes after it. This requires an epoch ending waypoint and the
+    // `DownloadLatestStates` bootstrapping mode. The epoch history before the
+    // waypoint is not verified or stored, so the node is unable to serve it.
+    TrustWaypoint,
+}
+
+impl EpochVerificationMode {
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            EpochVerificationMode::VerifyAllEpochs => "verify_all_epochs",
+            EpochVerificationMode::TrustWaypoint => "trust_waypoint",
+        }
+    }
+}
+
 /// The continuous syncing mode determines how the node will stay up-to-date
 /// once it has bootstrapped and the blockchain continues to grow, e.g.,
 /// continuously executing all transactions.
@@ -62,6 +90,7 @@ pub struct StateSyncDriverConfig {
     pub bootstrapping_mode: BootstrappingMode, // The mode by which to bootstrap
     pub commit_notification_timeout_ms: u64, // The max time taken to process a commit notification
     pub continuous_syncing_mode: ContinuousSyncingMode, // The mode by which to sync after bootstrapping
+    pub epoch_verification_mode: EpochVerificationMode, // The epoch changes to verify when bootstrapping
     pub progress_check_interval_ms: u64, // The interval (ms) at which to check state sync progress
     pub max_connection_deadline_secs: u64, // The max time (secs) to wait for connections from peers
     pub max_consecutive_stream_notifications: u64, // The max number of notifications to process per driver loop
@@ -78,6 +107,7 @@ impl Default for StateSyncDriverConfig {
             bootstrapping_mode: BootstrappingMode::ApplyTransactionOutputsFromGenesis,
             commit_notification_timeout_ms: 5000,
             continuous_syncing_mode: ContinuousSyncingMode::ApplyTransactionOutputs,
+            epoch_verification_mode: EpochVerificationMode::VerifyAllEpochs,
             progress_check_interval_ms: 100,
             max_connection_deadline_secs: 10,
             max_consecutive_stream_notifications: 10,
@@ -101,6 +131,7 @@ pub struct StorageServiceConfig {
     pub max_transaction_chunk_size: u64, // Max num of transactions per chunk
     pub max_transaction_output_chunk_size: u64, // Max num of transaction outputs per chunk
     pub storage_summary_refresh_interval_ms: u64, // The interval (ms) to refresh the storage summary
+    pub outbound_bandwidth_limits: BandwidthLimitsConfig, // The limits on bandwidth used by responses
 }
 
 impl Default for StorageServiceConfig {
@@ -116,10 +147,32 @@ impl Default for StorageServiceConfig {
             max_transaction_chunk_size: 2000,
             max_transaction_output_chunk_size: 2000,
             storage_summary_refresh_interval_ms: 50,
+            outbound_bandwidth_limits: BandwidthLimitsConfig::default(),
         }
     }
 }
 
+/// Bandwidth limits (in MiB per second) for state sync data, by the type of
+/// network the data is sent or received on. A limit of `None` is unlimited.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BandwidthLimitsConfig {
+    pub validator_network_mib_per_sec: Option<u64>, // The limit for the validator and VFN networks
+    pub public_network_mib_per_sec: Option<u64>,    // The limit for the public network
+}
+
+impl BandwidthLimitsConfig {
+    /// Returns the bandwidth limit (in bytes per second) for the given network
+    pub fn bytes_per_sec(&self, network_id: &NetworkId) -> Option<u64> {
+        let mib_per_sec = if network_id.is_public_network() {
+            self.public_network_mib_per_sec
+        } else {
+            self.validator_network_mib_per_sec
+        };
+        mib_per_sec.map(|mib_per_sec| mib_per_sec.saturating_mul(BYTES_PER_MIB))
+    }
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct DataStreamingServiceConfig {
@@ -145,6 +198,13 @@ pub struct DataStreamingServiceConfig {
     // memory. Once the number grows beyond this value, garbage collection occurs.
     pub max_notification_id_mappings: u64,
 
+    // Maximum number of data chunks (per stream) that are either in-flight or
+    // waiting to be consumed by the stream listener. This bounds the memory used
+    // to prefetch data. Within this bound and the maximum number of concurrent
+    // requests, the number of in-flight requests adapts to the rate at which the
+    // listener consumes data (e.g., how quickly chunks are executed or applied).
+    pub max_pending_notifications: u64,
+
     // The interval (milliseconds) at which to check the progress of each stream.
     pub progress_check_interval_ms: u64,
 }
@@ -158,6 +218,7 @@ impl Default for DataStreamingServiceConfig {
             max_data_stream_channel_sizes: 300,
             max_request_retry: 5,
             max_notification_id_mappings: 300,
+            max_pending_notifications: 50,
             progress_check_interval_ms: 100,
         }
     }
@@ -171,6 +232,7 @@ pub struct AptosDataClientConfig {
     pub response_timeout_ms: u64, // Timeout (in milliseconds) when waiting for a response
     pub summary_poll_interval_ms: u64, // Interval (in milliseconds) between data summary polls
     pub use_compression: bool,    // Whether or not to request compression for incoming data
+    pub inbound_bandwidth_limits: BandwidthLimitsConfig, // The limits on bandwidth used by incoming data
 }
 
 impl Default for AptosDataClientConfig {
@@ -181,6 +243,40 @@ impl Default for AptosDataClientConfig {
             response_timeout_ms: 10000,
             summary_poll_interval_ms: 200,
             use_compression: true,
+            inbound_bandwidth_limits: BandwidthLimitsConfig::default(),
+        }
+    }
+}
+
+/// A backup storage (as written by the backup coordinator) from which state
+/// sync reads the ledger history, instead of requesting it from peers. Data
+/// that isn't in the backups is still requested from the network.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupDataClientConfig {
+    pub storage: BackupStorageConfig, // The backup storage to read the backups from
+    pub metadata_cache_dir: Option<PathBuf>, // Where to cache the backup metadata (defaults to a temporary dir)
+    pub max_concurrent_downloads: usize,     // Max num of backup files downloaded at the same time
+    pub max_cached_chunks: usize,            // Max num of transaction backup chunks held in memory
+}
+
+impl Default for BackupDataClientConfig {
+    fn default() -> Self {
+        Self {
+            storage: BackupStorageConfig::LocalFs {
+                dir: PathBuf::from("/opt/aptos/backup"),
+            },
+            metadata_cache_dir: None,
+            max_concurrent_downloads: 8,
+            max_cached_chunks: 8,
         }
     }
 }
+
+/// The type of a backup storage, see `backup_cli::storage`
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStorageConfig {
+    LocalFs { dir: PathBuf }, // A local directory, mainly for tests
+    CommandAdapter { config_path: PathBuf }, // The config of shell commands to access the storage
+}
@@ -0,0 +1,43 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageWatchdogConfig {
+    // Tracks the free space of the volume the storage is on
+    pub enabled: bool,
+    // How often the free space is checked
+    pub check_interval_ms: u64,
+    // Below this share of free space, the node warns
+    pub warning_free_space_percent: u64,
+    // Below this share of free space, the node alerts and applies the mitigations below, until
+    // the free space is back above the warning threshold
+    pub critical_free_space_percent: u64,
+    // Tightens the prune window of the ledger pruner to at most this many versions, if it's set
+    pub critical_ledger_prune_window: Option<u64>,
+    // Tightens the prune window of the state merkle pruner to at most this many versions, if
+    // it's set
+    pub critical_state_merkle_prune_window: Option<u64>,
+    // Pauses the internal indexer, which catches up once the free space recovers. It can only
+    // catch up on the write sets the ledger pruner hasn't pruned in the meantime.
+    pub pause_indexer: bool,
+    // Rejects the transactions submitted to the API
+    pub reject_api_writes: bool,
+}
+
+impl Default for StorageWatchdogConfig {
+    fn default() -> StorageWatchdogConfig {
+        StorageWatchdogConfig {
+            enabled: true,
+            check_interval_ms: 10_000,
+            warning_free_space_percent: 20,
+            critical_free_space_percent: 10,
+            critical_ledger_prune_window: None,
+            critical_state_merkle_prune_window: None,
+            pause_indexer: false,
+            reject_api_writes: false,
+        }
+    }
+}
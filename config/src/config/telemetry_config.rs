@@ -0,0 +1,55 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// What the telemetry service collects and where it goes. The `APTOS_DISABLE_TELEMETRY*`
+/// environment variables still take precedence, e.g. to disable all telemetry.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    // The build, system and configuration information of the node
+    pub enable_build_info: bool,
+    // The Prometheus metrics and the core metrics of the node
+    pub enable_metrics: bool,
+    // The logs at or above logger.telemetry_level, if logger.enable_telemetry_remote_log is set
+    pub enable_logs: bool,
+    // The network metrics, i.e., the connections to peers, and the IP address of the node
+    pub enable_peer_info: bool,
+    // Writes the telemetry to local files instead of sending it, nothing leaves the node.
+    // The telemetry is sent to the telemetry service if it's not set.
+    pub local_only: Option<LocalTelemetryConfig>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> TelemetryConfig {
+        TelemetryConfig {
+            enable_build_info: true,
+            enable_metrics: true,
+            enable_logs: true,
+            enable_peer_info: true,
+            local_only: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalTelemetryConfig {
+    // The directory of the telemetry files: custom_events.jsonl, logs.jsonl (one JSON payload per
+    // line) and metrics.prom (the Prometheus text format, e.g., for the textfile collector of the
+    // node exporter)
+    pub dir: PathBuf,
+    // The jsonl files are moved to <file>.1 once they reach this size, replacing the previous one
+    pub max_file_size_bytes: u64,
+}
+
+impl Default for LocalTelemetryConfig {
+    fn default() -> LocalTelemetryConfig {
+        LocalTelemetryConfig {
+            dir: PathBuf::from("/opt/aptos/data/telemetry"),
+            max_file_size_bytes: 100 * 1024 * 1024, // 100 MiB
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TracingConfig {
+    // OTLP/HTTP collector endpoint spans are exported to e.g. "http://localhost:4318".
+    // Tracing is disabled if it's not set.
+    pub otlp_endpoint: Option<String>,
+    // The name of the service reported with the spans
+    pub service_name: String,
+    // The fraction of transactions submitted to the API that are traced, between 0 and 1
+    pub sampling_ratio: f64,
+    // Follow the sampling decision of the W3C `traceparent` header of API requests, if any. Only
+    // enable it if the API is only reached through a trusted proxy: otherwise any client can
+    // have its transactions traced, up to max_active_traces.
+    pub respect_parent_sampling: bool,
+    // Maximum number of transactions traced at the same time
+    pub max_active_traces: usize,
+    // Traces of transactions that didn't commit by then are ended
+    pub trace_timeout_ms: u64,
+    // Maximum number of spans buffered before they're dropped
+    pub max_queued_spans: usize,
+    // Maximum number of spans exported per request
+    pub export_batch_size: usize,
+    // How often spans are exported
+    pub export_interval_ms: u64,
+}
+
+impl Default for TracingConfig {
+    fn default() -> TracingConfig {
+        TracingConfig {
+            otlp_endpoint: None,
+            service_name: "aptos-node".to_string(),
+            sampling_ratio: 0.01,
+            respect_parent_sampling: false,
+            max_active_traces: 10_000,
+            trace_timeout_ms: 60_000,
+            max_queued_spans: 100_000,
+            export_batch_size: 512,
+            export_interval_ms: 5_000,
+        }
+    }
+}
+
+impl TracingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.otlp_endpoint.is_some()
+    }
+}
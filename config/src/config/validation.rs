@@ -0,0 +1,512 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Validating a config file without starting the node. Unlike the checks made when the config is
+//! loaded, which stop at the first error, every problem is collected along with how to fix it:
+//! the role against the networks, the paths the node writes to, the identities in their secure
+//! backends and the ports the services listen on.
+
+use crate::config::{
+    Identity, IdentityBlob, InitialSafetyRulesConfig, NetworkConfig, NodeConfig, RoleType,
+    RootPath, SecureBackend,
+};
+use aptos_crypto::bls12381;
+use aptos_global_constants::CONSENSUS_KEY;
+use aptos_secure_storage::{CryptoStorage, KVStorage, Storage};
+use aptos_types::PeerId;
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    net::IpAddr,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    /// The node fails to start, or misbehaves once it runs
+    Error,
+    /// The node runs, but likely not as intended
+    Warning,
+}
+
+/// A problem with a config, along with how to fix it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigProblem {
+    pub severity: Severity,
+    /// The setting with the problem, e.g. `base.data_dir`
+    pub setting: String,
+    pub problem: String,
+    pub fix: String,
+}
+
+impl ConfigProblem {
+    pub(crate) fn error(
+        setting: impl Into<String>,
+        problem: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: Severity::Error,
+            setting: setting.into(),
+            problem: problem.into(),
+            fix: fix.into(),
+        }
+    }
+
+    pub(crate) fn warning(
+        setting: impl Into<String>,
+        problem: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(setting, problem, fix)
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}: {}: {}\n    fix: {}",
+            severity, self.setting, self.problem, self.fix
+        )
+    }
+}
+
+/// A service the node listens with
+struct Listener {
+    setting: String,
+    // Unknown for a DNS name
+    ip: Option<IpAddr>,
+    port: u16,
+}
+
+impl Listener {
+    fn collides_with(&self, other: &Listener) -> bool {
+        self.port == other.port
+            && match (self.ip, other.ip) {
+                (Some(ip), Some(other_ip)) => {
+                    ip == other_ip || ip.is_unspecified() || other_ip.is_unspecified()
+                }
+                _ => true,
+            }
+    }
+}
+
+impl NodeConfig {
+    /// Loads the config file, with its override files, and returns every problem found with it
+    pub fn validate_file<P: AsRef<Path>, O: AsRef<Path>>(
+        input_path: P,
+        override_paths: &[O],
+    ) -> Vec<ConfigProblem> {
+        let mut config = match Self::load_config_file(&input_path, override_paths) {
+            Ok(config) => config,
+            Err(error) => {
+                return vec![ConfigProblem::error(
+                    "config file",
+                    error.to_string(),
+                    "Check the paths, and that the files are valid YAML with only known settings",
+                )]
+            }
+        };
+        config.set_data_dir(config.data_dir().to_path_buf());
+        let mut problems = config.validate(&RootPath::new(&input_path));
+
+        // Loading checks a few more things, which likely fail already if there are errors
+        if !problems.iter().any(ConfigProblem::is_error) {
+            if let Err(error) = Self::load_with_overrides(&input_path, override_paths) {
+                problems.push(ConfigProblem::error(
+                    "config file",
+                    format!("Failed to load the config: {}", error),
+                    "Fix the setting the error names",
+                ));
+            }
+        }
+        problems
+    }
+
+    /// Returns every problem found with the config, whose relative paths are under `root_dir`
+    pub fn validate(&self, root_dir: &RootPath) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        self.check_role(&mut problems);
+        self.check_paths(root_dir, &mut problems);
+        self.check_identities(&mut problems);
+        self.check_ports(&mut problems);
+        problems
+    }
+
+    fn check_role(&self, problems: &mut Vec<ConfigProblem>) {
+        match (self.base.role, &self.validator_network) {
+            (RoleType::Validator, None) => problems.push(ConfigProblem::error(
+                "validator_network",
+                "The node is a validator, but has no validator network",
+                "Add a validator_network, or set base.role to full_node",
+            )),
+            (RoleType::FullNode, Some(_)) => problems.push(ConfigProblem::error(
+                "validator_network",
+                "The node is a full node, but has a validator network",
+                "Remove the validator_network, or set base.role to validator",
+            )),
+            _ => {}
+        }
+        if self.base.role == RoleType::FullNode && self.full_node_networks.is_empty() {
+            problems.push(ConfigProblem::warning(
+                "full_node_networks",
+                "The full node has no networks, so it can't sync",
+                "Add a public or vfn network to full_node_networks",
+            ));
+        }
+
+        let mut network_ids = HashSet::new();
+        for (index, network) in self.full_node_networks.iter().enumerate() {
+            let setting = format!("full_node_networks[{}].network_id", index);
+            if network.network_id.is_validator_network() {
+                problems.push(ConfigProblem::error(
+                    setting,
+                    "The validator network is among the full node networks",
+                    "Move it to validator_network, or set its network_id to public or vfn",
+                ));
+            } else if !network_ids.insert(network.network_id) {
+                problems.push(ConfigProblem::error(
+                    setting,
+                    format!("There's more than one {} network", network.network_id),
+                    "Merge the networks, or remove all but one of them",
+                ));
+            }
+        }
+
+        if self.base.role.is_validator()
+            && self.consensus.safety_rules.backend == SecureBackend::InMemoryStorage
+        {
+            problems.push(ConfigProblem::warning(
+                "consensus.safety_rules.backend",
+                "The safety data of the validator is lost on every restart, so it may equivocate",
+                "Use an on_disk_storage or vault backend",
+            ));
+        }
+    }
+
+    fn check_paths(&self, root_dir: &RootPath, problems: &mut Vec<ConfigProblem>) {
+        let fix = "Create the directory, or give the user running the node write access to it";
+        if let Err(problem) = check_writable_dir(&self.base.data_dir) {
+            problems.push(ConfigProblem::error("base.data_dir", problem, fix));
+        }
+        let storage_dir = self.storage.dir();
+        if storage_dir != self.base.data_dir {
+            if let Err(problem) = check_writable_dir(&storage_dir) {
+                problems.push(ConfigProblem::error("storage.dir", problem, fix));
+            }
+        }
+        if let SecureBackend::OnDiskStorage(backend) = &self.consensus.safety_rules.backend {
+            let path = backend.path();
+            if let Err(problem) = check_writable_dir(path.parent().unwrap_or(&path)) {
+                problems.push(ConfigProblem::error(
+                    "consensus.safety_rules.backend.path",
+                    problem,
+                    fix,
+                ));
+            }
+        }
+
+        let genesis_file_location = &self.execution.genesis_file_location;
+        if !genesis_file_location.as_os_str().is_empty() {
+            let genesis_path = root_dir.full_path(genesis_file_location);
+            if !genesis_path.is_file() {
+                problems.push(ConfigProblem::error(
+                    "execution.genesis_file_location",
+                    format!("The genesis file {:?} doesn't exist", genesis_path),
+                    "Download the genesis.blob of the network, relative paths are relative to the config file",
+                ));
+            }
+        }
+
+        if let InitialSafetyRulesConfig::FromFile {
+            identity_blob_path, ..
+        } = &self.consensus.safety_rules.initial_safety_rules_config
+        {
+            if let Err(error) = IdentityBlob::from_file(identity_blob_path) {
+                problems.push(ConfigProblem::error(
+                    "consensus.safety_rules.initial_safety_rules_config.identity_blob_path",
+                    format!(
+                        "Failed to read the identity {:?}: {}",
+                        identity_blob_path, error
+                    ),
+                    "Point it at the validator-identity.yaml generated for the validator",
+                ));
+            }
+        }
+    }
+
+    fn check_identities(&self, problems: &mut Vec<ConfigProblem>) {
+        let networks = self
+            .validator_network
+            .iter()
+            .map(|network| ("validator_network".to_string(), network))
+            .chain(
+                self.full_node_networks
+                    .iter()
+                    .enumerate()
+                    .map(|(index, network)| (format!("full_node_networks[{}]", index), network)),
+            );
+        for (setting, network) in networks {
+            if let Err(problem) = check_identity(network) {
+                problems.push(ConfigProblem::error(
+                    format!("{}.identity", setting),
+                    problem,
+                    "Point the identity at a backend and keys that exist, or at an identity file",
+                ));
+            }
+        }
+
+        // Without an initial config, safety rules expect their storage to be initialized already
+        let safety_rules = &self.consensus.safety_rules;
+        if self.base.role.is_validator()
+            && safety_rules.initial_safety_rules_config == InitialSafetyRulesConfig::None
+            && safety_rules.backend != SecureBackend::InMemoryStorage
+        {
+            let result = open_storage(&safety_rules.backend).and_then(|storage| {
+                storage
+                    .get::<bls12381::PrivateKey>(CONSENSUS_KEY)
+                    .map_err(|error| format!("The consensus key isn't in the backend: {}", error))
+            });
+            if let Err(problem) = result {
+                problems.push(ConfigProblem::error(
+                    "consensus.safety_rules.backend",
+                    problem,
+                    "Set initial_safety_rules_config to the identity of the validator, or initialize the backend",
+                ));
+            }
+        }
+    }
+
+    fn check_ports(&self, problems: &mut Vec<ConfigProblem>) {
+        let mut listeners = Vec::new();
+        if self.api.enabled {
+            listeners.push(Listener {
+                setting: "api.address".to_string(),
+                ip: Some(self.api.address.ip()),
+                port: self.api.address.port(),
+            });
+        }
+        listeners.push(Listener {
+            setting: "inspection_service.port".to_string(),
+            ip: self.inspection_service.address.parse().ok(),
+            port: self.inspection_service.port,
+        });
+        if self.admin_service.enabled {
+            listeners.push(Listener {
+                setting: "admin_service.port".to_string(),
+                ip: self.admin_service.address.parse().ok(),
+                port: self.admin_service.port,
+            });
+        }
+        listeners.push(Listener {
+            setting: "storage.backup_service_address".to_string(),
+            ip: Some(self.storage.backup_service_address.ip()),
+            port: self.storage.backup_service_address.port(),
+        });
+        let networks = self
+            .validator_network
+            .iter()
+            .map(|network| ("validator_network".to_string(), network))
+            .chain(
+                self.full_node_networks
+                    .iter()
+                    .enumerate()
+                    .map(|(index, network)| (format!("full_node_networks[{}]", index), network)),
+            );
+        for (setting, network) in networks {
+            if let Some(port) = network.listen_address.find_port() {
+                listeners.push(Listener {
+                    setting: format!("{}.listen_address", setting),
+                    ip: network.listen_address.find_ip_addr(),
+                    port,
+                });
+            }
+        }
+
+        // Port 0 picks a free port
+        let listeners: Vec<_> = listeners
+            .into_iter()
+            .filter(|listener| listener.port != 0)
+            .collect();
+        for (index, listener) in listeners.iter().enumerate() {
+            if let Some(other) = listeners[..index]
+                .iter()
+                .find(|other| listener.collides_with(other))
+            {
+                problems.push(ConfigProblem::error(
+                    listener.setting.clone(),
+                    format!("Port {} is also used by {}", listener.port, other.setting),
+                    "Give each service its own port",
+                ));
+            }
+        }
+    }
+}
+
+/// Checks that the directory can be written to, or created if it doesn't exist yet
+fn check_writable_dir(dir: &Path) -> Result<(), String> {
+    let mut existing = dir;
+    while !existing.exists() {
+        existing = match existing.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+    }
+    if !existing.is_dir() {
+        return Err(format!("{:?} isn't a directory", existing));
+    }
+
+    // Read-only mounts and missing permissions only show when writing
+    let probe = existing.join(format!(".validate-config-{}", std::process::id()));
+    fs::write(&probe, b"").map_err(|error| format!("{:?} isn't writable: {}", existing, error))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+fn check_identity(network: &NetworkConfig) -> Result<(), String> {
+    match &network.identity {
+        Identity::FromStorage(identity) => {
+            let storage = open_storage(&identity.backend)?;
+            storage
+                .export_private_key(&identity.key_name)
+                .map_err(|error| {
+                    format!(
+                        "The key {} isn't in the backend: {}",
+                        identity.key_name, error
+                    )
+                })?;
+            storage
+                .get::<PeerId>(&identity.peer_id_name)
+                .map_err(|error| {
+                    format!(
+                        "The peer id {} isn't in the backend: {}",
+                        identity.peer_id_name, error
+                    )
+                })?;
+            Ok(())
+        }
+        Identity::FromFile(identity) => IdentityBlob::from_file(&identity.path)
+            .map(|_| ())
+            .map_err(|error| {
+                format!(
+                    "Failed to read the identity file {:?}: {}",
+                    identity.path, error
+                )
+            }),
+        Identity::FromConfig(_) | Identity::None => Ok(()),
+    }
+}
+
+/// Opens the backend without creating anything in it, unlike the node, which creates a missing
+/// on-disk storage
+fn open_storage(backend: &SecureBackend) -> Result<Storage, String> {
+    match backend {
+        SecureBackend::InMemoryStorage => {
+            return Err("The in-memory storage starts empty, so it has no keys".to_string())
+        }
+        SecureBackend::OnDiskStorage(config) if !config.path().exists() => {
+            return Err(format!(
+                "The storage file {:?} doesn't exist",
+                config.path()
+            ))
+        }
+        _ => {}
+    }
+    // Opening panics on the errors it doesn't return, like a token that can't be read
+    let storage = panic::catch_unwind(AssertUnwindSafe(|| Storage::from(backend)))
+        .map_err(|_| "Failed to open the backend".to_string())?;
+    storage
+        .available()
+        .map_err(|error| format!("The backend isn't available: {}", error))?;
+    Ok(storage)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::NetworkConfig;
+    use crate::network_id::NetworkId;
+    use aptos_temppath::TempPath;
+
+    fn problem_settings(config: &NodeConfig) -> Vec<String> {
+        config
+            .validate(&RootPath::new_path("."))
+            .into_iter()
+            .filter(ConfigProblem::is_error)
+            .map(|problem| problem.setting)
+            .collect()
+    }
+
+    fn full_node_config(data_dir: &Path) -> NodeConfig {
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.set_data_dir(data_dir.to_path_buf());
+        config.execution.genesis_file_location = Default::default();
+        config
+    }
+
+    #[test]
+    fn test_validate_role() {
+        let data_dir = TempPath::new();
+        data_dir.create_as_dir().unwrap();
+        let mut config = full_node_config(data_dir.path());
+        assert!(problem_settings(&config).is_empty());
+
+        config.base.role = RoleType::Validator;
+        config
+            .full_node_networks
+            .push(NetworkConfig::network_with_id(NetworkId::Validator));
+        assert_eq!(
+            problem_settings(&config),
+            vec![
+                "validator_network".to_string(),
+                "full_node_networks[1].network_id".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_paths() {
+        let data_dir = TempPath::new();
+        data_dir.create_as_file().unwrap();
+        let mut config = full_node_config(data_dir.path());
+        config.execution.genesis_file_location = data_dir.path().join("genesis.blob");
+        assert_eq!(
+            problem_settings(&config),
+            vec![
+                "base.data_dir".to_string(),
+                "storage.dir".to_string(),
+                "execution.genesis_file_location".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_ports() {
+        let data_dir = TempPath::new();
+        data_dir.create_as_dir().unwrap();
+        let mut config = full_node_config(data_dir.path());
+        config.inspection_service.port = config.api.address.port();
+        config.admin_service.enabled = true;
+        config.admin_service.port = config.storage.backup_service_address.port();
+        assert_eq!(
+            problem_settings(&config),
+            vec![
+                "inspection_service.port".to_string(),
+                "storage.backup_service_address".to_string(),
+            ]
+        );
+    }
+}
@@ -12,17 +12,19 @@
 //! while ignored during serialization.
 //!
 
+use crate::secret::serialize_secret;
 use aptos_crypto::{
     CryptoMaterialError, PrivateKey, ValidCryptoMaterial, ValidCryptoMaterialStringExt,
 };
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{de::DeserializeOwned, ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 /// ConfigKey places a clonable wrapper around PrivateKeys for config purposes only. The only time
 /// configs have keys is either for testing or for low security requirements. We recommend that
 /// keys be stored in key managers. If we make keys unclonable, then the configs must be mutable
 /// and that becomes a requirement strictly as a result of supporting test environments, which is
 /// undesirable. Hence this internal wrapper allows for keys to be clonable but only from configs.
-#[derive(Debug, Deserialize, Serialize)]
+/// The key is redacted when the config is serialized to be shown, see `crate::secret`.
+#[derive(Debug, Deserialize)]
 pub struct ConfigKey<T: PrivateKey + Serialize> {
     #[serde(bound(deserialize = "T: Deserialize<'de>"))]
     key: T,
@@ -46,6 +48,23 @@ impl<T: DeserializeOwned + PrivateKey + ValidCryptoMaterial + Serialize> ConfigK
     }
 }
 
+impl<T: PrivateKey + Serialize> Serialize for ConfigKey<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Key<'a, T>(&'a T);
+
+        impl<T: Serialize> Serialize for Key<'_, T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_secret(serializer, |serializer| self.0.serialize(serializer))
+            }
+        }
+
+        // The same as the derived implementation, which configs are saved with
+        let mut config_key = serializer.serialize_struct("ConfigKey", 1)?;
+        config_key.serialize_field("key", &Key(&self.key))?;
+        config_key.end()
+    }
+}
+
 impl<T: DeserializeOwned + PrivateKey + Serialize> Clone for ConfigKey<T> {
     fn clone(&self) -> Self {
         bcs::from_bytes(&bcs::to_bytes(self).unwrap()).unwrap()
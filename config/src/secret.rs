@@ -0,0 +1,139 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Secrets in configs, e.g. the tokens of the node endpoints, which must never be shown. A
+//! `Secret` is debug formatted as `REDACTED`, and serialized as it inside `redacted`, e.g. when a
+//! node serves its config. It's (de)serialized as the value it wraps otherwise, so that configs
+//! are saved with their secrets.
+
+use serde::{Deserialize, Serialize, Serializer};
+use std::{cell::Cell, fmt};
+
+/// Shown instead of the secrets
+pub const REDACTED: &str = "<elided secret>";
+
+thread_local! {
+    static REDACTING: Cell<bool> = Cell::new(false);
+}
+
+/// Runs `f`, e.g. the serialization of a config, with the secrets serialized as `REDACTED`
+pub fn redacted<R>(f: impl FnOnce() -> R) -> R {
+    struct Guard(bool);
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            REDACTING.with(|redacting| redacting.set(self.0));
+        }
+    }
+
+    let _guard = Guard(REDACTING.with(|redacting| redacting.replace(true)));
+    f()
+}
+
+/// Serializes a secret as `REDACTED` inside `redacted`, and with `serialize` otherwise. Binary
+/// formats are never redacted, since they're not shown and are used to copy the secrets.
+pub(crate) fn serialize_secret<S: Serializer>(
+    serializer: S,
+    serialize: impl FnOnce(S) -> Result<S::Ok, S::Error>,
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() && REDACTING.with(Cell::get) {
+        serializer.serialize_str(REDACTED)
+    } else {
+        serialize(serializer)
+    }
+}
+
+/// A config value that's never shown, e.g. a token
+#[derive(Clone, Default, Deserialize, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(secret: T) -> Self {
+        Self(secret)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl Secret<String> {
+    /// Whether `authorization`, the value of an `Authorization` header, carries this secret as
+    /// its bearer token. It's compared in constant time, so the secret can't be guessed from the
+    /// response times.
+    pub fn authorizes(&self, authorization: Option<&[u8]>) -> bool {
+        let expected = format!("Bearer {}", self.0);
+        match authorization {
+            Some(value) => {
+                value.len() == expected.len()
+                    && value
+                        .iter()
+                        .zip(expected.as_bytes())
+                        .fold(0, |diff, (a, b)| diff | (a ^ b))
+                        == 0
+            }
+            None => false,
+        }
+    }
+}
+
+impl From<String> for Secret<String> {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", REDACTED)
+    }
+}
+
+impl<T: Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_secret(serializer, |serializer| self.0.serialize(serializer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Config {
+        token: Secret<String>,
+        name: String,
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        let config: Config = serde_yaml::from_str("token: abc\nname: node").unwrap();
+        assert_eq!(config.token.expose(), "abc");
+        assert!(!format!("{:?}", config).contains("abc"));
+
+        let redacted_config = redacted(|| serde_yaml::to_value(&config)).unwrap();
+        assert_eq!(redacted_config["token"].as_str(), Some(REDACTED));
+        assert_eq!(redacted_config["name"].as_str(), Some("node"));
+
+        // Configs keep their secrets otherwise, e.g. when they're saved
+        let saved_config: Config =
+            serde_yaml::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(saved_config.token.expose(), "abc");
+    }
+
+    #[test]
+    fn test_authorizes() {
+        let secret = Secret::from("abc");
+        assert!(secret.authorizes(Some(b"Bearer abc")));
+        assert!(!secret.authorizes(Some(b"Bearer abd")));
+        assert!(!secret.authorizes(Some(b"Bearer abcd")));
+        assert!(!secret.authorizes(Some(b"abc")));
+        assert!(!secret.authorizes(None));
+    }
+}
@@ -63,6 +63,17 @@ pub static COMMITTED_TXNS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the equivocations observed by this validator since last restart, by kind: a vote
+/// or a proposal that conflicts with one seen before for the same round.
+pub static EQUIVOCATIONS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_equivocations_count",
+        "Count of the equivocating votes and proposals observed since last restart",
+        &["kind"]
+    )
+    .unwrap()
+});
+
 //////////////////////
 // PROPOSAL ELECTION
 //////////////////////
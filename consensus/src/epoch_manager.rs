@@ -80,6 +80,8 @@ use std::{
     sync::Arc,
     time::Duration,
 };
+use tokio::time::interval;
+use tokio_stream::wrappers::IntervalStream;
 
 /// Range of rounds (window) that we might be calling proposer election
 /// functions with at any given time, in addition to the proposer history length.
@@ -404,6 +406,8 @@ impl EpochManager {
             "Received verified epoch change",
         );
 
+        // Syncing to the new epoch may take longer than the liveness probe allows for a stall
+        let _suspended = aptos_liveness::suspend("consensus");
         // shutdown existing processor first to avoid race condition with state sync.
         self.shutdown_current_processor().await;
         // make sure storage is on this ledger_info too, it should be no-op if it's already committed
@@ -893,7 +897,11 @@ impl EpochManager {
     ) {
         // initial start of the processor
         self.await_reconfig_notification().await;
+        // Beats even when there's nothing to do, for the liveness probe
+        let mut heartbeat_interval =
+            IntervalStream::new(interval(aptos_liveness::HEARTBEAT_INTERVAL)).fuse();
         loop {
+            aptos_liveness::heartbeat("consensus");
             ::futures::select! {
                 (peer, msg) = network_receivers.consensus_messages.select_next_some() => {
                     if let Err(e) = self.process_message(peer, msg).await {
@@ -908,6 +916,7 @@ impl EpochManager {
                 round = round_timeout_sender_rx.select_next_some() => {
                     self.process_local_timeout(round);
                 },
+                _ = heartbeat_interval.select_next_some() => {},
             }
             // Continually capture the time of consensus process to ensure that clock skew between
             // validators is reasonable and to find any unusual (possibly byzantine) clock behavior.
@@ -32,6 +32,8 @@ mod persistent_liveness_storage;
 mod quorum_store;
 mod recovery_manager;
 mod round_manager;
+#[cfg(test)]
+mod simulation;
 mod state_computer;
 mod state_replication;
 #[cfg(any(test, feature = "fuzzing"))]
@@ -12,6 +12,7 @@ use consensus_types::{
 };
 
 use super::proposer_election::ProposerElection;
+use crate::counters;
 
 // Wrapper around ProposerElection.
 //
@@ -74,6 +75,9 @@ impl UnequivocalProposerElection {
                             already_proposed.1,
                             block.id()
                         );
+                        counters::EQUIVOCATIONS_COUNT
+                            .with_label_values(&["proposal"])
+                            .inc();
                         false
                     } else {
                         true
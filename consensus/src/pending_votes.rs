@@ -7,6 +7,7 @@
 //! when enough votes (or timeout votes) have been observed.
 //! Votes are automatically dropped when the structure goes out of scope.
 
+use crate::counters;
 use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_logger::prelude::*;
 use aptos_types::{
@@ -110,6 +111,9 @@ impl PendingVotes {
                     vote = vote,
                     previous_vote = previously_seen_vote
                 );
+                counters::EQUIVOCATIONS_COUNT
+                    .with_label_values(&["vote"])
+                    .inc();
 
                 return VoteReceptionResult::EquivocateVote;
             }
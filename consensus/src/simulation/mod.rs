@@ -0,0 +1,6 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+mod simulated_network;
+mod simulation_test;
+mod simulator;
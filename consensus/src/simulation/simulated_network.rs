@@ -0,0 +1,221 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::network_interface::ConsensusMsg;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+/// How the messages sent over a link are delayed and lost
+#[derive(Clone, Copy, Debug)]
+pub struct LinkConfig {
+    /// The delays are uniform between `min_delay` and `max_delay`
+    pub min_delay: Duration,
+    pub max_delay: Duration,
+    /// The probability, between 0 and 1, that a message is lost
+    pub drop_probability: f64,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            min_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+            drop_probability: 0.0,
+        }
+    }
+}
+
+/// Groups of nodes that can't reach each other from `start` until `end`. The nodes left out of
+/// the groups form a group of their own.
+#[derive(Clone, Debug)]
+struct ScheduledPartition {
+    start: Duration,
+    end: Duration,
+    groups: Vec<Vec<usize>>,
+}
+
+impl ScheduledPartition {
+    fn separates(&self, time: Duration, src: usize, dst: usize) -> bool {
+        if time < self.start || time >= self.end {
+            return false;
+        }
+        let group_of = |node| self.groups.iter().position(|group| group.contains(&node));
+        group_of(src) != group_of(dst)
+    }
+}
+
+/// A message on its way from node `src` to node `dst`
+#[derive(Debug)]
+pub struct Envelope {
+    pub src: usize,
+    pub dst: usize,
+    pub msg: ConsensusMsg,
+}
+
+/// A network between the nodes of a simulation, identified by their index. The delays and losses
+/// of the messages are sampled from a seeded rng, so the same seed gives the same network.
+/// Messages overtake each other when their delays differ, which reorders them.
+pub struct SimulatedNetwork {
+    rng: StdRng,
+    default_link: LinkConfig,
+    links: HashMap<(usize, usize), LinkConfig>,
+    partitions: Vec<ScheduledPartition>,
+    /// The messages to deliver, by delivery time and then by the order they were sent in
+    in_flight: BTreeMap<(Duration, u64), Envelope>,
+    next_sequence_number: u64,
+}
+
+impl SimulatedNetwork {
+    pub fn new(seed: u64, default_link: LinkConfig) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            default_link,
+            links: HashMap::new(),
+            partitions: vec![],
+            in_flight: BTreeMap::new(),
+            next_sequence_number: 0,
+        }
+    }
+
+    /// Overrides the default link configuration for the messages from `src` to `dst`
+    pub fn set_link(&mut self, src: usize, dst: usize, link: LinkConfig) {
+        self.links.insert((src, dst), link);
+    }
+
+    /// Drops the messages sent between the groups from `start` until `end`
+    pub fn partition(&mut self, start: Duration, end: Duration, groups: Vec<Vec<usize>>) {
+        self.partitions
+            .push(ScheduledPartition { start, end, groups });
+    }
+
+    pub fn is_partitioned(&self, time: Duration, src: usize, dst: usize) -> bool {
+        self.partitions
+            .iter()
+            .any(|partition| partition.separates(time, src, dst))
+    }
+
+    /// Sends a message at `now`, returns when it will be delivered or None if it's lost
+    pub fn send(
+        &mut self,
+        now: Duration,
+        src: usize,
+        dst: usize,
+        msg: ConsensusMsg,
+    ) -> Option<Duration> {
+        let link = *self.links.get(&(src, dst)).unwrap_or(&self.default_link);
+        // Always sample both, so scripting a partition doesn't change the fate of other messages
+        let lost = self.rng.gen_bool(link.drop_probability);
+        let delay = Duration::from_micros(self.rng.gen_range(
+            link.min_delay.as_micros() as u64,
+            link.max_delay.as_micros() as u64 + 1,
+        ));
+        if lost || self.is_partitioned(now, src, dst) {
+            return None;
+        }
+        let delivery_time = now + delay;
+        self.push(delivery_time, Envelope { src, dst, msg });
+        Some(delivery_time)
+    }
+
+    /// Messages from a node to itself skip the network, like with the self sender of
+    /// `NetworkSender`, and are delivered right away
+    pub fn send_to_self(&mut self, now: Duration, node: usize, msg: ConsensusMsg) {
+        self.push(
+            now,
+            Envelope {
+                src: node,
+                dst: node,
+                msg,
+            },
+        );
+    }
+
+    pub fn next_delivery_time(&self) -> Option<Duration> {
+        self.in_flight.keys().next().map(|(time, _)| *time)
+    }
+
+    /// Takes the next message to deliver, with its delivery time
+    pub fn pop(&mut self) -> Option<(Duration, Envelope)> {
+        let key = *self.in_flight.keys().next()?;
+        self.in_flight
+            .remove(&key)
+            .map(|envelope| (key.0, envelope))
+    }
+
+    fn push(&mut self, delivery_time: Duration, envelope: Envelope) {
+        self.in_flight
+            .insert((delivery_time, self.next_sequence_number), envelope);
+        self.next_sequence_number += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn sync_info() -> ConsensusMsg {
+        ConsensusMsg::SyncInfo(Box::new(test_utils::placeholder_sync_info()))
+    }
+
+    fn delivery_times(seed: u64) -> Vec<Option<Duration>> {
+        let mut network = SimulatedNetwork::new(
+            seed,
+            LinkConfig {
+                drop_probability: 0.2,
+                ..LinkConfig::default()
+            },
+        );
+        (0..20)
+            .map(|i| network.send(Duration::from_millis(i), 0, 1, sync_info()))
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_delivery_times() {
+        assert_eq!(delivery_times(7), delivery_times(7));
+        assert_ne!(delivery_times(7), delivery_times(8));
+    }
+
+    #[test]
+    fn test_reordering_and_partitions() {
+        let mut network = SimulatedNetwork::new(0, LinkConfig::default());
+        network.set_link(
+            0,
+            2,
+            LinkConfig {
+                min_delay: Duration::from_millis(500),
+                max_delay: Duration::from_millis(500),
+                drop_probability: 0.0,
+            },
+        );
+        network.partition(
+            Duration::from_secs(1),
+            Duration::from_secs(2),
+            vec![vec![0], vec![1, 2]],
+        );
+
+        let now = Duration::from_millis(0);
+        assert_eq!(
+            network.send(now, 0, 2, sync_info()),
+            Some(Duration::from_millis(500))
+        );
+        assert!(network.send(now, 0, 1, sync_info()).is_some());
+        network.send_to_self(now, 0, sync_info());
+        // Sent last, delivered first
+        let order: Vec<_> = std::iter::from_fn(|| network.pop())
+            .map(|(_, envelope)| envelope.dst)
+            .collect();
+        assert_eq!(order, vec![0, 1, 2]);
+
+        let during = Duration::from_millis(1500);
+        assert!(network.send(during, 0, 1, sync_info()).is_none());
+        assert!(network.send(during, 1, 2, sync_info()).is_some());
+        assert!(network
+            .send(Duration::from_secs(2), 0, 1, sync_info())
+            .is_some());
+    }
+}
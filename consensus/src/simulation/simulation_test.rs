@@ -0,0 +1,84 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block_storage::BlockReader,
+    simulation::{
+        simulated_network::{LinkConfig, SimulatedNetwork},
+        simulator::{Simulation, TraceEvent, START_TIME},
+    },
+};
+use aptos_crypto::HashValue;
+use consensus_types::common::Round;
+use std::{collections::HashMap, time::Duration};
+
+fn lossy_network(seed: u64) -> SimulatedNetwork {
+    SimulatedNetwork::new(
+        seed,
+        LinkConfig {
+            min_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(300),
+            drop_probability: 0.05,
+        },
+    )
+}
+
+/// Checks no two nodes committed different blocks in the same round, and returns the highest
+/// committed round
+fn check_commits(simulation: &Simulation) -> Option<Round> {
+    let mut committed: HashMap<Round, HashValue> = HashMap::new();
+    for event in simulation.trace() {
+        if let TraceEvent::Committed {
+            round, block_id, ..
+        } = event
+        {
+            assert_eq!(
+                committed.entry(*round).or_insert(*block_id),
+                block_id,
+                "Conflicting commits in round {}",
+                round
+            );
+        }
+    }
+    committed.keys().max().copied()
+}
+
+#[test]
+/// Runs with the same seed are identical, down to the order and timing of every message
+///
+/// Run the test:
+/// cargo xtest -p consensus test_replay_from_seed -- --nocapture
+fn test_replay_from_seed() {
+    let run = |seed| {
+        let mut simulation = Simulation::new(4, lossy_network(seed));
+        simulation.run_until(START_TIME + Duration::from_secs(30));
+        assert!(check_commits(&simulation).is_some());
+        simulation.trace().to_vec()
+    };
+    let trace = run(42);
+    assert_eq!(trace, run(42));
+    assert_ne!(trace, run(43));
+}
+
+#[test]
+/// Nothing is committed while neither side of a partition has a quorum, the nodes catch up once
+/// it heals
+fn test_commits_after_partition_heals() {
+    let partition_end = START_TIME + Duration::from_secs(20);
+    let mut simulation = Simulation::new(4, SimulatedNetwork::new(0, LinkConfig::default()));
+    simulation
+        .network_mut()
+        .partition(START_TIME, partition_end, vec![vec![0, 1], vec![2, 3]]);
+
+    simulation.run_until(partition_end);
+    assert_eq!(check_commits(&simulation), None);
+
+    simulation.run_until(partition_end + Duration::from_secs(40));
+    let highest_committed_round =
+        check_commits(&simulation).expect("No commit after the partition");
+    let commit_roots: Vec<_> = (0..4)
+        .map(|node| simulation.block_store(node).commit_root().round())
+        .collect();
+    assert!(commit_roots.iter().all(|round| *round > 0));
+    assert!(commit_roots.contains(&highest_committed_round));
+}
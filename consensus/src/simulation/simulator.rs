@@ -0,0 +1,500 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block_storage::BlockStore,
+    liveness::{
+        proposal_generator::ProposalGenerator,
+        rotating_proposer_election::RotatingProposer,
+        round_state::{ExponentialTimeInterval, RoundState},
+    },
+    metrics_safety_rules::MetricsSafetyRules,
+    network::{IncomingBlockRetrievalRequest, NetworkSender},
+    network_interface::{ConsensusMsg, ConsensusNetworkSender},
+    round_manager::{RoundManager, UnverifiedEvent, VerifiedEvent},
+    simulation::simulated_network::SimulatedNetwork,
+    test_utils::{consensus_runtime, EmptyPayloadManager, MockStateComputer, MockStorage},
+    util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
+};
+use aptos_config::{config::ConsensusConfig, network_id::NetworkId};
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_secure_storage::Storage;
+use aptos_types::{
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    on_chain_config::OnChainConsensusConfig,
+    transaction::SignedTransaction,
+    validator_signer::ValidatorSigner,
+    validator_verifier::{random_validator_verifier, ValidatorVerifier},
+    waypoint::Waypoint,
+    PeerId,
+};
+use channel::{self, aptos_channel, message_queues::QueueStyle};
+use consensus_types::common::{Author, Round};
+use futures::{
+    channel::mpsc,
+    future::{self, Either},
+    pin_mut, FutureExt, StreamExt,
+};
+use network::{
+    application::storage::PeerMetadataStorage,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::{
+        direct_send::Message,
+        network::{Event, NewNetworkSender},
+        wire::handshake::v1::ProtocolIdSet,
+    },
+    transport::ConnectionMetadata,
+    ProtocolId,
+};
+use safety_rules::{PersistentSafetyStorage, SafetyRulesManager};
+use std::{collections::HashMap, iter::FromIterator, sync::Arc, time::Duration};
+use tokio::runtime::Runtime;
+
+/// The time the simulations start at, after the timestamp of genesis since the timestamps of the
+/// blocks must increase
+pub const START_TIME: Duration = Duration::from_secs(1);
+
+/// What happened in a simulation, in order. Two runs with the same seed have the same trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// A message was sent, it's delivered at `delivery_time` unless it's lost
+    Sent {
+        time: Duration,
+        src: usize,
+        dst: usize,
+        msg: String,
+        delivery_time: Option<Duration>,
+    },
+    Delivered {
+        time: Duration,
+        src: usize,
+        dst: usize,
+        msg: String,
+    },
+    Timeout {
+        time: Duration,
+        node: usize,
+        round: Round,
+    },
+    Committed {
+        time: Duration,
+        node: usize,
+        round: Round,
+        block_id: HashValue,
+    },
+}
+
+enum NodeEvent {
+    Message(Author, ConsensusMsg),
+    LocalTimeout(Round),
+}
+
+/// A validator whose round manager is driven by the simulation, instead of by its event loop
+struct SimulatedNode {
+    block_store: Arc<BlockStore>,
+    round_manager: RoundManager,
+    network_reqs_rx: aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    self_receiver: channel::Receiver<Event<ConsensusMsg>>,
+    round_timeout_receiver: channel::Receiver<Round>,
+    commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    _state_sync_receiver: mpsc::UnboundedReceiver<Vec<SignedTransaction>>,
+}
+
+impl SimulatedNode {
+    fn new(
+        runtime: &Runtime,
+        signer: ValidatorSigner,
+        validators: &ValidatorVerifier,
+        waypoint: Waypoint,
+        time_service: &SimulatedTimeService,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) -> Self {
+        let author = signer.author();
+        let (initial_data, storage) = MockStorage::start_for_testing(validators.into());
+        let safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(aptos_secure_storage::InMemoryStorage::new()),
+            author,
+            signer.private_key().clone(),
+            waypoint,
+            true,
+        );
+        let safety_rules_manager = SafetyRulesManager::new_local(safety_storage);
+        let epoch_state = EpochState {
+            epoch: 1,
+            verifier: storage.get_validator_set().into(),
+        };
+
+        let (network_reqs_tx, network_reqs_rx) = aptos_channel::new(QueueStyle::FIFO, 1_024, None);
+        let (connection_reqs_tx, _) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+        let mut network_sender = ConsensusNetworkSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+        network_sender.initialize(peer_metadata_storage);
+        let (self_sender, self_receiver) = channel::new_test(1_024);
+        let network = NetworkSender::new(
+            author,
+            network_sender,
+            self_sender,
+            epoch_state.verifier.clone(),
+        );
+
+        let last_vote_sent = initial_data.last_vote();
+        let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
+        let (state_sync_client, _state_sync_receiver) = mpsc::unbounded();
+        let state_computer = Arc::new(MockStateComputer::new(
+            state_sync_client,
+            commit_cb_sender,
+            Arc::clone(&storage),
+        ));
+        let time_service = Arc::new(time_service.clone());
+        let block_store = Arc::new(BlockStore::new(
+            storage.clone(),
+            initial_data,
+            state_computer,
+            10, // max pruned blocks in mem
+            time_service.clone(),
+            10,
+        ));
+        let proposal_generator = ProposalGenerator::new(
+            author,
+            block_store.clone(),
+            Arc::new(EmptyPayloadManager),
+            time_service.clone(),
+            10,
+            1000,
+            10,
+        );
+
+        let config = ConsensusConfig::default();
+        let time_interval = Box::new(ExponentialTimeInterval::new(
+            Duration::from_millis(config.round_initial_timeout_ms),
+            config.round_timeout_backoff_exponent_base,
+            config.round_timeout_backoff_max_exponent,
+        ));
+        let (round_timeout_sender, round_timeout_receiver) = channel::new_test(1_024);
+        let round_state = RoundState::new(time_interval, time_service, round_timeout_sender);
+        let proposer_election = Box::new(RotatingProposer::new(
+            validators.get_ordered_account_addresses_iter().collect(),
+            1,
+        ));
+        let mut safety_rules =
+            MetricsSafetyRules::new(safety_rules_manager.client(), storage.clone());
+        safety_rules.perform_initialize().unwrap();
+        let (round_manager_tx, _) = aptos_channel::new(QueueStyle::LIFO, 1, None);
+
+        let mut round_manager = RoundManager::new(
+            epoch_state,
+            Arc::clone(&block_store),
+            round_state,
+            proposer_election,
+            proposal_generator,
+            Arc::new(Mutex::new(safety_rules)),
+            network,
+            storage,
+            OnChainConsensusConfig::default(),
+            round_manager_tx,
+            config,
+        );
+        runtime.block_on(round_manager.init(last_vote_sent));
+        Self {
+            block_store,
+            round_manager,
+            network_reqs_rx,
+            self_receiver,
+            round_timeout_receiver,
+            commit_cb_receiver,
+            _state_sync_receiver,
+        }
+    }
+}
+
+/// Runs validators on a virtual clock and a simulated network, one event at a time: a timeout
+/// fires or a message is delivered, then the messages sent while processing it go into the
+/// network. Nothing depends on real time or on the scheduling of tasks, so a run only depends on
+/// the seed of the network and can be replayed exactly.
+///
+/// Like with the `NetworkPlayground`, block retrieval RPCs are answered right away by the block
+/// store of the peer, and are never dropped.
+pub struct Simulation {
+    runtime: Runtime,
+    time_service: SimulatedTimeService,
+    network: SimulatedNetwork,
+    nodes: Vec<SimulatedNode>,
+    block_stores: Vec<Arc<BlockStore>>,
+    authors: Vec<Author>,
+    author_to_node: HashMap<Author, usize>,
+    validators: ValidatorVerifier,
+    trace: Vec<TraceEvent>,
+}
+
+impl Simulation {
+    pub fn new(num_nodes: usize, network: SimulatedNetwork) -> Self {
+        let runtime = consensus_runtime();
+        let time_service = SimulatedTimeService::new();
+        time_service.advance_to(START_TIME);
+
+        let (signers, validators) = random_validator_verifier(num_nodes, None, false);
+        let waypoint =
+            Waypoint::new_epoch_boundary(&LedgerInfo::mock_genesis(Some((&validators).into())))
+                .unwrap();
+        let peer_metadata_storage = PeerMetadataStorage::new(&[NetworkId::Validator]);
+        for signer in &signers {
+            let mut conn_meta = ConnectionMetadata::mock(signer.author());
+            conn_meta.application_protocols = ProtocolIdSet::from_iter([
+                ProtocolId::ConsensusDirectSendBcs,
+                ProtocolId::ConsensusRpcBcs,
+            ]);
+            peer_metadata_storage.insert_connection(NetworkId::Validator, conn_meta);
+        }
+
+        let authors: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+        let nodes: Vec<_> = signers
+            .into_iter()
+            .map(|signer| {
+                SimulatedNode::new(
+                    &runtime,
+                    signer,
+                    &validators,
+                    waypoint,
+                    &time_service,
+                    peer_metadata_storage.clone(),
+                )
+            })
+            .collect();
+        let mut simulation = Self {
+            runtime,
+            time_service,
+            network,
+            block_stores: nodes.iter().map(|node| node.block_store.clone()).collect(),
+            nodes,
+            author_to_node: authors
+                .iter()
+                .enumerate()
+                .map(|(id, author)| (*author, id))
+                .collect(),
+            authors,
+            validators,
+            trace: vec![],
+        };
+        // The first proposal is sent when the round managers start
+        for id in 0..num_nodes {
+            simulation.flush(id, vec![]);
+        }
+        simulation
+    }
+
+    pub fn now(&self) -> Duration {
+        self.time_service.get_current_timestamp()
+    }
+
+    pub fn trace(&self) -> &[TraceEvent] {
+        &self.trace
+    }
+
+    /// To script the network between the runs
+    pub fn network_mut(&mut self) -> &mut SimulatedNetwork {
+        &mut self.network
+    }
+
+    pub fn block_store(&self, node: usize) -> &Arc<BlockStore> {
+        &self.block_stores[node]
+    }
+
+    /// Processes the timeouts and messages due until `end`, in order
+    pub fn run_until(&mut self, end: Duration) {
+        loop {
+            let next_time = [
+                self.network.next_delivery_time(),
+                self.time_service.next_deadline(),
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            match next_time {
+                Some(time) if time <= end => self.time_service.advance_to(time),
+                _ => break,
+            }
+
+            // Timeouts first, then the messages due at the same time
+            for id in 0..self.nodes.len() {
+                while let Some(Some(round)) =
+                    self.nodes[id].round_timeout_receiver.next().now_or_never()
+                {
+                    self.trace.push(TraceEvent::Timeout {
+                        time: self.now(),
+                        node: id,
+                        round,
+                    });
+                    self.step(id, NodeEvent::LocalTimeout(round));
+                }
+            }
+            if self
+                .network
+                .next_delivery_time()
+                .map_or(false, |time| time <= self.now())
+            {
+                let (_, envelope) = self.network.pop().unwrap();
+                self.trace.push(TraceEvent::Delivered {
+                    time: self.now(),
+                    src: envelope.src,
+                    dst: envelope.dst,
+                    msg: describe(&envelope.msg),
+                });
+                let author = self.authors[envelope.src];
+                self.step(envelope.dst, NodeEvent::Message(author, envelope.msg));
+            }
+        }
+        self.time_service.advance_to(end);
+    }
+
+    fn step(&mut self, id: usize, event: NodeEvent) {
+        let Self {
+            runtime,
+            nodes,
+            block_stores,
+            author_to_node,
+            validators,
+            ..
+        } = self;
+        let SimulatedNode {
+            round_manager,
+            network_reqs_rx,
+            ..
+        } = &mut nodes[id];
+        let mut outbound = vec![];
+        let result = runtime.block_on(async {
+            // Answer the RPCs while the event is processed, since it waits for their responses
+            let process = process_event(round_manager, validators, event);
+            let serve =
+                serve_requests(network_reqs_rx, &mut outbound, block_stores, author_to_node);
+            pin_mut!(process, serve);
+            match future::select(process, serve).await {
+                Either::Left((result, _)) => result,
+                Either::Right((_, process)) => process.await,
+            }
+        });
+        if let Err(error) = result {
+            debug!(
+                "[simulation] node {} failed to process an event: {:?}",
+                id, error
+            );
+        }
+        self.flush(id, outbound);
+    }
+
+    /// Sends the messages of the node into the network, and records its commits
+    fn flush(&mut self, id: usize, mut outbound: Vec<(PeerId, Message)>) {
+        let now = self.now();
+        let node = &mut self.nodes[id];
+        while let Some(Some(request)) = node.network_reqs_rx.next().now_or_never() {
+            // Nothing waits for the response of an RPC anymore, so it's dropped
+            if let PeerManagerRequest::SendDirectSend(dst, msg) = request {
+                outbound.push((dst, msg));
+            }
+        }
+
+        while let Some(Some(event)) = node.self_receiver.next().now_or_never() {
+            if let Event::Message(_, msg) = event {
+                self.trace.push(TraceEvent::Sent {
+                    time: now,
+                    src: id,
+                    dst: id,
+                    msg: describe(&msg),
+                    delivery_time: Some(now),
+                });
+                self.network.send_to_self(now, id, msg);
+            }
+        }
+        for (dst, msg) in outbound {
+            let msg: ConsensusMsg = msg.protocol_id.from_bytes(&msg.mdata).unwrap();
+            let dst = self.author_to_node[&dst];
+            let description = describe(&msg);
+            let delivery_time = self.network.send(now, id, dst, msg);
+            self.trace.push(TraceEvent::Sent {
+                time: now,
+                src: id,
+                dst,
+                msg: description,
+                delivery_time,
+            });
+        }
+
+        while let Some(Some(ledger_info)) = node.commit_cb_receiver.next().now_or_never() {
+            self.trace.push(TraceEvent::Committed {
+                time: now,
+                node: id,
+                round: ledger_info.ledger_info().round(),
+                block_id: ledger_info.ledger_info().consensus_block_id(),
+            });
+        }
+    }
+}
+
+async fn process_event(
+    round_manager: &mut RoundManager,
+    validators: &ValidatorVerifier,
+    event: NodeEvent,
+) -> anyhow::Result<()> {
+    match event {
+        NodeEvent::LocalTimeout(round) => round_manager.process_local_timeout(round).await,
+        NodeEvent::Message(author, msg) => match UnverifiedEvent::from(msg).verify(validators)? {
+            VerifiedEvent::ProposalMsg(proposal_msg) => {
+                round_manager.process_proposal_msg(*proposal_msg).await
+            }
+            VerifiedEvent::VoteMsg(vote_msg) => round_manager.process_vote_msg(*vote_msg).await,
+            VerifiedEvent::UnverifiedSyncInfo(sync_info) => {
+                round_manager
+                    .process_sync_info_msg(*sync_info, author)
+                    .await
+            }
+            // The commit messages of decoupled execution go to the buffer manager, which isn't
+            // simulated
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Answers the block retrievals and buffers the other requests, until the channel closes
+async fn serve_requests(
+    network_reqs_rx: &mut aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    outbound: &mut Vec<(PeerId, Message)>,
+    block_stores: &[Arc<BlockStore>],
+    author_to_node: &HashMap<Author, usize>,
+) {
+    while let Some(request) = network_reqs_rx.next().await {
+        match request {
+            PeerManagerRequest::SendRpc(dst, rpc) => {
+                // Dropping the response sender of other requests fails them
+                if let Ok(ConsensusMsg::BlockRetrievalRequest(req)) =
+                    rpc.protocol_id.from_bytes(&rpc.data)
+                {
+                    let request = IncomingBlockRetrievalRequest {
+                        req: *req,
+                        protocol: rpc.protocol_id,
+                        response_sender: rpc.res_tx,
+                    };
+                    let block_store = &block_stores[author_to_node[&dst]];
+                    if let Err(error) = block_store.process_block_retrieval(request).await {
+                        debug!("[simulation] failed to retrieve blocks: {:?}", error);
+                    }
+                }
+            }
+            PeerManagerRequest::SendDirectSend(dst, msg) => outbound.push((dst, msg)),
+        }
+    }
+}
+
+fn describe(msg: &ConsensusMsg) -> String {
+    match msg {
+        ConsensusMsg::ProposalMsg(proposal_msg) => {
+            format!("ProposalMsg {}", proposal_msg.proposal())
+        }
+        ConsensusMsg::VoteMsg(vote_msg) => format!("VoteMsg {}", vote_msg),
+        ConsensusMsg::SyncInfo(sync_info) => format!("SyncInfo {}", sync_info),
+        msg => msg.name().to_string(),
+    }
+}
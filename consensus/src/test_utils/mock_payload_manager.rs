@@ -61,3 +61,20 @@ impl PayloadManager for MockPayloadManager {
         Ok(random_payload(10))
     }
 }
+
+/// Always proposes empty payloads, so the blocks only depend on the rounds and timestamps
+pub struct EmptyPayloadManager;
+
+#[async_trait::async_trait]
+impl PayloadManager for EmptyPayloadManager {
+    async fn pull_payload(
+        &self,
+        _max_size: u64,
+        _max_bytes: u64,
+        _exclude: PayloadFilter,
+        _wait_callback: BoxFuture<'static, ()>,
+        _pending_ordering: bool,
+    ) -> Result<Payload, QuorumStoreError> {
+        Ok(Payload::empty())
+    }
+}
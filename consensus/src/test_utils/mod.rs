@@ -23,7 +23,7 @@ mod mock_storage;
 use crate::util::mock_time_service::SimulatedTimeService;
 use aptos_types::block_info::BlockInfo;
 use consensus_types::{block::block_test_utils::gen_test_certificate, common::Payload};
-pub use mock_payload_manager::MockPayloadManager;
+pub use mock_payload_manager::{EmptyPayloadManager, MockPayloadManager};
 pub use mock_state_computer::{
     EmptyStateComputer, MockStateComputer, RandomComputeResultStateComputer,
 };
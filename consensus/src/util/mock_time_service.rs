@@ -120,6 +120,38 @@ impl SimulatedTimeService {
             futures::executor::block_on(t.run());
         }
     }
+
+    /// Moves the time forward to `time` (it never goes back) and runs the pending tasks whose
+    /// deadline passed, by order of deadline
+    pub fn advance_to(&self, time: Duration) {
+        let mut due = {
+            let mut inner = self.inner.lock();
+            inner.now = inner.now.max(time).min(inner.max);
+            inner.time_limit = inner.time_limit.max(inner.now);
+            let time_limit = inner.time_limit;
+            let (due, pending): (Vec<_>, Vec<_>) = inner
+                .pending
+                .drain(..)
+                .partition(|(deadline, _)| *deadline <= time_limit);
+            inner.pending = pending;
+            due
+        };
+        // The sort is stable, so tasks with the same deadline run in the order they were scheduled
+        due.sort_by_key(|(deadline, _)| *deadline);
+        for (_, mut t) in due {
+            futures::executor::block_on(t.run());
+        }
+    }
+
+    /// The earliest deadline of the pending tasks, if any
+    pub fn next_deadline(&self) -> Option<Duration> {
+        self.inner
+            .lock()
+            .pending
+            .iter()
+            .map(|(deadline, _)| *deadline)
+            .min()
+    }
 }
 
 impl Clone for SimulatedTimeService {
@@ -0,0 +1,201 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The debug bundle, a gzipped tarball of what support needs to diagnose a node: the end of the
+//! log files, a snapshot of the metrics, the effective config with its secrets redacted, the
+//! connected peers, the consensus round state, the storage stats and a dump of the threads. A
+//! part that can't be collected is listed in the manifest of the bundle along with its error,
+//! instead of failing the whole bundle.
+
+use crate::AdminServiceContext;
+use anyhow::{format_err, Result};
+use aptos_logger::sinks::LogSinkConfig;
+use flate2::{write::GzEncoder, Compression};
+use inspection_service::{
+    inspection_service::{encode_metrics, get_consensus_round_state, get_state_sync_progress},
+    runtime_configuration::get_effective_configuration,
+};
+use prometheus::TextEncoder;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use storage_interface::DbReader;
+
+/// Only the end of each log file is bundled, so the bundle stays small enough to share
+const MAX_LOG_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Collects the bundle, returning the gzipped tarball
+pub(crate) fn collect_debug_bundle(context: &AdminServiceContext) -> Result<Vec<u8>> {
+    let mut bundle = Bundle::new()?;
+    bundle.add("config.json", || {
+        to_json(&get_effective_configuration(&context.node_config)?)
+    });
+    bundle.add("metrics.txt", || Ok(encode_metrics(TextEncoder::new())));
+    bundle.add("peers.json", || to_json(&get_peers(context)));
+    bundle.add("consensus.json", || to_json(&get_consensus_round_state()));
+    bundle.add("state_sync.json", || to_json(&get_state_sync_progress()));
+    bundle.add("storage.json", || to_json(&get_storage_stats(context)?));
+    bundle.add("threads.txt", get_thread_dump);
+    for sink in &context.node_config.logger.sinks {
+        if let LogSinkConfig::File(config) = sink {
+            let file_name = config.path.file_name().unwrap_or_default();
+            bundle.add(&format!("logs/{}", file_name.to_string_lossy()), || {
+                read_tail(&config.path, MAX_LOG_BYTES)
+            });
+        }
+    }
+    bundle.finish()
+}
+
+struct Bundle {
+    builder: tar::Builder<GzEncoder<Vec<u8>>>,
+    created_at_secs: u64,
+    files: Vec<String>,
+    errors: Map<String, Value>,
+}
+
+impl Bundle {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            builder: tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default())),
+            created_at_secs: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            files: Vec::new(),
+            errors: Map::new(),
+        })
+    }
+
+    /// Adds a file to the bundle, or its error to the manifest
+    fn add(&mut self, path: &str, collect: impl FnOnce() -> Result<Vec<u8>>) {
+        match collect().and_then(|contents| self.append(path, &contents)) {
+            Ok(()) => self.files.push(path.to_string()),
+            Err(error) => {
+                self.errors
+                    .insert(path.to_string(), error.to_string().into());
+            }
+        }
+    }
+
+    fn append(&mut self, path: &str, contents: &[u8]) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(self.created_at_secs);
+        self.builder.append_data(&mut header, path, contents)?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>> {
+        let manifest = json!({
+            "created_at_secs": self.created_at_secs,
+            "files": self.files,
+            "errors": self.errors,
+        });
+        self.append("manifest.json", &serde_json::to_vec_pretty(&manifest)?)?;
+        Ok(self.builder.into_inner()?.finish()?)
+    }
+}
+
+fn to_json(value: &impl Serialize) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec_pretty(value)?)
+}
+
+/// The connected peers of each network
+fn get_peers(context: &AdminServiceContext) -> Map<String, Value> {
+    let peer_metadata_storage = &context.peer_metadata_storage;
+    peer_metadata_storage
+        .networks()
+        .map(|network_id| {
+            let peers: Vec<_> = peer_metadata_storage
+                .read_all(network_id)
+                .into_values()
+                .collect();
+            (network_id.to_string(), json!(peers))
+        })
+        .collect()
+}
+
+/// The versions in the DB, the state storage usage and the pruner settings
+fn get_storage_stats(context: &AdminServiceContext) -> Result<Value> {
+    let db = &context.db;
+    let latest_version = db.get_latest_version()?;
+    Ok(json!({
+        "latest_version": latest_version,
+        "first_txn_version": db.get_first_txn_version()?,
+        "first_write_set_version": db.get_first_write_set_version()?,
+        "state_storage_usage": db.get_state_storage_usage(Some(latest_version))?,
+        "ledger_pruner_enabled": db.is_ledger_pruner_enabled()?,
+        "ledger_prune_window": db.get_ledger_prune_window()?,
+        "state_pruner_enabled": db.is_state_pruner_enabled()?,
+        "epoch_snapshot_prune_window": db.get_epoch_snapshot_prune_window()?,
+    }))
+}
+
+/// The name, state and wait channel of each thread, with its kernel stack where it's readable.
+/// Only Linux exposes the threads of a process.
+fn get_thread_dump() -> Result<Vec<u8>> {
+    let tasks = Path::new("/proc/self/task");
+    if !tasks.exists() {
+        return Err(format_err!("Thread dumps are only supported on Linux"));
+    }
+
+    let mut thread_ids: Vec<u64> = fs::read_dir(tasks)?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    thread_ids.sort_unstable();
+
+    let mut dump = String::new();
+    for thread_id in thread_ids {
+        let task = tasks.join(thread_id.to_string());
+        let read = |file: &str| fs::read_to_string(task.join(file)).unwrap_or_default();
+        // The state follows the name in parentheses, which may contain spaces
+        let stat = read("stat");
+        let state = stat
+            .rsplit_once(')')
+            .and_then(|(_, fields)| fields.split_whitespace().next())
+            .unwrap_or("?");
+        dump.push_str(&format!(
+            "{} {} state={} wchan={}\n",
+            thread_id,
+            read("comm").trim(),
+            state,
+            read("wchan").trim(),
+        ));
+        for frame in read("stack").lines() {
+            dump.push_str(&format!("    {}\n", frame));
+        }
+    }
+    Ok(dump.into_bytes())
+}
+
+/// Reads the last `max_bytes` of the file
+fn read_tail(path: &Path, max_bytes: u64) -> Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max_bytes)))?;
+    let mut contents = Vec::new();
+    file.take(max_bytes).read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_temppath::TempPath;
+    use std::io::Write;
+
+    #[test]
+    fn test_read_tail() {
+        let path = TempPath::new();
+        File::create(path.path())
+            .unwrap()
+            .write_all(b"0123456789")
+            .unwrap();
+        assert_eq!(read_tail(path.path(), 4).unwrap(), b"6789");
+        assert_eq!(read_tail(path.path(), 20).unwrap(), b"0123456789");
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{debug_bundle::collect_debug_bundle, AdminServiceContext};
+use anyhow::{anyhow, format_err, Result};
+use aptos_audit::{audit, AuditEvent};
+use aptos_config::{config::AdminServiceConfig, network_id::NetworkId};
+use aptos_logger::prelude::*;
+use aptos_mempool::MempoolClientRequest;
+use aptos_types::PeerId;
+use futures::{channel::oneshot, SinkExt};
+use serde_json::json;
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use warp::{
+    filters::BoxedFilter,
+    http::StatusCode,
+    path::FullPath,
+    reply::{self, Response},
+    Filter, Reply,
+};
+
+/// The operational actions of the admin service, each behind its own flag in the config
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AdminAction {
+    /// `POST /prune`
+    TriggerPruning,
+    /// `POST /compact`
+    Compact,
+    /// `POST /checkpoint`
+    CreateCheckpoint,
+    /// `POST /peers/{network_id}/{peer_id}/disconnect`
+    DisconnectPeer { network_id: String, peer_id: String },
+    /// `POST /mempool/flush`
+    FlushMempool,
+    /// `POST /drain`, to shut the node down gracefully
+    Drain,
+    /// `POST /config/reload`, to apply the reloadable settings of the config file
+    ReloadConfig,
+    /// `POST /debug-bundle`, returning the debug bundle for support as a gzipped tarball
+    CollectDebugBundle,
+}
+
+impl AdminAction {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["prune"] => Some(AdminAction::TriggerPruning),
+            ["compact"] => Some(AdminAction::Compact),
+            ["checkpoint"] => Some(AdminAction::CreateCheckpoint),
+            ["peers", network_id, peer_id, "disconnect"] => Some(AdminAction::DisconnectPeer {
+                network_id: network_id.to_string(),
+                peer_id: peer_id.to_string(),
+            }),
+            ["mempool", "flush"] => Some(AdminAction::FlushMempool),
+            ["drain"] => Some(AdminAction::Drain),
+            ["config", "reload"] => Some(AdminAction::ReloadConfig),
+            ["debug-bundle"] => Some(AdminAction::CollectDebugBundle),
+            _ => None,
+        }
+    }
+
+    /// The action recorded in the audit log
+    pub fn audit_name(&self) -> &'static str {
+        match self {
+            AdminAction::TriggerPruning => "admin.trigger_pruning",
+            AdminAction::Compact => "admin.compact",
+            AdminAction::CreateCheckpoint => "admin.create_checkpoint",
+            AdminAction::DisconnectPeer { .. } => "admin.disconnect_peer",
+            AdminAction::FlushMempool => "admin.flush_mempool",
+            AdminAction::Drain => "admin.drain",
+            AdminAction::ReloadConfig => "admin.reload_config",
+            AdminAction::CollectDebugBundle => "admin.collect_debug_bundle",
+        }
+    }
+
+    /// The name of the config flag that enables the action
+    fn config_flag(&self) -> &'static str {
+        match self {
+            AdminAction::TriggerPruning => "enable_pruning",
+            AdminAction::Compact => "enable_compaction",
+            AdminAction::CreateCheckpoint => "enable_backup_checkpoints",
+            AdminAction::DisconnectPeer { .. } => "enable_peer_resets",
+            AdminAction::FlushMempool => "enable_mempool_flush",
+            AdminAction::Drain => "enable_drain",
+            AdminAction::ReloadConfig => "enable_config_reload",
+            AdminAction::CollectDebugBundle => "enable_debug_bundle",
+        }
+    }
+
+    pub fn is_enabled(&self, config: &AdminServiceConfig) -> bool {
+        match self {
+            AdminAction::TriggerPruning => config.enable_pruning,
+            AdminAction::Compact => config.enable_compaction,
+            AdminAction::CreateCheckpoint => config.enable_backup_checkpoints,
+            AdminAction::DisconnectPeer { .. } => config.enable_peer_resets,
+            AdminAction::FlushMempool => config.enable_mempool_flush,
+            AdminAction::Drain => config.enable_drain,
+            AdminAction::ReloadConfig => config.enable_config_reload,
+            AdminAction::CollectDebugBundle => config.enable_debug_bundle,
+        }
+    }
+
+    fn add_details(&self, event: AuditEvent) -> AuditEvent {
+        match self {
+            AdminAction::DisconnectPeer {
+                network_id,
+                peer_id,
+            } => event
+                .detail("network_id", network_id)
+                .detail("peer_id", peer_id),
+            _ => event,
+        }
+    }
+}
+
+pub(crate) fn get_routes(context: Arc<AdminServiceContext>) -> BoxedFilter<(Response,)> {
+    warp::post()
+        .and(warp::path::full())
+        .and(warp::addr::remote())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::any().map(move || context.clone()))
+        .and_then(handle_request)
+        .boxed()
+}
+
+async fn handle_request(
+    path: FullPath,
+    remote_addr: Option<SocketAddr>,
+    authorization: Option<String>,
+    context: Arc<AdminServiceContext>,
+) -> Result<Response, Infallible> {
+    let action = match AdminAction::from_path(path.as_str()) {
+        Some(action) => action,
+        None => return Ok(error_response(StatusCode::NOT_FOUND, "Unknown action")),
+    };
+    let actor = remote_addr.map_or_else(|| "unknown".to_string(), |addr| addr.to_string());
+    let event = action.add_details(AuditEvent::new(action.audit_name()).actor(actor.clone()));
+
+    if let Some(auth_token) = &context.config.auth_token {
+        if !auth_token.authorizes(authorization.as_deref().map(str::as_bytes)) {
+            audit(event.outcome(&Err::<(), _>("unauthorized")));
+            return Ok(error_response(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or missing bearer token",
+            ));
+        }
+    }
+    if !action.is_enabled(&context.config) {
+        audit(event.outcome(&Err::<(), _>("disabled")));
+        return Ok(error_response(
+            StatusCode::FORBIDDEN,
+            format!(
+                "This action is disabled! Set {} in the AdminServiceConfig.",
+                action.config_flag()
+            ),
+        ));
+    }
+
+    let result = run_action(&action, &context).await;
+    audit(event.outcome(&result));
+    Ok(match result {
+        Ok(response) => {
+            info!(
+                "Admin action {} performed by {}",
+                action.audit_name(),
+                actor
+            );
+            response
+        }
+        Err(error) => {
+            warn!(
+                "Admin action {} requested by {} failed: {}",
+                action.audit_name(),
+                actor,
+                error
+            );
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
+    })
+}
+
+async fn run_action(action: &AdminAction, context: &Arc<AdminServiceContext>) -> Result<Response> {
+    match action {
+        AdminAction::TriggerPruning => {
+            let db = context.db.clone();
+            let latest_version =
+                tokio::task::spawn_blocking(move || db.trigger_pruning()).await??;
+            Ok(reply::json(&json!({ "latest_version": latest_version })).into_response())
+        }
+        AdminAction::Compact => {
+            let db = context.db.clone();
+            tokio::task::spawn_blocking(move || db.compact()).await??;
+            Ok(reply::json(&json!({})).into_response())
+        }
+        AdminAction::CreateCheckpoint => {
+            let checkpoint_dir =
+                context.config.checkpoint_dir.as_ref().ok_or_else(|| {
+                    anyhow!("checkpoint_dir is not set in the AdminServiceConfig")
+                })?;
+            let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+            let path = checkpoint_dir.join(format!("checkpoint-{}", timestamp_ms));
+            let db = context.db.clone();
+            let checkpoint_path = path.clone();
+            tokio::task::spawn_blocking(move || db.create_checkpoint(checkpoint_path)).await??;
+            Ok(reply::json(&json!({ "path": path })).into_response())
+        }
+        AdminAction::DisconnectPeer {
+            network_id,
+            peer_id,
+        } => {
+            let network_id = NetworkId::from_str(network_id).map_err(|e| format_err!(e))?;
+            let peer_id = PeerId::from_str(peer_id)?;
+            let network_sender = context
+                .network_senders
+                .get(&network_id)
+                .ok_or_else(|| anyhow!("The node isn't on the {} network", network_id))?;
+            network_sender.disconnect_peer(peer_id).await?;
+            Ok(
+                reply::json(&json!({ "network_id": network_id, "peer_id": peer_id }))
+                    .into_response(),
+            )
+        }
+        AdminAction::FlushMempool => {
+            let (callback, callback_rcv) = oneshot::channel();
+            context
+                .mempool_client
+                .clone()
+                .send(MempoolClientRequest::FlushTransactions(callback))
+                .await?;
+            let num_flushed = callback_rcv.await?;
+            Ok(reply::json(&json!({ "num_flushed": num_flushed })).into_response())
+        }
+        AdminAction::Drain => {
+            let started = aptos_drain::start_draining("requested from the admin service");
+            Ok(reply::json(&json!({ "already_draining": !started })).into_response())
+        }
+        AdminAction::ReloadConfig => {
+            let config_reloader = context
+                .config_reloader
+                .clone()
+                .ok_or_else(|| anyhow!("The node wasn't started from a config file"))?;
+            let changes = tokio::task::spawn_blocking(move || config_reloader()).await??;
+            Ok(reply::json(&json!({ "changes": changes })).into_response())
+        }
+        AdminAction::CollectDebugBundle => {
+            let context = context.clone();
+            let bundle =
+                tokio::task::spawn_blocking(move || collect_debug_bundle(&context)).await??;
+            let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+            let reply = reply::with_header(bundle, "content-type", "application/gzip");
+            let reply = reply::with_header(
+                reply,
+                "content-disposition",
+                format!(
+                    "attachment; filename=\"debug-bundle-{}.tar.gz\"",
+                    timestamp_ms
+                ),
+            );
+            Ok(reply.into_response())
+        }
+    }
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> Response {
+    reply::with_status(reply::json(&json!({ "error": error.into() })), status).into_response()
+}
@@ -0,0 +1,257 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! The admin service performs operational actions on a running node, which otherwise require a
+//! restart: triggering the pruners, compacting the DB, creating a backup checkpoint, resetting the
+//! connection to a peer, flushing mempool, draining the node before it shuts down, reloading the
+//! config and collecting a debug bundle for support. The service only listens on a loopback
+//! address, unless it requires mutual TLS, every action must be enabled in the config, and every
+//! request is audited.
+
+mod debug_bundle;
+mod handlers;
+
+pub use handlers::AdminAction;
+
+use anyhow::{bail, ensure, Result};
+use aptos_config::{
+    config::{AdminServiceConfig, NodeConfig},
+    network_id::NetworkId,
+};
+use aptos_logger::prelude::*;
+use aptos_mempool::MempoolClientSender;
+use aptosdb::AptosDB;
+use network::{application::storage::PeerMetadataStorage, protocols::network::NetworkSender};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::runtime::{Builder, Runtime};
+
+/// Reloads the config file of the node, returning the reloadable settings that changed
+pub type ConfigReloader = Arc<dyn Fn() -> Result<Vec<String>> + Send + Sync>;
+
+/// The components the actions are performed on
+pub struct AdminServiceContext {
+    pub config: AdminServiceConfig,
+    pub db: Arc<AptosDB>,
+    /// Registered with no protocols on each network, only to disconnect peers
+    pub network_senders: HashMap<NetworkId, NetworkSender<()>>,
+    pub mempool_client: MempoolClientSender,
+    /// Only set if the node was started from a config file
+    pub config_reloader: Option<ConfigReloader>,
+    /// The config the node was started with, for the debug bundle
+    pub node_config: NodeConfig,
+    pub peer_metadata_storage: Arc<PeerMetadataStorage>,
+}
+
+/// Fails if the service would be reachable from another host without mutual TLS, or if an
+/// enabled action is missing its settings. The auth token is optional either way: it's checked
+/// on top of the transport if it's set.
+pub fn validate_config(config: &AdminServiceConfig) -> Result<()> {
+    let address: IpAddr = match config.address.parse() {
+        Ok(address) => address,
+        Err(_) => bail!(
+            "The admin service address must be an IP address, got {}",
+            config.address
+        ),
+    };
+    ensure!(
+        address.is_loopback() || config.tls.is_some(),
+        "The admin service must listen on a loopback address, unless TLS is configured"
+    );
+    ensure!(
+        !config.enable_backup_checkpoints || config.checkpoint_dir.is_some(),
+        "The checkpoint_dir must be set to enable backup checkpoints"
+    );
+    Ok(())
+}
+
+pub fn start_admin_service(context: AdminServiceContext) -> Result<Runtime> {
+    validate_config(&context.config)?;
+    let address = SocketAddr::new(context.config.address.parse()?, context.config.port);
+    let tls = context.config.tls.clone();
+    let routes = handlers::get_routes(Arc::new(context));
+
+    let runtime = Builder::new_multi_thread()
+        .thread_name_fn(|| {
+            static ATOMIC_ID: AtomicUsize = AtomicUsize::new(0);
+            let id = ATOMIC_ID.fetch_add(1, Ordering::SeqCst);
+            format!("admin-{}", id)
+        })
+        .worker_threads(2)
+        .disable_lifo_slot()
+        .enable_all()
+        .build()
+        .expect("[admin] failed to create runtime");
+
+    // The listener can only be bound inside a tokio context
+    let _guard = runtime.enter();
+    match tls {
+        Some(tls) => {
+            let server = warp::serve(routes)
+                .tls()
+                .cert_path(&tls.certificate_path)
+                .key_path(&tls.private_key_path)
+                .client_auth_required_path(&tls.client_ca_path)
+                .bind(address);
+            runtime.handle().spawn(server);
+        }
+        None => {
+            let server = warp::serve(routes).bind(address);
+            runtime.handle().spawn(server);
+        }
+    }
+    info!("Admin service spawned on {}.", address);
+    Ok(runtime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_config::config::AdminServiceTlsConfig;
+    use aptos_mempool::MempoolClientRequest;
+    use aptos_temppath::TempPath;
+    use flate2::read::GzDecoder;
+    use futures::{channel::mpsc, StreamExt};
+    use std::path::PathBuf;
+    use warp::http::StatusCode;
+
+    #[test]
+    fn test_validate_config() {
+        let mut config = AdminServiceConfig::default();
+        validate_config(&config).unwrap();
+
+        config.address = "0.0.0.0".to_string();
+        validate_config(&config).unwrap_err();
+        // An auth token doesn't make up for the missing mutual TLS
+        config.auth_token = Some("token".into());
+        let error = validate_config(&config).unwrap_err();
+        assert!(error.to_string().contains("unless TLS is configured"));
+        config.auth_token = None;
+        config.tls = Some(AdminServiceTlsConfig {
+            certificate_path: PathBuf::from("cert.pem"),
+            private_key_path: PathBuf::from("key.pem"),
+            client_ca_path: PathBuf::from("ca.pem"),
+        });
+        validate_config(&config).unwrap();
+        config.auth_token = Some("token".into());
+        validate_config(&config).unwrap();
+
+        config.enable_backup_checkpoints = true;
+        validate_config(&config).unwrap_err();
+        config.checkpoint_dir = Some(PathBuf::from("/tmp"));
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    fn test_action_from_path() {
+        assert_eq!(
+            AdminAction::from_path("/prune"),
+            Some(AdminAction::TriggerPruning)
+        );
+        assert_eq!(
+            AdminAction::from_path("/mempool/flush/"),
+            Some(AdminAction::FlushMempool)
+        );
+        assert_eq!(
+            AdminAction::from_path("/peers/vfn/0x1/disconnect"),
+            Some(AdminAction::DisconnectPeer {
+                network_id: "vfn".to_string(),
+                peer_id: "0x1".to_string(),
+            })
+        );
+        assert_eq!(
+            AdminAction::from_path("/config/reload"),
+            Some(AdminAction::ReloadConfig)
+        );
+        assert_eq!(
+            AdminAction::from_path("/debug-bundle"),
+            Some(AdminAction::CollectDebugBundle)
+        );
+        assert_eq!(AdminAction::from_path("/peers/vfn/disconnect"), None);
+        assert_eq!(AdminAction::from_path("/"), None);
+    }
+
+    #[tokio::test]
+    async fn test_requests() {
+        let tmpdir = TempPath::new();
+        let (mempool_client, mut mempool_events) = mpsc::channel(1);
+        let config = AdminServiceConfig {
+            enabled: true,
+            auth_token: Some("token".into()),
+            enable_mempool_flush: true,
+            enable_debug_bundle: true,
+            ..AdminServiceConfig::default()
+        };
+        let routes = handlers::get_routes(Arc::new(AdminServiceContext {
+            config,
+            db: Arc::new(AptosDB::new_for_test(&tmpdir)),
+            network_senders: HashMap::new(),
+            mempool_client,
+            config_reloader: None,
+            node_config: NodeConfig::default(),
+            peer_metadata_storage: PeerMetadataStorage::new(&[NetworkId::Public]),
+        }));
+        tokio::spawn(async move {
+            while let Some(request) = mempool_events.next().await {
+                if let MempoolClientRequest::FlushTransactions(callback) = request {
+                    callback.send(3).unwrap();
+                }
+            }
+        });
+
+        let request = || {
+            warp::test::request()
+                .method("POST")
+                .header("authorization", "Bearer token")
+        };
+
+        // Not authorized
+        let response = warp::test::request()
+            .method("POST")
+            .path("/mempool/flush")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Not enabled
+        let response = request().path("/compact").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Unknown action
+        let response = request().path("/restart").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = request().path("/mempool/flush").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["num_flushed"], 3);
+
+        let response = request().path("/debug-bundle").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/gzip");
+        let mut archive = tar::Archive::new(GzDecoder::new(response.body().as_ref()));
+        let files: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        for file in ["config.json", "metrics.txt", "peers.json", "manifest.json"] {
+            assert!(files.contains(&file.to_string()), "{} is missing", file);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_metrics_core::{register_int_counter_vec, IntCounterVec};
+use once_cell::sync::Lazy;
+
+/// Counter of audit records by whether they were written: success or failure
+pub(crate) static AUDIT_RECORDS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_audit_records",
+        "Number of audit records, by result",
+        &["result"]
+    )
+    .unwrap()
+});
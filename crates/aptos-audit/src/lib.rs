@@ -0,0 +1,329 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! A tamper-evident audit trail of the sensitive actions taken on a node, e.g., accesses to the
+//! keys of secure storage and configuration reloads.
+//!
+//! Audit records are appended to a dedicated file, one JSON record per line. Each record holds the
+//! hash of the previous one and is signed with the audit signing key, so editing, removing or
+//! reordering records breaks the chain, which [`verify_audit_log`] checks. Truncating the end of
+//! the file can't be detected from the file alone, so it should be shipped elsewhere as it grows.
+//!
+//! Auditing is a no-op until [`init`] is called.
+
+mod counters;
+
+use anyhow::{bail, ensure, Context};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::{CryptoHash, HashValue},
+    PrivateKey, Signature, SigningKey, ValidCryptoMaterialStringExt,
+};
+use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use chrono::{SecondsFormat, Utc};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+};
+
+/// The action recorded when auditing starts, its details hold the public key of the records
+pub const AUDIT_START_ACTION: &str = "audit.start";
+
+static AUDIT_LOG: OnceCell<Mutex<AuditLog>> = OnceCell::new();
+
+/// What was done, by whom and how it went
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditEvent {
+    pub action: String,
+    pub actor: String,
+    pub details: BTreeMap<String, String>,
+    pub outcome: AuditOutcome,
+}
+
+impl AuditEvent {
+    /// A successful action, by default attributed to the current thread
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            actor: thread::current().name().unwrap_or("unnamed").to_string(),
+            details: BTreeMap::new(),
+            outcome: AuditOutcome::Success,
+        }
+    }
+
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = actor.into();
+        self
+    }
+
+    pub fn detail(mut self, key: impl Into<String>, value: impl Display) -> Self {
+        self.details.insert(key.into(), value.to_string());
+        self
+    }
+
+    pub fn outcome<T, E: Display>(mut self, result: &Result<T, E>) -> Self {
+        self.outcome = match result {
+            Ok(_) => AuditOutcome::Success,
+            Err(error) => AuditOutcome::Failure(error.to_string()),
+        };
+        self
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure(String),
+}
+
+/// The signed part of an audit record
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct AuditRecordBody {
+    pub sequence_number: u64,
+    pub timestamp: String,
+    pub event: AuditEvent,
+    /// The hash of the body of the previous record, zero for the first one
+    pub previous_hash: HashValue,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditRecord {
+    pub body: AuditRecordBody,
+    pub hash: HashValue,
+    pub signature: Ed25519Signature,
+}
+
+struct AuditLog {
+    path: PathBuf,
+    file: File,
+    signing_key: Ed25519PrivateKey,
+    next_sequence_number: u64,
+    previous_hash: HashValue,
+}
+
+impl AuditLog {
+    fn open(path: &Path, signing_key: Ed25519PrivateKey) -> anyhow::Result<Self> {
+        // Continue the chain of the existing records, if any
+        let (next_sequence_number, previous_hash) = match last_record(path)? {
+            Some(record) => {
+                ensure!(
+                    record
+                        .signature
+                        .verify(&record.body, &signing_key.public_key())
+                        .is_ok(),
+                    "The last audit record of {:?} isn't signed by the audit signing key",
+                    path
+                );
+                (record.body.sequence_number + 1, record.hash)
+            }
+            None => (0, HashValue::zero()),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to open the audit log {:?}", path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            signing_key,
+            next_sequence_number,
+            previous_hash,
+        })
+    }
+
+    fn append(&mut self, event: AuditEvent) -> anyhow::Result<()> {
+        let body = AuditRecordBody {
+            sequence_number: self.next_sequence_number,
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            event,
+            previous_hash: self.previous_hash,
+        };
+        let record = AuditRecord {
+            hash: body.hash(),
+            signature: self.signing_key.sign(&body)?,
+            body,
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+
+        self.next_sequence_number += 1;
+        self.previous_hash = record.hash;
+        Ok(())
+    }
+}
+
+/// Starts appending audit records to `path`, signed with the hex encoded Ed25519 private key of
+/// `signing_key_path`. Records already in the file are continued, if they're signed by the same key.
+pub fn init(path: &Path, signing_key_path: &Path) -> anyhow::Result<()> {
+    let encoded_key = fs::read_to_string(signing_key_path).with_context(|| {
+        format!(
+            "Unable to read the audit signing key {:?}",
+            signing_key_path
+        )
+    })?;
+    let signing_key = Ed25519PrivateKey::from_encoded_string(encoded_key.trim())
+        .with_context(|| format!("Invalid audit signing key {:?}", signing_key_path))?;
+    let public_key = signing_key.public_key();
+
+    let audit_log = AuditLog::open(path, signing_key)?;
+    if AUDIT_LOG.set(Mutex::new(audit_log)).is_err() {
+        bail!("Auditing was already initialized");
+    }
+    audit(
+        AuditEvent::new(AUDIT_START_ACTION)
+            .actor("aptos-audit")
+            .detail("public_key", public_key),
+    );
+    info!("Audit records are written to {:?}", path);
+    Ok(())
+}
+
+/// Whether the audited actions are recorded
+pub fn is_enabled() -> bool {
+    AUDIT_LOG.get().is_some()
+}
+
+/// Records an event in the audit log. Failing to record doesn't stop the action, it's logged and
+/// counted in `aptos_audit_records{result="failure"}` instead.
+pub fn audit(event: AuditEvent) {
+    let audit_log = match AUDIT_LOG.get() {
+        Some(audit_log) => audit_log,
+        None => return,
+    };
+    let mut audit_log = audit_log.lock();
+    match audit_log.append(event.clone()) {
+        Ok(()) => counters::AUDIT_RECORDS
+            .with_label_values(&["success"])
+            .inc(),
+        Err(error) => {
+            counters::AUDIT_RECORDS
+                .with_label_values(&["failure"])
+                .inc();
+            error!(
+                "Failed to write the audit record of {:?} to {:?}: {}",
+                event, audit_log.path, error
+            );
+        }
+    }
+}
+
+/// Checks the records of an audit log are signed by `public_key`, and that none was edited,
+/// removed or reordered. Returns the number of records.
+pub fn verify_audit_log(path: &Path, public_key: &Ed25519PublicKey) -> anyhow::Result<u64> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read the audit log {:?}", path))?;
+    let mut previous_hash = HashValue::zero();
+    let mut num_records = 0;
+    for (line_number, line) in lines(&content).enumerate() {
+        let record: AuditRecord = serde_json::from_str(line)
+            .with_context(|| format!("Invalid audit record on line {}", line_number + 1))?;
+        ensure!(
+            record.body.sequence_number == num_records,
+            "Record {} has sequence number {}, some records are missing or reordered",
+            num_records,
+            record.body.sequence_number
+        );
+        ensure!(
+            record.body.previous_hash == previous_hash,
+            "Record {} doesn't follow the previous record",
+            num_records
+        );
+        ensure!(
+            record.body.hash() == record.hash,
+            "Record {} doesn't match its hash",
+            num_records
+        );
+        record
+            .signature
+            .verify(&record.body, public_key)
+            .with_context(|| format!("Record {} has an invalid signature", num_records))?;
+        previous_hash = record.hash;
+        num_records += 1;
+    }
+    Ok(num_records)
+}
+
+fn last_record(path: &Path) -> anyhow::Result<Option<AuditRecord>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read the audit log {:?}", path))?;
+    lines(&content)
+        .last()
+        .map(|line| {
+            serde_json::from_str(line)
+                .with_context(|| format!("The last audit record of {:?} is invalid", path))
+        })
+        .transpose()
+}
+
+fn lines(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().filter(|line| !line.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::Uniform;
+    use aptos_temppath::TempPath;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn signing_key(seed: u8) -> Ed25519PrivateKey {
+        Ed25519PrivateKey::generate(&mut StdRng::from_seed([seed; 32]))
+    }
+
+    fn write_records(path: &Path, signing_key: Ed25519PrivateKey, num_records: usize) {
+        let mut audit_log = AuditLog::open(path, signing_key).unwrap();
+        for i in 0..num_records {
+            let event = AuditEvent::new("test.action")
+                .actor("test")
+                .detail("index", i)
+                .outcome(&Err::<(), _>("failed"));
+            audit_log.append(event).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_verify_audit_log() {
+        let path = TempPath::new();
+        write_records(path.path(), signing_key(1), 2);
+        // The chain continues across restarts
+        write_records(path.path(), signing_key(1), 2);
+        let public_key = signing_key(1).public_key();
+        assert_eq!(verify_audit_log(path.path(), &public_key).unwrap(), 4);
+
+        // Another key can't continue the chain
+        assert!(AuditLog::open(path.path(), signing_key(2)).is_err());
+        assert!(verify_audit_log(path.path(), &signing_key(2).public_key()).is_err());
+
+        let content = fs::read_to_string(path.path()).unwrap();
+        let lines: Vec<_> = content.lines().collect();
+        let tampered = [
+            // A removed record
+            format!("{}\n{}\n", lines[0], lines[2]),
+            // Reordered records
+            format!("{}\n{}\n", lines[1], lines[0]),
+            // An edited record
+            content.replacen("failed", "succeeded", 1),
+        ];
+        for content in tampered {
+            fs::write(path.path(), content).unwrap();
+            assert!(verify_audit_log(path.path(), &public_key).is_err());
+        }
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! The config watcher notices the changes of on-chain configs and resources, e.g. of the gas
+//! schedule after a governance proposal, or of the resources of an account. The node checks the
+//! watched resources at the version of every new epoch, when the on-chain configs take effect,
+//! and periodically at the latest state checkpoint. Each check compares the values to those of
+//! the previous one, and every change is notified to each notifier: the logs, the metrics, the
+//! webhooks of the config and any notifier added in-process, e.g. a callback.
+
+use anyhow::{format_err, Result};
+use aptos_config::config::{ConfigWatcherConfig, WatchedResource, WebhookConfig};
+use aptos_crypto::HashValue;
+use aptos_logger::prelude::*;
+use aptos_metrics_core::{
+    register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec,
+};
+use aptos_types::{
+    access_path::AccessPath, account_address::AccountAddress, state_store::state_key::StateKey,
+    transaction::Version,
+};
+use move_core_types::parser::parse_struct_tag;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::time::Duration;
+
+static CHANGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_config_watcher_changes",
+        "Number of changes of the watched resources, by resource",
+        &["resource"]
+    )
+    .unwrap()
+});
+
+static LAST_CHANGE_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_config_watcher_last_change_version",
+        "The version of the last change of the watched resources, by resource",
+        &["resource"]
+    )
+    .unwrap()
+});
+
+static NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_config_watcher_notifications",
+        "Number of notifications of the changes, by notifier and result",
+        &["notifier", "result"]
+    )
+    .unwrap()
+});
+
+/// A change of a watched resource, which is posted as JSON to the webhooks
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ResourceChange {
+    pub account: AccountAddress,
+    pub resource: String,
+    /// The version the change was noticed at, the resource may have changed earlier
+    pub version: Version,
+    /// The hashes of the BCS bytes before and after the change, none while the resource
+    /// doesn't exist
+    pub previous_hash: Option<HashValue>,
+    pub hash: Option<HashValue>,
+    /// The BCS bytes after the change, in hex
+    pub value: Option<String>,
+}
+
+/// Notified of every change of the watched resources
+pub trait ChangeNotifier: Send {
+    /// The name of the notifier, in the logs and the metrics
+    fn name(&self) -> &str;
+
+    fn notify(&mut self, change: &ResourceChange) -> Result<()>;
+}
+
+/// Logs the changes
+pub struct LogNotifier;
+
+impl ChangeNotifier for LogNotifier {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn notify(&mut self, change: &ResourceChange) -> Result<()> {
+        match (change.previous_hash, change.hash) {
+            (None, _) => info!(
+                "The resource {} at {} was created at version {}",
+                change.resource, change.account, change.version
+            ),
+            (_, None) => warn!(
+                "The resource {} at {} was removed at version {}",
+                change.resource, change.account, change.version
+            ),
+            _ => info!(
+                "The resource {} at {} changed at version {}",
+                change.resource, change.account, change.version
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Counts the changes, and records the version of the last one, by resource
+pub struct MetricsNotifier;
+
+impl ChangeNotifier for MetricsNotifier {
+    fn name(&self) -> &str {
+        "metrics"
+    }
+
+    fn notify(&mut self, change: &ResourceChange) -> Result<()> {
+        let resource = format!("{}/{}", change.account, change.resource);
+        CHANGES.with_label_values(&[&resource]).inc();
+        LAST_CHANGE_VERSION
+            .with_label_values(&[&resource])
+            .set(change.version as i64);
+        Ok(())
+    }
+}
+
+/// Posts the changes to a webhook
+pub struct WebhookNotifier {
+    name: String,
+    client: reqwest::blocking::Client,
+    config: WebhookConfig,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+        Ok(Self {
+            name: format!("webhook {}", config.url),
+            client,
+            config,
+        })
+    }
+}
+
+impl ChangeNotifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn notify(&mut self, change: &ResourceChange) -> Result<()> {
+        let mut builder = self
+            .client
+            .post(&self.config.url)
+            .header("User-Agent", "aptos-node")
+            .json(change);
+        if let Some(auth_token) = &self.config.auth_token {
+            builder = builder.bearer_auth(auth_token.expose());
+        }
+        builder.send()?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Calls back a component of the node with the changes
+pub struct CallbackNotifier<F> {
+    name: String,
+    callback: F,
+}
+
+impl<F: FnMut(&ResourceChange) -> Result<()> + Send> CallbackNotifier<F> {
+    pub fn new(name: &str, callback: F) -> Self {
+        Self {
+            name: name.to_string(),
+            callback,
+        }
+    }
+}
+
+impl<F: FnMut(&ResourceChange) -> Result<()> + Send> ChangeNotifier for CallbackNotifier<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn notify(&mut self, change: &ResourceChange) -> Result<()> {
+        (self.callback)(change)
+    }
+}
+
+pub struct ConfigWatcher {
+    resources: Vec<(WatchedResource, StateKey)>,
+    // The hashes of the resources at the last check, and its version, none before the first one
+    last_check: Option<(Version, Vec<Option<HashValue>>)>,
+    notifiers: Vec<Box<dyn ChangeNotifier>>,
+}
+
+impl ConfigWatcher {
+    /// A watcher of the resources, without any notifier
+    pub fn new(resources: &[WatchedResource]) -> Result<Self> {
+        let resources = resources
+            .iter()
+            .map(|watched| {
+                let struct_tag = parse_struct_tag(&watched.resource).map_err(|error| {
+                    format_err!("Invalid watched resource {}: {}", watched.resource, error)
+                })?;
+                let state_key = StateKey::AccessPath(AccessPath::new(
+                    watched.account,
+                    AccessPath::resource_access_vec(struct_tag),
+                ));
+                Ok((watched.clone(), state_key))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            resources,
+            last_check: None,
+            notifiers: vec![],
+        })
+    }
+
+    /// A watcher of the resources of the config, notifying the logs, the metrics and the
+    /// webhooks of the config
+    pub fn from_config(config: &ConfigWatcherConfig) -> Result<Self> {
+        let mut watcher = Self::new(&config.resources)?;
+        watcher.add_notifier(Box::new(LogNotifier));
+        watcher.add_notifier(Box::new(MetricsNotifier));
+        for webhook in &config.webhooks {
+            watcher.add_notifier(Box::new(WebhookNotifier::new(webhook.clone())?));
+        }
+        Ok(watcher)
+    }
+
+    pub fn add_notifier(&mut self, notifier: Box<dyn ChangeNotifier>) {
+        self.notifiers.push(notifier);
+    }
+
+    /// Checks the resources at the version, reading their BCS bytes with `read`, and notifies
+    /// their changes since the last check. The first check only records the resources, and a
+    /// check at a version that isn't after the last one is skipped.
+    pub fn check(
+        &mut self,
+        version: Version,
+        read: impl Fn(&StateKey) -> Result<Option<Vec<u8>>>,
+    ) -> Result<Vec<ResourceChange>> {
+        if matches!(&self.last_check, Some((last_version, _)) if version <= *last_version) {
+            return Ok(vec![]);
+        }
+        let values = self
+            .resources
+            .iter()
+            .map(|(_, state_key)| read(state_key))
+            .collect::<Result<Vec<_>>>()?;
+        let hashes: Vec<_> = values
+            .iter()
+            .map(|value| value.as_ref().map(|bytes| HashValue::sha3_256_of(bytes)))
+            .collect();
+
+        let mut changes = vec![];
+        if let Some((_, last_hashes)) = &self.last_check {
+            for (((watched, _), value), (previous_hash, hash)) in self
+                .resources
+                .iter()
+                .zip(&values)
+                .zip(last_hashes.iter().zip(&hashes))
+            {
+                if previous_hash != hash {
+                    changes.push(ResourceChange {
+                        account: watched.account,
+                        resource: watched.resource.clone(),
+                        version,
+                        previous_hash: *previous_hash,
+                        hash: *hash,
+                        value: value.as_ref().map(hex::encode),
+                    });
+                }
+            }
+        }
+        self.last_check = Some((version, hashes));
+
+        for change in &changes {
+            self.notify(change);
+        }
+        Ok(changes)
+    }
+
+    // A notifier failing doesn't keep the others from being notified
+    fn notify(&mut self, change: &ResourceChange) {
+        for notifier in &mut self.notifiers {
+            let result = match notifier.notify(change) {
+                Ok(()) => "success",
+                Err(error) => {
+                    warn!(
+                        "Failed to notify {} of the change of {} at {}: {}",
+                        notifier.name(),
+                        change.resource,
+                        change.account,
+                        error
+                    );
+                    "failure"
+                }
+            };
+            NOTIFICATIONS
+                .with_label_values(&[notifier.name(), result])
+                .inc();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    const GAS_SCHEDULE: &str = "0x1::gas_schedule::GasScheduleV2";
+    const VERSION: &str = "0x1::version::Version";
+
+    fn watched(resource: &str) -> WatchedResource {
+        WatchedResource {
+            account: AccountAddress::ONE,
+            resource: resource.to_string(),
+        }
+    }
+
+    fn state_key(resource: &str) -> StateKey {
+        StateKey::AccessPath(AccessPath::new(
+            AccountAddress::ONE,
+            AccessPath::resource_access_vec(parse_struct_tag(resource).unwrap()),
+        ))
+    }
+
+    fn reader(values: &[(&str, &[u8])]) -> impl Fn(&StateKey) -> Result<Option<Vec<u8>>> + 'static {
+        let values: HashMap<_, _> = values
+            .iter()
+            .map(|(resource, value)| (state_key(resource), value.to_vec()))
+            .collect();
+        move |state_key| Ok(values.get(state_key).cloned())
+    }
+
+    #[test]
+    fn test_check() {
+        let mut watcher = ConfigWatcher::new(&[watched(GAS_SCHEDULE), watched(VERSION)]).unwrap();
+        let notified = Arc::new(Mutex::new(vec![]));
+        let callback_notified = Arc::clone(&notified);
+        watcher.add_notifier(Box::new(CallbackNotifier::new(
+            "test",
+            move |change: &ResourceChange| {
+                callback_notified.lock().unwrap().push(change.clone());
+                Ok(())
+            },
+        )));
+        // A failing notifier doesn't keep the others from being notified
+        watcher.add_notifier(Box::new(CallbackNotifier::new(
+            "failing",
+            |_: &ResourceChange| Err(format_err!("the webhook is down")),
+        )));
+
+        // The first check only records the resources
+        let gas_schedule: &[u8] = &[1, 2, 3];
+        let changes = watcher
+            .check(10, reader(&[(GAS_SCHEDULE, gas_schedule)]))
+            .unwrap();
+        assert!(changes.is_empty());
+        let changes = watcher
+            .check(20, reader(&[(GAS_SCHEDULE, gas_schedule)]))
+            .unwrap();
+        assert!(changes.is_empty());
+
+        let new_gas_schedule: &[u8] = &[4, 5, 6];
+        let version: &[u8] = &[1];
+        let changes = watcher
+            .check(
+                30,
+                reader(&[(GAS_SCHEDULE, new_gas_schedule), (VERSION, version)]),
+            )
+            .unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                ResourceChange {
+                    account: AccountAddress::ONE,
+                    resource: GAS_SCHEDULE.to_string(),
+                    version: 30,
+                    previous_hash: Some(HashValue::sha3_256_of(gas_schedule)),
+                    hash: Some(HashValue::sha3_256_of(new_gas_schedule)),
+                    value: Some("040506".to_string()),
+                },
+                ResourceChange {
+                    account: AccountAddress::ONE,
+                    resource: VERSION.to_string(),
+                    version: 30,
+                    previous_hash: None,
+                    hash: Some(HashValue::sha3_256_of(version)),
+                    value: Some("01".to_string()),
+                },
+            ]
+        );
+        assert_eq!(*notified.lock().unwrap(), changes);
+
+        // A check that isn't after the last one is skipped
+        let changes = watcher.check(30, reader(&[])).unwrap();
+        assert!(changes.is_empty());
+        let changes = watcher.check(40, reader(&[(VERSION, version)])).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].hash, None);
+        assert_eq!(notified.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_invalid_resource() {
+        ConfigWatcher::new(&[watched("0x1::gas_schedule")]).unwrap_err();
+        ConfigWatcher::from_config(&ConfigWatcherConfig::default()).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! A node drains before it shuts down gracefully: it stops taking new transactions from the API
+//! and its peers, lets the commits in flight finish, persists what it would otherwise lose, and
+//! only then exits. The drain is requested once, e.g. on SIGTERM or from the admin service, and
+//! every component checks `is_draining` where it takes new work.
+
+use aptos_logger::prelude::*;
+use once_cell::sync::Lazy;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Condvar, Mutex,
+};
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+// Wakes up the thread waiting for the drain request
+static DRAIN_REQUESTED: Lazy<(Mutex<bool>, Condvar)> =
+    Lazy::new(|| (Mutex::new(false), Condvar::new()));
+
+/// Whether the node is draining, in which case it mustn't take new transactions
+pub fn is_draining() -> bool {
+    DRAINING.load(Ordering::Acquire)
+}
+
+/// Requests the node to drain, returning false if it was already draining
+pub fn start_draining(reason: &str) -> bool {
+    if DRAINING.swap(true, Ordering::AcqRel) {
+        return false;
+    }
+    warn!("The node is draining before it shuts down: {}", reason);
+
+    let (requested, condvar) = &*DRAIN_REQUESTED;
+    *requested.lock().unwrap_or_else(|error| error.into_inner()) = true;
+    condvar.notify_all();
+    true
+}
+
+/// Blocks until the drain is requested
+pub fn wait_for_drain() {
+    let (requested, condvar) = &*DRAIN_REQUESTED;
+    let mut requested = requested.lock().unwrap_or_else(|error| error.into_inner());
+    while !*requested {
+        requested = condvar
+            .wait(requested)
+            .unwrap_or_else(|error| error.into_inner());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_drain() {
+        assert!(!is_draining());
+
+        let waiter = thread::spawn(wait_for_drain);
+        assert!(start_draining("test"));
+        waiter.join().unwrap();
+        assert!(is_draining());
+        // Doesn't block once the drain was requested
+        wait_for_drain();
+
+        // The drain is only requested once
+        assert!(!start_draining("test"));
+    }
+}
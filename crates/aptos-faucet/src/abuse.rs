@@ -0,0 +1,500 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protection against the faucet being drained by bots: per IP and per account rate limits, a
+//! denylist of IPs and accounts, the verification of tokens signed by a trusted site, e.g. one
+//! serving a captcha, and quotas on the coins granted that survive restarts.
+
+use crate::grants::{now_secs, Grant, GrantLedger, Reservation};
+use anyhow::{Context, Result};
+use aptos_config::secret::Secret;
+use aptos_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature, ED25519_SIGNATURE_LENGTH},
+    Signature, ValidCryptoMaterialStringExt,
+};
+use aptos_logger::{error, info};
+use aptos_metrics_core::{register_int_counter_vec, IntCounterVec};
+use aptos_sdk::types::account_address::AccountAddress;
+use clap::Parser;
+use once_cell::sync::Lazy;
+use reqwest::StatusCode;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    fmt,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Header carrying the token of a mint request.
+pub const TOKEN_HEADER: &str = "x-faucet-token";
+
+static REJECTED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_faucet_rejected_requests",
+        "Number of mint requests rejected by the abuse protection",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct AbuseProtectionArgs {
+    /// Maximum number of mint requests from one IP in a rate limit window.
+    /// Unlimited if not present
+    #[clap(long)]
+    pub max_requests_per_ip: Option<u32>,
+    /// Maximum number of mint requests for one account in a rate limit window.
+    /// Unlimited if not present
+    #[clap(long)]
+    pub max_requests_per_account: Option<u32>,
+    /// Length of the rate limit window in seconds.
+    /// If not present, it's a day
+    #[clap(long)]
+    pub rate_limit_window_secs: Option<u64>,
+    /// Ed25519PublicKey of the site issuing mint tokens. If present, each mint request must carry
+    /// a token signed by it in the `x-faucet-token` header.
+    /// A token is `<expiration in unix seconds>.<hex encoded signature>`, where the signature is
+    /// of `aptos-faucet-token:<receiver address>:<expiration in unix seconds>`
+    #[clap(long, parse(try_from_str = Ed25519PublicKey::from_encoded_string))]
+    pub token_public_key: Option<Ed25519PublicKey>,
+    /// Path to the file with the IPs and account addresses to reject requests from and for, one
+    /// per line. Empty lines and lines starting with `#` are ignored
+    #[clap(long, parse(from_os_str))]
+    pub denylist_file: Option<PathBuf>,
+    /// Take the client IP from the last entry of the `x-forwarded-for` header, as appended by the
+    /// load balancer in front of the faucet. Don't set it otherwise, since the header can be
+    /// spoofed to evade the per IP rate limit
+    #[clap(long)]
+    pub trust_forwarded_for: bool,
+    /// Path to the file every grant is recorded in. Needed for `--address-quota` and
+    /// `--ip-quota`, which are enforced across restarts of the faucet with it
+    #[clap(long, parse(from_os_str))]
+    pub grant_ledger_file: Option<PathBuf>,
+    /// Maximum amount of coins granted to one address in a rolling quota window.
+    /// Unlimited if not present
+    #[clap(long)]
+    pub address_quota: Option<u64>,
+    /// Maximum amount of coins granted to the requests from one IP in a rolling quota window.
+    /// Unlimited if not present
+    #[clap(long)]
+    pub ip_quota: Option<u64>,
+    /// Length of the rolling quota window in seconds.
+    /// If not present, it's a day
+    #[clap(long)]
+    pub quota_window_secs: Option<u64>,
+    /// Path to the file with the bearer token for the admin endpoints, to query and reset quotas.
+    /// The admin endpoints are disabled if not present
+    #[clap(long, parse(from_os_str))]
+    pub admin_token_file: Option<PathBuf>,
+}
+
+/// Why a mint request is rejected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectReason {
+    Denylisted,
+    IpRateLimited,
+    AccountRateLimited,
+    IpQuotaExceeded,
+    AddressQuotaExceeded,
+    MissingToken,
+    InvalidToken,
+    ExpiredToken,
+    ReusedToken,
+}
+
+impl RejectReason {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            RejectReason::IpRateLimited
+            | RejectReason::AccountRateLimited
+            | RejectReason::IpQuotaExceeded
+            | RejectReason::AddressQuotaExceeded => StatusCode::TOO_MANY_REQUESTS,
+            RejectReason::Denylisted
+            | RejectReason::MissingToken
+            | RejectReason::InvalidToken
+            | RejectReason::ExpiredToken
+            | RejectReason::ReusedToken => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RejectReason::Denylisted => "denylisted",
+            RejectReason::IpRateLimited => "ip_rate_limited",
+            RejectReason::AccountRateLimited => "account_rate_limited",
+            RejectReason::IpQuotaExceeded => "ip_quota_exceeded",
+            RejectReason::AddressQuotaExceeded => "address_quota_exceeded",
+            RejectReason::MissingToken => "missing_token",
+            RejectReason::InvalidToken => "invalid_token",
+            RejectReason::ExpiredToken => "expired_token",
+            RejectReason::ReusedToken => "reused_token",
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            RejectReason::Denylisted => "Request denied",
+            RejectReason::IpRateLimited => "Too many requests from this IP, try again later",
+            RejectReason::AccountRateLimited => {
+                "Too many requests for this account, try again later"
+            }
+            RejectReason::IpQuotaExceeded => "Quota of this IP exceeded, try again later",
+            RejectReason::AddressQuotaExceeded => "Quota of this account exceeded, try again later",
+            RejectReason::MissingToken => "Missing token in the x-faucet-token header",
+            RejectReason::InvalidToken => "Invalid token",
+            RejectReason::ExpiredToken => "Token expired",
+            RejectReason::ReusedToken => "Token already used",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+/// Checks mint requests against the configured limits. Checks not configured are skipped, so the
+/// default lets every request through.
+#[derive(Default)]
+pub struct AbuseProtection {
+    ip_rate_limiter: Option<RateLimiter<IpAddr>>,
+    account_rate_limiter: Option<RateLimiter<AccountAddress>>,
+    token_public_key: Option<Ed25519PublicKey>,
+    used_tokens: UsedTokens,
+    denied_ips: HashSet<IpAddr>,
+    denied_accounts: HashSet<AccountAddress>,
+    trust_forwarded_for: bool,
+    grant_ledger: Option<GrantLedger>,
+    admin_token: Option<Secret<String>>,
+}
+
+impl AbuseProtection {
+    pub fn new(args: &AbuseProtectionArgs) -> Result<Self> {
+        let window = Duration::from_secs(args.rate_limit_window_secs.unwrap_or(24 * 60 * 60));
+        anyhow::ensure!(!window.is_zero(), "Rate limit window can't be empty");
+        let (denied_ips, denied_accounts) = match &args.denylist_file {
+            Some(path) => {
+                let denylist = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read denylist file {:?}", path))?;
+                parse_denylist(&denylist)?
+            }
+            None => (HashSet::new(), HashSet::new()),
+        };
+        let grant_ledger = match &args.grant_ledger_file {
+            Some(path) => {
+                let window = Duration::from_secs(args.quota_window_secs.unwrap_or(24 * 60 * 60));
+                anyhow::ensure!(!window.is_zero(), "Quota window can't be empty");
+                Some(GrantLedger::open(
+                    path,
+                    args.address_quota,
+                    args.ip_quota,
+                    window,
+                )?)
+            }
+            None => {
+                anyhow::ensure!(
+                    args.address_quota.is_none() && args.ip_quota.is_none(),
+                    "Quotas need a grant ledger file"
+                );
+                None
+            }
+        };
+        let admin_token = match &args.admin_token_file {
+            Some(path) => {
+                let admin_token = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read admin token file {:?}", path))?;
+                let admin_token = admin_token.trim().to_string();
+                anyhow::ensure!(!admin_token.is_empty(), "Admin token can't be empty");
+                Some(Secret::from(admin_token))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            ip_rate_limiter: args
+                .max_requests_per_ip
+                .map(|max_requests| RateLimiter::new(max_requests, window)),
+            account_rate_limiter: args
+                .max_requests_per_account
+                .map(|max_requests| RateLimiter::new(max_requests, window)),
+            token_public_key: args.token_public_key.clone(),
+            used_tokens: UsedTokens::default(),
+            denied_ips,
+            denied_accounts,
+            trust_forwarded_for: args.trust_forwarded_for,
+            grant_ledger,
+            admin_token,
+        })
+    }
+
+    pub fn grant_ledger(&self) -> Option<&GrantLedger> {
+        self.grant_ledger.as_ref()
+    }
+
+    pub fn admin_token(&self) -> Option<&Secret<String>> {
+        self.admin_token.as_ref()
+    }
+
+    /// The IP the request came from, if known.
+    pub fn client_ip(
+        &self,
+        remote_addr: Option<SocketAddr>,
+        forwarded_for: Option<&str>,
+    ) -> Option<IpAddr> {
+        let forwarded_ip = forwarded_for
+            .filter(|_| self.trust_forwarded_for)
+            .and_then(|forwarded_for| forwarded_for.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        forwarded_ip.or_else(|| remote_addr.map(|addr| addr.ip()))
+    }
+
+    /// Checks a mint request from `ip` of `amount` for `receiver`. The request is only counted
+    /// towards the rate limits and the quotas, and its token only consumed, if it's let through.
+    /// Checks on the IP or the account are skipped if it's unknown.
+    pub fn check(
+        &self,
+        ip: Option<IpAddr>,
+        receiver: Option<AccountAddress>,
+        token: Option<&str>,
+        amount: u64,
+    ) -> Result<Admission, RejectReason> {
+        let result = self.check_impl(ip, receiver, token, amount);
+        if let Err(reason) = result {
+            record_rejection(reason, ip, receiver);
+        }
+        result
+    }
+
+    /// Records the grant of an admitted request in the ledger, once the funding succeeded.
+    pub fn commit_grant(&self, admission: Admission) {
+        if let (Some(ledger), Some(reservation)) = (&self.grant_ledger, admission.reservation) {
+            if let Err(err) = ledger.commit(reservation) {
+                error!("Failed to record grant: {:#}", err);
+            }
+        }
+    }
+
+    /// Stops counting the grant of an admitted request towards the quotas, since the funding
+    /// failed. The request still counts towards the rate limits.
+    pub fn release_grant(&self, admission: Admission) {
+        if let (Some(ledger), Some(reservation)) = (&self.grant_ledger, admission.reservation) {
+            ledger.release(reservation);
+        }
+    }
+
+    /// Undoes everything an admitted request was counted towards, e.g. when the rest of its batch
+    /// is rejected, as if it was never made.
+    pub fn revoke(&self, admission: Admission) {
+        if let Some(token) = &admission.token {
+            self.used_tokens.release(token);
+        }
+        if let (Some(limiter), Some(ip)) = (&self.ip_rate_limiter, admission.rate_limited_ip) {
+            limiter.release(ip);
+        }
+        if let (Some(limiter), Some(receiver)) =
+            (&self.account_rate_limiter, admission.rate_limited_receiver)
+        {
+            limiter.release(receiver);
+        }
+        self.release_grant(admission);
+    }
+
+    fn check_impl(
+        &self,
+        ip: Option<IpAddr>,
+        receiver: Option<AccountAddress>,
+        token: Option<&str>,
+        amount: u64,
+    ) -> Result<Admission, RejectReason> {
+        if ip.map_or(false, |ip| self.denied_ips.contains(&ip))
+            || receiver.map_or(false, |receiver| self.denied_accounts.contains(&receiver))
+        {
+            return Err(RejectReason::Denylisted);
+        }
+        // Verify the token before counting the request, so requests with forged tokens don't use
+        // up the limits of the account.
+        let mut admission = Admission::default();
+        if let (Some(public_key), Some(receiver)) = (&self.token_public_key, receiver) {
+            let token = verify_token(public_key, receiver, token)?;
+            if !self.used_tokens.try_consume(&token) {
+                return Err(RejectReason::ReusedToken);
+            }
+            admission.token = Some(token);
+        }
+
+        // Whatever the request was counted towards is undone if a later check rejects it.
+        if let (Some(limiter), Some(ip)) = (&self.ip_rate_limiter, ip) {
+            if !limiter.try_acquire(ip) {
+                self.revoke(admission);
+                return Err(RejectReason::IpRateLimited);
+            }
+            admission.rate_limited_ip = Some(ip);
+        }
+        if let (Some(limiter), Some(receiver)) = (&self.account_rate_limiter, receiver) {
+            if !limiter.try_acquire(receiver) {
+                self.revoke(admission);
+                return Err(RejectReason::AccountRateLimited);
+            }
+            admission.rate_limited_receiver = Some(receiver);
+        }
+        if let (Some(ledger), Some(receiver)) = (&self.grant_ledger, receiver) {
+            let grant = Grant {
+                address: receiver,
+                ip,
+                amount,
+                timestamp_secs: now_secs(),
+            };
+            match ledger.reserve(grant) {
+                Ok(reservation) => admission.reservation = Some(reservation),
+                Err(reason) => {
+                    self.revoke(admission);
+                    return Err(reason);
+                }
+            }
+        }
+        Ok(admission)
+    }
+}
+
+/// What a mint request let through was counted towards, so it can be undone.
+#[derive(Default)]
+pub struct Admission {
+    token: Option<VerifiedToken>,
+    rate_limited_ip: Option<IpAddr>,
+    rate_limited_receiver: Option<AccountAddress>,
+    reservation: Option<Reservation>,
+}
+
+fn record_rejection(reason: RejectReason, ip: Option<IpAddr>, receiver: Option<AccountAddress>) {
+    REJECTED_REQUESTS.with_label_values(&[reason.label()]).inc();
+    info!(
+        ip = ip.map(|ip| ip.to_string()),
+        receiver = receiver.map(|receiver| receiver.to_hex_literal()),
+        reason = reason.label(),
+        "mint request rejected"
+    );
+}
+
+/// The message signed in a token for minting to `receiver` until `expiration`.
+pub fn token_message(receiver: AccountAddress, expiration: u64) -> Vec<u8> {
+    format!(
+        "aptos-faucet-token:{}:{}",
+        receiver.to_hex_literal(),
+        expiration
+    )
+    .into_bytes()
+}
+
+/// A token with a valid signature, which isn't expired.
+struct VerifiedToken {
+    signature: [u8; ED25519_SIGNATURE_LENGTH],
+    expiration: u64,
+}
+
+fn verify_token(
+    public_key: &Ed25519PublicKey,
+    receiver: AccountAddress,
+    token: Option<&str>,
+) -> Result<VerifiedToken, RejectReason> {
+    let token = token.ok_or(RejectReason::MissingToken)?;
+    let (expiration, signature) = token.split_once('.').ok_or(RejectReason::InvalidToken)?;
+    let expiration: u64 = expiration.parse().map_err(|_| RejectReason::InvalidToken)?;
+    let signature = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| Ed25519Signature::try_from(bytes.as_slice()).ok())
+        .ok_or(RejectReason::InvalidToken)?;
+    signature
+        .verify_arbitrary_msg(&token_message(receiver, expiration), public_key)
+        .map_err(|_| RejectReason::InvalidToken)?;
+
+    if expiration < now_secs() {
+        return Err(RejectReason::ExpiredToken);
+    }
+    Ok(VerifiedToken {
+        signature: signature.to_bytes(),
+        expiration,
+    })
+}
+
+/// The tokens of the requests let through, kept until they expire so that each token is only
+/// used once. Signatures are canonical, so a token can't be altered into another valid one.
+#[derive(Default)]
+struct UsedTokens {
+    tokens: Mutex<HashMap<[u8; ED25519_SIGNATURE_LENGTH], u64>>,
+}
+
+impl UsedTokens {
+    fn try_consume(&self, token: &VerifiedToken) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        let now = now_secs();
+        tokens.retain(|_, expiration| *expiration >= now);
+        tokens.insert(token.signature, token.expiration).is_none()
+    }
+
+    fn release(&self, token: &VerifiedToken) {
+        self.tokens.lock().unwrap().remove(&token.signature);
+    }
+}
+
+fn parse_denylist(denylist: &str) -> Result<(HashSet<IpAddr>, HashSet<AccountAddress>)> {
+    let mut ips = HashSet::new();
+    let mut accounts = HashSet::new();
+    for line in denylist.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Ok(ip) = line.parse() {
+            ips.insert(ip);
+        } else {
+            let account = AccountAddress::from_hex_literal(line)
+                .or_else(|_| AccountAddress::from_hex(line))
+                .with_context(|| format!("Invalid IP or account address in denylist: {}", line))?;
+            accounts.insert(account);
+        }
+    }
+    Ok((ips, accounts))
+}
+
+/// Allows up to `max_requests` per key in each fixed window. The counts are dropped as a window
+/// ends, so the memory used is bounded by the keys seen in a window.
+struct RateLimiter<K> {
+    max_requests: u32,
+    window: Duration,
+    state: Mutex<(Instant, HashMap<K, u32>)>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    fn new(max_requests: u32, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            state: Mutex::new((Instant::now(), HashMap::new())),
+        }
+    }
+
+    fn try_acquire(&self, key: K) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let (window_start, counts) = &mut *state;
+        if window_start.elapsed() >= self.window {
+            *window_start = Instant::now();
+            counts.clear();
+        }
+
+        let count = counts.entry(key).or_insert(0);
+        if *count >= self.max_requests {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    /// Gives back a request counted by `try_acquire`, unless its window already ended.
+    fn release(&self, key: K) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(count) = state.1.get_mut(&key) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! A ledger of the coins granted by the faucet, persisted in an append only file of json lines, so
+//! the quotas enforced with it survive restarts of the faucet.
+
+use crate::{abuse::RejectReason, Service};
+use anyhow::{Context, Result};
+use aptos_sdk::types::account_address::AccountAddress;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    net::IpAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use warp::{Filter, Rejection, Reply};
+
+/// Coins granted to an address, as requested from an IP.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Grant {
+    pub address: AccountAddress,
+    pub ip: Option<IpAddr>,
+    pub amount: u64,
+    pub timestamp_secs: u64,
+}
+
+impl Grant {
+    fn identities(&self) -> impl Iterator<Item = Identity> {
+        std::iter::once(Identity::Address(self.address)).chain(self.ip.map(Identity::Ip))
+    }
+}
+
+/// Who a quota is enforced on.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Identity {
+    Address(AccountAddress),
+    Ip(IpAddr),
+}
+
+/// A line in the ledger file.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    Grant(Grant),
+    Reset {
+        identity: Identity,
+        timestamp_secs: u64,
+    },
+}
+
+/// A grant counted towards the quotas, before it's known if funding succeeds.
+pub struct Reservation {
+    id: u64,
+    grant: Grant,
+}
+
+/// Usage of the quota of an identity in the current window.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct QuotaUsage {
+    pub identity: Identity,
+    pub granted: u64,
+    pub quota: Option<u64>,
+    pub grants: Vec<Grant>,
+}
+
+pub struct GrantLedger {
+    address_quota: Option<u64>,
+    ip_quota: Option<u64>,
+    window: Duration,
+    state: Mutex<LedgerState>,
+}
+
+struct LedgerEntry {
+    id: u64,
+    grant: Grant,
+    /// Identities the grant still counts for, i.e. whose quotas weren't reset since.
+    counted: Vec<Identity>,
+}
+
+struct LedgerState {
+    file: File,
+    /// Grants in the current window, oldest first.
+    entries: VecDeque<LedgerEntry>,
+    granted: HashMap<Identity, u64>,
+    next_id: u64,
+}
+
+impl GrantLedger {
+    /// Opens the ledger at `path`, creating it if it doesn't exist, and replays the grants in the
+    /// current window.
+    pub fn open(
+        path: &Path,
+        address_quota: Option<u64>,
+        ip_quota: Option<u64>,
+        window: Duration,
+    ) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open grant ledger {:?}", path))?;
+        let mut state = LedgerState {
+            file: file.try_clone()?,
+            entries: VecDeque::new(),
+            granted: HashMap::new(),
+            next_id: 0,
+        };
+        let start = now_secs().saturating_sub(window.as_secs());
+        for (line_num, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record = serde_json::from_str(&line).with_context(|| {
+                format!("Invalid record at line {} of {:?}", line_num + 1, path)
+            })?;
+            match record {
+                Record::Grant(grant) if grant.timestamp_secs > start => {
+                    state.add(grant);
+                }
+                Record::Grant(_) => (),
+                Record::Reset { identity, .. } => state.reset(identity),
+            }
+        }
+
+        Ok(Self {
+            address_quota,
+            ip_quota,
+            window,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Counts `grant` towards the quotas, if it's within them.
+    pub fn reserve(&self, grant: Grant) -> Result<Reservation, RejectReason> {
+        let mut state = self.state.lock().unwrap();
+        state.prune(now_secs().saturating_sub(self.window.as_secs()));
+
+        for identity in grant.identities() {
+            let (quota, reason) = match identity {
+                Identity::Address(_) => (self.address_quota, RejectReason::AddressQuotaExceeded),
+                Identity::Ip(_) => (self.ip_quota, RejectReason::IpQuotaExceeded),
+            };
+            let granted = state.granted.get(&identity).copied().unwrap_or(0);
+            if quota.map_or(false, |quota| granted.saturating_add(grant.amount) > quota) {
+                return Err(reason);
+            }
+        }
+
+        let id = state.add(grant.clone());
+        Ok(Reservation { id, grant })
+    }
+
+    /// Records the reserved grant in the ledger file, once the funding succeeded. It's recorded
+    /// at the time it's committed, so it counts towards the quotas for a whole window after the
+    /// funding, even if the funding took long.
+    pub fn commit(&self, reservation: Reservation) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let grant = Grant {
+            timestamp_secs: now_secs(),
+            ..reservation.grant
+        };
+        // Keeps the entries ordered by time, for the pruning
+        if let Some(index) = state
+            .entries
+            .iter()
+            .position(|entry| entry.id == reservation.id)
+        {
+            let mut entry = state.entries.remove(index).unwrap();
+            entry.grant = grant.clone();
+            state.entries.push_back(entry);
+        }
+        state.append(&Record::Grant(grant))
+    }
+
+    /// Stops counting the reserved grant towards the quotas, since the funding failed.
+    pub fn release(&self, reservation: Reservation) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(index) = state
+            .entries
+            .iter()
+            .position(|entry| entry.id == reservation.id)
+        {
+            let entry = state.entries.remove(index).unwrap();
+            state.uncount(&entry);
+        }
+    }
+
+    pub fn usage(&self, identity: Identity) -> QuotaUsage {
+        let mut state = self.state.lock().unwrap();
+        state.prune(now_secs().saturating_sub(self.window.as_secs()));
+        QuotaUsage {
+            identity,
+            granted: state.granted.get(&identity).copied().unwrap_or(0),
+            quota: match identity {
+                Identity::Address(_) => self.address_quota,
+                Identity::Ip(_) => self.ip_quota,
+            },
+            grants: state
+                .entries
+                .iter()
+                .filter(|entry| entry.counted.contains(&identity))
+                .map(|entry| entry.grant.clone())
+                .collect(),
+        }
+    }
+
+    /// Resets the quota of `identity`, i.e. forgets what was granted to it so far.
+    pub fn reset(&self, identity: Identity) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.append(&Record::Reset {
+            identity,
+            timestamp_secs: now_secs(),
+        })?;
+        state.reset(identity);
+        Ok(())
+    }
+}
+
+impl LedgerState {
+    fn add(&mut self, grant: Grant) -> u64 {
+        let counted: Vec<_> = grant.identities().collect();
+        for identity in &counted {
+            let granted = self.granted.entry(*identity).or_insert(0);
+            *granted = granted.saturating_add(grant.amount);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push_back(LedgerEntry { id, grant, counted });
+        id
+    }
+
+    fn uncount(&mut self, entry: &LedgerEntry) {
+        for identity in &entry.counted {
+            if let Some(granted) = self.granted.get_mut(identity) {
+                *granted = granted.saturating_sub(entry.grant.amount);
+                if *granted == 0 {
+                    self.granted.remove(identity);
+                }
+            }
+        }
+    }
+
+    /// Drops the grants up to `start`, the start of the current window.
+    fn prune(&mut self, start: u64) {
+        while self
+            .entries
+            .front()
+            .map_or(false, |entry| entry.grant.timestamp_secs <= start)
+        {
+            let entry = self.entries.pop_front().unwrap();
+            self.uncount(&entry);
+        }
+    }
+
+    fn reset(&mut self, identity: Identity) {
+        self.granted.remove(&identity);
+        for entry in &mut self.entries {
+            entry.counted.retain(|counted| *counted != identity);
+        }
+    }
+
+    fn append(&mut self, record: &Record) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+#[derive(Debug, Deserialize)]
+struct IdentityParams {
+    address: Option<String>,
+    ip: Option<IpAddr>,
+}
+
+impl IdentityParams {
+    fn identity(&self) -> Option<Identity> {
+        match (&self.address, self.ip) {
+            (Some(address), None) => AccountAddress::from_hex_literal(address)
+                .or_else(|_| AccountAddress::from_hex(address))
+                .ok()
+                .map(Identity::Address),
+            (None, Some(ip)) => Some(Identity::Ip(ip)),
+            _ => None,
+        }
+    }
+}
+
+pub fn admin_routes(
+    service: Arc<Service>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    // GET /admin/quotas?address=xxx
+    // POST /admin/quotas/reset?ip=xxx
+    let query = warp::path!("admin" / "quotas")
+        .and(warp::get())
+        .map(|| false);
+    let reset = warp::path!("admin" / "quotas" / "reset")
+        .and(warp::post())
+        .map(|| true);
+    query
+        .or(reset)
+        .unify()
+        .and(warp::any().map(move || service.clone()))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::query::<IdentityParams>())
+        .and_then(handle_admin)
+}
+
+async fn handle_admin(
+    is_reset: bool,
+    service: Arc<Service>,
+    authorization: Option<String>,
+    params: IdentityParams,
+) -> Result<Box<dyn warp::Reply>, Infallible> {
+    let abuse_protection = &service.abuse_protection;
+    let (ledger, admin_token) = match (
+        abuse_protection.grant_ledger(),
+        abuse_protection.admin_token(),
+    ) {
+        (Some(ledger), Some(admin_token)) => (ledger, admin_token),
+        _ => {
+            return Ok(Box::new(warp::reply::with_status(
+                "Admin endpoints are disabled",
+                StatusCode::NOT_FOUND,
+            )))
+        }
+    };
+    if !admin_token.authorizes(authorization.as_deref().map(str::as_bytes)) {
+        return Ok(Box::new(warp::reply::with_status(
+            "Unauthorized",
+            StatusCode::UNAUTHORIZED,
+        )));
+    }
+    let identity = match params.identity() {
+        Some(identity) => identity,
+        None => {
+            return Ok(Box::new(warp::reply::with_status(
+                "You must provide either a valid 'address' or 'ip'",
+                StatusCode::BAD_REQUEST,
+            )))
+        }
+    };
+
+    if is_reset {
+        if let Err(err) = ledger.reset(identity) {
+            return Ok(Box::new(warp::reply::with_status(
+                err.to_string(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )));
+        }
+    }
+    Ok(Box::new(warp::reply::json(&ledger.usage(identity))))
+}
@@ -22,10 +22,12 @@
 //! cargo run -p aptos-faucet -- -h
 //! ```
 
+use crate::abuse::{AbuseProtection, AbuseProtectionArgs};
 use anyhow::Result;
 use aptos_config::keys::ConfigKey;
 use aptos_crypto::ed25519::Ed25519PrivateKey;
 use aptos_logger::info;
+use aptos_metrics_core::{Encoder, TextEncoder};
 use aptos_rest_client::Client;
 use aptos_sdk::{
     transaction_builder::{aptos_stdlib, TransactionFactory},
@@ -34,13 +36,25 @@ use aptos_sdk::{
         chain_id::ChainId, LocalAccount,
     },
 };
-use clap::Parser;
+use clap::{ArgEnum, Parser};
 use futures::lock::Mutex;
 use reqwest::StatusCode;
-use std::{convert::Infallible, path::PathBuf, sync::Arc};
+use std::{
+    convert::Infallible,
+    fmt,
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 use url::Url;
 use warp::{http, Filter, Rejection, Reply};
 
+pub mod abuse;
+pub mod grants;
 pub mod mint;
 
 /// Aptos Testnet utility service for creating test accounts and minting test coins
@@ -86,6 +100,17 @@ pub struct FaucetArgs {
     pub maximum_amount: Option<u64>,
     #[clap(long)]
     pub do_not_delegate: bool,
+    /// Number of accounts to delegate minting to. Mint requests are spread over them, so the
+    /// throughput isn't bound by the sequence number of one account.
+    /// Can't be more than 1 with `--do-not-delegate`
+    #[clap(long, default_value = "1")]
+    pub num_funders: usize,
+    /// How to fund accounts: `mint` new coins, which needs the mint capability, or `transfer`
+    /// them from the pre-funded mint account, which is then never delegated
+    #[clap(long, default_value_t = FundingStrategy::Mint)]
+    pub funding_strategy: FundingStrategy,
+    #[clap(flatten)]
+    pub abuse_protection: AbuseProtectionArgs,
 }
 
 impl FaucetArgs {
@@ -116,46 +141,136 @@ impl FaucetArgs {
             .unwrap_or_else(aptos_test_root_address);
         let faucet_account = LocalAccount::new(faucet_address, key, 0);
 
+        // Transfers are from the balance of the mint account, there is no capability to delegate.
+        let do_not_delegate =
+            self.do_not_delegate || self.funding_strategy == FundingStrategy::Transfer;
+        assert!(
+            self.num_funders >= 1 && (self.num_funders == 1 || !do_not_delegate),
+            "There must be 1 funder without delegation, and at least 1 with it"
+        );
+
         // Do not use maximum amount on delegation, this allows the new delegated faucet to
         // mint a lot for themselves!
-        let maximum_amount = if self.do_not_delegate {
+        let maximum_amount = if do_not_delegate {
             self.maximum_amount
         } else {
             None
         };
 
-        let service = Arc::new(Service::new(
-            self.server_url.clone(),
-            self.chain_id,
-            faucet_account,
-            maximum_amount,
-        ));
+        let abuse_protection = AbuseProtection::new(&self.abuse_protection)
+            .expect("Failed to set up abuse protection");
+        let service = Arc::new(
+            Service::new(
+                self.server_url.clone(),
+                self.chain_id,
+                faucet_account,
+                maximum_amount,
+            )
+            .with_abuse_protection(abuse_protection)
+            .with_funding_strategy(self.funding_strategy),
+        );
 
-        let actual_service = if self.do_not_delegate {
+        let actual_service = if do_not_delegate {
             service
         } else {
-            delegate_mint_account(service, self.server_url, self.chain_id, self.maximum_amount)
-                .await
+            delegate_mint_account(
+                service,
+                self.server_url,
+                self.chain_id,
+                self.maximum_amount,
+                self.num_funders,
+            )
+            .await
         };
 
         println!("Faucet is running.  Faucet endpoint: {}", address);
 
+        let mut funder_addresses = vec![];
+        for funder in &actual_service.funders {
+            funder_addresses.push(funder.account.lock().await.address());
+        }
         info!(
-            "[faucet]: running on: {}. Minting from {}",
-            address,
-            actual_service.faucet_account.lock().await.address()
+            "[faucet]: running on: {}. Minting from {:?}",
+            address, funder_addresses,
         );
         warp::serve(routes(actual_service)).run(address).await;
     }
 }
 
+/// An account mint requests are sent from. Each tracks its own sequence number, so funders don't
+/// wait on each other.
+pub struct Funder {
+    pub account: Mutex<LocalAccount>,
+    pub outstanding_requests: std::sync::RwLock<Vec<crate::mint::MintParams>>,
+    unhealthy_until: std::sync::Mutex<Option<Instant>>,
+}
+
+impl Funder {
+    /// How long a funder with transactions stuck is skipped for.
+    const UNHEALTHY_BACKOFF: Duration = Duration::from_secs(30);
+
+    fn new(account: LocalAccount) -> Self {
+        Self {
+            account: Mutex::new(account),
+            outstanding_requests: std::sync::RwLock::new(vec![]),
+            unhealthy_until: std::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.unhealthy_until
+            .lock()
+            .unwrap()
+            .map_or(true, |until| Instant::now() >= until)
+    }
+
+    /// Skips the funder for a while, so the requests go to the other funders in the meantime.
+    pub fn mark_unhealthy(&self) {
+        *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + Self::UNHEALTHY_BACKOFF);
+    }
+}
+
+/// How the faucet funds accounts.
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FundingStrategy {
+    /// Mints the coins, with the mint capability of the funders. For devnets.
+    Mint,
+    /// Transfers the coins from the balance of the funders. For testnets, where the faucet has
+    /// no mint capability but pre-funded accounts.
+    Transfer,
+}
+
+impl fmt::Display for FundingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            FundingStrategy::Mint => "mint",
+            FundingStrategy::Transfer => "transfer",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for FundingStrategy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mint" => Ok(FundingStrategy::Mint),
+            "transfer" => Ok(FundingStrategy::Transfer),
+            _ => Err("Invalid funding strategy. Valid values are mint, transfer"),
+        }
+    }
+}
+
 pub struct Service {
-    pub faucet_account: Mutex<LocalAccount>,
+    pub funders: Vec<Funder>,
     pub transaction_factory: TransactionFactory,
-    pub outstanding_requests: std::sync::RwLock<Vec<crate::mint::MintParams>>,
+    next_funder: AtomicUsize,
     client: Client,
     endpoint: Url,
     maximum_amount: Option<u64>,
+    abuse_protection: Arc<AbuseProtection>,
+    funding_strategy: FundingStrategy,
 }
 
 impl Service {
@@ -165,19 +280,45 @@ impl Service {
         faucet_account: LocalAccount,
         maximum_amount: Option<u64>,
     ) -> Self {
+        Self::new_with_funders(endpoint, chain_id, vec![faucet_account], maximum_amount)
+    }
+
+    /// Serves mint requests from a pool of accounts, going round robin over them.
+    pub fn new_with_funders(
+        endpoint: Url,
+        chain_id: ChainId,
+        funder_accounts: Vec<LocalAccount>,
+        maximum_amount: Option<u64>,
+    ) -> Self {
+        assert!(!funder_accounts.is_empty(), "No funder accounts");
         let client = Client::new(endpoint.clone());
         Service {
-            faucet_account: Mutex::new(faucet_account),
+            funders: funder_accounts.into_iter().map(Funder::new).collect(),
             transaction_factory: TransactionFactory::new(chain_id)
                 .with_gas_unit_price(std::cmp::max(1, aptos_global_constants::GAS_UNIT_PRICE))
                 .with_transaction_expiration_time(30),
-            outstanding_requests: std::sync::RwLock::new(vec![]),
+            next_funder: AtomicUsize::new(0),
             client,
             endpoint,
             maximum_amount,
+            abuse_protection: Arc::new(AbuseProtection::default()),
+            funding_strategy: FundingStrategy::Mint,
         }
     }
 
+    /// Funds accounts with `funding_strategy`. By default the coins are minted.
+    pub fn with_funding_strategy(mut self, funding_strategy: FundingStrategy) -> Self {
+        self.funding_strategy = funding_strategy;
+        self
+    }
+
+    /// Checks mint requests with `abuse_protection` before serving them. By default all are
+    /// served.
+    pub fn with_abuse_protection(mut self, abuse_protection: AbuseProtection) -> Self {
+        self.abuse_protection = Arc::new(abuse_protection);
+        self
+    }
+
     // By default the path is prefixed with the version, e.g. `v1/`. The fake
     // API used in the faucet tests doesn't have a versioned API however, so
     // we just set it to `/`.
@@ -189,15 +330,37 @@ impl Service {
     pub fn endpoint(&self) -> &Url {
         &self.endpoint
     }
+
+    /// The amount granted for a request of `amount`.
+    pub fn amount_to_grant(&self, amount: u64) -> u64 {
+        self.maximum_amount.map_or(amount, |maximum_amount| {
+            std::cmp::min(amount, maximum_amount)
+        })
+    }
+
+    /// The funder to send the next mint request from, going round robin over the healthy ones.
+    /// If none is healthy, the next one is used anyway.
+    pub fn next_funder(&self) -> &Funder {
+        let start = self.next_funder.fetch_add(1, Ordering::Relaxed);
+        (0..self.funders.len())
+            .map(|offset| &self.funders[(start + offset) % self.funders.len()])
+            .find(|funder| funder.is_healthy())
+            .unwrap_or(&self.funders[start % self.funders.len()])
+    }
 }
 
 pub fn routes(
     service: Arc<Service>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     let mint = mint::mint_routes(service.clone());
+    let batch_mint = mint::batch_mint_routes(service.clone());
+    let admin = grants::admin_routes(service.clone());
     let health = health_route(service);
 
     health
+        .or(metrics_route())
+        .or(batch_mint)
+        .or(admin)
         .or(mint)
         .with(warp::log::custom(|info| {
             let forwarded_for = info
@@ -222,7 +385,10 @@ pub fn routes(
         .with(
             warp::cors()
                 .allow_any_origin()
-                .allow_headers(vec![http::header::CONTENT_TYPE])
+                .allow_headers(vec![
+                    http::header::CONTENT_TYPE,
+                    http::header::HeaderName::from_static(abuse::TOKEN_HEADER),
+                ])
                 .allow_methods(vec!["POST"]),
         )
 }
@@ -236,17 +402,32 @@ fn health_route(
         .and_then(handle_health)
 }
 
+/// Replies with the sequence numbers of the funders, comma separated.
 async fn handle_health(service: Arc<Service>) -> Result<Box<dyn warp::Reply>, Infallible> {
-    let faucet_address = service.faucet_account.lock().await.address();
-    let faucet_account = service.client.get_account(faucet_address).await;
-
-    match faucet_account {
-        Ok(account) => Ok(Box::new(account.inner().sequence_number.to_string())),
-        Err(err) => Ok(Box::new(warp::reply::with_status(
-            err.to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ))),
+    let mut sequence_numbers = vec![];
+    for funder in &service.funders {
+        let faucet_address = funder.account.lock().await.address();
+        match service.client.get_account(faucet_address).await {
+            Ok(account) => sequence_numbers.push(account.inner().sequence_number.to_string()),
+            Err(err) => {
+                return Ok(Box::new(warp::reply::with_status(
+                    err.to_string(),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                )))
+            }
+        }
     }
+    Ok(Box::new(sequence_numbers.join(",")))
+}
+
+fn metrics_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("metrics").and(warp::get()).map(|| {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&aptos_metrics_core::gather(), &mut buffer)
+            .expect("Failed to encode metrics");
+        buffer
+    })
 }
 
 /// The idea is that this may be happening concurrently. If we end up in such a race, the faucets
@@ -257,13 +438,27 @@ pub async fn delegate_mint_account(
     server_url: Url,
     chain_id: ChainId,
     maximum_amount: Option<u64>,
+    num_funders: usize,
 ) -> Arc<Service> {
+    let mut delegated_accounts = Vec::with_capacity(num_funders);
+    for _ in 0..num_funders {
+        delegated_accounts.push(delegate_to_new_account(&service).await);
+    }
+
+    let mut delegated_service =
+        Service::new_with_funders(server_url, chain_id, delegated_accounts, maximum_amount);
+    delegated_service.abuse_protection = service.abuse_protection.clone();
+    delegated_service.funding_strategy = service.funding_strategy;
+    Arc::new(delegated_service)
+}
+
+async fn delegate_to_new_account(service: &Service) -> LocalAccount {
     // Create a new random account, then delegate to it
     let mut delegated_account = LocalAccount::generate(&mut rand::rngs::OsRng);
 
     // Create the account
     let response = mint::process(
-        &service,
+        service,
         mint::MintParams {
             amount: 100_000_000_000,
             auth_key: None,
@@ -296,7 +491,7 @@ pub async fn delegate_mint_account(
 
     // Delegate minting to the account
     {
-        let mut faucet_account = service.faucet_account.lock().await;
+        let mut faucet_account = service.funders[0].account.lock().await;
         service
             .client
             .submit_and_wait(&faucet_account.sign_with_transaction_builder(
@@ -321,10 +516,5 @@ pub async fn delegate_mint_account(
         .await
         .unwrap();
 
-    Arc::new(Service::new(
-        server_url,
-        chain_id,
-        delegated_account,
-        maximum_amount,
-    ))
+    delegated_account
 }
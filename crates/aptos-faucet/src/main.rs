@@ -16,8 +16,15 @@ async fn main() {
 
 #[cfg(test)]
 mod tests {
-    use aptos_crypto::{ed25519::Ed25519PublicKey, hash::HashValue};
-    use aptos_faucet::{routes, Service};
+    use aptos_crypto::{
+        ed25519::Ed25519PublicKey, hash::HashValue, SigningKey, ValidCryptoMaterial,
+    };
+    use aptos_faucet::{
+        abuse::{token_message, AbuseProtection, AbuseProtectionArgs, TOKEN_HEADER},
+        grants::QuotaUsage,
+        mint::{BatchResult, MAX_BATCH_SIZE},
+        routes, FundingStrategy, Service,
+    };
     use aptos_infallible::RwLock;
     use aptos_keygen::KeyGen;
     use aptos_rest_client::{
@@ -31,8 +38,9 @@ mod tests {
         account_address::AccountAddress,
         chain_id::ChainId,
         transaction::{
-            authenticator::AuthenticationKey, SignedTransaction, Transaction, TransactionArgument,
-            TransactionPayload::Script,
+            authenticator::AuthenticationKey,
+            SignedTransaction, Transaction, TransactionArgument,
+            TransactionPayload::{EntryFunction, Script},
         },
         LocalAccount,
     };
@@ -41,7 +49,9 @@ mod tests {
     use std::{
         collections::HashMap,
         convert::{Infallible, TryFrom, TryInto},
+        io::Write,
         sync::{Arc, Mutex},
+        time::{SystemTime, UNIX_EPOCH},
     };
     use tokio::task::JoinHandle;
     use url::Url;
@@ -72,27 +82,42 @@ mod tests {
     }
 
     fn setup(maximum_amount: Option<u64>) -> (AccountStates, Arc<Service>) {
-        let mut keygen = KeyGen::from_seed([0; 32]);
-        let (private_key, public_key) = keygen.generate_ed25519_keypair();
-        let account_address = AuthenticationKey::ed25519(&public_key).derived_address();
+        setup_with(maximum_amount, 1, |service| service)
+    }
 
-        let faucet_account = LocalAccount::new(account_address, private_key, 0);
+    fn setup_with(
+        maximum_amount: Option<u64>,
+        num_funders: usize,
+        configure: impl FnOnce(Service) -> Service,
+    ) -> (AccountStates, Arc<Service>) {
+        let mut keygen = KeyGen::from_seed([0; 32]);
+        let accounts = AccountStates::new(aptos_infallible::RwLock::new(HashMap::new()));
+        let funder_accounts = (0..num_funders)
+            .map(|_| {
+                let (private_key, public_key) = keygen.generate_ed25519_keypair();
+                let account_address = AuthenticationKey::ed25519(&public_key).derived_address();
+                accounts
+                    .write()
+                    .insert(account_address, AccountState::new(0));
+                LocalAccount::new(account_address, private_key, 0)
+            })
+            .collect();
 
         let chain_id = ChainId::test();
 
-        let accounts = AccountStates::new(aptos_infallible::RwLock::new(HashMap::new()));
-        accounts
-            .write()
-            .insert(account_address, AccountState::new(0));
-
         let last_txn = Arc::new(Mutex::new(None));
         let last_txn_0 = last_txn.clone();
 
         let accounts_cloned_0 = accounts.clone();
         let accounts_cloned_1 = accounts.clone();
+        let accounts_cloned_2 = accounts.clone();
         let stub = warp::path!("accounts" / String)
             .and(warp::any().map(move || accounts_cloned_0.clone()))
             .and_then(handle_get_account)
+            .or(warp::path!("accounts" / String / "resource" / String)
+                .and(warp::get())
+                .and(warp::any().map(move || accounts_cloned_2.clone()))
+                .and_then(handle_get_coin_store))
             .or(warp::path!("transactions" / "by_hash" / String)
                 .and(warp::get())
                 .and(warp::any().map(move || last_txn_0.clone()))
@@ -112,14 +137,14 @@ mod tests {
         let (address, future) = warp::serve(stub).bind_ephemeral(([127, 0, 0, 1], 0));
         tokio::task::spawn(async move { future.await });
 
-        let service = Service::new(
+        let service = Service::new_with_funders(
             Url::parse(&format!("http://localhost:{}/", address.port())).unwrap(),
             chain_id,
-            faucet_account,
+            funder_accounts,
             maximum_amount,
         )
         .configure_for_testing();
-        (accounts, Arc::new(service))
+        (accounts, Arc::new(configure(service)))
     }
 
     async fn handle_get_account(
@@ -145,6 +170,29 @@ mod tests {
         }
     }
 
+    async fn handle_get_coin_store(
+        address: String,
+        _resource_type: String,
+        accounts: AccountStates,
+    ) -> Result<impl Reply, Rejection> {
+        let reader = accounts.read();
+        let account = match AccountAddress::try_from(address.clone())
+            .or_else(|_e| AccountAddress::from_hex(address.clone()))
+        {
+            Ok(addr) => reader.get(&addr),
+            _ => None,
+        };
+        if let Some(account) = account {
+            let coin_store = serde_json::json!({
+                "type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                "data": { "coin": { "value": account.balance.to_string() } },
+            });
+            Ok(response(&coin_store))
+        } else {
+            Err(warp::reject())
+        }
+    }
+
     async fn handle_get_transaction(
         _hash: String,
         last_txn: Arc<Mutex<Option<Transaction>>>,
@@ -443,7 +491,7 @@ mod tests {
     #[tokio::test]
     async fn test_mint_fullnode_error() {
         let (accounts, service) = setup(None);
-        let address = service.faucet_account.lock().await.address();
+        let address = service.funders[0].account.lock().await.address();
         accounts.write().remove(&address);
         let filter = routes(service);
 
@@ -468,6 +516,337 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_mint_funder_pool() {
+        let (_accounts, service) = setup_with(None, 2, |service| service);
+        let mut funder_addresses = vec![];
+        for funder in &service.funders {
+            funder_addresses.push(funder.account.lock().await.address());
+        }
+        let filter = routes(service.clone());
+
+        let mut senders = vec![];
+        for _ in 0..4 {
+            let resp = warp::test::request()
+                .method("POST")
+                .path("/mint?address=0x1&amount=10&return_txns=true")
+                .reply(&filter)
+                .await;
+            let txns: Vec<SignedTransaction> =
+                bcs::from_bytes(&hex::decode(resp.body()).unwrap()).unwrap();
+            senders.push(txns[0].sender());
+        }
+        assert_eq!(
+            senders,
+            [
+                funder_addresses[0],
+                funder_addresses[1],
+                funder_addresses[0],
+                funder_addresses[1]
+            ]
+        );
+
+        // Stuck funders are skipped.
+        service.funders[0].mark_unhealthy();
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/mint?address=0x1&amount=10&return_txns=true")
+            .reply(&filter)
+            .await;
+        let txns: Vec<SignedTransaction> =
+            bcs::from_bytes(&hex::decode(resp.body()).unwrap()).unwrap();
+        assert_eq!(txns[0].sender(), funder_addresses[1]);
+    }
+
+    #[tokio::test]
+    async fn test_mint_batch() {
+        let (accounts, service) = setup(None);
+        let filter = routes(service);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/mint_batch")
+            .json(&serde_json::json!([
+                {"address": "0x1", "amount": 10},
+                {"address": "0x2", "amount": 20},
+                {"address": "0x01", "amount": 30},
+            ]))
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let results: Vec<BatchResult> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|result| result.error.is_none() && result.txn_hashes.len() == 1));
+
+        let reader = accounts.read();
+        assert_eq!(reader.get(&AccountAddress::ONE).unwrap().balance, 40);
+        assert_eq!(
+            reader
+                .get(&AccountAddress::from_hex_literal("0x2").unwrap())
+                .unwrap()
+                .balance,
+            20
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mint_batch_too_large() {
+        let (_accounts, service) = setup(None);
+        let filter = routes(service);
+
+        let entries: Vec<_> = (0..=MAX_BATCH_SIZE)
+            .map(|_| serde_json::json!({"address": "0x1", "amount": 10}))
+            .collect();
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/mint_batch")
+            .json(&entries)
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_mint_with_transfer_strategy() {
+        let (_accounts, service) = setup_with(None, 1, |service| {
+            service.with_funding_strategy(FundingStrategy::Transfer)
+        });
+        let filter = routes(service);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/mint?address=0x1&amount=10&return_txns=true")
+            .reply(&filter)
+            .await;
+        let txns: Vec<SignedTransaction> =
+            bcs::from_bytes(&hex::decode(resp.body()).unwrap()).unwrap();
+        match txns[0].payload() {
+            EntryFunction(entry_function) => {
+                assert_eq!(entry_function.function().as_str(), "transfer");
+                assert_eq!(entry_function.module().name().as_str(), "aptos_account");
+            }
+            payload => panic!("unexpected payload: {:?}", payload),
+        }
+    }
+
+    async fn mint_to_one<F>(filter: &F, amount: u64) -> StatusCode
+    where
+        F: Filter + 'static,
+        F::Extract: Reply + Send,
+    {
+        warp::test::request()
+            .method("POST")
+            .path(format!("/mint?address=0x1&amount={}", amount).as_str())
+            .reply(filter)
+            .await
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_mint_quotas() {
+        let dir = tempfile::tempdir().unwrap();
+        let admin_token_file = dir.path().join("admin_token");
+        std::fs::write(&admin_token_file, "secret\n").unwrap();
+        let args = AbuseProtectionArgs {
+            grant_ledger_file: Some(dir.path().join("grants")),
+            address_quota: Some(25),
+            admin_token_file: Some(admin_token_file),
+            ..AbuseProtectionArgs::default()
+        };
+        let (_accounts, service) = setup_with(None, 1, |service| {
+            service.with_abuse_protection(AbuseProtection::new(&args).unwrap())
+        });
+        let filter = routes(service);
+        assert_eq!(mint_to_one(&filter, 10).await, StatusCode::OK);
+        assert_eq!(mint_to_one(&filter, 10).await, StatusCode::OK);
+        assert_eq!(
+            mint_to_one(&filter, 10).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // The grants are still counted after a restart.
+        let (_accounts, service) = setup_with(None, 1, |service| {
+            service.with_abuse_protection(AbuseProtection::new(&args).unwrap())
+        });
+        let filter = routes(service);
+        assert_eq!(
+            mint_to_one(&filter, 10).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(mint_to_one(&filter, 5).await, StatusCode::OK);
+
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/admin/quotas?address=0x1")
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/admin/quotas?address=0x1")
+            .header("authorization", "Bearer secret")
+            .reply(&filter)
+            .await;
+        let usage: QuotaUsage = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(usage.granted, 25);
+        assert_eq!(usage.quota, Some(25));
+        assert_eq!(usage.grants.len(), 3);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/admin/quotas/reset?address=0x1")
+            .header("authorization", "Bearer secret")
+            .reply(&filter)
+            .await;
+        let usage: QuotaUsage = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(usage.granted, 0);
+        assert_eq!(mint_to_one(&filter, 10).await, StatusCode::OK);
+
+        // So is the reset.
+        let (_accounts, service) = setup_with(None, 1, |service| {
+            service.with_abuse_protection(AbuseProtection::new(&args).unwrap())
+        });
+        let filter = routes(service);
+        assert_eq!(mint_to_one(&filter, 15).await, StatusCode::OK);
+        assert_eq!(mint_to_one(&filter, 1).await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_mint_rate_limits() {
+        let (_accounts, service) = setup_with(None, 1, |service| {
+            service.with_abuse_protection(
+                AbuseProtection::new(&AbuseProtectionArgs {
+                    max_requests_per_ip: Some(2),
+                    max_requests_per_account: Some(1),
+                    ..AbuseProtectionArgs::default()
+                })
+                .unwrap(),
+            )
+        });
+        let filter = routes(service);
+
+        let mint = |address: &'static str, ip: [u8; 4]| {
+            warp::test::request()
+                .method("POST")
+                .path(format!("/mint?address={}&amount=10", address).as_str())
+                .remote_addr((ip, 1234).into())
+                .reply(&filter)
+        };
+        assert_eq!(mint("0x1", [1, 1, 1, 1]).await.status(), StatusCode::OK);
+        assert_eq!(
+            mint("0x1", [2, 2, 2, 2]).await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        // Requests rejected for the account don't count towards the limit of the IP
+        assert_eq!(
+            mint("0x1", [1, 1, 1, 1]).await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(mint("0x2", [1, 1, 1, 1]).await.status(), StatusCode::OK);
+        let resp = mint("0x3", [1, 1, 1, 1]).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            resp.body(),
+            "Too many requests from this IP, try again later"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mint_denylist() {
+        let mut denylist_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(denylist_file, "# bots\n1.1.1.1\n0x2").unwrap();
+        let (accounts, service) = setup_with(None, 1, |service| {
+            service.with_abuse_protection(
+                AbuseProtection::new(&AbuseProtectionArgs {
+                    denylist_file: Some(denylist_file.path().to_path_buf()),
+                    ..AbuseProtectionArgs::default()
+                })
+                .unwrap(),
+            )
+        });
+        let filter = routes(service);
+
+        let mint = |address: &'static str, ip: [u8; 4]| {
+            warp::test::request()
+                .method("POST")
+                .path(format!("/mint?address={}&amount=10", address).as_str())
+                .remote_addr((ip, 1234).into())
+                .reply(&filter)
+        };
+        assert_eq!(
+            mint("0x1", [1, 1, 1, 1]).await.status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            mint("0x2", [2, 2, 2, 2]).await.status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(mint("0x1", [2, 2, 2, 2]).await.status(), StatusCode::OK);
+        assert!(accounts.read().get(&AccountAddress::ONE).is_some());
+        assert!(accounts
+            .read()
+            .get(&AccountAddress::from_hex_literal("0x2").unwrap())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mint_token() {
+        let (private_key, public_key) = KeyGen::from_seed([2; 32]).generate_ed25519_keypair();
+        let (_accounts, service) = setup_with(None, 1, |service| {
+            service.with_abuse_protection(
+                AbuseProtection::new(&AbuseProtectionArgs {
+                    token_public_key: Some(public_key),
+                    ..AbuseProtectionArgs::default()
+                })
+                .unwrap(),
+            )
+        });
+        let filter = routes(service);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let token = |receiver: AccountAddress, expiration: u64| {
+            let signature =
+                private_key.sign_arbitrary_message(&token_message(receiver, expiration));
+            format!("{}.{}", expiration, hex::encode(signature.to_bytes()))
+        };
+        let mint = |token: Option<String>| {
+            let mut request = warp::test::request()
+                .method("POST")
+                .path("/mint?address=0x1&amount=10");
+            if let Some(token) = token {
+                request = request.header(TOKEN_HEADER, token);
+            }
+            request.reply(&filter)
+        };
+        assert_eq!(mint(None).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            mint(Some(token(
+                AccountAddress::from_hex_literal("0x2").unwrap(),
+                now + 60
+            )))
+            .await
+            .body(),
+            "Invalid token"
+        );
+        assert_eq!(
+            mint(Some(token(AccountAddress::ONE, now - 60)))
+                .await
+                .body(),
+            "Token expired"
+        );
+        let valid_token = token(AccountAddress::ONE, now + 60);
+        assert_eq!(
+            mint(Some(valid_token.clone())).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(mint(Some(valid_token)).await.body(), "Token already used");
+    }
+
     #[tokio::test]
     async fn create_account_with_client() {
         let (faucet_client, _service) = get_client().await;
@@ -483,6 +862,23 @@ mod tests {
         faucet_client.fund(address, 10).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn fund_many_accounts_with_client() {
+        let (faucet_client, _service) = get_client().await;
+        let mut keygen = KeyGen::from_seed([1; 32]);
+        let addresses: Vec<_> = (0..4)
+            .map(|_| {
+                let public_key = keygen.generate_ed25519_keypair().1;
+                AuthenticationKey::ed25519(&public_key).derived_address()
+            })
+            .collect();
+        faucet_client
+            .max_concurrent_requests(2)
+            .fund_many(&addresses, 10)
+            .await
+            .unwrap();
+    }
+
     async fn get_client() -> (FaucetClient, JoinHandle<()>) {
         let (_accounts, service) = setup(None);
         let endpoint = service.endpoint().clone();
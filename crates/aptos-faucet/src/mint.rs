@@ -4,23 +4,29 @@
 // README: The aptos-faucet is deprecated in favor of the tap. Do not add new code
 // to this until you've spoken with the Ecosystem Platform team + dport.
 
-use crate::Service;
+use crate::{abuse::TOKEN_HEADER, Funder, FundingStrategy, Service};
 use anyhow::Result;
 use aptos_crypto::{ed25519::Ed25519PublicKey, hash::HashValue};
 use aptos_logger::{error, info, warn};
-use aptos_sdk::types::{
-    account_address::AccountAddress,
-    transaction::{
-        authenticator::AuthenticationKey, Script, SignedTransaction, TransactionArgument,
+use aptos_sdk::{
+    transaction_builder::aptos_stdlib,
+    types::{
+        account_address::AccountAddress,
+        transaction::{
+            authenticator::AuthenticationKey, Script, SignedTransaction, TransactionArgument,
+        },
     },
 };
 use reqwest::StatusCode;
-use serde::Deserialize;
-use std::{convert::Infallible, fmt, sync::Arc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::Infallible, fmt, net::SocketAddr, sync::Arc};
 use warp::{Filter, Rejection, Reply};
 
 static MINTER_SCRIPT: &[u8] = include_bytes!("minter.mv");
 
+/// Maximum number of entries in a batch funding request.
+pub const MAX_BATCH_SIZE: usize = 100;
+
 pub fn mint_routes(
     service: Arc<Service>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -31,20 +37,185 @@ pub fn mint_routes(
         .and(warp::post())
         .and(warp::any().map(move || service.clone()))
         .and(warp::query().map(move |params: MintParams| params))
-        .and_then(|_, service, params| handle(service, params))
+        .and(warp::addr::remote())
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .and(warp::header::optional::<String>(TOKEN_HEADER))
+        .and_then(|_, service, params, remote_addr, forwarded_for, token| {
+            handle(service, params, remote_addr, forwarded_for, token)
+        })
 }
 
 async fn handle(
     service: Arc<Service>,
     params: MintParams,
+    remote_addr: Option<SocketAddr>,
+    forwarded_for: Option<String>,
+    token: Option<String>,
 ) -> Result<Box<dyn warp::Reply>, Infallible> {
+    let abuse_protection = &service.abuse_protection;
+    let ip = abuse_protection.client_ip(remote_addr, forwarded_for.as_deref());
+    let receiver = params.receiver();
+    let admission = match abuse_protection.check(
+        ip,
+        receiver,
+        token.as_deref(),
+        service.amount_to_grant(params.amount),
+    ) {
+        Ok(admission) => admission,
+        Err(reason) => {
+            return Ok(Box::new(warp::reply::with_status(
+                reason.to_string(),
+                reason.status_code(),
+            )))
+        }
+    };
+
     match process(&service, params).await {
-        Ok(body) => Ok(Box::new(body.to_string())),
-        Err(err) => Ok(Box::new(warp::reply::with_status(
-            err.to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ))),
+        Ok(body) => {
+            abuse_protection.commit_grant(admission);
+            Ok(Box::new(body.to_string()))
+        }
+        Err(err) => {
+            abuse_protection.release_grant(admission);
+            Ok(Box::new(warp::reply::with_status(
+                err.to_string(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )))
+        }
+    }
+}
+
+pub fn batch_mint_routes(
+    service: Arc<Service>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    // POST /mint_batch, with a json body of [{"address": "xxx", "amount": 25}, ...]
+    warp::path!("mint_batch")
+        .and(warp::post())
+        .and(warp::any().map(move || service.clone()))
+        .and(warp::body::content_length_limit(64 * 1024))
+        .and(warp::body::json())
+        .and(warp::addr::remote())
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .and(warp::header::optional::<String>(TOKEN_HEADER))
+        .and_then(handle_batch)
+}
+
+async fn handle_batch(
+    service: Arc<Service>,
+    entries: Vec<BatchEntry>,
+    remote_addr: Option<SocketAddr>,
+    forwarded_for: Option<String>,
+    token: Option<String>,
+) -> Result<Box<dyn warp::Reply>, Infallible> {
+    if entries.len() > MAX_BATCH_SIZE {
+        return Ok(Box::new(warp::reply::with_status(
+            format!(
+                "Too many entries in the batch: {}, the maximum is {}",
+                entries.len(),
+                MAX_BATCH_SIZE
+            ),
+            StatusCode::BAD_REQUEST,
+        )));
+    }
+
+    // Each receiver is funded once, with the amounts for it summed.
+    let mut amounts = BTreeMap::new();
+    for entry in entries {
+        let receiver = match entry.receiver() {
+            Some(receiver) => receiver,
+            None => {
+                return Ok(Box::new(warp::reply::with_status(
+                    format!("Invalid address in the batch: {}", entry.address),
+                    StatusCode::BAD_REQUEST,
+                )))
+            }
+        };
+        let (amount, entry_token) = amounts.entry(receiver).or_insert((0u64, None));
+        *amount = amount.saturating_add(entry.amount);
+        if entry.token.is_some() {
+            *entry_token = entry.token;
+        }
+    }
+
+    let abuse_protection = &service.abuse_protection;
+    let ip = abuse_protection.client_ip(remote_addr, forwarded_for.as_deref());
+    let mut grants = Vec::with_capacity(amounts.len());
+    for (receiver, (amount, entry_token)) in amounts {
+        let token = entry_token.as_deref().or(token.as_deref());
+        match abuse_protection.check(ip, Some(receiver), token, service.amount_to_grant(amount)) {
+            Ok(admission) => grants.push((receiver, amount, admission)),
+            Err(reason) => {
+                for (_, _, admission) in grants {
+                    abuse_protection.revoke(admission);
+                }
+                return Ok(Box::new(warp::reply::with_status(
+                    format!("{}: {}", receiver.to_hex_literal(), reason),
+                    reason.status_code(),
+                )));
+            }
+        }
     }
+
+    let results =
+        futures::future::join_all(grants.into_iter().map(|(receiver, amount, admission)| {
+            let service = service.clone();
+            async move {
+                let params = MintParams {
+                    amount,
+                    auth_key: None,
+                    address: Some(receiver.to_hex_literal()),
+                    pub_key: None,
+                    return_txns: None,
+                };
+                let (txn_hashes, error) = match process(&service, params).await {
+                    Ok(response) => {
+                        service.abuse_protection.commit_grant(admission);
+                        match response {
+                            Response::SubmittedTxnsHashes(hashes) => (hashes, None),
+                            Response::SubmittedTxns(txns) => {
+                                (txns.iter().map(|txn| txn.committed_hash()).collect(), None)
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        service.abuse_protection.release_grant(admission);
+                        (vec![], Some(err.to_string()))
+                    }
+                };
+                BatchResult {
+                    address: receiver.to_hex_literal(),
+                    txn_hashes,
+                    error,
+                }
+            }
+        }))
+        .await;
+    Ok(Box::new(warp::reply::json(&results)))
+}
+
+/// An entry of a batch funding request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct BatchEntry {
+    pub address: String,
+    pub amount: u64,
+    /// Token for funding `address`, if it isn't the one in the `x-faucet-token` header.
+    pub token: Option<String>,
+}
+
+impl BatchEntry {
+    fn receiver(&self) -> Option<AccountAddress> {
+        AccountAddress::from_hex_literal(&self.address)
+            .or_else(|_| AccountAddress::from_hex(&self.address))
+            .ok()
+    }
+}
+
+/// The result of funding an address in a batch funding request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BatchResult {
+    pub address: String,
+    pub txn_hashes: Vec<HashValue>,
+    pub error: Option<String>,
 }
 
 #[derive(Debug)]
@@ -109,20 +280,20 @@ impl MintParams {
 }
 
 pub async fn process(service: &Service, params: MintParams) -> Result<Response> {
-    let maybe_maximum_amount = service.maximum_amount.unwrap_or(params.amount);
-    let amount = std::cmp::min(params.amount, maybe_maximum_amount);
+    let amount = service.amount_to_grant(params.amount);
 
     let receiver_address = params.receiver().ok_or_else(|| {
         anyhow::format_err!("You must provide 'address' (preferred), 'pub_key', or 'auth_key'")
     })?;
 
-    let (mut faucet_seq, mut receiver_seq) = sequences(service, receiver_address).await?;
+    let funder = service.next_funder();
+    let (mut faucet_seq, mut receiver_seq) = sequences(service, funder, receiver_address).await?;
     if receiver_seq.is_some() && amount == 0 {
         anyhow::bail!("Account is already created and amount asked for is 0");
     }
 
     let our_faucet_seq = {
-        let mut faucet_account = service.faucet_account.lock().await;
+        let mut faucet_account = funder.account.lock().await;
 
         // If the onchain sequence_number is greater than what we have, update our
         // sequence_numbers
@@ -139,16 +310,16 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
             // Enforce a stronger ordering of priorities based upon the MintParams that arrived
             // first. Then put the other folks to sleep to try again until the queue fills up.
             if !set_outstanding {
-                let mut requests = service.outstanding_requests.write().unwrap();
+                let mut requests = funder.outstanding_requests.write().unwrap();
                 requests.push(params.clone());
                 set_outstanding = true;
             }
 
-            if service.outstanding_requests.read().unwrap().first() == Some(&params) {
+            if funder.outstanding_requests.read().unwrap().first() == Some(&params) {
                 // There might have been two requests with the same parameters, so we ensure that
                 // we only pop off one of them. We do a read lock first since that is cheap,
                 // followed by a write lock.
-                let mut requests = service.outstanding_requests.write().unwrap();
+                let mut requests = funder.outstanding_requests.write().unwrap();
                 if requests.first() == Some(&params) {
                     requests.remove(0);
                     break;
@@ -163,7 +334,7 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
         );
 
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        let (lhs, rhs) = sequences(service, receiver_address).await?;
+        let (lhs, rhs) = sequences(service, funder, receiver_address).await?;
         faucet_seq = lhs;
         receiver_seq = rhs;
 
@@ -175,7 +346,8 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
     // After 30 seconds, we still have not caught up, we are likely unhealthy
     if our_faucet_seq >= faucet_seq + 50 {
         error!("We are unhealthy, transactions have likely expired.");
-        let mut faucet_account = service.faucet_account.lock().await;
+        funder.mark_unhealthy();
+        let mut faucet_account = funder.account.lock().await;
         if faucet_account.sequence_number() >= faucet_seq + 50 {
             info!("Resetting the sequence number counter.");
             *faucet_account.sequence_number_mut() = faucet_seq;
@@ -184,26 +356,37 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
         }
     }
 
-    let txn = {
-        let mut faucet_account = service.faucet_account.lock().await;
-        faucet_account.sign_with_transaction_builder(service.transaction_factory.script(
-            Script::new(
-                MINTER_SCRIPT.to_vec(),
-                vec![],
-                vec![
-                    TransactionArgument::Address(receiver_address),
-                    TransactionArgument::U64(amount),
-                ],
-            ),
-        ))
+    let txn_builder = match service.funding_strategy {
+        FundingStrategy::Mint => service.transaction_factory.script(Script::new(
+            MINTER_SCRIPT.to_vec(),
+            vec![],
+            vec![
+                TransactionArgument::Address(receiver_address),
+                TransactionArgument::U64(amount),
+            ],
+        )),
+        FundingStrategy::Transfer => {
+            service
+                .transaction_factory
+                .payload(aptos_stdlib::aptos_account_transfer(
+                    receiver_address,
+                    amount,
+                ))
+        }
     };
+    let txn = funder
+        .account
+        .lock()
+        .await
+        .sign_with_transaction_builder(txn_builder);
 
     let response = service.client.submit(&txn).await;
 
     // If there was an issue submitting a transaction we should just reset our sequence_numbers
     // to what was on chain
     if response.is_err() {
-        *service.faucet_account.lock().await.sequence_number_mut() = faucet_seq;
+        funder.mark_unhealthy();
+        *funder.account.lock().await.sequence_number_mut() = faucet_seq;
         response?;
     }
 
@@ -214,8 +397,12 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
     }
 }
 
-async fn sequences(service: &Service, receiver: AccountAddress) -> Result<(u64, Option<u64>)> {
-    let faucet_address = service.faucet_account.lock().await.address();
+async fn sequences(
+    service: &Service,
+    funder: &Funder,
+    receiver: AccountAddress,
+) -> Result<(u64, Option<u64>)> {
+    let faucet_address = funder.account.lock().await.address();
     let f_request = service.client.get_account(faucet_address);
     let r_request = service.client.get_account(receiver);
     let mut responses = futures::future::join_all([f_request, r_request]).await;
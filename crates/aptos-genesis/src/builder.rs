@@ -24,7 +24,10 @@ use aptos_crypto::{
 };
 use aptos_keygen::KeyGen;
 use aptos_logger::prelude::*;
-use aptos_types::{chain_id::ChainId, transaction::Transaction, waypoint::Waypoint};
+use aptos_types::{
+    chain_id::ChainId, state_store::state_key::StateKey, transaction::Transaction,
+    waypoint::Waypoint,
+};
 use framework::ReleaseBundle;
 use rand::Rng;
 use serde::{de::DeserializeOwned, Serialize};
@@ -415,6 +418,9 @@ pub struct Builder {
     randomize_first_validator_ports: bool,
     init_config: Option<InitConfigFn>,
     init_genesis_config: Option<InitGenesisConfigFn>,
+    extra_genesis_state: Vec<(StateKey, Vec<u8>)>,
+    forked_genesis_state: Vec<(StateKey, Vec<u8>)>,
+    chain_id: ChainId,
 }
 
 impl Builder {
@@ -429,6 +435,9 @@ impl Builder {
             randomize_first_validator_ports: true,
             init_config: None,
             init_genesis_config: None,
+            extra_genesis_state: Vec::new(),
+            forked_genesis_state: Vec::new(),
+            chain_id: ChainId::test(),
         })
     }
 
@@ -455,6 +464,29 @@ impl Builder {
         self
     }
 
+    /// State to write in genesis on top of what the framework creates
+    pub fn with_extra_genesis_state(
+        mut self,
+        extra_genesis_state: Vec<(StateKey, Vec<u8>)>,
+    ) -> Self {
+        self.extra_genesis_state = extra_genesis_state;
+        self
+    }
+
+    /// State of an existing network to carry over into genesis, see [`crate::fork`]
+    pub fn with_forked_genesis_state(
+        mut self,
+        forked_genesis_state: Vec<(StateKey, Vec<u8>)>,
+    ) -> Self {
+        self.forked_genesis_state = forked_genesis_state;
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: ChainId) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
     /// Build all of the validators and save their configs
     pub fn build<R>(
         mut self,
@@ -605,14 +637,17 @@ impl Builder {
 
         // Build genesis & waypoint
         let mut genesis_info = GenesisInfo::new(
-            ChainId::test(),
+            self.chain_id,
             root_key,
             configs,
             self.framework.clone(),
             &genesis_config,
         )?;
+        genesis_info.extra_state = self.extra_genesis_state.clone();
+        // The forked state can be the whole state of a network, so it's moved rather than copied
+        genesis_info.forked_state = std::mem::take(&mut self.forked_genesis_state);
         let waypoint = genesis_info.generate_waypoint()?;
-        let genesis = genesis_info.get_genesis();
+        let genesis = genesis_info.get_genesis()?;
 
         // Insert genesis and waypoint into validators
         // TODO: verify genesis?
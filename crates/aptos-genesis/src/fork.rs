@@ -0,0 +1,473 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Forking a network: a new genesis carrying the state of an existing network at a chosen
+//! version, e.g. to rehearse an upgrade against mainnet's state, with a fresh validator set.
+//!
+//! The fresh genesis is generated as usual with the new validators, and the forked state is
+//! then written over it, except for what makes up the new network: the validator set, the
+//! epoch and block counters, the chain id, and the accounts of the new validators and of the
+//! core resources. The rest of the state, the framework included, is that of the forked network.
+//! So are the staking rules: the new validators must stake at least the minimum stake of the
+//! forked network, or the first epoch change drops them all from the validator set.
+//!
+//! The resources of the new network are written by the fresh framework, but read by the forked
+//! one, so the fork fails if their layouts differ between the two frameworks.
+//!
+//! The whole forked state is held in memory, and the total supply of the coin doesn't count the
+//! stake of the new validators.
+
+use crate::add_extra_state;
+use anyhow::{anyhow, bail, ensure};
+use aptos_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_config::{aptos_test_root_address, CORE_CODE_ADDRESS},
+    state_store::state_key::StateKey,
+    transaction::{Transaction, WriteSetPayload},
+    write_set::{WriteOp, WriteSet},
+};
+use move_binary_format::{
+    normalized::{Module, Struct, Type},
+    CompiledModule,
+};
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag},
+};
+use std::collections::{HashMap, HashSet};
+use vm_genesis::Validator;
+
+/// The framework resources that stay those of the fresh genesis, as `(module, name)`
+const FRESH_FRAMEWORK_RESOURCES: &[(&str, &str)] = &[
+    ("block", "BlockResource"),
+    ("chain_id", "ChainId"),
+    ("reconfiguration", "Configuration"),
+    ("stake", "ValidatorPerformance"),
+    ("stake", "ValidatorSet"),
+];
+
+/// The minimum stake of the forked network, if its state has a staking config. Only the first
+/// field of the config is read, once the forked framework is checked to declare it as the
+/// minimum stake, so the rest of the layout may differ from that of this framework.
+pub fn forked_minimum_stake(forked_state: &[(StateKey, Vec<u8>)]) -> anyhow::Result<Option<u64>> {
+    let staking_config = framework_resource("staking_config", "StakingConfig");
+    let value = match forked_state
+        .iter()
+        .find(|(state_key, _)| *state_key == staking_config)
+    {
+        Some((_, value)) => value,
+        None => return Ok(None),
+    };
+
+    let forked_state = state_values(forked_state);
+    let mut forked_framework = Framework::new(|state_key| forked_state.get(state_key).copied());
+    if let Some(layout) = forked_framework.struct_def("staking_config", "StakingConfig")? {
+        ensure!(
+            matches!(
+                layout.fields.first(),
+                Some(field) if field.name.as_str() == "minimum_stake" && field.type_ == Type::U64
+            ),
+            "The staking_config::StakingConfig of the forked framework doesn't start with \
+            minimum_stake: u64, so the forked minimum stake can't be read"
+        );
+    }
+    let minimum_stake = value
+        .get(..8)
+        .ok_or_else(|| anyhow!("The forked staking config is too short for a minimum stake"))?;
+    Ok(Some(bcs::from_bytes(minimum_stake)?))
+}
+
+/// Writes `forked_state` into the write set of a fresh genesis transaction, except for the
+/// resources of the new network. Fails if a new validator stakes less than the forked minimum.
+pub(crate) fn add_forked_state(
+    genesis: Transaction,
+    forked_state: &[(StateKey, Vec<u8>)],
+    validators: &[Validator],
+) -> anyhow::Result<Transaction> {
+    if let Some(minimum_stake) = forked_minimum_stake(forked_state)? {
+        for validator in validators {
+            ensure!(
+                validator.stake_amount >= minimum_stake,
+                "Validator {} stakes {}, less than the minimum stake of the forked network {}",
+                validator.owner_address,
+                validator.stake_amount,
+                minimum_stake
+            );
+        }
+    }
+
+    let fresh_write_set = match &genesis {
+        Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set)) => {
+            change_set.write_set()
+        }
+        _ => bail!("Genesis transaction must be a direct write set"),
+    };
+    check_fresh_resource_layouts(fresh_write_set, forked_state)?;
+
+    let fresh_resources: HashSet<StateKey> = FRESH_FRAMEWORK_RESOURCES
+        .iter()
+        .map(|(module, name)| framework_resource(module, name))
+        .collect();
+    let mut fresh_accounts: HashSet<AccountAddress> = validators
+        .iter()
+        .flat_map(|validator| {
+            [
+                validator.owner_address,
+                validator.operator_address,
+                validator.voter_address,
+            ]
+        })
+        .collect();
+    fresh_accounts.insert(aptos_test_root_address());
+
+    let is_forked = |state_key: &StateKey| match state_key {
+        StateKey::AccessPath(access_path) => {
+            !fresh_accounts.contains(&access_path.address) && !fresh_resources.contains(state_key)
+        }
+        _ => true,
+    };
+    add_extra_state(
+        genesis,
+        forked_state
+            .iter()
+            .filter(|(state_key, _)| is_forked(state_key)),
+    )
+}
+
+/// Fails if a resource that stays that of the fresh genesis, or a struct in one of its fields,
+/// has another layout in the forked framework than in the fresh one
+fn check_fresh_resource_layouts(
+    fresh_write_set: &WriteSet,
+    forked_state: &[(StateKey, Vec<u8>)],
+) -> anyhow::Result<()> {
+    let mut fresh_framework = Framework::new(|state_key| match fresh_write_set.get(state_key) {
+        Some(WriteOp::Creation(value) | WriteOp::Modification(value)) => Some(value.as_slice()),
+        _ => None,
+    });
+    let forked_state = state_values(forked_state);
+    let mut forked_framework = Framework::new(|state_key| forked_state.get(state_key).copied());
+
+    let mut structs: Vec<(String, String)> = FRESH_FRAMEWORK_RESOURCES
+        .iter()
+        .map(|(module, name)| (module.to_string(), name.to_string()))
+        .collect();
+    let mut checked = HashSet::new();
+    while let Some((module, name)) = structs.pop() {
+        if !checked.insert((module.clone(), name.clone())) {
+            continue;
+        }
+        // The fresh module stays if the forked state doesn't have it
+        if !forked_framework.has_module(&module)? {
+            continue;
+        }
+        let forked_struct = forked_framework
+            .struct_def(&module, &name)?
+            .ok_or_else(|| {
+                anyhow!(
+                    "The forked framework has no {}::{}, which the fresh genesis writes",
+                    module,
+                    name
+                )
+            })?;
+        ensure!(
+            fresh_framework.struct_def(&module, &name)?.as_ref() == Some(&forked_struct),
+            "{}::{} has another layout in the forked framework than in this one, so the forked \
+            framework can't read the resources of the new network",
+            module,
+            name
+        );
+        for field in &forked_struct.fields {
+            add_framework_structs(&field.type_, &mut structs);
+        }
+    }
+    Ok(())
+}
+
+/// Adds the framework structs `type_` is made of to `structs`, as `(module, name)`
+fn add_framework_structs(type_: &Type, structs: &mut Vec<(String, String)>) {
+    match type_ {
+        Type::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => {
+            if *address == CORE_CODE_ADDRESS {
+                structs.push((module.to_string(), name.to_string()));
+            }
+            for type_argument in type_arguments {
+                add_framework_structs(type_argument, structs);
+            }
+        }
+        Type::Vector(element) => add_framework_structs(element, structs),
+        _ => {}
+    }
+}
+
+/// The framework modules of a state, deserialized as they're looked up
+struct Framework<'a, F: Fn(&StateKey) -> Option<&'a [u8]>> {
+    state_value: F,
+    modules: HashMap<String, Option<Module>>,
+}
+
+impl<'a, F: Fn(&StateKey) -> Option<&'a [u8]>> Framework<'a, F> {
+    fn new(state_value: F) -> Self {
+        Self {
+            state_value,
+            modules: HashMap::new(),
+        }
+    }
+
+    fn module(&mut self, module: &str) -> anyhow::Result<Option<&Module>> {
+        if !self.modules.contains_key(module) {
+            let normalized_module = match (self.state_value)(&framework_module(module)) {
+                Some(code) => Some(Module::new(&CompiledModule::deserialize(code).map_err(
+                    |error| anyhow!("Failed to deserialize the module {}: {:?}", module, error),
+                )?)),
+                None => None,
+            };
+            self.modules.insert(module.to_string(), normalized_module);
+        }
+        Ok(self.modules[module].as_ref())
+    }
+
+    fn has_module(&mut self, module: &str) -> anyhow::Result<bool> {
+        Ok(self.module(module)?.is_some())
+    }
+
+    fn struct_def(&mut self, module: &str, name: &str) -> anyhow::Result<Option<Struct>> {
+        let name = Identifier::new(name)?;
+        Ok(self
+            .module(module)?
+            .and_then(|normalized_module| normalized_module.structs.get(&name))
+            .cloned())
+    }
+}
+
+fn state_values(state: &[(StateKey, Vec<u8>)]) -> HashMap<&StateKey, &[u8]> {
+    state
+        .iter()
+        .map(|(state_key, value)| (state_key, value.as_slice()))
+        .collect()
+}
+
+fn framework_module(module: &str) -> StateKey {
+    let module_id = ModuleId::new(
+        CORE_CODE_ADDRESS,
+        Identifier::new(module).expect("Module name must be valid"),
+    );
+    StateKey::AccessPath(AccessPath::code_access_path(module_id))
+}
+
+fn framework_resource(module: &str, name: &str) -> StateKey {
+    let struct_tag = StructTag {
+        address: CORE_CODE_ADDRESS,
+        module: Identifier::new(module).expect("Module name must be valid"),
+        name: Identifier::new(name).expect("Resource name must be valid"),
+        type_params: vec![],
+    };
+    StateKey::AccessPath(AccessPath::new(
+        CORE_CODE_ADDRESS,
+        AccessPath::resource_access_vec(struct_tag),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::{access::ModuleAccess, file_format::StructFieldInformation};
+
+    fn write_set(genesis: &Transaction) -> &WriteSet {
+        match genesis {
+            Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set)) => {
+                change_set.write_set()
+            }
+            _ => panic!("Genesis transaction must be a direct write set"),
+        }
+    }
+
+    /// The code of a module published by the fresh genesis
+    fn fresh_module(write_set: &WriteSet, module: &str) -> (StateKey, Vec<u8>) {
+        let state_key = framework_module(module);
+        match write_set.get(&state_key) {
+            Some(WriteOp::Creation(code) | WriteOp::Modification(code)) => {
+                (state_key, code.clone())
+            }
+            _ => panic!("The fresh genesis must publish {}", module),
+        }
+    }
+
+    /// The code of a module, with the first two fields of the struct `name` swapped
+    fn swap_fields(code: &[u8], name: &str) -> Vec<u8> {
+        let mut module = CompiledModule::deserialize(code).unwrap();
+        let index = module
+            .struct_defs()
+            .iter()
+            .position(|struct_def| {
+                module
+                    .identifier_at(module.struct_handle_at(struct_def.struct_handle).name)
+                    .as_str()
+                    == name
+            })
+            .unwrap();
+        match &mut module.struct_defs[index].field_information {
+            StructFieldInformation::Declared(fields) => fields.swap(0, 1),
+            StructFieldInformation::Native => panic!("{} must have fields", name),
+        }
+        let mut code = vec![];
+        module.serialize(&mut code).unwrap();
+        code
+    }
+
+    #[test]
+    fn test_add_forked_state() {
+        let (change_set, test_validators) =
+            vm_genesis::test_genesis_change_set_and_validators(Some(1));
+        let validators: Vec<Validator> = test_validators
+            .into_iter()
+            .map(|validator| validator.data)
+            .collect();
+        let genesis = Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set));
+        let fresh_chain_id = framework_resource("chain_id", "ChainId");
+        let fresh_validator_set = framework_resource("stake", "ValidatorSet");
+        let fresh_write_set = write_set(&genesis).clone();
+
+        let forked_account = StateKey::AccessPath(AccessPath::new(
+            AccountAddress::random(),
+            b"forked resource".to_vec(),
+        ));
+        let forked_framework_resource = framework_resource("timestamp", "CurrentTimeMicroseconds");
+        let validator_resource = StateKey::AccessPath(AccessPath::new(
+            validators[0].owner_address,
+            b"forked resource".to_vec(),
+        ));
+        let forked_state = vec![
+            (forked_account.clone(), vec![1]),
+            (forked_framework_resource.clone(), vec![2]),
+            (fresh_chain_id.clone(), vec![3]),
+            (fresh_validator_set.clone(), vec![4]),
+            (validator_resource.clone(), vec![5]),
+        ];
+        let genesis = add_forked_state(genesis, &forked_state, &validators).unwrap();
+        let write_set = write_set(&genesis);
+
+        // The forked state is written over the fresh genesis
+        assert_eq!(
+            write_set.get(&forked_account),
+            Some(&WriteOp::Creation(vec![1]))
+        );
+        assert_eq!(
+            write_set.get(&forked_framework_resource),
+            Some(&WriteOp::Creation(vec![2]))
+        );
+        // Except for the resources of the new network
+        assert_eq!(
+            write_set.get(&fresh_chain_id),
+            fresh_write_set.get(&fresh_chain_id)
+        );
+        assert_eq!(
+            write_set.get(&fresh_validator_set),
+            fresh_write_set.get(&fresh_validator_set)
+        );
+        assert_eq!(write_set.get(&validator_resource), None);
+    }
+
+    #[test]
+    fn test_forked_minimum_stake() {
+        let (change_set, test_validators) =
+            vm_genesis::test_genesis_change_set_and_validators(Some(1));
+        let mut validators: Vec<Validator> = test_validators
+            .into_iter()
+            .map(|validator| validator.data)
+            .collect();
+        let genesis = Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set));
+        let minimum_stake = validators[0].stake_amount + 1;
+        let staking_config = (
+            framework_resource("staking_config", "StakingConfig"),
+            bcs::to_bytes(&(
+                minimum_stake,
+                u64::MAX,
+                86400u64,
+                false,
+                10u64,
+                100u64,
+                50u64,
+            ))
+            .unwrap(),
+        );
+        let forked_state = vec![staking_config];
+        assert_eq!(
+            forked_minimum_stake(&forked_state).unwrap(),
+            Some(minimum_stake)
+        );
+        assert_eq!(forked_minimum_stake(&[]).unwrap(), None);
+
+        // The new validators must stake at least the forked minimum
+        add_forked_state(genesis.clone(), &forked_state, &validators).unwrap_err();
+        validators[0].stake_amount = minimum_stake;
+        add_forked_state(genesis, &forked_state, &validators).unwrap();
+    }
+
+    #[test]
+    fn test_forked_minimum_stake_layouts() {
+        let (change_set, _) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+        let (module_key, code) = fresh_module(change_set.write_set(), "staking_config");
+        let staking_config = framework_resource("staking_config", "StakingConfig");
+
+        // The fields after the minimum stake may differ from those of this framework
+        let forked_state = vec![
+            (module_key.clone(), code.clone()),
+            (
+                staking_config.clone(),
+                bcs::to_bytes(&(
+                    100u64,
+                    u64::MAX,
+                    86400u64,
+                    false,
+                    10u64,
+                    100u64,
+                    50u64,
+                    vec![1u8, 2, 3],
+                ))
+                .unwrap(),
+            ),
+        ];
+        assert_eq!(forked_minimum_stake(&forked_state).unwrap(), Some(100));
+
+        // But the minimum stake must come first
+        let forked_state = vec![
+            (module_key, swap_fields(&code, "StakingConfig")),
+            (staking_config, bcs::to_bytes(&(u64::MAX, 100u64)).unwrap()),
+        ];
+        let error = forked_minimum_stake(&forked_state).unwrap_err();
+        assert!(error.to_string().contains("minimum_stake"), "{}", error);
+    }
+
+    #[test]
+    fn test_forked_framework_layouts() {
+        let (change_set, test_validators) =
+            vm_genesis::test_genesis_change_set_and_validators(Some(1));
+        let validators: Vec<Validator> = test_validators
+            .into_iter()
+            .map(|validator| validator.data)
+            .collect();
+        let genesis = Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set));
+        let (module_key, code) = fresh_module(write_set(&genesis), "stake");
+
+        // The forked framework may be the same as the fresh one
+        let forked_state = vec![(module_key.clone(), code.clone())];
+        add_forked_state(genesis.clone(), &forked_state, &validators).unwrap();
+
+        // But it can't read a fresh validator set of another layout, down to its validators
+        for name in ["ValidatorSet", "ValidatorInfo"] {
+            let forked_state = vec![(module_key.clone(), swap_fields(&code, name))];
+            let error = add_forked_state(genesis.clone(), &forked_state, &validators).unwrap_err();
+            assert!(
+                error.to_string().contains(&format!("stake::{}", name)),
+                "{}",
+                error
+            );
+        }
+    }
+}
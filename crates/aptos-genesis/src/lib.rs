@@ -5,6 +5,7 @@
 
 pub mod builder;
 pub mod config;
+pub mod fork;
 pub mod keys;
 pub mod mainnet;
 
@@ -12,13 +13,21 @@ pub mod mainnet;
 pub mod test_utils;
 
 use crate::{builder::GenesisConfiguration, config::ValidatorConfiguration};
+use anyhow::{anyhow, bail};
 use aptos_config::config::{
     RocksdbConfigs, BUFFERED_STATE_TARGET_ITEMS, DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
     NO_OP_STORAGE_PRUNER_CONFIG,
 };
 use aptos_crypto::ed25519::Ed25519PublicKey;
+use aptos_gas::LATEST_GAS_FEATURE_VERSION;
 use aptos_temppath::TempPath;
-use aptos_types::{chain_id::ChainId, transaction::Transaction, waypoint::Waypoint};
+use aptos_types::{
+    chain_id::ChainId,
+    state_store::state_key::StateKey,
+    transaction::{ChangeSet, Transaction, WriteSetPayload},
+    waypoint::Waypoint,
+    write_set::WriteOp,
+};
 use aptos_vm::AptosVM;
 use aptosdb::AptosDB;
 use framework::ReleaseBundle;
@@ -61,6 +70,12 @@ pub struct GenesisInfo {
     pub voting_duration_secs: u64,
     /// Percent of current epoch's total voting power that can be added in this epoch.
     pub voting_power_increase_limit: u64,
+    /// State to write in genesis on top of what the framework creates, e.g. accounts exported
+    /// from another network.  Overrides any framework state at the same keys.
+    pub extra_state: Vec<(StateKey, Vec<u8>)>,
+    /// State of an existing network to carry over into genesis, see [`fork`].  Written before
+    /// the extra state.
+    pub forked_state: Vec<(StateKey, Vec<u8>)>,
 }
 
 impl GenesisInfo {
@@ -94,20 +109,20 @@ impl GenesisInfo {
             rewards_apy_percentage: genesis_config.rewards_apy_percentage,
             voting_duration_secs: genesis_config.voting_duration_secs,
             voting_power_increase_limit: genesis_config.voting_power_increase_limit,
+            extra_state: Vec::new(),
+            forked_state: Vec::new(),
         })
     }
 
-    pub fn get_genesis(&mut self) -> &Transaction {
-        if let Some(ref genesis) = self.genesis {
-            genesis
-        } else {
-            self.genesis = Some(self.generate_genesis_txn());
-            self.genesis.as_ref().unwrap()
+    pub fn get_genesis(&mut self) -> anyhow::Result<&Transaction> {
+        if self.genesis.is_none() {
+            self.genesis = Some(self.generate_genesis_txn()?);
         }
+        Ok(self.genesis.as_ref().unwrap())
     }
 
-    fn generate_genesis_txn(&self) -> Transaction {
-        vm_genesis::encode_genesis_transaction(
+    fn generate_genesis_txn(&self) -> anyhow::Result<Transaction> {
+        let genesis = vm_genesis::encode_genesis_transaction(
             self.root_key.clone(),
             &self.validators,
             &self.framework,
@@ -127,11 +142,21 @@ impl GenesisInfo {
                 employee_vesting_start: 1663456089,
                 employee_vesting_period_duration: 5 * 60, // 5 minutes
             },
-        )
+        );
+        let genesis = if self.forked_state.is_empty() {
+            genesis
+        } else {
+            fork::add_forked_state(genesis, &self.forked_state, &self.validators)?
+        };
+        if self.extra_state.is_empty() {
+            Ok(genesis)
+        } else {
+            add_extra_state(genesis, &self.extra_state)
+        }
     }
 
     pub fn generate_waypoint(&mut self) -> anyhow::Result<Waypoint> {
-        let genesis = self.get_genesis();
+        let genesis = self.get_genesis()?;
         let path = TempPath::new();
         let aptosdb = AptosDB::open(
             &path,
@@ -146,3 +171,30 @@ impl GenesisInfo {
         executor::db_bootstrapper::generate_waypoint::<AptosVM>(&db_rw, genesis)
     }
 }
+
+/// Writes `extra_state` into the write set of a genesis transaction.  The state usually comes
+/// from a file, e.g. exported from another network, so it's checked rather than trusted.
+fn add_extra_state<'a>(
+    genesis: Transaction,
+    extra_state: impl IntoIterator<Item = &'a (StateKey, Vec<u8>)>,
+) -> anyhow::Result<Transaction> {
+    let change_set = match genesis {
+        Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set)) => change_set,
+        _ => bail!("Genesis transaction must be a direct write set"),
+    };
+    let (write_set, events) = change_set.into_inner();
+    let mut write_set = write_set.into_mut();
+    for (state_key, value) in extra_state {
+        write_set.insert((state_key.clone(), WriteOp::Creation(value.clone())));
+    }
+    let change_set = ChangeSet::new(write_set.freeze()?, events, LATEST_GAS_FEATURE_VERSION)
+        .map_err(|status| {
+            anyhow!(
+                "Extra genesis state must fit within storage limits: {:?}",
+                status
+            )
+        })?;
+    Ok(Transaction::GenesisTransaction(WriteSetPayload::Direct(
+        change_set,
+    )))
+}
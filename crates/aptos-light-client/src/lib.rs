@@ -0,0 +1,416 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Verifying the ledger of the Aptos blockchain as a light client, e.g. for bridges and wallets
+//! that talk to untrusted full nodes. The client starts from a trusted waypoint, usually that of
+//! the genesis, and is advanced through the epoch changes by the state proofs of the nodes. Once
+//! it trusts a ledger info, it verifies the inclusion proofs of the transactions and of the state
+//! values against it.
+//!
+//! The client only computes: it does no I/O, reads no clock and spawns no thread, so fetching the
+//! proofs and persisting the client, which is serializable, is up to the caller. It still builds
+//! on `aptos-types` and `aptos-crypto`, which need the standard library.
+
+use aptos_crypto::hash::CryptoHash;
+use aptos_types::{
+    account_address::AccountAddress,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{SparseMerkleProof, TransactionInfoWithProof},
+    state_proof::StateProof,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{TransactionWithProof, Version},
+    trusted_state::{TrustedState, TrustedStateChange},
+    waypoint::Waypoint,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Invalid proof: {0}")]
+    InvalidProof(String),
+    #[error("No ledger info is trusted yet, the client has to be updated with a state proof")]
+    NoTrustedLedgerInfo,
+    #[error("Version {version} is after the trusted version {trusted_version}")]
+    UntrustedVersion {
+        version: Version,
+        trusted_version: Version,
+    },
+    #[error(
+        "The transaction at version {0} isn't a state checkpoint, it doesn't commit to the state"
+    )]
+    NotAStateCheckpoint(Version),
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(error: anyhow::Error) -> Self {
+        Error::InvalidProof(format!("{:#}", error))
+    }
+}
+
+/// How an update moved the trusted state of the client
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Update {
+    /// The state proof is at the trusted version
+    NoChange,
+    /// The client trusts a newer version of the same epoch
+    Version(Version),
+    /// The client moved to a newer epoch, and trusts its validators
+    Epoch { epoch: u64, version: Version },
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LightClient {
+    trusted_state: TrustedState,
+    /// The latest ledger info verified, the proofs are verified against it. There's none until
+    /// the first update, as a waypoint is only a hash of a ledger info.
+    latest_ledger_info: Option<LedgerInfoWithSignatures>,
+}
+
+impl LightClient {
+    /// A client trusting the waypoint, which has to be that of an epoch change, e.g. the one of
+    /// the genesis
+    pub fn new(waypoint: Waypoint) -> Self {
+        Self {
+            trusted_state: TrustedState::from_epoch_waypoint(waypoint),
+            latest_ledger_info: None,
+        }
+    }
+
+    pub fn trusted_state(&self) -> &TrustedState {
+        &self.trusted_state
+    }
+
+    /// The latest version the client trusts
+    pub fn version(&self) -> Version {
+        self.trusted_state.version()
+    }
+
+    pub fn latest_ledger_info(&self) -> Option<&LedgerInfoWithSignatures> {
+        self.latest_ledger_info.as_ref()
+    }
+
+    /// Verifies the state proof of a node, and moves the trusted state through its epoch
+    /// changes to its latest ledger info. A proof that's behind the trusted version is rejected,
+    /// and the trusted state doesn't change if the proof doesn't verify.
+    pub fn update(&mut self, state_proof: &StateProof) -> Result<Update, Error> {
+        let latest_ledger_info = state_proof.latest_ledger_info_w_sigs();
+        let (new_state, verified_ledger_info, is_epoch_change) =
+            match self.trusted_state.verify_and_ratchet(state_proof)? {
+                TrustedStateChange::NoChange => {
+                    // The latest ledger info matches the trusted version, so it's trusted too
+                    if self.latest_ledger_info.is_none() {
+                        self.latest_ledger_info = Some(latest_ledger_info.clone());
+                    }
+                    return Ok(Update::NoChange);
+                }
+                TrustedStateChange::Version { new_state } => (new_state, latest_ledger_info, false),
+                TrustedStateChange::Epoch {
+                    new_state,
+                    latest_epoch_change_li,
+                } => {
+                    // The latest ledger info is only verified if it's in the epoch the proof
+                    // moves to, otherwise the client stops at the last epoch change
+                    if new_state.version() == latest_ledger_info.ledger_info().version() {
+                        (new_state, latest_ledger_info, true)
+                    } else {
+                        (new_state, latest_epoch_change_li, true)
+                    }
+                }
+            };
+
+        let ledger_info = verified_ledger_info.ledger_info();
+        let update = if is_epoch_change {
+            Update::Epoch {
+                epoch: ledger_info.next_block_epoch(),
+                version: ledger_info.version(),
+            }
+        } else {
+            Update::Version(ledger_info.version())
+        };
+        self.trusted_state = new_state;
+        self.latest_ledger_info = Some(verified_ledger_info.clone());
+        Ok(update)
+    }
+
+    /// Verifies that the transaction info is the one at the version of the trusted ledger
+    pub fn verify_transaction_info(
+        &self,
+        transaction_info_proof: &TransactionInfoWithProof,
+        version: Version,
+    ) -> Result<(), Error> {
+        let ledger_info = self.trusted_ledger_info(version)?;
+        transaction_info_proof.verify(ledger_info, version)?;
+        Ok(())
+    }
+
+    /// Verifies that the transaction of the sender with the sequence number is in the trusted
+    /// ledger, at the version of the proof, and so are its events if the proof has them
+    pub fn verify_user_transaction(
+        &self,
+        transaction_proof: &TransactionWithProof,
+        sender: AccountAddress,
+        sequence_number: u64,
+    ) -> Result<(), Error> {
+        let version = transaction_proof.version;
+        let ledger_info = self.trusted_ledger_info(version)?;
+        transaction_proof.verify_user_txn(ledger_info, version, sender, sequence_number)?;
+        Ok(())
+    }
+
+    /// Verifies the value of the state key at the version, or that it has none, e.g. the
+    /// resource of an account. The version has to be that of a state checkpoint, whose
+    /// transaction info commits to the root of the state.
+    pub fn verify_state_value(
+        &self,
+        state_key: &StateKey,
+        state_value: Option<&StateValue>,
+        version: Version,
+        transaction_info_proof: &TransactionInfoWithProof,
+        state_value_proof: &SparseMerkleProof,
+    ) -> Result<(), Error> {
+        self.verify_transaction_info(transaction_info_proof, version)?;
+        let state_root_hash = transaction_info_proof
+            .transaction_info()
+            .state_checkpoint_hash()
+            .ok_or(Error::NotAStateCheckpoint(version))?;
+        state_value_proof.verify(state_root_hash, state_key.hash(), state_value)?;
+        Ok(())
+    }
+
+    fn trusted_ledger_info(&self, version: Version) -> Result<&LedgerInfo, Error> {
+        let ledger_info = self
+            .latest_ledger_info
+            .as_ref()
+            .ok_or(Error::NoTrustedLedgerInfo)?
+            .ledger_info();
+        if version > ledger_info.version() {
+            return Err(Error::UntrustedVersion {
+                version,
+                trusted_version: ledger_info.version(),
+            });
+        }
+        Ok(ledger_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{hash::TransactionAccumulatorHasher, HashValue};
+    use aptos_types::{
+        aggregate_signature::{AggregateSignature, PartialSignatures},
+        block_info::BlockInfo,
+        epoch_change::EpochChangeProof,
+        epoch_state::EpochState,
+        proof::{
+            accumulator::InMemoryAccumulator, SparseMerkleLeafNode, TransactionAccumulatorProof,
+        },
+        transaction::{ExecutionStatus, TransactionInfo},
+        validator_signer::ValidatorSigner,
+        validator_verifier::random_validator_verifier,
+    };
+
+    fn ledger_info(
+        epoch: u64,
+        version: Version,
+        root_hash: HashValue,
+        next_epoch_state: Option<EpochState>,
+    ) -> LedgerInfo {
+        LedgerInfo::new(
+            BlockInfo::new(
+                epoch,
+                0,                 /* round */
+                HashValue::zero(), /* id */
+                root_hash,
+                version,
+                0, /* timestamp_usecs */
+                next_epoch_state,
+            ),
+            HashValue::zero(),
+        )
+    }
+
+    fn sign(
+        signers: &[ValidatorSigner],
+        epoch_state: &EpochState,
+        ledger_info: LedgerInfo,
+    ) -> LedgerInfoWithSignatures {
+        let signatures = PartialSignatures::new(
+            signers
+                .iter()
+                .map(|signer| (signer.author(), signer.sign(&ledger_info).unwrap()))
+                .collect(),
+        );
+        let signatures = epoch_state
+            .verifier
+            .aggregate_signatures(&signatures)
+            .unwrap();
+        LedgerInfoWithSignatures::new(ledger_info, signatures)
+    }
+
+    fn transaction_info(state_checkpoint_hash: Option<HashValue>) -> TransactionInfo {
+        TransactionInfo::new(
+            HashValue::random(),
+            HashValue::random(),
+            HashValue::random(),
+            state_checkpoint_hash,
+            0, /* gas_used */
+            ExecutionStatus::Success,
+        )
+    }
+
+    /// The genesis at version 0, which starts the epoch 1 of the signers, and a ledger info of
+    /// the epoch 1 at version 1, the state checkpoint of the single state value
+    struct Ledger {
+        waypoint: Waypoint,
+        state_proof: StateProof,
+        transaction_infos: [TransactionInfo; 2],
+        state_key: StateKey,
+        state_value: StateValue,
+    }
+
+    fn ledger() -> Ledger {
+        let (signers, verifier) = random_validator_verifier(3, None, true);
+        let epoch_state = EpochState { epoch: 1, verifier };
+
+        let state_key = StateKey::Raw(b"key".to_vec());
+        let state_value = StateValue::new(b"value".to_vec());
+        let state_root_hash =
+            SparseMerkleLeafNode::new(state_key.hash(), state_value.hash()).hash();
+        let transaction_infos = [
+            transaction_info(None),
+            transaction_info(Some(state_root_hash)),
+        ];
+        let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::from_leaves(&[
+            transaction_infos[0].hash(),
+            transaction_infos[1].hash(),
+        ]);
+
+        let genesis = ledger_info(0, 0, transaction_infos[0].hash(), Some(epoch_state.clone()));
+        let waypoint = Waypoint::new_epoch_boundary(&genesis).unwrap();
+        let genesis = LedgerInfoWithSignatures::new(genesis, AggregateSignature::empty());
+        let latest = sign(
+            &signers,
+            &epoch_state,
+            ledger_info(1, 1, accumulator.root_hash(), None),
+        );
+        Ledger {
+            waypoint,
+            state_proof: StateProof::new(latest, EpochChangeProof::new(vec![genesis], false)),
+            transaction_infos,
+            state_key,
+            state_value,
+        }
+    }
+
+    fn transaction_info_proof(ledger: &Ledger) -> TransactionInfoWithProof {
+        TransactionInfoWithProof::new(
+            TransactionAccumulatorProof::new(vec![ledger.transaction_infos[0].hash()]),
+            ledger.transaction_infos[1].clone(),
+        )
+    }
+
+    fn state_value_proof(ledger: &Ledger) -> SparseMerkleProof {
+        SparseMerkleProof::new(
+            Some(SparseMerkleLeafNode::new(
+                ledger.state_key.hash(),
+                ledger.state_value.hash(),
+            )),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_update() {
+        let ledger = ledger();
+        let mut client = LightClient::new(ledger.waypoint);
+        assert!(client.latest_ledger_info().is_none());
+
+        assert_eq!(
+            client.update(&ledger.state_proof).unwrap(),
+            Update::Epoch {
+                epoch: 1,
+                version: 1
+            }
+        );
+        assert_eq!(client.version(), 1);
+        assert_eq!(
+            client.latest_ledger_info(),
+            Some(ledger.state_proof.latest_ledger_info_w_sigs())
+        );
+        assert_eq!(
+            client.update(&ledger.state_proof).unwrap(),
+            Update::NoChange
+        );
+    }
+
+    #[test]
+    fn test_invalid_update() {
+        let ledger = ledger();
+        let (latest, _) = ledger.state_proof.clone().into_inner();
+
+        // Without the epoch change, the waypoint doesn't verify the validators
+        let mut client = LightClient::new(ledger.waypoint);
+        let state_proof = StateProof::new(latest.clone(), EpochChangeProof::new(vec![], false));
+        client.update(&state_proof).unwrap_err();
+
+        // Nor does a ledger info signed by other validators
+        let (signers, verifier) = random_validator_verifier(3, None, false);
+        let forged = sign(
+            &signers,
+            &EpochState { epoch: 1, verifier },
+            latest.ledger_info().clone(),
+        );
+        let (_, epoch_changes) = ledger.state_proof.clone().into_inner();
+        client
+            .update(&StateProof::new(forged, epoch_changes))
+            .unwrap_err();
+        assert_eq!(client, LightClient::new(ledger.waypoint));
+    }
+
+    #[test]
+    fn test_verify_state_value() {
+        let ledger = ledger();
+        let mut client = LightClient::new(ledger.waypoint);
+        let transaction_info_proof = transaction_info_proof(&ledger);
+        let state_value_proof = state_value_proof(&ledger);
+        let verify = |client: &LightClient, state_value: &StateValue, version| {
+            client.verify_state_value(
+                &ledger.state_key,
+                Some(state_value),
+                version,
+                &transaction_info_proof,
+                &state_value_proof,
+            )
+        };
+
+        assert!(matches!(
+            verify(&client, &ledger.state_value, 1),
+            Err(Error::NoTrustedLedgerInfo)
+        ));
+        client.update(&ledger.state_proof).unwrap();
+        verify(&client, &ledger.state_value, 1).unwrap();
+        client
+            .verify_transaction_info(&transaction_info_proof, 1)
+            .unwrap();
+
+        assert!(matches!(
+            verify(&client, &StateValue::new(b"other value".to_vec()), 1),
+            Err(Error::InvalidProof(_))
+        ));
+        assert!(matches!(
+            verify(&client, &ledger.state_value, 0),
+            Err(Error::InvalidProof(_))
+        ));
+        assert!(matches!(
+            verify(&client, &ledger.state_value, 2),
+            Err(Error::UntrustedVersion {
+                version: 2,
+                trusted_version: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! The heartbeats of the event loops of a node. Each loop beats at least every
+//! `HEARTBEAT_INTERVAL`, even when it's idle, so a loop whose last heartbeat is much older is
+//! stuck, and the liveness probe fails for the orchestrator to restart the node.
+
+use aptos_infallible::Mutex;
+use once_cell::sync::Lazy;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+/// How often an idle event loop beats
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+static HEARTBEATS: Lazy<Mutex<BTreeMap<&'static str, Instant>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Records that the event loop made progress. A loop is only watched once it first beats.
+pub fn heartbeat(event_loop: &'static str) {
+    HEARTBEATS.lock().insert(event_loop, Instant::now());
+}
+
+/// Stops watching the event loop until the returned guard is dropped, e.g. while it waits on
+/// work that may rightly outlast the stall limit. It beats again once the guard is dropped.
+#[must_use]
+pub fn suspend(event_loop: &'static str) -> Suspended {
+    HEARTBEATS.lock().remove(event_loop);
+    Suspended { event_loop }
+}
+
+/// Resumes watching the event loop when it's dropped, see `suspend`
+pub struct Suspended {
+    event_loop: &'static str,
+}
+
+impl Drop for Suspended {
+    fn drop(&mut self) {
+        heartbeat(self.event_loop);
+    }
+}
+
+/// How long ago each event loop last made progress
+pub fn last_heartbeats() -> BTreeMap<&'static str, Duration> {
+    HEARTBEATS
+        .lock()
+        .iter()
+        .map(|(event_loop, last_heartbeat)| (*event_loop, last_heartbeat.elapsed()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeats() {
+        assert!(last_heartbeats().is_empty());
+
+        heartbeat("mempool");
+        heartbeat("state_sync");
+        let last_heartbeats = last_heartbeats();
+        assert_eq!(
+            last_heartbeats.keys().collect::<Vec<_>>(),
+            vec![&"mempool", &"state_sync"]
+        );
+        assert!(last_heartbeats["mempool"] < HEARTBEAT_INTERVAL);
+
+        // A suspended loop isn't watched
+        let suspended = suspend("mempool");
+        assert_eq!(
+            super::last_heartbeats().keys().collect::<Vec<_>>(),
+            vec![&"state_sync"]
+        );
+        drop(suspended);
+        assert_eq!(super::last_heartbeats().len(), 2);
+    }
+}
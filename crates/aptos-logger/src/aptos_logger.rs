@@ -8,26 +8,30 @@ use crate::sample::SampleRate;
 use crate::telemetry_log_writer::{TelemetryLog, TelemetryLogWriter};
 use crate::{
     counters::{
-        PROCESSED_STRUCT_LOG_COUNT, SENT_STRUCT_LOG_BYTES, SENT_STRUCT_LOG_COUNT,
-        STRUCT_LOG_PARSE_ERROR_COUNT, STRUCT_LOG_QUEUE_ERROR_COUNT, STRUCT_LOG_SEND_ERROR_COUNT,
+        LIMITED_STRUCT_LOG_COUNT, PROCESSED_STRUCT_LOG_COUNT, SENT_STRUCT_LOG_BYTES,
+        SENT_STRUCT_LOG_COUNT, STRUCT_LOG_PARSE_ERROR_COUNT, STRUCT_LOG_QUEUE_ERROR_COUNT,
+        STRUCT_LOG_SEND_ERROR_COUNT,
     },
+    level_overrides::{LevelOverride, LevelOverrides},
+    limits::LogLimits,
     logger::Logger,
     sample,
+    sinks::LogSink,
     struct_log::TcpWriter,
     Event, Filter, Key, Level, LevelFilter, Metadata,
 };
-use aptos_infallible::RwLock;
+use aptos_infallible::{Mutex, RwLock};
 use backtrace::Backtrace;
 use chrono::{SecondsFormat, Utc};
 use futures::channel;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use std::fmt::Debug;
 use std::io::Stdout;
 use std::time::Duration;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, VecDeque},
     env, fmt,
     io::Write,
     str::FromStr,
@@ -41,6 +45,7 @@ const RUST_LOG: &str = "RUST_LOG";
 const RUST_LOG_REMOTE: &str = "RUST_LOG_REMOTE";
 pub const RUST_LOG_TELEMETRY: &str = "RUST_LOG_TELEMETRY";
 const RUST_LOG_FORMAT: &str = "RUST_LOG_FORMAT";
+pub const RUST_LOG_LIMITS: &str = "RUST_LOG_LIMITS";
 /// Default size of log write channel, if the channel is full, logs will be dropped
 pub const CHANNEL_SIZE: usize = 10000;
 const NUM_SEND_RETRIES: u8 = 1;
@@ -48,6 +53,9 @@ const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 const FILTER_REFRESH_INTERVAL: Duration =
     Duration::from_secs(5 /* minutes */ * 60 /* seconds */);
 
+/// The logger built as the global logger, so its levels can be changed at runtime
+static GLOBAL_APTOS_DATA: OnceCell<Arc<AptosData>> = OnceCell::new();
+
 #[derive(EnumString)]
 #[strum(serialize_all = "lowercase")]
 enum LogFormat {
@@ -104,7 +112,7 @@ impl Serialize for LogEntry {
 }
 
 impl LogEntry {
-    fn new(event: &Event, thread_name: Option<&str>, enable_backtrace: bool) -> Self {
+    pub(crate) fn new(event: &Event, thread_name: Option<&str>, enable_backtrace: bool) -> Self {
         use crate::{Value, Visitor};
 
         struct JsonVisitor<'a>(&'a mut BTreeMap<Key, serde_json::Value>);
@@ -220,6 +228,10 @@ pub struct AptosDataBuilder {
     is_async: bool,
     enable_telemetry_flush: bool,
     custom_format: Option<fn(&LogEntry) -> Result<String, fmt::Error>>,
+    sink_levels: Vec<Level>,
+    sinks: Vec<Box<dyn LogSink>>,
+    limits: Option<String>,
+    recent_logs_capacity: usize,
 }
 
 impl AptosDataBuilder {
@@ -238,6 +250,10 @@ impl AptosDataBuilder {
             is_async: false,
             enable_telemetry_flush: true,
             custom_format: None,
+            sink_levels: Vec::new(),
+            sinks: Vec::new(),
+            limits: None,
+            recent_logs_capacity: 0,
         }
     }
 
@@ -273,6 +289,13 @@ impl AptosDataBuilder {
         self
     }
 
+    /// Rate limits and sampling of logs per module, see [`crate::limits`]. They are overridden by
+    /// the `RUST_LOG_LIMITS` environment variable.
+    pub fn limits(&mut self, limits: String) -> &mut Self {
+        self.limits = Some(limits);
+        self
+    }
+
     pub fn channel_size(&mut self, channel_size: usize) -> &mut Self {
         self.channel_size = channel_size;
         self
@@ -283,6 +306,13 @@ impl AptosDataBuilder {
         self
     }
 
+    /// Adds a sink that logs of `level` and above are written to, only used by async logging
+    pub fn sink(&mut self, level: Level, sink: Box<dyn LogSink>) -> &mut Self {
+        self.sink_levels.push(level);
+        self.sinks.push(sink);
+        self
+    }
+
     pub fn console_port(&mut self, console_port: Option<u16>) -> &mut Self {
         self.console_port = console_port;
         self
@@ -296,6 +326,12 @@ impl AptosDataBuilder {
         self
     }
 
+    /// Keeps the last `capacity` lines of the local printer in memory, e.g. for crash reports
+    pub fn recent_logs(&mut self, capacity: usize) -> &mut Self {
+        self.recent_logs_capacity = capacity;
+        self
+    }
+
     pub fn is_async(&mut self, is_async: bool) -> &mut Self {
         self.is_async = is_async;
         self
@@ -363,15 +399,41 @@ impl AptosDataBuilder {
             filter_builder.build()
         };
 
+        let sink_filters = self
+            .sink_levels
+            .iter()
+            .map(|level| {
+                let mut filter_builder = Filter::builder();
+
+                if self.is_async {
+                    filter_builder.filter_level((*level).into());
+                } else {
+                    filter_builder.filter_level(LevelFilter::Off);
+                }
+
+                filter_builder.build()
+            })
+            .collect();
+
         FilterTuple {
             local_filter,
             remote_filter,
             telemetry_filter,
+            sink_filters,
+            level_overrides: LevelOverrides::default(),
+        }
+    }
+
+    fn build_limits(&self) -> LogLimits {
+        match env::var(RUST_LOG_LIMITS) {
+            Ok(limits) => LogLimits::parse(&limits),
+            Err(_) => LogLimits::parse(self.limits.as_deref().unwrap_or_default()),
         }
     }
 
     fn build_logger(&mut self) -> Arc<AptosData> {
         let filter = self.build_filter();
+        let limits = self.build_limits();
 
         if let Ok(log_format) = env::var(RUST_LOG_FORMAT) {
             let log_format = LogFormat::from_str(&log_format).unwrap();
@@ -393,8 +455,10 @@ impl AptosDataBuilder {
                 sender: Some(sender),
                 printer: None,
                 filter: RwLock::new(filter),
+                limits: RwLock::new(limits),
                 enable_telemetry_flush: self.enable_telemetry_flush,
                 formatter: self.custom_format.take().unwrap_or(text_format),
+                recent_logs: RecentLogs::new(self.recent_logs_capacity),
             });
             let service = LoggerService {
                 receiver,
@@ -402,6 +466,7 @@ impl AptosDataBuilder {
                 printer: self.printer.take(),
                 facade: logger.clone(),
                 remote_tx,
+                sinks: std::mem::take(&mut self.sinks),
             };
 
             thread::spawn(move || service.run());
@@ -412,8 +477,10 @@ impl AptosDataBuilder {
                 sender: None,
                 printer: self.printer.take(),
                 filter: RwLock::new(filter),
+                limits: RwLock::new(limits),
                 enable_telemetry_flush: self.enable_telemetry_flush,
                 formatter: self.custom_format.take().unwrap_or(text_format),
+                recent_logs: RecentLogs::new(self.recent_logs_capacity),
             })
         }
     }
@@ -428,6 +495,7 @@ impl AptosDataBuilder {
         };
 
         crate::logger::set_global_logger(logger.clone(), console_port);
+        let _ = GLOBAL_APTOS_DATA.set(logger.clone());
         logger
     }
 }
@@ -440,13 +508,31 @@ pub struct FilterTuple {
     remote_filter: Filter,
     /// The logging `Filter` to control what is sent to telemetry service
     telemetry_filter: Filter,
+    /// The `Filter`s to control what is written to each log sink
+    sink_filters: Vec<Filter>,
+    /// The levels set at runtime, which take precedence over the local and sink `Filter`s
+    level_overrides: LevelOverrides,
 }
 
 impl FilterTuple {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        self.local_filter.enabled(metadata)
+        self.local_enabled(metadata)
             || self.remote_filter.enabled(metadata)
             || self.telemetry_filter.enabled(metadata)
+            || self
+                .sink_filters
+                .iter()
+                .any(|filter| self.sink_enabled(filter, metadata))
+    }
+
+    fn local_enabled(&self, metadata: &Metadata) -> bool {
+        self.level_overrides
+            .enabled(metadata, || self.local_filter.enabled(metadata))
+    }
+
+    fn sink_enabled(&self, sink_filter: &Filter, metadata: &Metadata) -> bool {
+        self.level_overrides
+            .enabled(metadata, || sink_filter.enabled(metadata))
     }
 }
 
@@ -455,8 +541,39 @@ pub struct AptosData {
     sender: Option<sync::mpsc::SyncSender<LoggerServiceEvent>>,
     printer: Option<Box<dyn Writer>>,
     filter: RwLock<FilterTuple>,
+    limits: RwLock<LogLimits>,
     enable_telemetry_flush: bool,
     pub(crate) formatter: fn(&LogEntry) -> Result<String, fmt::Error>,
+    recent_logs: Option<RecentLogs>,
+}
+
+/// The last lines written to the local printer, oldest first
+struct RecentLogs {
+    capacity: usize,
+    // Not an infallible lock, its lines are read by the panic hook
+    lines: sync::Mutex<VecDeque<String>>,
+}
+
+impl RecentLogs {
+    fn new(capacity: usize) -> Option<Self> {
+        (capacity > 0).then(|| Self {
+            capacity,
+            lines: sync::Mutex::new(VecDeque::with_capacity(capacity)),
+        })
+    }
+
+    fn push(&self, line: String) {
+        let mut lines = self.lines.lock().unwrap_or_else(|error| error.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|error| error.into_inner());
+        lines.iter().cloned().collect()
+    }
 }
 
 impl AptosData {
@@ -481,8 +598,40 @@ impl AptosData {
             .build();
     }
 
-    pub fn set_filter(&self, filter_tuple: FilterTuple) {
-        *self.filter.write() = filter_tuple;
+    /// The logger built as the global logger, if any
+    pub fn global() -> Option<&'static Arc<AptosData>> {
+        GLOBAL_APTOS_DATA.get()
+    }
+
+    /// Replaces the filters, the levels set at runtime are kept
+    pub fn set_filter(&self, mut filter_tuple: FilterTuple) {
+        let mut filter = self.filter.write();
+        filter_tuple.level_overrides = std::mem::take(&mut filter.level_overrides);
+        *filter = filter_tuple;
+    }
+
+    /// The filter of the local printer, e.g. `info,consensus=debug`
+    pub fn local_filter(&self) -> String {
+        self.filter.read().local_filter.to_string()
+    }
+
+    /// Sets the level of a module (all modules if `module` isn't set) for the local printer and
+    /// the sinks, which reverts to their filters after `duration`
+    pub fn set_level_override(&self, module: Option<&str>, level: LevelFilter, duration: Duration) {
+        self.filter
+            .write()
+            .level_overrides
+            .set(module, level, duration);
+    }
+
+    /// Reverts the level of a module set at runtime, returns whether it was set
+    pub fn remove_level_override(&self, module: Option<&str>) -> bool {
+        self.filter.write().level_overrides.remove(module)
+    }
+
+    /// The levels set at runtime that didn't revert yet
+    pub fn level_overrides(&self) -> Vec<LevelOverride> {
+        self.filter.write().level_overrides.active()
     }
 
     pub fn set_local_filter(&self, filter: Filter) {
@@ -497,10 +646,26 @@ impl AptosData {
         self.filter.write().telemetry_filter = filter;
     }
 
+    pub fn set_limits(&self, limits: LogLimits) {
+        *self.limits.write() = limits;
+    }
+
+    /// The last lines of the local printer, oldest first, if the builder kept any
+    pub fn recent_logs(&self) -> Vec<String> {
+        self.recent_logs
+            .as_ref()
+            .map_or_else(Vec::new, RecentLogs::lines)
+    }
+
     fn send_entry(&self, entry: LogEntry) {
-        if let Some(printer) = &self.printer {
+        if self.printer.is_some() || self.recent_logs.is_some() {
             let s = (self.formatter)(&entry).expect("Unable to format");
-            printer.write(s);
+            if let Some(recent_logs) = &self.recent_logs {
+                recent_logs.push(s.clone());
+            }
+            if let Some(printer) = &self.printer {
+                printer.write(s);
+            }
         }
 
         if let Some(sender) = &self.sender {
@@ -520,11 +685,34 @@ impl Logger for AptosData {
     }
 
     fn record(&self, event: &Event) {
-        let entry = LogEntry::new(
+        let limits = self.limits.read();
+        let limit = limits.limit(event.metadata());
+        // Sample before creating the entry, so dropped logs aren't formatted
+        if !limit.map_or(true, |limit| limit.sample()) {
+            LIMITED_STRUCT_LOG_COUNT.inc();
+            return;
+        }
+
+        let mut entry = LogEntry::new(
             event,
             ::std::thread::current().name(),
             self.enable_backtrace,
         );
+        if let Some(limit) = limit {
+            match limit.admit(entry.metadata.source_path(), entry.message.as_deref()) {
+                Some(0) => {}
+                Some(suppressed) => {
+                    entry
+                        .data
+                        .insert(Key::new("suppressed_identical_logs"), suppressed.into());
+                }
+                None => {
+                    LIMITED_STRUCT_LOG_COUNT.inc();
+                    return;
+                }
+            }
+        }
+        drop(limits);
 
         self.send_entry(entry)
     }
@@ -560,6 +748,7 @@ struct LoggerService {
     printer: Option<Box<dyn Writer>>,
     facade: Arc<AptosData>,
     remote_tx: Option<channel::mpsc::Sender<TelemetryLog>>,
+    sinks: Vec<Box<dyn LogSink>>,
 }
 
 impl LoggerService {
@@ -572,15 +761,14 @@ impl LoggerService {
                 LoggerServiceEvent::LogEntry(entry) => {
                     PROCESSED_STRUCT_LOG_COUNT.inc();
 
-                    if let Some(printer) = &mut self.printer {
-                        if self
-                            .facade
-                            .filter
-                            .read()
-                            .local_filter
-                            .enabled(&entry.metadata)
-                        {
-                            let s = (self.facade.formatter)(&entry).expect("Unable to format");
+                    if (self.printer.is_some() || self.facade.recent_logs.is_some())
+                        && self.facade.filter.read().local_enabled(&entry.metadata)
+                    {
+                        let s = (self.facade.formatter)(&entry).expect("Unable to format");
+                        if let Some(recent_logs) = &self.facade.recent_logs {
+                            recent_logs.push(s.clone());
+                        }
+                        if let Some(printer) = &mut self.printer {
                             printer.write_buferred(s);
                         }
                     }
@@ -609,9 +797,16 @@ impl LoggerService {
                             let _ = writer.write(s);
                         }
                     }
+
+                    let filter = self.facade.filter.read();
+                    for (sink, sink_filter) in self.sinks.iter_mut().zip(&filter.sink_filters) {
+                        if filter.sink_enabled(sink_filter, &entry.metadata) {
+                            sink.write(&entry);
+                        }
+                    }
                 }
                 LoggerServiceEvent::Flush(sender) => {
-                    // Flush is only done on TelemetryLogWriter
+                    // Flush is only done on TelemetryLogWriter and the sinks
                     if let Some(writer) = &mut telemetry_writer {
                         if self.facade.enable_telemetry_flush {
                             match writer.flush() {
@@ -632,6 +827,9 @@ impl LoggerService {
                             }
                         }
                     }
+                    for sink in &mut self.sinks {
+                        sink.flush();
+                    }
                     let _ = sender.send(());
                 }
             }
@@ -740,7 +938,7 @@ impl Writer for FileWriter {
 /// UNIX_TIMESTAMP LOG_LEVEL [thread_name] FILE:LINE MESSAGE JSON_DATA
 /// Example:
 /// 2020-03-07 05:03:03 INFO [thread_name] common/aptos-logger/src/lib.rs:261 Hello { "world": true }
-fn text_format(entry: &LogEntry) -> Result<String, fmt::Error> {
+pub(crate) fn text_format(entry: &LogEntry) -> Result<String, fmt::Error> {
     use std::fmt::Write;
 
     let mut w = String::new();
@@ -769,7 +967,7 @@ fn text_format(entry: &LogEntry) -> Result<String, fmt::Error> {
 }
 
 // converts a record into json format
-fn json_format(entry: &LogEntry) -> Result<String, fmt::Error> {
+pub(crate) fn json_format(entry: &LogEntry) -> Result<String, fmt::Error> {
     match serde_json::to_string(&entry) {
         Ok(s) => Ok(s),
         Err(_) => {
@@ -785,14 +983,22 @@ fn json_format(entry: &LogEntry) -> Result<String, fmt::Error> {
 /// environment variables such as `RUST_LOG_TELEMETRY`.
 pub struct LoggerFilterUpdater {
     logger: Arc<AptosData>,
-    logger_builder: AptosDataBuilder,
+    logger_builder: Arc<Mutex<AptosDataBuilder>>,
 }
 
 impl LoggerFilterUpdater {
     pub fn new(logger: Arc<AptosData>, logger_builder: AptosDataBuilder) -> Self {
         Self {
             logger,
-            logger_builder,
+            logger_builder: Arc::new(Mutex::new(logger_builder)),
+        }
+    }
+
+    /// Returns a handle to change the levels and the limits the filter is rebuilt from
+    pub fn reload_handle(&self) -> LoggerReloadHandle {
+        LoggerReloadHandle {
+            logger: self.logger.clone(),
+            logger_builder: self.logger_builder.clone(),
         }
     }
 
@@ -806,9 +1012,37 @@ impl LoggerFilterUpdater {
     }
 
     fn update_filter(&self) {
-        // TODO: check for change to env var before rebuilding filter.
-        let filter = self.logger_builder.build_filter();
-        self.logger.set_filter(filter);
+        update_filter(&self.logger, &self.logger_builder.lock());
+    }
+}
+
+/// Changes the levels and the limits of a running logger, e.g. when the node config is reloaded.
+/// The environment variables still take precedence over them.
+#[derive(Clone)]
+pub struct LoggerReloadHandle {
+    logger: Arc<AptosData>,
+    logger_builder: Arc<Mutex<AptosDataBuilder>>,
+}
+
+impl LoggerReloadHandle {
+    pub fn reload(&self, level: Level, telemetry_level: Level, limits: Option<String>) {
+        let mut logger_builder = self.logger_builder.lock();
+        logger_builder.level = level;
+        logger_builder.telemetry_level = telemetry_level;
+        logger_builder.limits = limits;
+        update_filter(&self.logger, &logger_builder);
+    }
+}
+
+fn update_filter(logger: &AptosData, logger_builder: &AptosDataBuilder) {
+    // TODO: check for change to env var before rebuilding filter.
+    let filter = logger_builder.build_filter();
+    logger.set_filter(filter);
+
+    // Only replace the limits if they changed, so they keep counting the logs
+    let limits = logger_builder.build_limits();
+    if limits.directives() != logger.limits.read().directives() {
+        logger.set_limits(limits);
     }
 }
 
@@ -1112,4 +1346,91 @@ mod tests {
                 "source_path"
             )));
     }
+
+    #[test]
+    fn test_logger_limits_updater() {
+        let mut logger_builder = AptosDataBuilder::new();
+        let logger = logger_builder
+            .is_async(true)
+            .limits("module_path=1/60s".into())
+            .build_logger();
+        let metadata = &Metadata::new(Level::Info, "target", "module_path", "source_path");
+        assert!(logger.limits.read().limit(metadata).is_some());
+
+        let updater = LoggerFilterUpdater::new(logger.clone(), logger_builder);
+        updater
+            .reload_handle()
+            .reload(Level::Info, Level::Warn, Some("other_module=1%".into()));
+
+        assert_eq!(logger.limits.read().directives(), "other_module=1%");
+        assert!(logger.limits.read().limit(metadata).is_none());
+    }
+
+    #[test]
+    fn test_logger_level_overrides_updater() {
+        let mut logger_builder = AptosDataBuilder::new();
+        let logger = logger_builder
+            .is_async(true)
+            .level(Level::Info)
+            .build_logger();
+        let metadata = &Metadata::new(Level::Debug, "target", "module_path", "source_path");
+        assert!(!logger.enabled(metadata));
+
+        logger.set_level_override(
+            Some("module_path"),
+            LevelFilter::Debug,
+            std::time::Duration::from_secs(60),
+        );
+        assert!(logger.enabled(metadata));
+
+        // The levels set at runtime outlive the updates of the filter
+        let updater = LoggerFilterUpdater::new(logger.clone(), logger_builder);
+        updater.update_filter();
+        assert!(logger.enabled(metadata));
+        assert_eq!(logger.level_overrides().len(), 1);
+
+        assert!(logger.remove_level_override(Some("module_path")));
+        assert!(!logger.enabled(metadata));
+    }
+
+    #[test]
+    fn test_logger_reload_handle() {
+        let mut logger_builder = AptosDataBuilder::new();
+        let logger = logger_builder
+            .is_async(true)
+            .level(Level::Info)
+            .build_logger();
+        let metadata = &Metadata::new(Level::Debug, "target", "module_path", "source_path");
+        assert!(!logger.enabled(metadata));
+
+        let updater = LoggerFilterUpdater::new(logger.clone(), logger_builder);
+        let reload_handle = updater.reload_handle();
+        reload_handle.reload(Level::Debug, Level::Error, None);
+        assert!(logger.enabled(metadata));
+
+        // The reloaded level outlives the periodic updates of the filter
+        updater.update_filter();
+        assert!(logger.enabled(metadata));
+    }
+
+    #[test]
+    fn test_recent_logs() {
+        let logger = AptosDataBuilder::new()
+            .is_async(false)
+            .recent_logs(2)
+            .build_logger();
+        let metadata = Metadata::new(Level::Info, "target", "module_path", "source_path");
+        for i in 0..3 {
+            logger.record(&Event::new(
+                &metadata,
+                Some(format_args!("message {}", i)),
+                &[],
+            ));
+        }
+
+        let recent_logs = logger.recent_logs();
+        assert_eq!(recent_logs.len(), 2);
+        assert!(recent_logs[0].contains("message 1"));
+        assert!(recent_logs[1].contains("message 2"));
+    }
 }
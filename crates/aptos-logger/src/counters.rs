@@ -3,7 +3,7 @@
 
 //! Logging metrics for determining quality of log submission
 use once_cell::sync::Lazy;
-use prometheus::{register_int_counter, IntCounter};
+use prometheus::{register_int_counter, register_int_counter_vec, IntCounter, IntCounterVec};
 
 /// Count of the struct logs submitted by macro
 pub static STRUCT_LOG_COUNT: Lazy<IntCounter> = Lazy::new(|| {
@@ -19,6 +19,15 @@ pub static PROCESSED_STRUCT_LOG_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of struct logs dropped by the rate limits and sampling of their module
+pub static LIMITED_STRUCT_LOG_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_struct_log_limited_count",
+        "Count of the struct logs dropped by log limits."
+    )
+    .unwrap()
+});
+
 /// Count of struct logs submitted through TCP
 pub static SENT_STRUCT_LOG_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -97,3 +106,23 @@ pub static APTOS_LOG_INGEST_WRITER_DISCONNECTED: Lazy<IntCounter> = Lazy::new(||
     )
     .unwrap()
 });
+
+/// Count of errors writing logs to sinks, by sink
+pub static LOG_SINK_ERROR_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_log_sink_error_count",
+        "Count of all errors during writing logs to log sinks.",
+        &["sink"]
+    )
+    .unwrap()
+});
+
+/// Count of logs dropped by sinks that can't keep up, by sink
+pub static LOG_SINK_DROPPED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_log_sink_dropped_count",
+        "Count of the logs dropped by log sinks because their queue is full.",
+        &["sink"]
+    )
+    .unwrap()
+});
@@ -4,7 +4,7 @@
 //! Filtering definitions for controlling what modules and levels are logged
 
 use crate::{Level, Metadata};
-use std::{env, str::FromStr};
+use std::{env, fmt, str::FromStr};
 
 pub struct FilterParseError;
 
@@ -40,6 +40,20 @@ impl FromStr for LevelFilter {
     }
 }
 
+impl fmt::Display for LevelFilter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self {
+            LevelFilter::Off => return fmt.pad("OFF"),
+            LevelFilter::Error => Level::Error,
+            LevelFilter::Warn => Level::Warn,
+            LevelFilter::Info => Level::Info,
+            LevelFilter::Debug => Level::Debug,
+            LevelFilter::Trace => Level::Trace,
+        };
+        fmt::Display::fmt(&level, fmt)
+    }
+}
+
 impl From<Level> for LevelFilter {
     fn from(level: Level) -> Self {
         match level {
@@ -145,6 +159,22 @@ impl Filter {
     }
 }
 
+/// Formats the directives the way they're parsed, e.g. `info,crate1::mod1=warn`
+impl fmt::Display for Filter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, directive) in self.directives.iter().enumerate() {
+            if i > 0 {
+                fmt.write_str(",")?;
+            }
+            match &directive.name {
+                Some(name) => write!(fmt, "{}={}", name, directive.level)?,
+                None => write!(fmt, "{}", directive.level)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 /// A `Filter` directive for which logs to keep based on a module `name` based filter
 #[derive(Debug)]
 struct Directive {
@@ -351,4 +381,15 @@ mod tests {
         assert_eq!(dirs[1].name.as_deref(), Some("crate2"));
         assert_eq!(dirs[1].level, LevelFilter::Debug);
     }
+
+    #[test]
+    fn display() {
+        let filter = Builder::new()
+            .parse("info,crate1::mod1=warn,crate2=off")
+            .build();
+        assert_eq!(filter.to_string(), "INFO,crate2=OFF,crate1::mod1=WARN");
+        // The displayed filter parses back to the same filter
+        let filter = Builder::new().parse(&filter.to_string()).build();
+        assert_eq!(filter.to_string(), "INFO,crate2=OFF,crate1::mod1=WARN");
+    }
 }
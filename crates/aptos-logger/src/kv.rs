@@ -30,8 +30,8 @@ impl Key {
         Self(Cow::Owned(s))
     }
 
-    pub fn as_str(&self) -> &'_ Self {
-        self.borrow()
+    pub fn as_str(&self) -> &str {
+        self.0.borrow()
     }
 }
 
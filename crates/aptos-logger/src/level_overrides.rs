@@ -0,0 +1,143 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Log levels of modules changed at runtime, e.g. while debugging a production issue. They take
+//! precedence over the local and sink filters, and revert by themselves once they expire.
+
+use crate::{LevelFilter, Metadata};
+use std::time::{Duration, Instant};
+
+/// The level of a module (all modules if `module` isn't set) until `expires_at`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelOverride {
+    pub module: Option<String>,
+    pub level: LevelFilter,
+    pub expires_at: Instant,
+}
+
+impl LevelOverride {
+    fn is_active(&self, now: Instant) -> bool {
+        now < self.expires_at
+    }
+
+    /// How long until the override reverts
+    pub fn remaining(&self) -> Duration {
+        self.expires_at.saturating_duration_since(Instant::now())
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct LevelOverrides {
+    /// Sorted by length of the module, like the directives of a `Filter`
+    overrides: Vec<LevelOverride>,
+}
+
+impl LevelOverrides {
+    /// The level of the most specific active override of the module of the log, if any
+    pub(crate) fn level(&self, metadata: &Metadata) -> Option<LevelFilter> {
+        if self.overrides.is_empty() {
+            return None;
+        }
+        let now = Instant::now();
+        self.overrides
+            .iter()
+            .rev()
+            .filter(|level_override| match &level_override.module {
+                Some(module) => metadata.module_path().starts_with(module.as_str()),
+                None => true,
+            })
+            .find(|level_override| level_override.is_active(now))
+            .map(|level_override| level_override.level)
+    }
+
+    /// Whether a log is enabled, by its override if there's one and by `filter` otherwise
+    pub(crate) fn enabled(&self, metadata: &Metadata, filter: impl FnOnce() -> bool) -> bool {
+        match self.level(metadata) {
+            Some(level) => LevelFilter::from(metadata.level()) <= level,
+            None => filter(),
+        }
+    }
+
+    /// Sets or replaces the override of the module
+    pub(crate) fn set(&mut self, module: Option<&str>, level: LevelFilter, duration: Duration) {
+        self.remove(module);
+        self.overrides.push(LevelOverride {
+            module: module.map(str::to_string),
+            level,
+            expires_at: Instant::now() + duration,
+        });
+        self.overrides
+            .sort_by_key(|level_override| level_override.module.as_ref().map_or(0, String::len));
+    }
+
+    /// Reverts the override of the module, returns whether there was one
+    pub(crate) fn remove(&mut self, module: Option<&str>) -> bool {
+        self.remove_expired();
+        let len = self.overrides.len();
+        self.overrides
+            .retain(|level_override| level_override.module.as_deref() != module);
+        self.overrides.len() != len
+    }
+
+    /// The overrides that didn't expire yet
+    pub(crate) fn active(&mut self) -> Vec<LevelOverride> {
+        self.remove_expired();
+        self.overrides.clone()
+    }
+
+    fn remove_expired(&mut self) {
+        let now = Instant::now();
+        self.overrides
+            .retain(|level_override| level_override.is_active(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Level;
+
+    fn metadata(level: Level, module_path: &'static str) -> Metadata {
+        Metadata::new(level, module_path, module_path, "file.rs:1")
+    }
+
+    #[test]
+    fn test_most_specific_override() {
+        let mut overrides = LevelOverrides::default();
+        let minute = Duration::from_secs(60);
+        overrides.set(Some("network::peer"), LevelFilter::Trace, minute);
+        overrides.set(None, LevelFilter::Warn, minute);
+        overrides.set(Some("network"), LevelFilter::Debug, minute);
+
+        let level = |module_path| overrides.level(&metadata(Level::Info, module_path));
+        assert_eq!(level("network::peer::conn"), Some(LevelFilter::Trace));
+        assert_eq!(level("network::rpc"), Some(LevelFilter::Debug));
+        assert_eq!(level("mempool"), Some(LevelFilter::Warn));
+
+        assert!(overrides.enabled(&metadata(Level::Debug, "network::rpc"), || false));
+        assert!(!overrides.enabled(&metadata(Level::Info, "mempool"), || true));
+
+        assert!(overrides.remove(None));
+        assert!(!overrides.remove(None));
+        assert!(overrides.enabled(&metadata(Level::Info, "mempool"), || true));
+        assert_eq!(overrides.active().len(), 2);
+    }
+
+    #[test]
+    fn test_expired_override() {
+        let mut overrides = LevelOverrides::default();
+        overrides.set(
+            Some("network"),
+            LevelFilter::Debug,
+            Duration::from_millis(50),
+        );
+        overrides.set(Some("mempool"), LevelFilter::Debug, Duration::from_secs(60));
+        assert_eq!(overrides.active().len(), 2);
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(overrides.level(&metadata(Level::Debug, "network")), None);
+        let active = overrides.active();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].module.as_deref(), Some("mempool"));
+    }
+}
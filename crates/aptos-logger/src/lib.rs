@@ -147,10 +147,13 @@ pub mod aptos_logger;
 mod event;
 mod filter;
 mod kv;
+mod level_overrides;
+pub mod limits;
 mod logger;
 mod macros;
 mod metadata;
 pub mod sample;
+pub mod sinks;
 pub mod telemetry_log_writer;
 pub mod tracing_adapter;
 
@@ -158,10 +161,12 @@ mod security;
 mod struct_log;
 
 pub use crate::aptos_logger::{
-    AptosData as Logger, AptosDataBuilder, LoggerFilterUpdater, Writer, CHANNEL_SIZE,
+    AptosData as Logger, AptosDataBuilder, LoggerFilterUpdater, LoggerReloadHandle, Writer,
+    CHANNEL_SIZE,
 };
 pub use event::Event;
 pub use filter::{Filter, LevelFilter};
+pub use level_overrides::LevelOverride;
 pub use logger::flush;
 pub use metadata::{Level, Metadata};
 
@@ -0,0 +1,296 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rate limiting and sampling of logs per module, so a noisy module can't flood the logs
+//!
+//! Limits are given as comma separated directives of the form `[module][@level]=limit`, where the
+//! limit is either `<count>/<window>` (at most `count` identical logs, i.e. from the same call site
+//! with the same message, per window e.g. `10/60s`, `100/5m`) or `<percent>%` (only keep this
+//! fraction of the logs). For example, `network@warn=10/60s,mempool@debug=1%` keeps at most 10
+//! identical warnings per minute from `network`, and 1% of the debug logs of `mempool`.
+//!
+//! The most specific directive matching a log applies: the one with the longest module, preferring
+//! one with a level.
+
+use crate::{
+    sample::{SampleRate, Sampling},
+    Level, Metadata,
+};
+use aptos_infallible::Mutex;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// Maximum number of distinct logs tracked per rate limit
+const MAX_TRACKED_LOGS: usize = 10_000;
+
+#[derive(Debug)]
+pub struct LimitParseError;
+
+/// The limits of the logs of the modules
+#[derive(Debug, Default)]
+pub struct LogLimits {
+    directives: String,
+    /// Sorted from the least to the most specific
+    limits: Vec<Directive>,
+}
+
+impl LogLimits {
+    /// Parses a directives string, invalid directives are ignored
+    pub fn parse(directives: &str) -> Self {
+        let mut limits: Vec<Directive> = directives
+            .split(',')
+            .filter(|directive| !directive.trim().is_empty())
+            .filter_map(|directive| match directive.parse() {
+                Ok(directive) => Some(directive),
+                Err(_) => {
+                    eprintln!("[Logging] Ignoring invalid log limit: {}", directive);
+                    None
+                }
+            })
+            .collect();
+        limits.sort_by_key(|directive| {
+            (
+                directive.module.as_ref().map_or(0, String::len),
+                directive.level.is_some(),
+            )
+        });
+        Self {
+            directives: directives.to_string(),
+            limits,
+        }
+    }
+
+    /// The directives the limits were parsed from
+    pub fn directives(&self) -> &str {
+        &self.directives
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    pub(crate) fn limit(&self, metadata: &Metadata) -> Option<&Limit> {
+        self.limits
+            .iter()
+            .rev()
+            .find(|directive| directive.matches(metadata))
+            .map(|directive| &directive.limit)
+    }
+}
+
+#[derive(Debug)]
+struct Directive {
+    module: Option<String>,
+    level: Option<Level>,
+    limit: Limit,
+}
+
+impl Directive {
+    fn matches(&self, metadata: &Metadata) -> bool {
+        self.module
+            .as_ref()
+            .map_or(true, |module| metadata.module_path().starts_with(module))
+            && self.level.map_or(true, |level| level == metadata.level())
+    }
+}
+
+impl FromStr for Directive {
+    type Err = LimitParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selector, limit) = s.split_once('=').ok_or(LimitParseError)?;
+        let (module, level) = match selector.trim().split_once('@') {
+            Some((module, level)) => (
+                module.trim(),
+                Some(level.trim().parse().map_err(|_| LimitParseError)?),
+            ),
+            None => (selector.trim(), None),
+        };
+        Ok(Self {
+            module: (!module.is_empty()).then(|| module.to_string()),
+            level,
+            limit: limit.trim().parse()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Limit {
+    /// At most `max` identical logs per `window`
+    Rate {
+        max: u64,
+        window: Duration,
+        windows: Mutex<HashMap<u64, Window>>,
+    },
+    Sample(Sampling),
+}
+
+#[derive(Debug)]
+pub(crate) struct Window {
+    start: Instant,
+    count: u64,
+    suppressed: u64,
+}
+
+impl Limit {
+    /// Whether a sampled log is kept, this is always true for rate limits
+    pub(crate) fn sample(&self) -> bool {
+        match self {
+            Limit::Rate { .. } => true,
+            Limit::Sample(sampling) => sampling.sample(),
+        }
+    }
+
+    /// Whether a rate limited log is kept, with the number of identical logs dropped in the
+    /// previous window if it starts a new one
+    pub(crate) fn admit(&self, source_path: &str, message: Option<&str>) -> Option<u64> {
+        let (max, window, windows) = match self {
+            Limit::Rate {
+                max,
+                window,
+                windows,
+            } => (*max, *window, windows),
+            Limit::Sample(_) => return Some(0),
+        };
+        let mut hasher = DefaultHasher::new();
+        (source_path, message).hash(&mut hasher);
+        let key = hasher.finish();
+
+        let now = Instant::now();
+        let mut windows = windows.lock();
+        if windows.len() >= MAX_TRACKED_LOGS && !windows.contains_key(&key) {
+            windows.retain(|_, tracked| now.duration_since(tracked.start) < window);
+            if windows.len() >= MAX_TRACKED_LOGS {
+                return Some(0);
+            }
+        }
+        let tracked = windows.entry(key).or_insert(Window {
+            start: now,
+            count: 0,
+            suppressed: 0,
+        });
+        if now.duration_since(tracked.start) >= window {
+            let suppressed = tracked.suppressed;
+            *tracked = Window {
+                start: now,
+                count: 1,
+                suppressed: 0,
+            };
+            Some(suppressed)
+        } else if tracked.count < max {
+            tracked.count += 1;
+            Some(0)
+        } else {
+            tracked.suppressed += 1;
+            None
+        }
+    }
+}
+
+impl FromStr for Limit {
+    type Err = LimitParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent: f64 = percent.trim().parse().map_err(|_| LimitParseError)?;
+            if !(percent > 0.0 && percent <= 100.0) {
+                return Err(LimitParseError);
+            }
+            let every = (100.0 / percent).round() as u64;
+            return Ok(Limit::Sample(Sampling::new(SampleRate::Frequency(every))));
+        }
+
+        let (max, window) = s.split_once('/').ok_or(LimitParseError)?;
+        let window = window.trim();
+        let (amount, unit_secs) = if let Some(secs) = window.strip_suffix('s') {
+            (secs, 1)
+        } else if let Some(mins) = window.strip_suffix('m') {
+            (mins, 60)
+        } else if let Some(hours) = window.strip_suffix('h') {
+            (hours, 60 * 60)
+        } else {
+            return Err(LimitParseError);
+        };
+        let amount: u64 = amount.parse().map_err(|_| LimitParseError)?;
+        if amount == 0 {
+            return Err(LimitParseError);
+        }
+        Ok(Limit::Rate {
+            max: max.trim().parse().map_err(|_| LimitParseError)?,
+            window: Duration::from_secs(amount * unit_secs),
+            windows: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(level: Level, module_path: &'static str) -> Metadata {
+        Metadata::new(level, module_path, module_path, "file.rs:1")
+    }
+
+    #[test]
+    fn test_parse() {
+        let limits = LogLimits::parse("network@warn=10/60s, mempool=1%,=100/1h,invalid,x@bad=1/1s");
+        assert_eq!(limits.limits.len(), 3);
+        assert_eq!(limits.limits[0].module, None);
+        assert!(matches!(
+            limits.limits[0].limit,
+            Limit::Rate { max: 100, window, .. } if window == Duration::from_secs(3600)
+        ));
+        assert_eq!(limits.limits[1].module.as_deref(), Some("mempool"));
+        assert_eq!(limits.limits[1].level, None);
+        assert!(matches!(limits.limits[1].limit, Limit::Sample(_)));
+        assert_eq!(limits.limits[2].module.as_deref(), Some("network"));
+        assert_eq!(limits.limits[2].level, Some(Level::Warn));
+
+        for limit in ["0%", "101%", "10", "10/", "10/0s", "10/5d", "x/5s"] {
+            assert!(limit.parse::<Limit>().is_err(), "{}", limit);
+        }
+    }
+
+    #[test]
+    fn test_most_specific_limit() {
+        let limits = LogLimits::parse("network=50%,network::peer=1/1s,network::peer@warn=2/1s");
+        let max = |metadata| match limits.limit(&metadata) {
+            Some(Limit::Rate { max, .. }) => Some(*max),
+            _ => None,
+        };
+        assert_eq!(max(metadata(Level::Warn, "network::peer::conn")), Some(2));
+        assert_eq!(max(metadata(Level::Info, "network::peer")), Some(1));
+        assert!(matches!(
+            limits.limit(&metadata(Level::Warn, "network::rpc")),
+            Some(Limit::Sample(_))
+        ));
+        assert!(limits.limit(&metadata(Level::Warn, "mempool")).is_none());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let limit: Limit = "2/1s".parse().unwrap();
+        assert_eq!(limit.admit("file.rs:1", Some("a")), Some(0));
+        assert_eq!(limit.admit("file.rs:1", Some("a")), Some(0));
+        assert_eq!(limit.admit("file.rs:1", Some("a")), None);
+        assert_eq!(limit.admit("file.rs:1", Some("a")), None);
+        // Different messages and call sites are limited separately
+        assert_eq!(limit.admit("file.rs:1", Some("b")), Some(0));
+        assert_eq!(limit.admit("file.rs:2", Some("a")), Some(0));
+
+        std::thread::sleep(Duration::from_millis(1100));
+        assert_eq!(limit.admit("file.rs:1", Some("a")), Some(2));
+        assert_eq!(limit.admit("file.rs:1", Some("a")), Some(0));
+    }
+
+    #[test]
+    fn test_sample() {
+        let limit: Limit = "25%".parse().unwrap();
+        let kept = (0..100).filter(|_| limit.sample()).count();
+        assert_eq!(kept, 25);
+    }
+}
@@ -23,6 +23,7 @@ pub enum SampleRate {
 }
 
 /// An internal struct that can be checked if a sample is ready for the `sample!` macro
+#[derive(Debug)]
 pub struct Sampling {
     rate: SampleRate,
     state: AtomicU64,
@@ -0,0 +1,269 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{default_level, LogSink, SinkFormat};
+use crate::{aptos_logger::LogEntry, counters::LOG_SINK_ERROR_COUNT, Level};
+use chrono::Utc;
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const SINK_LABEL: &str = "file";
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileSinkConfig {
+    /// The file logs are written to, rotated files are kept next to it
+    pub path: PathBuf,
+    #[serde(default = "default_level")]
+    pub level: Level,
+    #[serde(default = "default_format")]
+    pub format: SinkFormat,
+    /// Rotate the file before it grows larger than this
+    #[serde(default)]
+    pub max_size_bytes: Option<u64>,
+    /// Rotate the file once it's been written to for this long
+    #[serde(default)]
+    pub rotation_interval_secs: Option<u64>,
+    /// How many rotated files are kept, the oldest ones are deleted
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// Compress rotated files with gzip
+    #[serde(default)]
+    pub compress: bool,
+}
+
+fn default_format() -> SinkFormat {
+    SinkFormat::Json
+}
+
+fn default_max_files() -> usize {
+    10
+}
+
+/// Writes logs to a file, which is renamed to `<path>.<timestamp>` when it's rotated. Compressing
+/// the rotated files and deleting the old ones is done in the background.
+pub struct RotatingFileSink {
+    config: FileSinkConfig,
+    file: File,
+    size: u64,
+    opened_at: Instant,
+    cleanup: Option<JoinHandle<()>>,
+}
+
+impl RotatingFileSink {
+    pub fn new(config: FileSinkConfig) -> io::Result<Self> {
+        let file = open(&config.path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            config,
+            file,
+            size,
+            opened_at: Instant::now(),
+            cleanup: None,
+        })
+    }
+
+    fn should_rotate(&self, len: u64) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        let too_large = self
+            .config
+            .max_size_bytes
+            .map_or(false, |max_size| self.size + len > max_size);
+        let too_old = self
+            .config
+            .rotation_interval_secs
+            .map_or(false, |interval| {
+                self.opened_at.elapsed() >= Duration::from_secs(interval)
+            });
+        too_large || too_old
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Only one cleanup at a time, so they don't race on the same files
+        if let Some(cleanup) = self.cleanup.take() {
+            let _ = cleanup.join();
+        }
+
+        let rotated = rotated_path(&self.config.path);
+        fs::rename(&self.config.path, &rotated)?;
+        self.file = open(&self.config.path)?;
+        self.size = 0;
+        self.opened_at = Instant::now();
+
+        let path = self.config.path.clone();
+        let compress = self.config.compress;
+        let max_files = self.config.max_files;
+        self.cleanup = Some(thread::Builder::new().name("log-rotation".into()).spawn(
+            move || {
+                if compress {
+                    if let Err(err) = compress_file(&rotated) {
+                        LOG_SINK_ERROR_COUNT.with_label_values(&[SINK_LABEL]).inc();
+                        eprintln!("[Logging] Failed to compress {:?}: {}", rotated, err);
+                    }
+                }
+                if let Err(err) = remove_old_files(&path, max_files) {
+                    LOG_SINK_ERROR_COUNT.with_label_values(&[SINK_LABEL]).inc();
+                    eprintln!("[Logging] Failed to remove old logs of {:?}: {}", path, err);
+                }
+            },
+        )?);
+        Ok(())
+    }
+}
+
+impl LogSink for RotatingFileSink {
+    fn write(&mut self, entry: &LogEntry) {
+        let line = match self.config.format.format(entry) {
+            Ok(line) => line + "\n",
+            Err(_) => return,
+        };
+        if self.should_rotate(line.len() as u64) {
+            if let Err(err) = self.rotate() {
+                LOG_SINK_ERROR_COUNT.with_label_values(&[SINK_LABEL]).inc();
+                eprintln!(
+                    "[Logging] Failed to rotate log file {:?}: {}",
+                    self.config.path, err
+                );
+            }
+        }
+        match self.file.write_all(line.as_bytes()) {
+            Ok(()) => self.size += line.len() as u64,
+            Err(err) => {
+                LOG_SINK_ERROR_COUNT.with_label_values(&[SINK_LABEL]).inc();
+                eprintln!(
+                    "[Logging] Unable to write to log file {:?}: {}",
+                    self.config.path, err
+                );
+            }
+        }
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+fn file_name(path: &Path) -> OsString {
+    path.file_name().unwrap_or_default().to_os_string()
+}
+
+/// The timestamp sorts rotated files from the oldest to the newest
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = file_name(path);
+    name.push(format!(".{}", Utc::now().format("%Y%m%dT%H%M%S%.9fZ")));
+    path.with_file_name(name)
+}
+
+fn compress_file(path: &Path) -> io::Result<()> {
+    let mut compressed_name = file_name(path);
+    compressed_name.push(".gz");
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(
+        File::create(path.with_file_name(compressed_name))?,
+        Compression::default(),
+    );
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(path)
+}
+
+fn remove_old_files(path: &Path, max_files: usize) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.", file_name(path).to_string_lossy());
+    let mut rotated = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            rotated.push(entry.path());
+        }
+    }
+    rotated.sort();
+    let num_old = rotated.len().saturating_sub(max_files);
+    for old in &rotated[..num_old] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, Metadata};
+    use std::io::Read;
+
+    fn entry() -> LogEntry {
+        let metadata = Metadata::new(Level::Info, "target", "module_path", "source_path");
+        LogEntry::new(
+            &Event::new(&metadata, Some(format_args!("Rotate me")), &[]),
+            None,
+            false,
+        )
+    }
+
+    fn rotated_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.file_name().unwrap() != "node.log")
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("aptos-logger-rotation-{}", rand_suffix()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("node.log");
+        let line_len = SinkFormat::Json.format(&entry()).unwrap().len() as u64 + 1;
+
+        // Each file fits 2 lines
+        let mut sink = RotatingFileSink::new(FileSinkConfig {
+            path: path.clone(),
+            level: Level::Info,
+            format: SinkFormat::Json,
+            max_size_bytes: Some(2 * line_len),
+            rotation_interval_secs: None,
+            max_files: 2,
+            compress: true,
+        })
+        .unwrap();
+        for _ in 0..7 {
+            sink.write(&entry());
+        }
+        sink.cleanup.take().unwrap().join().unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().len(), line_len);
+        let rotated = rotated_files(&dir);
+        assert_eq!(rotated.len(), 2);
+        for file in rotated {
+            assert_eq!(file.extension().unwrap(), "gz");
+            let mut content = String::new();
+            flate2::read::GzDecoder::new(File::open(file).unwrap())
+                .read_to_string(&mut content)
+                .unwrap();
+            assert_eq!(content.lines().count(), 2);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn rand_suffix() -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    }
+}
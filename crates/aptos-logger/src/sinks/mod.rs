@@ -0,0 +1,87 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sinks the logger service writes logs to in addition to the local printer, each with its own
+//! level: OTLP/gRPC collectors, syslog, and local files rotated by size or time.
+//!
+//! ```
+//! use aptos_logger::{sinks::LogSinkConfig, Level, Logger};
+//!
+//! let config: LogSinkConfig =
+//!     serde_json::from_str(r#"{"type": "syslog", "address": "udp://127.0.0.1:514"}"#).unwrap();
+//! let mut builder = Logger::builder();
+//! builder.is_async(true).sink(config.level(), config.build().unwrap());
+//! ```
+
+mod file;
+mod otlp;
+mod syslog;
+
+pub use file::{FileSinkConfig, RotatingFileSink};
+pub use otlp::{OtlpSink, OtlpSinkConfig};
+pub use syslog::{SyslogSink, SyslogSinkConfig};
+
+use crate::{
+    aptos_logger::{json_format, text_format, LogEntry},
+    Level,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, io};
+
+/// A destination the logger service writes logs to, see [`crate::AptosDataBuilder::sink`].
+pub trait LogSink: Send {
+    /// Write the log, errors are reported by the sink itself since there's no one to return them to.
+    fn write(&mut self, entry: &LogEntry);
+
+    /// Flush the logs written so far, called when the logger is flushed.
+    fn flush(&mut self) {}
+}
+
+/// The configuration of a sink, tagged by its `type`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LogSinkConfig {
+    File(FileSinkConfig),
+    Otlp(OtlpSinkConfig),
+    Syslog(SyslogSinkConfig),
+}
+
+impl LogSinkConfig {
+    /// The most verbose level written to the sink
+    pub fn level(&self) -> Level {
+        match self {
+            LogSinkConfig::File(config) => config.level,
+            LogSinkConfig::Otlp(config) => config.level,
+            LogSinkConfig::Syslog(config) => config.level,
+        }
+    }
+
+    pub fn build(&self) -> io::Result<Box<dyn LogSink>> {
+        Ok(match self {
+            LogSinkConfig::File(config) => Box::new(RotatingFileSink::new(config.clone())?),
+            LogSinkConfig::Otlp(config) => Box::new(OtlpSink::new(config.clone())?),
+            LogSinkConfig::Syslog(config) => Box::new(SyslogSink::new(config.clone())?),
+        })
+    }
+}
+
+/// How sinks writing lines of text format logs
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkFormat {
+    Json,
+    Text,
+}
+
+impl SinkFormat {
+    pub(crate) fn format(&self, entry: &LogEntry) -> Result<String, fmt::Error> {
+        match self {
+            SinkFormat::Json => json_format(entry),
+            SinkFormat::Text => text_format(entry),
+        }
+    }
+}
+
+fn default_level() -> Level {
+    Level::Info
+}
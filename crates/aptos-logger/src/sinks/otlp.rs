@@ -0,0 +1,419 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{default_level, LogSink};
+use crate::{
+    aptos_logger::LogEntry,
+    counters::{LOG_SINK_DROPPED_COUNT, LOG_SINK_ERROR_COUNT},
+    Level,
+};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+    thread,
+    time::{Duration, Instant},
+};
+use tonic::{
+    client::Grpc,
+    codec::ProstCodec,
+    codegen::http::uri::PathAndQuery,
+    transport::{Channel, Endpoint},
+};
+
+const SINK_LABEL: &str = "otlp";
+const EXPORT_PATH: &str = "/opentelemetry.proto.collector.logs.v1.LogsService/Export";
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtlpSinkConfig {
+    /// The gRPC endpoint of the collector e.g. `http://localhost:4317`
+    pub endpoint: String,
+    #[serde(default = "default_level")]
+    pub level: Level,
+    /// The `service.name` of the logs
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    /// Maximum number of logs exported per request
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// How often logs are exported
+    #[serde(default = "default_export_interval_ms")]
+    pub export_interval_ms: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Maximum number of logs waiting to be exported, more are dropped
+    #[serde(default = "default_max_queued_logs")]
+    pub max_queued_logs: usize,
+}
+
+fn default_service_name() -> String {
+    "aptos-node".into()
+}
+
+fn default_batch_size() -> usize {
+    512
+}
+
+fn default_export_interval_ms() -> u64 {
+    1_000
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_max_queued_logs() -> usize {
+    10_000
+}
+
+/// Exports logs to an OpenTelemetry collector over OTLP/gRPC, in batches from a background
+/// thread.
+pub struct OtlpSink {
+    sender: SyncSender<proto::LogRecord>,
+}
+
+impl OtlpSink {
+    pub fn new(config: OtlpSinkConfig) -> io::Result<Self> {
+        let endpoint = Endpoint::from_shared(config.endpoint.clone())
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid OTLP endpoint {}: {}", config.endpoint, err),
+                )
+            })?
+            .timeout(Duration::from_millis(config.timeout_ms));
+        let (sender, receiver) = mpsc::sync_channel(config.max_queued_logs);
+        thread::Builder::new()
+            .name("otlp-log-exporter".into())
+            .spawn(move || {
+                if let Err(err) = export_logs(config, endpoint, receiver) {
+                    eprintln!("[Logging] OTLP log exporter failed: {}", err);
+                }
+            })?;
+        Ok(Self { sender })
+    }
+}
+
+impl LogSink for OtlpSink {
+    fn write(&mut self, entry: &LogEntry) {
+        if self.sender.try_send(log_record(entry)).is_err() {
+            LOG_SINK_DROPPED_COUNT
+                .with_label_values(&[SINK_LABEL])
+                .inc();
+        }
+    }
+}
+
+fn export_logs(
+    config: OtlpSinkConfig,
+    endpoint: Endpoint,
+    receiver: Receiver<proto::LogRecord>,
+) -> io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let channel = {
+        let _guard = runtime.enter();
+        endpoint.connect_lazy()
+    };
+    let mut client = Grpc::new(channel);
+    let resource = proto::Resource {
+        attributes: vec![
+            proto::string_attribute("service.name", config.service_name.clone()),
+            proto::string_attribute(
+                "host.name",
+                hostname::get()
+                    .map(|hostname| hostname.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ),
+        ],
+        dropped_attributes_count: 0,
+    };
+    let interval = Duration::from_millis(config.export_interval_ms);
+    let batch_size = config.batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut last_export = Instant::now();
+
+    loop {
+        match receiver.recv_timeout(interval) {
+            Ok(record) => {
+                batch.push(record);
+                if batch.len() < batch_size && last_export.elapsed() < interval {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        if !batch.is_empty() {
+            let num_logs = batch.len() as u64;
+            let request = proto::ExportLogsServiceRequest {
+                resource_logs: vec![proto::ResourceLogs {
+                    resource: Some(resource.clone()),
+                    scope_logs: vec![proto::ScopeLogs {
+                        scope: Some(proto::InstrumentationScope {
+                            name: "aptos-logger".into(),
+                            version: String::new(),
+                        }),
+                        log_records: std::mem::take(&mut batch),
+                        schema_url: String::new(),
+                    }],
+                    schema_url: String::new(),
+                }],
+            };
+            if let Err(status) = runtime.block_on(export(&mut client, request)) {
+                LOG_SINK_ERROR_COUNT
+                    .with_label_values(&[SINK_LABEL])
+                    .inc_by(num_logs);
+                eprintln!(
+                    "[Logging] Error while exporting logs to OTLP({}): {}",
+                    config.endpoint, status
+                );
+            }
+        }
+        last_export = Instant::now();
+    }
+}
+
+async fn export(
+    client: &mut Grpc<Channel>,
+    request: proto::ExportLogsServiceRequest,
+) -> Result<(), tonic::Status> {
+    client
+        .ready()
+        .await
+        .map_err(|err| tonic::Status::unavailable(err.to_string()))?;
+    let codec: ProstCodec<proto::ExportLogsServiceRequest, proto::ExportLogsServiceResponse> =
+        ProstCodec::default();
+    client
+        .unary(
+            tonic::Request::new(request),
+            PathAndQuery::from_static(EXPORT_PATH),
+            codec,
+        )
+        .await?;
+    Ok(())
+}
+
+fn log_record(entry: &LogEntry) -> proto::LogRecord {
+    let metadata = entry.metadata();
+    let time_unix_nano = DateTime::parse_from_rfc3339(entry.timestamp())
+        .map_or(0, |timestamp| timestamp.timestamp_nanos() as u64);
+    let mut attributes = vec![
+        proto::string_attribute("code.filepath", metadata.source_path().into()),
+        proto::string_attribute("code.namespace", metadata.module_path().into()),
+    ];
+    if let Some(thread_name) = entry.thread_name() {
+        attributes.push(proto::string_attribute("thread.name", thread_name.into()));
+    }
+    if let Some(backtrace) = entry.backtrace() {
+        attributes.push(proto::string_attribute(
+            "exception.stacktrace",
+            backtrace.into(),
+        ));
+    }
+    for (key, value) in entry.data() {
+        attributes.push(proto::KeyValue {
+            key: key.as_str().to_string(),
+            value: Some(proto::any_value(value)),
+        });
+    }
+    let level = metadata.level();
+    proto::LogRecord {
+        time_unix_nano,
+        observed_time_unix_nano: time_unix_nano,
+        severity_number: severity_number(level),
+        severity_text: level.to_string(),
+        body: entry.message().map(|message| proto::AnyValue {
+            value: Some(proto::any_value::Value::StringValue(message.into())),
+        }),
+        attributes,
+        dropped_attributes_count: 0,
+        flags: 0,
+        trace_id: vec![],
+        span_id: vec![],
+    }
+}
+
+/// The OpenTelemetry `SeverityNumber` of a level
+fn severity_number(level: Level) -> i32 {
+    match level {
+        Level::Error => 17,
+        Level::Warn => 13,
+        Level::Info => 9,
+        Level::Debug => 5,
+        Level::Trace => 1,
+    }
+}
+
+/// The parts of the OTLP logs protocol that are used, see
+/// https://github.com/open-telemetry/opentelemetry-proto/blob/main/opentelemetry/proto/logs/v1/logs.proto
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportLogsServiceRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub resource_logs: Vec<ResourceLogs>,
+    }
+
+    /// The partial success of the response isn't used
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportLogsServiceResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResourceLogs {
+        #[prost(message, optional, tag = "1")]
+        pub resource: Option<Resource>,
+        #[prost(message, repeated, tag = "2")]
+        pub scope_logs: Vec<ScopeLogs>,
+        #[prost(string, tag = "3")]
+        pub schema_url: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Resource {
+        #[prost(message, repeated, tag = "1")]
+        pub attributes: Vec<KeyValue>,
+        #[prost(uint32, tag = "2")]
+        pub dropped_attributes_count: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ScopeLogs {
+        #[prost(message, optional, tag = "1")]
+        pub scope: Option<InstrumentationScope>,
+        #[prost(message, repeated, tag = "2")]
+        pub log_records: Vec<LogRecord>,
+        #[prost(string, tag = "3")]
+        pub schema_url: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct InstrumentationScope {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub version: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct LogRecord {
+        #[prost(fixed64, tag = "1")]
+        pub time_unix_nano: u64,
+        #[prost(fixed64, tag = "11")]
+        pub observed_time_unix_nano: u64,
+        #[prost(int32, tag = "2")]
+        pub severity_number: i32,
+        #[prost(string, tag = "3")]
+        pub severity_text: String,
+        #[prost(message, optional, tag = "5")]
+        pub body: Option<AnyValue>,
+        #[prost(message, repeated, tag = "6")]
+        pub attributes: Vec<KeyValue>,
+        #[prost(uint32, tag = "7")]
+        pub dropped_attributes_count: u32,
+        #[prost(fixed32, tag = "8")]
+        pub flags: u32,
+        #[prost(bytes = "vec", tag = "9")]
+        pub trace_id: Vec<u8>,
+        #[prost(bytes = "vec", tag = "10")]
+        pub span_id: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct KeyValue {
+        #[prost(string, tag = "1")]
+        pub key: String,
+        #[prost(message, optional, tag = "2")]
+        pub value: Option<AnyValue>,
+    }
+
+    /// Arrays and maps are sent as their json instead of the nested values
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AnyValue {
+        #[prost(oneof = "any_value::Value", tags = "1, 2, 3, 4")]
+        pub value: Option<any_value::Value>,
+    }
+
+    pub mod any_value {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Value {
+            #[prost(string, tag = "1")]
+            StringValue(String),
+            #[prost(bool, tag = "2")]
+            BoolValue(bool),
+            #[prost(int64, tag = "3")]
+            IntValue(i64),
+            #[prost(double, tag = "4")]
+            DoubleValue(f64),
+        }
+    }
+
+    pub fn string_attribute(key: &str, value: String) -> KeyValue {
+        KeyValue {
+            key: key.into(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value)),
+            }),
+        }
+    }
+
+    pub fn any_value(value: &serde_json::Value) -> AnyValue {
+        let value = match value {
+            serde_json::Value::String(value) => any_value::Value::StringValue(value.clone()),
+            serde_json::Value::Bool(value) => any_value::Value::BoolValue(*value),
+            serde_json::Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+                (Some(value), _) => any_value::Value::IntValue(value),
+                (None, Some(value)) => any_value::Value::DoubleValue(value),
+                _ => any_value::Value::StringValue(number.to_string()),
+            },
+            value => any_value::Value::StringValue(value.to_string()),
+        };
+        AnyValue { value: Some(value) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, KeyValue, Metadata, Schema, Value};
+
+    #[test]
+    fn test_log_record() {
+        let metadata = Metadata::new(Level::Error, "target", "module_path", "source_path");
+        let value = 5;
+        let key_values = [KeyValue::new("count", Value::from_serde(&value))];
+        let schemas: Vec<&dyn Schema> = key_values.iter().map(|kv| kv as &dyn Schema).collect();
+        let entry = LogEntry::new(
+            &Event::new(&metadata, Some(format_args!("Failed")), &schemas),
+            Some("thread"),
+            false,
+        );
+
+        let record = log_record(&entry);
+        assert_eq!(record.severity_number, 17);
+        assert_eq!(record.severity_text, "ERROR");
+        assert_ne!(record.time_unix_nano, 0);
+        assert_eq!(
+            record.body,
+            Some(proto::AnyValue {
+                value: Some(proto::any_value::Value::StringValue("Failed".into())),
+            })
+        );
+        assert_eq!(
+            record.attributes,
+            vec![
+                proto::string_attribute("code.filepath", "source_path".into()),
+                proto::string_attribute("code.namespace", "module_path".into()),
+                proto::string_attribute("thread.name", "thread".into()),
+                proto::KeyValue {
+                    key: "count".into(),
+                    value: Some(proto::AnyValue {
+                        value: Some(proto::any_value::Value::IntValue(5)),
+                    }),
+                },
+            ]
+        );
+    }
+}
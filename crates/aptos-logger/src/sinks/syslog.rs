@@ -0,0 +1,247 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{default_level, LogSink, SinkFormat};
+use crate::{aptos_logger::LogEntry, counters::LOG_SINK_ERROR_COUNT, struct_log::TcpWriter, Level};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::{
+    io::{self, Write},
+    net::{ToSocketAddrs, UdpSocket},
+    process,
+};
+
+const SINK_LABEL: &str = "syslog";
+/// Longer messages are truncated, so they fit in a datagram
+const MAX_DATAGRAM_LEN: usize = 65_000;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyslogSinkConfig {
+    /// Where logs are sent: `udp://<host>:<port>`, `tcp://<host>:<port>` or `unix://<socket path>`
+    #[serde(default = "default_address")]
+    pub address: String,
+    #[serde(default = "default_level")]
+    pub level: Level,
+    /// The format of the message of the syslog records
+    #[serde(default = "default_format")]
+    pub format: SinkFormat,
+    /// The syslog facility code, 16 to 23 are local0 to local7
+    #[serde(default = "default_facility")]
+    pub facility: u8,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+}
+
+fn default_address() -> String {
+    "unix:///dev/log".into()
+}
+
+fn default_format() -> SinkFormat {
+    SinkFormat::Text
+}
+
+fn default_facility() -> u8 {
+    16
+}
+
+fn default_app_name() -> String {
+    "aptos-node".into()
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    /// Records are framed by octet counting, see RFC 6587
+    Tcp(TcpWriter),
+    #[cfg(unix)]
+    Unix(UnixDatagram, String),
+}
+
+/// Sends logs as RFC 5424 syslog records.
+pub struct SyslogSink {
+    config: SyslogSinkConfig,
+    transport: Transport,
+}
+
+impl SyslogSink {
+    pub fn new(config: SyslogSinkConfig) -> io::Result<Self> {
+        if config.facility > 23 {
+            return Err(invalid_config(format!(
+                "Invalid syslog facility {}, it must be at most 23",
+                config.facility
+            )));
+        }
+        let transport = if let Some(address) = config.address.strip_prefix("udp://") {
+            let address = address
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| invalid_config(format!("Unable to resolve {}", address)))?;
+            let socket = if address.is_ipv4() {
+                UdpSocket::bind("0.0.0.0:0")?
+            } else {
+                UdpSocket::bind("[::]:0")?
+            };
+            socket.connect(address)?;
+            Transport::Udp(socket)
+        } else if let Some(address) = config.address.strip_prefix("tcp://") {
+            Transport::Tcp(TcpWriter::new(address.to_string()))
+        } else if let Some(path) = config.address.strip_prefix("unix://") {
+            Self::unix_transport(path)?
+        } else {
+            return Err(invalid_config(format!(
+                "Invalid syslog address {}, it must start with udp://, tcp:// or unix://",
+                config.address
+            )));
+        };
+        Ok(Self { config, transport })
+    }
+
+    #[cfg(unix)]
+    fn unix_transport(path: &str) -> io::Result<Transport> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Transport::Unix(socket, path.to_string()))
+    }
+
+    #[cfg(not(unix))]
+    fn unix_transport(_path: &str) -> io::Result<Transport> {
+        Err(invalid_config(
+            "Unix sockets aren't supported on this platform".into(),
+        ))
+    }
+
+    /// Formats the record: `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`
+    fn record(&self, entry: &LogEntry) -> Option<String> {
+        let message = self.config.format.format(entry).ok()?;
+        let priority = self.config.facility as u32 * 8 + severity(entry.metadata().level());
+        Some(format!(
+            "<{}>1 {} {} {} {} - - {}",
+            priority,
+            entry.timestamp(),
+            entry.hostname().unwrap_or("-"),
+            self.config.app_name,
+            process::id(),
+            message
+        ))
+    }
+
+    fn send(&mut self, record: &str) -> io::Result<()> {
+        match &mut self.transport {
+            Transport::Udp(socket) => socket.send(truncate(record).as_bytes()).map(|_| ()),
+            Transport::Tcp(writer) => {
+                writer.write_all(format!("{} {}", record.len(), record).as_bytes())
+            }
+            #[cfg(unix)]
+            Transport::Unix(socket, path) => {
+                let record = truncate(record).as_bytes();
+                socket.send(record).map(|_| ()).or_else(|_| {
+                    // The syslog daemon may have been restarted
+                    socket.connect(path.as_str())?;
+                    socket.send(record).map(|_| ())
+                })
+            }
+        }
+    }
+}
+
+impl LogSink for SyslogSink {
+    fn write(&mut self, entry: &LogEntry) {
+        let record = match self.record(entry) {
+            Some(record) => record,
+            None => return,
+        };
+        if let Err(err) = self.send(&record) {
+            LOG_SINK_ERROR_COUNT.with_label_values(&[SINK_LABEL]).inc();
+            eprintln!(
+                "[Logging] Error while sending logs to syslog({}): {}",
+                self.config.address, err
+            );
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Transport::Tcp(writer) = &mut self.transport {
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// The syslog severity of a level
+fn severity(level: Level) -> u32 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+fn truncate(record: &str) -> &str {
+    if record.len() <= MAX_DATAGRAM_LEN {
+        return record;
+    }
+    let mut len = MAX_DATAGRAM_LEN;
+    while !record.is_char_boundary(len) {
+        len -= 1;
+    }
+    &record[..len]
+}
+
+fn invalid_config(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, Metadata};
+
+    #[test]
+    fn test_record() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut sink = SyslogSink::new(SyslogSinkConfig {
+            address: format!("udp://{}", socket.local_addr().unwrap()),
+            level: Level::Info,
+            format: SinkFormat::Text,
+            facility: 17,
+            app_name: "test".into(),
+        })
+        .unwrap();
+
+        let metadata = Metadata::new(Level::Warn, "target", "module_path", "source_path");
+        let entry = LogEntry::new(
+            &Event::new(&metadata, Some(format_args!("Hello")), &[]),
+            None,
+            false,
+        );
+        sink.write(&entry);
+
+        let mut buf = [0; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+        let record = std::str::from_utf8(&buf[..len]).unwrap();
+        // local1 (17) * 8 + warning (4)
+        let expected_prefix = format!(
+            "<140>1 {} {} test {} - - ",
+            entry.timestamp(),
+            entry.hostname().unwrap_or("-"),
+            process::id()
+        );
+        assert!(record.starts_with(&expected_prefix), "{}", record);
+        assert!(record.ends_with("WARN source_path Hello"), "{}", record);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for address in ["localhost:514", "unix://", "udp://"] {
+            assert!(SyslogSink::new(SyslogSinkConfig {
+                address: address.into(),
+                level: Level::Info,
+                format: SinkFormat::Text,
+                facility: 16,
+                app_name: "test".into(),
+            })
+            .is_err());
+        }
+    }
+}
@@ -375,6 +375,21 @@ impl Bucket {
         self.allowed_in_period = self.allowed_in_period.saturating_sub(new_tokens);
         self.add_tokens(new_tokens);
     }
+
+    /// Changes the fill rate (and the size, to one period of the rate) of a running bucket.
+    /// Without a rate, the bucket is open, until a rate is set again.
+    pub fn set_rate(&mut self, rate: Option<usize>) {
+        match rate {
+            Some(rate) => {
+                let rate = rate.max(1);
+                self.enabled = true;
+                self.size = rate;
+                self.rate = rate;
+                self.tokens = min(self.tokens, rate);
+            }
+            None => self.enabled = false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -421,6 +436,23 @@ mod tests {
         assert_acquire(&mut bucket, bucket_size);
     }
 
+    #[test]
+    fn test_set_rate() {
+        let rate_limiter = TokenBucketRateLimiter::test(5, 5);
+        let bucket_arc = rate_limiter.bucket("Key");
+        let mut bucket = bucket_arc.lock();
+
+        // A lower rate takes effect right away
+        bucket.set_rate(Some(2));
+        assert_acquire(&mut bucket, 2);
+
+        // An open bucket allows everything
+        bucket.set_rate(None);
+        assert_eq!(1000, bucket.acquire_tokens(1000).unwrap());
+        bucket.set_rate(Some(2));
+        bucket.acquire_tokens(1).expect_err("Expected time to wait");
+    }
+
     #[test]
     fn test_message_rate_limiting() {
         let bucket_size = 5;
@@ -38,8 +38,8 @@ enum Kind {
 impl FaucetClientError {
     pub fn is_retriable(&self) -> bool {
         match self.inner.kind {
-            // internal server errors are retriable
-            Kind::HttpStatus(status) => (500..=599).contains(&status),
+            // internal server errors and rate limiting are retriable
+            Kind::HttpStatus(status) => status == 429 || (500..=599).contains(&status),
             Kind::Timeout | Kind::StaleResponse | Kind::NeedSync => true,
             Kind::RpcResponse
             | Kind::Request
@@ -56,6 +56,10 @@ impl FaucetClientError {
         matches!(self.inner.kind, Kind::NeedSync)
     }
 
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self.inner.kind, Kind::HttpStatus(429))
+    }
+
     //
     // Private Constructors
     //
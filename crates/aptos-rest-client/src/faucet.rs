@@ -1,16 +1,29 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{error::FaucetClientError, Client, Result};
+use crate::{
+    error::{AptosErrorResponse, FaucetClientError, RestError},
+    Client, Result,
+};
+use aptos_logger::info;
 use aptos_types::transaction::SignedTransaction;
+use futures::{stream, StreamExt, TryStreamExt};
 use move_core_types::account_address::AccountAddress;
-use reqwest::{Client as ReqwestClient, Url};
+use reqwest::{header::RETRY_AFTER, Client as ReqwestClient, StatusCode, Url};
 use std::time::Duration;
 
+const DEFAULT_MAX_RETRIES: usize = 5;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 8;
+
 pub struct FaucetClient {
     faucet_url: Url,
     inner: ReqwestClient,
     rest_client: Client,
+    max_retries: usize,
+    initial_backoff: Duration,
+    max_concurrent_requests: usize,
 }
 
 impl FaucetClient {
@@ -22,6 +35,9 @@ impl FaucetClient {
                 .build()
                 .unwrap(),
             rest_client: Client::new(rest_url),
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
         }
     }
 
@@ -38,80 +54,182 @@ impl FaucetClient {
                 // versioned API however, so we just set it to `/`.
                 .version_path_base("/".to_string())
                 .unwrap(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: Duration::from_millis(10),
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
         }
     }
 
+    /// Set the number of times a rate limited or failed faucet request is retried.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the first backoff between retries. It doubles on each retry, up to 30 seconds,
+    /// unless a rate limiting faucet asks for a specific delay (also up to 30 seconds) via
+    /// `Retry-After`.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the number of faucet requests that `fund_many` keeps in flight at once.
+    pub fn max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_requests = std::cmp::max(1, max_concurrent_requests);
+        self
+    }
+
     /// Create an account with zero balance.
     pub async fn create_account(&self, address: AccountAddress) -> Result<()> {
-        let mut url = self.faucet_url.clone();
-        url.set_path("mint");
-        let query = format!("auth_key={}&amount=0&return_txns=true", address);
-        url.set_query(Some(&query));
+        self.mint_with_retries(address, 0).await
+    }
 
-        let response = self
-            .inner
-            .post(url)
-            .header("content-length", 0)
-            .send()
-            .await
-            .map_err(FaucetClientError::request)?;
-        let status_code = response.status();
-        let body = response.text().await.map_err(FaucetClientError::decode)?;
-        if !status_code.is_success() {
-            return Err(anyhow::anyhow!("body: {}", body));
-        }
+    /// Fund an account with the given amount.
+    pub async fn fund(&self, address: AccountAddress, amount: u64) -> Result<()> {
+        self.mint_with_retries(address, amount).await
+    }
+
+    /// Fund each of the given accounts with the given amount.
+    ///
+    /// The faucet mints to one receiver per request, so the requests are fanned out with at most
+    /// `max_concurrent_requests` in flight. Returns once every funding transaction has been
+    /// committed successfully, or with the first error encountered.
+    pub async fn fund_many(&self, addresses: &[AccountAddress], amount: u64) -> Result<()> {
+        stream::iter(addresses.iter().map(|address| self.fund(*address, amount)))
+            .buffer_unordered(self.max_concurrent_requests)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+
+    // Create and fund an account.
+    pub async fn mint(&self, address: AccountAddress, amount: u64) -> Result<()> {
+        self.create_account(address).await?;
+        self.fund(address, amount).await?;
+
+        Ok(())
+    }
 
-        let bytes = hex::decode(body).map_err(FaucetClientError::decode)?;
-        let txns: Vec<SignedTransaction> =
-            bcs::from_bytes(&bytes).map_err(FaucetClientError::decode)?;
+    /// Calls the faucet, backing off on rate limits and server errors, and then waits for the
+    /// returned transactions to be committed.
+    async fn mint_with_retries(&self, address: AccountAddress, amount: u64) -> Result<()> {
+        let previous_balance = self.balance_before_mint(address).await?;
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 0;
+        let txns = loop {
+            match self.request_mint(address, amount).await {
+                Ok(txns) => break txns,
+                Err((err, retry_after)) => {
+                    if !err.is_retriable() || attempt >= self.max_retries {
+                        return Err(err.into());
+                    }
+                    // The faucet may ask for a longer delay when it rate limits, but never for
+                    // more than the longest backoff.
+                    let delay = match retry_after {
+                        Some(retry_after) if err.is_rate_limited() => {
+                            std::cmp::min(retry_after, DEFAULT_MAX_BACKOFF)
+                        }
+                        _ => backoff,
+                    };
+                    info!(
+                        "Faucet request for {} failed, retrying in {}ms: {}",
+                        address,
+                        delay.as_millis(),
+                        err
+                    );
+                    tokio::time::sleep(delay).await;
+                    backoff = std::cmp::min(backoff.saturating_mul(2), DEFAULT_MAX_BACKOFF);
+                    attempt += 1;
+                }
+            }
+        };
+
+        // Faucet returns the transactions that create or fund the account and they need to be
+        // waited on before returning. Waiting fails if any of them failed execution.
+        for txn in &txns {
+            self.rest_client
+                .wait_for_signed_transaction(txn)
+                .await
+                .map_err(FaucetClientError::unknown)?;
+        }
 
-        self.rest_client
-            .wait_for_signed_transaction(&txns[0])
+        // Make sure the funds are visible on chain before handing the account back to the caller.
+        let balance = self
+            .rest_client
+            .get_account_balance(address)
             .await
-            .map_err(FaucetClientError::unknown)?;
+            .map_err(FaucetClientError::state_store)?
+            .into_inner()
+            .get();
+        if balance < previous_balance.saturating_add(amount) {
+            return Err(FaucetClientError::state_store(format!(
+                "Account {} has a balance of {} after being funded with {}, expected at least {}",
+                address,
+                balance,
+                amount,
+                previous_balance.saturating_add(amount)
+            ))
+            .into());
+        }
 
         Ok(())
     }
 
-    /// Fund an account with the given amount.
-    pub async fn fund(&self, address: AccountAddress, amount: u64) -> Result<()> {
+    /// The balance of the account before it's funded, which is zero if it doesn't exist yet.
+    async fn balance_before_mint(&self, address: AccountAddress) -> Result<u64> {
+        match self.rest_client.get_account_balance(address).await {
+            Ok(balance) => Ok(balance.into_inner().get()),
+            Err(RestError::Api(AptosErrorResponse {
+                status_code: StatusCode::NOT_FOUND,
+                ..
+            }))
+            | Err(RestError::Http(StatusCode::NOT_FOUND, _)) => Ok(0),
+            Err(err) => Err(FaucetClientError::state_store(err).into()),
+        }
+    }
+
+    /// Makes a single mint request, returning the error along with any delay the faucet
+    /// requested through the `Retry-After` header.
+    async fn request_mint(
+        &self,
+        address: AccountAddress,
+        amount: u64,
+    ) -> std::result::Result<Vec<SignedTransaction>, (FaucetClientError, Option<Duration>)> {
         let mut url = self.faucet_url.clone();
         url.set_path("mint");
         let query = format!("auth_key={}&amount={}&return_txns=true", address, amount);
         url.set_query(Some(&query));
 
-        // Faucet returns the transaction that creates the account and needs to be waited on before
-        // returning.
         let response = self
             .inner
             .post(url)
             .header("content-length", 0)
             .send()
             .await
-            .map_err(FaucetClientError::request)?;
+            .map_err(|e| {
+                if e.is_timeout() {
+                    (FaucetClientError::timeout(e), None)
+                } else {
+                    (FaucetClientError::request(e), None)
+                }
+            })?;
         let status_code = response.status();
-        let body = response.text().await.map_err(FaucetClientError::decode)?;
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response
+            .text()
+            .await
+            .map_err(|e| (FaucetClientError::decode(e), None))?;
         if !status_code.is_success() {
-            return Err(FaucetClientError::status(status_code.as_u16()).into());
+            return Err((FaucetClientError::status(status_code.as_u16()), retry_after));
         }
 
-        let bytes = hex::decode(body).map_err(FaucetClientError::decode)?;
-        let txns: Vec<SignedTransaction> =
-            bcs::from_bytes(&bytes).map_err(FaucetClientError::decode)?;
-
-        self.rest_client
-            .wait_for_signed_transaction(&txns[0])
-            .await
-            .map_err(FaucetClientError::unknown)?;
-
-        Ok(())
-    }
-
-    // Create and fund an account.
-    pub async fn mint(&self, address: AccountAddress, amount: u64) -> Result<()> {
-        self.create_account(address).await?;
-        self.fund(address, amount).await?;
-
-        Ok(())
+        let bytes = hex::decode(body).map_err(|e| (FaucetClientError::decode(e), None))?;
+        bcs::from_bytes(&bytes).map_err(|e| (FaucetClientError::decode(e), None))
     }
 }
@@ -17,6 +17,7 @@ use crate::{
     RosettaContext,
 };
 use aptos_logger::{debug, trace, warn};
+use aptos_rest_client::{aptos_api_types::AptosErrorCode, error::RestError};
 use aptos_types::account_address::AccountAddress;
 use aptos_types::account_config::{AccountResource, CoinStoreResource};
 use std::collections::HashSet;
@@ -94,11 +95,23 @@ async fn get_balances(
 ) -> ApiResult<(u64, Option<Vec<AccountAddress>>, Vec<Amount>)> {
     let owner_address = account.account_address()?;
 
-    // Retrieve all account resources
-    if let Ok(response) = rest_client
+    // Retrieve all account resources at the version.  Only a missing account has no balance, any
+    // other failure (e.g. a pruned version) must not be reported as a balance of zero, otherwise
+    // historical balances won't reconcile.
+    let maybe_response = match rest_client
         .get_account_resources_at_version_bcs(owner_address, version)
         .await
     {
+        Ok(response) => Some(response),
+        Err(RestError::Api(err))
+            if matches!(err.error.error_code, AptosErrorCode::AccountNotFound) =>
+        {
+            None
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if let Some(response) = maybe_response {
         let resources = response.into_inner();
         let mut maybe_sequence_number = None;
         let mut maybe_operators = None;
@@ -198,6 +211,18 @@ async fn get_balances(
             )));
         };
 
+        // An account without a coin store still has a balance of zero
+        if account.is_base_account()
+            && !balances
+                .iter()
+                .any(|balance| balance.currency == native_coin())
+        {
+            balances.push(Amount {
+                value: 0.to_string(),
+                currency: native_coin(),
+            });
+        }
+
         // Filter based on requested currencies
         if let Some(currencies) = maybe_filter_currencies {
             let mut currencies: HashSet<Currency> = currencies.into_iter().collect();
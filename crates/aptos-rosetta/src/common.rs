@@ -170,10 +170,21 @@ pub async fn get_block_index_from_request(
     partial_block_identifier: Option<PartialBlockIdentifier>,
 ) -> ApiResult<u64> {
     Ok(match partial_block_identifier {
+        // Lookup by block index, as long as it's the same block as the hash
         Some(PartialBlockIdentifier {
             index: Some(block_index),
-            hash: Some(_),
-        }) => block_index,
+            hash: Some(hash),
+        }) => {
+            let hash_block_index =
+                BlockHash::from_str(&hash)?.block_height(server_context.chain_id)?;
+            if hash_block_index != block_index {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Block hash {} doesn't match block index {}",
+                    hash, block_index
+                ))));
+            }
+            block_index
+        }
         // Lookup by block index
         Some(PartialBlockIdentifier {
             index: Some(block_index),
@@ -230,59 +230,23 @@ async fn fill_in_operator(
         InternalOperation::SetOperator(op) => {
             // If there was no old operator set, and there is only one, we should use that
             if op.old_operator.is_none() {
-                let store = rest_client
-                    .get_account_resource_bcs::<Store>(op.owner, "0x1::staking_contract::Store")
-                    .await?
-                    .into_inner();
-                if store.staking_contracts.len() != 1 {
-                    let operators: Vec<_> = store
-                        .staking_contracts
-                        .iter()
-                        .map(|(operator, _)| operator)
-                        .collect();
-                    return Err(ApiError::InvalidInput(Some(format!(
-                        "Account has more than one operator, operator must be specified from: {:?}",
-                        operators
-                    ))));
-                } else {
-                    op.old_operator = Some(
-                        *store
-                            .staking_contracts
-                            .iter()
-                            .next()
-                            .map(|inner| inner.0)
-                            .unwrap(),
-                    );
-                }
+                op.old_operator = Some(get_only_operator(rest_client, op.owner).await?);
             }
         }
         InternalOperation::SetVoter(op) => {
             // If there was no operator set, and there is only one, we should use that
             if op.operator.is_none() {
-                let store = rest_client
-                    .get_account_resource_bcs::<Store>(op.owner, "0x1::staking_contract::Store")
-                    .await?
-                    .into_inner();
-                if store.staking_contracts.len() != 1 {
-                    let operators: Vec<_> = store
-                        .staking_contracts
-                        .iter()
-                        .map(|(operator, _)| operator)
-                        .collect();
-                    return Err(ApiError::InvalidInput(Some(format!(
-                        "Account has more than one operator, operator must be specified from: {:?}",
-                        operators
-                    ))));
-                } else {
-                    op.operator = Some(
-                        *store
-                            .staking_contracts
-                            .iter()
-                            .next()
-                            .map(|inner| inner.0)
-                            .unwrap(),
-                    );
-                }
+                op.operator = Some(get_only_operator(rest_client, op.owner).await?);
+            }
+        }
+        InternalOperation::AddStake(op) => {
+            if op.operator.is_none() {
+                op.operator = Some(get_only_operator(rest_client, op.owner).await?);
+            }
+        }
+        InternalOperation::UnlockStake(op) => {
+            if op.operator.is_none() {
+                op.operator = Some(get_only_operator(rest_client, op.owner).await?);
             }
         }
         _ => {}
@@ -291,6 +255,35 @@ async fn fill_in_operator(
     Ok(internal_operation)
 }
 
+/// Retrieves the operator of the owner's staking contract, if it only has one
+async fn get_only_operator(
+    rest_client: &aptos_rest_client::Client,
+    owner: AccountAddress,
+) -> ApiResult<AccountAddress> {
+    let store = rest_client
+        .get_account_resource_bcs::<Store>(owner, "0x1::staking_contract::Store")
+        .await?
+        .into_inner();
+    if store.staking_contracts.len() != 1 {
+        let operators: Vec<_> = store
+            .staking_contracts
+            .iter()
+            .map(|(operator, _)| operator)
+            .collect();
+        Err(ApiError::InvalidInput(Some(format!(
+            "Account has more than one operator, operator must be specified from: {:?}",
+            operators
+        ))))
+    } else {
+        Ok(*store
+            .staking_contracts
+            .iter()
+            .next()
+            .map(|inner| inner.0)
+            .unwrap())
+    }
+}
+
 async fn simulate_transaction(
     rest_client: &aptos_rest_client::Client,
     chain_id: ChainId,
@@ -423,7 +416,7 @@ async fn simulate_transaction(
     }
 }
 
-/// Construction metadata command
+/// Construction metadata command (ONLINE)
 ///
 /// Retrieve sequence number for submitting transactions
 ///
@@ -451,7 +444,7 @@ async fn construction_metadata(
         response.inner().sequence_number
     };
 
-    // We have to cheat the set operator and set voter operations right here
+    // We have to cheat the operator of the staking operations right here
     let internal_operation = fill_in_operator(
         rest_client.as_ref(),
         request.options.internal_operation.clone(),
@@ -549,6 +542,15 @@ async fn construction_parse(
                 (AccountAddress::ONE, STAKING_CONTRACT_MODULE, CREATE_STAKING_CONTRACT) => {
                     parse_create_stake_pool_operation(sender, &type_args, &args)?
                 }
+                (AccountAddress::ONE, STAKING_CONTRACT_MODULE, ADD_STAKE_FUNCTION) => {
+                    parse_add_stake_operation(sender, &type_args, &args)?
+                }
+                (AccountAddress::ONE, STAKING_CONTRACT_MODULE, UNLOCK_STAKE_FUNCTION) => {
+                    parse_unlock_stake_operation(sender, &type_args, &args)?
+                }
+                (AccountAddress::ONE, STAKING_CONTRACT_MODULE, DISTRIBUTE_FUNCTION) => {
+                    parse_distribute_staking_rewards_operation(sender, &type_args, &args)?
+                }
                 _ => {
                     return Err(ApiError::TransactionParseError(Some(format!(
                         "Unsupported entry function type {:x}::{}::{}",
@@ -802,6 +804,76 @@ pub fn parse_create_stake_pool_operation(
     )])
 }
 
+pub fn parse_add_stake_operation(
+    sender: AccountAddress,
+    type_args: &[TypeTag],
+    args: &[Vec<u8>],
+) -> ApiResult<Vec<Operation>> {
+    if !type_args.is_empty() {
+        return Err(ApiError::TransactionParseError(Some(format!(
+            "Add stake should not have type arguments: {:?}",
+            type_args
+        ))));
+    }
+
+    let operator = parse_function_arg("add_stake", args, 0)?;
+    let amount: u64 = parse_function_arg("add_stake", args, 1)?;
+    Ok(vec![Operation::add_stake(
+        0,
+        None,
+        sender,
+        Some(AccountIdentifier::base_account(operator)),
+        Some(amount),
+    )])
+}
+
+pub fn parse_unlock_stake_operation(
+    sender: AccountAddress,
+    type_args: &[TypeTag],
+    args: &[Vec<u8>],
+) -> ApiResult<Vec<Operation>> {
+    if !type_args.is_empty() {
+        return Err(ApiError::TransactionParseError(Some(format!(
+            "Unlock stake should not have type arguments: {:?}",
+            type_args
+        ))));
+    }
+
+    let operator = parse_function_arg("unlock_stake", args, 0)?;
+    let amount: u64 = parse_function_arg("unlock_stake", args, 1)?;
+    Ok(vec![Operation::unlock_stake(
+        0,
+        None,
+        sender,
+        Some(AccountIdentifier::base_account(operator)),
+        Some(amount),
+    )])
+}
+
+pub fn parse_distribute_staking_rewards_operation(
+    sender: AccountAddress,
+    type_args: &[TypeTag],
+    args: &[Vec<u8>],
+) -> ApiResult<Vec<Operation>> {
+    if !type_args.is_empty() {
+        return Err(ApiError::TransactionParseError(Some(format!(
+            "Distribute staking rewards should not have type arguments: {:?}",
+            type_args
+        ))));
+    }
+
+    let staker = parse_function_arg("distribute_staking_rewards", args, 0)?;
+    let operator = parse_function_arg("distribute_staking_rewards", args, 1)?;
+    Ok(vec![Operation::distribute_staking_rewards(
+        0,
+        None,
+        sender,
+        AccountIdentifier::base_account(operator),
+        AccountIdentifier::base_account(staker),
+        None,
+    )])
+}
+
 /// Construction payloads command (OFFLINE)
 ///
 /// Constructs payloads for given known operations
@@ -888,6 +960,52 @@ async fn construction_payloads(
                 ))));
             }
         }
+        InternalOperation::AddStake(inner) => {
+            if let InternalOperation::AddStake(ref metadata_op) = metadata.internal_operation {
+                if inner.owner == metadata_op.owner && inner.amount == metadata_op.amount {
+                    if inner.operator.is_none() {
+                        inner.operator = metadata_op.operator;
+                    }
+                } else {
+                    return Err(ApiError::InvalidInput(Some(format!(
+                        "Add stake operation doesn't match metadata {:?} vs {:?}",
+                        inner, metadata.internal_operation
+                    ))));
+                }
+            } else {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Add stake operation doesn't match metadata {:?} vs {:?}",
+                    inner, metadata.internal_operation
+                ))));
+            }
+        }
+        InternalOperation::UnlockStake(inner) => {
+            if let InternalOperation::UnlockStake(ref metadata_op) = metadata.internal_operation {
+                if inner.owner == metadata_op.owner && inner.amount == metadata_op.amount {
+                    if inner.operator.is_none() {
+                        inner.operator = metadata_op.operator;
+                    }
+                } else {
+                    return Err(ApiError::InvalidInput(Some(format!(
+                        "Unlock stake operation doesn't match metadata {:?} vs {:?}",
+                        inner, metadata.internal_operation
+                    ))));
+                }
+            } else {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Unlock stake operation doesn't match metadata {:?} vs {:?}",
+                    inner, metadata.internal_operation
+                ))));
+            }
+        }
+        InternalOperation::DistributeStakingRewards(_) => {
+            if operation != metadata.internal_operation {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Distribute staking rewards operation doesn't match metadata {:?} vs {:?}",
+                    operation, metadata.internal_operation
+                ))));
+            }
+        }
     }
 
     // Encode operation
@@ -1030,7 +1148,7 @@ async fn construction_preprocess(
     })
 }
 
-/// Construction submit command (OFFLINE)
+/// Construction submit command (ONLINE)
 ///
 /// Submits a transaction to the blockchain
 ///
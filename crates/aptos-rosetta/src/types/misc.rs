@@ -90,6 +90,9 @@ pub enum OperationType {
     SetOperator,
     SetVoter,
     InitializeStakePool,
+    AddStake,
+    UnlockStake,
+    DistributeStakingRewards,
     // Fee must always be last for ordering
     Fee,
 }
@@ -103,6 +106,9 @@ impl OperationType {
     const SET_OPERATOR: &'static str = "set_operator";
     const SET_VOTER: &'static str = "set_voter";
     const INITIALIZE_STAKE_POOL: &'static str = "initialize_stake_pool";
+    const ADD_STAKE: &'static str = "add_stake";
+    const UNLOCK_STAKE: &'static str = "unlock_stake";
+    const DISTRIBUTE_STAKING_REWARDS: &'static str = "distribute_staking_rewards";
 
     pub fn all() -> Vec<OperationType> {
         use OperationType::*;
@@ -115,6 +121,9 @@ impl OperationType {
             SetVoter,
             StakingReward,
             InitializeStakePool,
+            AddStake,
+            UnlockStake,
+            DistributeStakingRewards,
         ]
     }
 }
@@ -132,6 +141,9 @@ impl FromStr for OperationType {
             Self::SET_OPERATOR => Ok(OperationType::SetOperator),
             Self::SET_VOTER => Ok(OperationType::SetVoter),
             Self::INITIALIZE_STAKE_POOL => Ok(OperationType::InitializeStakePool),
+            Self::ADD_STAKE => Ok(OperationType::AddStake),
+            Self::UNLOCK_STAKE => Ok(OperationType::UnlockStake),
+            Self::DISTRIBUTE_STAKING_REWARDS => Ok(OperationType::DistributeStakingRewards),
             _ => Err(ApiError::DeserializationFailed(Some(format!(
                 "Invalid OperationType: {}",
                 s
@@ -151,6 +163,9 @@ impl Display for OperationType {
             SetOperator => Self::SET_OPERATOR,
             SetVoter => Self::SET_VOTER,
             InitializeStakePool => Self::INITIALIZE_STAKE_POOL,
+            AddStake => Self::ADD_STAKE,
+            UnlockStake => Self::UNLOCK_STAKE,
+            DistributeStakingRewards => Self::DISTRIBUTE_STAKING_REWARDS,
             Fee => Self::FEE,
         })
     }
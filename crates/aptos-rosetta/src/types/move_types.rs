@@ -33,6 +33,9 @@ pub const CREATE_STAKING_CONTRACT: &str = "create_staking_contract";
 pub const SWITCH_OPERATOR_WITH_SAME_COMMISSION_FUNCTION: &str =
     "switch_operator_with_same_commission";
 pub const UPDATE_VOTER_FUNCTION: &str = "update_voter";
+pub const ADD_STAKE_FUNCTION: &str = "add_stake";
+pub const UNLOCK_STAKE_FUNCTION: &str = "unlock_stake";
+pub const DISTRIBUTE_FUNCTION: &str = "distribute";
 
 pub const DECIMALS_FIELD: &str = "decimal";
 pub const DEPOSIT_EVENTS_FIELD: &str = "deposit_events";
@@ -6,7 +6,10 @@
 //! [Spec](https://www.rosetta-api.org/docs/api_objects.html)
 
 use crate::common::native_coin_tag;
-use crate::construction::{parse_set_operator_operation, parse_set_voter_operation};
+use crate::construction::{
+    parse_add_stake_operation, parse_distribute_staking_rewards_operation,
+    parse_set_operator_operation, parse_set_voter_operation, parse_unlock_stake_operation,
+};
 use crate::types::move_types::*;
 use crate::{
     common::{is_native_coin, native_coin},
@@ -31,7 +34,7 @@ use aptos_types::write_set::{WriteOp, WriteSet};
 use aptos_types::{account_address::AccountAddress, event::EventKey};
 use cached_packages::aptos_stdlib;
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::{
@@ -351,6 +354,62 @@ impl Operation {
             Some(OperationMetadata::set_voter(operator, new_voter)),
         )
     }
+
+    pub fn add_stake(
+        operation_index: u64,
+        status: Option<OperationStatusType>,
+        owner: AccountAddress,
+        operator: Option<AccountIdentifier>,
+        amount: Option<u64>,
+    ) -> Operation {
+        Operation::new(
+            OperationType::AddStake,
+            operation_index,
+            status,
+            AccountIdentifier::base_account(owner),
+            None,
+            Some(OperationMetadata::stake_amount(operator, amount)),
+        )
+    }
+
+    pub fn unlock_stake(
+        operation_index: u64,
+        status: Option<OperationStatusType>,
+        owner: AccountAddress,
+        operator: Option<AccountIdentifier>,
+        amount: Option<u64>,
+    ) -> Operation {
+        Operation::new(
+            OperationType::UnlockStake,
+            operation_index,
+            status,
+            AccountIdentifier::base_account(owner),
+            None,
+            Some(OperationMetadata::stake_amount(operator, amount)),
+        )
+    }
+
+    /// The account is the sender when constructing a transaction, and the recipient of the
+    /// distributed coins when read from a block
+    pub fn distribute_staking_rewards(
+        operation_index: u64,
+        status: Option<OperationStatusType>,
+        account: AccountAddress,
+        operator: AccountIdentifier,
+        staker: AccountIdentifier,
+        amount: Option<u64>,
+    ) -> Operation {
+        Operation::new(
+            OperationType::DistributeStakingRewards,
+            operation_index,
+            status,
+            AccountIdentifier::base_account(account),
+            None,
+            Some(OperationMetadata::distribute_staking_rewards(
+                operator, staker, amount,
+            )),
+        )
+    }
 }
 
 impl std::cmp::PartialOrd for Operation {
@@ -397,6 +456,12 @@ pub struct OperationMetadata {
     pub new_voter: Option<AccountIdentifier>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub staked_balance: Option<U64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staker: Option<AccountIdentifier>,
+    /// Amount of stake moved, kept out of the operation's amount since it doesn't change the
+    /// account's balance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<U64>,
 }
 
 impl OperationMetadata {
@@ -428,6 +493,27 @@ impl OperationMetadata {
         }
     }
 
+    pub fn stake_amount(operator: Option<AccountIdentifier>, amount: Option<u64>) -> Self {
+        OperationMetadata {
+            operator,
+            amount: amount.map(U64::from),
+            ..Default::default()
+        }
+    }
+
+    pub fn distribute_staking_rewards(
+        operator: AccountIdentifier,
+        staker: AccountIdentifier,
+        amount: Option<u64>,
+    ) -> Self {
+        OperationMetadata {
+            operator: Some(operator),
+            staker: Some(staker),
+            amount: amount.map(U64::from),
+            ..Default::default()
+        }
+    }
+
     pub fn create_stake_pool(
         new_operator: Option<AccountIdentifier>,
         new_voter: Option<AccountIdentifier>,
@@ -717,6 +803,41 @@ fn parse_failed_operations_from_txn_payload(
                     warn!("Failed to parse set voter {:?}", inner);
                 }
             }
+            (AccountAddress::ONE, STAKING_CONTRACT_MODULE, ADD_STAKE_FUNCTION) => {
+                if let Ok(mut ops) =
+                    parse_add_stake_operation(sender, inner.ty_args(), inner.args())
+                {
+                    let mut operation = ops.remove(0);
+                    operation.status = Some(OperationStatusType::Failure.to_string());
+                    operations.push(operation);
+                } else {
+                    warn!("Failed to parse add stake {:?}", inner);
+                }
+            }
+            (AccountAddress::ONE, STAKING_CONTRACT_MODULE, UNLOCK_STAKE_FUNCTION) => {
+                if let Ok(mut ops) =
+                    parse_unlock_stake_operation(sender, inner.ty_args(), inner.args())
+                {
+                    let mut operation = ops.remove(0);
+                    operation.status = Some(OperationStatusType::Failure.to_string());
+                    operations.push(operation);
+                } else {
+                    warn!("Failed to parse unlock stake {:?}", inner);
+                }
+            }
+            (AccountAddress::ONE, STAKING_CONTRACT_MODULE, DISTRIBUTE_FUNCTION) => {
+                if let Ok(mut ops) = parse_distribute_staking_rewards_operation(
+                    sender,
+                    inner.ty_args(),
+                    inner.args(),
+                ) {
+                    let mut operation = ops.remove(0);
+                    operation.status = Some(OperationStatusType::Failure.to_string());
+                    operations.push(operation);
+                } else {
+                    warn!("Failed to parse distribute staking rewards {:?}", inner);
+                }
+            }
             _ => {
                 // If we don't recognize the transaction payload, then we can't parse operations
             }
@@ -1206,6 +1327,56 @@ async fn parse_staking_contract_resource_changes(
             operation_index += 1;
         }
 
+        // Parse all stake changes on the staking contracts
+        let add_stake_events =
+            filter_events(events, store.add_stake_events.key(), |event_key, event| {
+                parse_event::<AddStakeEvent>(event_key, event, "add stake")
+            });
+        for event in add_stake_events {
+            operations.push(Operation::add_stake(
+                operation_index,
+                Some(OperationStatusType::Success),
+                owner_address,
+                Some(AccountIdentifier::base_account(event.operator)),
+                Some(event.amount),
+            ));
+            operation_index += 1;
+        }
+
+        let unlock_stake_events = filter_events(
+            events,
+            store.unlock_stake_events.key(),
+            |event_key, event| parse_event::<UnlockStakeEvent>(event_key, event, "unlock stake"),
+        );
+        for event in unlock_stake_events {
+            operations.push(Operation::unlock_stake(
+                operation_index,
+                Some(OperationStatusType::Success),
+                owner_address,
+                Some(AccountIdentifier::base_account(event.operator)),
+                Some(event.amount),
+            ));
+            operation_index += 1;
+        }
+
+        // Distributions pay out both the staker's unlocked stake and rewards, and the operator's
+        // commission.  The coins themselves show up as deposits on the recipients.
+        let distribute_events =
+            filter_events(events, store.distribute_events.key(), |event_key, event| {
+                parse_event::<DistributeEvent>(event_key, event, "distribute")
+            });
+        for event in distribute_events {
+            operations.push(Operation::distribute_staking_rewards(
+                operation_index,
+                Some(OperationStatusType::Success),
+                event.recipient,
+                AccountIdentifier::base_account(event.operator),
+                AccountIdentifier::base_account(owner_address),
+                Some(event.amount),
+            ));
+            operation_index += 1;
+        }
+
         // Attach all set operators now, but with the total stake listed
         for mut operation in set_operator_operations.into_iter() {
             if let Some(inner) = operation.metadata.as_mut() {
@@ -1282,6 +1453,25 @@ fn get_amount_from_event(events: &[ContractEvent], event_key: &EventKey) -> Vec<
     })
 }
 
+/// Parses an event, skipping it if it can't be parsed
+fn parse_event<T: DeserializeOwned>(
+    event_key: &EventKey,
+    event: &ContractEvent,
+    event_name: &str,
+) -> Option<T> {
+    if let Ok(event) = bcs::from_bytes::<T>(event.event_data()) {
+        Some(event)
+    } else {
+        warn!(
+            "Failed to parse {} event!  Skipping for {}:{}",
+            event_name,
+            event_key.get_creator_address(),
+            event_key.get_creation_number()
+        );
+        None
+    }
+}
+
 fn filter_events<F: Fn(&EventKey, &ContractEvent) -> Option<T>, T>(
     events: &[ContractEvent],
     event_key: &EventKey,
@@ -1313,6 +1503,9 @@ pub enum InternalOperation {
     SetOperator(SetOperator),
     SetVoter(SetVoter),
     InitializeStakePool(InitializeStakePool),
+    AddStake(AddStake),
+    UnlockStake(UnlockStake),
+    DistributeStakingRewards(DistributeStakingRewards),
 }
 
 impl InternalOperation {
@@ -1415,6 +1608,76 @@ impl InternalOperation {
                                 }));
                             }
                         }
+                        Ok(OperationType::AddStake) => {
+                            if let (
+                                Some(OperationMetadata {
+                                    operator,
+                                    amount: Some(amount),
+                                    ..
+                                }),
+                                Some(account),
+                            ) = (&operation.metadata, &operation.account)
+                            {
+                                let operator = if let Some(operator) = operator {
+                                    Some(operator.account_address()?)
+                                } else {
+                                    None
+                                };
+                                return Ok(Self::AddStake(AddStake {
+                                    owner: account.account_address()?,
+                                    operator,
+                                    amount: amount.0,
+                                }));
+                            }
+                        }
+                        Ok(OperationType::UnlockStake) => {
+                            if let (
+                                Some(OperationMetadata {
+                                    operator,
+                                    amount: Some(amount),
+                                    ..
+                                }),
+                                Some(account),
+                            ) = (&operation.metadata, &operation.account)
+                            {
+                                let operator = if let Some(operator) = operator {
+                                    Some(operator.account_address()?)
+                                } else {
+                                    None
+                                };
+                                return Ok(Self::UnlockStake(UnlockStake {
+                                    owner: account.account_address()?,
+                                    operator,
+                                    amount: amount.0,
+                                }));
+                            }
+                        }
+                        Ok(OperationType::DistributeStakingRewards) => {
+                            if let (
+                                Some(OperationMetadata {
+                                    operator: Some(operator),
+                                    staker,
+                                    ..
+                                }),
+                                Some(account),
+                            ) = (&operation.metadata, &operation.account)
+                            {
+                                let sender = account.account_address()?;
+                                // Stakers distribute their own stake unless told otherwise
+                                let staker = if let Some(staker) = staker {
+                                    staker.account_address()?
+                                } else {
+                                    sender
+                                };
+                                return Ok(Self::DistributeStakingRewards(
+                                    DistributeStakingRewards {
+                                        sender,
+                                        operator: operator.account_address()?,
+                                        staker,
+                                    },
+                                ));
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -1441,6 +1704,9 @@ impl InternalOperation {
             Self::SetOperator(inner) => inner.owner,
             Self::SetVoter(inner) => inner.owner,
             Self::InitializeStakePool(inner) => inner.owner,
+            Self::AddStake(inner) => inner.owner,
+            Self::UnlockStake(inner) => inner.owner,
+            Self::DistributeStakingRewards(inner) => inner.sender,
         }
     }
 
@@ -1497,6 +1763,34 @@ impl InternalOperation {
                 ),
                 init_stake_pool.owner,
             ),
+            InternalOperation::AddStake(add_stake) => {
+                if let Some(operator) = add_stake.operator {
+                    (
+                        aptos_stdlib::staking_contract_add_stake(operator, add_stake.amount),
+                        add_stake.owner,
+                    )
+                } else {
+                    return Err(ApiError::InvalidInput(Some(
+                        "Add stake doesn't have an operator".to_string(),
+                    )));
+                }
+            }
+            InternalOperation::UnlockStake(unlock_stake) => {
+                if let Some(operator) = unlock_stake.operator {
+                    (
+                        aptos_stdlib::staking_contract_unlock_stake(operator, unlock_stake.amount),
+                        unlock_stake.owner,
+                    )
+                } else {
+                    return Err(ApiError::InvalidInput(Some(
+                        "Unlock stake doesn't have an operator".to_string(),
+                    )));
+                }
+            }
+            InternalOperation::DistributeStakingRewards(distribute) => (
+                aptos_stdlib::staking_contract_distribute(distribute.staker, distribute.operator),
+                distribute.sender,
+            ),
         })
     }
 }
@@ -1649,3 +1943,25 @@ pub struct InitializeStakePool {
     pub commission_percentage: u64,
     pub seed: Vec<u8>,
 }
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AddStake {
+    pub owner: AccountAddress,
+    pub operator: Option<AccountAddress>,
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UnlockStake {
+    pub owner: AccountAddress,
+    pub operator: Option<AccountAddress>,
+    pub amount: u64,
+}
+
+/// Distributes the unlocked stake of a staking contract, anyone can send it
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DistributeStakingRewards {
+    pub sender: AccountAddress,
+    pub operator: AccountAddress,
+    pub staker: AccountAddress,
+}
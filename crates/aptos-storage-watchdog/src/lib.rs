@@ -0,0 +1,162 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! The storage watchdog tracks the free space of the volume the storage is on, so the node can
+//! mitigate a shortage before RocksDB runs out of space, which may corrupt it. The node checks
+//! the free space periodically and records it here, and the components that take new writes
+//! check `api_writes_rejected` where they do.
+
+use anyhow::{format_err, Result};
+use aptos_config::config::StorageWatchdogConfig;
+use aptos_metrics_core::{register_int_gauge, IntGauge};
+use once_cell::sync::Lazy;
+use std::{
+    fmt, fs,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use sysinfo::{DiskExt, System, SystemExt};
+
+static API_WRITES_REJECTED: AtomicBool = AtomicBool::new(false);
+
+static AVAILABLE_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_storage_watchdog_available_bytes",
+        "The available space of the storage volume in bytes"
+    )
+    .unwrap()
+});
+
+static TOTAL_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_storage_watchdog_total_bytes",
+        "The size of the storage volume in bytes"
+    )
+    .unwrap()
+});
+
+static LEVEL: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_storage_watchdog_level",
+        "How short the storage volume is on space: 0 is ok, 1 is warning and 2 is critical"
+    )
+    .unwrap()
+});
+
+static MITIGATING: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_storage_watchdog_mitigating",
+        "Whether the mitigations of a storage space shortage are applied"
+    )
+    .unwrap()
+});
+
+/// How short the storage volume is on space, from the thresholds of the config
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum DiskSpaceLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl DiskSpaceLevel {
+    pub fn new(disk_space: &DiskSpace, config: &StorageWatchdogConfig) -> Self {
+        let free_space_percent = disk_space.free_space_percent();
+        if free_space_percent < config.critical_free_space_percent as f64 {
+            DiskSpaceLevel::Critical
+        } else if free_space_percent < config.warning_free_space_percent as f64 {
+            DiskSpaceLevel::Warning
+        } else {
+            DiskSpaceLevel::Ok
+        }
+    }
+}
+
+/// The space of a volume
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DiskSpace {
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl DiskSpace {
+    /// Measures the space of the volume the path is on
+    pub fn of(path: &Path) -> Result<Self> {
+        let path = fs::canonicalize(path)?;
+        let mut system = System::new();
+        system.refresh_disks_list();
+        // The volume is mounted on the longest mount point the path is under
+        system
+            .disks()
+            .iter()
+            .filter(|disk| path.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len())
+            .map(|disk| DiskSpace {
+                available_bytes: disk.available_space(),
+                total_bytes: disk.total_space(),
+            })
+            .ok_or_else(|| format_err!("No volume is mounted for {:?}", path))
+    }
+
+    pub fn free_space_percent(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        self.available_bytes as f64 * 100.0 / self.total_bytes as f64
+    }
+}
+
+impl fmt::Display for DiskSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} bytes available ({:.1}%)",
+            self.available_bytes,
+            self.total_bytes,
+            self.free_space_percent()
+        )
+    }
+}
+
+/// Records the last check of the storage volume in the metrics
+pub fn record_check(disk_space: &DiskSpace, level: DiskSpaceLevel, mitigating: bool) {
+    AVAILABLE_BYTES.set(disk_space.available_bytes as i64);
+    TOTAL_BYTES.set(disk_space.total_bytes as i64);
+    LEVEL.set(level as i64);
+    MITIGATING.set(mitigating as i64);
+}
+
+/// Whether the transactions submitted to the API are rejected until the storage has space again
+pub fn api_writes_rejected() -> bool {
+    API_WRITES_REJECTED.load(Ordering::Acquire)
+}
+
+pub fn set_api_writes_rejected(rejected: bool) {
+    API_WRITES_REJECTED.store(rejected, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_space_level() {
+        let config = StorageWatchdogConfig::default();
+        let level = |available_bytes| {
+            DiskSpaceLevel::new(
+                &DiskSpace {
+                    available_bytes,
+                    total_bytes: 100,
+                },
+                &config,
+            )
+        };
+        assert_eq!(level(50), DiskSpaceLevel::Ok);
+        assert_eq!(level(20), DiskSpaceLevel::Ok);
+        assert_eq!(level(15), DiskSpaceLevel::Warning);
+        assert_eq!(level(5), DiskSpaceLevel::Critical);
+        assert_eq!(level(0), DiskSpaceLevel::Critical);
+    }
+}
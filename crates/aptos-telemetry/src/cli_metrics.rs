@@ -1,7 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{service, utils};
+use crate::{
+    service::{self, EventDestination},
+    utils,
+};
 use aptos_logger::debug;
 use aptos_telemetry_service::types::telemetry::TelemetryEvent;
 use std::{collections::BTreeMap, time::Duration};
@@ -37,9 +40,16 @@ pub async fn send_cli_telemetry_event(
 
     // Send the event (we block on the join handle to ensure the
     // event is processed before terminating the cli command).
-    let join_handle =
-        service::send_telemetry_event_with_ip(user_id, "NO_CHAIN".into(), None, telemetry_event)
-            .await;
+    let join_handle = service::send_telemetry_event_with_ip(
+        user_id,
+        "NO_CHAIN".into(),
+        EventDestination::Remote {
+            telemetry_sender: None,
+            include_ip_address: true,
+        },
+        telemetry_event,
+    )
+    .await;
     if let Err(error) = join_handle.await {
         debug!(
             "Failed to send telemetry event with join error: {:?}",
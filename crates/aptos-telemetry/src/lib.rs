@@ -5,6 +5,7 @@
 
 mod constants;
 mod core_metrics;
+mod local_exporter;
 mod metrics;
 mod network_metrics;
 mod sender;
@@ -0,0 +1,149 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Writes the telemetry payloads to local files instead of the telemetry service, for operators
+//! that don't allow any data to leave their nodes.
+
+use crate::metrics::{increment_log_ingest_failures_by, increment_log_ingest_successes_by};
+use anyhow::Context;
+use aptos_config::config::LocalTelemetryConfig;
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_telemetry_service::types::telemetry::TelemetryDump;
+use prometheus::Registry;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const CUSTOM_EVENTS_FILE: &str = "custom_events.jsonl";
+const LOGS_FILE: &str = "logs.jsonl";
+const METRICS_FILE: &str = "metrics.prom";
+
+/// The writes block on the file system, so they're run on the blocking pool of the runtime.
+pub(crate) struct LocalExporter {
+    dir: PathBuf,
+    max_file_size_bytes: u64,
+    // Serializes the appends and rotations of the files
+    lock: Mutex<()>,
+}
+
+impl LocalExporter {
+    pub(crate) fn new(config: &LocalTelemetryConfig) -> anyhow::Result<Self> {
+        fs::create_dir_all(&config.dir).with_context(|| {
+            format!("Unable to create the telemetry directory {:?}", config.dir)
+        })?;
+        Ok(Self {
+            dir: config.dir.clone(),
+            max_file_size_bytes: config.max_file_size_bytes,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub(crate) fn write_custom_event(&self, event_name: &str, telemetry_dump: &TelemetryDump) {
+        let result = serde_json::to_string(telemetry_dump)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| self.append_lines(CUSTOM_EVENTS_FILE, &[line]));
+        match result {
+            Ok(()) => debug!("Wrote telemetry event {} locally", event_name),
+            Err(error) => warn!(
+                "Failed to write telemetry event {} locally: {}",
+                event_name, error
+            ),
+        }
+    }
+
+    pub(crate) fn write_logs(&self, batch: Vec<String>) {
+        let len = batch.len() as u64;
+        match self.append_lines(LOGS_FILE, &batch) {
+            Ok(()) => increment_log_ingest_successes_by(len),
+            Err(error) => {
+                increment_log_ingest_failures_by(len);
+                debug!("Failed to write {} logs locally: {}", len, error);
+            }
+        }
+    }
+
+    /// Replaces the metrics file with the current metrics of the registry
+    pub(crate) fn write_metrics(&self, registry: &Registry) {
+        let result = prometheus::TextEncoder::new()
+            .encode_to_string(&registry.gather())
+            .map_err(anyhow::Error::from)
+            .and_then(|metrics| {
+                // Write then rename, so a collector never reads a partial file
+                let path = self.dir.join(METRICS_FILE);
+                let tmp_path = path.with_extension("prom.tmp");
+                fs::write(&tmp_path, metrics)?;
+                fs::rename(&tmp_path, &path)?;
+                Ok(())
+            });
+        if let Err(error) = result {
+            debug!("Failed to write the Prometheus metrics locally: {}", error);
+        }
+    }
+
+    fn append_lines(&self, file_name: &str, lines: &[String]) -> anyhow::Result<()> {
+        let mut content = String::new();
+        for line in lines {
+            content.push_str(line.trim_end());
+            content.push('\n');
+        }
+
+        let _lock = self.lock.lock();
+        let path = self.dir.join(file_name);
+        if let Ok(metadata) = fs::metadata(&path) {
+            if metadata.len() + content.len() as u64 > self.max_file_size_bytes {
+                fs::rename(&path, self.dir.join(format!("{}.1", file_name)))?;
+            }
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_temppath::TempPath;
+    use prometheus::{IntCounter, Opts};
+
+    fn exporter(dir: &Path, max_file_size_bytes: u64) -> LocalExporter {
+        LocalExporter::new(&LocalTelemetryConfig {
+            dir: dir.to_path_buf(),
+            max_file_size_bytes,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_write_logs_and_rotate() {
+        let dir = TempPath::new();
+        let exporter = exporter(dir.path(), 10);
+        exporter.write_logs(vec!["{\"a\":1}".into()]);
+        exporter.write_logs(vec!["{\"b\":2}".into()]);
+
+        let logs = |file_name: &str| fs::read_to_string(dir.path().join(file_name)).unwrap();
+        assert_eq!(logs(LOGS_FILE), "{\"b\":2}\n");
+        assert_eq!(logs("logs.jsonl.1"), "{\"a\":1}\n");
+    }
+
+    #[test]
+    fn test_write_metrics() {
+        let dir = TempPath::new();
+        let exporter = exporter(dir.path(), 1024);
+        let registry = Registry::new();
+        let counter = IntCounter::with_opts(Opts::new("test_counter", "test counter")).unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        counter.inc_by(3);
+
+        exporter.write_metrics(&registry);
+        let metrics = fs::read_to_string(dir.path().join(METRICS_FILE)).unwrap();
+        assert!(metrics.contains("test_counter 3"));
+    }
+}
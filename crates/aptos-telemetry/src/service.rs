@@ -3,7 +3,8 @@
 
 #![forbid(unsafe_code)]
 
-use aptos_config::config::NodeConfig;
+use aptos_audit::{audit, AuditEvent};
+use aptos_config::config::{LocalTelemetryConfig, NodeConfig, TelemetryConfig};
 use aptos_logger::{
     aptos_logger::RUST_LOG_TELEMETRY, prelude::*, telemetry_log_writer::TelemetryLog,
     LoggerFilterUpdater,
@@ -12,6 +13,7 @@ use aptos_telemetry_service::types::telemetry::{TelemetryDump, TelemetryEvent};
 use aptos_types::chain_id::{ChainId, NamedChain};
 use futures::channel::mpsc::{self, Receiver};
 use once_cell::sync::Lazy;
+use prometheus::default_registry;
 use rand::Rng;
 use rand_core::OsRng;
 use serde::Deserialize;
@@ -19,7 +21,10 @@ use std::{
     collections::BTreeMap,
     env,
     future::Future,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
@@ -30,8 +35,8 @@ use tokio::{
 use uuid::Uuid;
 
 use crate::{
-    constants::*, core_metrics::create_core_metric_telemetry_event, metrics,
-    network_metrics::create_network_metric_telemetry_event, sender::TelemetrySender,
+    constants::*, core_metrics::create_core_metric_telemetry_event, local_exporter::LocalExporter,
+    metrics, network_metrics::create_network_metric_telemetry_event, sender::TelemetrySender,
     system_information::create_system_info_telemetry_event,
     telemetry_log_sender::TelemetryLogSender, utils::create_build_info_telemetry_event,
 };
@@ -56,6 +61,19 @@ static TELEMETRY_TOKEN: Lazy<String> = Lazy::new(|| {
     format!("TOKEN_{:?}", token)
 });
 
+/// Where the custom events go
+#[derive(Clone)]
+pub(crate) enum EventDestination {
+    /// GA4, and the telemetry service if it's configured for the chain
+    Remote {
+        telemetry_sender: Option<TelemetrySender>,
+        // Whether the events hold the IP address of the node
+        include_ip_address: bool,
+    },
+    /// The local telemetry files only
+    Local(Arc<LocalExporter>),
+}
+
 /// Returns true iff telemetry is disabled
 #[inline]
 pub fn telemetry_is_disabled() -> bool {
@@ -149,6 +167,20 @@ async fn spawn_telemetry_service(
     remote_log_rx: Option<mpsc::Receiver<TelemetryLog>>,
     logger_filter_update_job: Option<LoggerFilterUpdater>,
 ) {
+    if let Some(local_config) = node_config.telemetry.local_only.clone() {
+        if let Some(job) = logger_filter_update_job {
+            tokio::spawn(job.run());
+        }
+        spawn_local_telemetry_service(
+            &local_config,
+            node_config,
+            chain_id,
+            build_info,
+            remote_log_rx,
+        );
+        return;
+    }
+
     let telemetry_svc_url = env::var(ENV_TELEMETRY_SERVICE_URL).unwrap_or_else(|_| {
         if chain_id == ChainId::mainnet() || chain_id == ChainId::new(NamedChain::PREMAINNET.id()) {
             MAINNET_TELEMETRY_SERVICE_URL.into()
@@ -168,7 +200,10 @@ async fn spawn_telemetry_service(
         // This is a temporary workaround while we deprecate and remove GA4 completely.
         let peer_id = fetch_peer_id(&node_config);
         let handle = tokio::spawn(custom_event_sender(
-            None,
+            EventDestination::Remote {
+                telemetry_sender: None,
+                include_ip_address: node_config.telemetry.enable_peer_info,
+            },
             peer_id,
             chain_id,
             node_config.clone(),
@@ -188,9 +223,18 @@ async fn spawn_telemetry_service(
         }
     }
 
-    try_spawn_log_sender(telemetry_sender.clone(), remote_log_rx);
-    try_spawn_metrics_sender(telemetry_sender.clone());
-    try_spawn_custom_event_sender(node_config, telemetry_sender.clone(), chain_id, build_info);
+    let telemetry_config = node_config.telemetry.clone();
+    try_spawn_log_sender(
+        &telemetry_config,
+        TelemetryLogSender::new(telemetry_sender.clone()),
+        remote_log_rx,
+    );
+    try_spawn_metrics_sender(&telemetry_config, telemetry_sender.clone());
+    let destination = EventDestination::Remote {
+        telemetry_sender: Some(telemetry_sender.clone()),
+        include_ip_address: telemetry_config.enable_peer_info,
+    };
+    try_spawn_custom_event_sender(node_config, destination, chain_id, build_info);
     try_spawn_log_env_poll_task(telemetry_sender);
 
     // Run the logger filter update job within the telemetry runtime.
@@ -201,6 +245,59 @@ async fn spawn_telemetry_service(
     info!("Telemetry service started!");
 }
 
+/// Writes the telemetry to the local files, nothing is sent to the telemetry service nor GA4
+fn spawn_local_telemetry_service(
+    local_config: &LocalTelemetryConfig,
+    node_config: NodeConfig,
+    chain_id: ChainId,
+    build_info: BTreeMap<String, String>,
+    remote_log_rx: Option<mpsc::Receiver<TelemetryLog>>,
+) {
+    let exporter = match LocalExporter::new(local_config) {
+        Ok(exporter) => Arc::new(exporter),
+        Err(error) => {
+            error!(
+                "Aptos telemetry is disabled, it can't be written locally: {}",
+                error
+            );
+            return;
+        }
+    };
+
+    let telemetry_config = node_config.telemetry.clone();
+    try_spawn_log_sender(
+        &telemetry_config,
+        TelemetryLogSender::new_local(exporter.clone()),
+        remote_log_rx,
+    );
+    if telemetry_config.enable_metrics && enable_prometheus_push_metrics() {
+        let exporter = exporter.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                time::interval(Duration::from_secs(PROMETHEUS_PUSH_METRICS_FREQ_SECS));
+            loop {
+                interval.tick().await;
+                let exporter = exporter.clone();
+                if let Err(error) =
+                    tokio::task::spawn_blocking(move || exporter.write_metrics(default_registry()))
+                        .await
+                {
+                    warn!("Failed to write the Prometheus metrics locally: {}", error);
+                }
+            }
+        });
+    }
+    let dir = exporter.dir().to_path_buf();
+    try_spawn_custom_event_sender(
+        node_config,
+        EventDestination::Local(exporter),
+        chain_id,
+        build_info,
+    );
+
+    info!("Telemetry service started, writing to {:?} only!", dir);
+}
+
 fn try_spawn_log_env_poll_task(sender: TelemetrySender) {
     if enable_log_env_polling() {
         tokio::spawn(async move {
@@ -208,12 +305,11 @@ fn try_spawn_log_env_poll_task(sender: TelemetrySender) {
             let mut interval = time::interval(Duration::from_secs(LOG_ENV_POLL_FREQ_SECS));
             loop {
                 interval.tick().await;
+                let previous_value = env::var(RUST_LOG_TELEMETRY).ok();
                 if let Some(env) = sender.get_telemetry_log_env().await {
                     info!(
                         "Updating {} env variable: previous value: {:?}, new value: {}",
-                        RUST_LOG_TELEMETRY,
-                        env::var(RUST_LOG_TELEMETRY).ok(),
-                        env
+                        RUST_LOG_TELEMETRY, previous_value, env
                     );
                     env::set_var(RUST_LOG_TELEMETRY, env)
                 } else if let Some(ref value) = original_value {
@@ -221,6 +317,18 @@ fn try_spawn_log_env_poll_task(sender: TelemetrySender) {
                 } else {
                     env::remove_var(RUST_LOG_TELEMETRY)
                 }
+
+                // The remote log filter is applied by the logger filter update job
+                let new_value = env::var(RUST_LOG_TELEMETRY).ok();
+                if new_value != previous_value {
+                    audit(
+                        AuditEvent::new("config.reload")
+                            .actor("telemetry-service")
+                            .detail("variable", RUST_LOG_TELEMETRY)
+                            .detail("previous_value", format!("{:?}", previous_value))
+                            .detail("new_value", format!("{:?}", new_value)),
+                    );
+                }
             }
         });
     }
@@ -228,7 +336,7 @@ fn try_spawn_log_env_poll_task(sender: TelemetrySender) {
 
 fn try_spawn_custom_event_sender(
     node_config: NodeConfig,
-    telemetry_sender: TelemetrySender,
+    destination: EventDestination,
     chain_id: ChainId,
     build_info: BTreeMap<String, String>,
 ) {
@@ -236,7 +344,7 @@ fn try_spawn_custom_event_sender(
         // Spawn the custom event sender
         let peer_id = fetch_peer_id(&node_config);
         tokio::spawn(custom_event_sender(
-            Some(telemetry_sender),
+            destination,
             peer_id,
             chain_id,
             node_config,
@@ -245,8 +353,8 @@ fn try_spawn_custom_event_sender(
     }
 }
 
-fn try_spawn_metrics_sender(telemetry_sender: TelemetrySender) {
-    if enable_prometheus_push_metrics() {
+fn try_spawn_metrics_sender(telemetry_config: &TelemetryConfig, telemetry_sender: TelemetrySender) {
+    if telemetry_config.enable_metrics && enable_prometheus_push_metrics() {
         tokio::spawn(async move {
             // Periodically send ALL prometheus metrics (This replaces the previous core and network metrics implementation)
             let mut interval =
@@ -260,12 +368,12 @@ fn try_spawn_metrics_sender(telemetry_sender: TelemetrySender) {
 }
 
 fn try_spawn_log_sender(
-    telemetry_sender: TelemetrySender,
+    telemetry_config: &TelemetryConfig,
+    telemetry_log_sender: TelemetryLogSender,
     remote_log_rx: Option<Receiver<TelemetryLog>>,
 ) {
-    if enable_push_logs() {
+    if telemetry_config.enable_logs && enable_push_logs() {
         if let Some(rx) = remote_log_rx {
-            let telemetry_log_sender = TelemetryLogSender::new(telemetry_sender);
             tokio::spawn(telemetry_log_sender.start(rx));
         }
     }
@@ -280,10 +388,17 @@ fn fetch_peer_id(node_config: &NodeConfig) -> String {
     }
 }
 
-async fn run_function_periodically<Fut>(interval_seconds: u64, function_to_run: impl Fn() -> Fut)
-where
+/// Runs the function periodically, unless it's not enabled
+async fn run_function_periodically<Fut>(
+    enabled: bool,
+    interval_seconds: u64,
+    function_to_run: impl Fn() -> Fut,
+) where
     Fut: Future<Output = ()>,
 {
+    if !enabled {
+        return;
+    }
     let mut interval = time::interval(Duration::from_secs(interval_seconds));
     loop {
         interval.tick().await;
@@ -293,55 +408,70 @@ where
 
 /// Spawns the dedicated telemetry service that operates periodically
 async fn custom_event_sender(
-    telemetry_sender: Option<TelemetrySender>,
+    destination: EventDestination,
     peer_id: String,
     chain_id: ChainId,
     node_config: NodeConfig,
     build_info: BTreeMap<String, String>,
 ) {
+    let telemetry_config = &node_config.telemetry;
     futures::future::join5(
         // Periodically send build information
-        run_function_periodically(NODE_BUILD_INFO_FREQ_SECS, || {
-            send_build_information(
-                peer_id.clone(),
-                chain_id.to_string(),
-                build_info.clone(),
-                telemetry_sender.clone(),
-            )
-        }),
+        run_function_periodically(
+            telemetry_config.enable_build_info,
+            NODE_BUILD_INFO_FREQ_SECS,
+            || {
+                send_build_information(
+                    peer_id.clone(),
+                    chain_id.to_string(),
+                    build_info.clone(),
+                    destination.clone(),
+                )
+            },
+        ),
         // Periodically send system information
-        run_function_periodically(NODE_SYS_INFO_FREQ_SECS, || {
-            send_system_information(
-                peer_id.clone(),
-                chain_id.to_string(),
-                telemetry_sender.clone(),
-            )
-        }),
+        run_function_periodically(
+            telemetry_config.enable_build_info,
+            NODE_SYS_INFO_FREQ_SECS,
+            || send_system_information(peer_id.clone(), chain_id.to_string(), destination.clone()),
+        ),
         // Periodically send node core metrics
-        run_function_periodically(NODE_CORE_METRICS_FREQ_SECS, || {
-            send_node_core_metrics(
-                peer_id.clone(),
-                chain_id.to_string(),
-                &node_config,
-                telemetry_sender.clone(),
-            )
-        }),
+        run_function_periodically(
+            telemetry_config.enable_metrics,
+            NODE_CORE_METRICS_FREQ_SECS,
+            || {
+                send_node_core_metrics(
+                    peer_id.clone(),
+                    chain_id.to_string(),
+                    &node_config,
+                    destination.clone(),
+                )
+            },
+        ),
         // Periodically send node network metrics
-        run_function_periodically(NODE_NETWORK_METRICS_FREQ_SECS, || {
-            send_node_network_metrics(
-                peer_id.clone(),
-                chain_id.to_string(),
-                telemetry_sender.clone(),
-            )
-        }),
-        run_function_periodically(NODE_CONFIG_FREQ_SECS, || {
-            send_node_config(
-                peer_id.clone(),
-                chain_id.to_string(),
-                &node_config,
-                telemetry_sender.clone(),
-            )
-        }),
+        run_function_periodically(
+            telemetry_config.enable_peer_info,
+            NODE_NETWORK_METRICS_FREQ_SECS,
+            || {
+                send_node_network_metrics(
+                    peer_id.clone(),
+                    chain_id.to_string(),
+                    destination.clone(),
+                )
+            },
+        ),
+        run_function_periodically(
+            telemetry_config.enable_build_info,
+            NODE_CONFIG_FREQ_SECS,
+            || {
+                send_node_config(
+                    peer_id.clone(),
+                    chain_id.to_string(),
+                    &node_config,
+                    destination.clone(),
+                )
+            },
+        ),
     )
     .await;
 }
@@ -351,10 +481,10 @@ async fn send_build_information(
     peer_id: String,
     chain_id: String,
     build_info: BTreeMap<String, String>,
-    telemetry_sender: Option<TelemetrySender>,
+    destination: EventDestination,
 ) {
     let telemetry_event = create_build_info_telemetry_event(build_info).await;
-    send_telemetry_event_with_ip(peer_id, chain_id, telemetry_sender, telemetry_event).await;
+    send_telemetry_event_with_ip(peer_id, chain_id, destination, telemetry_event).await;
 }
 
 /// Collects and sends the core node metrics via telemetry
@@ -362,7 +492,7 @@ async fn send_node_config(
     peer_id: String,
     chain_id: String,
     node_config: &NodeConfig,
-    telemetry_sender: Option<TelemetrySender>,
+    destination: EventDestination,
 ) {
     let node_config: BTreeMap<String, String> = serde_json::to_value(node_config)
         .map(|value| {
@@ -381,7 +511,7 @@ async fn send_node_config(
         name: APTOS_NODE_CONFIG_EVENT_NAME.into(),
         params: node_config,
     };
-    send_telemetry_event_with_ip(peer_id, chain_id, telemetry_sender, telemetry_event).await;
+    send_telemetry_event_with_ip(peer_id, chain_id, destination, telemetry_event).await;
 }
 
 /// Collects and sends the core node metrics via telemetry
@@ -389,30 +519,26 @@ async fn send_node_core_metrics(
     peer_id: String,
     chain_id: String,
     node_config: &NodeConfig,
-    telemetry_sender: Option<TelemetrySender>,
+    destination: EventDestination,
 ) {
     let telemetry_event = create_core_metric_telemetry_event(node_config).await;
-    send_telemetry_event_with_ip(peer_id, chain_id, telemetry_sender, telemetry_event).await;
+    send_telemetry_event_with_ip(peer_id, chain_id, destination, telemetry_event).await;
 }
 
 /// Collects and sends the node network metrics via telemetry
 async fn send_node_network_metrics(
     peer_id: String,
     chain_id: String,
-    telemetry_sender: Option<TelemetrySender>,
+    destination: EventDestination,
 ) {
     let telemetry_event = create_network_metric_telemetry_event().await;
-    send_telemetry_event_with_ip(peer_id, chain_id, telemetry_sender, telemetry_event).await;
+    send_telemetry_event_with_ip(peer_id, chain_id, destination, telemetry_event).await;
 }
 
 /// Collects and sends the system information via telemetry
-async fn send_system_information(
-    peer_id: String,
-    chain_id: String,
-    telemetry_sender: Option<TelemetrySender>,
-) {
+async fn send_system_information(peer_id: String, chain_id: String, destination: EventDestination) {
     let telemetry_event = create_system_info_telemetry_event().await;
-    send_telemetry_event_with_ip(peer_id, chain_id, telemetry_sender, telemetry_event).await;
+    send_telemetry_event_with_ip(peer_id, chain_id, destination, telemetry_event).await;
 }
 
 /// Fetches the IP address and sends the given telemetry event
@@ -421,18 +547,25 @@ async fn send_system_information(
 pub(crate) async fn send_telemetry_event_with_ip(
     peer_id: String,
     chain_id: String,
-    telemetry_sender: Option<TelemetrySender>,
+    destination: EventDestination,
     telemetry_event: TelemetryEvent,
 ) -> JoinHandle<()> {
-    // Update the telemetry event with the ip address and random token
+    // Update the telemetry event with the ip address and random token.
+    // The local telemetry doesn't need the address, nor to ping a remote url for it.
     let TelemetryEvent { name, mut params } = telemetry_event;
-    params.insert(IP_ADDRESS_KEY.to_string(), get_origin_ip().await);
+    if let EventDestination::Remote {
+        include_ip_address: true,
+        ..
+    } = destination
+    {
+        params.insert(IP_ADDRESS_KEY.to_string(), get_origin_ip().await);
+    }
     params.insert(TELEMETRY_TOKEN_KEY.to_string(), TELEMETRY_TOKEN.clone());
     params.insert(CHAIN_ID_KEY.into(), chain_id);
     let telemetry_event = TelemetryEvent { name, params };
 
     // Send the telemetry event
-    send_telemetry_event(peer_id, telemetry_sender, telemetry_event).await
+    send_telemetry_event(peer_id, destination, telemetry_event).await
 }
 
 /// Gets the IP origin of the machine by pinging a url.
@@ -451,15 +584,9 @@ async fn get_origin_ip() -> String {
 /// Sends the given event and params to the telemetry endpoint
 async fn send_telemetry_event(
     peer_id: String,
-    telemetry_sender: Option<TelemetrySender>,
+    destination: EventDestination,
     telemetry_event: TelemetryEvent,
 ) -> JoinHandle<()> {
-    // Parse the Google analytics env variables
-    let api_secret =
-        env::var(ENV_GA_API_SECRET).unwrap_or_else(|_| APTOS_GA_API_SECRET.to_string());
-    let measurement_id =
-        env::var(ENV_GA_MEASUREMENT_ID).unwrap_or_else(|_| APTOS_GA_MEASUREMENT_ID.to_string());
-
     // Create and send the telemetry dump
     let event_name = telemetry_event.name.clone();
     let timestamp_micros = match SystemTime::now().duration_since(UNIX_EPOCH) {
@@ -472,6 +599,23 @@ async fn send_telemetry_event(
         timestamp_micros,
         events: vec![telemetry_event],
     };
+    let telemetry_sender = match destination {
+        EventDestination::Remote {
+            telemetry_sender, ..
+        } => telemetry_sender,
+        EventDestination::Local(exporter) => {
+            return tokio::task::spawn_blocking(move || {
+                exporter.write_custom_event(&event_name, &telemetry_dump);
+            });
+        }
+    };
+
+    // Parse the Google analytics env variables
+    let api_secret =
+        env::var(ENV_GA_API_SECRET).unwrap_or_else(|_| APTOS_GA_API_SECRET.to_string());
+    let measurement_id =
+        env::var(ENV_GA_MEASUREMENT_ID).unwrap_or_else(|_| APTOS_GA_MEASUREMENT_ID.to_string());
+
     let _handle = spawn_telemetry_service_event_sender(
         event_name.clone(),
         telemetry_sender,
@@ -1,12 +1,14 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::local_exporter::LocalExporter;
 use crate::metrics::increment_log_ingest_too_large_by;
 use crate::sender::TelemetrySender;
 use aptos_logger::prelude::*;
 use aptos_logger::telemetry_log_writer::TelemetryLog;
 use futures::channel::mpsc;
 use futures::StreamExt;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::interval;
 use tokio_stream::wrappers::IntervalStream;
@@ -14,9 +16,31 @@ use tokio_stream::wrappers::IntervalStream;
 const MAX_BYTES: usize = 128 * 1024;
 const MAX_BATCH_TIME: Duration = Duration::from_secs(5);
 
+/// Where the batches of logs go
+enum LogDestination {
+    Remote(TelemetrySender),
+    Local(Arc<LocalExporter>),
+}
+
+impl LogDestination {
+    async fn send_logs(&self, batch: Vec<String>) {
+        match self {
+            LogDestination::Remote(sender) => sender.try_send_logs(batch).await,
+            LogDestination::Local(exporter) => {
+                let exporter = exporter.clone();
+                if let Err(error) =
+                    tokio::task::spawn_blocking(move || exporter.write_logs(batch)).await
+                {
+                    warn!("Failed to write the logs locally: {}", error);
+                }
+            }
+        }
+    }
+}
+
 /// Buffered
 pub(crate) struct TelemetryLogSender {
-    sender: TelemetrySender,
+    sender: LogDestination,
     batch: Vec<String>,
     max_bytes: usize,
     current_bytes: usize,
@@ -24,8 +48,17 @@ pub(crate) struct TelemetryLogSender {
 
 impl TelemetryLogSender {
     pub fn new(sender: TelemetrySender) -> Self {
+        // TODO: use an existing sender?
+        Self::with_destination(LogDestination::Remote(sender))
+    }
+
+    /// Writes the logs to the local telemetry files instead of sending them
+    pub fn new_local(exporter: Arc<LocalExporter>) -> Self {
+        Self::with_destination(LogDestination::Local(exporter))
+    }
+
+    fn with_destination(sender: LogDestination) -> Self {
         Self {
-            // TODO: use an existing sender?
             sender,
             batch: Vec::new(),
             max_bytes: MAX_BYTES,
@@ -59,7 +92,7 @@ impl TelemetryLogSender {
         match log {
             TelemetryLog::Log(log) => {
                 if let Some(batch) = self.add_to_batch(log) {
-                    self.sender.try_send_logs(batch).await;
+                    self.sender.send_logs(batch).await;
                 }
             }
             TelemetryLog::Flush(tx) => {
@@ -72,7 +105,7 @@ impl TelemetryLogSender {
     pub async fn flush_batch(&mut self) {
         if !self.batch.is_empty() {
             let drained = self.drain_batch();
-            self.sender.try_send_logs(drained).await;
+            self.sender.send_logs(drained).await;
         }
     }
 
@@ -0,0 +1,26 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_metrics_core::{
+    register_int_counter, register_int_counter_vec, IntCounter, IntCounterVec,
+};
+use once_cell::sync::Lazy;
+
+/// Counter of spans by what happened to them: exported, failed (to export) or dropped
+pub(crate) static SPANS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_tracing_spans",
+        "Number of transaction trace spans, by result",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Counter of sampled transactions that weren't traced because too many already are
+pub(crate) static DROPPED_TRACES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_tracing_dropped_traces",
+        "Number of sampled transactions not traced because of the limit of active traces"
+    )
+    .unwrap()
+});
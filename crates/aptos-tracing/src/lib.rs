@@ -0,0 +1,398 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Traces of sampled transactions through the node, exported to an OpenTelemetry collector over
+//! OTLP/HTTP.
+//!
+//! A trace is started when a sampled transaction is submitted to the API, continuing the trace of
+//! the W3C `traceparent` header of the request if it has one. The stages the transaction then goes
+//! through (mempool admission, consensus inclusion, execution and commit) each record a span of the
+//! trace, which is looked up by the hash of the transaction, until the trace is ended when the
+//! transaction is committed. Trace contexts aren't sent to other nodes, so a trace only covers what
+//! happens to the transaction on the node it was submitted to.
+
+mod counters;
+mod otlp;
+
+use aptos_config::config::TracingConfig;
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use once_cell::sync::OnceCell;
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The header trace contexts are propagated in, see https://www.w3.org/TR/trace-context/
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+static TRACER: OnceCell<Tracer> = OnceCell::new();
+
+/// The stages of the processing of a transaction a span is recorded for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stage {
+    Api,
+    MempoolAdmission,
+    ConsensusInclusion,
+    Execution,
+    Commit,
+}
+
+impl Stage {
+    pub fn span_name(&self) -> &'static str {
+        match self {
+            Stage::Api => "api.submit_transaction",
+            Stage::MempoolAdmission => "mempool.admission",
+            Stage::ConsensusInclusion => "consensus.inclusion",
+            Stage::Execution => "execution.execute_block",
+            Stage::Commit => "storage.commit",
+        }
+    }
+}
+
+/// A W3C trace context, identifying the span a trace is continued from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// Parses a `traceparent` header e.g. `00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01`
+    pub fn from_traceparent(header: &str) -> Option<Self> {
+        let mut parts = header.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+        // Later versions may only add fields
+        if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        let mut context = TraceContext {
+            trace_id: [0; 16],
+            span_id: [0; 8],
+            sampled: false,
+        };
+        hex::decode_to_slice(trace_id, &mut context.trace_id).ok()?;
+        hex::decode_to_slice(span_id, &mut context.span_id).ok()?;
+        let mut flags_byte = [0; 1];
+        hex::decode_to_slice(flags, &mut flags_byte).ok()?;
+        context.sampled = flags_byte[0] & 1 == 1;
+
+        if context.trace_id == [0; 16] || context.span_id == [0; 8] {
+            return None;
+        }
+        Some(context)
+    }
+
+    pub fn to_traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            hex::encode(self.trace_id),
+            hex::encode(self.span_id),
+            self.sampled as u8
+        )
+    }
+}
+
+/// A finished span, waiting to be exported.
+#[derive(Clone, Debug)]
+pub(crate) struct Span {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: &'static str,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Vec<(&'static str, String)>,
+    pub error: Option<String>,
+}
+
+/// A trace of a transaction that isn't committed yet.
+struct ActiveTrace {
+    trace_id: [u8; 16],
+    root_span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    start: SystemTime,
+    deadline: Instant,
+}
+
+struct Tracer {
+    config: TracingConfig,
+    traces: Mutex<HashMap<HashValue, ActiveTrace>>,
+    // The number of traces, so checking if there are any doesn't need the lock
+    num_traces: AtomicUsize,
+    spans: SyncSender<Span>,
+}
+
+/// Starts tracing transactions if it's enabled in the config, exporting the spans from a
+/// background thread.
+pub fn init(config: &TracingConfig) {
+    let endpoint = match &config.otlp_endpoint {
+        Some(endpoint) => format!("{}/v1/traces", endpoint.trim_end_matches('/')),
+        None => return,
+    };
+    let (sender, receiver) = mpsc::sync_channel(config.max_queued_spans);
+    let tracer = Tracer {
+        config: config.clone(),
+        traces: Mutex::new(HashMap::new()),
+        num_traces: AtomicUsize::new(0),
+        spans: sender,
+    };
+    if TRACER.set(tracer).is_err() {
+        warn!("Transaction tracing is already initialized");
+        return;
+    }
+
+    thread::Builder::new()
+        .name("otlp-exporter".into())
+        .spawn(move || export_spans(endpoint, receiver))
+        .expect("Failed to spawn the OTLP exporter thread");
+    info!(
+        "Transaction tracing enabled, exporting to {:?}",
+        config.otlp_endpoint
+    );
+}
+
+/// Whether any transaction is being traced, so callers can skip e.g. hashing transactions to look
+/// up their traces otherwise.
+pub fn is_tracing() -> bool {
+    TRACER.get().map_or(false, |tracer| {
+        tracer.num_traces.load(Ordering::Relaxed) > 0
+    })
+}
+
+/// Starts a trace of the transaction with the hash returned by `txn_hash`, if it's sampled.
+/// Returns the hash the trace is looked up with, or `None` if the transaction isn't traced.
+pub fn start_trace(
+    txn_hash: impl FnOnce() -> HashValue,
+    parent: Option<TraceContext>,
+    start: SystemTime,
+) -> Option<HashValue> {
+    let tracer = TRACER.get()?;
+    let sampled = match parent {
+        Some(parent) if tracer.config.respect_parent_sampling => parent.sampled,
+        _ => rand::thread_rng().gen_bool(tracer.config.sampling_ratio.clamp(0.0, 1.0)),
+    };
+    if !sampled {
+        return None;
+    }
+
+    let txn_hash = txn_hash();
+    let mut traces = tracer.traces.lock();
+    if traces.contains_key(&txn_hash) {
+        return None;
+    }
+    if traces.len() >= tracer.config.max_active_traces {
+        counters::DROPPED_TRACES.inc();
+        return None;
+    }
+    let mut rng = rand::thread_rng();
+    traces.insert(
+        txn_hash,
+        ActiveTrace {
+            trace_id: parent.map_or_else(|| rng.gen(), |parent| parent.trace_id),
+            root_span_id: rng.gen(),
+            parent_span_id: parent.map(|parent| parent.span_id),
+            start,
+            deadline: Instant::now() + Duration::from_millis(tracer.config.trace_timeout_ms),
+        },
+    );
+    tracer.num_traces.store(traces.len(), Ordering::Relaxed);
+    Some(txn_hash)
+}
+
+/// Records a span of `stage` in the trace of the transaction, if it's traced.
+pub fn record_span(
+    txn_hash: &HashValue,
+    stage: Stage,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, String)>,
+) {
+    let tracer = match TRACER.get() {
+        Some(tracer) => tracer,
+        None => return,
+    };
+    let (trace_id, root_span_id) = match tracer.traces.lock().get(txn_hash) {
+        Some(trace) => (trace.trace_id, trace.root_span_id),
+        None => return,
+    };
+    tracer.send(Span {
+        trace_id,
+        span_id: rand::thread_rng().gen(),
+        parent_span_id: Some(root_span_id),
+        name: stage.span_name(),
+        start,
+        end,
+        attributes,
+        error: None,
+    });
+}
+
+/// Ends the trace of the transaction, if it's traced, e.g. once it's committed or if it's
+/// rejected.
+pub fn end_trace(txn_hash: &HashValue, end: SystemTime, error: Option<String>) {
+    let tracer = match TRACER.get() {
+        Some(tracer) => tracer,
+        None => return,
+    };
+    let trace = {
+        let mut traces = tracer.traces.lock();
+        let trace = traces.remove(txn_hash);
+        tracer.num_traces.store(traces.len(), Ordering::Relaxed);
+        trace
+    };
+    if let Some(trace) = trace {
+        tracer.send(root_span(txn_hash, trace, end, error));
+    }
+}
+
+fn root_span(
+    txn_hash: &HashValue,
+    trace: ActiveTrace,
+    end: SystemTime,
+    error: Option<String>,
+) -> Span {
+    Span {
+        trace_id: trace.trace_id,
+        span_id: trace.root_span_id,
+        parent_span_id: trace.parent_span_id,
+        name: "transaction",
+        start: trace.start,
+        end,
+        attributes: vec![("aptos.txn_hash", txn_hash.to_hex_literal())],
+        error,
+    }
+}
+
+impl Tracer {
+    fn send(&self, span: Span) {
+        if let Err(TrySendError::Full(_)) = self.spans.try_send(span) {
+            counters::SPANS.with_label_values(&["dropped"]).inc();
+        }
+    }
+
+    /// Ends the traces of the transactions that weren't committed in time.
+    fn expire_traces(&self) {
+        let now = Instant::now();
+        let expired: Vec<_> = {
+            let mut traces = self.traces.lock();
+            let expired_hashes: Vec<_> = traces
+                .iter()
+                .filter(|(_, trace)| trace.deadline <= now)
+                .map(|(txn_hash, _)| *txn_hash)
+                .collect();
+            let expired = expired_hashes
+                .into_iter()
+                .filter_map(|txn_hash| traces.remove(&txn_hash).map(|trace| (txn_hash, trace)))
+                .collect();
+            self.num_traces.store(traces.len(), Ordering::Relaxed);
+            expired
+        };
+        let end = SystemTime::now();
+        for (txn_hash, trace) in expired {
+            self.send(root_span(
+                &txn_hash,
+                trace,
+                end,
+                Some("Transaction wasn't committed before the trace timed out".into()),
+            ));
+        }
+    }
+}
+
+fn export_spans(endpoint: String, receiver: Receiver<Span>) {
+    let tracer = TRACER.get().expect("Tracer must be initialized");
+    let interval = Duration::from_millis(tracer.config.export_interval_ms);
+    let batch_size = tracer.config.export_batch_size.max(1);
+    let client = reqwest::blocking::Client::new();
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut last_export = Instant::now();
+
+    loop {
+        match receiver.recv_timeout(interval) {
+            Ok(span) => {
+                batch.push(span);
+                if batch.len() < batch_size && last_export.elapsed() < interval {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        tracer.expire_traces();
+        if !batch.is_empty() {
+            let result = client
+                .post(&endpoint)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(otlp::encode(&tracer.config.service_name, &batch).to_string())
+                .send()
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => counters::SPANS
+                    .with_label_values(&["exported"])
+                    .inc_by(batch.len() as u64),
+                Err(err) => {
+                    warn!("Failed to export {} spans: {}", batch.len(), err);
+                    counters::SPANS
+                        .with_label_values(&["failed"])
+                        .inc_by(batch.len() as u64);
+                }
+            }
+            batch.clear();
+        }
+        last_export = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent() {
+        let header = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let context = TraceContext::from_traceparent(header).unwrap();
+        assert_eq!(
+            hex::encode(context.trace_id),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        assert_eq!(hex::encode(context.span_id), "b7ad6b7169203331");
+        assert!(context.sampled);
+        assert_eq!(context.to_traceparent(), header);
+
+        let not_sampled = TraceContext::from_traceparent(
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00",
+        )
+        .unwrap();
+        assert!(!not_sampled.sampled);
+    }
+
+    #[test]
+    fn test_invalid_traceparent() {
+        for header in [
+            "",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01",
+            "00-0af7651916cd43dd-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b716920333z-01",
+        ] {
+            assert_eq!(TraceContext::from_traceparent(header), None, "{}", header);
+        }
+    }
+}
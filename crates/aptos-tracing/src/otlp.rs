@@ -0,0 +1,103 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encoding of spans as an OTLP/HTTP JSON `ExportTraceServiceRequest`, see
+//! https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding
+
+use crate::Span;
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// `Span.SpanKind` and `Status.StatusCode` of the protocol
+const SPAN_KIND_INTERNAL: u8 = 1;
+const STATUS_CODE_OK: u8 = 1;
+const STATUS_CODE_ERROR: u8 = 2;
+
+pub(crate) fn encode(service_name: &str, spans: &[Span]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", service_name)],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "aptos-tracing" },
+                "spans": spans.iter().map(encode_span).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+fn encode_span(span: &Span) -> Value {
+    let mut value = json!({
+        "traceId": hex::encode(span.trace_id),
+        "spanId": hex::encode(span.span_id),
+        "name": span.name,
+        "kind": SPAN_KIND_INTERNAL,
+        // 64 bit integers are encoded as strings
+        "startTimeUnixNano": unix_nanos(span.start).to_string(),
+        "endTimeUnixNano": unix_nanos(span.end).to_string(),
+        "attributes": span
+            .attributes
+            .iter()
+            .map(|(key, value)| attribute(key, value))
+            .collect::<Vec<_>>(),
+        "status": match &span.error {
+            Some(error) => json!({ "code": STATUS_CODE_ERROR, "message": error }),
+            None => json!({ "code": STATUS_CODE_OK }),
+        },
+    });
+    if let Some(parent_span_id) = span.parent_span_id {
+        value["parentSpanId"] = Value::String(hex::encode(parent_span_id));
+    }
+    value
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_encode() {
+        let span = Span {
+            trace_id: [1; 16],
+            span_id: [2; 8],
+            parent_span_id: Some([3; 8]),
+            name: "mempool.admission",
+            start: UNIX_EPOCH + Duration::from_nanos(1_000),
+            end: UNIX_EPOCH + Duration::from_nanos(2_000),
+            attributes: vec![("aptos.status", "accepted".into())],
+            error: Some("Rejected".into()),
+        };
+        let request = encode("aptos-node", &[span]);
+
+        let resource_spans = &request["resourceSpans"][0];
+        assert_eq!(
+            resource_spans["resource"]["attributes"][0],
+            json!({ "key": "service.name", "value": { "stringValue": "aptos-node" } })
+        );
+        assert_eq!(
+            resource_spans["scopeSpans"][0]["spans"][0],
+            json!({
+                "traceId": "01010101010101010101010101010101",
+                "spanId": "0202020202020202",
+                "parentSpanId": "0303030303030303",
+                "name": "mempool.admission",
+                "kind": 1,
+                "startTimeUnixNano": "1000",
+                "endTimeUnixNano": "2000",
+                "attributes": [{ "key": "aptos.status", "value": { "stringValue": "accepted" } }],
+                "status": { "code": 2, "message": "Rejected" },
+            })
+        );
+    }
+}
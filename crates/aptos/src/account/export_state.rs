@@ -0,0 +1,178 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::{
+    types::{CliCommand, CliError, CliTypedResult, ProfileOptions, RestOptions, SaveFile},
+    utils::read_from_file,
+};
+use aptos_types::{
+    access_path::AccessPath, account_address::AccountAddress, state_store::state_key::StateKey,
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_binary_format::{access::ModuleAccess, CompiledModule};
+use move_core_types::language_storage::ResourceKey;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Resources and modules of a set of accounts, as exported from a network at a version
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ExportedAccountState {
+    pub chain_id: u8,
+    pub version: u64,
+    pub accounts: Vec<AccountAddress>,
+    /// Raw state values keyed by their location in storage
+    pub state: Vec<(StateKey, Vec<u8>)>,
+}
+
+impl ExportedAccountState {
+    pub fn load(path: &Path) -> CliTypedResult<Self> {
+        bcs::from_bytes(&read_from_file(path)?)
+            .map_err(|err| CliError::UnableToParse("exported account state", err.to_string()))
+    }
+}
+
+/// Export all resources and modules of accounts at a version
+///
+/// The exported state can be imported into a new local testnet with
+/// `aptos node run-local-testnet --import-state`, to test against real on-chain data.  Note that
+/// accounts keep their on-chain authentication keys, so transactions can only be sent from them
+/// with their original keys.
+///
+/// Table items can't be listed through the REST API, so they aren't exported.  Resources holding
+/// tables, e.g. a `0x3::token::TokenStore`, would be imported without their content, so the
+/// export fails on them unless `--skip-table-items` is given.
+#[derive(Debug, Parser)]
+pub struct ExportState {
+    /// Addresses of the accounts to export
+    #[clap(long, multiple_values = true, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) accounts: Vec<AccountAddress>,
+
+    /// Ledger version to export the state at
+    ///
+    /// Defaults to the latest version of the network
+    #[clap(long)]
+    pub(crate) version: Option<u64>,
+
+    /// Export the resources holding tables without their table items
+    #[clap(long)]
+    pub(crate) skip_table_items: bool,
+
+    #[clap(flatten)]
+    pub(crate) save_file: SaveFile,
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+/// Summary of an account state export
+#[derive(Debug, Serialize)]
+pub struct ExportStateSummary {
+    pub chain_id: u8,
+    pub version: u64,
+    pub resources: usize,
+    pub modules: usize,
+    /// Resources exported without their table items, with `--skip-table-items`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resources_without_table_items: Vec<String>,
+}
+
+#[async_trait]
+impl CliCommand<ExportStateSummary> for ExportState {
+    fn command_name(&self) -> &'static str {
+        "ExportState"
+    }
+
+    async fn execute(self) -> CliTypedResult<ExportStateSummary> {
+        if self.accounts.is_empty() {
+            return Err(CliError::CommandArgumentError(
+                "At least one account must be given with --accounts".to_string(),
+            ));
+        }
+        self.save_file.check_file()?;
+
+        let client = self.rest_options.client(&self.profile_options)?;
+        let ledger_info = client.get_ledger_information().await?.into_inner();
+        let version = self.version.unwrap_or(ledger_info.version);
+
+        let mut state = Vec::new();
+        let mut resources = 0;
+        let mut modules = 0;
+        let mut resources_with_tables = Vec::new();
+        for account in &self.accounts {
+            for resource in client
+                .get_account_resources_at_version(*account, version)
+                .await?
+                .into_inner()
+            {
+                if holds_table(&resource.data) {
+                    resources_with_tables
+                        .push(format!("{} at {}", resource.resource_type, account));
+                }
+            }
+
+            let account_resources = client
+                .get_account_resources_at_version_bcs(*account, version)
+                .await?
+                .into_inner();
+            for (struct_tag, value) in account_resources {
+                let access_path =
+                    AccessPath::resource_access_path(ResourceKey::new(*account, struct_tag));
+                state.push((StateKey::AccessPath(access_path), value));
+                resources += 1;
+            }
+
+            let account_modules = client
+                .get_account_modules_at_version_bcs(*account, version)
+                .await?
+                .into_inner();
+            for bytecode in account_modules.into_values() {
+                let module = CompiledModule::deserialize(&bytecode)
+                    .map_err(|err| CliError::UnableToParse("module bytecode", err.to_string()))?;
+                let access_path = AccessPath::code_access_path(module.self_id());
+                state.push((StateKey::AccessPath(access_path), bytecode));
+                modules += 1;
+            }
+        }
+
+        if !resources_with_tables.is_empty() && !self.skip_table_items {
+            return Err(CliError::CommandArgumentError(format!(
+                "Table items can't be exported, and these resources hold tables: {}.  \
+                Use --skip-table-items to export them without their table items",
+                resources_with_tables.join(", ")
+            )));
+        }
+
+        let exported = ExportedAccountState {
+            chain_id: ledger_info.chain_id,
+            version,
+            accounts: self.accounts,
+            state,
+        };
+        self.save_file
+            .save_to_file("Exported account state", &bcs::to_bytes(&exported)?)?;
+
+        Ok(ExportStateSummary {
+            chain_id: exported.chain_id,
+            version,
+            resources,
+            modules,
+            resources_without_table_items: resources_with_tables,
+        })
+    }
+}
+
+/// Whether a resource, as returned in JSON by the REST API, holds a table, which shows as an
+/// object with a `handle`, e.g. `0x1::table::Table` or the inner table of a
+/// `0x1::table_with_length::TableWithLength`
+fn holds_table(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Object(fields) => {
+            matches!(fields.get("handle"), Some(serde_json::Value::String(_)))
+                || fields.values().any(holds_table)
+        }
+        serde_json::Value::Array(values) => values.iter().any(holds_table),
+        _ => false,
+    }
+}
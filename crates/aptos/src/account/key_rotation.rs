@@ -6,15 +6,16 @@ use crate::common::{
     types::{
         CliCommand, CliConfig, CliError, CliTypedResult, ConfigSearchMode, EncodingOptions,
         EncodingType, ExtractPublicKey, ParsePrivateKey, ProfileConfig, ProfileOptions,
-        PublicKeyInputOptions, RestOptions, RotationProofChallenge, TransactionOptions,
+        PublicKeyInputOptions, RestOptions, RotationProofChallenge, SenderKey, TransactionOptions,
         TransactionSummary,
     },
-    utils::{prompt_yes_with_override, read_line},
+    utils::{prompt_yes_with_override, read_line, write_to_user_only_file},
 };
 use aptos_crypto::{
-    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
-    PrivateKey, SigningKey,
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    PrivateKey, Signature, SigningKey, ValidCryptoMaterialStringExt,
 };
+use aptos_keygen::KeyGen;
 use aptos_rest_client::aptos_api_types::{AptosError, AptosErrorCode};
 use aptos_rest_client::error::{AptosErrorResponse, RestError};
 use aptos_rest_client::Client;
@@ -31,10 +32,17 @@ use std::{collections::BTreeMap, path::PathBuf};
 /// Rotate an account's authentication key
 ///
 /// Rotating the account's authentication key allows you to use a new
-/// private key.  You must provide a new private key.  Once it is
-/// rotated you will need to use the original account address, with the
-/// new private key.  There is an interactive prompt to help you add it
-/// to a new profile.
+/// private key.  You must provide a new private key, or have one generated
+/// with `--generate-new-key`.  Once it is rotated you will need to use the
+/// original account address, with the new private key.  There is an
+/// interactive prompt to help you add it to a new profile.
+///
+/// If the new key is held by a hardware wallet or another external signer,
+/// provide its `--new-public-key` instead.  The command then prints the
+/// rotation proof challenge for the external signer to sign, and the
+/// signature is passed back with `--new-key-signature`.  The same goes for a
+/// current key given by `--sender-public-key`, or a profile without a private
+/// key, whose signature is passed back with `--current-key-signature`.
 #[derive(Debug, Parser)]
 pub struct RotateKey {
     #[clap(flatten)]
@@ -48,6 +56,34 @@ pub struct RotateKey {
     #[clap(long, group = "new_private_key")]
     pub(crate) new_private_key: Option<String>,
 
+    /// Generate a new private key to rotate to
+    ///
+    /// The generated key is stored in the saved profile, and in `--new-private-key-output-file`
+    /// if provided
+    #[clap(long, group = "new_private_key")]
+    pub(crate) generate_new_key: bool,
+
+    /// File to write the generated private key to, encoded in the type from `--encoding`
+    #[clap(long, requires = "generate_new_key", parse(from_os_str))]
+    pub(crate) new_private_key_output_file: Option<PathBuf>,
+
+    /// Hex encoded public key of a new key held by an external signer, e.g. a hardware wallet
+    #[clap(long, group = "new_private_key")]
+    pub(crate) new_public_key: Option<String>,
+
+    /// Hex encoded signature of the rotation proof challenge by the `--new-public-key`
+    ///
+    /// If not provided, the challenge to sign is printed and nothing is submitted
+    #[clap(long, requires = "new_public_key")]
+    pub(crate) new_key_signature: Option<String>,
+
+    /// Hex encoded signature of the rotation proof challenge by the current key, when it's held
+    /// by an external signer
+    ///
+    /// If not provided, the challenge to sign is printed and nothing is submitted
+    #[clap(long)]
+    pub(crate) current_key_signature: Option<String>,
+
     /// Name of the profile to save the new private key
     ///
     /// If not provided, it will interactively have you save a profile,
@@ -76,6 +112,139 @@ impl RotateKey {
             self.new_private_key.clone(),
         )
     }
+
+    /// Resolves the key to rotate to, generating one if requested
+    fn new_key(&self) -> CliTypedResult<NewKey> {
+        let encoding = self.txn_options.encoding_options.encoding;
+        if let Some(private_key) = self.extract_private_key(encoding)? {
+            return Ok(NewKey::Local(private_key));
+        }
+
+        if self.generate_new_key {
+            // A generated key only exists locally, so make sure it can't be lost
+            if self.save_to_profile.is_none() && self.new_private_key_output_file.is_none() {
+                return Err(CliError::CommandArgumentError(
+                    "A generated key must be saved with '--save-to-profile' or '--new-private-key-output-file'"
+                        .to_string(),
+                ));
+            }
+            let private_key = KeyGen::from_os_rng().generate_ed25519_private_key();
+            if let Some(ref file) = self.new_private_key_output_file {
+                let encoded = encoding.encode_key("new private key", &private_key)?;
+                write_to_user_only_file(file, "new private key", &encoded)?;
+            }
+            return Ok(NewKey::Local(private_key));
+        }
+
+        if let Some(ref public_key) = self.new_public_key {
+            let public_key = Ed25519PublicKey::from_encoded_string(public_key.trim())
+                .map_err(|err| CliError::UnableToParse("--new-public-key", err.to_string()))?;
+            let signature = parse_signature("--new-key-signature", &self.new_key_signature)?;
+            return Ok(NewKey::External {
+                public_key,
+                signature,
+            });
+        }
+
+        Err(CliError::CommandArgumentError(
+            "One of ['--new-private-key', '--new-private-key-file', '--generate-new-key', '--new-public-key'] must be used"
+                .to_string(),
+        ))
+    }
+}
+
+/// The key an account is rotated to
+enum NewKey {
+    /// A private key available to the CLI
+    Local(Ed25519PrivateKey),
+    /// A key held by an external signer, along with its signature of the rotation proof
+    External {
+        public_key: Ed25519PublicKey,
+        signature: Option<Ed25519Signature>,
+    },
+}
+
+impl NewKey {
+    fn public_key(&self) -> Ed25519PublicKey {
+        match self {
+            NewKey::Local(private_key) => private_key.public_key(),
+            NewKey::External { public_key, .. } => public_key.clone(),
+        }
+    }
+
+    fn private_key(&self) -> Option<Ed25519PrivateKey> {
+        match self {
+            NewKey::Local(private_key) => Some(private_key.clone()),
+            NewKey::External { .. } => None,
+        }
+    }
+
+    /// Signs the rotation proof, verifying signatures produced outside of the CLI
+    fn sign_rotation_proof(&self, rotation_msg: &[u8]) -> CliTypedResult<Option<Ed25519Signature>> {
+        match self {
+            NewKey::Local(private_key) => rotation_proof_signature(
+                Some(private_key),
+                &private_key.public_key(),
+                None,
+                "--new-key-signature",
+                rotation_msg,
+            ),
+            NewKey::External {
+                public_key,
+                signature,
+            } => rotation_proof_signature(
+                None,
+                public_key,
+                signature.as_ref(),
+                "--new-key-signature",
+                rotation_msg,
+            ),
+        }
+    }
+}
+
+/// Signs the rotation proof with the private key if the CLI has it, and otherwise verifies the
+/// signature of it passed back from the external signer
+///
+/// Returns `None` if the external signer hasn't signed the rotation proof yet.
+pub(crate) fn rotation_proof_signature(
+    private_key: Option<&Ed25519PrivateKey>,
+    public_key: &Ed25519PublicKey,
+    signature: Option<&Ed25519Signature>,
+    signature_arg: &str,
+    rotation_msg: &[u8],
+) -> CliTypedResult<Option<Ed25519Signature>> {
+    if let Some(private_key) = private_key {
+        return Ok(Some(private_key.sign_arbitrary_message(rotation_msg)));
+    }
+
+    match signature {
+        Some(signature) => {
+            signature
+                .verify_arbitrary_msg(rotation_msg, public_key)
+                .map_err(|err| {
+                    CliError::CommandArgumentError(format!(
+                        "'{}' is not a valid signature of the rotation proof challenge: {}",
+                        signature_arg, err
+                    ))
+                })?;
+            Ok(Some(signature.clone()))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_signature(
+    arg: &'static str,
+    signature: &Option<String>,
+) -> CliTypedResult<Option<Ed25519Signature>> {
+    signature
+        .as_ref()
+        .map(|signature| {
+            Ed25519Signature::from_encoded_string(signature.trim())
+                .map_err(|err| CliError::UnableToParse(arg, err.to_string()))
+        })
+        .transpose()
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -91,16 +260,11 @@ impl CliCommand<RotateSummary> for RotateKey {
     }
 
     async fn execute(self) -> CliTypedResult<RotateSummary> {
-        let new_private_key = self
-            .extract_private_key(self.txn_options.encoding_options.encoding)?
-            .ok_or_else(|| {
-                CliError::CommandArgumentError(
-                    "One of ['--new-private-key', '--new-private-key-file'] must be used"
-                        .to_string(),
-                )
-            })?;
+        let new_key = self.new_key()?;
+        let new_public_key = new_key.public_key();
 
-        let (current_private_key, sender_address) = self.txn_options.get_key_and_address()?;
+        let (current_key, sender_address) = self.txn_options.get_sender_key_and_address()?;
+        let current_public_key = current_key.public_key();
 
         // Get sequence number for account
         let sequence_number = self.txn_options.sequence_number(sender_address).await?;
@@ -114,27 +278,59 @@ impl CliCommand<RotateSummary> for RotateKey {
             originator: sender_address,
             current_auth_key: AccountAddress::from_bytes(&auth_key)
                 .map_err(|err| CliError::UnableToParse("auth_key", err.to_string()))?,
-            new_public_key: new_private_key.public_key().to_bytes().to_vec(),
+            new_public_key: new_public_key.to_bytes().to_vec(),
         };
 
         let rotation_msg =
             bcs::to_bytes(&rotation_proof).map_err(|err| CliError::BCS("rotation_proof", err))?;
 
         // Signs the struct using both the current private key and the next private key
-        let rotation_proof_signed_by_current_private_key =
-            current_private_key.sign_arbitrary_message(&rotation_msg.clone());
-        let rotation_proof_signed_by_new_private_key =
-            new_private_key.sign_arbitrary_message(&rotation_msg);
+        let new_key_signature = new_key.sign_rotation_proof(&rotation_msg)?;
+        let current_private_key = match current_key {
+            SenderKey::Local(ref private_key) => Some(private_key),
+            SenderKey::External(_) => None,
+        };
+        let current_key_signature = rotation_proof_signature(
+            current_private_key,
+            &current_public_key,
+            parse_signature("--current-key-signature", &self.current_key_signature)?.as_ref(),
+            "--current-key-signature",
+            &rotation_msg,
+        )?;
+        let (
+            rotation_proof_signed_by_new_private_key,
+            rotation_proof_signed_by_current_private_key,
+        ) = match (new_key_signature, current_key_signature) {
+            (Some(new_key_signature), Some(current_key_signature)) => {
+                (new_key_signature, current_key_signature)
+            }
+            (new_key_signature, current_key_signature) => {
+                let missing: Vec<_> = [
+                    (new_key_signature, "'--new-key-signature'"),
+                    (current_key_signature, "'--current-key-signature'"),
+                ]
+                .into_iter()
+                .filter(|(signature, _)| signature.is_none())
+                .map(|(_, arg)| arg)
+                .collect();
+                eprintln!("Rotation proof challenge to sign with the external signer:");
+                eprintln!("{}", hex::encode(&rotation_msg));
+                return Err(CliError::CommandArgumentError(format!(
+                    "Sign the rotation proof challenge, and rerun with {}",
+                    missing.join(" and ")
+                )));
+            }
+        };
 
         let txn_summary = self
             .txn_options
             .submit_transaction(aptos_stdlib::account_rotate_authentication_key(
                 0,
                 // Existing public key
-                current_private_key.public_key().to_bytes().to_vec(),
+                current_public_key.to_bytes().to_vec(),
                 0,
                 // New public key
-                new_private_key.public_key().to_bytes().to_vec(),
+                new_public_key.to_bytes().to_vec(),
                 rotation_proof_signed_by_current_private_key
                     .to_bytes()
                     .to_vec(),
@@ -160,6 +356,22 @@ impl CliCommand<RotateSummary> for RotateKey {
             ));
         }
 
+        // The new key must resolve back to the account through the originating address table,
+        // otherwise the account can't be found from the new key later
+        let rest_client = self.txn_options.rest_client()?;
+        let new_address_key = AuthenticationKey::ed25519(&new_public_key).derived_address();
+        match lookup_address(&rest_client, new_address_key).await {
+            Ok(address) if address == sender_address => {}
+            Ok(address) => eprintln!(
+                "Warning: the new key resolves to {} instead of {} in the originating address table",
+                address, sender_address
+            ),
+            Err(err) => eprintln!(
+                "Warning: unable to verify the originating address table: {}",
+                err
+            ),
+        }
+
         let mut profile_name: String;
 
         if self.save_to_profile.is_none() {
@@ -214,12 +426,12 @@ impl CliCommand<RotateSummary> for RotateKey {
             return Err(CliError::AbortedError);
         }
 
-        let mut profile_config = ProfileConfig {
-            private_key: Some(new_private_key.clone()),
-            public_key: Some(new_private_key.public_key()),
-            account: Some(sender_address),
-            ..self.txn_options.profile_options.profile()?
-        };
+        let mut profile_config = rotated_profile(
+            self.txn_options.profile_options.profile()?,
+            new_key.private_key(),
+            new_public_key,
+            sender_address,
+        );
 
         if let Some(url) = self.txn_options.rest_options.url {
             profile_config.rest_url = Some(url.into());
@@ -245,6 +457,24 @@ impl CliCommand<RotateSummary> for RotateKey {
     }
 }
 
+/// The profile of a rotated account, based on the profile it was rotated from.  It signs with
+/// the new private key if the CLI has it, and otherwise has no signing key at all: the private
+/// key and key file of the old profile are those of the rotated out key.
+pub(crate) fn rotated_profile(
+    profile: ProfileConfig,
+    new_private_key: Option<Ed25519PrivateKey>,
+    new_public_key: Ed25519PublicKey,
+    account: AccountAddress,
+) -> ProfileConfig {
+    ProfileConfig {
+        private_key: new_private_key,
+        private_key_file: None,
+        public_key: Some(new_public_key),
+        account: Some(account),
+        ..profile
+    }
+}
+
 /// Lookup the account address through the on-chain lookup table
 ///
 /// If the account is rotated, it will provide the address accordingly.  If the account was not
@@ -285,41 +515,52 @@ impl CliCommand<AccountAddress> for LookupAddress {
     async fn execute(self) -> CliTypedResult<AccountAddress> {
         let rest_client = self.rest_client()?;
 
-        let originating_resource: OriginatingResource = rest_client
-            .get_account_resource_bcs(CORE_CODE_ADDRESS, "0x1::account::OriginatingAddress")
-            .await?
-            .into_inner();
-
-        let table_handle = originating_resource.address_map.handle;
-
         // The derived address that can be used to look up the original address
         // TODO: This command needs to support multi-ed25519
         let address_key = AuthenticationKey::ed25519(&self.public_key()?).derived_address();
-        match rest_client
-            .get_table_item_bcs(
-                table_handle,
-                "address",
-                "address",
-                address_key.to_hex_literal(),
-            )
-            .await
-        {
-            Ok(inner) => Ok(inner.into_inner()),
-            Err(RestError::Api(AptosErrorResponse {
-                error:
-                    AptosError {
-                        error_code: AptosErrorCode::TableItemNotFound,
-                        ..
-                    },
-                ..
-            })) => {
-                // If the table item wasn't found, let's at least check if the account exists
-                // It won't be in the table if it wasn't rotated, then return the derived account address
-                rest_client.get_account_bcs(address_key).await?;
-                Ok(address_key)
-            }
-            Err(err) => Err(err)?,
+        lookup_address(&rest_client, address_key).await
+    }
+}
+
+/// Looks up the account an authentication key's derived address belongs to
+///
+/// It won't be in the originating address table if the account wasn't rotated, in which case the
+/// derived address is returned if the account exists.
+pub(crate) async fn lookup_address(
+    rest_client: &Client,
+    address_key: AccountAddress,
+) -> CliTypedResult<AccountAddress> {
+    let originating_resource: OriginatingResource = rest_client
+        .get_account_resource_bcs(CORE_CODE_ADDRESS, "0x1::account::OriginatingAddress")
+        .await?
+        .into_inner();
+
+    let table_handle = originating_resource.address_map.handle;
+
+    match rest_client
+        .get_table_item_bcs(
+            table_handle,
+            "address",
+            "address",
+            address_key.to_hex_literal(),
+        )
+        .await
+    {
+        Ok(inner) => Ok(inner.into_inner()),
+        Err(RestError::Api(AptosErrorResponse {
+            error:
+                AptosError {
+                    error_code: AptosErrorCode::TableItemNotFound,
+                    ..
+                },
+            ..
+        })) => {
+            // If the table item wasn't found, let's at least check if the account exists
+            // It won't be in the table if it wasn't rotated, then return the derived account address
+            rest_client.get_account_bcs(address_key).await?;
+            Ok(address_key)
         }
+        Err(err) => Err(err)?,
     }
 }
 
@@ -6,9 +6,11 @@ use clap::Subcommand;
 
 pub mod create;
 pub mod create_resource_account;
+pub mod export_state;
 pub mod fund;
 pub mod key_rotation;
 pub mod list;
+pub mod multisig;
 pub mod transfer;
 
 /// Tool for interacting with accounts
@@ -19,9 +21,12 @@ pub mod transfer;
 pub enum AccountTool {
     Create(create::CreateAccount),
     CreateResourceAccount(create_resource_account::CreateResourceAccount),
+    ExportState(export_state::ExportState),
     FundWithFaucet(fund::FundWithFaucet),
     List(list::ListAccount),
     LookupAddress(key_rotation::LookupAddress),
+    #[clap(subcommand)]
+    Multisig(multisig::MultisigTool),
     RotateKey(key_rotation::RotateKey),
     Transfer(transfer::TransferCoins),
 }
@@ -31,9 +36,11 @@ impl AccountTool {
         match self {
             AccountTool::Create(tool) => tool.execute_serialized().await,
             AccountTool::CreateResourceAccount(tool) => tool.execute_serialized().await,
+            AccountTool::ExportState(tool) => tool.execute_serialized().await,
             AccountTool::FundWithFaucet(tool) => tool.execute_serialized().await,
             AccountTool::List(tool) => tool.execute_serialized().await,
             AccountTool::LookupAddress(tool) => tool.execute_serialized().await,
+            AccountTool::Multisig(tool) => tool.execute().await,
             AccountTool::RotateKey(tool) => tool.execute_serialized().await,
             AccountTool::Transfer(tool) => tool.execute_serialized().await,
         }
@@ -0,0 +1,549 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{
+        types::{
+            CliCommand, CliError, CliResult, CliTypedResult, EncodingOptions, GasOptions,
+            PrivateKeyInputOptions, ProfileOptions, PromptOptions, RestOptions, SaveFile,
+            TransactionOptions, TransactionSummary,
+        },
+        utils::{chain_id, get_sequence_number, prompt_yes_with_override, read_from_file},
+    },
+    move_tool::{ArgWithType, MemberId},
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+    multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    HashValue, PrivateKey, Signature, SigningKey, ValidCryptoMaterialStringExt,
+};
+use aptos_rest_client::aptos_api_types::MoveType;
+use aptos_sdk::transaction_builder::TransactionFactory;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{
+        authenticator::AuthenticationKey, EntryFunction, RawTransaction, SignedTransaction,
+        TransactionPayload,
+    },
+};
+use async_trait::async_trait;
+use cached_packages::aptos_stdlib;
+use clap::{Parser, Subcommand};
+use move_core_types::language_storage::TypeTag;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, path::PathBuf};
+
+/// Default number of seconds a proposal can wait for approvals before it expires
+const DEFAULT_PROPOSAL_EXPIRATION_SECS: u64 = 3600;
+
+/// Tool for managing K-of-N multisig accounts
+///
+/// A multisig account is an account whose authentication key is derived from a set of
+/// N Ed25519 public keys and a threshold K.  Transactions are proposed to a file, approved
+/// by signers offline, and executed once at least K approvals have been collected.
+#[derive(Debug, Subcommand)]
+pub enum MultisigTool {
+    Create(CreateMultisig),
+    Propose(ProposeMultisigTransaction),
+    VerifyProposal(VerifyMultisigProposal),
+    Approve(ApproveMultisigTransaction),
+    Execute(ExecuteMultisigTransaction),
+}
+
+impl MultisigTool {
+    pub async fn execute(self) -> CliResult {
+        use MultisigTool::*;
+        match self {
+            Create(tool) => tool.execute_serialized().await,
+            Propose(tool) => tool.execute_serialized().await,
+            VerifyProposal(tool) => tool.execute_serialized().await,
+            Approve(tool) => tool.execute_serialized().await,
+            Execute(tool) => tool.execute_serialized().await,
+        }
+    }
+}
+
+/// The set of keys and threshold that make up a multisig account
+#[derive(Debug, Parser)]
+pub struct MultisigKeyArgs {
+    /// Ed25519 public keys of the signers, hex encoded and separated by spaces
+    ///
+    /// The order of the keys matters, as it determines the account address
+    #[clap(long, multiple_values = true, parse(try_from_str = Ed25519PublicKey::from_encoded_string))]
+    pub(crate) public_keys: Vec<Ed25519PublicKey>,
+
+    /// Number of signers required to approve a transaction
+    #[clap(long)]
+    pub(crate) threshold: u8,
+}
+
+impl MultisigKeyArgs {
+    pub(crate) fn multisig_public_key(&self) -> CliTypedResult<MultiEd25519PublicKey> {
+        MultiEd25519PublicKey::new(self.public_keys.clone(), self.threshold).map_err(|err| {
+            CliError::CommandArgumentError(format!(
+                "Invalid multisig keys, the threshold must be between 1 and the number of keys: {}",
+                err
+            ))
+        })
+    }
+}
+
+pub(crate) fn multisig_address(public_key: &MultiEd25519PublicKey) -> AccountAddress {
+    AuthenticationKey::multi_ed25519(public_key).derived_address()
+}
+
+/// Create a multisig account on-chain
+///
+/// The account is created and its address is derived from the given public keys and threshold.
+/// The transaction is paid for by the account given by `--private-key` or the profile.
+#[derive(Debug, Parser)]
+pub struct CreateMultisig {
+    #[clap(flatten)]
+    pub(crate) key_args: MultisigKeyArgs,
+
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateMultisigSummary {
+    multisig_address: AccountAddress,
+    threshold: u8,
+    public_keys: Vec<Ed25519PublicKey>,
+    transaction: TransactionSummary,
+}
+
+#[async_trait]
+impl CliCommand<CreateMultisigSummary> for CreateMultisig {
+    fn command_name(&self) -> &'static str {
+        "CreateMultisig"
+    }
+
+    async fn execute(self) -> CliTypedResult<CreateMultisigSummary> {
+        let public_key = self.key_args.multisig_public_key()?;
+        let multisig_address = multisig_address(&public_key);
+        let transaction = self
+            .txn_options
+            .submit_transaction(aptos_stdlib::aptos_account_create_account(multisig_address))
+            .await
+            .map(TransactionSummary::from)?;
+
+        Ok(CreateMultisigSummary {
+            multisig_address,
+            threshold: *public_key.threshold(),
+            public_keys: public_key.public_keys().clone(),
+            transaction,
+        })
+    }
+}
+
+/// A transaction waiting on approvals from the signers of a multisig account
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MultisigProposal {
+    pub public_key: MultiEd25519PublicKey,
+    pub raw_txn: RawTransaction,
+}
+
+impl MultisigProposal {
+    pub(crate) fn load(path: &std::path::Path) -> CliTypedResult<Self> {
+        Ok(bcs::from_bytes(&read_from_file(path)?)?)
+    }
+
+    /// Index of the signer's key in the multisig key set
+    fn signer_index(&self, public_key: &Ed25519PublicKey) -> CliTypedResult<u8> {
+        self.public_key
+            .public_keys()
+            .iter()
+            .position(|key| key == public_key)
+            .map(|index| index as u8)
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(format!(
+                    "Public key {} is not one of the signers of multisig account {}",
+                    public_key,
+                    self.raw_txn.sender()
+                ))
+            })
+    }
+
+    fn summary(&self) -> MultisigProposalSummary {
+        let payload = match self.raw_txn.payload() {
+            TransactionPayload::EntryFunction(entry_function) => PayloadSummary::EntryFunction {
+                function: format!("{}::{}", entry_function.module(), entry_function.function()),
+                type_args: entry_function
+                    .ty_args()
+                    .iter()
+                    .map(|type_arg| type_arg.to_string())
+                    .collect(),
+                args: entry_function.args().iter().map(hex::encode).collect(),
+            },
+            TransactionPayload::Script(script) => PayloadSummary::Script {
+                code_hash: HashValue::sha3_256_of(script.code()),
+            },
+            TransactionPayload::ModuleBundle(bundle) => PayloadSummary::ModuleBundle {
+                num_modules: bundle.iter().count(),
+            },
+        };
+
+        MultisigProposalSummary {
+            multisig_address: self.raw_txn.sender(),
+            threshold: *self.public_key.threshold(),
+            signers: self.public_key.public_keys().clone(),
+            sequence_number: self.raw_txn.sequence_number(),
+            chain_id: self.raw_txn.chain_id().id(),
+            gas_unit_price: self.raw_txn.gas_unit_price(),
+            max_gas_amount: self.raw_txn.max_gas_amount(),
+            expiration_timestamp_secs: self.raw_txn.expiration_timestamp_secs(),
+            payload,
+            payload_hash: HashValue::sha3_256_of(
+                &bcs::to_bytes(self.raw_txn.payload()).expect("Payload must serialize"),
+            ),
+            proposal_hash: self.raw_txn.hash(),
+        }
+    }
+}
+
+/// Human readable contents of a proposal, for signers to audit before approving
+#[derive(Debug, Serialize)]
+pub struct MultisigProposalSummary {
+    multisig_address: AccountAddress,
+    threshold: u8,
+    signers: Vec<Ed25519PublicKey>,
+    sequence_number: u64,
+    chain_id: u8,
+    gas_unit_price: u64,
+    max_gas_amount: u64,
+    expiration_timestamp_secs: u64,
+    payload: PayloadSummary,
+    /// SHA3-256 of the BCS encoded payload
+    payload_hash: HashValue,
+    /// Hash of the raw transaction, which is what each signer signs
+    proposal_hash: HashValue,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadSummary {
+    EntryFunction {
+        function: String,
+        type_args: Vec<String>,
+        args: Vec<String>,
+    },
+    Script {
+        code_hash: HashValue,
+    },
+    ModuleBundle {
+        num_modules: usize,
+    },
+}
+
+/// Propose a transaction to be executed by a multisig account
+///
+/// Writes the unsigned transaction to `--output-file`, which can then be shared with
+/// the signers to approve.
+#[derive(Debug, Parser)]
+pub struct ProposeMultisigTransaction {
+    #[clap(flatten)]
+    pub(crate) key_args: MultisigKeyArgs,
+
+    /// Function name as `<ADDRESS>::<MODULE_ID>::<FUNCTION_NAME>`
+    ///
+    /// Example: `0x1::aptos_account::transfer`
+    #[clap(long)]
+    pub(crate) function_id: MemberId,
+
+    /// Arguments combined with their type separated by spaces.
+    ///
+    /// Supported types [u8, u64, u128, bool, hex, string, address, raw]
+    ///
+    /// Example: `address:0x1 bool:true u8:0`
+    #[clap(long, multiple_values = true)]
+    pub(crate) args: Vec<ArgWithType>,
+
+    /// TypeTag arguments separated by spaces.
+    ///
+    /// Example: `u8 u64 u128 bool address vector signer`
+    #[clap(long, multiple_values = true)]
+    pub(crate) type_args: Vec<MoveType>,
+
+    /// Sequence number of the multisig account to use for the transaction
+    ///
+    /// Defaults to the account's current on-chain sequence number.  Set it explicitly to
+    /// queue several proposals at once.
+    #[clap(long)]
+    pub(crate) sequence_number: Option<u64>,
+
+    /// Number of seconds the proposal can wait for approvals before it expires
+    #[clap(long, default_value_t = DEFAULT_PROPOSAL_EXPIRATION_SECS)]
+    pub(crate) expiration_secs: u64,
+
+    #[clap(flatten)]
+    pub(crate) gas_options: GasOptions,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+
+    #[clap(flatten)]
+    pub(crate) save_file: SaveFile,
+}
+
+#[async_trait]
+impl CliCommand<MultisigProposalSummary> for ProposeMultisigTransaction {
+    fn command_name(&self) -> &'static str {
+        "ProposeMultisigTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<MultisigProposalSummary> {
+        self.save_file.check_file()?;
+        let public_key = self.key_args.multisig_public_key()?;
+        let sender = multisig_address(&public_key);
+
+        let mut type_args: Vec<TypeTag> = Vec::new();
+        for type_arg in self.type_args.into_iter() {
+            let type_tag = TypeTag::try_from(type_arg)
+                .map_err(|err| CliError::UnableToParse("--type-args", err.to_string()))?;
+            type_args.push(type_tag)
+        }
+        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+            self.function_id.module_id,
+            self.function_id.member_id,
+            type_args,
+            self.args.into_iter().map(|arg| arg.arg).collect(),
+        ));
+
+        let client = self.rest_options.client(&self.profile_options)?;
+        let sequence_number = if let Some(sequence_number) = self.sequence_number {
+            sequence_number
+        } else {
+            get_sequence_number(&client, sender).await?
+        };
+        let gas_unit_price = if let Some(gas_unit_price) = self.gas_options.gas_unit_price {
+            gas_unit_price
+        } else {
+            client.estimate_gas_price().await?.into_inner().gas_estimate
+        };
+
+        let mut transaction_factory = TransactionFactory::new(chain_id(&client).await?)
+            .with_gas_unit_price(gas_unit_price)
+            .with_transaction_expiration_time(self.expiration_secs);
+        if let Some(max_gas) = self.gas_options.max_gas {
+            transaction_factory = transaction_factory.with_max_gas_amount(max_gas);
+        }
+        let raw_txn = transaction_factory
+            .payload(payload)
+            .sender(sender)
+            .sequence_number(sequence_number)
+            .build();
+
+        let proposal = MultisigProposal {
+            public_key,
+            raw_txn,
+        };
+        self.save_file
+            .save_to_file("Multisig proposal", &bcs::to_bytes(&proposal)?)?;
+        Ok(proposal.summary())
+    }
+}
+
+/// Show the contents of a multisig proposal
+///
+/// Use this to audit what a proposal does before approving it.  If `--expected-hash` is
+/// given, fails unless the proposal hash matches it.
+#[derive(Debug, Parser)]
+pub struct VerifyMultisigProposal {
+    /// Proposal file created by `aptos account multisig propose`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) proposal_file: PathBuf,
+
+    /// Proposal hash shared out of band by the proposer
+    #[clap(long)]
+    pub(crate) expected_hash: Option<HashValue>,
+}
+
+#[async_trait]
+impl CliCommand<MultisigProposalSummary> for VerifyMultisigProposal {
+    fn command_name(&self) -> &'static str {
+        "VerifyMultisigProposal"
+    }
+
+    async fn execute(self) -> CliTypedResult<MultisigProposalSummary> {
+        let proposal = MultisigProposal::load(&self.proposal_file)?;
+        let summary = proposal.summary();
+        if let Some(expected_hash) = self.expected_hash {
+            if expected_hash != summary.proposal_hash {
+                return Err(CliError::UnexpectedError(format!(
+                    "Proposal hash {} does not match expected hash {}",
+                    summary.proposal_hash, expected_hash
+                )));
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// A signer's approval of a multisig proposal
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MultisigApproval {
+    pub public_key: Ed25519PublicKey,
+    pub signature: Ed25519Signature,
+}
+
+/// Approve a multisig proposal by signing it
+///
+/// Writes the signature to `--output-file`, to be handed to whoever executes the proposal.
+#[derive(Debug, Parser)]
+pub struct ApproveMultisigTransaction {
+    /// Proposal file created by `aptos account multisig propose`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) proposal_file: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) private_key_options: PrivateKeyInputOptions,
+
+    #[clap(flatten)]
+    pub(crate) encoding_options: EncodingOptions,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+
+    #[clap(flatten)]
+    pub(crate) save_file: SaveFile,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MultisigApprovalSummary {
+    signer: Ed25519PublicKey,
+    signer_index: u8,
+    proposal: MultisigProposalSummary,
+}
+
+#[async_trait]
+impl CliCommand<MultisigApprovalSummary> for ApproveMultisigTransaction {
+    fn command_name(&self) -> &'static str {
+        "ApproveMultisigTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<MultisigApprovalSummary> {
+        self.save_file.check_file()?;
+        let proposal = MultisigProposal::load(&self.proposal_file)?;
+        let private_key = self
+            .private_key_options
+            .extract_private_key(self.encoding_options.encoding, &self.profile_options)?;
+        let public_key = private_key.public_key();
+        let signer_index = proposal.signer_index(&public_key)?;
+
+        let summary = proposal.summary();
+        eprintln!(
+            "{}",
+            serde_json::to_string_pretty(&summary)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+        );
+        prompt_yes_with_override(
+            &format!("Do you want to approve proposal {}?", summary.proposal_hash),
+            self.save_file.prompt_options,
+        )?;
+
+        let signature = private_key
+            .sign(&proposal.raw_txn)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        let approval = MultisigApproval {
+            public_key: public_key.clone(),
+            signature,
+        };
+        self.save_file
+            .save_to_file("Multisig approval", &bcs::to_bytes(&approval)?)?;
+
+        Ok(MultisigApprovalSummary {
+            signer: public_key,
+            signer_index,
+            proposal: summary,
+        })
+    }
+}
+
+/// Execute a multisig proposal once enough signers have approved it
+#[derive(Debug, Parser)]
+pub struct ExecuteMultisigTransaction {
+    /// Proposal file created by `aptos account multisig propose`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) proposal_file: PathBuf,
+
+    /// Approval files created by `aptos account multisig approve`, separated by spaces
+    #[clap(long, multiple_values = true, parse(from_os_str))]
+    pub(crate) approval_files: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<TransactionSummary> for ExecuteMultisigTransaction {
+    fn command_name(&self) -> &'static str {
+        "ExecuteMultisigTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionSummary> {
+        let proposal = MultisigProposal::load(&self.proposal_file)?;
+
+        // Check every approval against the proposal, so a bad signature is reported by file
+        // rather than as a rejected transaction
+        let mut signatures = Vec::new();
+        for approval_file in &self.approval_files {
+            let approval: MultisigApproval = bcs::from_bytes(&read_from_file(approval_file)?)?;
+            let index = proposal.signer_index(&approval.public_key)?;
+            approval
+                .signature
+                .verify(&proposal.raw_txn, &approval.public_key)
+                .map_err(|err| {
+                    CliError::UnexpectedError(format!(
+                        "Approval {} is not a valid signature of the proposal: {}",
+                        approval_file.display(),
+                        err
+                    ))
+                })?;
+            if signatures.iter().any(|(_, existing)| *existing == index) {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Duplicate approval from signer {}",
+                    approval.public_key
+                )));
+            }
+            signatures.push((approval.signature, index));
+        }
+
+        let threshold = *proposal.public_key.threshold() as usize;
+        if signatures.len() < threshold {
+            return Err(CliError::CommandArgumentError(format!(
+                "Proposal has {} approvals, but {} are required",
+                signatures.len(),
+                threshold
+            )));
+        }
+
+        prompt_yes_with_override(
+            &format!(
+                "Do you want to execute proposal {}?",
+                proposal.raw_txn.hash()
+            ),
+            self.prompt_options,
+        )?;
+
+        let signature = MultiEd25519Signature::new(signatures)?;
+        let transaction =
+            SignedTransaction::new_multisig(proposal.raw_txn, proposal.public_key, signature);
+        let client = self.rest_options.client(&self.profile_options)?;
+        let response = client
+            .submit_and_wait(&transaction)
+            .await
+            .map_err(|err| CliError::ApiError(err.to_string()))?;
+        Ok(TransactionSummary::from(response.into_inner()))
+    }
+}
@@ -8,7 +8,7 @@ use crate::common::{
         EncodingOptions, PrivateKeyInputOptions, ProfileConfig, ProfileOptions, PromptOptions,
         RngArgs,
     },
-    utils::{fund_account, prompt_yes_with_override, read_line},
+    utils::{chain_id, fund_account, prompt_yes_with_override, read_line},
 };
 use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, ValidCryptoMaterialStringExt};
 use aptos_rest_client::aptos_api_types::{AptosError, AptosErrorCode};
@@ -164,6 +164,9 @@ impl CliCommand<()> for InitTool {
                 .map_err(|err| CliError::UnableToParse("rest_url", err.to_string()))?,
         );
 
+        // Record the chain id, so commands using this profile can check they're on the same network
+        profile_config.chain_id = chain_id(&client).await.ok();
+
         // Check if account exists
         let account_exists = match client.get_account(address).await {
             Ok(_) => true,
@@ -3,6 +3,7 @@
 
 use crate::common::init::Network;
 use crate::common::utils::prompt_yes_with_override;
+use crate::move_tool::abort_source::AbortSource;
 use crate::{
     common::utils::{
         chain_id, check_if_file_exists, create_dir_if_not_exist, dir_default_to_current,
@@ -15,16 +16,21 @@ use crate::{
 use aptos_crypto::ed25519::Ed25519Signature;
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
-    x25519, PrivateKey, ValidCryptoMaterial, ValidCryptoMaterialStringExt,
+    signing_message, x25519, PrivateKey, Signature, ValidCryptoMaterial,
+    ValidCryptoMaterialStringExt,
 };
 use aptos_global_constants::adjust_gas_headroom;
 use aptos_keygen::KeyGen;
-use aptos_rest_client::aptos_api_types::{ExplainVMStatus, HashValue, UserTransaction};
+use aptos_rest_client::aptos_api_types::{
+    ExplainVMStatus, HashValue, TransactionData, UserTransaction,
+};
 use aptos_rest_client::error::RestError;
 use aptos_rest_client::{Client, Transaction};
-use aptos_sdk::{transaction_builder::TransactionFactory, types::LocalAccount};
+use aptos_sdk::transaction_builder::TransactionFactory;
+use aptos_types::chain_id::ChainId;
 use aptos_types::transaction::{
-    authenticator::AuthenticationKey, SignedTransaction, TransactionPayload,
+    authenticator::AuthenticationKey, ExecutionStatus, RawTransaction, SignedTransaction,
+    TransactionPayload,
 };
 use async_trait::async_trait;
 use clap::{ArgEnum, Parser};
@@ -75,6 +81,8 @@ pub enum CliError {
     MoveCompilationError(String),
     #[error("Move unit tests failed")]
     MoveTestError,
+    #[error("Move coverage check failed: {0}")]
+    MoveCoverageError(String),
     #[error("Move Prover failed: {0}")]
     MoveProverError(String),
     #[error("Unable to parse '{0}': error: {1}")]
@@ -85,6 +93,8 @@ pub enum CliError {
     UnexpectedError(String),
     #[error("Simulation failed with status: {0}")]
     SimulationError(String),
+    #[error("Transaction failed: {0}")]
+    TransactionFailed(String),
 }
 
 impl CliError {
@@ -99,11 +109,13 @@ impl CliError {
             CliError::IO(_, _) => "IO",
             CliError::MoveCompilationError(_) => "MoveCompilationError",
             CliError::MoveTestError => "MoveTestError",
+            CliError::MoveCoverageError(_) => "MoveCoverageError",
             CliError::MoveProverError(_) => "MoveProverError",
             CliError::UnableToParse(_, _) => "UnableToParse",
             CliError::UnableToReadFile(_, _) => "UnableToReadFile",
             CliError::UnexpectedError(_) => "UnexpectedError",
             CliError::SimulationError(_) => "SimulationError",
+            CliError::TransactionFailed(_) => "TransactionFailed",
         }
     }
 }
@@ -200,6 +212,47 @@ pub struct ProfileConfig {
     /// URL for the Faucet endpoint (if applicable)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub faucet_url: Option<String>,
+    /// Chain id of the network, checked against the REST endpoint before submitting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<ChainId>,
+    /// Hex encoded private key file to sign with, instead of storing `private_key` in the config
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key_file: Option<PathBuf>,
+    /// Move Prover settings for `aptos move prove`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prover: Option<ProverConfig>,
+}
+
+/// Move Prover settings of a profile, overridden by the flags of `aptos move prove`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProverConfig {
+    /// Only verify a module, or a single function given as `module::function`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only: Option<String>,
+    /// Timeout in seconds for each verification condition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vc_timeout: Option<usize>,
+    /// Number of cores to use for verification
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proc_cores: Option<usize>,
+    /// Seed for the solver
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<usize>,
+}
+
+impl ProfileConfig {
+    /// Private key for commands, from either the config itself or the profile's key file
+    pub fn signing_key(&self) -> CliTypedResult<Option<Ed25519PrivateKey>> {
+        if let Some(ref private_key) = self.private_key {
+            Ok(Some(private_key.clone()))
+        } else if let Some(ref file) = self.private_key_file {
+            Ok(Some(
+                EncodingType::Hex.load_key("profile private_key_file", file.as_path())?,
+            ))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 /// ProfileConfig but without the private parts
@@ -214,16 +267,22 @@ pub struct ProfileSummary {
     pub rest_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub faucet_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<ChainId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key_file: Option<PathBuf>,
 }
 
 impl From<&ProfileConfig> for ProfileSummary {
     fn from(config: &ProfileConfig) -> Self {
         ProfileSummary {
-            has_private_key: config.private_key.is_some(),
+            has_private_key: config.private_key.is_some() || config.private_key_file.is_some(),
             public_key: config.public_key.clone(),
             account: config.account,
             rest_url: config.rest_url.clone(),
             faucet_url: config.faucet_url.clone(),
+            chain_id: config.chain_id,
+            private_key_file: config.private_key_file.clone(),
         }
     }
 }
@@ -741,7 +800,8 @@ impl PrivateKeyInputOptions {
             profile.profile_name(),
             ConfigSearchMode::CurrentDirAndParents,
         )?
-        .map(|p| (p.private_key, p.account))
+        .map(|p| p.signing_key().map(|key| (key, p.account)))
+        .transpose()?
         {
             match (maybe_address, maybe_config_address) {
                 (Some(address), _) => Ok((key, address)),
@@ -770,7 +830,8 @@ impl PrivateKeyInputOptions {
             profile.profile_name(),
             ConfigSearchMode::CurrentDirAndParents,
         )?
-        .map(|p| p.private_key)
+        .map(|p| p.signing_key())
+        .transpose()?
         {
             Ok(private_key)
         } else {
@@ -953,6 +1014,48 @@ impl FromStr for AccountAddressWrapper {
     }
 }
 
+/// Human readable summary of what a transaction does, shown before asking to submit it
+pub fn payload_summary(sender: AccountAddress, payload: &TransactionPayload) -> String {
+    let details = match payload {
+        TransactionPayload::EntryFunction(entry_function) => {
+            let ty_args = if entry_function.ty_args().is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "<{}>",
+                    entry_function
+                        .ty_args()
+                        .iter()
+                        .map(|ty_arg| ty_arg.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            format!(
+                "\tFunction: {}::{}{}\n\tArguments (BCS): [{}]",
+                entry_function.module(),
+                entry_function.function(),
+                ty_args,
+                entry_function
+                    .args()
+                    .iter()
+                    .map(|arg| format!("0x{}", hex::encode(arg)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+        TransactionPayload::Script(script) => format!(
+            "\tScript hash: {}\n\tArguments: {:?}",
+            aptos_crypto::HashValue::sha3_256_of(script.code()),
+            script.args()
+        ),
+        TransactionPayload::ModuleBundle(bundle) => {
+            format!("\tPublishing {} modules", bundle.iter().count())
+        }
+    };
+    format!("Transaction from {}:\n{}", sender, details)
+}
+
 /// Loads an account arg and allows for naming based on profiles
 pub fn load_account_arg(str: &str) -> Result<AccountAddress, CliError> {
     if str.starts_with("0x") {
@@ -963,7 +1066,8 @@ pub fn load_account_arg(str: &str) -> Result<AccountAddress, CliError> {
         Ok(account_address)
     } else if let Some(Some(private_key)) =
         CliConfig::load_profile(Some(str), ConfigSearchMode::CurrentDirAndParents)?
-            .map(|p| p.private_key)
+            .map(|p| p.signing_key())
+            .transpose()?
     {
         let public_key = private_key.public_key();
         Ok(account_address_from_public_key(&public_key))
@@ -1004,7 +1108,8 @@ pub fn load_manifest_account_arg(str: &str) -> Result<Option<AccountAddress>, Cl
         Ok(Some(account_address))
     } else if let Some(Some(private_key)) =
         CliConfig::load_profile(Some(str), ConfigSearchMode::CurrentDirAndParents)?
-            .map(|p| p.private_key)
+            .map(|p| p.signing_key())
+            .transpose()?
     {
         let public_key = private_key.public_key();
         Ok(Some(account_address_from_public_key(&public_key)))
@@ -1015,6 +1120,22 @@ pub fn load_manifest_account_arg(str: &str) -> Result<Option<AccountAddress>, Cl
     }
 }
 
+/// Format commands print their results and errors in
+///
+/// `json` is meant for scripts: the result is under `Result`, or the error message is under
+/// `Error` with the kind of error under `ErrorCode`.  `text` is meant to be read by people.
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Text,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Json
+    }
+}
+
 /// A common trait for all CLI commands to have consistent outputs
 #[async_trait]
 pub trait CliCommand<T: Serialize + Send>: Sized + Send {
@@ -1070,6 +1191,16 @@ pub struct TransactionSummary {
     pub version: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vm_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<EventSummary>>,
+}
+
+/// An event emitted by a transaction, with its data decoded
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EventSummary {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: serde_json::Value,
 }
 
 impl From<Transaction> for TransactionSummary {
@@ -1091,6 +1222,7 @@ impl From<&Transaction> for TransactionSummary {
                 version: None,
                 vm_status: None,
                 timestamp_us: None,
+                events: None,
             },
             Transaction::UserTransaction(txn) => TransactionSummary {
                 transaction_hash: txn.info.hash,
@@ -1103,6 +1235,15 @@ impl From<&Transaction> for TransactionSummary {
                 sequence_number: Some(txn.request.sequence_number.0),
                 timestamp_us: Some(txn.timestamp.0),
                 pending: None,
+                events: Some(
+                    txn.events
+                        .iter()
+                        .map(|event| EventSummary {
+                            event_type: event.typ.to_string(),
+                            data: event.data.clone(),
+                        })
+                        .collect(),
+                ),
             },
             Transaction::GenesisTransaction(txn) => TransactionSummary {
                 transaction_hash: txn.info.hash,
@@ -1115,6 +1256,7 @@ impl From<&Transaction> for TransactionSummary {
                 pending: None,
                 sequence_number: None,
                 timestamp_us: None,
+                events: None,
             },
             Transaction::BlockMetadataTransaction(txn) => TransactionSummary {
                 transaction_hash: txn.info.hash,
@@ -1127,6 +1269,7 @@ impl From<&Transaction> for TransactionSummary {
                 gas_unit_price: None,
                 pending: None,
                 sequence_number: None,
+                events: None,
             },
             Transaction::StateCheckpointTransaction(txn) => TransactionSummary {
                 transaction_hash: txn.info.hash,
@@ -1139,6 +1282,7 @@ impl From<&Transaction> for TransactionSummary {
                 gas_unit_price: None,
                 pending: None,
                 sequence_number: None,
+                events: None,
             },
         }
     }
@@ -1244,11 +1388,87 @@ pub struct TransactionOptions {
     pub(crate) gas_options: GasOptions,
     #[clap(flatten)]
     pub(crate) prompt_options: PromptOptions,
+    #[clap(flatten)]
+    pub(crate) external_signer_options: ExternalSignerOptions,
+}
+
+/// Options for signing transactions with a key held by an external signer, e.g. a hardware wallet
+#[derive(Debug, Default, Parser)]
+pub struct ExternalSignerOptions {
+    /// Hex encoded public key of the sender, when its private key is held by an external signer
+    ///
+    /// The CLI then doesn't sign the transaction.  Instead the transaction signing message is
+    /// printed for the external signer to sign, and the signature is passed back with
+    /// `--sender-signature`.  A profile without a private key is signed for in the same way.
+    #[clap(long)]
+    pub(crate) sender_public_key: Option<String>,
+
+    /// Hex encoded signature of the transaction signing message by the sender's external signer
+    ///
+    /// If not provided, the signing message is printed and nothing is submitted
+    #[clap(long)]
+    pub(crate) sender_signature: Option<String>,
+
+    /// Expiration time of the transaction in seconds since the Unix epoch
+    ///
+    /// Externally signed transactions must set it, so that the transaction rebuilt when the
+    /// signature is passed back is the one that was signed.  Defaults to 30 seconds from now.
+    #[clap(long)]
+    pub(crate) expiration_timestamp_secs: Option<u64>,
+}
+
+impl ExternalSignerOptions {
+    /// Signs the transaction with the signature passed back by the external signer, printing the
+    /// signing message when there is none yet
+    pub(crate) fn sign_transaction(
+        &self,
+        raw_txn: RawTransaction,
+        public_key: Ed25519PublicKey,
+    ) -> CliTypedResult<SignedTransaction> {
+        let signature = match self.sender_signature {
+            Some(ref signature) => Ed25519Signature::from_encoded_string(signature.trim())
+                .map_err(|err| CliError::UnableToParse("--sender-signature", err.to_string()))?,
+            None => {
+                let signing_message = signing_message(&raw_txn)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                eprintln!("Transaction signing message to sign with the sender key:");
+                eprintln!("{}", hex::encode(signing_message));
+                return Err(CliError::CommandArgumentError(
+                    "Sign the transaction signing message with the sender key, and rerun with '--sender-signature'"
+                        .to_string(),
+                ));
+            }
+        };
+        signature.verify(&raw_txn, &public_key).map_err(|err| {
+            CliError::CommandArgumentError(format!(
+                "'--sender-signature' is not a valid signature of the transaction by the sender key: {}",
+                err
+            ))
+        })?;
+        Ok(SignedTransaction::new(raw_txn, public_key, signature))
+    }
+}
+
+/// The key signing the transactions of a sender
+pub enum SenderKey {
+    /// A private key available to the CLI
+    Local(Ed25519PrivateKey),
+    /// A key held by an external signer
+    External(Ed25519PublicKey),
+}
+
+impl SenderKey {
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        match self {
+            SenderKey::Local(private_key) => private_key.public_key(),
+            SenderKey::External(public_key) => public_key.clone(),
+        }
+    }
 }
 
 impl TransactionOptions {
     /// Builds a rest client
-    fn rest_client(&self) -> CliTypedResult<Client> {
+    pub(crate) fn rest_client(&self) -> CliTypedResult<Client> {
         self.rest_options.client(&self.profile_options)
     }
 
@@ -1262,8 +1482,60 @@ impl TransactionOptions {
         )
     }
 
+    /// Retrieves the key signing for the sender and the sender address, which is held by an
+    /// external signer if it is only known by its public key
+    pub fn get_sender_key_and_address(&self) -> CliTypedResult<(SenderKey, AccountAddress)> {
+        let encoding = self.encoding_options.encoding;
+        if let Some(ref public_key) = self.external_signer_options.sender_public_key {
+            if self
+                .private_key_options
+                .extract_private_key_cli(encoding)?
+                .is_some()
+            {
+                return Err(CliError::CommandArgumentError(
+                    "'--sender-public-key' can't be used with a private key".to_string(),
+                ));
+            }
+            let public_key = Ed25519PublicKey::from_encoded_string(public_key.trim())
+                .map_err(|err| CliError::UnableToParse("--sender-public-key", err.to_string()))?;
+            let address = self
+                .sender_account
+                .unwrap_or_else(|| account_address_from_public_key(&public_key));
+            return Ok((SenderKey::External(public_key), address));
+        }
+
+        match self.get_key_and_address() {
+            Ok((private_key, address)) => Ok((SenderKey::Local(private_key), address)),
+            Err(err) => {
+                // A profile without a private key, e.g. one rotated to an external key, is
+                // signed for externally
+                if self
+                    .private_key_options
+                    .extract_private_key_cli(encoding)?
+                    .is_none()
+                {
+                    if let Ok(ProfileConfig {
+                        private_key: None,
+                        private_key_file: None,
+                        public_key: Some(public_key),
+                        account,
+                        ..
+                    }) = self.profile_options.profile()
+                    {
+                        let address = self
+                            .sender_account
+                            .or(account)
+                            .unwrap_or_else(|| account_address_from_public_key(&public_key));
+                        return Ok((SenderKey::External(public_key), address));
+                    }
+                }
+                Err(err)
+            }
+        }
+    }
+
     pub fn sender_address(&self) -> CliTypedResult<AccountAddress> {
-        Ok(self.get_key_and_address()?.1)
+        Ok(self.get_sender_key_and_address()?.1)
     }
 
     /// Gets the auth key by account address. We need to fetch the auth key from Rest API rather than creating an
@@ -1281,13 +1553,52 @@ impl TransactionOptions {
         get_sequence_number(&client, sender_address).await
     }
 
+    /// Retrieves the chain id from the REST endpoint, checking it matches the profile's
+    async fn chain_id(&self, client: &Client) -> CliTypedResult<ChainId> {
+        let chain_id = chain_id(client).await?;
+        if let Ok(ProfileConfig {
+            chain_id: Some(expected_chain_id),
+            ..
+        }) = self.profile_options.profile()
+        {
+            if expected_chain_id != chain_id {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Profile {} is for chain id {}, but the REST endpoint is on chain id {}",
+                    self.profile_options
+                        .profile_name()
+                        .unwrap_or(DEFAULT_PROFILE),
+                    expected_chain_id,
+                    chain_id
+                )));
+            }
+        }
+        Ok(chain_id)
+    }
+
     /// Submit a transaction
     pub async fn submit_transaction(
         &self,
         payload: TransactionPayload,
     ) -> CliTypedResult<Transaction> {
         let client = self.rest_client()?;
-        let (sender_key, sender_address) = self.get_key_and_address()?;
+        let (sender_key, sender_address) = self.get_sender_key_and_address()?;
+
+        // The transaction is rebuilt when the external signature is passed back, so everything
+        // signed must be the same on both runs
+        if let SenderKey::External(_) = sender_key {
+            if self.gas_options.gas_unit_price.is_none()
+                || self.gas_options.max_gas.is_none()
+                || self
+                    .external_signer_options
+                    .expiration_timestamp_secs
+                    .is_none()
+            {
+                return Err(CliError::CommandArgumentError(
+                    "An externally signed transaction must set '--gas-unit-price', '--max-gas' and '--expiration-timestamp-secs'"
+                        .to_string(),
+                ));
+            }
+        }
 
         // Get sequence number for account
         let sequence_number = self.sequence_number(sender_address).await?;
@@ -1308,12 +1619,12 @@ impl TransactionOptions {
         let max_gas = if let Some(max_gas) = self.gas_options.max_gas {
             // If the gas unit price was estimated ask, but otherwise you've chosen hwo much you want to spend
             if ask_to_confirm_price {
-                let message = format!("Do you want to submit transaction for a maximum of {} Octas at a gas unit price of {} Octas?",  max_gas * gas_unit_price, gas_unit_price);
+                let message = format!("{}\nDo you want to submit transaction for a maximum of {} Octas at a gas unit price of {} Octas?", payload_summary(sender_address, &payload), max_gas * gas_unit_price, gas_unit_price);
                 prompt_yes_with_override(&message, self.prompt_options)?;
             }
             max_gas
         } else {
-            let transaction_factory = TransactionFactory::new(chain_id(&client).await?)
+            let transaction_factory = TransactionFactory::new(self.chain_id(&client).await?)
                 .with_gas_unit_price(gas_unit_price);
 
             let unsigned_transaction = transaction_factory
@@ -1337,8 +1648,7 @@ impl TransactionOptions {
             // TODO: Add move resolver so we can explain the VM status with a proper error map
             let status = simulated_txn.info.status();
             if !status.is_success() {
-                let status = client.explain_vm_status(status);
-                return Err(CliError::SimulationError(status));
+                return Err(CliError::SimulationError(explain_status(&client, status)));
             }
 
             // Take the gas used and use a headroom factor on it
@@ -1355,7 +1665,8 @@ impl TransactionOptions {
             let upper_cost_bound = adjusted_max_gas * gas_unit_price;
             let lower_cost_bound = simulated_txn.info.gas_used() * gas_unit_price;
             let message = format!(
-                    "Do you want to submit a transaction for a range of [{} - {}] Octas at a gas unit price of {} Octas?",
+                    "{}\nDo you want to submit a transaction for a range of [{} - {}] Octas at a gas unit price of {} Octas?",
+                    payload_summary(sender_address, &payload),
                     lower_cost_bound,
                     upper_cost_bound,
                     gas_unit_price);
@@ -1364,18 +1675,41 @@ impl TransactionOptions {
         };
 
         // Sign and submit transaction
-        let transaction_factory = TransactionFactory::new(chain_id(&client).await?)
+        let transaction_factory = TransactionFactory::new(self.chain_id(&client).await?)
             .with_gas_unit_price(gas_unit_price)
             .with_max_gas_amount(max_gas);
-        let sender_account = &mut LocalAccount::new(sender_address, sender_key, sequence_number);
-        let transaction =
-            sender_account.sign_with_transaction_builder(transaction_factory.payload(payload));
-        let response = client
-            .submit_and_wait(&transaction)
-            .await
-            .map_err(|err| CliError::ApiError(err.to_string()))?;
-
-        Ok(response.into_inner())
+        let mut transaction_builder = transaction_factory
+            .payload(payload.clone())
+            .sender(sender_address)
+            .sequence_number(sequence_number);
+        if let Some(expiration_timestamp_secs) =
+            self.external_signer_options.expiration_timestamp_secs
+        {
+            transaction_builder =
+                transaction_builder.expiration_timestamp_secs(expiration_timestamp_secs);
+        }
+        let raw_txn = transaction_builder.build();
+        let transaction = match sender_key {
+            SenderKey::Local(private_key) => {
+                let public_key = private_key.public_key();
+                raw_txn
+                    .sign(&private_key, public_key)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+                    .into_inner()
+            }
+            SenderKey::External(public_key) => {
+                // The external signer is where the transaction is approved, so show what it is
+                eprintln!("{}", payload_summary(sender_address, &payload));
+                self.external_signer_options
+                    .sign_transaction(raw_txn, public_key)?
+            }
+        };
+        match client.submit_and_wait(&transaction).await {
+            Ok(response) => Ok(response.into_inner()),
+            Err(err) => Err(explain_failed_transaction(&client, &transaction)
+                .await
+                .unwrap_or_else(|| CliError::ApiError(err.to_string()))),
+        }
     }
 
     pub async fn simulate_transaction(
@@ -1385,7 +1719,7 @@ impl TransactionOptions {
         amount_transfer: Option<u64>,
     ) -> CliTypedResult<UserTransaction> {
         let client = self.rest_client()?;
-        let (sender_key, sender_address) = self.get_key_and_address()?;
+        let (sender_key, sender_address) = self.get_sender_key_and_address()?;
 
         // Get sequence number for account
         let sequence_number = get_sequence_number(&client, sender_address).await?;
@@ -1422,7 +1756,7 @@ impl TransactionOptions {
             )
         };
 
-        let transaction_factory = TransactionFactory::new(chain_id(&client).await?)
+        let transaction_factory = TransactionFactory::new(self.chain_id(&client).await?)
             .with_gas_unit_price(gas_price)
             .with_max_gas_amount(max_possible_gas);
 
@@ -1456,6 +1790,50 @@ impl TransactionOptions {
     }
 }
 
+/// Explains a VM status, pointing at the error constant in the sources when the aborting module is
+/// part of the package in the current directory
+fn explain_status(client: &Client, status: &ExecutionStatus) -> String {
+    let explanation = client.explain_vm_status(status);
+    match std::env::current_dir()
+        .ok()
+        .and_then(|dir| AbortSource::find(&dir, status))
+    {
+        Some(source) => format!("{} ({})", explanation, source.describe()),
+        None => explanation,
+    }
+}
+
+/// Looks up a submitted transaction that failed execution, describing why it failed and the gas
+/// it used
+///
+/// Returns `None` if the transaction wasn't committed, e.g. because it expired.
+async fn explain_failed_transaction(
+    client: &Client,
+    transaction: &SignedTransaction,
+) -> Option<CliError> {
+    let hash = transaction.clone().committed_hash();
+    let txn = match client
+        .get_transaction_by_hash_bcs(hash)
+        .await
+        .ok()?
+        .into_inner()
+    {
+        TransactionData::OnChain(txn) => txn,
+        TransactionData::Pending(_) => return None,
+    };
+    let status = txn.info.status();
+    if status.is_success() {
+        return None;
+    }
+    Some(CliError::TransactionFailed(format!(
+        "Transaction {} committed at version {} with {} gas used, but failed with status: {}",
+        hash,
+        txn.version,
+        txn.info.gas_used(),
+        explain_status(client, status)
+    )))
+}
+
 #[derive(Parser)]
 pub struct OptionalPoolAddressArgs {
     /// Address of the Staking pool
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    common::types::{CliError, CliTypedResult, PromptOptions},
+    common::types::{CliError, CliTypedResult, OutputFormat, PromptOptions},
     CliResult,
 };
 use aptos_build_info::build_information;
@@ -13,6 +13,7 @@ use aptos_telemetry::service::telemetry_is_disabled;
 use aptos_types::{chain_id::ChainId, transaction::authenticator::AuthenticationKey};
 use itertools::Itertools;
 use move_core_types::account_address::AccountAddress;
+use once_cell::sync::OnceCell;
 use reqwest::Url;
 use serde::Serialize;
 #[cfg(unix)]
@@ -27,6 +28,19 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// Format of the command output, set once from the `--output` flag
+static OUTPUT_FORMAT: OnceCell<OutputFormat> = OnceCell::new();
+
+/// Sets the format command results are printed in, which can only be done once
+pub fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+/// Format command results are printed in, JSON unless set otherwise
+pub fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or_default()
+}
+
 /// Prompts for confirmation until a yes or no is given explicitly
 pub fn prompt_yes(prompt: &str) -> bool {
     let mut result: Result<bool, ()> = Err(());
@@ -76,7 +90,10 @@ pub async fn to_common_result<T: Serialize>(
     }
 
     let result: ResultWrapper<T> = result.into();
-    let string = serde_json::to_string_pretty(&result).unwrap();
+    let string = match output_format() {
+        OutputFormat::Json => serde_json::to_string_pretty(&result).unwrap(),
+        OutputFormat::Text => result.to_text(),
+    };
     if is_err {
         Err(string)
     } else {
@@ -120,20 +137,46 @@ async fn send_telemetry_event(
 /// }
 ///
 /// {
-///   "Error":"Failed to run command"
+///   "Error":"Failed to run command",
+///   "ErrorCode":"UnexpectedError"
 /// }
 ///
 #[derive(Debug, Serialize)]
-enum ResultWrapper<T> {
-    Result(T),
-    Error(String),
+struct ResultWrapper<T> {
+    #[serde(rename = "Result", skip_serializing_if = "Option::is_none")]
+    result: Option<T>,
+    #[serde(rename = "Error", skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The kind of error, which stays the same across releases unlike the error message
+    #[serde(rename = "ErrorCode", skip_serializing_if = "Option::is_none")]
+    error_code: Option<&'static str>,
+}
+
+impl<T: Serialize> ResultWrapper<T> {
+    /// Renders the result as YAML, or the error on a single line
+    fn to_text(&self) -> String {
+        if let Some(ref error) = self.error {
+            format!("Error [{}]: {}", self.error_code.unwrap_or_default(), error)
+        } else {
+            let yaml = serde_yaml::to_string(&self.result).unwrap();
+            yaml.trim_start_matches("---").trim().to_string()
+        }
+    }
 }
 
 impl<T> From<CliTypedResult<T>> for ResultWrapper<T> {
     fn from(result: CliTypedResult<T>) -> Self {
         match result {
-            Ok(inner) => ResultWrapper::Result(inner),
-            Err(inner) => ResultWrapper::Error(inner.to_string()),
+            Ok(inner) => ResultWrapper {
+                result: Some(inner),
+                error: None,
+                error_code: None,
+            },
+            Err(inner) => ResultWrapper {
+                result: None,
+                error_code: Some(inner.to_str()),
+                error: Some(inner.to_string()),
+            },
         }
     }
 }
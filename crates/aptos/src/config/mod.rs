@@ -2,18 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::common::types::{
-    CliCommand, CliConfig, CliError, CliResult, CliTypedResult, ConfigSearchMode, ProfileSummary,
-    CONFIG_FOLDER,
+    CliCommand, CliConfig, CliError, CliResult, CliTypedResult, ConfigSearchMode, ProfileConfig,
+    ProfileSummary, PromptOptions, SaveFile, CONFIG_FOLDER, DEFAULT_PROFILE,
 };
 use crate::common::utils::{
-    create_dir_if_not_exist, current_dir, read_from_file, write_to_user_only_file,
+    create_dir_if_not_exist, current_dir, prompt_yes_with_override, read_from_file,
+    write_to_user_only_file,
 };
 use crate::genesis::git::{from_yaml, to_yaml};
 use crate::Tool;
 use async_trait::async_trait;
-use clap::ArgEnum;
-use clap::CommandFactory;
 use clap::Parser;
+use clap::{ArgEnum, CommandFactory};
 use clap_complete::{generate, Shell};
 use serde::Deserialize;
 use serde::Serialize;
@@ -33,6 +33,8 @@ pub enum ConfigTool {
     SetGlobalConfig(SetGlobalConfig),
     ShowGlobalConfig(ShowGlobalConfig),
     ShowProfiles(ShowProfiles),
+    ExportProfile(ExportProfile),
+    ImportProfile(ImportProfile),
 }
 
 impl ConfigTool {
@@ -43,6 +45,8 @@ impl ConfigTool {
             ConfigTool::SetGlobalConfig(tool) => tool.execute_serialized().await,
             ConfigTool::ShowGlobalConfig(tool) => tool.execute_serialized().await,
             ConfigTool::ShowProfiles(tool) => tool.execute_serialized().await,
+            ConfigTool::ExportProfile(tool) => tool.execute_serialized_success().await,
+            ConfigTool::ImportProfile(tool) => tool.execute_serialized_success().await,
         }
     }
 }
@@ -53,7 +57,7 @@ impl ConfigTool {
 /// to install the completion file.
 #[derive(Parser)]
 pub struct GenerateShellCompletions {
-    /// Shell to generate completions for one of [bash, elvish, powershell, zsh]
+    /// Shell to generate completions for one of [bash, elvish, fish, powershell, zsh]
     #[clap(long)]
     shell: Shell,
 
@@ -148,6 +152,94 @@ impl CliCommand<BTreeMap<String, ProfileSummary>> for ShowProfiles {
     }
 }
 
+/// Export a profile to a file
+///
+/// The exported profile can be imported into another project's config with
+/// `aptos config import-profile`.  The private key is left out unless
+/// `--include-private-key` is given.
+#[derive(Parser, Debug)]
+pub struct ExportProfile {
+    /// Which profile to export
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// Include the private key in the exported profile
+    #[clap(long)]
+    include_private_key: bool,
+
+    #[clap(flatten)]
+    save_file: SaveFile,
+}
+
+#[async_trait]
+impl CliCommand<()> for ExportProfile {
+    fn command_name(&self) -> &'static str {
+        "ExportProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        self.save_file.check_file()?;
+        let mut profile = CliConfig::load_profile(
+            Some(self.profile.as_str()),
+            ConfigSearchMode::CurrentDirAndParents,
+        )?
+        .ok_or_else(|| CliError::ConfigNotFoundError(self.profile.clone()))?;
+        if !self.include_private_key {
+            profile.private_key = None;
+        }
+
+        let yaml = to_yaml(&profile)?;
+        if self.include_private_key {
+            self.save_file
+                .save_to_file_confidential("Profile", yaml.as_bytes())
+        } else {
+            self.save_file.save_to_file("Profile", yaml.as_bytes())
+        }
+    }
+}
+
+/// Import a profile from a file exported with `aptos config export-profile`
+#[derive(Parser, Debug)]
+pub struct ImportProfile {
+    /// Name to save the imported profile as
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// File containing the exported profile
+    #[clap(long, parse(from_os_str))]
+    input_file: PathBuf,
+
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<()> for ImportProfile {
+    fn command_name(&self) -> &'static str {
+        "ImportProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let bytes = read_from_file(self.input_file.as_path())?;
+        let profile: ProfileConfig = from_yaml(&String::from_utf8(bytes)?)?;
+
+        let mut config = if CliConfig::config_exists(ConfigSearchMode::CurrentDir) {
+            CliConfig::load(ConfigSearchMode::CurrentDir)?
+        } else {
+            CliConfig::default()
+        };
+        let profiles = config.profiles.get_or_insert_with(BTreeMap::new);
+        if profiles.contains_key(&self.profile) {
+            prompt_yes_with_override(
+                &format!("Profile {} already exists, overwrite it?", self.profile),
+                self.prompt_options,
+            )?;
+        }
+        profiles.insert(self.profile, profile);
+        config.save()
+    }
+}
+
 /// Shows the properties in the global config
 #[derive(Parser, Debug)]
 pub struct ShowGlobalConfig {}
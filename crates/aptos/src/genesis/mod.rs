@@ -3,6 +3,7 @@
 
 pub mod git;
 pub mod keys;
+pub mod node_config;
 #[cfg(test)]
 mod tests;
 pub mod tools;
@@ -53,6 +54,7 @@ pub enum GenesisTool {
     GenerateGenesis(GenerateGenesis),
     GenerateKeys(keys::GenerateKeys),
     GenerateLayoutTemplate(keys::GenerateLayoutTemplate),
+    GenerateNodeConfig(node_config::GenerateNodeConfig),
     GenerateAdminWriteSet(keys::GenerateAdminWriteSet),
     SetupGit(git::SetupGit),
     SetValidatorConfiguration(keys::SetValidatorConfiguration),
@@ -65,6 +67,7 @@ impl GenesisTool {
             GenesisTool::GenerateGenesis(tool) => tool.execute_serialized().await,
             GenesisTool::GenerateKeys(tool) => tool.execute_serialized().await,
             GenesisTool::GenerateLayoutTemplate(tool) => tool.execute_serialized_success().await,
+            GenesisTool::GenerateNodeConfig(tool) => tool.execute_serialized().await,
             GenesisTool::GenerateAdminWriteSet(tool) => tool.execute_serialized_success().await,
             GenesisTool::SetupGit(tool) => tool.execute_serialized_success().await,
             GenesisTool::SetValidatorConfiguration(tool) => tool.execute_serialized_success().await,
@@ -115,7 +118,7 @@ impl CliCommand<Vec<PathBuf>> for GenerateGenesis {
             (genesis_bytes, mainnet_genesis.generate_waypoint()?)
         } else {
             let mut test_genesis = fetch_genesis_info(self.git_options)?;
-            let genesis_bytes = bcs::to_bytes(test_genesis.clone().get_genesis())
+            let genesis_bytes = bcs::to_bytes(test_genesis.clone().get_genesis()?)
                 .map_err(|e| CliError::BCS(GENESIS_FILE, e))?;
             (genesis_bytes, test_genesis.generate_waypoint()?)
         };
@@ -0,0 +1,276 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{
+        types::{CliError, CliTypedResult, PromptOptions, RngArgs},
+        utils::{
+            check_if_file_exists, create_dir_if_not_exist, dir_default_to_current, read_line,
+            write_to_user_only_file,
+        },
+    },
+    genesis::git::to_yaml,
+    CliCommand,
+};
+use aptos_config::{
+    config::{
+        DiscoveryMethod, Identity, IdentityBlob, InitialSafetyRulesConfig, NetworkConfig,
+        NodeConfig, OnDiskStorageConfig, Peer, PeerRole, RoleType, SafetyRulesService,
+        SecureBackend, WaypointConfig,
+    },
+    network_id::NetworkId,
+};
+use aptos_crypto::{x25519, PrivateKey};
+use aptos_global_constants::FULLNODE_NETWORK_KEY;
+use aptos_secure_storage::{CryptoStorage, KVStorage, OnDiskStorage};
+use aptos_types::{account_address::from_identity_public_key, network_address::NetworkAddress};
+use async_trait::async_trait;
+use clap::{ArgEnum, Parser};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+const NODE_CONFIG_FILE: &str = "node.yaml";
+const GENESIS_FILE: &str = "genesis.blob";
+const WAYPOINT_FILE: &str = "waypoint.txt";
+const VALIDATOR_FILE: &str = "validator-identity.yaml";
+const VFN_FILE: &str = "validator-full-node-identity.yaml";
+const FULLNODE_IDENTITY_FILE: &str = "fullnode-identity.yaml";
+const FULLNODE_STORAGE_FILE: &str = "fullnode-secure-data.json";
+const FULLNODE_PEER_ID: &str = "fullnode_network_peer_id";
+const SAFETY_RULES_STORAGE_FILE: &str = "secure-data.json";
+
+const VALIDATOR_NETWORK_ADDRESS: &str = "/ip4/0.0.0.0/tcp/6180";
+const VFN_NETWORK_ADDRESS: &str = "/ip4/0.0.0.0/tcp/6181";
+const PUBLIC_NETWORK_ADDRESS: &str = "/ip4/0.0.0.0/tcp/6182";
+
+/// The kind of node to generate a config for
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum NodeRole {
+    Validator,
+    ValidatorFullnode,
+    PublicFullnode,
+}
+
+/// Where to keep a public fullnode's generated network identity
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum IdentityBackend {
+    /// An identity YAML file
+    File,
+    /// A JSON secure storage file
+    OnDiskStorage,
+}
+
+/// Generate a ready to run node config
+///
+/// Generates the YAML config for a validator, validator fullnode, or public fullnode, along with
+/// the network identity of a public fullnode.  Validators and validator fullnodes use the identity
+/// files generated by `aptos genesis generate-keys`.  The generated files are written to
+/// `output-dir`, and refer to the genesis, waypoint, and identity files in `config-dir`, where
+/// they need to be copied to on the node.
+#[derive(Parser)]
+pub struct GenerateNodeConfig {
+    /// Role of the node, asked for if not given
+    #[clap(long, arg_enum)]
+    pub(crate) role: Option<NodeRole>,
+
+    /// Output directory for the generated files
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_dir: Option<PathBuf>,
+
+    /// Directory the node reads its genesis, waypoint and identity files from
+    #[clap(long, parse(from_os_str), default_value = "/opt/aptos/genesis")]
+    pub(crate) config_dir: PathBuf,
+
+    /// Directory the node stores its databases in
+    #[clap(long, parse(from_os_str), default_value = "/opt/aptos/data")]
+    pub(crate) data_dir: PathBuf,
+
+    /// Peers to connect to on startup, as `<peer id>=<network address>` pairs
+    ///
+    /// For a validator fullnode this is its validator, and for a public fullnode these are
+    /// upstream fullnodes.  The network addresses must include the peers' noise keys, e.g.
+    /// `/dns/fullnode.example.com/tcp/6182/noise-ik/<key>/handshake/0`
+    #[clap(long, parse(try_from_str = crate::common::utils::parse_map), default_value = "")]
+    pub(crate) seed_peers: BTreeMap<aptos_types::PeerId, NetworkAddress>,
+
+    /// Where to keep a public fullnode's network identity
+    #[clap(long, arg_enum, default_value = "file")]
+    pub(crate) identity_backend: IdentityBackend,
+
+    /// Opt out of sending telemetry to Aptos
+    ///
+    /// Also set `APTOS_DISABLE_TELEMETRY=true` in the node's environment to disable metrics
+    #[clap(long)]
+    pub(crate) disable_telemetry: bool,
+
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+    #[clap(flatten)]
+    pub(crate) rng_args: RngArgs,
+}
+
+impl GenerateNodeConfig {
+    fn role(&self) -> CliTypedResult<NodeRole> {
+        if let Some(role) = self.role {
+            return Ok(role);
+        }
+        eprintln!("Choose the node's role [validator, validator-fullnode, public-fullnode]");
+        let input = read_line("role")?;
+        NodeRole::from_str(input.trim(), true).map_err(CliError::CommandArgumentError)
+    }
+
+    fn seeds(&self, role: PeerRole) -> HashMap<aptos_types::PeerId, Peer> {
+        self.seed_peers
+            .iter()
+            .map(|(peer_id, address)| (*peer_id, Peer::from_addrs(role, vec![address.clone()])))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl CliCommand<Vec<PathBuf>> for GenerateNodeConfig {
+    fn command_name(&self) -> &'static str {
+        "GenerateNodeConfig"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<PathBuf>> {
+        let role = self.role()?;
+        let output_dir = dir_default_to_current(self.output_dir.clone())?;
+        let node_config_file = output_dir.join(NODE_CONFIG_FILE);
+        check_if_file_exists(node_config_file.as_path(), self.prompt_options)?;
+        create_dir_if_not_exist(output_dir.as_path())?;
+        let mut files = vec![node_config_file.clone()];
+
+        let mut config = NodeConfig::default();
+        config.base.data_dir = self.data_dir.clone();
+        config.base.waypoint = WaypointConfig::FromFile(self.config_dir.join(WAYPOINT_FILE));
+        config.execution.genesis_file_location = self.config_dir.join(GENESIS_FILE);
+        if self.disable_telemetry {
+            config.logger.enable_telemetry_remote_log = false;
+            config.logger.enable_telemetry_flush = false;
+        }
+
+        match role {
+            NodeRole::Validator => {
+                let identity_file = self.config_dir.join(VALIDATOR_FILE);
+                config.base.role = RoleType::Validator;
+
+                let mut safety_rules_storage = OnDiskStorageConfig::default();
+                safety_rules_storage.path = self.data_dir.join(SAFETY_RULES_STORAGE_FILE);
+                let safety_rules = &mut config.consensus.safety_rules;
+                safety_rules.service = SafetyRulesService::Local;
+                safety_rules.backend = SecureBackend::OnDiskStorage(safety_rules_storage);
+                safety_rules.initial_safety_rules_config = InitialSafetyRulesConfig::from_file(
+                    identity_file.clone(),
+                    WaypointConfig::FromFile(self.config_dir.join(WAYPOINT_FILE)),
+                );
+
+                let mut validator_network = NetworkConfig::network_with_id(NetworkId::Validator);
+                validator_network.discovery_method = DiscoveryMethod::Onchain;
+                validator_network.mutual_authentication = true;
+                validator_network.listen_address = parse_address(VALIDATOR_NETWORK_ADDRESS);
+                validator_network.identity = Identity::from_file(identity_file.clone());
+                config.validator_network = Some(validator_network);
+
+                let mut vfn_network = NetworkConfig::network_with_id(NetworkId::Vfn);
+                vfn_network.listen_address = parse_address(VFN_NETWORK_ADDRESS);
+                vfn_network.identity = Identity::from_file(identity_file);
+                config.full_node_networks = vec![vfn_network];
+            }
+            NodeRole::ValidatorFullnode => {
+                config.base.role = RoleType::FullNode;
+
+                let mut vfn_network = NetworkConfig::network_with_id(NetworkId::Vfn);
+                vfn_network.listen_address = parse_address(VFN_NETWORK_ADDRESS);
+                vfn_network.seeds = self.seeds(PeerRole::Validator);
+
+                let mut public_network = NetworkConfig::network_with_id(NetworkId::Public);
+                public_network.discovery_method = DiscoveryMethod::Onchain;
+                public_network.listen_address = parse_address(PUBLIC_NETWORK_ADDRESS);
+                public_network.identity = Identity::from_file(self.config_dir.join(VFN_FILE));
+                config.full_node_networks = vec![vfn_network, public_network];
+            }
+            NodeRole::PublicFullnode => {
+                config.base.role = RoleType::FullNode;
+
+                let mut public_network = NetworkConfig::network_with_id(NetworkId::Public);
+                public_network.discovery_method = DiscoveryMethod::Onchain;
+                public_network.listen_address = parse_address(PUBLIC_NETWORK_ADDRESS);
+                public_network.seeds = self.seeds(PeerRole::Upstream);
+
+                let mut key_generator = self.rng_args.key_generator()?;
+                public_network.identity = match self.identity_backend {
+                    IdentityBackend::File => {
+                        let network_private_key = key_generator.generate_x25519_private_key()?;
+                        let identity_blob = IdentityBlob {
+                            account_address: Some(from_identity_public_key(
+                                network_private_key.public_key(),
+                            )),
+                            account_private_key: None,
+                            consensus_private_key: None,
+                            network_private_key,
+                        };
+                        let identity_file = output_dir.join(FULLNODE_IDENTITY_FILE);
+                        check_if_file_exists(identity_file.as_path(), self.prompt_options)?;
+                        write_to_user_only_file(
+                            identity_file.as_path(),
+                            FULLNODE_IDENTITY_FILE,
+                            to_yaml(&identity_blob)?.as_bytes(),
+                        )?;
+                        files.push(identity_file);
+                        Identity::from_file(self.config_dir.join(FULLNODE_IDENTITY_FILE))
+                    }
+                    IdentityBackend::OnDiskStorage => {
+                        // Secure storage holds network keys as ed25519 keys, which the node
+                        // converts to x25519
+                        let private_key = key_generator.generate_ed25519_private_key();
+                        let network_key =
+                            x25519::PrivateKey::from_ed25519_private_bytes(&private_key.to_bytes())
+                                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                        let peer_id = from_identity_public_key(network_key.public_key());
+
+                        let storage_file = output_dir.join(FULLNODE_STORAGE_FILE);
+                        check_if_file_exists(storage_file.as_path(), self.prompt_options)?;
+                        if storage_file.exists() {
+                            std::fs::remove_file(&storage_file).map_err(|err| {
+                                CliError::IO(FULLNODE_STORAGE_FILE.to_string(), err)
+                            })?;
+                        }
+                        let mut storage = OnDiskStorage::new(storage_file.clone());
+                        storage
+                            .import_private_key(FULLNODE_NETWORK_KEY, private_key)
+                            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                        storage
+                            .set(FULLNODE_PEER_ID, peer_id)
+                            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                        files.push(storage_file);
+
+                        let mut storage_config = OnDiskStorageConfig::default();
+                        storage_config.path = self.config_dir.join(FULLNODE_STORAGE_FILE);
+                        Identity::from_storage(
+                            FULLNODE_NETWORK_KEY.to_string(),
+                            FULLNODE_PEER_ID.to_string(),
+                            SecureBackend::OnDiskStorage(storage_config),
+                        )
+                    }
+                };
+                config.full_node_networks = vec![public_network];
+            }
+        }
+
+        config.save(&node_config_file)?;
+        eprintln!(
+            "Copy the generated files to {} on the node, along with the {} and {}",
+            self.config_dir.display(),
+            GENESIS_FILE,
+            WAYPOINT_FILE
+        );
+        Ok(files)
+    }
+}
+
+fn parse_address(address: &str) -> NetworkAddress {
+    address.parse().expect("Listen address must be valid")
+}
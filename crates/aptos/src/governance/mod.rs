@@ -91,18 +91,7 @@ impl CliCommand<VerifiedProposal> for ViewProposal {
     async fn execute(mut self) -> CliTypedResult<VerifiedProposal> {
         // Get proposal
         let client = self.rest_options.client(&self.profile)?;
-        let forum = client
-            .get_account_resource_bcs::<VotingForum>(
-                AccountAddress::ONE,
-                "0x1::voting::VotingForum<0x1::governance_proposal::GovernanceProposal>",
-            )
-            .await?
-            .into_inner();
-        let voting_table = forum.table_handle.0;
-
-        let proposal: Proposal = get_proposal(&client, voting_table, self.proposal_id)
-            .await?
-            .into();
+        let proposal = get_onchain_proposal(&client, self.proposal_id).await?;
 
         let metadata_hash = proposal.metadata.get("metadata_hash").unwrap();
         let metadata_url = proposal.metadata.get("metadata_location").unwrap();
@@ -209,18 +198,7 @@ impl CliCommand<VerifyProposalResponse> for VerifyProposal {
 
         // Retrieve the onchain proposal
         let client = self.rest_options.client(&self.profile)?;
-        let forum = client
-            .get_account_resource_bcs::<VotingForum>(
-                AccountAddress::ONE,
-                "0x1::voting::VotingForum<0x1::governance_proposal::GovernanceProposal>",
-            )
-            .await?
-            .into_inner();
-        let voting_table = forum.table_handle.0;
-
-        let proposal: Proposal = get_proposal(&client, voting_table, self.proposal_id)
-            .await?
-            .into();
+        let proposal = get_onchain_proposal(&client, self.proposal_id).await?;
 
         // Compare the hashes
         let computed_hash = hash.to_hex();
@@ -234,6 +212,30 @@ impl CliCommand<VerifyProposalResponse> for VerifyProposal {
     }
 }
 
+/// Current time in seconds, to compare against proposal expiration
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Retrieves a proposal from the governance voting forum
+async fn get_onchain_proposal(client: &Client, proposal_id: u64) -> CliTypedResult<Proposal> {
+    let forum = client
+        .get_account_resource_bcs::<VotingForum>(
+            AccountAddress::ONE,
+            "0x1::voting::VotingForum<0x1::governance_proposal::GovernanceProposal>",
+        )
+        .await?
+        .into_inner();
+    let voting_table = forum.table_handle.0;
+
+    Ok(get_proposal(client, voting_table, proposal_id)
+        .await?
+        .into())
+}
+
 async fn get_proposal(
     client: &aptos_rest_client::Client,
     voting_table: AccountAddress,
@@ -461,13 +463,28 @@ impl CliCommand<Vec<TransactionSummary>> for SubmitVote {
             .rest_options
             .client(&self.txn_options.profile_options)?;
         let proposal_id = self.proposal_id;
+
+        // Votes are only accepted while the proposal is open
+        let proposal = get_onchain_proposal(client, proposal_id).await?;
+        if proposal.is_resolved {
+            return Err(CliError::CommandArgumentError(format!(
+                "Proposal {} has already been resolved",
+                proposal_id
+            )));
+        }
+        if proposal.expiration_secs <= now_secs() {
+            return Err(CliError::CommandArgumentError(format!(
+                "Voting on proposal {} has ended",
+                proposal_id
+            )));
+        }
+
         let voting_records = client
             .get_account_resource_bcs::<VotingRecords>(
                 CORE_CODE_ADDRESS,
                 "0x1::aptos_governance::VotingRecords",
             )
-            .await
-            .unwrap()
+            .await?
             .into_inner()
             .votes;
 
@@ -499,6 +516,10 @@ impl CliCommand<Vec<TransactionSummary>> for SubmitVote {
                 .await?
                 .into_inner();
             let voting_power = stake_pool.get_governance_voting_power();
+            if voting_power == 0 {
+                println!("Stake pool {} has no voting power", pool_address);
+                continue;
+            }
 
             prompt_yes_with_override(
                 &format!(
@@ -636,10 +657,37 @@ impl CliCommand<TransactionSummary> for ExecuteProposal {
     }
 
     async fn execute(mut self) -> CliTypedResult<TransactionSummary> {
-        let (bytecode, _script_hash) = self
+        let (bytecode, script_hash) = self
             .compile_proposal_args
             .compile("ExecuteProposal", self.txn_options.prompt_options)?;
-        // TODO: Check hash so we don't do a failed roundtrip?
+
+        // Check the proposal can be resolved with this script so we don't do a failed roundtrip
+        let client = self
+            .txn_options
+            .rest_options
+            .client(&self.txn_options.profile_options)?;
+        let proposal = get_onchain_proposal(&client, self.proposal_id).await?;
+        if proposal.execution_hash != script_hash.to_hex() {
+            return Err(CliError::CommandArgumentError(format!(
+                "Script hash {} does not match the execution hash {} of proposal {}",
+                script_hash, proposal.execution_hash, self.proposal_id
+            )));
+        }
+        if proposal.is_resolved {
+            return Err(CliError::CommandArgumentError(format!(
+                "Proposal {} has already been resolved",
+                self.proposal_id
+            )));
+        }
+        if !proposal.is_approved(now_secs()) {
+            return Err(CliError::CommandArgumentError(format!(
+                "Proposal {} has not passed voting, yes votes: {}, no votes: {}, minimum votes: {}",
+                self.proposal_id,
+                proposal.yes_votes,
+                proposal.no_votes,
+                proposal.min_vote_threshold
+            )));
+        }
 
         let args = vec![TransactionArgument::U64(self.proposal_id)];
         let txn = TransactionPayload::Script(Script::new(bytecode, vec![], args));
@@ -720,8 +768,10 @@ pub struct GenerateUpgradeProposal {
     pub(crate) account: AccountAddress,
 
     /// Where to store the generated proposal
+    ///
+    /// This used to be `--output`, which now selects the output format of every command
     #[clap(long, parse(from_os_str), default_value = "proposal.move")]
-    pub(crate) output: PathBuf,
+    pub(crate) output_file: PathBuf,
 
     /// What artifacts to include in the package. This can be one of `none`, `sparse`, and
     /// `all`. `none` is the most compact form and does not allow to reconstruct a source
@@ -752,7 +802,7 @@ impl CliCommand<()> for GenerateUpgradeProposal {
             move_options,
             account,
             included_artifacts,
-            output,
+            output_file,
             testnet,
         } = self;
         let package_path = move_options.get_package_path()?;
@@ -760,9 +810,9 @@ impl CliCommand<()> for GenerateUpgradeProposal {
         let package = BuiltPackage::build(package_path, options)?;
         let release = ReleasePackage::new(package)?;
         if testnet {
-            release.generate_script_proposal_testnet(account, output)?;
+            release.generate_script_proposal_testnet(account, output_file)?;
         } else {
-            release.generate_script_proposal(account, output)?;
+            release.generate_script_proposal(account, output_file)?;
         }
         Ok(())
     }
@@ -855,6 +905,25 @@ pub struct Proposal {
     resolution_time_secs: u64,
 }
 
+impl Proposal {
+    /// Whether the proposal has passed voting at the given time and can be resolved
+    ///
+    /// This mirrors `0x1::voting::get_proposal_state`: a proposal passes either once voting has
+    /// ended with more yes than no votes and enough votes in total, or early once either side
+    /// reaches the early resolution threshold.
+    fn is_approved(&self, now_secs: u64) -> bool {
+        let early_resolved = self
+            .early_resolution_vote_threshold
+            .map(|threshold| self.yes_votes >= threshold || self.no_votes >= threshold)
+            .unwrap_or(false);
+        if self.expiration_secs > now_secs && !early_resolved {
+            return false;
+        }
+        self.yes_votes > self.no_votes
+            && self.yes_votes.saturating_add(self.no_votes) >= self.min_vote_threshold
+    }
+}
+
 impl From<JsonProposal> for Proposal {
     fn from(proposal: JsonProposal) -> Self {
         let metadata = proposal
@@ -14,17 +14,28 @@ pub mod op;
 pub mod stake;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod test;
+pub mod util;
 
-use crate::common::types::{CliCommand, CliResult, CliTypedResult};
-use crate::common::utils::cli_build_information;
+use crate::common::types::{CliCommand, CliResult, CliTypedResult, OutputFormat};
+use crate::common::utils::{cli_build_information, set_output_format};
 use async_trait::async_trait;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::collections::BTreeMap;
 
 /// Command Line Interface (CLI) for developing and interacting with the Aptos blockchain
 #[derive(Parser)]
 #[clap(name = "aptos", author, version, propagate_version = true)]
-pub enum Tool {
+pub struct Tool {
+    /// Format to print the command's result or error in
+    #[clap(long, global = true, arg_enum, default_value_t = OutputFormat::Json)]
+    pub output: OutputFormat,
+
+    #[clap(subcommand)]
+    pub command: ToolCommand,
+}
+
+#[derive(Subcommand)]
+pub enum ToolCommand {
     #[clap(subcommand)]
     Account(account::AccountTool),
     #[clap(subcommand)]
@@ -43,12 +54,15 @@ pub enum Tool {
     Node(node::NodeTool),
     #[clap(subcommand)]
     Stake(stake::StakeTool),
+    #[clap(subcommand)]
+    Util(util::UtilTool),
 }
 
 impl Tool {
     pub async fn execute(self) -> CliResult {
-        use Tool::*;
-        match self {
+        use ToolCommand::*;
+        set_output_format(self.output);
+        match self.command {
             Account(tool) => tool.execute().await,
             Config(tool) => tool.execute().await,
             Genesis(tool) => tool.execute().await,
@@ -60,6 +74,7 @@ impl Tool {
             Move(tool) => tool.execute().await,
             Node(tool) => tool.execute().await,
             Stake(tool) => tool.execute().await,
+            Util(tool) => tool.execute().await,
         }
     }
 }
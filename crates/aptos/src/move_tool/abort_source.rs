@@ -0,0 +1,247 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Maps Move abort codes back to the sources of a local package
+//!
+//! The VM only reports the module and the code of an abort.  When the aborting module is part of
+//! the package in the current directory, the error constant with that code, and the places that
+//! use it, are found in the package sources.
+//!
+//! A module of the package is the aborting one if both its name and its address match.  Named
+//! addresses are resolved with the `[addresses]` of `Move.toml`; an address left unassigned
+//! (`_`) is given when the package is published, so it matches any address except those
+//! reserved for the framework.
+
+use aptos_types::{account_address::AccountAddress, transaction::ExecutionStatus};
+use move_core_types::{language_storage::ModuleId, vm_status::AbortLocation};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+/// Aptos error codes carry a category in the bits above the lowest 16, which hold the reason
+const REASON_MASK: u64 = 0xFFFF;
+
+/// Named addresses of a package, `None` for those left unassigned
+type NamedAddresses = BTreeMap<String, Option<AccountAddress>>;
+
+/// The error constant an abort code was raised with
+pub struct AbortSource {
+    pub constant_name: String,
+    pub file: PathBuf,
+    /// 1-based line of the constant's declaration
+    pub line: usize,
+    /// 1-based lines in the module referring to the constant
+    pub uses: Vec<usize>,
+}
+
+impl AbortSource {
+    /// Looks up an abort of `status` in the package in `package_dir`, if there is one
+    pub fn find(package_dir: &Path, status: &ExecutionStatus) -> Option<Self> {
+        let (module_id, code) = match status {
+            ExecutionStatus::MoveAbort {
+                location: AbortLocation::Module(module_id),
+                code,
+                ..
+            } => (module_id, *code),
+            _ => return None,
+        };
+        let manifest = std::fs::read_to_string(package_dir.join("Move.toml")).ok()?;
+        let named_addresses = named_addresses(&manifest);
+
+        source_files(&package_dir.join("sources"))
+            .into_iter()
+            .find_map(|file| {
+                let source = std::fs::read_to_string(&file).ok()?;
+                find_in_module(&source, module_id, &named_addresses, code).map(
+                    |(constant_name, line, uses)| AbortSource {
+                        constant_name,
+                        file,
+                        line,
+                        uses,
+                    },
+                )
+            })
+    }
+
+    /// Human readable description, e.g. to append to a VM status
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "aborted with {} declared at {}:{}",
+            self.constant_name,
+            self.file.display(),
+            self.line
+        );
+        if !self.uses.is_empty() {
+            write!(description, ", used at line(s)").unwrap();
+            for (i, line) in self.uses.iter().enumerate() {
+                let separator = if i == 0 { " " } else { ", " };
+                write!(description, "{}{}", separator, line).unwrap();
+            }
+        }
+        description
+    }
+}
+
+/// All `.move` files under `dir`, recursively
+fn source_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(source_files(&path));
+            } else if path.extension().map(|ext| ext == "move").unwrap_or(false) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// The `[addresses]` of a `Move.toml`
+fn named_addresses(manifest: &str) -> NamedAddresses {
+    let manifest: toml::Value = match toml::from_str(manifest) {
+        Ok(manifest) => manifest,
+        Err(_) => return NamedAddresses::new(),
+    };
+    manifest
+        .get("addresses")
+        .and_then(|addresses| addresses.as_table())
+        .map(|addresses| {
+            addresses
+                .iter()
+                .filter_map(|(name, address)| {
+                    let address = address.as_str()?;
+                    let address = if address == "_" {
+                        None
+                    } else {
+                        Some(AccountAddress::from_hex_literal(address).ok()?)
+                    };
+                    Some((name.clone(), address))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether the address of a module declaration, a literal or a named address, is `address`
+fn is_address(declared: &str, address: AccountAddress, named_addresses: &NamedAddresses) -> bool {
+    if declared.starts_with("0x") {
+        return AccountAddress::from_hex_literal(declared)
+            .map_or(false, |declared| declared == address);
+    }
+    match named_addresses.get(declared) {
+        Some(Some(named)) => *named == address,
+        Some(None) => !is_framework_reserved(address),
+        None => false,
+    }
+}
+
+/// The framework reserves the addresses 0x1 to 0xa, packages can't be published there
+fn is_framework_reserved(address: AccountAddress) -> bool {
+    let bytes = address.into_bytes();
+    let (last, rest) = bytes.split_last().unwrap();
+    rest.iter().all(|byte| *byte == 0) && (1..=10).contains(last)
+}
+
+/// Finds the constant matching `code` in the module `module_id` in `source`, returning its name,
+/// the line it is declared on, and the lines using it
+fn find_in_module(
+    source: &str,
+    module_id: &ModuleId,
+    named_addresses: &NamedAddresses,
+    code: u64,
+) -> Option<(String, usize, Vec<usize>)> {
+    let lines: Vec<&str> = source.lines().collect();
+    // Modules declared without an address take that of the enclosing `address` block
+    let mut block_address = None;
+    let start = lines.iter().position(|line| {
+        if let Some(address) = declared_address_block(line) {
+            block_address = Some(address);
+        }
+        match declared_module(line) {
+            Some((address, name)) => {
+                name == module_id.name().as_str()
+                    && address.or(block_address).map_or(false, |address| {
+                        is_address(address, *module_id.address(), named_addresses)
+                    })
+            }
+            None => false,
+        }
+    })?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| declared_module(line).is_some())
+        .map(|offset| start + 1 + offset)
+        .unwrap_or(lines.len());
+    let module_lines = &lines[start..end];
+
+    let (constant_index, constant_name) =
+        module_lines.iter().enumerate().find_map(|(index, line)| {
+            let (name, value) = parse_u64_constant(line)?;
+            if value == code || value == code & REASON_MASK {
+                Some((index, name))
+            } else {
+                None
+            }
+        })?;
+    let uses = module_lines
+        .iter()
+        .enumerate()
+        .filter(|(index, line)| *index != constant_index && mentions(line, &constant_name))
+        .map(|(index, _)| start + index + 1)
+        .collect();
+    Some((constant_name, start + constant_index + 1, uses))
+}
+
+/// Address and name of the module declared on the line, for `module addr::name {` and
+/// `module name {`
+fn declared_module(line: &str) -> Option<(Option<&str>, &str)> {
+    let declaration = line.trim_start().strip_prefix("module ")?;
+    let path = declaration
+        .split(|c: char| c.is_whitespace() || c == '{')
+        .next()?;
+    match path.rsplit_once("::") {
+        Some((address, name)) => Some((Some(address), name)),
+        None => Some((None, path)),
+    }
+}
+
+/// Address of the block declared on the line, for `address addr {`
+fn declared_address_block(line: &str) -> Option<&str> {
+    let declaration = line.trim_start().strip_prefix("address ")?;
+    declaration
+        .split(|c: char| c.is_whitespace() || c == '{')
+        .next()
+}
+
+/// Parses `const NAME: u64 = VALUE;`
+fn parse_u64_constant(line: &str) -> Option<(String, u64)> {
+    let declaration = line.trim_start().strip_prefix("const ")?;
+    let (name, rest) = declaration.split_once(':')?;
+    let (ty, value) = rest.split_once('=')?;
+    if ty.trim() != "u64" {
+        return None;
+    }
+    let value = value.split(';').next()?.trim();
+    let value = value.strip_suffix("u64").unwrap_or(value);
+    let value = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => value.parse().ok()?,
+    };
+    Some((name.trim().to_string(), value))
+}
+
+/// Whether `name` appears in the line as a whole identifier, outside of comments
+fn mentions(line: &str, name: &str) -> bool {
+    let code = line.split("//").next().unwrap_or_default();
+    code.match_indices(name).any(|(index, _)| {
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+        let before = code[..index].chars().next_back();
+        let after = code[index + name.len()..].chars().next();
+        !before.map(is_ident).unwrap_or(false) && !after.map(is_ident).unwrap_or(false)
+    })
+}
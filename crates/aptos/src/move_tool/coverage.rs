@@ -0,0 +1,311 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Source level coverage for Move unit tests
+//!
+//! The Move unit test runner records which bytecode instructions were executed in a coverage
+//! map.  This maps those instructions back onto source lines through the source maps produced
+//! by the compiler, so that coverage can be reported per module and exported in lcov format.
+
+use crate::common::{
+    types::{CliError, CliTypedResult},
+    utils::write_to_file,
+};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{
+        Bytecode, CodeOffset, CompiledModule, FunctionDefinition, FunctionDefinitionIndex,
+    },
+};
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_core_types::{
+    identifier::{IdentStr, Identifier},
+    language_storage::ModuleId,
+};
+use move_coverage::coverage_map::CoverageMap;
+use move_package::{compilation::compiled_package::CompiledPackage, BuildConfig};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+/// The file the Move unit test runner writes the coverage map to, relative to the package root
+const COVERAGE_MAP_FILE: &str = ".coverage_map.mvcov";
+
+/// Line and branch coverage of a single Move module
+pub struct ModuleCoverage {
+    pub module_name: String,
+    pub source_path: PathBuf,
+    /// Execution count of each source line containing code, keyed by 1-based line number
+    pub lines: BTreeMap<usize, u64>,
+    /// Branch outcomes of each conditional jump, keyed by 1-based line number
+    ///
+    /// Each outcome records whether the branch was taken at least once.
+    pub branches: BTreeMap<usize, Vec<bool>>,
+}
+
+impl ModuleCoverage {
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|count| **count > 0).count()
+    }
+
+    pub fn branches_found(&self) -> usize {
+        self.branches.values().map(|outcomes| outcomes.len()).sum()
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        self.branches
+            .values()
+            .flat_map(|outcomes| outcomes.iter())
+            .filter(|taken| **taken)
+            .count()
+    }
+}
+
+/// Coverage of all modules in the root package
+pub struct PackageCoverage {
+    pub modules: Vec<ModuleCoverage>,
+}
+
+impl PackageCoverage {
+    /// Loads the coverage map left by a unit test run and maps it onto the package sources
+    pub fn load(package_path: &Path, config: BuildConfig) -> CliTypedResult<Self> {
+        let coverage_map = CoverageMap::from_binary_file(package_path.join(COVERAGE_MAP_FILE))
+            .map_err(|err| {
+                CliError::UnexpectedError(format!("Failed to load coverage map: {}", err))
+            })?
+            .to_unified_exec_map();
+        let package = compile(package_path, config.clone())?;
+        // Test only modules and functions are left out of a build without test mode
+        let production_package = compile(
+            package_path,
+            BuildConfig {
+                test_mode: false,
+                ..config
+            },
+        )?;
+
+        Self::from_packages(
+            &package,
+            &production_package,
+            |module_id, function_name, offset| {
+                coverage_map
+                    .module_maps
+                    .get(&(*module_id.address(), module_id.name().to_owned()))
+                    .and_then(|map| map.function_maps.get(function_name))
+                    .and_then(|map| map.get(&(offset as u64)))
+                    .copied()
+                    .unwrap_or(0)
+            },
+        )
+    }
+
+    /// Maps the execution counts given by `execution_count` onto the sources of the test build `package`
+    ///
+    /// Only the modules and functions also in `production_package` are counted, so that test
+    /// only code doesn't count towards the coverage.
+    pub(crate) fn from_packages(
+        package: &CompiledPackage,
+        production_package: &CompiledPackage,
+        execution_count: impl Fn(&ModuleId, &IdentStr, CodeOffset) -> u64,
+    ) -> CliTypedResult<Self> {
+        let production_functions: BTreeMap<ModuleId, BTreeSet<Identifier>> = production_package
+            .root_modules()
+            .filter_map(|unit| match &unit.unit {
+                CompiledUnit::Module(NamedCompiledModule { module, .. }) => Some((
+                    module.self_id(),
+                    module
+                        .function_defs()
+                        .iter()
+                        .map(|function_def| function_name(module, function_def).to_owned())
+                        .collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+
+        let mut modules = Vec::new();
+        for unit in package.root_modules() {
+            let (module, source_map) = match &unit.unit {
+                CompiledUnit::Module(NamedCompiledModule {
+                    module, source_map, ..
+                }) => (module, source_map),
+                _ => continue,
+            };
+            let module_id = module.self_id();
+            let functions = match production_functions.get(&module_id) {
+                Some(functions) => functions,
+                None => continue,
+            };
+            let source = std::fs::read_to_string(&unit.source_path).map_err(|err| {
+                CliError::UnableToReadFile(unit.source_path.display().to_string(), err.to_string())
+            })?;
+            let line_starts = line_starts(&source);
+
+            let mut lines: BTreeMap<usize, u64> = BTreeMap::new();
+            let mut branches: BTreeMap<usize, Vec<bool>> = BTreeMap::new();
+            for (index, function_def) in module.function_defs().iter().enumerate() {
+                let code = match &function_def.code {
+                    Some(code) => &code.code,
+                    None => continue,
+                };
+                let function_name = function_name(module, function_def);
+                if !functions.contains(function_name) {
+                    continue;
+                }
+                let hits = |offset: CodeOffset| execution_count(&module_id, function_name, offset);
+
+                for (offset, instruction) in code.iter().enumerate() {
+                    let offset = offset as CodeOffset;
+                    let loc = match source_map
+                        .get_code_location(FunctionDefinitionIndex(index as u16), offset)
+                    {
+                        Ok(loc) => loc,
+                        Err(_) => continue,
+                    };
+                    let line = line_of(&line_starts, loc.start() as usize);
+                    let count = lines.entry(line).or_insert(0);
+                    *count = std::cmp::max(*count, hits(offset));
+
+                    // A conditional jump has two outcomes: jumping to its target, or falling
+                    // through to the next instruction.  An outcome is counted as taken when the
+                    // instruction it leads to was executed.
+                    if let Bytecode::BrTrue(target) | Bytecode::BrFalse(target) = instruction {
+                        branches
+                            .entry(line)
+                            .or_default()
+                            .extend([hits(*target) > 0, hits(offset + 1) > 0]);
+                    }
+                }
+            }
+
+            modules.push(ModuleCoverage {
+                module_name: module_id.short_str_lossless(),
+                source_path: unit.source_path.clone(),
+                lines,
+                branches,
+            });
+        }
+
+        Ok(PackageCoverage { modules })
+    }
+
+    /// Percentage of source lines executed across all modules
+    pub fn line_percentage(&self) -> f64 {
+        percentage(
+            self.modules.iter().map(|module| module.lines_hit()).sum(),
+            self.modules.iter().map(|module| module.lines_found()).sum(),
+        )
+    }
+
+    /// Human readable per module summary
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        writeln!(summary, "+-------------------------+").unwrap();
+        writeln!(summary, "| Move Coverage Summary   |").unwrap();
+        writeln!(summary, "+-------------------------+").unwrap();
+        for module in &self.modules {
+            writeln!(
+                summary,
+                "Module {}: lines {:.2}% ({}/{}), branches {:.2}% ({}/{})",
+                module.module_name,
+                percentage(module.lines_hit(), module.lines_found()),
+                module.lines_hit(),
+                module.lines_found(),
+                percentage(module.branches_hit(), module.branches_found()),
+                module.branches_hit(),
+                module.branches_found(),
+            )
+            .unwrap();
+        }
+        writeln!(summary, "+-------------------------+").unwrap();
+        writeln!(summary, "| % Line Coverage: {:.2}", self.line_percentage()).unwrap();
+        writeln!(summary, "+-------------------------+").unwrap();
+        summary
+    }
+
+    /// Fails if the line coverage is below `threshold` percent
+    pub fn check_threshold(&self, threshold: f64) -> CliTypedResult<()> {
+        let line_percentage = self.line_percentage();
+        if line_percentage < threshold {
+            Err(CliError::MoveCoverageError(format!(
+                "line coverage {:.2}% is below the threshold of {:.2}%",
+                line_percentage, threshold
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes the coverage in lcov tracefile format
+    pub fn write_lcov(&self, path: &Path) -> CliTypedResult<()> {
+        let mut lcov = String::new();
+        for module in &self.modules {
+            writeln!(lcov, "TN:").unwrap();
+            writeln!(lcov, "SF:{}", module.source_path.display()).unwrap();
+            for (line, count) in &module.lines {
+                writeln!(lcov, "DA:{},{}", line, count).unwrap();
+            }
+            for (block, (line, outcomes)) in module.branches.iter().enumerate() {
+                for (branch, taken) in outcomes.iter().enumerate() {
+                    writeln!(
+                        lcov,
+                        "BRDA:{},{},{},{}",
+                        line,
+                        block,
+                        branch,
+                        if *taken { "1" } else { "0" }
+                    )
+                    .unwrap();
+                }
+            }
+            writeln!(lcov, "LF:{}", module.lines_found()).unwrap();
+            writeln!(lcov, "LH:{}", module.lines_hit()).unwrap();
+            writeln!(lcov, "BRF:{}", module.branches_found()).unwrap();
+            writeln!(lcov, "BRH:{}", module.branches_hit()).unwrap();
+            writeln!(lcov, "end_of_record").unwrap();
+        }
+        write_to_file(path, "lcov file", lcov.as_bytes())
+    }
+}
+
+fn compile(package_path: &Path, config: BuildConfig) -> CliTypedResult<CompiledPackage> {
+    config
+        .compile_package(package_path, &mut Vec::new())
+        .map_err(|err| CliError::MoveCompilationError(err.to_string()))
+}
+
+fn function_name<'a>(
+    module: &'a CompiledModule,
+    function_def: &FunctionDefinition,
+) -> &'a IdentStr {
+    module.identifier_at(module.function_handle_at(function_def.function).name)
+}
+
+fn percentage(hit: usize, found: usize) -> f64 {
+    if found == 0 {
+        100.0
+    } else {
+        hit as f64 / found as f64 * 100.0
+    }
+}
+
+/// Byte offsets of the start of each line in `source`
+pub(crate) fn line_starts(source: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+        .collect()
+}
+
+/// 1-based line number containing the byte offset
+pub(crate) fn line_of(line_starts: &[usize], offset: usize) -> usize {
+    match line_starts.binary_search(&offset) {
+        Ok(index) => index + 1,
+        Err(index) => index,
+    }
+}
@@ -1,7 +1,9 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod abort_source;
 mod aptos_debug_natives;
+pub(crate) mod coverage;
 mod manifest;
 pub mod package_hooks;
 pub use package_hooks::*;
@@ -31,7 +33,8 @@ use crate::{
 };
 use aptos_gas::{AbstractValueSizeGasParameters, NativeGasParameters};
 use aptos_module_verifier::module_init::verify_module_init_function;
-use aptos_rest_client::aptos_api_types::MoveType;
+use aptos_rest_client::aptos_api_types::{AptosErrorCode, MoveType};
+use aptos_rest_client::error::{AptosErrorResponse, RestError};
 use aptos_transactional_test_harness::run_aptos_test;
 use aptos_types::account_address::AccountAddress;
 use aptos_types::transaction::{EntryFunction, Script, TransactionArgument, TransactionPayload};
@@ -41,8 +44,12 @@ use framework::docgen::DocgenOptions;
 use framework::natives::code::UpgradePolicy;
 use framework::{BuildOptions, BuiltPackage};
 use itertools::Itertools;
+use move_binary_format::access::ModuleAccess;
 use move_cli::base::test::UnitTestResult;
 use move_command_line_common::env::MOVE_HOME;
+use move_stackless_bytecode::options::VerificationScope;
+use reqwest::StatusCode;
+use serde::Serialize;
 use std::fmt::{Display, Formatter};
 use std::{
     collections::BTreeMap,
@@ -313,6 +320,22 @@ pub struct TestPackage {
         long = "instructions"
     )]
     pub instruction_execution_bound: u64,
+
+    /// Collect source coverage while running the tests and print a per module summary
+    #[clap(long)]
+    pub coverage: bool,
+
+    /// Fail if the line coverage of the package is below this percentage
+    ///
+    /// Implies `--coverage`
+    #[clap(long)]
+    pub coverage_threshold: Option<f64>,
+
+    /// Write the coverage in lcov format to this file
+    ///
+    /// Implies `--coverage`
+    #[clap(long, parse(from_os_str))]
+    pub lcov_file: Option<PathBuf>,
 }
 
 #[async_trait]
@@ -328,9 +351,12 @@ impl CliCommand<&'static str> for TestPackage {
             install_dir: self.move_options.output_dir.clone(),
             ..Default::default()
         };
+        let package_path = self.move_options.get_package_path()?;
+        let compute_coverage =
+            self.coverage || self.coverage_threshold.is_some() || self.lcov_file.is_some();
         let result = move_cli::base::test::run_move_unit_tests(
-            self.move_options.get_package_path()?.as_path(),
-            config,
+            package_path.as_path(),
+            config.clone(),
             UnitTestingConfig {
                 filter: self.filter,
                 instruction_execution_bound: Some(self.instruction_execution_bound),
@@ -341,15 +367,27 @@ impl CliCommand<&'static str> for TestPackage {
                 NativeGasParameters::zeros(),
                 AbstractValueSizeGasParameters::zeros(),
             ),
-            false,
+            compute_coverage,
             &mut std::io::stdout(),
         )
         .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
 
-        match result {
-            UnitTestResult::Success => Ok("Success"),
-            UnitTestResult::Failure => Err(CliError::MoveTestError),
+        if let UnitTestResult::Failure = result {
+            return Err(CliError::MoveTestError);
+        }
+
+        if compute_coverage {
+            let coverage = coverage::PackageCoverage::load(package_path.as_path(), config)?;
+            eprint!("{}", coverage.summary());
+            if let Some(ref lcov_file) = self.lcov_file {
+                coverage.write_lcov(lcov_file)?;
+            }
+            if let Some(threshold) = self.coverage_threshold {
+                coverage.check_threshold(threshold)?;
+            }
         }
+
+        Ok("Success")
     }
 }
 
@@ -376,15 +414,36 @@ impl CliCommand<()> for TransactionalTestOpts {
 /// Proves a Move package
 ///
 /// This is a tool for formal verification of a Move package using
-/// the Move prover
+/// the Move prover.  Settings not given as flags are taken from the `prover` section of the
+/// profile, if it has one.  Verification errors, including counterexamples, are reported against
+/// the package sources.
 #[derive(Parser)]
 pub struct ProvePackage {
     /// A filter string to determine which files to verify
     #[clap(long)]
     pub filter: Option<String>,
 
+    /// Only verify a module, or a single function given as `module::function`
+    #[clap(long)]
+    pub only: Option<String>,
+
+    /// Timeout in seconds for each verification condition
+    #[clap(long)]
+    pub vc_timeout: Option<usize>,
+
+    /// Number of cores to use for verification
+    #[clap(long)]
+    pub proc_cores: Option<usize>,
+
+    /// Seed for the solver, to reproduce a verification run
+    #[clap(long)]
+    pub random_seed: Option<usize>,
+
     #[clap(flatten)]
     move_options: MovePackageDir,
+
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
 }
 
 #[async_trait]
@@ -410,13 +469,37 @@ impl CliCommand<&'static str> for ProvePackage {
                 module_instance_names: vec![],
             });
 
+        // Flags take precedence over the profile, which takes precedence over the defaults
+        let profile = self
+            .profile_options
+            .profile()
+            .ok()
+            .and_then(|profile| profile.prover)
+            .unwrap_or_default();
+        if let Some(only) = self.only.or(profile.only) {
+            options.prover.verify_scope = if only.contains("::") {
+                VerificationScope::Only(only)
+            } else {
+                VerificationScope::OnlyModule(only)
+            };
+        }
+        if let Some(vc_timeout) = self.vc_timeout.or(profile.vc_timeout) {
+            options.backend.vc_timeout = vc_timeout;
+        }
+        if let Some(proc_cores) = self.proc_cores.or(profile.proc_cores) {
+            options.backend.proc_cores = proc_cores;
+        }
+        if let Some(random_seed) = self.random_seed.or(profile.random_seed) {
+            options.backend.random_seed = random_seed;
+        }
+
         let result = task::spawn_blocking(move || {
             move_cli::base::prove::run_move_prover(
                 config,
                 self.move_options.get_package_path()?.as_path(),
                 &self.filter,
                 true,
-                move_prover::cli::Options::default(),
+                options,
             )
         })
         .await
@@ -670,6 +753,10 @@ impl CliCommand<&'static str> for DownloadPackage {
 }
 
 /// Downloads a package and verifies that the bytecode matches a local compilation of the Move code
+///
+/// Each module's bytecode is compared against the module published at the account, along with
+/// the package metadata such as the source digest and manifest.  The command fails if anything
+/// doesn't match, after reporting the status of each module.
 #[derive(Parser)]
 pub struct VerifyPackage {
     /// Address of the account containing the package
@@ -688,10 +775,41 @@ pub struct VerifyPackage {
     pub(crate) profile_options: ProfileOptions,
 }
 
+/// Result of comparing a locally compiled module against the published one
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum ModuleVerificationStatus {
+    /// The bytecode is identical
+    Match,
+    /// The module is published, but with different bytecode
+    BytecodeMismatch,
+    /// The module is not published at the account
+    NotPublished,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyPackageSummary {
+    package: String,
+    account: AccountAddress,
+    metadata_verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata_error: Option<String>,
+    modules: BTreeMap<String, ModuleVerificationStatus>,
+}
+
+impl VerifyPackageSummary {
+    fn verified(&self) -> bool {
+        self.metadata_verified
+            && self
+                .modules
+                .values()
+                .all(|status| *status == ModuleVerificationStatus::Match)
+    }
+}
+
 #[async_trait]
 impl CliCommand<&'static str> for VerifyPackage {
     fn command_name(&self) -> &'static str {
-        "DownloadPackage"
+        "VerifyPackage"
     }
 
     async fn execute(self) -> CliTypedResult<&'static str> {
@@ -724,9 +842,75 @@ impl CliCommand<&'static str> for VerifyPackage {
         }
 
         // Verify that the source digest matches
-        package.verify(&compiled_metadata)?;
+        let metadata_error = package
+            .verify(&compiled_metadata)
+            .err()
+            .map(|err| err.to_string());
+
+        // Compare the bytecode of each module with what's published
+        let client = self.rest_options.client(&self.profile_options)?;
+        let mut modules = BTreeMap::new();
+        for module in pack.modules() {
+            let name = module.self_id().name().to_string();
+            let mut local_bytecode = vec![];
+            module
+                .serialize(&mut local_bytecode)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            let status = match client.get_account_module_bcs(self.account, &name).await {
+                Ok(onchain_bytecode) => {
+                    if onchain_bytecode.inner().as_ref() == local_bytecode.as_slice() {
+                        ModuleVerificationStatus::Match
+                    } else {
+                        ModuleVerificationStatus::BytecodeMismatch
+                    }
+                }
+                Err(err) if is_not_found(&err) => ModuleVerificationStatus::NotPublished,
+                Err(err) => return Err(err.into()),
+            };
+            modules.insert(name, status);
+        }
 
-        Ok("Successfully verified source of package")
+        let summary = VerifyPackageSummary {
+            package: pack.name().to_string(),
+            account: self.account,
+            metadata_verified: metadata_error.is_none(),
+            metadata_error,
+            modules,
+        };
+        eprintln!(
+            "{}",
+            serde_json::to_string_pretty(&summary)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+        );
+
+        if summary.verified() {
+            Ok("Successfully verified source of package")
+        } else {
+            Err(CliError::UnexpectedError(format!(
+                "Package {} does not match the package published at {}",
+                summary.package, summary.account
+            )))
+        }
+    }
+}
+
+/// Whether the REST API reports the module or its account doesn't exist, rather than failing to
+/// answer
+fn is_not_found(err: &RestError) -> bool {
+    match err {
+        RestError::Api(AptosErrorResponse {
+            error, status_code, ..
+        }) => {
+            *status_code == StatusCode::NOT_FOUND
+                || matches!(
+                    error.error_code,
+                    AptosErrorCode::AccountNotFound
+                        | AptosErrorCode::ResourceNotFound
+                        | AptosErrorCode::ModuleNotFound
+                )
+        }
+        RestError::Http(status_code, _) => *status_code == StatusCode::NOT_FOUND,
+        _ => false,
     }
 }
 
@@ -1032,6 +1216,7 @@ impl FromStr for FunctionArgType {
 }
 
 /// A parseable arg with a type separated by a colon
+#[derive(Debug)]
 pub struct ArgWithType {
     pub(crate) _ty: FunctionArgType,
     pub(crate) arg: Vec<u8>,
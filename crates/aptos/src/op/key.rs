@@ -13,12 +13,23 @@ use crate::{
 use aptos_config::config::{Peer, PeerRole};
 use aptos_crypto::{bls12381, ed25519, x25519, PrivateKey, ValidCryptoMaterial};
 use aptos_genesis::config::HostAndPort;
-use aptos_types::account_address::{from_identity_public_key, AccountAddress};
+use aptos_keygen::KeyGen;
+use aptos_types::{
+    account_address::{from_identity_public_key, AccountAddress},
+    transaction::authenticator::AuthenticationKey,
+};
 use async_trait::async_trait;
 use clap::{Parser, Subcommand};
 use std::{
     collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 pub const PUBLIC_KEY_EXTENSION: &str = "pub";
@@ -167,6 +178,23 @@ pub struct GenerateKey {
     #[clap(long, default_value_t = KeyType::Ed25519)]
     pub(crate) key_type: KeyType,
 
+    /// Search for an ed25519 key whose derived account address starts with this hex prefix
+    ///
+    /// e.g. `0xdead`.  Every extra hex character makes the search about 16 times longer.
+    #[clap(long)]
+    pub(crate) vanity_prefix: Option<String>,
+
+    /// Match the vanity prefix after any leading zeros of the account address
+    ///
+    /// Addresses are usually displayed without their leading zeros, e.g. `0x1`, so this
+    /// matches the prefix against the address as it is displayed rather than at its first byte.
+    #[clap(long, requires = "vanity_prefix")]
+    pub(crate) vanity_skip_leading_zeros: bool,
+
+    /// Number of threads to search for the vanity prefix with, defaults to the number of CPUs
+    #[clap(long, requires = "vanity_prefix")]
+    pub(crate) vanity_threads: Option<usize>,
+
     #[clap(flatten)]
     pub rng_args: RngArgs,
     #[clap(flatten)]
@@ -181,6 +209,11 @@ impl CliCommand<HashMap<&'static str, PathBuf>> for GenerateKey {
 
     async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
         self.save_params.check_key_file()?;
+        if self.vanity_prefix.is_some() && !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(
+                "--vanity-prefix can only be used with --key-type ed25519".to_string(),
+            ));
+        }
         let mut keygen = self.rng_args.key_generator()?;
 
         match self.key_type {
@@ -194,7 +227,11 @@ impl CliCommand<HashMap<&'static str, PathBuf>> for GenerateKey {
                 self.save_params.save_key(&private_key, "x25519")
             }
             KeyType::Ed25519 => {
-                let private_key = keygen.generate_ed25519_private_key();
+                let private_key = if let Some(ref prefix) = self.vanity_prefix {
+                    self.generate_vanity_ed25519_key(&mut keygen, prefix)?
+                } else {
+                    keygen.generate_ed25519_private_key()
+                };
                 self.save_params.save_key(&private_key, "ed25519")
             }
             KeyType::Bls12381 => {
@@ -206,6 +243,105 @@ impl CliCommand<HashMap<&'static str, PathBuf>> for GenerateKey {
 }
 
 impl GenerateKey {
+    /// Searches for an ed25519 key with a derived account address starting with `prefix`
+    ///
+    /// Each thread searches with its own key generator seeded from `keygen`, and progress is
+    /// printed to stderr until one of them finds a match.
+    pub(crate) fn generate_vanity_ed25519_key(
+        &self,
+        keygen: &mut KeyGen,
+        prefix: &str,
+    ) -> CliTypedResult<ed25519::Ed25519PrivateKey> {
+        let prefix = prefix.strip_prefix("0x").unwrap_or(prefix).to_lowercase();
+        if prefix.is_empty() || prefix.len() > AccountAddress::LENGTH * 2 {
+            return Err(CliError::CommandArgumentError(format!(
+                "--vanity-prefix must have between 1 and {} hex characters",
+                AccountAddress::LENGTH * 2
+            )));
+        }
+        if !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(CliError::CommandArgumentError(format!(
+                "--vanity-prefix {} is not hex encoded",
+                prefix
+            )));
+        }
+        if self.vanity_skip_leading_zeros && prefix.starts_with('0') {
+            return Err(CliError::CommandArgumentError(
+                "--vanity-prefix can't start with 0 when skipping leading zeros".to_string(),
+            ));
+        }
+
+        let num_threads = self
+            .vanity_threads
+            .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .max(1);
+        let skip_leading_zeros = self.vanity_skip_leading_zeros;
+        let found = Arc::new(AtomicBool::new(false));
+        let searched = Arc::new(AtomicU64::new(0));
+        let (sender, receiver) = mpsc::channel();
+
+        let handles: Vec<_> = (0..num_threads)
+            .map(|_| {
+                let mut keygen =
+                    KeyGen::from_seed(keygen.generate_ed25519_private_key().to_bytes());
+                let prefix = prefix.clone();
+                let found = found.clone();
+                let searched = searched.clone();
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    while !found.load(Ordering::Relaxed) {
+                        let private_key = keygen.generate_ed25519_private_key();
+                        let address =
+                            AuthenticationKey::ed25519(&private_key.public_key()).derived_address();
+                        let address = hex::encode(address.into_bytes());
+                        let address = if skip_leading_zeros {
+                            address.trim_start_matches('0')
+                        } else {
+                            address.as_str()
+                        };
+                        searched.fetch_add(1, Ordering::Relaxed);
+                        if address.starts_with(&prefix) && !found.swap(true, Ordering::Relaxed) {
+                            let _ = sender.send(private_key);
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let start = Instant::now();
+        let private_key = loop {
+            match receiver.recv_timeout(Duration::from_secs(5)) {
+                Ok(private_key) => break private_key,
+                Err(RecvTimeoutError::Timeout) => {
+                    let searched = searched.load(Ordering::Relaxed);
+                    eprintln!(
+                        "Searched {} keys for prefix 0x{} ({:.0} keys/s)",
+                        searched,
+                        prefix,
+                        searched as f64 / start.elapsed().as_secs_f64()
+                    );
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(CliError::UnexpectedError(
+                        "Vanity address search stopped without finding a key".to_string(),
+                    ));
+                }
+            }
+        };
+        for handle in handles {
+            let _ = handle.join();
+        }
+
+        eprintln!(
+            "Found account address {} after searching {} keys",
+            AuthenticationKey::ed25519(&private_key.public_key()).derived_address(),
+            searched.load(Ordering::Relaxed)
+        );
+        Ok(private_key)
+    }
+
     /// A test friendly typed key generation for x25519 keys.
     pub async fn generate_x25519(
         encoding: EncodingType,
@@ -232,6 +232,11 @@ impl CliTestFramework {
             new_private_key: Some(new_private_key),
             save_to_profile: None,
             new_private_key_file: None,
+            generate_new_key: false,
+            new_private_key_output_file: None,
+            new_public_key: None,
+            new_key_signature: None,
+            current_key_signature: None,
             skip_saving_profile: true,
         }
         .execute()
@@ -690,6 +695,9 @@ impl CliTestFramework {
     ) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
         GenerateKey {
             key_type: KeyType::X25519,
+            vanity_prefix: None,
+            vanity_skip_leading_zeros: false,
+            vanity_threads: None,
             rng_args: RngArgs::from_seed(seed),
             save_params: SaveKey {
                 file_options: SaveFile {
@@ -2,11 +2,54 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    move_tool::{ArgWithType, FunctionArgType},
+    account::{
+        key_rotation::{rotated_profile, rotation_proof_signature},
+        multisig::{
+            multisig_address, ExecuteMultisigTransaction, MultisigApproval, MultisigKeyArgs,
+            MultisigProposal, VerifyMultisigProposal,
+        },
+    },
+    common::types::{
+        account_address_from_public_key, CliCommand, CliError, ExternalSignerOptions, KeyType,
+        OutputFormat, PrivateKeyInputOptions, ProfileConfig, ProfileOptions, PromptOptions,
+        RestOptions, RngArgs, SaveFile, SenderKey, TransactionOptions,
+    },
+    move_tool::{
+        abort_source::AbortSource,
+        coverage::{line_of, line_starts, ModuleCoverage, PackageCoverage},
+        ArgWithType, FunctionArgType,
+    },
+    node::epoch_ending_waypoint,
+    op::key::{GenerateKey, SaveKey},
+    util::BcsType,
     CliResult, Tool,
 };
-use clap::Parser;
-use std::str::FromStr;
+use aptos_crypto::{
+    ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, SigningKey,
+    ValidCryptoMaterialStringExt,
+};
+use aptos_keygen::KeyGen;
+use aptos_sdk::transaction_builder::TransactionFactory;
+use aptos_temppath::TempPath;
+use aptos_types::{
+    aggregate_signature::AggregateSignature,
+    block_info::BlockInfo,
+    chain_id::ChainId,
+    epoch_change::EpochChangeProof,
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::{ExecutionStatus, Version},
+    waypoint::Waypoint,
+};
+use cached_packages::aptos_stdlib;
+use clap::{CommandFactory, Parser};
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+    vm_status::AbortLocation,
+};
+use move_package::BuildConfig;
+use std::{path::PathBuf, str::FromStr};
+use storage_interface::DbReader;
 
 /// In order to ensure that there aren't duplicate input arguments for untested CLI commands,
 /// we call help on every command to ensure it at least runs
@@ -17,14 +60,21 @@ async fn ensure_every_command_args_work() {
     assert_cmd_not_panic(&["aptos", "account"]).await;
     assert_cmd_not_panic(&["aptos", "account", "create", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "account", "create-resource-account", "--help"]).await;
+    assert_cmd_not_panic(&["aptos", "account", "export-state", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "account", "fund-with-faucet", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "account", "list", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "account", "lookup-address", "--help"]).await;
+    assert_cmd_not_panic(&["aptos", "account", "multisig"]).await;
+    for subcommand in ["create", "propose", "verify-proposal", "approve", "execute"] {
+        assert_cmd_not_panic(&["aptos", "account", "multisig", subcommand, "--help"]).await;
+    }
     assert_cmd_not_panic(&["aptos", "account", "rotate-key", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "account", "transfer", "--help"]).await;
 
     assert_cmd_not_panic(&["aptos", "config"]).await;
+    assert_cmd_not_panic(&["aptos", "config", "export-profile", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "config", "generate-shell-completions", "--help"]).await;
+    assert_cmd_not_panic(&["aptos", "config", "import-profile", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "config", "init", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "config", "set-global-config", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "config", "show-global-config"]).await;
@@ -34,6 +84,7 @@ async fn ensure_every_command_args_work() {
     assert_cmd_not_panic(&["aptos", "genesis", "generate-genesis", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "genesis", "generate-keys", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "genesis", "generate-layout-template", "--help"]).await;
+    assert_cmd_not_panic(&["aptos", "genesis", "generate-node-config", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "genesis", "set-validator-configuration", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "genesis", "setup-git", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "genesis", "generate-admin-write-set", "--help"]).await;
@@ -69,6 +120,7 @@ async fn ensure_every_command_args_work() {
     assert_cmd_not_panic(&["aptos", "node", "get-stake-pool", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "node", "analyze-validator-performance", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "node", "bootstrap-db-from-backup", "--help"]).await;
+    assert_cmd_not_panic(&["aptos", "node", "bootstrap-db", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "node", "initialize-validator", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "node", "join-validator-set", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "node", "leave-validator-set", "--help"]).await;
@@ -93,6 +145,39 @@ async fn ensure_every_command_args_work() {
     assert_cmd_not_panic(&["aptos", "stake", "set-operator", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "stake", "unlock-stake", "--help"]).await;
     assert_cmd_not_panic(&["aptos", "stake", "withdraw-stake", "--help"]).await;
+
+    assert_cmd_not_panic(&["aptos", "util", "bcs", "decode", "--help"]).await;
+    assert_cmd_not_panic(&["aptos", "util", "bcs", "encode", "--help"]).await;
+}
+
+/// Ensure the flags shared by all commands don't clash with the flags of any command
+#[test]
+fn global_args_do_not_conflict() {
+    Tool::command().debug_assert();
+}
+
+/// Ensure the output format can be given before or after the command
+#[test]
+fn output_format_is_parsed_for_every_command() {
+    let tool = Tool::try_parse_from(["aptos", "account", "list"]).unwrap();
+    assert_eq!(tool.output, OutputFormat::Json);
+    let tool = Tool::try_parse_from(["aptos", "account", "list", "--output", "text"]).unwrap();
+    assert_eq!(tool.output, OutputFormat::Text);
+    let tool = Tool::try_parse_from(["aptos", "--output", "text", "info"]).unwrap();
+    assert_eq!(tool.output, OutputFormat::Text);
+    assert!(Tool::try_parse_from(["aptos", "info", "--output", "yaml"]).is_err());
+
+    // The upgrade proposal is written to `--output-file`, `--output` is only the format
+    assert!(Tool::try_parse_from([
+        "aptos",
+        "governance",
+        "generate-upgrade-proposal",
+        "--account",
+        "0x1",
+        "--output",
+        "proposal.move",
+    ])
+    .is_err());
 }
 
 /// Ensure we can parse URLs for args
@@ -106,6 +191,316 @@ async fn ensure_can_parse_args_with_urls() {
     );
 }
 
+/// Ensure Move values round trip through BCS, and match the encoding of the `bcs` crate
+#[test]
+fn bcs_encode_decode_move_values() {
+    let bcs_type = BcsType::from_str("vector<0x1::option::Option<u64>>").unwrap();
+    let json = serde_json::json!([null, "18446744073709551615"]);
+    let bytes = bcs_type.encode(json.clone()).unwrap();
+    assert_eq!(bytes, bcs::to_bytes(&vec![None, Some(u64::MAX)]).unwrap());
+    assert_eq!(bcs_type.decode(&bytes).unwrap(), json);
+
+    let bcs_type = BcsType::from_str("0x1::string::String").unwrap();
+    let bytes = bcs_type.encode(serde_json::json!("aptos")).unwrap();
+    assert_eq!(bytes, bcs::to_bytes("aptos").unwrap());
+
+    // Trailing bytes are rejected
+    let bcs_type = BcsType::from_str("u8").unwrap();
+    assert!(bcs_type.decode(&[1, 2]).is_err());
+}
+
+/// Ensure abort codes are mapped to the error constants of a local package
+#[test]
+fn abort_source_finds_error_constant() {
+    let package_dir = TempPath::new();
+    package_dir.create_as_dir().unwrap();
+    let sources_dir = package_dir.path().join("sources");
+    std::fs::create_dir(&sources_dir).unwrap();
+    std::fs::write(package_dir.path().join("Move.toml"), "").unwrap();
+    std::fs::write(
+        sources_dir.join("counter.move"),
+        "module 0x42::counter {
+    use std::error;
+    const ENOT_OWNER: u64 = 1;
+    const EOVERFLOW: u64 = 0x2; // Overflow
+    public fun check(owner: bool) {
+        assert!(owner, error::permission_denied(ENOT_OWNER));
+    }
+}
+",
+    )
+    .unwrap();
+
+    let abort = |code| ExecutionStatus::MoveAbort {
+        location: AbortLocation::Module(ModuleId::new(
+            AccountAddress::from_hex_literal("0x42").unwrap(),
+            Identifier::new("counter").unwrap(),
+        )),
+        code,
+        info: None,
+    };
+
+    // The category of the error code is ignored
+    let source = AbortSource::find(package_dir.path(), &abort(0x50001)).unwrap();
+    assert_eq!(source.constant_name, "ENOT_OWNER");
+    assert_eq!(source.line, 3);
+    assert_eq!(source.uses, vec![6]);
+
+    let source = AbortSource::find(package_dir.path(), &abort(2)).unwrap();
+    assert_eq!(source.constant_name, "EOVERFLOW");
+    assert!(source.uses.is_empty());
+
+    assert!(AbortSource::find(package_dir.path(), &abort(3)).is_none());
+}
+
+/// Ensure aborts are only mapped to the package modules at the aborting address
+#[test]
+fn abort_source_matches_module_address() {
+    let package_dir = TempPath::new();
+    package_dir.create_as_dir().unwrap();
+    let sources_dir = package_dir.path().join("sources");
+    std::fs::create_dir(&sources_dir).unwrap();
+    std::fs::write(
+        package_dir.path().join("Move.toml"),
+        "[package]
+name = \"Coins\"
+version = \"0.0.0\"
+
+[addresses]
+coins = \"_\"
+counters = \"0x42\"
+",
+    )
+    .unwrap();
+    std::fs::write(
+        sources_dir.join("coin.move"),
+        "module coins::coin {
+    const EINSUFFICIENT_BALANCE: u64 = 6;
+}
+module counters::counter {
+    const EOVERFLOW: u64 = 6;
+}
+",
+    )
+    .unwrap();
+
+    let abort = |address, name| ExecutionStatus::MoveAbort {
+        location: AbortLocation::Module(ModuleId::new(
+            AccountAddress::from_hex_literal(address).unwrap(),
+            Identifier::new(name).unwrap(),
+        )),
+        code: 6,
+        info: None,
+    };
+
+    // An unassigned named address matches wherever the package is published
+    let source = AbortSource::find(package_dir.path(), &abort("0xcafe", "coin")).unwrap();
+    assert_eq!(source.constant_name, "EINSUFFICIENT_BALANCE");
+    // But not the framework modules of the same name
+    assert!(AbortSource::find(package_dir.path(), &abort("0x1", "coin")).is_none());
+
+    let source = AbortSource::find(package_dir.path(), &abort("0x42", "counter")).unwrap();
+    assert_eq!(source.constant_name, "EOVERFLOW");
+    assert!(AbortSource::find(package_dir.path(), &abort("0x43", "counter")).is_none());
+}
+
+/// Ensure byte offsets are mapped to the lines containing them
+#[test]
+fn coverage_maps_offsets_to_lines() {
+    let source = "module 0x42::m {\n    fun f() {}\n}\n";
+    let starts = line_starts(source);
+    assert_eq!(starts, vec![0, 17, 32, 34]);
+    assert_eq!(line_of(&starts, 0), 1);
+    assert_eq!(line_of(&starts, 16), 1);
+    assert_eq!(line_of(&starts, 17), 2);
+    assert_eq!(line_of(&starts, 21), 2);
+    assert_eq!(line_of(&starts, 32), 3);
+}
+
+/// Ensure the coverage of test only modules and functions isn't counted
+#[test]
+fn coverage_excludes_test_only_code() {
+    let package_dir = TempPath::new();
+    package_dir.create_as_dir().unwrap();
+    let sources_dir = package_dir.path().join("sources");
+    std::fs::create_dir(&sources_dir).unwrap();
+    std::fs::write(
+        package_dir.path().join("Move.toml"),
+        "[package]
+name = \"Coverage\"
+version = \"0.0.0\"
+",
+    )
+    .unwrap();
+    std::fs::write(
+        sources_dir.join("math.move"),
+        "module 0x42::math {
+    public fun abs_diff(a: u64, b: u64): u64 {
+        if (a > b) a - b else b - a
+    }
+
+    #[test_only]
+    fun double(a: u64): u64 {
+        a * 2
+    }
+
+    #[test]
+    fun test_abs_diff() {
+        assert!(abs_diff(double(2), 1) == 3, 0);
+    }
+}
+",
+    )
+    .unwrap();
+    std::fs::write(
+        sources_dir.join("math_tests.move"),
+        "#[test_only]
+module 0x42::math_tests {
+    #[test]
+    fun test_abs_diff_swapped() {
+        assert!(0x42::math::abs_diff(1, 3) == 2, 0);
+    }
+}
+",
+    )
+    .unwrap();
+
+    let compile = |test_mode| {
+        BuildConfig {
+            test_mode,
+            ..Default::default()
+        }
+        .compile_package(package_dir.path(), &mut Vec::new())
+        .unwrap()
+    };
+    let package = compile(true);
+    let production_package = compile(false);
+
+    // Only the lines of `abs_diff` are counted
+    let coverage =
+        PackageCoverage::from_packages(&package, &production_package, |_, function_name, _| {
+            (function_name.as_str() == "abs_diff").into()
+        })
+        .unwrap();
+    assert_eq!(coverage.modules.len(), 1);
+    let module = &coverage.modules[0];
+    assert_eq!(module.module_name, "0x42::math");
+    assert!(module.lines.keys().all(|line| (2..=4).contains(line)));
+    assert!(module.lines.contains_key(&3));
+    assert_eq!(module.lines_hit(), module.lines_found());
+    assert_eq!(module.branches_found(), 2);
+    assert_eq!(coverage.line_percentage(), 100.0);
+
+    let coverage =
+        PackageCoverage::from_packages(&package, &production_package, |_, _, _| 0).unwrap();
+    assert_eq!(coverage.line_percentage(), 0.0);
+    assert_eq!(coverage.modules[0].branches_hit(), 0);
+}
+
+fn fixture_coverage() -> PackageCoverage {
+    PackageCoverage {
+        modules: vec![ModuleCoverage {
+            module_name: "0x42::m".to_string(),
+            source_path: PathBuf::from("sources/m.move"),
+            lines: [(2, 1), (3, 0)].into_iter().collect(),
+            branches: [(2, vec![true, false])].into_iter().collect(),
+        }],
+    }
+}
+
+/// Ensure coverage is written in lcov tracefile format
+#[test]
+fn coverage_writes_lcov() {
+    let lcov_file = TempPath::new();
+    fixture_coverage().write_lcov(lcov_file.path()).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(lcov_file.path()).unwrap(),
+        "TN:
+SF:sources/m.move
+DA:2,1
+DA:3,0
+BRDA:2,0,0,1
+BRDA:2,0,1,0
+LF:2
+LH:1
+BRF:2
+BRH:1
+end_of_record
+"
+    );
+}
+
+/// Ensure line coverage below the threshold fails the test run
+#[test]
+fn coverage_checks_threshold() {
+    let coverage = fixture_coverage();
+    assert_eq!(coverage.line_percentage(), 50.0);
+    coverage.check_threshold(50.0).unwrap();
+    assert!(matches!(
+        coverage.check_threshold(50.1),
+        Err(CliError::MoveCoverageError(_))
+    ));
+}
+
+/// A DB holding only epoch ending ledger infos
+struct EpochEndingDb(Vec<LedgerInfoWithSignatures>);
+
+impl DbReader for EpochEndingDb {
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> anyhow::Result<EpochChangeProof> {
+        let ledger_infos = self
+            .0
+            .iter()
+            .filter(|ledger_info| {
+                (start_epoch..end_epoch).contains(&ledger_info.ledger_info().epoch())
+            })
+            .cloned()
+            .collect();
+        Ok(EpochChangeProof::new(ledger_infos, false))
+    }
+}
+
+fn ledger_info(epoch: u64, version: Version, ends_epoch: bool) -> LedgerInfo {
+    LedgerInfo::new(
+        BlockInfo::new(
+            epoch,
+            0,
+            HashValue::zero(),
+            HashValue::zero(),
+            version,
+            0,
+            ends_epoch.then(EpochState::empty),
+        ),
+        HashValue::zero(),
+    )
+}
+
+/// Ensure a restored DB is bootstrapped from the waypoint of its latest epoch boundary
+#[test]
+fn test_epoch_ending_waypoint() {
+    let epoch_ending = ledger_info(1, 50, true);
+    let db = EpochEndingDb(vec![
+        LedgerInfoWithSignatures::new(ledger_info(0, 0, true), AggregateSignature::empty()),
+        LedgerInfoWithSignatures::new(epoch_ending.clone(), AggregateSignature::empty()),
+    ]);
+    let expected = Waypoint::new_epoch_boundary(&epoch_ending).unwrap();
+
+    // A ledger info that ends an epoch is the boundary itself
+    assert_eq!(epoch_ending_waypoint(&db, &epoch_ending).unwrap(), expected);
+
+    // Otherwise it is the end of the previous epoch
+    let waypoint = epoch_ending_waypoint(&db, &ledger_info(2, 75, false)).unwrap();
+    assert_eq!(waypoint, expected);
+    assert_eq!(waypoint.version(), 50);
+
+    // Without the previous epoch ending ledger info there is no boundary to start from
+    assert!(epoch_ending_waypoint(&db, &ledger_info(3, 120, false)).is_err());
+    assert!(epoch_ending_waypoint(&db, &ledger_info(0, 10, false)).is_err());
+}
+
 async fn assert_cmd_not_panic(args: &[&str]) {
     // When a command fails, it will have a panic in it due to an improperly setup command
     // thread 'main' panicked at 'Command propose: Argument names must be unique, but 'assume-yes' is
@@ -127,6 +522,358 @@ async fn assert_cmd_not_panic(args: &[&str]) {
     }
 }
 
+#[test]
+fn test_rotated_profile_to_external_key() {
+    let mut keygen = KeyGen::from_seed([0; 32]);
+    let old_key = keygen.generate_ed25519_private_key();
+    let new_public_key = keygen.generate_ed25519_private_key().public_key();
+    let account = AccountAddress::random();
+    let profile = ProfileConfig {
+        private_key: Some(old_key.clone()),
+        public_key: Some(old_key.public_key()),
+        account: Some(account),
+        rest_url: Some("http://localhost:8080".to_string()),
+        private_key_file: Some(PathBuf::from("old.key")),
+        ..Default::default()
+    };
+
+    // Rotated to a key held by an external signer, the profile must not sign with the old key
+    let rotated = rotated_profile(profile, None, new_public_key.clone(), account);
+    assert!(rotated.private_key.is_none());
+    assert!(rotated.private_key_file.is_none());
+    assert!(rotated.signing_key().unwrap().is_none());
+    assert_eq!(rotated.public_key, Some(new_public_key));
+    assert_eq!(rotated.account, Some(account));
+    assert_eq!(rotated.rest_url.as_deref(), Some("http://localhost:8080"));
+}
+
+/// Creates a proposal to be approved by `threshold` of the keys
+fn multisig_proposal(private_keys: &[Ed25519PrivateKey], threshold: u8) -> MultisigProposal {
+    let public_key = MultisigKeyArgs {
+        public_keys: private_keys.iter().map(PrivateKey::public_key).collect(),
+        threshold,
+    }
+    .multisig_public_key()
+    .unwrap();
+    let raw_txn = TransactionFactory::new(ChainId::test())
+        .payload(aptos_stdlib::aptos_account_create_account(
+            AccountAddress::ONE,
+        ))
+        .sender(multisig_address(&public_key))
+        .sequence_number(0)
+        .build();
+    MultisigProposal {
+        public_key,
+        raw_txn,
+    }
+}
+
+fn multisig_approval(
+    proposal: &MultisigProposal,
+    private_key: &Ed25519PrivateKey,
+) -> MultisigApproval {
+    MultisigApproval {
+        public_key: private_key.public_key(),
+        signature: private_key.sign(&proposal.raw_txn).unwrap(),
+    }
+}
+
+/// Writes the proposal and approvals to files, and executes the proposal with them
+async fn execute_multisig(
+    proposal: &MultisigProposal,
+    approvals: &[MultisigApproval],
+) -> Result<(), CliError> {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let proposal_file = dir.path().join("proposal");
+    std::fs::write(&proposal_file, bcs::to_bytes(proposal).unwrap()).unwrap();
+    let approval_files = approvals
+        .iter()
+        .enumerate()
+        .map(|(index, approval)| {
+            let approval_file = dir.path().join(format!("approval{}", index));
+            std::fs::write(&approval_file, bcs::to_bytes(approval).unwrap()).unwrap();
+            approval_file
+        })
+        .collect();
+
+    ExecuteMultisigTransaction {
+        proposal_file,
+        approval_files,
+        rest_options: RestOptions::default(),
+        profile_options: ProfileOptions::default(),
+        prompt_options: PromptOptions::no(),
+    }
+    .execute()
+    .await
+    .map(|_| ())
+}
+
+fn vanity_key_generator(skip_leading_zeros: bool) -> GenerateKey {
+    GenerateKey {
+        key_type: KeyType::Ed25519,
+        vanity_prefix: None,
+        vanity_skip_leading_zeros: skip_leading_zeros,
+        vanity_threads: Some(2),
+        rng_args: RngArgs::from_seed([0; 32]),
+        save_params: SaveKey {
+            file_options: SaveFile {
+                output_file: PathBuf::from("unused"),
+                prompt_options: PromptOptions::yes(),
+            },
+            encoding_options: Default::default(),
+        },
+    }
+}
+
+/// Ensure vanity keys derive an account address starting with the prefix
+#[test]
+fn test_generate_vanity_ed25519_key() {
+    let mut keygen = KeyGen::from_seed([0; 32]);
+    let private_key = vanity_key_generator(false)
+        .generate_vanity_ed25519_key(&mut keygen, "0xA")
+        .unwrap();
+    let address = account_address_from_public_key(&private_key.public_key());
+    assert!(hex::encode(address.into_bytes()).starts_with('a'));
+
+    let private_key = vanity_key_generator(true)
+        .generate_vanity_ed25519_key(&mut keygen, "f")
+        .unwrap();
+    let address = account_address_from_public_key(&private_key.public_key());
+    assert!(hex::encode(address.into_bytes())
+        .trim_start_matches('0')
+        .starts_with('f'));
+}
+
+/// Ensure vanity prefixes that no address can start with are rejected
+#[test]
+fn test_generate_vanity_ed25519_key_invalid_prefix() {
+    let mut keygen = KeyGen::from_seed([0; 32]);
+    let too_long = "a".repeat(AccountAddress::LENGTH * 2 + 1);
+    for prefix in ["", "0x", "0xg", too_long.as_str()] {
+        assert!(matches!(
+            vanity_key_generator(false).generate_vanity_ed25519_key(&mut keygen, prefix),
+            Err(CliError::CommandArgumentError(_))
+        ));
+    }
+    assert!(matches!(
+        vanity_key_generator(true).generate_vanity_ed25519_key(&mut keygen, "0a"),
+        Err(CliError::CommandArgumentError(_))
+    ));
+}
+
+/// Ensure rotation proofs are signed by local keys, and only accept valid external signatures
+#[test]
+fn test_rotation_proof_signature() {
+    let private_key = KeyGen::from_seed([1; 32]).generate_ed25519_private_key();
+    let public_key = private_key.public_key();
+    let rotation_msg = b"rotation proof challenge";
+    let signature = private_key.sign_arbitrary_message(rotation_msg);
+
+    let local_signature =
+        rotation_proof_signature(Some(&private_key), &public_key, None, "--sig", rotation_msg)
+            .unwrap();
+    assert_eq!(local_signature, Some(signature.clone()));
+
+    // An external signer that hasn't signed yet has the challenge printed
+    assert_eq!(
+        rotation_proof_signature(None, &public_key, None, "--sig", rotation_msg).unwrap(),
+        None
+    );
+    assert_eq!(
+        rotation_proof_signature(None, &public_key, Some(&signature), "--sig", rotation_msg)
+            .unwrap(),
+        Some(signature.clone())
+    );
+    assert!(matches!(
+        rotation_proof_signature(
+            None,
+            &public_key,
+            Some(&signature),
+            "--sig",
+            b"another challenge"
+        ),
+        Err(CliError::CommandArgumentError(_))
+    ));
+}
+
+/// Ensure a sender known only by its public key is signed for externally
+#[test]
+fn test_external_sender_key() {
+    let private_key = KeyGen::from_seed([1; 32]).generate_ed25519_private_key();
+    let public_key = private_key.public_key();
+    let mut txn_options = TransactionOptions {
+        external_signer_options: ExternalSignerOptions {
+            sender_public_key: Some(public_key.to_encoded_string().unwrap()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (sender_key, sender_address) = txn_options.get_sender_key_and_address().unwrap();
+    assert!(matches!(sender_key, SenderKey::External(ref key) if *key == public_key));
+    assert_eq!(sender_address, account_address_from_public_key(&public_key));
+
+    // The sender can't be both signed for by the CLI and externally
+    txn_options.private_key_options =
+        PrivateKeyInputOptions::from_private_key(&private_key).unwrap();
+    assert!(matches!(
+        txn_options.get_sender_key_and_address(),
+        Err(CliError::CommandArgumentError(_))
+    ));
+}
+
+/// Ensure externally signed transactions are only signed with a valid signature
+#[test]
+fn test_external_signer_signs_transaction() {
+    let private_key = KeyGen::from_seed([1; 32]).generate_ed25519_private_key();
+    let public_key = private_key.public_key();
+    let raw_txn = || {
+        TransactionFactory::new(ChainId::test())
+            .payload(aptos_stdlib::aptos_account_create_account(
+                AccountAddress::ONE,
+            ))
+            .sender(account_address_from_public_key(&public_key))
+            .sequence_number(0)
+            .expiration_timestamp_secs(100)
+            .build()
+    };
+    let signed_by = |private_key: &Ed25519PrivateKey| ExternalSignerOptions {
+        sender_signature: Some(
+            private_key
+                .sign(&raw_txn())
+                .unwrap()
+                .to_encoded_string()
+                .unwrap(),
+        ),
+        ..Default::default()
+    };
+
+    // Without a signature only the signing message is printed
+    assert!(matches!(
+        ExternalSignerOptions::default().sign_transaction(raw_txn(), public_key.clone()),
+        Err(CliError::CommandArgumentError(_))
+    ));
+
+    let transaction = signed_by(&private_key)
+        .sign_transaction(raw_txn(), public_key.clone())
+        .unwrap();
+    assert!(transaction.check_signature().is_ok());
+
+    let other_key = KeyGen::from_seed([2; 32]).generate_ed25519_private_key();
+    assert!(matches!(
+        signed_by(&other_key).sign_transaction(raw_txn(), public_key),
+        Err(CliError::CommandArgumentError(_))
+    ));
+}
+
+#[test]
+fn test_multisig_threshold() {
+    let mut keygen = KeyGen::from_seed([0; 32]);
+    let public_keys: Vec<_> = (0..3)
+        .map(|_| keygen.generate_ed25519_private_key().public_key())
+        .collect();
+    let key_args = |threshold| MultisigKeyArgs {
+        public_keys: public_keys.clone(),
+        threshold,
+    };
+
+    assert!(key_args(1).multisig_public_key().is_ok());
+    assert!(key_args(3).multisig_public_key().is_ok());
+    assert!(matches!(
+        key_args(0).multisig_public_key(),
+        Err(CliError::CommandArgumentError(_))
+    ));
+    assert!(matches!(
+        key_args(4).multisig_public_key(),
+        Err(CliError::CommandArgumentError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_multisig_execute_checks_approvals() {
+    let mut keygen = KeyGen::from_seed([0; 32]);
+    let private_keys: Vec<_> = (0..3)
+        .map(|_| keygen.generate_ed25519_private_key())
+        .collect();
+    let proposal = multisig_proposal(&private_keys, 2);
+    let approval_0 = multisig_approval(&proposal, &private_keys[0]);
+    let approval_1 = multisig_approval(&proposal, &private_keys[1]);
+
+    // Fewer approvals than the threshold
+    let error = execute_multisig(&proposal, &[multisig_approval(&proposal, &private_keys[0])])
+        .await
+        .unwrap_err();
+    assert!(matches!(error, CliError::CommandArgumentError(_)));
+
+    // The same signer twice doesn't reach the threshold either
+    let error = execute_multisig(
+        &proposal,
+        &[
+            multisig_approval(&proposal, &private_keys[0]),
+            multisig_approval(&proposal, &private_keys[0]),
+        ],
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, CliError::CommandArgumentError(_)));
+
+    // A signature claimed by another signer than the one who signed, i.e. at the wrong index
+    // of the signature bitmap
+    let mismatched_approval = MultisigApproval {
+        public_key: approval_1.public_key.clone(),
+        signature: approval_0.signature.clone(),
+    };
+    let error = execute_multisig(&proposal, &[approval_0, mismatched_approval])
+        .await
+        .unwrap_err();
+    assert!(matches!(error, CliError::UnexpectedError(_)));
+
+    // A key that isn't one of the signers
+    let outsider_approval = multisig_approval(&proposal, &keygen.generate_ed25519_private_key());
+    let error = execute_multisig(&proposal, &[approval_1, outsider_approval])
+        .await
+        .unwrap_err();
+    assert!(matches!(error, CliError::CommandArgumentError(_)));
+
+    // Enough valid approvals get as far as the prompt, which is declined
+    let error = execute_multisig(
+        &proposal,
+        &[
+            multisig_approval(&proposal, &private_keys[2]),
+            multisig_approval(&proposal, &private_keys[0]),
+        ],
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, CliError::AbortedError));
+}
+
+#[tokio::test]
+async fn test_multisig_proposal_file_round_trip() {
+    let mut keygen = KeyGen::from_seed([0; 32]);
+    let private_keys: Vec<_> = (0..2)
+        .map(|_| keygen.generate_ed25519_private_key())
+        .collect();
+    let proposal = multisig_proposal(&private_keys, 2);
+
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let proposal_file = dir.path().join("proposal");
+    std::fs::write(&proposal_file, bcs::to_bytes(&proposal).unwrap()).unwrap();
+
+    let loaded = MultisigProposal::load(&proposal_file).unwrap();
+    assert_eq!(loaded.public_key, proposal.public_key);
+    assert_eq!(loaded.raw_txn, proposal.raw_txn);
+
+    // The proposal hash shared out of band identifies the loaded proposal
+    let verify = |expected_hash| VerifyMultisigProposal {
+        proposal_file: proposal_file.clone(),
+        expected_hash: Some(expected_hash),
+    };
+    assert!(verify(proposal.raw_txn.hash()).execute().await.is_ok());
+    assert!(verify(HashValue::random()).execute().await.is_err());
+}
+
 async fn run_cmd(args: &[&str]) -> CliResult {
     let tool: Tool = Tool::try_parse_from(args).map_err(|msg| msg.to_string())?;
     tool.execute().await
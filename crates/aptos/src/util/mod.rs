@@ -0,0 +1,378 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::types::{CliCommand, CliError, CliResult, CliTypedResult};
+use aptos_rest_client::aptos_api_types::MoveType;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{RawTransaction, SignedTransaction, TransactionPayload},
+};
+use async_trait::async_trait;
+use clap::{Parser, Subcommand};
+use move_core_types::language_storage::TypeTag;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{convert::TryFrom, str::FromStr};
+
+/// Tool for miscellaneous developer utilities
+#[derive(Subcommand)]
+pub enum UtilTool {
+    #[clap(subcommand)]
+    Bcs(BcsTool),
+}
+
+impl UtilTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            UtilTool::Bcs(tool) => tool.execute().await,
+        }
+    }
+}
+
+/// Tool for converting between JSON and BCS
+///
+/// Values are described with `--type`, either a Move type such as `u64`,
+/// `vector<address>` or `0x1::string::String`, or one of the known types
+/// `transaction_payload`, `raw_transaction`, `signed_transaction` and `type_tag`.
+#[derive(Subcommand)]
+pub enum BcsTool {
+    Encode(EncodeBcs),
+    Decode(DecodeBcs),
+}
+
+impl BcsTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            BcsTool::Encode(tool) => tool.execute_serialized().await,
+            BcsTool::Decode(tool) => tool.execute_serialized().await,
+        }
+    }
+}
+
+/// Encode a JSON value as hex encoded BCS
+///
+/// Move integers can be given as JSON numbers or strings, and `vector<u8>` can be given as a
+/// hex string.  Known types use the same JSON layout that `decode` outputs.
+#[derive(Parser)]
+pub struct EncodeBcs {
+    /// Type of the value
+    #[clap(long = "type")]
+    pub(crate) value_type: BcsType,
+
+    /// JSON value to encode
+    #[clap(long)]
+    pub(crate) json: String,
+}
+
+#[async_trait]
+impl CliCommand<String> for EncodeBcs {
+    fn command_name(&self) -> &'static str {
+        "EncodeBcs"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let value: Value = serde_json::from_str(&self.json)
+            .map_err(|err| CliError::UnableToParse("--json", err.to_string()))?;
+        let bytes = self.value_type.encode(value)?;
+        Ok(format!("0x{}", hex::encode(bytes)))
+    }
+}
+
+/// Decode hex encoded BCS into JSON
+///
+/// Move `u64` and `u128` values are output as strings, and `vector<u8>` as hex strings, in the
+/// same way as the REST API.
+#[derive(Parser)]
+pub struct DecodeBcs {
+    /// Type of the value
+    #[clap(long = "type")]
+    pub(crate) value_type: BcsType,
+
+    /// Hex encoded BCS bytes to decode
+    #[clap(long)]
+    pub(crate) bcs: String,
+}
+
+#[async_trait]
+impl CliCommand<Value> for DecodeBcs {
+    fn command_name(&self) -> &'static str {
+        "DecodeBcs"
+    }
+
+    async fn execute(self) -> CliTypedResult<Value> {
+        let bytes = hex::decode(self.bcs.trim().strip_prefix("0x").unwrap_or(&self.bcs))?;
+        self.value_type.decode(&bytes)
+    }
+}
+
+/// The type of a BCS encoded value
+#[derive(Clone, Debug)]
+pub enum BcsType {
+    Move(TypeTag),
+    TransactionPayload,
+    RawTransaction,
+    SignedTransaction,
+    TypeTag,
+}
+
+impl FromStr for BcsType {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "transaction_payload" => BcsType::TransactionPayload,
+            "raw_transaction" => BcsType::RawTransaction,
+            "signed_transaction" => BcsType::SignedTransaction,
+            "type_tag" => BcsType::TypeTag,
+            type_str => {
+                let move_type = MoveType::from_str(type_str)
+                    .map_err(|err| CliError::UnableToParse("--type", err.to_string()))?;
+                BcsType::Move(
+                    TypeTag::try_from(move_type)
+                        .map_err(|err| CliError::UnableToParse("--type", err.to_string()))?,
+                )
+            }
+        })
+    }
+}
+
+impl BcsType {
+    pub fn encode(&self, value: Value) -> CliTypedResult<Vec<u8>> {
+        match self {
+            BcsType::Move(type_tag) => {
+                let mut bytes = vec![];
+                encode_move_value(type_tag, &value, &mut bytes)?;
+                Ok(bytes)
+            }
+            BcsType::TransactionPayload => encode_serde::<TransactionPayload>(value),
+            BcsType::RawTransaction => encode_serde::<RawTransaction>(value),
+            BcsType::SignedTransaction => encode_serde::<SignedTransaction>(value),
+            BcsType::TypeTag => encode_serde::<TypeTag>(value),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> CliTypedResult<Value> {
+        match self {
+            BcsType::Move(type_tag) => {
+                let mut remaining = bytes;
+                let value = decode_move_value(type_tag, &mut remaining)?;
+                if !remaining.is_empty() {
+                    return Err(CliError::UnableToParse(
+                        "--bcs",
+                        format!("{} unexpected trailing bytes", remaining.len()),
+                    ));
+                }
+                Ok(value)
+            }
+            BcsType::TransactionPayload => decode_serde::<TransactionPayload>(bytes),
+            BcsType::RawTransaction => decode_serde::<RawTransaction>(bytes),
+            BcsType::SignedTransaction => decode_serde::<SignedTransaction>(bytes),
+            BcsType::TypeTag => decode_serde::<TypeTag>(bytes),
+        }
+    }
+}
+
+fn encode_serde<T: DeserializeOwned + Serialize>(value: Value) -> CliTypedResult<Vec<u8>> {
+    let value: T = serde_json::from_value(value)
+        .map_err(|err| CliError::UnableToParse("--json", err.to_string()))?;
+    bcs::to_bytes(&value).map_err(|err| CliError::BCS("value", err))
+}
+
+fn decode_serde<T: DeserializeOwned + Serialize>(bytes: &[u8]) -> CliTypedResult<Value> {
+    let value: T = bcs::from_bytes(bytes).map_err(|err| CliError::BCS("value", err))?;
+    serde_json::to_value(&value).map_err(|err| CliError::UnexpectedError(err.to_string()))
+}
+
+/// Whether the struct is `0x1::<module>::<name>`
+fn is_framework_struct(type_tag: &TypeTag, module: &str, name: &str) -> bool {
+    match type_tag {
+        TypeTag::Struct(struct_tag) => {
+            struct_tag.address == AccountAddress::ONE
+                && struct_tag.module.as_str() == module
+                && struct_tag.name.as_str() == name
+        }
+        _ => false,
+    }
+}
+
+fn encode_move_value(type_tag: &TypeTag, value: &Value, out: &mut Vec<u8>) -> CliTypedResult<()> {
+    let invalid = || {
+        CliError::UnableToParse(
+            "--json",
+            format!("{} is not a valid value of type {}", value, type_tag),
+        )
+    };
+    match type_tag {
+        TypeTag::Bool => out.push(u8::from(value.as_bool().ok_or_else(invalid)?)),
+        TypeTag::U8 => out.push(parse_integer::<u8>(value).ok_or_else(invalid)?),
+        TypeTag::U64 => out.extend(
+            parse_integer::<u64>(value)
+                .ok_or_else(invalid)?
+                .to_le_bytes(),
+        ),
+        TypeTag::U128 => out.extend(
+            parse_integer::<u128>(value)
+                .ok_or_else(invalid)?
+                .to_le_bytes(),
+        ),
+        TypeTag::Address => {
+            let address = value
+                .as_str()
+                .and_then(|address| AccountAddress::from_hex_literal(address).ok())
+                .ok_or_else(invalid)?;
+            out.extend(address.into_bytes())
+        }
+        TypeTag::Vector(inner) => {
+            if let (TypeTag::U8, Some(hex_str)) = (inner.as_ref(), value.as_str()) {
+                let bytes = hex::decode(hex_str.strip_prefix("0x").unwrap_or(hex_str))?;
+                write_uleb128(bytes.len(), out);
+                out.extend(bytes);
+            } else {
+                let values = value.as_array().ok_or_else(invalid)?;
+                write_uleb128(values.len(), out);
+                for value in values {
+                    encode_move_value(inner, value, out)?;
+                }
+            }
+        }
+        TypeTag::Struct(_) if is_framework_struct(type_tag, "string", "String") => {
+            let string = value.as_str().ok_or_else(invalid)?;
+            write_uleb128(string.len(), out);
+            out.extend(string.as_bytes());
+        }
+        TypeTag::Struct(struct_tag) if is_framework_struct(type_tag, "option", "Option") => {
+            // Options are vectors of zero or one element
+            if value.is_null() {
+                write_uleb128(0, out);
+            } else {
+                write_uleb128(1, out);
+                encode_move_value(
+                    struct_tag.type_params.first().ok_or_else(invalid)?,
+                    value,
+                    out,
+                )?;
+            }
+        }
+        _ => {
+            return Err(CliError::CommandArgumentError(format!(
+                "Encoding values of type {} is not supported",
+                type_tag
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn decode_move_value(type_tag: &TypeTag, bytes: &mut &[u8]) -> CliTypedResult<Value> {
+    Ok(match type_tag {
+        TypeTag::Bool => match take(bytes, 1)?[0] {
+            0 => Value::Bool(false),
+            1 => Value::Bool(true),
+            byte => {
+                return Err(CliError::UnableToParse(
+                    "--bcs",
+                    format!("{} is not a valid bool", byte),
+                ))
+            }
+        },
+        TypeTag::U8 => Value::from(take(bytes, 1)?[0]),
+        TypeTag::U64 => {
+            let mut le_bytes = [0u8; 8];
+            le_bytes.copy_from_slice(take(bytes, 8)?);
+            Value::String(u64::from_le_bytes(le_bytes).to_string())
+        }
+        TypeTag::U128 => {
+            let mut le_bytes = [0u8; 16];
+            le_bytes.copy_from_slice(take(bytes, 16)?);
+            Value::String(u128::from_le_bytes(le_bytes).to_string())
+        }
+        TypeTag::Address => {
+            let address = AccountAddress::from_bytes(take(bytes, AccountAddress::LENGTH)?)
+                .map_err(|err| CliError::UnableToParse("--bcs", err.to_string()))?;
+            Value::String(address.to_hex_literal())
+        }
+        TypeTag::Vector(inner) => {
+            let len = read_uleb128(bytes)?;
+            if let TypeTag::U8 = inner.as_ref() {
+                Value::String(format!("0x{}", hex::encode(take(bytes, len)?)))
+            } else {
+                let mut values = Vec::new();
+                for _ in 0..len {
+                    values.push(decode_move_value(inner, bytes)?);
+                }
+                Value::Array(values)
+            }
+        }
+        TypeTag::Struct(_) if is_framework_struct(type_tag, "string", "String") => {
+            let len = read_uleb128(bytes)?;
+            let string = String::from_utf8(take(bytes, len)?.to_vec())?;
+            Value::String(string)
+        }
+        TypeTag::Struct(struct_tag) if is_framework_struct(type_tag, "option", "Option") => {
+            let inner = struct_tag.type_params.first().ok_or_else(|| {
+                CliError::UnableToParse("--type", "Option requires a type parameter".to_string())
+            })?;
+            match read_uleb128(bytes)? {
+                0 => Value::Null,
+                1 => decode_move_value(inner, bytes)?,
+                len => {
+                    return Err(CliError::UnableToParse(
+                        "--bcs",
+                        format!("Option can't have {} elements", len),
+                    ))
+                }
+            }
+        }
+        _ => {
+            return Err(CliError::CommandArgumentError(format!(
+                "Decoding values of type {} is not supported",
+                type_tag
+            )))
+        }
+    })
+}
+
+/// Parses an integer given either as a JSON number or a string
+fn parse_integer<T: FromStr + TryFrom<u64>>(value: &Value) -> Option<T> {
+    match value {
+        Value::Number(number) => number.as_u64().and_then(|number| T::try_from(number).ok()),
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+fn write_uleb128(mut value: usize, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_uleb128(bytes: &mut &[u8]) -> CliTypedResult<usize> {
+    let mut value: u64 = 0;
+    for shift in (0..32).step_by(7) {
+        let byte = take(bytes, 1)?[0];
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value as usize);
+        }
+    }
+    Err(CliError::UnableToParse(
+        "--bcs",
+        "ULEB128 length is too long".to_string(),
+    ))
+}
+
+/// Takes the next `len` bytes, failing if there aren't enough left
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> CliTypedResult<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(CliError::UnableToParse(
+            "--bcs",
+            "Unexpected end of input".to_string(),
+        ));
+    }
+    let (taken, remaining) = bytes.split_at(len);
+    *bytes = remaining;
+    Ok(taken)
+}
@@ -3,20 +3,47 @@
 
 #![forbid(unsafe_code)]
 
+use anyhow::Result;
+use aptos_config::{config::CrashReportConfig, secret::Secret};
 use aptos_logger::prelude::*;
 use backtrace::Backtrace;
+use once_cell::sync::OnceCell;
 use serde::Serialize;
 use std::{
+    collections::BTreeMap,
+    fs,
     panic::{self, PanicInfo},
+    path::{Path, PathBuf},
     process,
+    sync::mpsc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+/// The prefix and extension of the crash report files, whose names sort from the oldest
+const CRASH_REPORT_PREFIX: &str = "crash-";
+const CRASH_REPORT_EXTENSION: &str = ".json";
+
+// Only set once the node config is loaded, the panics before that are only logged
+static CRASH_REPORTER: OnceCell<CrashReporter> = OnceCell::new();
+
 #[derive(Debug, Serialize)]
 pub struct CrashInfo {
     details: String,
     backtrace: String,
 }
 
+/// Everything needed to triage a crash nobody was watching
+#[derive(Debug, Serialize)]
+struct CrashReport {
+    timestamp_ms: u128,
+    thread: Option<String>,
+    details: String,
+    backtrace: String,
+    build_information: BTreeMap<String, String>,
+    recent_logs: Vec<String>,
+}
+
 /// Invoke to ensure process exits on a thread panic.
 ///
 /// Tokio's default behavior is to catch panics and ignore them.  Invoking this function will
@@ -28,6 +55,30 @@ pub fn setup_panic_handler() {
     }));
 }
 
+/// Writes a crash report on every subsequent panic, in the directory of the config (relative to
+/// `data_dir`), and posts it to the endpoint of the config if it's set. The recent logs are only
+/// included if the logger keeps them.
+pub fn setup_crash_reporting(
+    config: &CrashReportConfig,
+    data_dir: &Path,
+    build_information: BTreeMap<String, String>,
+) {
+    if !config.enabled {
+        return;
+    }
+    let reporter = CrashReporter {
+        dir: data_dir.join(&config.dir),
+        max_reports: config.max_reports,
+        endpoint: config.endpoint.clone(),
+        auth_token: config.auth_token.clone(),
+        post_timeout: Duration::from_millis(config.post_timeout_ms),
+        build_information,
+    };
+    if CRASH_REPORTER.set(reporter).is_err() {
+        warn!("Crash reporting was already set up");
+    }
+}
+
 // Formats and logs panic information
 fn handle_panic(panic_info: &PanicInfo<'_>) {
     // The Display formatter for a PanicInfo contains the message, payload and location.
@@ -44,6 +95,142 @@ fn handle_panic(panic_info: &PanicInfo<'_>) {
     // Wait till the logs have been flushed
     aptos_logger::flush();
 
+    // The logs are flushed, so the panic itself is among the recent logs of the report
+    if let Some(reporter) = CRASH_REPORTER.get() {
+        reporter.report(info);
+    }
+
     // Kill the process
     process::exit(12);
 }
+
+struct CrashReporter {
+    dir: PathBuf,
+    max_reports: usize,
+    endpoint: Option<String>,
+    auth_token: Option<Secret<String>>,
+    post_timeout: Duration,
+    build_information: BTreeMap<String, String>,
+}
+
+impl CrashReporter {
+    fn report(&self, info: CrashInfo) {
+        let report = CrashReport {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            thread: thread::current().name().map(str::to_string),
+            details: info.details,
+            backtrace: info.backtrace,
+            build_information: self.build_information.clone(),
+            recent_logs: aptos_logger::Logger::global()
+                .map(|logger| logger.recent_logs())
+                .unwrap_or_default(),
+        };
+        let body = match serde_json::to_string_pretty(&report) {
+            Ok(body) => body,
+            Err(err) => {
+                eprintln!("Failed to serialize the crash report: {}", err);
+                return;
+            }
+        };
+
+        // The logger may be gone by now, like in the rest of the panic handler
+        match self.write(report.timestamp_ms, &body) {
+            Ok(path) => eprintln!("Wrote the crash report to {:?}", path),
+            Err(err) => eprintln!(
+                "Failed to write the crash report to {:?}: {}",
+                self.dir, err
+            ),
+        }
+        if let Some(endpoint) = &self.endpoint {
+            match self.post(endpoint, body) {
+                Ok(()) => eprintln!("Posted the crash report to {}", endpoint),
+                Err(err) => eprintln!("Failed to post the crash report to {}: {}", endpoint, err),
+            }
+        }
+    }
+
+    /// Writes the report and deletes the oldest ones beyond `max_reports`
+    fn write(&self, timestamp_ms: u128, body: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!(
+            "{}{}{}",
+            CRASH_REPORT_PREFIX, timestamp_ms, CRASH_REPORT_EXTENSION
+        ));
+        fs::write(&path, body)?;
+
+        let mut reports = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(CRASH_REPORT_PREFIX) && name.ends_with(CRASH_REPORT_EXTENSION) {
+                reports.push(entry.path());
+            }
+        }
+        reports.sort();
+        let num_old = reports.len().saturating_sub(self.max_reports.max(1));
+        for old in &reports[..num_old] {
+            fs::remove_file(old)?;
+        }
+        Ok(path)
+    }
+
+    /// Posts the report from another thread, since the panic may be on a tokio thread, where
+    /// the blocking client can't run
+    fn post(&self, endpoint: &str, body: String) -> Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        let endpoint = endpoint.to_string();
+        let auth_token = self.auth_token.clone();
+        let timeout = self.post_timeout;
+        thread::spawn(move || {
+            let result = (|| -> Result<()> {
+                let client = reqwest::blocking::Client::builder()
+                    .timeout(timeout)
+                    .build()?;
+                let mut request = client
+                    .post(endpoint)
+                    .header("content-type", "application/json")
+                    .body(body);
+                if let Some(auth_token) = auth_token {
+                    request = request.bearer_auth(auth_token.expose());
+                }
+                request.send()?.error_for_status()?;
+                Ok(())
+            })();
+            let _ = sender.send(result);
+        });
+        receiver.recv_timeout(self.post_timeout)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_temppath::TempPath;
+
+    #[test]
+    fn test_write_crash_reports() {
+        let dir = TempPath::new();
+        let reporter = CrashReporter {
+            dir: dir.path().to_path_buf(),
+            max_reports: 2,
+            endpoint: None,
+            auth_token: None,
+            post_timeout: Duration::from_secs(1),
+            build_information: BTreeMap::new(),
+        };
+        for timestamp_ms in 1_000..1_003 {
+            reporter.write(timestamp_ms, "{}").unwrap();
+        }
+
+        // Only the newest reports are kept
+        let mut reports: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        reports.sort();
+        assert_eq!(reports, vec!["crash-1001.json", "crash-1002.json"]);
+    }
+}
@@ -115,7 +115,7 @@ pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
 
     info!(processor_name = processor_name, "Starting indexer...");
 
-    let db_uri = &config.postgres_uri.unwrap();
+    let db_uri = config.postgres_uri.as_ref().unwrap().expose();
     info!(
         processor_name = processor_name,
         "Creating connection pool..."
@@ -0,0 +1,232 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `/readiness` and `/liveness` probes of orchestrators, and the systemd notifications.
+//!
+//! A node is ready while it can serve: it isn't draining, its storage is open, it's synced to
+//! within the configured lag and its API answers. A node is live while all of its event loops
+//! make progress, so a node that is only slow to sync is never restarted, but a hung one is.
+
+use crate::runtime_configuration;
+use aptos_config::config::{HealthProbesConfig, NodeConfig};
+use aptos_logger::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How often systemd is checked on without a watchdog, until the node is ready
+const SYSTEMD_READINESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The outcome of a probe, which only passes if all of its checks pass
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProbeResult {
+    pub passed: bool,
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CheckResult {
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl ProbeResult {
+    fn new() -> Self {
+        Self {
+            passed: true,
+            checks: BTreeMap::new(),
+        }
+    }
+
+    fn check(&mut self, name: &str, passed: bool, detail: impl Into<String>) {
+        self.passed &= passed;
+        self.checks.insert(
+            name.to_string(),
+            CheckResult {
+                passed,
+                detail: detail.into(),
+            },
+        );
+    }
+}
+
+/// Whether the node is draining, has its storage open, is synced and serves its API
+pub async fn check_readiness(node_config: &NodeConfig) -> ProbeResult {
+    let config = &node_config.inspection_service.health_probes;
+    let mut result = ProbeResult::new();
+
+    let draining = aptos_drain::is_draining();
+    result.check(
+        "draining",
+        !draining,
+        if draining {
+            "The node is draining before it shuts down"
+        } else {
+            "The node isn't draining"
+        },
+    );
+
+    match runtime_configuration::db_reader() {
+        Some(db_reader) => {
+            result.check("storage", true, "The storage is open");
+            let (passed, detail) = match db_reader.get_latest_ledger_info() {
+                Ok(ledger_info) => {
+                    let lag_secs = sync_lag_secs(ledger_info.ledger_info().timestamp_usecs());
+                    (
+                        lag_secs <= config.max_sync_lag_secs,
+                        format!(
+                            "The latest ledger info is {} s old, at most {} s are allowed",
+                            lag_secs, config.max_sync_lag_secs
+                        ),
+                    )
+                }
+                Err(error) => (
+                    false,
+                    format!("Failed to read the latest ledger info: {}", error),
+                ),
+            };
+            result.check("sync", passed, detail);
+        }
+        None => result.check("storage", false, "The storage isn't open yet"),
+    }
+
+    if node_config.api.enabled && config.require_api {
+        let timeout = Duration::from_millis(config.api_timeout_ms);
+        let (passed, detail) = match check_api(node_config.api.address, timeout).await {
+            Ok(()) => (true, "The API is serving".to_string()),
+            Err(error) => (false, format!("The API isn't serving: {}", error)),
+        };
+        result.check("api", passed, detail);
+    }
+    result
+}
+
+/// Whether every event loop made progress recently, the loops that never ran aren't checked
+pub fn check_liveness(config: &HealthProbesConfig) -> ProbeResult {
+    let max_stall = Duration::from_secs(config.max_stall_secs);
+    let mut result = ProbeResult::new();
+    for (event_loop, since_heartbeat) in aptos_liveness::last_heartbeats() {
+        result.check(
+            event_loop,
+            since_heartbeat <= max_stall,
+            format!(
+                "Made progress {} ms ago, at most {} s are allowed",
+                since_heartbeat.as_millis(),
+                config.max_stall_secs
+            ),
+        );
+    }
+    result
+}
+
+fn sync_lag_secs(timestamp_usecs: u64) -> u64 {
+    let now_usecs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64;
+    now_usecs.saturating_sub(timestamp_usecs) / 1_000_000
+}
+
+async fn check_api(address: SocketAddr, timeout: Duration) -> anyhow::Result<()> {
+    // The API listens on all the interfaces of an unspecified address, the loopback among them
+    let ip = match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    let url = format!(
+        "http://{}/v1/-/healthy",
+        SocketAddr::new(ip, address.port())
+    );
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()?
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Notifies systemd once the storage is open, since syncing may take longer than systemd waits
+/// for a node to start, and stops if the node drains. In between, pings the watchdog at half
+/// its interval, but only while the node is live. Does nothing unless systemd runs the node.
+pub(crate) async fn run_systemd_notifier(node_config: NodeConfig) {
+    let socket_path = match env::var_os("NOTIFY_SOCKET") {
+        Some(socket_path) => PathBuf::from(socket_path),
+        None => {
+            warn!("systemd_notify is set, but NOTIFY_SOCKET isn't: the node isn't run by systemd");
+            return;
+        }
+    };
+    let watchdog_interval = env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|usecs| usecs.parse().ok())
+        .map(|usecs| Duration::from_micros(usecs) / 2);
+    let mut interval =
+        tokio::time::interval(watchdog_interval.unwrap_or(SYSTEMD_READINESS_INTERVAL));
+
+    let mut ready = false;
+    loop {
+        interval.tick().await;
+        if aptos_drain::is_draining() {
+            notify_systemd(&socket_path, "STOPPING=1");
+            return;
+        }
+        if !ready && runtime_configuration::db_reader().is_some() {
+            ready = notify_systemd(&socket_path, "READY=1");
+        }
+        match watchdog_interval {
+            Some(_) => {
+                let liveness = check_liveness(&node_config.inspection_service.health_probes);
+                if liveness.passed {
+                    notify_systemd(&socket_path, "WATCHDOG=1");
+                } else {
+                    warn!(
+                        "Not pinging the systemd watchdog, the node isn't live: {:?}",
+                        liveness
+                    );
+                }
+            }
+            None if ready => return,
+            None => {}
+        }
+    }
+}
+
+fn notify_systemd(socket_path: &Path, state: &str) -> bool {
+    match send_notification(socket_path, state) {
+        Ok(()) => true,
+        Err(error) => {
+            warn!("Failed to notify systemd of {}: {}", state, error);
+            false
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send_notification(socket_path: &Path, state: &str) -> io::Result<()> {
+    // Abstract sockets start with '@', which the standard library can't address
+    if socket_path.to_string_lossy().starts_with('@') {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "abstract notification sockets aren't supported",
+        ));
+    }
+    let socket = std::os::unix::net::UnixDatagram::unbound()?;
+    socket.send_to(state.as_bytes(), socket_path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_notification(_socket_path: &Path, _state: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "systemd only runs on unix",
+    ))
+}
@@ -1,6 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::log_levels::{LogLevels, SetLogLevelRequest};
 use anyhow::Result;
 use reqwest::Url;
 use std::collections::HashMap;
@@ -107,4 +108,42 @@ impl InspectionClient {
             })
             .collect()
     }
+
+    /// Retrieves the log filter and the log levels changed at runtime
+    pub async fn get_log_levels(&self, auth_token: &str) -> Result<LogLevels> {
+        let mut url = self.url.clone();
+        url.set_path("log_levels");
+        let response = self.client.get(url).bearer_auth(auth_token).send().await?;
+        Self::parse_log_levels(response).await
+    }
+
+    /// Changes the log level of a module, returns the log levels after the change
+    pub async fn set_log_level(
+        &self,
+        auth_token: &str,
+        request: &SetLogLevelRequest,
+    ) -> Result<LogLevels> {
+        let mut url = self.url.clone();
+        url.set_path("log_levels");
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(auth_token)
+            .json(request)
+            .send()
+            .await?;
+        Self::parse_log_levels(response).await
+    }
+
+    async fn parse_log_levels(response: reqwest::Response) -> Result<LogLevels> {
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::format_err!(
+                "Failed to query the log levels ({}): {}",
+                status,
+                response.text().await?
+            ));
+        }
+        Ok(response.json().await?)
+    }
 }
@@ -1,10 +1,17 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{gather_metrics, json_encoder::JsonEncoder, NUM_METRICS};
+use crate::{
+    gather_metrics,
+    health_probes::{self, ProbeResult},
+    json_encoder::JsonEncoder,
+    log_levels::handle_log_levels,
+    remote_write, runtime_configuration, NUM_METRICS,
+};
 use aptos_build_info::build_information;
 use aptos_config::config::NodeConfig;
 use hyper::{
+    server::conn::AddrStream,
     service::{make_service_fn, service_fn},
     Body, Method, Request, Response, Server, StatusCode,
 };
@@ -24,6 +31,27 @@ use tokio::runtime;
 const DISABLED_ENDPOINT_MESSAGE: &str =
     "This endpoint is disabled! Enable it in the InspectionServiceConfig.";
 
+// The consensus metric families summarized by the round state, without their prefix
+const CONSENSUS_METRIC_PREFIX: &str = "aptos_consensus_";
+const CONSENSUS_ROUND_STATE_METRICS: &[&str] = &[
+    "aptos_consensus_epoch",
+    "aptos_consensus_current_round",
+    "aptos_consensus_last_committed_round",
+    "aptos_consensus_last_committed_version",
+    "aptos_consensus_round_timeout_s",
+    "aptos_consensus_qc_rounds_count",
+    "aptos_consensus_timeout_rounds_count",
+    "aptos_consensus_timeout_count",
+    "aptos_consensus_num_blocks_in_tree",
+    "aptos_consensus_num_blocks_in_pipeline",
+];
+
+// The state sync metric families summarized by the progress endpoint
+const STATE_SYNC_EPOCH_VERIFICATION_MODE_METRIC: &str = "aptos_state_sync_epoch_verification_mode";
+const STATE_SYNC_MODE_METRIC: &str = "aptos_state_sync_mode";
+const STATE_SYNC_PROGRESS_METRIC: &str = "aptos_state_sync_progress";
+const STATE_SYNC_VERSION_METRIC: &str = "aptos_state_sync_version";
+
 pub fn encode_metrics(encoder: impl Encoder) -> Vec<u8> {
     let metric_families = gather_metrics();
     let mut buffer = vec![];
@@ -82,12 +110,74 @@ pub fn get_all_metrics() -> HashMap<String, String> {
     get_metrics(all_metric_families)
 }
 
+/// Returns a summary of the state sync progress: the synced and target
+/// versions, the sync rate, the estimated seconds remaining (-1 if unknown),
+/// the active syncing mode and the epoch verification mode.
+pub fn get_state_sync_progress() -> serde_json::Map<String, serde_json::Value> {
+    let mut progress = serde_json::Map::new();
+    for metric_family in gather_metrics() {
+        for metric in metric_family.get_metric() {
+            let label_value = match metric.get_label().first() {
+                Some(label) => label.get_value(),
+                None => continue,
+            };
+            let value = metric.get_gauge().get_value() as i64;
+            match metric_family.get_name() {
+                STATE_SYNC_VERSION_METRIC if label_value == "synced" => {
+                    progress.insert("synced_version".into(), value.into());
+                }
+                STATE_SYNC_PROGRESS_METRIC => {
+                    progress.insert(label_value.into(), value.into());
+                }
+                STATE_SYNC_MODE_METRIC if value == 1 => {
+                    progress.insert("mode".into(), label_value.into());
+                }
+                STATE_SYNC_EPOCH_VERIFICATION_MODE_METRIC if value == 1 => {
+                    progress.insert("epoch_verification_mode".into(), label_value.into());
+                }
+                _ => {}
+            }
+        }
+    }
+    progress
+}
+
+/// Returns a summary of the consensus round state: the current epoch and round, the last
+/// committed round and version, the round timeout and the rounds that timed out since the last
+/// restart. The values are missing if the node doesn't run consensus.
+pub fn get_consensus_round_state() -> serde_json::Map<String, serde_json::Value> {
+    let mut round_state = serde_json::Map::new();
+    for metric_family in gather_metrics() {
+        let name = metric_family.get_name();
+        if !CONSENSUS_ROUND_STATE_METRICS.contains(&name) {
+            continue;
+        }
+        if let Some(metric) = metric_family.get_metric().first() {
+            let value = match metric_family.get_field_type() {
+                MetricType::COUNTER => metric.get_counter().get_value(),
+                _ => metric.get_gauge().get_value(),
+            };
+            round_state.insert(
+                name.trim_start_matches(CONSENSUS_METRIC_PREFIX).into(),
+                (value as i64).into(),
+            );
+        }
+    }
+    round_state
+}
+
 async fn serve_requests(
     req: Request<Body>,
     node_config: NodeConfig,
+    remote_addr: SocketAddr,
 ) -> Result<Response<Body>, hyper::Error> {
     let mut resp = Response::new(Body::empty());
     match (req.method(), req.uri().path()) {
+        // Query and change the log levels at runtime
+        (&Method::GET | &Method::POST, "/log_levels") => {
+            resp = handle_log_levels(req, &node_config.inspection_service.log_levels, remote_addr)
+                .await;
+        }
         // Expose the node configuration
         (&Method::GET, "/configuration") => {
             if node_config.inspection_service.expose_configuration {
@@ -100,6 +190,43 @@ async fn serve_requests(
                 *resp.body_mut() = Body::from(DISABLED_ENDPOINT_MESSAGE);
             }
         }
+        // Expose the effective node configuration, as JSON with the secrets redacted
+        (&Method::GET, "/configuration/effective") => {
+            if node_config.inspection_service.expose_configuration {
+                match runtime_configuration::get_effective_configuration(&node_config) {
+                    Ok(configuration) => {
+                        *resp.body_mut() = Body::from(configuration.to_string());
+                    }
+                    Err(error) => {
+                        *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                        *resp.body_mut() = Body::from(error.to_string());
+                    }
+                }
+            } else {
+                *resp.body_mut() = Body::from(DISABLED_ENDPOINT_MESSAGE);
+            }
+        }
+        // Expose the feature flags and on-chain configs currently in effect
+        (&Method::GET, "/configuration/on_chain") => {
+            match runtime_configuration::get_on_chain_configuration() {
+                Ok(configuration) => {
+                    *resp.body_mut() = Body::from(configuration.to_string());
+                }
+                Err(error) => {
+                    *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                    *resp.body_mut() = Body::from(error.to_string());
+                }
+            }
+        }
+        // Expose all of the above and the build information in one response
+        (&Method::GET, "/configuration/dump") => {
+            if node_config.inspection_service.expose_configuration {
+                let dump = runtime_configuration::get_configuration_dump(&node_config);
+                *resp.body_mut() = Body::from(dump.to_string());
+            } else {
+                *resp.body_mut() = Body::from(DISABLED_ENDPOINT_MESSAGE);
+            }
+        }
         // Exposes JSON encoded metrics
         (&Method::GET, "/json_metrics") => {
             let encoder = JsonEncoder;
@@ -118,6 +245,23 @@ async fn serve_requests(
             let encoded_metrics = serde_json::to_string(&metrics).unwrap();
             *resp.body_mut() = Body::from(encoded_metrics);
         }
+        // The readiness probe, which fails while the node can't serve, e.g. until it's synced
+        (&Method::GET, "/readiness") => {
+            let readiness = health_probes::check_readiness(&node_config).await;
+            resp = probe_response(readiness);
+        }
+        // The liveness probe, which fails once an event loop of the node stops making progress
+        (&Method::GET, "/liveness") => {
+            let liveness =
+                health_probes::check_liveness(&node_config.inspection_service.health_probes);
+            resp = probe_response(liveness);
+        }
+        // Exposes the state sync progress and estimated time remaining
+        (&Method::GET, "/state_sync_progress") => {
+            let progress = get_state_sync_progress();
+            let encoded_progress = serde_json::to_string(&progress).unwrap();
+            *resp.body_mut() = Body::from(encoded_progress);
+        }
         // Expose the system and build information
         (&Method::GET, "/system_information") => {
             if node_config.inspection_service.expose_system_information {
@@ -139,6 +283,14 @@ async fn serve_requests(
     Ok(resp)
 }
 
+fn probe_response(result: ProbeResult) -> Response<Body> {
+    let mut resp = Response::new(Body::from(serde_json::to_string(&result).unwrap()));
+    if !result.passed {
+        *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    }
+    resp
+}
+
 pub fn start_inspection_service(node_config: NodeConfig) {
     // Fetch the service port and address
     let service_port = node_config.inspection_service.port;
@@ -156,25 +308,39 @@ pub fn start_inspection_service(node_config: NodeConfig) {
         .next()
         .unwrap();
 
+    // Push the metrics, if a remote write endpoint is configured
+    if let Some(remote_write_config) = node_config.inspection_service.remote_write.clone() {
+        remote_write::start_remote_write(remote_write_config);
+    }
+
     // Spawn the server
+    let systemd_notifier = node_config
+        .inspection_service
+        .health_probes
+        .systemd_notify
+        .then(|| health_probes::run_systemd_notifier(node_config.clone()));
     thread::spawn(move || {
-        let make_service = make_service_fn(move |_conn| {
+        let make_service = make_service_fn(move |conn: &AddrStream| {
             let node_config = node_config.clone();
+            let remote_addr = conn.remote_addr();
             async move {
                 Ok::<_, Infallible>(service_fn(move |request| {
-                    serve_requests(request, node_config.clone())
+                    serve_requests(request, node_config.clone(), remote_addr)
                 }))
             }
         });
 
         let runtime = runtime::Builder::new_current_thread()
             .thread_name("inspection")
-            .enable_io()
+            .enable_all()
             .disable_lifo_slot()
             .build()
             .unwrap();
         runtime
             .block_on(async {
+                if let Some(systemd_notifier) = systemd_notifier {
+                    tokio::spawn(systemd_notifier);
+                }
                 let server = Server::bind(&addr).serve(make_service);
                 server.await
             })
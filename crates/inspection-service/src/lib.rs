@@ -3,9 +3,13 @@
 
 #![forbid(unsafe_code)]
 
+pub mod health_probes;
 pub mod inspection_client;
 pub mod inspection_service;
 mod json_encoder;
+pub mod log_levels;
+pub mod remote_write;
+pub mod runtime_configuration;
 
 #[cfg(test)]
 mod unit_tests;
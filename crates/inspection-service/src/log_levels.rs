@@ -0,0 +1,160 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `/log_levels` endpoint, to query and change the log levels of modules at runtime. Changed
+//! levels revert by themselves, so debugging doesn't leave the node logging at DEBUG for good.
+
+use aptos_audit::{audit, AuditEvent};
+use aptos_config::config::LogLevelsEndpointConfig;
+use aptos_logger::{prelude::*, LevelFilter, Logger};
+use hyper::{
+    header::{HeaderValue, AUTHORIZATION},
+    Body, Method, Request, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, time::Duration};
+
+/// The log levels of a node, as returned by the endpoint
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LogLevels {
+    /// The filter of the local printer, e.g. `INFO,consensus=DEBUG`
+    pub filter: String,
+    /// The levels changed at runtime, which take precedence over the filter
+    pub overrides: Vec<LogLevelOverride>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LogLevelOverride {
+    /// All modules if it isn't set
+    pub module: Option<String>,
+    pub level: String,
+    pub remaining_secs: u64,
+}
+
+/// A change of log level, posted to the endpoint
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetLogLevelRequest {
+    /// All modules if it isn't set
+    pub module: Option<String>,
+    /// The new level: off, error, warn, info, debug or trace. The level set at runtime reverts to
+    /// the filter if it isn't set.
+    pub level: Option<String>,
+    /// How long the level lasts, the default and maximum durations are set in the node config
+    pub duration_secs: Option<u64>,
+}
+
+pub(crate) async fn handle_log_levels(
+    req: Request<Body>,
+    config: &LogLevelsEndpointConfig,
+    remote_addr: SocketAddr,
+) -> Response<Body> {
+    let auth_token = match &config.auth_token {
+        Some(auth_token) => auth_token,
+        None => {
+            return response(
+                StatusCode::FORBIDDEN,
+                "This endpoint is disabled! Set an auth_token in the InspectionServiceConfig.",
+            )
+        }
+    };
+    let is_post = req.method() == Method::POST;
+    if !auth_token.authorizes(req.headers().get(AUTHORIZATION).map(HeaderValue::as_bytes)) {
+        if is_post {
+            audit(
+                AuditEvent::new("admin.set_log_level")
+                    .actor(remote_addr.to_string())
+                    .outcome(&Err::<(), _>("unauthorized")),
+            );
+        }
+        return response(StatusCode::UNAUTHORIZED, "Invalid or missing bearer token");
+    }
+    let logger = match Logger::global() {
+        Some(logger) => logger,
+        None => return response(StatusCode::SERVICE_UNAVAILABLE, "The logger isn't set up"),
+    };
+
+    if is_post {
+        let result = set_log_level(req, config, logger).await;
+        let mut event = AuditEvent::new("admin.set_log_level")
+            .actor(remote_addr.to_string())
+            .outcome(&result);
+        if let Ok(request) = &result {
+            event = event
+                .detail("module", request.module.as_deref().unwrap_or("*"))
+                .detail("level", request.level.as_deref().unwrap_or("reverted"));
+            if let Some(duration_secs) = request.duration_secs {
+                event = event.detail("duration_secs", duration_secs);
+            }
+            info!(
+                "Log level of {} changed to {} by {}",
+                request.module.as_deref().unwrap_or("all modules"),
+                request.level.as_deref().unwrap_or("its filter"),
+                remote_addr
+            );
+        }
+        audit(event);
+        if let Err(error) = result {
+            return response(StatusCode::BAD_REQUEST, error);
+        }
+    }
+
+    let encoded_levels = serde_json::to_string(&log_levels(logger)).unwrap();
+    response(StatusCode::OK, encoded_levels)
+}
+
+/// Applies the request, which is returned with the duration that was applied
+async fn set_log_level(
+    req: Request<Body>,
+    config: &LogLevelsEndpointConfig,
+    logger: &Logger,
+) -> Result<SetLogLevelRequest, String> {
+    let body = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|error| format!("Unable to read the request: {}", error))?;
+    let mut request: SetLogLevelRequest =
+        serde_json::from_slice(&body).map_err(|error| format!("Invalid request: {}", error))?;
+
+    let module = request
+        .module
+        .as_deref()
+        .filter(|module| !module.is_empty());
+    match &request.level {
+        Some(level) => {
+            let level: LevelFilter = level
+                .parse()
+                .map_err(|_| format!("Invalid log level: {}", level))?;
+            let duration_secs = request
+                .duration_secs
+                .unwrap_or(config.default_duration_secs)
+                .min(config.max_duration_secs);
+            logger.set_level_override(module, level, Duration::from_secs(duration_secs));
+            request.duration_secs = Some(duration_secs);
+        }
+        None => {
+            logger.remove_level_override(module);
+        }
+    }
+    Ok(request)
+}
+
+fn log_levels(logger: &Logger) -> LogLevels {
+    LogLevels {
+        filter: logger.local_filter(),
+        overrides: logger
+            .level_overrides()
+            .into_iter()
+            .map(|level_override| LogLevelOverride {
+                remaining_secs: level_override.remaining().as_secs(),
+                module: level_override.module,
+                level: level_override.level.to_string(),
+            })
+            .collect(),
+    }
+}
+
+fn response(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+}
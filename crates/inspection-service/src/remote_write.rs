@@ -0,0 +1,244 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Periodically pushes the metrics to a Prometheus remote write endpoint, for nodes that can't be
+//! scraped (e.g., because they sit behind a NAT). See the remote write specification:
+//! https://prometheus.io/docs/concepts/remote_write_spec/
+
+use crate::gather_metrics;
+use aptos_config::config::{MetricsRemoteWriteConfig, RemoteWriteCredentials};
+use aptos_logger::prelude::*;
+use aptos_metrics_core::{register_int_counter_vec, IntCounterVec};
+use once_cell::sync::Lazy;
+use prometheus::proto::{MetricFamily, MetricType};
+use prost::Message;
+use std::{
+    collections::BTreeMap,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The longest literal of a snappy block
+const MAX_SNAPPY_LITERAL_LEN: usize = 1 << 16;
+
+pub static REMOTE_WRITE_PUSHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_metrics_remote_write_pushes",
+        "Number of pushes of the metrics to the remote write endpoint, by result",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Spawns a thread that pushes the metrics every `push_interval_ms`
+pub fn start_remote_write(config: MetricsRemoteWriteConfig) {
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(Duration::from_millis(config.timeout_ms))
+        .build()
+    {
+        Ok(client) => client,
+        Err(error) => {
+            error!(
+                "Unable to start pushing metrics to {}: {}",
+                config.url, error
+            );
+            return;
+        }
+    };
+
+    thread::Builder::new()
+        .name("metrics-remote-write".into())
+        .spawn(move || loop {
+            thread::sleep(Duration::from_millis(config.push_interval_ms));
+            match push_metrics(&client, &config) {
+                Ok(()) => REMOTE_WRITE_PUSHES.with_label_values(&["success"]).inc(),
+                Err(error) => {
+                    REMOTE_WRITE_PUSHES.with_label_values(&["failure"]).inc();
+                    sample!(
+                        SampleRate::Duration(Duration::from_secs(60)),
+                        warn!("Failed to push metrics to {}: {}", config.url, error)
+                    );
+                }
+            }
+        })
+        .expect("Failed to spawn the metrics remote write thread");
+}
+
+fn push_metrics(
+    client: &reqwest::blocking::Client,
+    config: &MetricsRemoteWriteConfig,
+) -> anyhow::Result<()> {
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+    let request = proto::WriteRequest {
+        timeseries: to_time_series(&gather_metrics(), &config.labels, now_ms),
+    };
+
+    let mut builder = client
+        .post(&config.url)
+        .header("Content-Encoding", "snappy")
+        .header("Content-Type", "application/x-protobuf")
+        .header("User-Agent", "aptos-node")
+        .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+        .body(snappy_encode(&request.encode_to_vec()));
+    builder = match &config.credentials {
+        Some(RemoteWriteCredentials::BasicAuth { username, password }) => {
+            builder.basic_auth(username, Some(password.expose()))
+        }
+        Some(RemoteWriteCredentials::BearerToken(token)) => builder.bearer_auth(token.expose()),
+        None => builder,
+    };
+    builder.send()?.error_for_status()?;
+    Ok(())
+}
+
+/// Converts the metric families to remote write series, the way Prometheus flattens them when
+/// scraping. The labels of a metric take precedence over the `extra_labels`.
+pub(crate) fn to_time_series(
+    families: &[MetricFamily],
+    extra_labels: &BTreeMap<String, String>,
+    now_ms: i64,
+) -> Vec<proto::TimeSeries> {
+    let mut series = vec![];
+    for family in families {
+        let name = family.get_name();
+        for metric in family.get_metric() {
+            let mut labels = extra_labels.clone();
+            for label in metric.get_label() {
+                labels.insert(label.get_name().into(), label.get_value().into());
+            }
+            let timestamp = match metric.get_timestamp_ms() {
+                0 => now_ms,
+                timestamp => timestamp,
+            };
+            let mut push = |suffix: &str, extra_label: Option<(&str, String)>, value: f64| {
+                let mut labels = labels.clone();
+                labels.insert("__name__".into(), format!("{}{}", name, suffix));
+                if let Some((label, label_value)) = extra_label {
+                    labels.insert(label.into(), label_value);
+                }
+                series.push(proto::TimeSeries {
+                    labels: labels
+                        .into_iter()
+                        .map(|(name, value)| proto::Label { name, value })
+                        .collect(),
+                    samples: vec![proto::Sample { value, timestamp }],
+                });
+            };
+
+            match family.get_field_type() {
+                MetricType::COUNTER => push("", None, metric.get_counter().get_value()),
+                MetricType::GAUGE => push("", None, metric.get_gauge().get_value()),
+                MetricType::UNTYPED => push("", None, metric.get_untyped().get_value()),
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let mut has_inf_bucket = false;
+                    for bucket in histogram.get_bucket() {
+                        let upper_bound = bucket.get_upper_bound();
+                        has_inf_bucket |= upper_bound == f64::INFINITY;
+                        push(
+                            "_bucket",
+                            Some(("le", format_bound(upper_bound))),
+                            bucket.get_cumulative_count() as f64,
+                        );
+                    }
+                    if !has_inf_bucket {
+                        push(
+                            "_bucket",
+                            Some(("le", format_bound(f64::INFINITY))),
+                            histogram.get_sample_count() as f64,
+                        );
+                    }
+                    push("_sum", None, histogram.get_sample_sum());
+                    push("_count", None, histogram.get_sample_count() as f64);
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        push(
+                            "",
+                            Some(("quantile", format_bound(quantile.get_quantile()))),
+                            quantile.get_value(),
+                        );
+                    }
+                    push("_sum", None, summary.get_sample_sum());
+                    push("_count", None, summary.get_sample_count() as f64);
+                }
+            }
+        }
+    }
+    series
+}
+
+fn format_bound(bound: f64) -> String {
+    if bound == f64::INFINITY {
+        "+Inf".into()
+    } else {
+        bound.to_string()
+    }
+}
+
+/// Frames the data as a snappy block made only of literals, see
+/// https://github.com/google/snappy/blob/main/format_description.txt
+/// This doesn't compress anything, but every snappy decoder reads it.
+pub(crate) fn snappy_encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(data.len() + data.len() / MAX_SNAPPY_LITERAL_LEN * 3 + 8);
+
+    // The uncompressed length, as a varint
+    let mut len = data.len();
+    while len >= 0x80 {
+        encoded.push((len as u8 & 0x7f) | 0x80);
+        len >>= 7;
+    }
+    encoded.push(len as u8);
+
+    for literal in data.chunks(MAX_SNAPPY_LITERAL_LEN) {
+        let len = literal.len() - 1;
+        if len < 60 {
+            encoded.push((len as u8) << 2);
+        } else if len < 1 << 8 {
+            encoded.push(60 << 2);
+            encoded.push(len as u8);
+        } else {
+            encoded.push(61 << 2);
+            encoded.extend_from_slice(&(len as u16).to_le_bytes());
+        }
+        encoded.extend_from_slice(literal);
+    }
+    encoded
+}
+
+/// The remote write protocol, see
+/// https://github.com/prometheus/prometheus/blob/main/prompb/remote.proto
+pub(crate) mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WriteRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub timeseries: Vec<TimeSeries>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TimeSeries {
+        /// Sorted by name
+        #[prost(message, repeated, tag = "1")]
+        pub labels: Vec<Label>,
+        #[prost(message, repeated, tag = "2")]
+        pub samples: Vec<Sample>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Label {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sample {
+        #[prost(double, tag = "1")]
+        pub value: f64,
+        /// In milliseconds
+        #[prost(int64, tag = "2")]
+        pub timestamp: i64,
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `/configuration/*` endpoints, which tell what a node is actually running: the effective
+//! node config with its secrets redacted, the feature flags and on-chain configs currently in
+//! effect, and the build information.
+
+use aptos_build_info::build_information;
+use aptos_config::{config::NodeConfig, secret};
+use aptos_logger::prelude::*;
+use aptos_types::on_chain_config::{
+    ApprovedExecutionHashes, FeatureFlag, Features, OnChainConfig, OnChainConsensusConfig,
+    ValidatorSet, Version,
+};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use storage_interface::DbReader;
+
+/// The feature flags reported by name. Unknown flags still show in the raw bitset.
+const FEATURE_FLAGS: &[FeatureFlag] = &[
+    FeatureFlag::CODE_DEPENDENCY_CHECK,
+    FeatureFlag::TREAT_FRIEND_AS_PRIVATE,
+];
+
+// The storage only opens after the inspection service starts, so it's set once it's ready
+static DB_READER: OnceCell<Arc<dyn DbReader>> = OnceCell::new();
+
+/// Makes the on-chain configs available to the endpoints, once the storage is open
+pub fn set_db_reader(db_reader: Arc<dyn DbReader>) {
+    if DB_READER.set(db_reader).is_err() {
+        warn!("The inspection service storage reader was already set");
+    }
+}
+
+/// The storage reader, once the storage is open
+pub(crate) fn db_reader() -> Option<&'static Arc<dyn DbReader>> {
+    DB_READER.get()
+}
+
+/// Returns the fully-resolved node config, i.e. after the defaults and the overrides are
+/// applied, with its secrets redacted
+pub fn get_effective_configuration(node_config: &NodeConfig) -> Result<Value, serde_json::Error> {
+    secret::redacted(|| serde_json::to_value(node_config))
+}
+
+fn fetch_on_chain_config<T: OnChainConfig + Serialize>(
+    db_reader: &dyn DbReader,
+    version: u64,
+) -> Value {
+    let config = db_reader
+        .fetch_config_by_version(T::CONFIG_ID, version)
+        .and_then(|bytes| T::deserialize_into_config(&bytes))
+        .and_then(|config| serde_json::to_value(config).map_err(Into::into));
+    match config {
+        Ok(config) => config,
+        Err(error) => json!({ "error": error.to_string() }),
+    }
+}
+
+/// Returns the feature flags and the on-chain configs at the latest version of the storage
+pub fn get_on_chain_configuration() -> anyhow::Result<Value> {
+    let db_reader = DB_READER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("The storage is not open yet"))?
+        .as_ref();
+    let version = db_reader.get_latest_version()?;
+
+    let features = db_reader
+        .fetch_config_by_version(Features::CONFIG_ID, version)
+        .and_then(|bytes| Features::deserialize_into_config(&bytes))
+        .unwrap_or_default();
+    let feature_flags: Map<String, Value> = FEATURE_FLAGS
+        .iter()
+        .map(|flag| {
+            (
+                format!("{:?}", flag).to_lowercase(),
+                features.is_enabled(*flag).into(),
+            )
+        })
+        .collect();
+
+    let mut on_chain_configs = Map::new();
+    on_chain_configs.insert(
+        ApprovedExecutionHashes::CONFIG_ID.name(),
+        fetch_on_chain_config::<ApprovedExecutionHashes>(db_reader, version),
+    );
+    on_chain_configs.insert(
+        OnChainConsensusConfig::CONFIG_ID.name(),
+        fetch_on_chain_config::<OnChainConsensusConfig>(db_reader, version),
+    );
+    on_chain_configs.insert(
+        ValidatorSet::CONFIG_ID.name(),
+        fetch_on_chain_config::<ValidatorSet>(db_reader, version),
+    );
+    on_chain_configs.insert(
+        Version::CONFIG_ID.name(),
+        fetch_on_chain_config::<Version>(db_reader, version),
+    );
+
+    let feature_flags_bitset: String = features
+        .features
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    Ok(json!({
+        "version": version,
+        "feature_flags": feature_flags,
+        "feature_flags_bitset": feature_flags_bitset,
+        "on_chain_configs": on_chain_configs,
+    }))
+}
+
+/// Returns everything in one response, for support to diagnose a node from a single request.
+/// The build information is only included if the system information is exposed.
+pub fn get_configuration_dump(node_config: &NodeConfig) -> Value {
+    let mut dump = Map::new();
+    dump.insert(
+        "effective_configuration".into(),
+        get_effective_configuration(node_config)
+            .unwrap_or_else(|error| json!({ "error": error.to_string() })),
+    );
+    dump.insert(
+        "on_chain_configuration".into(),
+        get_on_chain_configuration().unwrap_or_else(|error| json!({ "error": error.to_string() })),
+    );
+    if node_config.inspection_service.expose_system_information {
+        dump.insert("build_information".into(), json!(build_information!()));
+    }
+    Value::Object(dump)
+}
@@ -0,0 +1,35 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::health_probes::{check_liveness, check_readiness};
+use aptos_config::config::{HealthProbesConfig, NodeConfig};
+use std::{thread, time::Duration};
+
+#[test]
+fn liveness_probe_test() {
+    aptos_liveness::heartbeat("test_event_loop");
+    let liveness = check_liveness(&HealthProbesConfig::default());
+    assert!(liveness.passed);
+    assert!(liveness.checks["test_event_loop"].passed);
+
+    // An event loop that hasn't made progress for longer than allowed fails the probe
+    thread::sleep(Duration::from_millis(10));
+    let liveness = check_liveness(&HealthProbesConfig {
+        max_stall_secs: 0,
+        ..HealthProbesConfig::default()
+    });
+    assert!(!liveness.passed);
+    assert!(!liveness.checks["test_event_loop"].passed);
+}
+
+#[tokio::test]
+async fn readiness_probe_test() {
+    // The storage is never opened in the tests, so the node isn't ready
+    let mut node_config = NodeConfig::default();
+    node_config.api.enabled = false;
+    let readiness = check_readiness(&node_config).await;
+    assert!(!readiness.passed);
+    assert!(readiness.checks["draining"].passed);
+    assert!(!readiness.checks["storage"].passed);
+    assert!(!readiness.checks.contains_key("api"));
+}
@@ -1,10 +1,13 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::inspection_service::get_all_metrics;
+use crate::inspection_service::{get_all_metrics, get_consensus_round_state};
 use assert_approx_eq::assert_approx_eq;
 use once_cell::sync::Lazy;
-use prometheus::{proto::MetricFamily, register_int_counter, Counter, IntCounter, Opts, Registry};
+use prometheus::{
+    proto::MetricFamily, register_int_counter, register_int_gauge, Counter, IntCounter, Opts,
+    Registry,
+};
 use rusty_fork::rusty_fork_test;
 
 const INT_COUNTER_NAME: &str = "INT_COUNTER";
@@ -70,3 +73,20 @@ fn get_all_metrics_test() {
     }
 }
 }
+
+rusty_fork_test! {
+#[test]
+fn get_consensus_round_state_test() {
+    register_int_gauge!("aptos_consensus_current_round", "The current round")
+        .unwrap()
+        .set(42);
+    register_int_counter!("aptos_consensus_timeout_rounds_count", "The timeout rounds")
+        .unwrap()
+        .inc_by(3);
+
+    let round_state = get_consensus_round_state();
+    assert_eq!(round_state.len(), 2);
+    assert_eq!(round_state["current_round"], 42);
+    assert_eq!(round_state["timeout_rounds_count"], 3);
+}
+}
@@ -0,0 +1,94 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::log_levels::{handle_log_levels, LogLevels, SetLogLevelRequest};
+use aptos_config::config::LogLevelsEndpointConfig;
+use aptos_logger::{Level, Logger};
+use hyper::{header::AUTHORIZATION, Body, Method, Request, Response, StatusCode};
+use std::net::SocketAddr;
+
+fn request(method: Method, auth_token: &str, body: Option<SetLogLevelRequest>) -> Request<Body> {
+    let body = body.map_or_else(Body::empty, |body| {
+        Body::from(serde_json::to_vec(&body).unwrap())
+    });
+    Request::builder()
+        .method(method)
+        .uri("/log_levels")
+        .header(AUTHORIZATION, format!("Bearer {}", auth_token))
+        .body(body)
+        .unwrap()
+}
+
+async fn log_levels(response: Response<Body>) -> LogLevels {
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn log_levels_test() {
+    Logger::builder().is_async(false).level(Level::Info).build();
+    let config = LogLevelsEndpointConfig {
+        auth_token: Some("token".into()),
+        ..Default::default()
+    };
+    let remote_addr: SocketAddr = "127.0.0.1:1234".parse().unwrap();
+
+    let response =
+        handle_log_levels(request(Method::GET, "wrong", None), &config, remote_addr).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let disabled_config = LogLevelsEndpointConfig::default();
+    let response = handle_log_levels(
+        request(Method::GET, "token", None),
+        &disabled_config,
+        remote_addr,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // The duration is capped by the config
+    let set_level = SetLogLevelRequest {
+        module: Some("consensus".into()),
+        level: Some("debug".into()),
+        duration_secs: Some(config.max_duration_secs * 2),
+    };
+    let response = handle_log_levels(
+        request(Method::POST, "token", Some(set_level)),
+        &config,
+        remote_addr,
+    )
+    .await;
+    let levels = log_levels(response).await;
+    assert_eq!(levels.overrides.len(), 1);
+    let level_override = &levels.overrides[0];
+    assert_eq!(level_override.module.as_deref(), Some("consensus"));
+    assert_eq!(level_override.level, "DEBUG");
+    assert!(level_override.remaining_secs < config.max_duration_secs);
+    assert!(level_override.remaining_secs + 60 > config.max_duration_secs);
+
+    let invalid_level = SetLogLevelRequest {
+        level: Some("verbose".into()),
+        ..Default::default()
+    };
+    let response = handle_log_levels(
+        request(Method::POST, "token", Some(invalid_level)),
+        &config,
+        remote_addr,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let reset_level = SetLogLevelRequest {
+        module: Some("consensus".into()),
+        ..Default::default()
+    };
+    let response = handle_log_levels(
+        request(Method::POST, "token", Some(reset_level)),
+        &config,
+        remote_addr,
+    )
+    .await;
+    let levels = log_levels(response).await;
+    assert_eq!(levels.filter, "INFO");
+    assert!(levels.overrides.is_empty());
+}
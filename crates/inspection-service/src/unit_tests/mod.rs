@@ -1,4 +1,8 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+mod health_probes_test;
 mod lib_test;
+mod log_levels_test;
+mod remote_write_test;
+mod runtime_configuration_test;
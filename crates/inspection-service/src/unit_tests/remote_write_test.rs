@@ -0,0 +1,99 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::remote_write::{snappy_encode, to_time_series};
+use prometheus::{Histogram, HistogramOpts, IntCounterVec, Opts, Registry};
+use std::collections::BTreeMap;
+
+fn series_to_strings(
+    series: Vec<crate::remote_write::proto::TimeSeries>,
+) -> Vec<(String, f64, i64)> {
+    series
+        .into_iter()
+        .map(|series| {
+            let labels: Vec<_> = series
+                .labels
+                .iter()
+                .map(|label| format!("{}={}", label.name, label.value))
+                .collect();
+            let sample = &series.samples[0];
+            (labels.join(","), sample.value, sample.timestamp)
+        })
+        .collect()
+}
+
+#[test]
+fn to_time_series_test() {
+    let registry = Registry::new();
+    let counter =
+        IntCounterVec::new(Opts::new("test_counter", "test counter help"), &["peer"]).unwrap();
+    registry.register(Box::new(counter.clone())).unwrap();
+    counter.with_label_values(&["a"]).inc_by(3);
+    let histogram = Histogram::with_opts(
+        HistogramOpts::new("test_histogram", "test histogram help").buckets(vec![0.5, 1.0]),
+    )
+    .unwrap();
+    registry.register(Box::new(histogram.clone())).unwrap();
+    histogram.observe(0.25);
+    histogram.observe(2.0);
+
+    // The labels of the metrics take precedence over the extra labels
+    let extra_labels = BTreeMap::from([
+        ("node".to_string(), "validator".to_string()),
+        ("peer".to_string(), "default".to_string()),
+    ]);
+    let series = series_to_strings(to_time_series(&registry.gather(), &extra_labels, 42));
+    assert_eq!(
+        series,
+        vec![
+            (
+                "__name__=test_counter,node=validator,peer=a".into(),
+                3.0,
+                42
+            ),
+            (
+                "__name__=test_histogram_bucket,le=0.5,node=validator,peer=default".into(),
+                1.0,
+                42
+            ),
+            (
+                "__name__=test_histogram_bucket,le=1,node=validator,peer=default".into(),
+                1.0,
+                42
+            ),
+            (
+                "__name__=test_histogram_bucket,le=+Inf,node=validator,peer=default".into(),
+                2.0,
+                42
+            ),
+            (
+                "__name__=test_histogram_sum,node=validator,peer=default".into(),
+                2.25,
+                42
+            ),
+            (
+                "__name__=test_histogram_count,node=validator,peer=default".into(),
+                2.0,
+                42
+            ),
+        ]
+    );
+}
+
+#[test]
+fn snappy_encode_test() {
+    assert_eq!(snappy_encode(b""), vec![0]);
+    assert_eq!(snappy_encode(b"hello"), b"\x05\x10hello".to_vec());
+
+    // Longer literals have their length after the tag
+    let data = vec![7; 300];
+    let encoded = snappy_encode(&data);
+    assert_eq!(encoded[..5], [0xac, 0x02, 61 << 2, 0x2b, 0x01]);
+    assert_eq!(encoded[5..], data[..]);
+
+    // The literals are at most 64KiB
+    let data = vec![7; (1 << 16) + 1];
+    let encoded = snappy_encode(&data);
+    assert_eq!(encoded[..6], [0x81, 0x80, 0x04, 61 << 2, 0xff, 0xff]);
+    assert_eq!(encoded[6 + (1 << 16)..], [0, 7]);
+}
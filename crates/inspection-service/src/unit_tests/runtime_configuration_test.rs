@@ -0,0 +1,63 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::runtime_configuration::get_effective_configuration;
+use aptos_config::{
+    config::{
+        Identity, MetricsRemoteWriteConfig, NetworkConfig, NodeConfig, RemoteWriteCredentials,
+        TestConfig,
+    },
+    network_id::NetworkId,
+    secret::REDACTED,
+};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, x25519};
+use aptos_types::PeerId;
+use std::convert::TryFrom;
+
+#[test]
+fn effective_configuration_redacts_secrets_test() {
+    let mut node_config = NodeConfig::default();
+    let mut network_config = NetworkConfig::network_with_id(NetworkId::Validator);
+    network_config.identity =
+        Identity::from_config(x25519::PrivateKey::from([7u8; 32]), PeerId::random());
+    node_config.validator_network = Some(network_config);
+    node_config.inspection_service.log_levels.auth_token = Some("log-levels-token".into());
+    node_config.inspection_service.remote_write = Some(MetricsRemoteWriteConfig {
+        credentials: Some(RemoteWriteCredentials::BasicAuth {
+            username: "user".into(),
+            password: "remote-write-password".into(),
+        }),
+        ..MetricsRemoteWriteConfig::default()
+    });
+    let mut test_config = TestConfig::open_module();
+    test_config.operator_key(Ed25519PrivateKey::try_from([8u8; 32].as_ref()).unwrap());
+    test_config.owner_key(Ed25519PrivateKey::try_from([9u8; 32].as_ref()).unwrap());
+    test_config.execution_key(Ed25519PrivateKey::try_from([10u8; 32].as_ref()).unwrap());
+    node_config.test = Some(test_config);
+
+    let configuration = get_effective_configuration(&node_config).unwrap();
+    let encoded_configuration = configuration.to_string();
+    assert!(!encoded_configuration.contains("0707070707"));
+    assert!(!encoded_configuration.contains("log-levels-token"));
+    assert!(!encoded_configuration.contains("remote-write-password"));
+    for key in ["0808080808", "0909090909", "0a0a0a0a0a"] {
+        assert!(!encoded_configuration.contains(key));
+    }
+    assert_eq!(
+        configuration["validator_network"]["identity"]["key"],
+        REDACTED
+    );
+    assert_eq!(
+        configuration["inspection_service"]["log_levels"]["auth_token"],
+        REDACTED
+    );
+    for key in ["operator_key", "owner_key", "execution_key"] {
+        assert_eq!(configuration["test"][key]["key"], REDACTED);
+    }
+
+    // The rest of the configuration is returned as is
+    assert_eq!(
+        configuration["inspection_service"]["port"],
+        node_config.inspection_service.port
+    );
+}
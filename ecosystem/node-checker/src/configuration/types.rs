@@ -5,9 +5,9 @@ use crate::evaluator::EvaluationResult;
 use crate::{
     evaluators::{
         direct::{
-            get_node_identity, HandshakeEvaluatorArgs, LatencyEvaluatorArgs,
-            NodeIdentityEvaluatorArgs, StateSyncVersionEvaluatorArgs, TpsEvaluatorArgs,
-            TransactionAvailabilityEvaluatorArgs,
+            get_node_identity, ConsensusParticipationEvaluatorArgs, HandshakeEvaluatorArgs,
+            LatencyEvaluatorArgs, NodeIdentityEvaluatorArgs, StateSyncVersionEvaluatorArgs,
+            StorageLagEvaluatorArgs, TpsEvaluatorArgs, TransactionAvailabilityEvaluatorArgs,
         },
         metrics::{
             ConsensusProposalsEvaluatorArgs, ConsensusRoundEvaluatorArgs,
@@ -139,6 +139,9 @@ pub struct EvaluatorArgs {
     #[clap(flatten)]
     pub build_version_args: BuildVersionEvaluatorArgs,
 
+    #[clap(flatten)]
+    pub consensus_participation_args: ConsensusParticipationEvaluatorArgs,
+
     #[clap(flatten)]
     pub consensus_proposals_args: ConsensusProposalsEvaluatorArgs,
 
@@ -172,6 +175,9 @@ pub struct EvaluatorArgs {
     #[clap(flatten)]
     pub state_sync_version_metrics_args: StateSyncVersionMetricsEvaluatorArgs,
 
+    #[clap(flatten)]
+    pub storage_lag_args: StorageLagEvaluatorArgs,
+
     #[clap(flatten)]
     #[oai(skip)]
     pub tps_args: TpsEvaluatorArgs,
@@ -6,9 +6,9 @@ use crate::{
     evaluator::Evaluator,
     evaluators::{
         direct::{
-            ApiEvaluatorError, DirectEvaluatorInput, HandshakeEvaluator, LatencyEvaluator,
-            NoiseEvaluatorError, StateSyncVersionEvaluator, TpsEvaluator, TpsEvaluatorError,
-            TransactionAvailabilityEvaluator,
+            ApiEvaluatorError, ConsensusParticipationEvaluator, DirectEvaluatorInput,
+            HandshakeEvaluator, LatencyEvaluator, NoiseEvaluatorError, StateSyncVersionEvaluator,
+            StorageLagEvaluator, TpsEvaluator, TpsEvaluatorError, TransactionAvailabilityEvaluator,
         },
         metrics::{
             ConsensusProposalsEvaluator, ConsensusRoundEvaluator, ConsensusTimeoutsEvaluator,
@@ -142,6 +142,11 @@ pub fn build_evaluators(
         &mut evaluator_identifiers,
         evaluator_args,
     )?;
+    ConsensusParticipationEvaluator::add_from_evaluator_args(
+        &mut evaluators,
+        &mut evaluator_identifiers,
+        evaluator_args,
+    )?;
     ConsensusProposalsEvaluator::add_from_evaluator_args(
         &mut evaluators,
         &mut evaluator_identifiers,
@@ -192,6 +197,11 @@ pub fn build_evaluators(
         &mut evaluator_identifiers,
         evaluator_args,
     )?;
+    StorageLagEvaluator::add_from_evaluator_args(
+        &mut evaluators,
+        &mut evaluator_identifiers,
+        evaluator_args,
+    )?;
     TpsEvaluator::add_from_evaluator_args(
         &mut evaluators,
         &mut evaluator_identifiers,
@@ -0,0 +1,446 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::DirectEvaluatorInput;
+use super::ApiEvaluatorError;
+use crate::{
+    configuration::{EvaluatorArgs, NodeAddress},
+    evaluator::{EvaluationResult, Evaluator},
+    evaluators::EvaluatorType,
+};
+use anyhow::{format_err, Result};
+use aptos_config::config::RoleType;
+use aptos_crypto::x25519;
+use aptos_sdk::{
+    move_types::language_storage::CORE_CODE_ADDRESS,
+    types::{
+        account_config::NewBlockEvent, on_chain_config::ValidatorSet,
+        validator_info::ValidatorInfo, validator_performances::ValidatorPerformances,
+    },
+};
+use clap::Parser;
+use poem_openapi::Object as PoemObject;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const VALIDATOR_SET_RESOURCE: &str = "0x1::stake::ValidatorSet";
+const VALIDATOR_PERFORMANCE_RESOURCE: &str = "0x1::stake::ValidatorPerformance";
+const NEW_BLOCK_EVENTS_ENDPOINT: &str =
+    "/accounts/0x1/events/0x1::block::BlockResource/new_block_events";
+
+#[derive(Clone, Debug, Deserialize, Parser, PoemObject, Serialize)]
+pub struct ConsensusParticipationEvaluatorArgs {
+    /// How many of the latest blocks to look at for the votes of the target validator.
+    #[clap(long, default_value_t = 100)]
+    pub participation_num_blocks: u16,
+
+    /// The minimum percentage of those blocks the target validator must have voted for.
+    #[clap(long, default_value_t = 70)]
+    pub min_vote_participation_percent: u64,
+
+    /// The maximum percentage of the proposals of the target validator in the
+    /// current epoch that may have failed.
+    #[clap(long, default_value_t = 20)]
+    pub max_failed_proposals_percent: u64,
+
+    #[clap(long, default_value_t = 4)]
+    pub participation_api_call_timeout_secs: u64,
+}
+
+/// The participation of a validator in consensus, derived from on-chain data.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Participation {
+    /// The recent blocks of the current epoch, and how many of them carry the
+    /// vote of the validator for the block before them.
+    blocks: u64,
+    votes: u64,
+    /// The proposals of the validator in the current epoch.
+    successful_proposals: u64,
+    failed_proposals: u64,
+}
+
+impl Participation {
+    /// Counts the votes of the validator at `validator_index` in the blocks of
+    /// the latest epoch among `events`.
+    fn count_votes(&mut self, events: &[NewBlockEvent], validator_index: u64) {
+        let epoch = match events.iter().map(|event| event.epoch()).max() {
+            Some(epoch) => epoch,
+            None => return,
+        };
+        for event in events.iter().filter(|event| event.epoch() == epoch) {
+            // The bitvec has the most significant bit of the first byte for the
+            // validator at index 0, as in aptos-bitvec.
+            let votes = event.previous_block_votes_bitvec();
+            let voted = votes
+                .get(validator_index as usize / 8)
+                .map_or(false, |byte| {
+                    byte & (0b1000_0000 >> (validator_index % 8)) != 0
+                });
+            self.blocks += 1;
+            if voted {
+                self.votes += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConsensusParticipationEvaluator {
+    args: ConsensusParticipationEvaluatorArgs,
+}
+
+impl ConsensusParticipationEvaluator {
+    pub fn new(args: ConsensusParticipationEvaluatorArgs) -> Self {
+        Self { args }
+    }
+
+    /// Finds the validator whose validator network addresses use the given
+    /// public key.
+    fn find_validator(
+        validator_set: &ValidatorSet,
+        public_key: x25519::PublicKey,
+    ) -> Option<&ValidatorInfo> {
+        validator_set.payload().find(|info| {
+            info.config()
+                .validator_network_addresses()
+                .unwrap_or_default()
+                .iter()
+                .any(|address| address.find_noise_proto() == Some(public_key))
+        })
+    }
+
+    /// Reads the validator set, the proposals and the recent blocks from the
+    /// baseline node, which we trust to be in sync with the network.
+    async fn get_participation(
+        &self,
+        baseline_node_address: &NodeAddress,
+        public_key: x25519::PublicKey,
+    ) -> Result<Result<Participation, EvaluationResult>, ApiEvaluatorError> {
+        let client = baseline_node_address.get_api_client(Duration::from_secs(
+            self.args.participation_api_call_timeout_secs,
+        ));
+        let resource_error = |resource: &str, e: anyhow::Error| {
+            ApiEvaluatorError::EndpointError(
+                format!("/accounts/{}/resource/{}", CORE_CODE_ADDRESS, resource),
+                e,
+            )
+        };
+
+        let validator_set = client
+            .get_account_resource_bcs::<ValidatorSet>(CORE_CODE_ADDRESS, VALIDATOR_SET_RESOURCE)
+            .await
+            .map_err(|e| resource_error(VALIDATOR_SET_RESOURCE, e.into()))?
+            .into_inner();
+        let validator_index = match Self::find_validator(&validator_set, public_key) {
+            Some(info) => info.config().validator_index,
+            None => {
+                return Ok(Err(self.build_evaluation_result(
+                    "Validator is not in the validator set".to_string(),
+                    0,
+                    format!(
+                        "None of the validators of the current epoch have a validator \
+                        network address with the public key {}. Make sure you provided \
+                        the public key of the validator network of your node. If you did, \
+                        your validator did not join the validator set: make sure your \
+                        stake pool has enough stake and that you joined the validator set, \
+                        the change takes effect at the start of the next epoch.",
+                        public_key
+                    ),
+                )));
+            }
+        };
+
+        let performances = client
+            .get_account_resource_bcs::<ValidatorPerformances>(
+                CORE_CODE_ADDRESS,
+                VALIDATOR_PERFORMANCE_RESOURCE,
+            )
+            .await
+            .map_err(|e| resource_error(VALIDATOR_PERFORMANCE_RESOURCE, e.into()))?
+            .into_inner();
+        let performance = performances
+            .validators
+            .get(validator_index as usize)
+            .ok_or_else(|| {
+                resource_error(
+                    VALIDATOR_PERFORMANCE_RESOURCE,
+                    format_err!("No performance for validator index {}", validator_index),
+                )
+            })?;
+
+        // The sequence number of a new block event is the height of its block.
+        let latest_block_height = client
+            .get_index()
+            .await
+            .map_err(|e| ApiEvaluatorError::EndpointError("/".to_string(), e.into()))?
+            .into_inner()
+            .block_height
+            .0;
+        let num_blocks = self.args.participation_num_blocks.max(1);
+        let events = client
+            .get_new_block_events_bcs(
+                Some(latest_block_height.saturating_sub(num_blocks as u64 - 1)),
+                Some(num_blocks),
+            )
+            .await
+            .map_err(|e| {
+                ApiEvaluatorError::EndpointError(NEW_BLOCK_EVENTS_ENDPOINT.to_string(), e)
+            })?
+            .into_inner()
+            .into_iter()
+            .map(|versioned| versioned.event)
+            .collect::<Vec<_>>();
+
+        let mut participation = Participation {
+            successful_proposals: performance.successful_proposals,
+            failed_proposals: performance.failed_proposals,
+            ..Participation::default()
+        };
+        participation.count_votes(&events, validator_index);
+        Ok(Ok(participation))
+    }
+
+    fn build_vote_evaluation(&self, participation: &Participation) -> EvaluationResult {
+        if participation.blocks == 0 {
+            return self.build_evaluation_result(
+                "No recent blocks to check votes against".to_string(),
+                100,
+                "The baseline node returned no recent blocks, so the votes of your \
+                validator could not be checked."
+                    .to_string(),
+            );
+        }
+        let vote_percent = participation.votes * 100 / participation.blocks;
+        if vote_percent < self.args.min_vote_participation_percent {
+            self.build_evaluation_result(
+                "Validator is missing votes".to_string(),
+                if participation.votes == 0 { 0 } else { 50 },
+                format!(
+                    "Your validator voted for {} of the latest {} blocks of the current \
+                    epoch ({}%), fewer than the required {}%. To fix this, make sure your \
+                    validator is in sync with the network, that the other validators can \
+                    reach it on its validator network address, that its clock is synced \
+                    (e.g. with NTP), and that the consensus key of the node matches the \
+                    consensus public key of your validator on chain.",
+                    participation.votes,
+                    participation.blocks,
+                    vote_percent,
+                    self.args.min_vote_participation_percent,
+                ),
+            )
+        } else {
+            self.build_evaluation_result(
+                "Validator is voting".to_string(),
+                100,
+                format!(
+                    "Your validator voted for {} of the latest {} blocks of the current \
+                    epoch ({}%), above the required {}%.",
+                    participation.votes,
+                    participation.blocks,
+                    vote_percent,
+                    self.args.min_vote_participation_percent,
+                ),
+            )
+        }
+    }
+
+    fn build_proposal_evaluation(&self, participation: &Participation) -> EvaluationResult {
+        let proposals = participation.successful_proposals + participation.failed_proposals;
+        if proposals == 0 {
+            return self.build_evaluation_result(
+                "Validator has not proposed yet".to_string(),
+                100,
+                "Your validator has not been elected to propose a block in the current \
+                epoch yet, which is expected early in an epoch or with a small stake. \
+                Check again later."
+                    .to_string(),
+            );
+        }
+        let failed_percent = participation.failed_proposals * 100 / proposals;
+        if failed_percent > self.args.max_failed_proposals_percent {
+            self.build_evaluation_result(
+                "Validator proposals are failing".to_string(),
+                if participation.successful_proposals == 0 {
+                    0
+                } else {
+                    50
+                },
+                format!(
+                    "{} of the {} proposals of your validator in the current epoch failed \
+                    ({}%), more than the allowed {}%. To fix this, make sure your validator \
+                    is not short on CPU, that the other validators can reach it with low \
+                    latency, and that its mempool receives transactions from its fullnode.",
+                    participation.failed_proposals,
+                    proposals,
+                    failed_percent,
+                    self.args.max_failed_proposals_percent,
+                ),
+            )
+        } else {
+            self.build_evaluation_result(
+                "Validator is proposing".to_string(),
+                100,
+                format!(
+                    "{} of the {} proposals of your validator in the current epoch \
+                    succeeded, the failed ones are within the allowed {}%.",
+                    participation.successful_proposals,
+                    proposals,
+                    self.args.max_failed_proposals_percent,
+                ),
+            )
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Evaluator for ConsensusParticipationEvaluator {
+    type Input = DirectEvaluatorInput;
+    type Error = ApiEvaluatorError;
+
+    /// Assert that the target validator recently voted for enough blocks and
+    /// that few of its proposals in the current epoch failed.
+    async fn evaluate(&self, input: &Self::Input) -> Result<Vec<EvaluationResult>, Self::Error> {
+        if input.target_index_response.node_role != RoleType::Validator {
+            return Ok(vec![self.build_evaluation_result(
+                "Node is not a validator".to_string(),
+                0,
+                format!(
+                    "Consensus participation can only be checked for validators, but \
+                    your node reports the role {}. Make sure you are checking the API \
+                    of your validator, not of its fullnode.",
+                    input.target_index_response.node_role
+                ),
+            )]);
+        }
+
+        // This is checked by validate_check_node_call.
+        let public_key = input
+            .target_node_address
+            .get_public_key()
+            .expect("A public key is required for the consensus participation evaluator");
+        let participation = match self
+            .get_participation(&input.baseline_node_information.node_address, public_key)
+            .await?
+        {
+            Ok(participation) => participation,
+            Err(evaluation_result) => return Ok(vec![evaluation_result]),
+        };
+
+        Ok(vec![
+            self.build_vote_evaluation(&participation),
+            self.build_proposal_evaluation(&participation),
+        ])
+    }
+
+    fn get_category_name() -> String {
+        "consensus".to_string()
+    }
+
+    fn get_evaluator_name() -> String {
+        "participation".to_string()
+    }
+
+    fn validate_check_node_call(&self, target_node_address: &NodeAddress) -> anyhow::Result<()> {
+        if target_node_address.get_public_key().is_none() {
+            return Err(format_err!(
+                "A public key must be provided to use the consensus participation evaluator"
+            ));
+        }
+        Ok(())
+    }
+
+    fn from_evaluator_args(evaluator_args: &EvaluatorArgs) -> Result<Self> {
+        Ok(Self::new(
+            evaluator_args.consensus_participation_args.clone(),
+        ))
+    }
+
+    fn evaluator_type_from_evaluator_args(evaluator_args: &EvaluatorArgs) -> Result<EvaluatorType> {
+        Ok(EvaluatorType::Api(Box::new(Self::from_evaluator_args(
+            evaluator_args,
+        )?)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aptos_sdk::types::account_address::AccountAddress;
+
+    fn get_default_evaluator() -> ConsensusParticipationEvaluator {
+        ConsensusParticipationEvaluator::new(ConsensusParticipationEvaluatorArgs {
+            participation_num_blocks: 100,
+            min_vote_participation_percent: 70,
+            max_failed_proposals_percent: 20,
+            participation_api_call_timeout_secs: 4,
+        })
+    }
+
+    fn new_block_event(epoch: u64, votes: Vec<u8>) -> NewBlockEvent {
+        NewBlockEvent::new(
+            AccountAddress::ZERO,
+            epoch,
+            0,
+            0,
+            votes,
+            AccountAddress::ZERO,
+            vec![],
+            0,
+        )
+    }
+
+    #[test]
+    fn test_count_votes() {
+        let events = vec![
+            // From the previous epoch, ignored.
+            new_block_event(1, vec![0b0000_0000, 0b0000_0000]),
+            new_block_event(2, vec![0b0000_0000, 0b0100_0000]),
+            new_block_event(2, vec![0b1111_1111, 0b0100_0000]),
+            new_block_event(2, vec![0b1111_1111]),
+        ];
+        let mut participation = Participation::default();
+        participation.count_votes(&events, 9);
+        assert_eq!(participation.blocks, 3);
+        assert_eq!(participation.votes, 2);
+
+        let mut participation = Participation::default();
+        participation.count_votes(&events, 0);
+        assert_eq!(participation.votes, 2);
+    }
+
+    #[test]
+    fn test_evaluations() {
+        let evaluator = get_default_evaluator();
+        let participation = Participation {
+            blocks: 100,
+            votes: 95,
+            successful_proposals: 9,
+            failed_proposals: 1,
+        };
+        assert_eq!(evaluator.build_vote_evaluation(&participation).score, 100);
+        assert_eq!(
+            evaluator.build_proposal_evaluation(&participation).score,
+            100
+        );
+
+        let participation = Participation {
+            blocks: 100,
+            votes: 0,
+            successful_proposals: 5,
+            failed_proposals: 5,
+        };
+        assert_eq!(evaluator.build_vote_evaluation(&participation).score, 0);
+        assert_eq!(
+            evaluator.build_proposal_evaluation(&participation).score,
+            50
+        );
+
+        // Nothing to evaluate yet.
+        let participation = Participation::default();
+        assert_eq!(evaluator.build_vote_evaluation(&participation).score, 100);
+        assert_eq!(
+            evaluator.build_proposal_evaluation(&participation).score,
+            100
+        );
+    }
+}
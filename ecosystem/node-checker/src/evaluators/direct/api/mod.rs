@@ -1,17 +1,23 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+mod consensus_participation;
 mod latency;
 mod node_identity;
 mod state_sync;
+mod storage_lag;
 mod transaction_availability;
 
 use anyhow::Error;
+pub use consensus_participation::{
+    ConsensusParticipationEvaluator, ConsensusParticipationEvaluatorArgs,
+};
 pub use latency::{LatencyEvaluator, LatencyEvaluatorArgs};
 pub use node_identity::{
     get_node_identity, NodeIdentityEvaluator, NodeIdentityEvaluatorArgs, NodeIdentityEvaluatorError,
 };
 pub use state_sync::{StateSyncVersionEvaluator, StateSyncVersionEvaluatorArgs};
+pub use storage_lag::{StorageLagEvaluator, StorageLagEvaluatorArgs};
 use thiserror::Error as ThisError;
 pub use transaction_availability::{
     TransactionAvailabilityEvaluator, TransactionAvailabilityEvaluatorArgs,
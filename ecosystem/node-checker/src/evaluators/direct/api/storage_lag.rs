@@ -0,0 +1,176 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::DirectEvaluatorInput;
+use super::ApiEvaluatorError;
+use crate::{
+    configuration::EvaluatorArgs,
+    evaluator::{EvaluationResult, Evaluator},
+    evaluators::EvaluatorType,
+};
+use anyhow::Result;
+use clap::Parser;
+use poem_openapi::Object as PoemObject;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Deserialize, Parser, PoemObject, Serialize)]
+pub struct StorageLagEvaluatorArgs {
+    /// How many versions the synced version of the target node may be behind the baseline.
+    #[clap(long, default_value_t = 10000)]
+    pub storage_lag_version_tolerance: u64,
+
+    /// How many seconds the timestamp of the latest block of the target node may be
+    /// behind the baseline.
+    #[clap(long, default_value_t = 30)]
+    pub storage_lag_timestamp_tolerance_secs: u64,
+
+    #[clap(long, default_value_t = 4)]
+    pub storage_lag_api_call_timeout_secs: u64,
+}
+
+#[derive(Debug)]
+pub struct StorageLagEvaluator {
+    args: StorageLagEvaluatorArgs,
+}
+
+impl StorageLagEvaluator {
+    pub fn new(args: StorageLagEvaluatorArgs) -> Self {
+        Self { args }
+    }
+
+    fn build_version_lag_evaluation(
+        &self,
+        target_version: u64,
+        baseline_version: u64,
+    ) -> EvaluationResult {
+        // The target being ahead of the baseline is not a problem.
+        let version_lag = baseline_version.saturating_sub(target_version);
+        if version_lag > self.args.storage_lag_version_tolerance {
+            self.build_evaluation_result(
+                "Synced version is lagging".to_string(),
+                50,
+                format!(
+                    "The synced version of your node ({}) is {} versions behind the \
+                    baseline node ({}), more than the allowed lag of {} versions. \
+                    To fix this, make sure your node is connected to enough peers, \
+                    that its disk can keep up with the write load of the network \
+                    (we recommend NVMe SSDs) and that it is not short on CPU. If your \
+                    node was restarted recently, let it catch up and check it again.",
+                    target_version,
+                    version_lag,
+                    baseline_version,
+                    self.args.storage_lag_version_tolerance,
+                ),
+            )
+        } else {
+            self.build_evaluation_result(
+                "Synced version is within tolerance".to_string(),
+                100,
+                format!(
+                    "The synced version of your node ({}) is within the allowed lag \
+                    of {} versions of the baseline node ({}).",
+                    target_version, self.args.storage_lag_version_tolerance, baseline_version,
+                ),
+            )
+        }
+    }
+
+    fn build_timestamp_lag_evaluation(
+        &self,
+        target_timestamp_usecs: u64,
+        baseline_timestamp_usecs: u64,
+    ) -> EvaluationResult {
+        let timestamp_lag =
+            Duration::from_micros(baseline_timestamp_usecs.saturating_sub(target_timestamp_usecs));
+        if timestamp_lag.as_secs() > self.args.storage_lag_timestamp_tolerance_secs {
+            self.build_evaluation_result(
+                "Latest block is too old".to_string(),
+                50,
+                format!(
+                    "The latest block of your node is {} seconds older than the latest \
+                    block of the baseline node, more than the allowed lag of {} seconds. \
+                    This usually means state sync is stalled or falling behind. To fix \
+                    this, look for state sync errors in the logs of your node, make sure \
+                    its upstream peers are reachable and that it runs the same release \
+                    as the rest of the network.",
+                    timestamp_lag.as_secs(),
+                    self.args.storage_lag_timestamp_tolerance_secs,
+                ),
+            )
+        } else {
+            self.build_evaluation_result(
+                "Latest block is recent".to_string(),
+                100,
+                format!(
+                    "The latest block of your node is {} seconds older than the latest \
+                    block of the baseline node, which is within the allowed lag of {} seconds.",
+                    timestamp_lag.as_secs(),
+                    self.args.storage_lag_timestamp_tolerance_secs,
+                ),
+            )
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Evaluator for StorageLagEvaluator {
+    type Input = DirectEvaluatorInput;
+    type Error = ApiEvaluatorError;
+
+    /// Assert that the synced version and the timestamp of the latest block
+    /// of the target node are within tolerance of the baseline node.
+    async fn evaluate(&self, input: &Self::Input) -> Result<Vec<EvaluationResult>, Self::Error> {
+        let api_call_timeout = Duration::from_secs(self.args.storage_lag_api_call_timeout_secs);
+
+        // Fetch the ledger of both nodes at the same time, so the lag isn't
+        // skewed by the time between the calls.
+        let (target_response, baseline_response) = futures::join!(
+            input
+                .target_node_address
+                .get_index_response_or_evaluation_result(api_call_timeout),
+            input
+                .baseline_node_information
+                .node_address
+                .get_index_response(api_call_timeout),
+        );
+
+        // As with the other evaluators, failing to reach the baseline is an
+        // error with NHC, failing to reach the target is a negative evaluation.
+        let baseline_response =
+            baseline_response.map_err(|e| ApiEvaluatorError::EndpointError("/".to_string(), e))?;
+        let target_response = match target_response {
+            Ok(response) => response,
+            Err(evaluation_result) => return Ok(vec![evaluation_result]),
+        };
+
+        Ok(vec![
+            self.build_version_lag_evaluation(
+                target_response.ledger_version.0,
+                baseline_response.ledger_version.0,
+            ),
+            self.build_timestamp_lag_evaluation(
+                target_response.ledger_timestamp.0,
+                baseline_response.ledger_timestamp.0,
+            ),
+        ])
+    }
+
+    fn get_category_name() -> String {
+        "state_sync".to_string()
+    }
+
+    fn get_evaluator_name() -> String {
+        "storage_lag".to_string()
+    }
+
+    fn from_evaluator_args(evaluator_args: &EvaluatorArgs) -> Result<Self> {
+        Ok(Self::new(evaluator_args.storage_lag_args.clone()))
+    }
+
+    fn evaluator_type_from_evaluator_args(evaluator_args: &EvaluatorArgs) -> Result<EvaluatorType> {
+        Ok(EvaluatorType::Api(Box::new(Self::from_evaluator_args(
+            evaluator_args,
+        )?)))
+    }
+}
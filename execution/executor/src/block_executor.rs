@@ -5,10 +5,11 @@
 
 use crate::logging::{LogEntry, LogSchema};
 use anyhow::Result;
-use aptos_crypto::HashValue;
+use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_infallible::RwLock;
 use aptos_logger::prelude::*;
 use aptos_state_view::StateViewId;
+use aptos_tracing::Stage;
 use aptos_types::{
     ledger_info::LedgerInfoWithSignatures, state_store::state_value::StateValue,
     transaction::Transaction,
@@ -17,7 +18,7 @@ use aptos_vm::VMExecutor;
 use executor_types::{BlockExecutorTrait, Error, StateComputeResult};
 use fail::fail_point;
 use scratchpad::SparseMerkleTree;
-use std::{marker::PhantomData, sync::Arc};
+use std::{marker::PhantomData, sync::Arc, time::SystemTime};
 use storage_interface::async_proof_fetcher::AsyncProofFetcher;
 
 use crate::{
@@ -192,6 +193,10 @@ where
                 Arc::new(AsyncProofFetcher::new(self.db.reader.clone())),
             )?;
 
+            let traced_txns = aptos_tracing::is_tracing().then(|| {
+                let hashes: Vec<_> = transactions.iter().map(CryptoHash::hash).collect();
+                (hashes, SystemTime::now())
+            });
             let chunk_output = {
                 let _timer = APTOS_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS.start_timer();
                 fail_point!("executor::vm_execute_block", |_| {
@@ -202,6 +207,18 @@ where
                 ChunkOutput::by_transaction_execution::<V>(transactions, state_view)?
             };
             chunk_output.trace_log_transaction_status();
+            if let Some((hashes, start)) = traced_txns {
+                let end = SystemTime::now();
+                for txn_hash in &hashes {
+                    aptos_tracing::record_span(
+                        txn_hash,
+                        Stage::Execution,
+                        start,
+                        end,
+                        vec![("aptos.block_id", block_id.to_hex())],
+                    );
+                }
+            }
 
             let (output, _, _) = chunk_output.apply_to_ledger(parent_view)?;
             output
@@ -221,6 +238,7 @@ where
         sync_commit: bool,
     ) -> Result<(), Error> {
         let _timer = APTOS_EXECUTOR_COMMIT_BLOCKS_SECONDS.start_timer();
+        let commit_start = SystemTime::now();
 
         // Ensure the block ids are not empty
         if block_ids.is_empty() {
@@ -294,6 +312,28 @@ where
             sync_commit,
             result_in_memory_state,
         )?;
+        if aptos_tracing::is_tracing() {
+            let end = SystemTime::now();
+            for (version, txn) in (first_version..).zip(&txns_to_commit) {
+                let txn_info = txn.transaction_info();
+                let status = txn_info.status();
+                aptos_tracing::record_span(
+                    &txn_info.transaction_hash(),
+                    Stage::Commit,
+                    commit_start,
+                    end,
+                    vec![
+                        ("aptos.version", version.to_string()),
+                        ("aptos.execution_status", format!("{:?}", status)),
+                    ],
+                );
+                aptos_tracing::end_trace(
+                    &txn_info.transaction_hash(),
+                    end,
+                    (!status.is_success()).then(|| format!("{:?}", status)),
+                );
+            }
+        }
         self.block_tree
             .prune(ledger_info_with_sigs.ledger_info())
             .expect("Failure pruning block tree.");
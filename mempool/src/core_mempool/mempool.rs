@@ -14,7 +14,7 @@ use crate::{
     counters,
     logging::{LogEntry, LogSchema, TxnsLog},
 };
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{MempoolConfig, NodeConfig};
 use aptos_crypto::HashValue;
 use aptos_logger::prelude::*;
 use aptos_types::{
@@ -264,6 +264,21 @@ impl Mempool {
         self.transactions.gc_by_system_ttl(now);
     }
 
+    /// Removes all the transactions, on request of an operator.
+    pub(crate) fn flush(&mut self) -> usize {
+        self.transactions.flush()
+    }
+
+    /// Applies the capacities of a reloaded config.
+    pub(crate) fn update_capacity(&mut self, config: &MempoolConfig) {
+        self.transactions.update_capacity(config);
+    }
+
+    /// Returns all the transactions, ready or not, e.g. to persist them before a shutdown.
+    pub(crate) fn get_all_transactions(&self) -> Vec<SignedTransaction> {
+        self.transactions.get_all_transactions()
+    }
+
     /// Garbage collection based on client-specified expiration time.
     pub(crate) fn gc_by_expiration_time(&mut self, block_time: Duration) {
         self.transactions.gc_by_expiration_time(block_time);
@@ -638,6 +638,32 @@ impl TransactionStore {
         self.gc(block_time, false);
     }
 
+    /// Applies the capacities of a reloaded config. A lower capacity doesn't evict any
+    /// transaction, it only rejects the new ones until mempool is back under it.
+    pub(crate) fn update_capacity(&mut self, config: &MempoolConfig) {
+        self.capacity = config.capacity;
+        self.capacity_bytes = config.capacity_bytes;
+        self.capacity_per_user = config.capacity_per_user;
+    }
+
+    /// Returns all the transactions, by account and sequence number.
+    pub(crate) fn get_all_transactions(&self) -> Vec<SignedTransaction> {
+        let mut accounts: Vec<_> = self.transactions.iter().collect();
+        accounts.sort_by_key(|(address, _)| **address);
+        accounts
+            .into_iter()
+            .flat_map(|(_, txns)| txns.values().map(|txn| txn.txn.clone()))
+            .collect()
+    }
+
+    /// Removes all the transactions, returning how many there were. Every transaction is in the
+    /// system TTL index, so they're all garbage collected past the end of time.
+    pub(crate) fn flush(&mut self) -> usize {
+        let num_txns = self.system_ttl_index.size();
+        self.gc(Duration::MAX, true);
+        num_txns
+    }
+
     fn gc(&mut self, now: Duration, by_system_ttl: bool) {
         let (metric_label, index, log_event) = if by_system_ttl {
             (
@@ -67,8 +67,13 @@ pub(crate) async fn coordinator<V>(
     let workers_available = smp.config.shared_mempool_max_concurrent_inbound_syncs;
     let bounded_executor = BoundedExecutor::new(workers_available, executor.clone());
 
+    // Beats even when there's nothing to do, for the liveness probe
+    let mut heartbeat_interval =
+        IntervalStream::new(interval(aptos_liveness::HEARTBEAT_INTERVAL)).fuse();
+
     loop {
         let _timer = counters::MAIN_LOOP.start_timer();
+        aptos_liveness::heartbeat("mempool");
         ::futures::select! {
             msg = client_events.select_next_some() => {
                 handle_client_request(&mut smp, &bounded_executor, msg).await;
@@ -88,6 +93,7 @@ pub(crate) async fn coordinator<V>(
             (network_id, event) = events.select_next_some() => {
                 handle_network_event(&executor, &bounded_executor, &mut scheduled_broadcasts, &mut smp, network_id, event).await;
             },
+            _ = heartbeat_interval.select_next_some() => {},
             complete => break,
         }
     }
@@ -148,6 +154,28 @@ async fn handle_client_request<V>(
                 ))
                 .await;
         }
+        MempoolClientRequest::FlushTransactions(callback) => {
+            let num_txns = smp.mempool.lock().flush();
+            info!(num_txns = num_txns, "Flushed mempool");
+            if callback.send(num_txns).is_err() {
+                counters::CLIENT_CALLBACK_FAIL.inc();
+            }
+        }
+        MempoolClientRequest::GetAllTransactions(callback) => {
+            let txns = smp.mempool.lock().get_all_transactions();
+            if callback.send(txns).is_err() {
+                counters::CLIENT_CALLBACK_FAIL.inc();
+            }
+        }
+        MempoolClientRequest::UpdateCapacity(config) => {
+            smp.mempool.lock().update_capacity(&config);
+            info!(
+                capacity = config.capacity,
+                capacity_bytes = config.capacity_bytes,
+                capacity_per_user = config.capacity_per_user,
+                "Updated the capacity of mempool"
+            );
+        }
     }
 }
 
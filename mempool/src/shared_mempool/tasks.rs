@@ -20,6 +20,7 @@ use aptos_crypto::HashValue;
 use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
 use aptos_metrics_core::HistogramTimer;
+use aptos_tracing::Stage;
 use aptos_types::{
     mempool_status::{MempoolStatus, MempoolStatusCode},
     on_chain_config::OnChainConfigPayload,
@@ -34,7 +35,7 @@ use std::{
     cmp,
     collections::HashSet,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use storage_interface::state_view::LatestDbStateCheckpointView;
 use tokio::runtime::Handle;
@@ -164,10 +165,19 @@ pub(crate) async fn process_transaction_broadcast<V>(
 {
     timer.stop_and_record();
     let _timer = counters::process_txn_submit_latency_timer(peer.network_id());
-    let results = process_incoming_transactions(&smp, transactions, timeline_state);
-    log_txn_process_results(&results, Some(peer));
-
-    let ack_response = gen_ack_response(request_id, results, &peer);
+    // A draining node doesn't take new transactions, so the peer backs off and retries them
+    let ack_response = if aptos_drain::is_draining() {
+        update_ack_counter(&peer, counters::SENT_LABEL, true, true);
+        MempoolSyncMsg::BroadcastTransactionsResponse {
+            request_id,
+            retry: true,
+            backoff: true,
+        }
+    } else {
+        let results = process_incoming_transactions(&smp, transactions, timeline_state);
+        log_txn_process_results(&results, Some(peer));
+        gen_ack_response(request_id, results, &peer)
+    };
     let network_sender = smp.network_interface.sender();
 
     // Respond to the peer with an ack. Note: ack response messages should be
@@ -244,6 +254,7 @@ pub(crate) fn process_incoming_transactions<V>(
 where
     V: TransactionValidation,
 {
+    let start = SystemTime::now();
     let mut statuses = vec![];
 
     let start_storage_read = Instant::now();
@@ -347,6 +358,19 @@ where
         }
     }
     notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
+
+    if aptos_tracing::is_tracing() {
+        let end = SystemTime::now();
+        for (transaction, (mempool_status, _)) in &statuses {
+            aptos_tracing::record_span(
+                &transaction.clone().committed_hash(),
+                Stage::MempoolAdmission,
+                start,
+                end,
+                vec![("aptos.mempool_status", mempool_status.code.to_string())],
+            );
+        }
+    }
     statuses
 }
 
@@ -394,6 +418,7 @@ pub(crate) fn process_quorum_store_request<V: TransactionValidation>(
 ) {
     // Start latency timer
     let start_time = Instant::now();
+    let start = SystemTime::now();
     debug!(LogSchema::event_log(LogEntry::QuorumStore, LogEvent::Received).quorum_store_msg(&req));
 
     let (resp, callback, counter_label) = match req {
@@ -432,6 +457,19 @@ pub(crate) fn process_quorum_store_request<V: TransactionValidation>(
 
             // mempool_service_transactions is logged inside get_batch
 
+            if aptos_tracing::is_tracing() {
+                let end = SystemTime::now();
+                for txn in &txns {
+                    aptos_tracing::record_span(
+                        &txn.clone().committed_hash(),
+                        Stage::ConsensusInclusion,
+                        start,
+                        end,
+                        vec![],
+                    );
+                }
+            }
+
             (
                 QuorumStoreResponse::GetBatchResponse(txns),
                 callback,
@@ -208,6 +208,12 @@ pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 pub enum MempoolClientRequest {
     SubmitTransaction(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>),
     GetTransactionByHash(HashValue, oneshot::Sender<Option<SignedTransaction>>),
+    /// Removes all the transactions from mempool, replying with how many there were
+    FlushTransactions(oneshot::Sender<usize>),
+    /// Replies with all the transactions in mempool, by account and sequence number
+    GetAllTransactions(oneshot::Sender<Vec<SignedTransaction>>),
+    /// Applies the capacities of a reloaded config
+    UpdateCapacity(MempoolConfig),
 }
 
 pub type MempoolClientSender = mpsc::Sender<MempoolClientRequest>;
@@ -485,6 +485,25 @@ fn test_capacity() {
     assert!(add_txn(&mut pool, TestTransaction::new(1, 2, 1)).is_ok());
 }
 
+#[test]
+fn test_update_capacity() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 1;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 1, 1)).is_err());
+
+    config.mempool.capacity = 2;
+    pool.update_capacity(&config.mempool);
+    add_txn(&mut pool, TestTransaction::new(1, 1, 1)).unwrap();
+
+    // A lower capacity keeps the transactions in mempool, and rejects the new ones
+    config.mempool.capacity = 1;
+    pool.update_capacity(&config.mempool);
+    assert_eq!(pool.get_batch(10, 1024, HashSet::new()).len(), 2);
+    assert!(add_txn(&mut pool, TestTransaction::new(2, 0, 1)).is_err());
+}
+
 #[test]
 fn test_capacity_bytes() {
     let capacity_bytes = 2_048;
@@ -658,6 +677,55 @@ fn test_gc_ready_transaction() {
     assert_eq!(timeline.len(), 4);
 }
 
+#[test]
+fn test_get_all_transactions() {
+    let mut pool = setup_mempool().0;
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    // Parked transactions are included too
+    add_txn(&mut pool, TestTransaction::new(0, 3, 1)).unwrap();
+
+    let txns: Vec<_> = pool
+        .get_all_transactions()
+        .iter()
+        .map(|txn| (txn.sender(), txn.sequence_number()))
+        .collect();
+    let (account_0, account_1) = (
+        TestTransaction::get_address(0),
+        TestTransaction::get_address(1),
+    );
+    let mut expected = vec![
+        (account_0, 0),
+        (account_0, 1),
+        (account_0, 3),
+        (account_1, 0),
+    ];
+    expected.sort();
+    assert_eq!(txns, expected);
+}
+
+#[test]
+fn test_flush() {
+    let mut pool = setup_mempool().0;
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+    // Parked, since sequence number 2 is missing
+    add_txn(&mut pool, TestTransaction::new(0, 3, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    assert_eq!(1, pool.get_parking_lot_size());
+
+    assert_eq!(pool.flush(), 4);
+    assert_eq!(0, pool.get_parking_lot_size());
+    assert!(pool.get_batch(10, 1024, HashSet::new()).is_empty());
+    let (timeline, _) = pool.read_timeline(&vec![0].into(), 10);
+    assert!(timeline.is_empty());
+
+    // The transactions can be submitted again
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert_eq!(pool.get_batch(10, 1024, HashSet::new()).len(), 1);
+}
+
 #[test]
 fn test_clean_stuck_transactions() {
     let mut pool = setup_mempool().0;
@@ -7,14 +7,20 @@ use crate::{
 };
 use aptos_config::{config::NodeConfig, network_id::NetworkId};
 use aptos_infallible::{Mutex, RwLock};
-use aptos_types::transaction::SignedTransaction;
+use aptos_types::{
+    account_config::AccountSequenceInfo, mempool_status::MempoolStatusCode,
+    transaction::SignedTransaction,
+};
 use network::application::storage::PeerMetadataStorage;
 use proptest::{
     arbitrary::any,
     prelude::*,
     strategy::{Just, Strategy},
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
 use storage_interface::mock::MockDbReaderWriter;
 use vm_validator::mocks::mock_vm_validator::MockVMValidator;
 
@@ -50,6 +56,48 @@ pub fn test_mempool_process_incoming_transactions_impl(
     let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state);
 }
 
+/// Adds transactions accepted by the VM validator to a core mempool, with the sequence numbers of
+/// their senders in storage. Checks that the old transactions are rejected, and that the batches
+/// for consensus only have accepted transactions, in order and without gaps from the sequence
+/// numbers in storage.
+pub fn test_core_mempool_add_validated_transactions_impl(txns: Vec<(SignedTransaction, u64)>) {
+    let mut mempool = CoreMempool::new(&NodeConfig::default());
+    let mut db_sequence_numbers = HashMap::new();
+    let mut accepted = HashSet::new();
+    for (txn, db_sequence_number) in txns {
+        let key = (txn.sender(), txn.sequence_number());
+        let ranking_score = txn.gas_unit_price();
+        let status = mempool.add_txn(
+            txn,
+            ranking_score,
+            AccountSequenceInfo::Sequential(db_sequence_number),
+            TimelineState::NotReady,
+        );
+        if key.1 < db_sequence_number {
+            assert_eq!(status.code, MempoolStatusCode::InvalidSeqNumber);
+        } else if status.code == MempoolStatusCode::Accepted {
+            db_sequence_numbers.insert(key.0, db_sequence_number);
+            accepted.insert(key);
+        }
+    }
+
+    let mut batch_sequence_numbers = BTreeMap::<_, Vec<_>>::new();
+    for txn in mempool.get_batch(u64::MAX, u64::MAX, HashSet::new()) {
+        assert!(accepted.contains(&(txn.sender(), txn.sequence_number())));
+        batch_sequence_numbers
+            .entry(txn.sender())
+            .or_default()
+            .push(txn.sequence_number());
+    }
+    for (sender, mut sequence_numbers) in batch_sequence_numbers {
+        sequence_numbers.sort_unstable();
+        let db_sequence_number = db_sequence_numbers[&sender];
+        for (sequence_number, expected) in sequence_numbers.into_iter().zip(db_sequence_number..) {
+            assert_eq!(sequence_number, expected);
+        }
+    }
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
 
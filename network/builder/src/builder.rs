@@ -474,7 +474,7 @@ impl NetworkBuilder {
 }
 
 /// Retrieve and merge seeds so that they have all keys associated
-fn merge_seeds(config: &NetworkConfig) -> PeerSet {
+pub fn merge_seeds(config: &NetworkConfig) -> PeerSet {
     config.verify_seeds().expect("Seeds must be well formed");
     let mut seeds = config.seeds.clone();
 
@@ -0,0 +1,126 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CryptoStorage, Error, GetResponse, KVStorage, PublicKeyResponse};
+use aptos_audit::{audit, AuditEvent};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// This provides a light wrapper around storages to record the creation, export, import and
+/// rotation of keys in the audit log. Signing doesn't expose the keys and happens on every vote,
+/// so it isn't recorded, nor are the key value operations.
+pub struct Audited<S> {
+    inner: S,
+}
+
+impl<S> Audited<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+fn audit_key_access<T>(action: &str, name: &str, result: &Result<T, Error>) {
+    audit(
+        AuditEvent::new(format!("secure_storage.{}", action))
+            .detail("key", name)
+            .outcome(result),
+    );
+}
+
+impl<S: KVStorage> KVStorage for Audited<S> {
+    fn available(&self) -> Result<(), Error> {
+        self.inner.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.inner.get(key)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.inner.set(key, value)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for Audited<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let result = self.inner.create_key(name);
+        audit_key_access("create_key", name, &result);
+        result
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        let result = self.inner.export_private_key(name);
+        audit_key_access("export_private_key", name, &result);
+        result
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        let result = self.inner.import_private_key(name, key);
+        audit_key_access("import_private_key", name, &result);
+        result
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        let result = self
+            .inner
+            .export_private_key_for_version(name, version.clone());
+        audit(
+            AuditEvent::new("secure_storage.export_private_key_for_version")
+                .detail("key", name)
+                .detail("version", version)
+                .outcome(&result),
+        );
+        result
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.inner.get_public_key(name)
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.inner.get_public_key_previous_version(name)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let result = self.inner.rotate_key(name);
+        audit_key_access("rotate_key", name, &result);
+        result
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inner.sign(name, message)
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inner.sign_using_version(name, version, message)
+    }
+}
@@ -3,6 +3,7 @@
 
 #![forbid(unsafe_code)]
 
+mod audited;
 mod crypto_kv_storage;
 mod crypto_storage;
 mod error;
@@ -17,6 +18,7 @@ mod storage;
 mod vault;
 
 pub use crate::{
+    audited::Audited,
     crypto_kv_storage::CryptoKVStorage,
     crypto_storage::{CryptoStorage, PublicKeyResponse},
     error::Error,
@@ -45,6 +45,26 @@ pub static ERROR_RESPONSES: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter for tracking the data requests served by each peer
+pub static PEER_SERVED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_data_client_peer_served_requests",
+        "Counters related to the data requests served by each peer",
+        &["peer_id", "network"]
+    )
+    .unwrap()
+});
+
+/// Bytes allowed and throttled by the inbound bandwidth limiters per period
+pub static BANDWIDTH_LIMITER: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_data_client_bandwidth_limiter",
+        "Bytes allowed and throttled by the data client bandwidth limiters",
+        &["network", "metric"]
+    )
+    .unwrap()
+});
+
 /// Counter for tracking request latencies
 pub static REQUEST_LATENCIES: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
@@ -147,6 +167,16 @@ pub fn increment_request_counter(
         .inc();
 }
 
+/// Increments the served requests counter of the given peer.
+pub fn increment_peer_served_requests(peer_network_id: PeerNetworkId) {
+    PEER_SERVED_REQUESTS
+        .with_label_values(&[
+            peer_network_id.peer_id().short_str().as_str(),
+            peer_network_id.network_id().as_str(),
+        ])
+        .inc();
+}
+
 /// Sets the gauge with the specific label and value
 pub fn set_gauge(counter: &Lazy<IntGaugeVec>, label: &str, value: u64) {
     counter.with_label_values(&[label]).set(value as i64);
@@ -5,8 +5,8 @@ use crate::{
     aptosnet::{
         logging::{LogEntry, LogEvent, LogSchema},
         metrics::{
-            increment_request_counter, set_gauge, start_request_timer, DataType, PRIORITIZED_PEER,
-            REGULAR_PEER,
+            increment_peer_served_requests, increment_request_counter, set_gauge,
+            start_request_timer, DataType, PRIORITIZED_PEER, REGULAR_PEER,
         },
         state::{ErrorType, PeerStates},
     },
@@ -15,11 +15,12 @@ use crate::{
 };
 use aptos_config::{
     config::{AptosDataClientConfig, BaseConfig, StorageServiceConfig},
-    network_id::PeerNetworkId,
+    network_id::{NetworkId, PeerNetworkId},
 };
 use aptos_id_generator::{IdGenerator, U64IdGenerator};
-use aptos_infallible::RwLock;
+use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
+use aptos_rate_limiter::rate_limit::{Bucket, SharedBucket};
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{
     epoch_change::EpochChangeProof,
@@ -34,7 +35,7 @@ use network::{
     protocols::{rpc::error::RpcError, wire::handshake::v1::ProtocolId},
 };
 use rand::seq::SliceRandom;
-use std::{convert::TryFrom, fmt, sync::Arc, time::Duration};
+use std::{collections::HashMap, convert::TryFrom, fmt, sync::Arc, time::Duration};
 use storage_service_client::StorageServiceClient;
 use storage_service_types::requests::{
     DataRequest, EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
@@ -92,6 +93,10 @@ pub struct AptosNetDataClient {
     global_summary_cache: Arc<RwLock<GlobalDataSummary>>,
     /// Used for generating the next request/response id.
     response_id_generator: Arc<U64IdGenerator>,
+    /// Used for measuring the latency of peer responses.
+    time_service: TimeService,
+    /// The limiters for the bandwidth used by responses, by network.
+    bandwidth_limiters: Arc<HashMap<NetworkId, SharedBucket>>,
 }
 
 impl AptosNetDataClient {
@@ -113,6 +118,8 @@ impl AptosNetDataClient {
             ))),
             global_summary_cache: Arc::new(RwLock::new(GlobalDataSummary::empty())),
             response_id_generator: Arc::new(U64IdGenerator::new()),
+            time_service: time_service.clone(),
+            bandwidth_limiters: Arc::new(inbound_bandwidth_limiters(&data_client_config)),
         };
         let poller = DataSummaryPoller::new(
             client.clone(),
@@ -123,6 +130,15 @@ impl AptosNetDataClient {
         (client, poller)
     }
 
+    /// Applies the inbound bandwidth limits of a reloaded config
+    pub fn update_bandwidth_limits(&self, data_client_config: &AptosDataClientConfig) {
+        for (network_id, bandwidth_limiter) in self.bandwidth_limiters.iter() {
+            bandwidth_limiter
+                .lock()
+                .set_rate(inbound_bytes_per_sec(data_client_config, network_id));
+        }
+    }
+
     /// Returns true iff compression should be requested
     fn use_compression(&self) -> bool {
         self.data_client_config.use_compression
@@ -160,11 +176,15 @@ impl AptosNetDataClient {
             self.identify_serviceable(regular_peers, request)
         };
 
-        // Randomly select a peer to handle the request
+        // Randomly select a peer to handle the request, favouring peers
+        // with higher scores and lower latencies.
+        let peer_states = self.peer_states.read();
         serviceable_peers
-            .choose(&mut rand::thread_rng())
+            .choose_weighted(&mut rand::thread_rng(), |peer| {
+                peer_states.selection_weight(peer)
+            })
             .copied()
-            .ok_or_else(|| {
+            .map_err(|_| {
                 Error::DataIsUnavailable(
                     format!("No connected peers are advertising that they can serve this data! Request: {:?}",request),
                 )
@@ -373,6 +393,7 @@ impl AptosNetDataClient {
 
         increment_request_counter(&metrics::SENT_REQUESTS, &request.get_label(), peer);
 
+        let start_time = self.time_service.now();
         let result = self
             .network_client
             .send_request(
@@ -382,6 +403,10 @@ impl AptosNetDataClient {
             )
             .await;
 
+        // The latency of the peer only covers the request, not the time
+        // spent waiting for the bandwidth limiter below.
+        let latency = self.time_service.now().duration_since(start_time);
+
         match result {
             Ok(response) => {
                 trace!(
@@ -402,6 +427,20 @@ impl AptosNetDataClient {
                 // feels simpler for the consumer.
                 self.peer_states.write().update_score_success(peer);
 
+                // Wait for the bandwidth used by the response to be available,
+                // which throttles the requests sent on the peer's network.
+                self.wait_for_bandwidth(&peer, &response).await;
+
+                // Track the latency and contribution of the peer for data
+                // requests. Summary polls are small, so they say little
+                // about how quickly the peer can serve data.
+                if !request.data_request.is_storage_summary_request()
+                    && !request.data_request.is_protocol_version_request()
+                {
+                    self.peer_states.write().update_latency(peer, latency);
+                    increment_peer_served_requests(peer);
+                }
+
                 // Package up all of the context needed to fully report an error
                 // with this RPC.
                 let response_callback = AptosNetResponseCallback {
@@ -452,6 +491,29 @@ impl AptosNetDataClient {
         }
     }
 
+    /// Waits until the bandwidth limiter of the peer's network has
+    /// enough bandwidth available for the given response.
+    async fn wait_for_bandwidth(&self, peer: &PeerNetworkId, response: &StorageServiceResponse) {
+        let bandwidth_limiter = match self.bandwidth_limiters.get(&peer.network_id()) {
+            Some(bandwidth_limiter) => bandwidth_limiter,
+            None => return,
+        };
+
+        // Responses can be larger than the bucket, so take whatever is available
+        let mut num_bytes_remaining = bcs::serialized_size(response).unwrap_or_default();
+        while num_bytes_remaining > 0 {
+            let result = bandwidth_limiter.lock().acquire_tokens(num_bytes_remaining);
+            match result {
+                Ok(num_bytes_acquired) => {
+                    num_bytes_remaining = num_bytes_remaining.saturating_sub(num_bytes_acquired);
+                }
+                Err(next_refill_time) => {
+                    tokio::time::sleep_until(next_refill_time.into()).await;
+                }
+            }
+        }
+    }
+
     /// Updates the score of the peer who sent the response with the specified id
     fn notify_bad_response(
         &self,
@@ -747,6 +809,41 @@ fn update_connected_peer_metrics(num_priority_peers: usize, num_regular_peers: u
     );
 }
 
+/// Creates the bandwidth limiters for responses received on each network.
+/// The limiters of the networks without a bandwidth limit are open.
+fn inbound_bandwidth_limiters(
+    data_client_config: &AptosDataClientConfig,
+) -> HashMap<NetworkId, SharedBucket> {
+    [NetworkId::Validator, NetworkId::Vfn, NetworkId::Public]
+        .into_iter()
+        .map(|network_id| {
+            let bytes_per_sec = inbound_bytes_per_sec(data_client_config, &network_id);
+            let initial_bytes = bytes_per_sec.unwrap_or(1);
+            let mut bucket = Bucket::new(
+                network_id.to_string(),
+                "data_client_inbound".into(),
+                network_id.to_string(),
+                initial_bytes,
+                initial_bytes,
+                initial_bytes,
+                Some(metrics::BANDWIDTH_LIMITER.clone()),
+            );
+            bucket.set_rate(bytes_per_sec);
+            (network_id, Arc::new(Mutex::new(bucket)))
+        })
+        .collect()
+}
+
+fn inbound_bytes_per_sec(
+    data_client_config: &AptosDataClientConfig,
+    network_id: &NetworkId,
+) -> Option<usize> {
+    data_client_config
+        .inbound_bandwidth_limits
+        .bytes_per_sec(network_id)
+        .map(|bytes_per_sec| (bytes_per_sec as usize).max(1))
+}
+
 /// Spawns a dedicated poller for the given peer.
 pub(crate) fn poll_peer(
     data_client: AptosNetDataClient,
@@ -17,6 +17,7 @@ use std::{
     cmp::min,
     collections::{HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 use storage_service_types::requests::StorageServiceRequest;
 use storage_service_types::responses::StorageServerSummary;
@@ -33,6 +34,8 @@ const NOT_USEFUL_MULTIPLIER: f64 = 0.95;
 const MALICIOUS_MULTIPLIER: f64 = 0.8;
 /// Ignore a peer when their score dips below this threshold.
 const IGNORE_PEER_THRESHOLD: f64 = 25.0;
+/// Weight of the latest response latency in the moving average of a peer's latency.
+const LATENCY_AVERAGE_WEIGHT: f64 = 0.2;
 
 pub(crate) enum ErrorType {
     /// A response or error that's not actively malicious but also doesn't help
@@ -61,6 +64,9 @@ struct PeerState {
     storage_summary: Option<StorageServerSummary>,
     /// For now, a simplified port of the original state-sync v1 scoring system.
     score: f64,
+    /// The moving average of the peer's latency (in seconds) when serving data
+    /// requests, or `None` if the peer hasn't served any data yet.
+    average_latency_secs: Option<f64>,
 }
 
 impl Default for PeerState {
@@ -68,6 +74,7 @@ impl Default for PeerState {
         Self {
             storage_summary: None,
             score: STARTING_SCORE,
+            average_latency_secs: None,
         }
     }
 }
@@ -100,6 +107,26 @@ impl PeerState {
         };
         self.score = f64::max(self.score * multiplier, MIN_SCORE);
     }
+
+    /// Updates the average latency of the peer with the latency of a served data request
+    fn update_latency(&mut self, latency: Duration) {
+        let latency_secs = latency.as_secs_f64();
+        self.average_latency_secs = Some(match self.average_latency_secs {
+            Some(average_latency_secs) => {
+                average_latency_secs * (1.0 - LATENCY_AVERAGE_WEIGHT)
+                    + latency_secs * LATENCY_AVERAGE_WEIGHT
+            }
+            None => latency_secs,
+        });
+    }
+
+    /// Returns the weight of the peer when selecting a peer for a request. Peers
+    /// with higher scores and lower latencies are more likely to be selected.
+    /// Peers without any observed latency are treated as fast, so that they are
+    /// tried out.
+    fn selection_weight(&self) -> f64 {
+        self.score / (1.0 + self.average_latency_secs.unwrap_or(0.0))
+    }
 }
 
 /// Contains all of the unbanned peers' most recent [`StorageServerSummary`] data
@@ -190,6 +217,22 @@ impl PeerStates {
         }
     }
 
+    /// Updates the average latency of the peer with the latency of a served data request
+    pub fn update_latency(&mut self, peer: PeerNetworkId, latency: Duration) {
+        self.peer_to_state
+            .entry(peer)
+            .or_default()
+            .update_latency(latency);
+    }
+
+    /// Returns the weight of the peer when randomly selecting a peer for a request
+    pub fn selection_weight(&self, peer: &PeerNetworkId) -> f64 {
+        self.peer_to_state
+            .get(peer)
+            .map(PeerState::selection_weight)
+            .unwrap_or(STARTING_SCORE)
+    }
+
     /// Returns the number of in-flight priority polls
     pub fn num_in_flight_priority_polls(&self) -> u64 {
         self.in_flight_priority_polls.len() as u64
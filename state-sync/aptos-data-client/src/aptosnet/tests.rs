@@ -873,6 +873,48 @@ async fn all_peer_request_selection() {
     assert!(peer_for_request == priority_peer_1 || peer_for_request == priority_peer_2);
 }
 
+#[tokio::test]
+async fn weighted_peer_request_selection() {
+    ::aptos_logger::Logger::init_for_testing();
+    let (mut mock_network, _, client, _) = MockNetwork::new(None, None, None);
+
+    // Add two regular peers that advertise the same data
+    let fast_peer = mock_network.add_peer(false);
+    let slow_peer = mock_network.add_peer(false);
+    client.update_summary(fast_peer, mock_storage_summary(100));
+    client.update_summary(slow_peer, mock_storage_summary(100));
+
+    // Make the slow peer respond slowly
+    client
+        .peer_states
+        .write()
+        .update_latency(slow_peer, Duration::from_secs(9));
+
+    // Request the data many times and verify the fast peer is selected far more often
+    let output_data_request =
+        DataRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
+            proof_version: 100,
+            start_version: 0,
+            end_version: 100,
+        });
+    let storage_request = StorageServiceRequest::new(output_data_request, false);
+    let mut num_fast_peer_selections = 0;
+    let num_requests = 1000;
+    for _ in 0..num_requests {
+        if client.choose_peer_for_request(&storage_request).unwrap() == fast_peer {
+            num_fast_peer_selections += 1;
+        }
+    }
+    assert!(num_fast_peer_selections > num_requests * 3 / 4);
+
+    // Make the fast peer unable to serve the data and verify the slow peer is selected
+    client.update_summary(fast_peer, mock_storage_summary(0));
+    assert_eq!(
+        client.choose_peer_for_request(&storage_request),
+        Ok(slow_peer)
+    );
+}
+
 #[tokio::test]
 async fn validator_peer_prioritization() {
     ::aptos_logger::Logger::init_for_testing();
@@ -0,0 +1,840 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! A state sync data client that reads the ledger history from a backup storage, e.g. to
+//! bootstrap an archival node without having live peers stream the whole history to it.
+//!
+//! The epoch ending ledger infos, transactions and transaction outputs covered by the epoch
+//! ending and transaction backups are read from the backup storage. Everything else, i.e. the
+//! tail of the chain, the state values and the new data, is requested from the network by the
+//! wrapped data client, which also serves the requests that the backups can't prove.
+//!
+//! Nothing read from the backups is trusted: the responses go through the same verification as
+//! those of peers. The proof of a transaction backup chunk is relative to the ledger info the
+//! chunk was backed up with, so the range proof of a response is rebuilt from the transaction
+//! infos and the frozen accumulator nodes in the proofs of the chunks covering it.
+
+use accumulator::{HashReader, MerkleAccumulator};
+use anyhow::{ensure, format_err, Result};
+use aptos_config::config::{BackupDataClientConfig, BackupStorageConfig, StorageServiceConfig};
+use aptos_crypto::{
+    hash::{CryptoHash, TransactionAccumulatorHasher, ACCUMULATOR_PLACEHOLDER_HASH},
+    HashValue,
+};
+use aptos_data_client::{
+    AptosDataClient, GlobalDataSummary, Response, ResponseCallback, ResponseContext, ResponseError,
+};
+use aptos_infallible::{Mutex, RwLock};
+use aptos_logger::prelude::*;
+use aptos_types::{
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{
+        definition::LeafCount, position::Position, TransactionAccumulatorInternalNode,
+        TransactionAccumulatorRangeProof, TransactionInfoListWithProof,
+    },
+    state_store::state_value::StateValueChunkWithProof,
+    transaction::{
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
+        TransactionOutputListWithProof, Version,
+    },
+    write_set::WriteSet,
+};
+use async_trait::async_trait;
+use backup_cli::{
+    backup_types::{
+        epoch_ending::manifest::EpochEndingBackup,
+        transaction::manifest::{TransactionBackup, TransactionChunk},
+    },
+    metadata,
+    metadata::cache::MetadataCacheOpt,
+    storage::{
+        command_adapter::{config::CommandAdapterConfig, CommandAdapter},
+        local_fs::LocalFs,
+        BackupStorage,
+    },
+    utils::{read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt},
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use storage_service_types::{responses::CompleteDataRange, Epoch};
+use tokio::{io::BufReader, sync::OnceCell};
+
+#[cfg(test)]
+mod tests;
+
+/// The interval at which to retry loading the backups after a failure
+const LOAD_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+type TransactionAccumulator = MerkleAccumulator<ChunkHashReader, TransactionAccumulatorHasher>;
+
+/// A data client serving the ledger history from the backups, and delegating everything else to
+/// the network data client.
+#[derive(Clone)]
+pub struct BackupDataClient<T> {
+    network_client: T,
+    storage_service_config: StorageServiceConfig,
+    backups: Arc<RwLock<Option<Arc<Backups>>>>,
+    // Set once the backups failed the verification of a response, after which the backups are no
+    // longer read from.
+    disabled: Arc<AtomicBool>,
+    next_response_id: Arc<AtomicU64>,
+}
+
+impl<T: AptosDataClient + Send + Sync + Clone + 'static> BackupDataClient<T> {
+    /// Creates a data client reading from the backups of `config`, or only from the network if
+    /// there's no config. Every request is sent to `network_client` until the loader has loaded
+    /// the backups.
+    pub fn new(
+        config: Option<BackupDataClientConfig>,
+        storage_service_config: StorageServiceConfig,
+        network_client: T,
+    ) -> (Self, Option<BackupLoader>) {
+        let backups = Arc::new(RwLock::new(None));
+        let data_client = Self {
+            network_client,
+            storage_service_config,
+            backups: backups.clone(),
+            disabled: Arc::new(AtomicBool::new(false)),
+            next_response_id: Arc::new(AtomicU64::new(0)),
+        };
+        let backup_loader = config.map(|config| BackupLoader { config, backups });
+        (data_client, backup_loader)
+    }
+
+    /// Returns the backups to read from, if they are loaded and haven't been disabled
+    fn backups(&self) -> Option<Arc<Backups>> {
+        if self.disabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.backups.read().clone()
+    }
+
+    fn response<P>(&self, payload: P) -> Response<P> {
+        let id = self.next_response_id.fetch_add(1, Ordering::Relaxed);
+        let response_callback = Box::new(BackupResponseCallback {
+            disabled: self.disabled.clone(),
+        });
+        Response::new(
+            ResponseContext {
+                id,
+                response_callback,
+            },
+            payload,
+        )
+    }
+}
+
+#[async_trait]
+impl<T: AptosDataClient + Send + Sync + Clone + 'static> AptosDataClient for BackupDataClient<T> {
+    fn get_global_data_summary(&self) -> GlobalDataSummary {
+        let mut summary = self.network_client.get_global_data_summary();
+        let backups = match self.backups() {
+            Some(backups) => backups,
+            None => return summary,
+        };
+
+        let advertised_data = &mut summary.advertised_data;
+        if let Some(last_epoch) = backups.last_epoch() {
+            advertised_data
+                .epoch_ending_ledger_infos
+                .push(CompleteDataRange::from_genesis(last_epoch));
+        }
+        if let Some(last_version) = backups.last_version() {
+            let versions = CompleteDataRange::from_genesis(last_version);
+            advertised_data.transactions.push(versions);
+            advertised_data.transaction_outputs.push(versions);
+        }
+
+        // Without peers, chunk sizes are still needed to request the backups
+        let chunk_sizes = &mut summary.optimal_chunk_sizes;
+        let config = &self.storage_service_config;
+        if chunk_sizes.epoch_chunk_size == 0 {
+            chunk_sizes.epoch_chunk_size = config.max_epoch_chunk_size;
+        }
+        if chunk_sizes.transaction_chunk_size == 0 {
+            chunk_sizes.transaction_chunk_size = config.max_transaction_chunk_size;
+        }
+        if chunk_sizes.transaction_output_chunk_size == 0 {
+            chunk_sizes.transaction_output_chunk_size = config.max_transaction_output_chunk_size;
+        }
+        summary
+    }
+
+    async fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: Epoch,
+        expected_end_epoch: Epoch,
+    ) -> aptos_data_client::Result<Response<Vec<LedgerInfoWithSignatures>>> {
+        if let Some(backups) = self.backups() {
+            match backups
+                .read_epoch_ending_ledger_infos(start_epoch, expected_end_epoch)
+                .await
+            {
+                Ok(Some(ledger_infos)) => return Ok(self.response(ledger_infos)),
+                Ok(None) => {}
+                Err(error) => warn!(
+                    start_epoch = start_epoch,
+                    end_epoch = expected_end_epoch,
+                    error = ?error,
+                    "Failed to read the epoch ending ledger infos from the backups."
+                ),
+            }
+        }
+        self.network_client
+            .get_epoch_ending_ledger_infos(start_epoch, expected_end_epoch)
+            .await
+    }
+
+    async fn get_new_transaction_outputs_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> aptos_data_client::Result<
+        Response<(TransactionOutputListWithProof, LedgerInfoWithSignatures)>,
+    > {
+        self.network_client
+            .get_new_transaction_outputs_with_proof(known_version, known_epoch)
+            .await
+    }
+
+    async fn get_new_transactions_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+        include_events: bool,
+    ) -> aptos_data_client::Result<Response<(TransactionListWithProof, LedgerInfoWithSignatures)>>
+    {
+        self.network_client
+            .get_new_transactions_with_proof(known_version, known_epoch, include_events)
+            .await
+    }
+
+    async fn get_number_of_states(
+        &self,
+        version: Version,
+    ) -> aptos_data_client::Result<Response<u64>> {
+        self.network_client.get_number_of_states(version).await
+    }
+
+    async fn get_state_values_with_proof(
+        &self,
+        version: u64,
+        start_index: u64,
+        end_index: u64,
+    ) -> aptos_data_client::Result<Response<StateValueChunkWithProof>> {
+        self.network_client
+            .get_state_values_with_proof(version, start_index, end_index)
+            .await
+    }
+
+    async fn get_transaction_outputs_with_proof(
+        &self,
+        proof_version: Version,
+        start_version: Version,
+        end_version: Version,
+    ) -> aptos_data_client::Result<Response<TransactionOutputListWithProof>> {
+        if let Some(backups) = self.backups() {
+            match backups
+                .read_transactions(proof_version, start_version, end_version)
+                .await
+            {
+                Ok(Some(transactions)) => {
+                    return Ok(self.response(transactions.into_output_list_with_proof()))
+                }
+                Ok(None) => {}
+                Err(error) => warn!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    proof_version = proof_version,
+                    error = ?error,
+                    "Failed to read the transaction outputs from the backups."
+                ),
+            }
+        }
+        self.network_client
+            .get_transaction_outputs_with_proof(proof_version, start_version, end_version)
+            .await
+    }
+
+    async fn get_transactions_with_proof(
+        &self,
+        proof_version: Version,
+        start_version: Version,
+        end_version: Version,
+        include_events: bool,
+    ) -> aptos_data_client::Result<Response<TransactionListWithProof>> {
+        if let Some(backups) = self.backups() {
+            match backups
+                .read_transactions(proof_version, start_version, end_version)
+                .await
+            {
+                Ok(Some(transactions)) => {
+                    return Ok(self.response(transactions.into_list_with_proof(include_events)))
+                }
+                Ok(None) => {}
+                Err(error) => warn!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    proof_version = proof_version,
+                    error = ?error,
+                    "Failed to read the transactions from the backups."
+                ),
+            }
+        }
+        self.network_client
+            .get_transactions_with_proof(proof_version, start_version, end_version, include_events)
+            .await
+    }
+}
+
+/// Stops reading from the backups once one of their responses fails verification
+struct BackupResponseCallback {
+    disabled: Arc<AtomicBool>,
+}
+
+impl ResponseCallback for BackupResponseCallback {
+    fn notify_bad_response(&self, error: ResponseError) {
+        error!(
+            error = ?error,
+            "A response read from the backups is invalid! The backups are no longer read from."
+        );
+        self.disabled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for BackupResponseCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackupResponseCallback").finish()
+    }
+}
+
+/// Loads the manifests of the backups in the background, once the node has started
+pub struct BackupLoader {
+    config: BackupDataClientConfig,
+    backups: Arc<RwLock<Option<Arc<Backups>>>>,
+}
+
+impl BackupLoader {
+    pub async fn start_loader(self) {
+        loop {
+            match Backups::load(&self.config).await {
+                Ok(backups) => {
+                    info!(
+                        last_epoch = backups.last_epoch(),
+                        last_version = backups.last_version(),
+                        "Loaded the backups to sync from."
+                    );
+                    *self.backups.write() = Some(Arc::new(backups));
+                    return;
+                }
+                Err(error) => {
+                    warn!(error = ?error, "Failed to load the backups to sync from.");
+                    tokio::time::sleep(LOAD_RETRY_INTERVAL).await;
+                }
+            }
+        }
+    }
+}
+
+/// The chunks of the epoch ending and transaction backups in the storage
+struct Backups {
+    storage: Arc<dyn BackupStorage>,
+    // The epoch ending backup chunks, as `(first_epoch, last_epoch, ledger_infos)`
+    epoch_ending_chunks: Vec<(Epoch, Epoch, String)>,
+    transaction_chunks: Vec<TransactionChunk>,
+    chunk_cache: ChunkCache,
+}
+
+impl Backups {
+    async fn load(config: &BackupDataClientConfig) -> Result<Self> {
+        let storage: Arc<dyn BackupStorage> = match &config.storage {
+            BackupStorageConfig::LocalFs { dir } => Arc::new(LocalFs::new(dir.clone())),
+            BackupStorageConfig::CommandAdapter { config_path } => Arc::new(CommandAdapter::new(
+                CommandAdapterConfig::load_from_file(config_path).await?,
+            )),
+        };
+        let concurrent_downloads = config.max_concurrent_downloads.max(1);
+        let metadata_view = metadata::cache::sync_and_load(
+            &MetadataCacheOpt::new(config.metadata_cache_dir.clone()),
+            storage.clone(),
+            concurrent_downloads,
+        )
+        .await?;
+
+        let epoch_ending_manifests: Vec<EpochEndingBackup> =
+            stream::iter(metadata_view.select_epoch_ending_backups(Version::MAX)?)
+                .map(|backup| {
+                    let storage = storage.clone();
+                    async move { storage.load_json_file(&backup.manifest).await }
+                })
+                .buffered(concurrent_downloads)
+                .try_collect()
+                .await?;
+        let mut epoch_ending_chunks = vec![];
+        for manifest in epoch_ending_manifests {
+            manifest.verify()?;
+            epoch_ending_chunks.extend(
+                manifest
+                    .chunks
+                    .into_iter()
+                    .map(|chunk| (chunk.first_epoch, chunk.last_epoch, chunk.ledger_infos)),
+            );
+        }
+
+        let transaction_manifests: Vec<TransactionBackup> =
+            stream::iter(metadata_view.select_transaction_backups(0, Version::MAX)?)
+                .map(|backup| {
+                    let storage = storage.clone();
+                    async move { storage.load_json_file(&backup.manifest).await }
+                })
+                .buffered(concurrent_downloads)
+                .try_collect()
+                .await?;
+        let mut transaction_chunks = vec![];
+        for manifest in transaction_manifests {
+            manifest.verify()?;
+            transaction_chunks.extend(manifest.chunks);
+        }
+
+        Ok(Self {
+            storage,
+            epoch_ending_chunks,
+            transaction_chunks,
+            chunk_cache: ChunkCache::new(config.max_cached_chunks),
+        })
+    }
+
+    fn last_epoch(&self) -> Option<Epoch> {
+        self.epoch_ending_chunks
+            .last()
+            .map(|(_, last_epoch, _)| *last_epoch)
+    }
+
+    fn last_version(&self) -> Option<Version> {
+        self.transaction_chunks
+            .last()
+            .map(|chunk| chunk.last_version)
+    }
+
+    /// Reads the epoch ending ledger infos from `start_epoch` to `end_epoch` (inclusive), or
+    /// returns `None` if the backups don't have all of them.
+    async fn read_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: Epoch,
+        end_epoch: Epoch,
+    ) -> Result<Option<Vec<LedgerInfoWithSignatures>>> {
+        if start_epoch > end_epoch || self.last_epoch().map_or(true, |last| end_epoch > last) {
+            return Ok(None);
+        }
+
+        let first_chunk = self
+            .epoch_ending_chunks
+            .partition_point(|(_, last_epoch, _)| *last_epoch < start_epoch);
+        let mut ledger_infos = vec![];
+        for (first_epoch, last_epoch, file_handle) in &self.epoch_ending_chunks[first_chunk..] {
+            if *first_epoch > end_epoch {
+                break;
+            }
+            let mut file = BufReader::new(self.storage.open_for_read(file_handle).await?);
+            let mut chunk = vec![];
+            while let Some(record_bytes) = file.read_record_bytes().await? {
+                chunk.push(bcs::from_bytes::<LedgerInfoWithSignatures>(&record_bytes)?);
+            }
+            ensure!(
+                chunk.len() as u64 == last_epoch - first_epoch + 1,
+                "Number of ledger infos in the chunk doesn't match that in the manifest. \
+                first_epoch: {}, last_epoch: {}, ledger infos in chunk: {}",
+                first_epoch,
+                last_epoch,
+                chunk.len(),
+            );
+            ledger_infos.extend(
+                chunk
+                    .into_iter()
+                    .zip(*first_epoch..)
+                    .filter(|(_, epoch)| (start_epoch..=end_epoch).contains(epoch))
+                    .map(|(ledger_info, _)| ledger_info),
+            );
+        }
+        Ok(Some(ledger_infos))
+    }
+
+    /// Reads the transactions from `start_version` to `end_version` (inclusive) with a proof
+    /// relative to the ledger info at `proof_version`, or returns `None` if the chunks covering
+    /// them can't prove them relative to that ledger info.
+    async fn read_transactions(
+        &self,
+        proof_version: Version,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Option<BackupTransactions>> {
+        if start_version > end_version
+            || end_version > proof_version
+            || self.last_version().map_or(true, |last| end_version > last)
+        {
+            return Ok(None);
+        }
+
+        let first_chunk = self
+            .transaction_chunks
+            .partition_point(|chunk| chunk.last_version < start_version);
+        let chunks = self.transaction_chunks[first_chunk..]
+            .iter()
+            .take_while(|chunk| chunk.first_version <= end_version);
+        let chunks: Vec<Arc<LoadedChunk>> = stream::iter(chunks)
+            .map(|chunk| self.chunk_cache.get_or_load(&self.storage, chunk))
+            .buffered(self.chunk_cache.capacity)
+            .try_collect()
+            .await?;
+
+        let mut reader = ChunkHashReader::default();
+        for chunk in &chunks {
+            reader.add_chunk(chunk)?;
+        }
+        // The proof is unavailable if the chunks don't hold the accumulator nodes it needs
+        let range_proof = match reader.get_range_proof(
+            proof_version + 1,
+            start_version,
+            end_version - start_version + 1,
+        ) {
+            Ok(range_proof) => range_proof,
+            Err(error) => {
+                debug!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    proof_version = proof_version,
+                    error = ?error,
+                    "The transaction backups can't prove the transactions."
+                );
+                return Ok(None);
+            }
+        };
+
+        let mut transactions = BackupTransactions {
+            first_version: start_version,
+            transactions: vec![],
+            transaction_infos: vec![],
+            events: vec![],
+            write_sets: vec![],
+            range_proof,
+        };
+        for chunk in &chunks {
+            let first_version = chunk.first_version.max(start_version);
+            let last_version = chunk.last_version().min(end_version);
+            let range = (first_version - chunk.first_version) as usize
+                ..(last_version - chunk.first_version + 1) as usize;
+            transactions
+                .transactions
+                .extend_from_slice(&chunk.transactions[range.clone()]);
+            transactions
+                .transaction_infos
+                .extend_from_slice(&chunk.transaction_infos[range.clone()]);
+            transactions
+                .events
+                .extend_from_slice(&chunk.events[range.clone()]);
+            transactions
+                .write_sets
+                .extend_from_slice(&chunk.write_sets[range]);
+        }
+        Ok(Some(transactions))
+    }
+}
+
+/// The transactions read from the backups, with their range proof
+struct BackupTransactions {
+    first_version: Version,
+    transactions: Vec<Transaction>,
+    transaction_infos: Vec<TransactionInfo>,
+    events: Vec<Vec<ContractEvent>>,
+    write_sets: Vec<WriteSet>,
+    range_proof: TransactionAccumulatorRangeProof,
+}
+
+impl BackupTransactions {
+    fn into_list_with_proof(self, include_events: bool) -> TransactionListWithProof {
+        TransactionListWithProof::new(
+            self.transactions,
+            include_events.then(|| self.events),
+            Some(self.first_version),
+            TransactionInfoListWithProof::new(self.range_proof, self.transaction_infos),
+        )
+    }
+
+    fn into_output_list_with_proof(self) -> TransactionOutputListWithProof {
+        let transactions_and_outputs = self
+            .transactions
+            .into_iter()
+            .zip(self.write_sets)
+            .zip(self.events)
+            .zip(&self.transaction_infos)
+            .map(|(((transaction, write_set), events), transaction_info)| {
+                let output = TransactionOutput::new(
+                    write_set,
+                    events,
+                    transaction_info.gas_used(),
+                    transaction_info.status().clone().into(),
+                );
+                (transaction, output)
+            })
+            .collect();
+        TransactionOutputListWithProof::new(
+            transactions_and_outputs,
+            Some(self.first_version),
+            TransactionInfoListWithProof::new(self.range_proof, self.transaction_infos),
+        )
+    }
+}
+
+/// A transaction backup chunk, read from the backup storage
+struct LoadedChunk {
+    first_version: Version,
+    transactions: Vec<Transaction>,
+    transaction_infos: Vec<TransactionInfo>,
+    events: Vec<Vec<ContractEvent>>,
+    write_sets: Vec<WriteSet>,
+    range_proof: TransactionAccumulatorRangeProof,
+    // The version of the ledger info the range proof is relative to
+    proof_version: Version,
+}
+
+impl LoadedChunk {
+    async fn load(storage: &Arc<dyn BackupStorage>, chunk: &TransactionChunk) -> Result<Self> {
+        let mut file = BufReader::new(storage.open_for_read(&chunk.transactions).await?);
+        let mut transactions = vec![];
+        let mut transaction_infos = vec![];
+        let mut events = vec![];
+        let mut write_sets = vec![];
+        while let Some(record_bytes) = file.read_record_bytes().await? {
+            let (transaction, transaction_info, event_vec, write_set) =
+                bcs::from_bytes(&record_bytes)?;
+            transactions.push(transaction);
+            transaction_infos.push(transaction_info);
+            events.push(event_vec);
+            write_sets.push(write_set);
+        }
+        ensure!(
+            chunk.first_version + (transactions.len() as Version) == chunk.last_version + 1,
+            "Number of items in chunks doesn't match that in manifest. first_version: {}, \
+            last_version: {}, items in chunk: {}",
+            chunk.first_version,
+            chunk.last_version,
+            transactions.len(),
+        );
+
+        let (range_proof, ledger_info) = storage
+            .load_bcs_file::<(TransactionAccumulatorRangeProof, LedgerInfoWithSignatures)>(
+                &chunk.proof,
+            )
+            .await?;
+        Ok(Self {
+            first_version: chunk.first_version,
+            transactions,
+            transaction_infos,
+            events,
+            write_sets,
+            range_proof,
+            proof_version: ledger_info.ledger_info().version(),
+        })
+    }
+
+    fn last_version(&self) -> Version {
+        self.first_version + self.transactions.len() as Version - 1
+    }
+}
+
+/// The most recently read transaction backup chunks. Consecutive requests of a stream usually
+/// fall into the same chunk, which is read only once.
+struct ChunkCache {
+    capacity: usize,
+    // The chunks by first version, the most recently read last
+    chunks: Mutex<VecDeque<(Version, Arc<OnceCell<Arc<LoadedChunk>>>)>>,
+}
+
+impl ChunkCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            chunks: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    async fn get_or_load(
+        &self,
+        storage: &Arc<dyn BackupStorage>,
+        chunk: &TransactionChunk,
+    ) -> Result<Arc<LoadedChunk>> {
+        let cell = {
+            let mut chunks = self.chunks.lock();
+            match chunks
+                .iter()
+                .position(|(first_version, _)| *first_version == chunk.first_version)
+            {
+                Some(index) => {
+                    let entry = chunks.remove(index).expect("The index must be valid");
+                    let cell = entry.1.clone();
+                    chunks.push_back(entry);
+                    cell
+                }
+                None => {
+                    if chunks.len() >= self.capacity {
+                        chunks.pop_front();
+                    }
+                    let cell = Arc::new(OnceCell::new());
+                    chunks.push_back((chunk.first_version, cell.clone()));
+                    cell
+                }
+            }
+        };
+        // Concurrent requests for the same chunk wait for a single read
+        cell.get_or_try_init(|| async { LoadedChunk::load(storage, chunk).await.map(Arc::new) })
+            .await
+            .map(Arc::clone)
+    }
+}
+
+/// Reads the transaction accumulator from consecutive backup chunks: the nodes over their
+/// transactions are computed from the transaction infos, and the others are taken from the range
+/// proofs of the chunks.
+///
+/// Frozen nodes are the same in the accumulator at any later version, so the chunks can prove
+/// their transactions relative to the ledger info a chunk was backed up with, or to any ledger
+/// info whose accumulator has no frozen nodes after the chunks, e.g., one within the chunks.
+#[derive(Default)]
+struct ChunkHashReader {
+    first_leaf_index: u64,
+    leaves: Vec<HashValue>,
+    frozen_siblings: HashMap<Position, HashValue>,
+    // The siblings in the range proofs, by the number of leaves of their accumulator
+    siblings: HashMap<(LeafCount, Position), HashValue>,
+}
+
+impl ChunkHashReader {
+    fn add_chunk(&mut self, chunk: &LoadedChunk) -> Result<()> {
+        if self.leaves.is_empty() {
+            self.first_leaf_index = chunk.first_version;
+        }
+        ensure!(
+            self.first_leaf_index + self.leaves.len() as u64 == chunk.first_version,
+            "The chunks are not consecutive. Expected first version: {}, actual: {}.",
+            self.first_leaf_index + self.leaves.len() as u64,
+            chunk.first_version,
+        );
+
+        let num_leaves = chunk.proof_version + 1;
+        let (left_siblings, right_siblings) = TransactionAccumulator::get_range_proof_positions(
+            self,
+            num_leaves,
+            Some(chunk.first_version),
+            chunk.transaction_infos.len() as LeafCount,
+        )?;
+        let range_proof = &chunk.range_proof;
+        ensure!(
+            left_siblings.len() == range_proof.left_siblings().len()
+                && right_siblings.len() == range_proof.right_siblings().len(),
+            "The range proof of the chunk at version {} doesn't match its ledger info at \
+            version {}.",
+            chunk.first_version,
+            chunk.proof_version,
+        );
+        let siblings = left_siblings
+            .into_iter()
+            .zip(range_proof.left_siblings())
+            .chain(right_siblings.into_iter().zip(range_proof.right_siblings()));
+        for (position, hash) in siblings {
+            if position.is_freezable(chunk.proof_version) {
+                self.frozen_siblings.insert(position, *hash);
+            }
+            self.siblings.insert((num_leaves, position), *hash);
+        }
+
+        self.leaves.extend(
+            chunk
+                .transaction_infos
+                .iter()
+                .map(|transaction_info| transaction_info.hash()),
+        );
+        Ok(())
+    }
+
+    /// Returns the proof of `num_leaves` leaves from `first_leaf_index` in the accumulator of
+    /// `full_acc_leaves` leaves
+    fn get_range_proof(
+        &self,
+        full_acc_leaves: LeafCount,
+        first_leaf_index: u64,
+        num_leaves: LeafCount,
+    ) -> Result<TransactionAccumulatorRangeProof> {
+        let (left_siblings, right_siblings) = TransactionAccumulator::get_range_proof_positions(
+            self,
+            full_acc_leaves,
+            Some(first_leaf_index),
+            num_leaves,
+        )?;
+        let get_hashes = |positions: Vec<Position>| {
+            positions
+                .into_iter()
+                .map(|position| self.get_hash(full_acc_leaves, position))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(TransactionAccumulatorRangeProof::new(
+            get_hashes(left_siblings)?,
+            get_hashes(right_siblings)?,
+        ))
+    }
+
+    /// Returns the hash of the node at `position` in the accumulator of `num_leaves` leaves
+    fn get_hash(&self, num_leaves: LeafCount, position: Position) -> Result<HashValue> {
+        if let Some(hash) = self.siblings.get(&(num_leaves, position)) {
+            return Ok(*hash);
+        }
+        let rightmost_leaf_index = num_leaves - 1;
+        if position.is_placeholder(rightmost_leaf_index) {
+            Ok(*ACCUMULATOR_PLACEHOLDER_HASH)
+        } else if position.is_freezable(rightmost_leaf_index) {
+            self.get(position)
+        } else {
+            Ok(TransactionAccumulatorInternalNode::new(
+                self.get_hash(num_leaves, position.left_child())?,
+                self.get_hash(num_leaves, position.right_child())?,
+            )
+            .hash())
+        }
+    }
+}
+
+impl HashReader for ChunkHashReader {
+    /// Returns the hash of the frozen node at `position`
+    fn get(&self, position: Position) -> Result<HashValue> {
+        if let Some(hash) = self.frozen_siblings.get(&position) {
+            return Ok(*hash);
+        }
+
+        // Only the nodes over some of the transactions of the chunks can be computed
+        let first_leaf = position.left_most_child().to_inorder_index() / 2;
+        let last_leaf = position.right_most_child().to_inorder_index() / 2;
+        let known_leaves = self.first_leaf_index..self.first_leaf_index + self.leaves.len() as u64;
+        if last_leaf < known_leaves.start || first_leaf >= known_leaves.end {
+            return Err(format_err!(
+                "The accumulator node {} is not in the backup chunks.",
+                position
+            ));
+        }
+        if position.is_leaf() {
+            return Ok(self.leaves[(first_leaf - known_leaves.start) as usize]);
+        }
+        Ok(TransactionAccumulatorInternalNode::new(
+            self.get(position.left_child())?,
+            self.get(position.right_child())?,
+        )
+        .hash())
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use aptos_types::{transaction::ExecutionStatus, write_set::WriteSetMut};
+
+const NUM_TRANSACTIONS: u64 = 100;
+
+fn transaction_infos() -> Vec<TransactionInfo> {
+    (0..NUM_TRANSACTIONS)
+        .map(|gas_used| {
+            TransactionInfo::new(
+                HashValue::random(),
+                HashValue::random(),
+                HashValue::random(),
+                None,
+                gas_used,
+                ExecutionStatus::Success,
+            )
+        })
+        .collect()
+}
+
+/// Returns a reader holding the whole accumulator
+fn full_reader(transaction_infos: &[TransactionInfo]) -> ChunkHashReader {
+    ChunkHashReader {
+        first_leaf_index: 0,
+        leaves: transaction_infos.iter().map(CryptoHash::hash).collect(),
+        frozen_siblings: HashMap::new(),
+        siblings: HashMap::new(),
+    }
+}
+
+/// Returns the chunk of `[first_version, last_version]` as it is backed up with the ledger info
+/// at `proof_version`
+fn chunk(
+    transaction_infos: &[TransactionInfo],
+    first_version: Version,
+    last_version: Version,
+    proof_version: Version,
+) -> LoadedChunk {
+    let range_proof = TransactionAccumulator::get_range_proof(
+        &full_reader(transaction_infos),
+        proof_version + 1,
+        Some(first_version),
+        last_version - first_version + 1,
+    )
+    .unwrap();
+    let range = first_version as usize..last_version as usize + 1;
+    LoadedChunk {
+        first_version,
+        transactions: range
+            .clone()
+            .map(|_| Transaction::StateCheckpoint(HashValue::random()))
+            .collect(),
+        transaction_infos: transaction_infos[range.clone()].to_vec(),
+        events: range.clone().map(|_| vec![]).collect(),
+        write_sets: range
+            .map(|_| WriteSetMut::new(vec![]).freeze().unwrap())
+            .collect(),
+        range_proof,
+        proof_version,
+    }
+}
+
+/// Proves `[first_version, last_version]` relative to the ledger info at `proof_version` with
+/// the given chunks, and verifies the proof
+fn prove(
+    transaction_infos: &[TransactionInfo],
+    chunks: &[LoadedChunk],
+    first_version: Version,
+    last_version: Version,
+    proof_version: Version,
+) -> Result<()> {
+    let mut reader = ChunkHashReader::default();
+    for chunk in chunks {
+        reader.add_chunk(chunk)?;
+    }
+    let range_proof = reader.get_range_proof(
+        proof_version + 1,
+        first_version,
+        last_version - first_version + 1,
+    )?;
+
+    let root_hash =
+        TransactionAccumulator::get_root_hash(&full_reader(transaction_infos), proof_version + 1)
+            .unwrap();
+    let leaves: Vec<HashValue> = transaction_infos[first_version as usize..=last_version as usize]
+        .iter()
+        .map(CryptoHash::hash)
+        .collect();
+    range_proof.verify(root_hash, Some(first_version), &leaves)
+}
+
+#[test]
+fn test_prove_within_chunk() {
+    let transaction_infos = transaction_infos();
+    let chunks = [chunk(&transaction_infos, 20, 59, 79)];
+
+    // Relative to the ledger info of the chunk
+    prove(&transaction_infos, &chunks, 20, 59, 79).unwrap();
+    prove(&transaction_infos, &chunks, 33, 41, 79).unwrap();
+    // Relative to a ledger info within the chunk, e.g., the end of an earlier epoch
+    prove(&transaction_infos, &chunks, 21, 37, 37).unwrap();
+    prove(&transaction_infos, &chunks, 20, 59, 59).unwrap();
+}
+
+#[test]
+fn test_prove_across_chunks() {
+    let transaction_infos = transaction_infos();
+    let chunks = [
+        chunk(&transaction_infos, 0, 30, 40),
+        chunk(&transaction_infos, 31, 63, 99),
+        chunk(&transaction_infos, 64, 90, 99),
+    ];
+
+    prove(&transaction_infos, &chunks, 0, 90, 99).unwrap();
+    prove(&transaction_infos, &chunks, 17, 75, 99).unwrap();
+    prove(&transaction_infos, &chunks, 5, 70, 88).unwrap();
+}
+
+#[test]
+fn test_unprovable_ledger_info() {
+    let transaction_infos = transaction_infos();
+    let chunks = [chunk(&transaction_infos, 20, 59, 79)];
+
+    // The chunk doesn't hold the accumulator nodes after it, other than those of its ledger info
+    prove(&transaction_infos, &chunks, 20, 59, 70).unwrap_err();
+    prove(&transaction_infos, &chunks, 20, 59, 99).unwrap_err();
+}
+
+#[test]
+fn test_chunks_must_be_consecutive() {
+    let transaction_infos = transaction_infos();
+    let chunks = [
+        chunk(&transaction_infos, 0, 30, 40),
+        chunk(&transaction_infos, 32, 63, 99),
+    ];
+
+    prove(&transaction_infos, &chunks, 0, 63, 99).unwrap_err();
+}
@@ -27,13 +27,17 @@ use aptos_logger::prelude::*;
 use futures::channel::mpsc;
 use futures::{stream::FusedStream, SinkExt, Stream};
 use std::{
+    cmp::{max, min},
     collections::{BTreeMap, VecDeque},
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
     time::Duration,
 };
-use tokio::task::JoinHandle;
+use tokio::{sync::Notify, task::JoinHandle};
 
 // The frequency at which to log sent data request messages
 const SENT_REQUESTS_LOG_FREQ_SECS: u64 = 1;
@@ -71,6 +75,18 @@ pub struct DataStream<T> {
     // a data notification can be created and sent along the stream.
     sent_data_requests: Option<VecDeque<PendingClientResponse>>,
 
+    // The maximum number of data client requests to keep in-flight. This
+    // adapts to how quickly the listener consumes the data notifications.
+    prefetch_window: u64,
+
+    // The number of data notifications sent along the stream that the
+    // listener hasn't consumed yet.
+    pending_notifications: Arc<AtomicU64>,
+
+    // Notifies the streaming service when a response has arrived, so that it
+    // can be forwarded along the stream without waiting for a progress check.
+    progress_notifier: Arc<Notify>,
+
     // Handles of all spawned tasks. This is useful for aborting the tasks in
     // the case the stream is terminated prematurely.
     spawned_tasks: Vec<JoinHandle<()>>,
@@ -106,6 +122,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
         aptos_data_client: T,
         notification_id_generator: Arc<U64IdGenerator>,
         advertised_data: &AdvertisedData,
+        progress_notifier: Arc<Notify>,
     ) -> Result<(Self, DataStreamListener), Error> {
         // Create a new data stream listener
         let (notification_sender, notification_receiver) =
@@ -116,12 +133,16 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
         let stream_engine = StreamEngine::new(stream_request, advertised_data)?;
 
         // Create a new data stream
-        let data_stream = Self {
+        let pending_notifications = data_stream_listener.pending_notifications.clone();
+        let mut data_stream = Self {
             config,
             data_stream_id,
             aptos_data_client,
             stream_engine,
             sent_data_requests: None,
+            prefetch_window: 0,
+            pending_notifications,
+            progress_notifier,
             spawned_tasks: vec![],
             notifications_to_responses: BTreeMap::new(),
             notification_sender,
@@ -130,6 +151,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
             request_failure_count: 0,
             send_failure: false,
         };
+        data_stream.prefetch_window = data_stream.get_max_concurrent_requests();
 
         Ok((data_stream, data_stream_listener))
     }
@@ -210,14 +232,16 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
         &mut self,
         global_data_summary: &GlobalDataSummary,
     ) -> Result<(), Error> {
-        // Determine how many requests (at most) can be sent to the network
+        // Determine how many requests (at most) can be sent to the network. The
+        // number of in-flight requests and unconsumed notifications is bounded
+        // to limit the number of chunks held in memory.
         let num_sent_requests = self.get_sent_data_requests().len() as u64;
-        let max_concurrent_requests = self.get_max_concurrent_requests();
-        let max_num_requests_to_send = max_concurrent_requests
-            .checked_sub(num_sent_requests)
-            .ok_or_else(|| {
-                Error::IntegerOverflow("Max number of requests to send has overflown!".into())
-            })?;
+        let num_prefetched_chunks_allowed = self
+            .config
+            .max_pending_notifications
+            .saturating_sub(self.pending_notifications.load(Ordering::Relaxed));
+        let max_num_requests_to_send = min(self.prefetch_window, num_prefetched_chunks_allowed)
+            .saturating_sub(num_sent_requests);
 
         if max_num_requests_to_send > 0 {
             let client_requests = self
@@ -267,6 +291,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
             data_client_request,
             self.aptos_data_client.clone(),
             pending_client_response.clone(),
+            self.progress_notifier.clone(),
         );
         self.spawned_tasks.push(join_handle);
 
@@ -279,6 +304,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
         &mut self,
         data_notification: DataNotification,
     ) -> Result<(), Error> {
+        self.pending_notifications.fetch_add(1, Ordering::Relaxed);
         if let Err(error) = self.notification_sender.send(data_notification).await {
             let error = Error::UnexpectedErrorEncountered(error.to_string());
             warn!(
@@ -319,7 +345,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
         self.send_data_notification(data_notification).await
     }
 
-    /// Processes any data client responses that have been received. Note: the
+    /// Processes all data client responses that have been received. Note: the
     /// responses must be processed in FIFO order.
     pub async fn process_data_responses(
         &mut self,
@@ -335,8 +361,8 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
             return Ok(()); // There's nothing left to do
         }
 
-        // Process any ready data responses
-        for _ in 0..self.get_max_concurrent_requests() {
+        // Process all ready data responses (in order)
+        loop {
             if let Some(pending_response) = self.pop_pending_response_queue() {
                 let client_response = pending_response
                     .lock()
@@ -368,11 +394,40 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
             }
         }
 
+        // Adapt the prefetch window to the rate at which notifications are consumed
+        self.update_prefetch_window();
+
+        // If the stream is now complete, notify the streaming service so that
+        // the end of stream notification is sent without delay.
+        if self.stream_engine.is_stream_complete() {
+            self.progress_notifier.notify_one();
+        }
+
         // Create and send further client requests to the network
         // to ensure we're maximizing the number of concurrent requests.
         self.create_and_send_client_requests(&global_data_summary)
     }
 
+    /// Grows the prefetch window (up to the maximum number of concurrent
+    /// requests) if the listener has consumed all notifications, i.e., it is
+    /// waiting for data. Halves the window if the listener is falling behind.
+    fn update_prefetch_window(&mut self) {
+        let num_pending_notifications = self.pending_notifications.load(Ordering::Relaxed);
+        if num_pending_notifications == 0 {
+            self.prefetch_window = min(
+                self.prefetch_window.saturating_add(1),
+                self.get_max_concurrent_requests(),
+            );
+        } else if num_pending_notifications >= self.config.max_pending_notifications / 2 {
+            self.prefetch_window = max(self.prefetch_window / 2, 1);
+        }
+    }
+
+    /// Returns the current prefetch window (i.e., the max number of in-flight requests)
+    pub fn get_prefetch_window(&self) -> u64 {
+        self.prefetch_window
+    }
+
     /// Pops and returns the first pending client response if the response has
     /// been received. Returns `None` otherwise.
     fn pop_pending_response_queue(&mut self) -> Option<PendingClientResponse> {
@@ -613,6 +668,9 @@ pub struct DataStreamListener {
 
     /// Stores the number of consecutive timeouts encountered when listening to this stream
     pub num_consecutive_timeouts: u64,
+
+    /// The number of notifications sent along the stream but not yet consumed
+    pending_notifications: Arc<AtomicU64>,
 }
 
 impl DataStreamListener {
@@ -624,6 +682,7 @@ impl DataStreamListener {
             data_stream_id,
             notification_receiver,
             num_consecutive_timeouts: 0,
+            pending_notifications: Arc::new(AtomicU64::new(0)),
         }
     }
 }
@@ -632,7 +691,14 @@ impl Stream for DataStreamListener {
     type Item = DataNotification;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().notification_receiver).poll_next(cx)
+        let data_stream_listener = self.get_mut();
+        let poll_result = Pin::new(&mut data_stream_listener.notification_receiver).poll_next(cx);
+        if let Poll::Ready(Some(_)) = &poll_result {
+            data_stream_listener
+                .pending_notifications
+                .fetch_sub(1, Ordering::Relaxed);
+        }
+        poll_result
     }
 }
 
@@ -714,6 +780,7 @@ fn spawn_request_task<T: AptosDataClient + Send + Clone + 'static>(
     data_client_request: DataClientRequest,
     aptos_data_client: T,
     pending_response: PendingClientResponse,
+    progress_notifier: Arc<Notify>,
 ) -> JoinHandle<()> {
     // Update the requests sent counter
     increment_counter(
@@ -767,8 +834,9 @@ fn spawn_request_task<T: AptosDataClient + Send + Clone + 'static>(
             }
         }
 
-        // Save the response
+        // Save the response and notify the streaming service
         pending_response.lock().client_response = Some(client_response);
+        progress_notifier.notify_one();
     })
 }
 
@@ -14,9 +14,9 @@ use aptos_config::config::DataStreamingServiceConfig;
 use aptos_data_client::{AptosDataClient, GlobalDataSummary, OptimalChunkSizes};
 use aptos_id_generator::{IdGenerator, U64IdGenerator};
 use aptos_logger::prelude::*;
-use futures::StreamExt;
+use futures::{FutureExt, StreamExt};
 use std::{collections::HashMap, sync::Arc, time::Duration};
-use tokio::time::interval;
+use tokio::{sync::Notify, time::interval};
 use tokio_stream::wrappers::IntervalStream;
 
 // Useful constants for the Data Streaming Service
@@ -42,6 +42,10 @@ pub struct DataStreamingService<T> {
     // The listener through which to hear new client stream requests
     stream_requests: StreamingServiceListener,
 
+    // Notified when a data stream is able to make progress (e.g., a new stream
+    // was created or a data client response has arrived).
+    progress_notifier: Arc<Notify>,
+
     // Unique ID generators to maintain unique IDs across streams
     stream_id_generator: U64IdGenerator,
     notification_id_generator: Arc<U64IdGenerator>,
@@ -59,6 +63,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStreamingService<T> {
             global_data_summary: GlobalDataSummary::empty(),
             data_streams: HashMap::new(),
             stream_requests,
+            progress_notifier: Arc::new(Notify::new()),
             stream_id_generator: U64IdGenerator::new(),
             notification_id_generator: Arc::new(U64IdGenerator::new()),
         }
@@ -74,6 +79,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStreamingService<T> {
             self.config.progress_check_interval_ms,
         )))
         .fuse();
+        let progress_notifier = self.progress_notifier.clone();
 
         loop {
             ::futures::select! {
@@ -86,6 +92,9 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStreamingService<T> {
                 _ = progress_check_interval.select_next_some() => {
                     self.check_progress_of_all_data_streams().await;
                 }
+                _ = progress_notifier.notified().fuse() => {
+                    self.check_progress_of_all_data_streams().await;
+                }
             }
         }
     }
@@ -201,6 +210,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStreamingService<T> {
             self.aptos_data_client.clone(),
             self.notification_id_generator.clone(),
             &self.global_data_summary.advertised_data,
+            self.progress_notifier.clone(),
         )?;
 
         // Verify the data stream can be fulfilled using the currently advertised data
@@ -221,6 +231,9 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStreamingService<T> {
                 request_message
             )));
 
+        // Initialize the new stream without waiting for a progress check
+        self.progress_notifier.notify_one();
+
         // Return the listener
         Ok(stream_listener)
     }
@@ -33,7 +33,7 @@ use claims::{assert_err, assert_ge, assert_matches, assert_none, assert_ok};
 use futures::{FutureExt, StreamExt};
 use std::{sync::Arc, time::Duration};
 use storage_service_types::responses::CompleteDataRange;
-use tokio::time::timeout;
+use tokio::{sync::Notify, time::timeout};
 
 #[tokio::test]
 async fn test_stream_blocked() {
@@ -415,6 +415,58 @@ async fn test_state_stream_out_of_order_responses() {
     assert_none!(stream_listener.select_next_some().now_or_never());
 }
 
+#[tokio::test]
+async fn test_stream_prefetch_window() {
+    // Create an epoch ending data stream
+    let max_concurrent_requests = 4;
+    let streaming_service_config = DataStreamingServiceConfig {
+        max_concurrent_requests,
+        max_pending_notifications: 4,
+        ..Default::default()
+    };
+    let (mut data_stream, mut stream_listener) =
+        create_epoch_ending_stream(streaming_service_config, MIN_ADVERTISED_EPOCH_END);
+
+    // Initialize the data stream and verify the prefetch window is at the maximum
+    let global_data_summary = create_global_data_summary(1);
+    data_stream
+        .initialize_data_requests(global_data_summary.clone())
+        .unwrap();
+    assert_eq!(data_stream.get_prefetch_window(), max_concurrent_requests);
+
+    // Set responses for the first two requests without consuming the notifications
+    set_epoch_ending_response_in_queue(&mut data_stream, 0);
+    set_epoch_ending_response_in_queue(&mut data_stream, 1);
+    data_stream
+        .process_data_responses(global_data_summary.clone())
+        .await
+        .unwrap();
+
+    // Verify the prefetch window shrinks because the listener is falling behind
+    assert_eq!(
+        data_stream.get_prefetch_window(),
+        max_concurrent_requests / 2
+    );
+
+    // Consume the notifications
+    while let Some(data_notification) = stream_listener.select_next_some().now_or_never() {
+        assert_matches!(
+            data_notification.data_payload,
+            DataPayload::EpochEndingLedgerInfos(_)
+        );
+    }
+
+    // Verify the prefetch window grows again now the listener is waiting for data
+    data_stream
+        .process_data_responses(global_data_summary.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        data_stream.get_prefetch_window(),
+        max_concurrent_requests / 2 + 1
+    );
+}
+
 #[tokio::test]
 async fn test_stream_listener_dropped() {
     // Create an epoch ending data stream
@@ -556,6 +608,7 @@ fn create_data_stream(
         aptos_data_client,
         notification_generator,
         &advertised_data,
+        Arc::new(Notify::new()),
     )
     .unwrap()
 }
@@ -707,6 +707,45 @@ async fn test_notifications_epoch_ending_limited_chunks() {
     }
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_notifications_without_progress_checks() {
+    // Create a streaming service that (effectively) never runs periodic progress checks
+    let (streaming_client, streaming_service_listener) =
+        new_streaming_service_client_listener_pair();
+    let aptos_data_client = MockAptosDataClient::new(false, false, false);
+    let data_streaming_service_config = DataStreamingServiceConfig {
+        progress_check_interval_ms: 24 * 60 * 60 * 1000,
+        ..Default::default()
+    };
+    let streaming_service = DataStreamingService::new(
+        data_streaming_service_config,
+        aptos_data_client,
+        streaming_service_listener,
+    );
+    tokio::spawn(streaming_service.start_service());
+
+    // Request an epoch ending stream and get a data stream listener
+    let mut stream_listener = streaming_client
+        .get_all_epoch_ending_ledger_infos(MIN_ADVERTISED_EPOCH_END)
+        .await
+        .unwrap();
+
+    // Verify the stream still completes (responses are processed as they arrive)
+    let mut next_expected_epoch = MIN_ADVERTISED_EPOCH_END;
+    loop {
+        let data_notification = get_data_notification(&mut stream_listener).await.unwrap();
+        match data_notification.data_payload {
+            DataPayload::EpochEndingLedgerInfos(ledger_infos_with_sigs) => {
+                next_expected_epoch += ledger_infos_with_sigs.len() as u64;
+            }
+            DataPayload::EndOfStream => {
+                return assert_eq!(next_expected_epoch, MAX_ADVERTISED_EPOCH_END + 1)
+            }
+            data_payload => unexpected_payload_type!(data_payload),
+        }
+    }
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_notifications_epoch_ending_multiple_streams() {
     // Create a new streaming client and service
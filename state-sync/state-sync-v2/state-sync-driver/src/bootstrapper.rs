@@ -10,7 +10,7 @@ use crate::{
     utils,
     utils::{SpeculativeStreamState, PENDING_DATA_LOG_FREQ_SECS},
 };
-use aptos_config::config::BootstrappingMode;
+use aptos_config::config::{BootstrappingMode, EpochVerificationMode};
 use aptos_data_client::GlobalDataSummary;
 use aptos_logger::{prelude::*, sample::SampleRate};
 use aptos_types::{
@@ -50,16 +50,23 @@ pub(crate) struct VerifiedEpochStates {
 
     // If the node has successfully verified the waypoint
     verified_waypoint: bool,
+
+    // Which epoch changes (before the waypoint) the node verifies
+    epoch_verification_mode: EpochVerificationMode,
 }
 
 impl VerifiedEpochStates {
-    pub fn new(latest_epoch_state: EpochState) -> Self {
+    pub fn new(
+        latest_epoch_state: EpochState,
+        epoch_verification_mode: EpochVerificationMode,
+    ) -> Self {
         Self {
             fetched_epoch_ending_ledger_infos: false,
             highest_fetched_epoch_ending_version: 0,
             latest_epoch_state,
             new_epoch_ending_ledger_infos: BTreeMap::new(),
             verified_waypoint: false,
+            epoch_verification_mode,
         }
     }
 
@@ -91,6 +98,11 @@ impl VerifiedEpochStates {
         epoch_ending_ledger_info: &LedgerInfoWithSignatures,
         waypoint: &Waypoint,
     ) -> Result<(), Error> {
+        // If we trust the waypoint, skip the epoch changes that precede it
+        if self.trusts_unverified_waypoint() {
+            return self.verify_trusted_waypoint(epoch_ending_ledger_info, waypoint);
+        }
+
         // Verify the ledger info against the latest epoch state
         self.latest_epoch_state
             .verify(epoch_ending_ledger_info)
@@ -119,6 +131,79 @@ impl VerifiedEpochStates {
         self.verify_waypoint(epoch_ending_ledger_info, waypoint)
     }
 
+    /// Restores the given (previously verified and persisted) epoch ending
+    /// ledger infos, so that they don't need to be fetched again. Ledger
+    /// infos for epochs that have already been synced are ignored. Returns
+    /// the number of restored ledger infos.
+    pub fn restore_epoch_ending_ledger_infos(
+        &mut self,
+        epoch_ending_ledger_infos: Vec<LedgerInfoWithSignatures>,
+        waypoint: &Waypoint,
+    ) -> usize {
+        let mut num_restored_ledger_infos = 0;
+        for epoch_ending_ledger_info in epoch_ending_ledger_infos {
+            if epoch_ending_ledger_info.ledger_info().epoch() < self.latest_epoch_state.epoch {
+                continue; // The epoch has already been synced
+            }
+
+            // The ledger infos are verified again, in case the storage is stale
+            if let Err(error) =
+                self.update_verified_epoch_states(&epoch_ending_ledger_info, waypoint)
+            {
+                warn!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                    "Failed to restore the persisted epoch ending ledger infos! Error: {:?}",
+                    error
+                )));
+                break;
+            }
+            num_restored_ledger_infos += 1;
+        }
+        num_restored_ledger_infos
+    }
+
+    /// Returns true iff the waypoint is trusted (instead of verifying the
+    /// epoch changes that precede it) and it hasn't been reached yet.
+    fn trusts_unverified_waypoint(&self) -> bool {
+        self.epoch_verification_mode == EpochVerificationMode::TrustWaypoint
+            && !self.verified_waypoint
+    }
+
+    /// Skips the given epoch ending ledger info if it precedes the trusted
+    /// waypoint. Otherwise, the ledger info must match the waypoint, and it
+    /// becomes the latest verified epoch state.
+    fn verify_trusted_waypoint(
+        &mut self,
+        epoch_ending_ledger_info: &LedgerInfoWithSignatures,
+        waypoint: &Waypoint,
+    ) -> Result<(), Error> {
+        let ledger_info = epoch_ending_ledger_info.ledger_info();
+        let ledger_info_version = ledger_info.version();
+        let waypoint_version = waypoint.version();
+        if ledger_info_version < waypoint_version {
+            return Ok(()); // The ledger info precedes the waypoint
+        } else if ledger_info_version > waypoint_version {
+            return Err(Error::VerificationError(format!(
+                "Failed to find the trusted waypoint! It must be an epoch ending waypoint. Waypoint version: {:?}, ledger info version: {:?}",
+                waypoint_version, ledger_info_version
+            )));
+        }
+
+        // The waypoint authenticates the ledger info (including the next epoch state)
+        self.verify_waypoint(epoch_ending_ledger_info, waypoint)?;
+        let next_epoch_state = ledger_info.next_epoch_state().ok_or_else(|| {
+            Error::VerificationError("The trusted waypoint is not epoch ending!".into())
+        })?;
+        self.highest_fetched_epoch_ending_version = ledger_info_version;
+        self.latest_epoch_state = next_epoch_state.clone();
+        self.insert_new_epoch_ending_ledger_info(epoch_ending_ledger_info.clone());
+
+        info!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+            "Trusted the waypoint without verifying the preceding epochs. Verifying epochs from: {:?}",
+            self.latest_epoch_state.epoch
+        )));
+        Ok(())
+    }
+
     /// Attempts to verify the waypoint using the new epoch ending ledger info
     fn verify_waypoint(
         &mut self,
@@ -325,7 +410,40 @@ impl<
         // Load the latest epoch state from storage
         let latest_epoch_state = utils::fetch_latest_epoch_state(storage.clone())
             .expect("Unable to fetch latest epoch state!");
-        let verified_epoch_states = VerifiedEpochStates::new(latest_epoch_state);
+        let mut verified_epoch_states = VerifiedEpochStates::new(
+            latest_epoch_state,
+            driver_configuration.config.epoch_verification_mode,
+        );
+
+        // If storage has already synced beyond the waypoint, it's verified
+        let latest_ledger_info = utils::fetch_latest_synced_ledger_info(storage.clone())
+            .expect("Unable to fetch latest synced ledger info!");
+        if latest_ledger_info.ledger_info().version() >= driver_configuration.waypoint.version() {
+            verified_epoch_states.set_verified_waypoint();
+        }
+
+        // Restore any epoch ending ledger infos fetched before a reboot
+        match metadata_storage.get_epoch_ending_ledger_infos() {
+            Ok(epoch_ending_ledger_infos) => {
+                let num_restored_ledger_infos = verified_epoch_states
+                    .restore_epoch_ending_ledger_infos(
+                        epoch_ending_ledger_infos,
+                        &driver_configuration.waypoint,
+                    );
+                if num_restored_ledger_infos > 0 {
+                    info!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                        "Restored {} persisted epoch ending ledger infos!",
+                        num_restored_ledger_infos
+                    )));
+                }
+            }
+            Err(error) => {
+                warn!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                    "Failed to read the persisted epoch ending ledger infos! Error: {:?}",
+                    error
+                )));
+            }
+        }
 
         Self {
             state_value_syncer: StateValueSyncer::new(),
@@ -673,7 +791,6 @@ impl<
         highest_synced_version: Version,
         highest_known_ledger_info: LedgerInfoWithSignatures,
     ) -> Result<(), Error> {
-        let highest_known_ledger_version = highest_known_ledger_info.ledger_info().version();
         let next_version = highest_synced_version.checked_add(1).ok_or_else(|| {
             Error::IntegerOverflow("The next output version has overflown!".into())
         })?;
@@ -681,24 +798,23 @@ impl<
             .verified_epoch_states
             .next_epoch_ending_version(highest_synced_version)
             .expect("No higher epoch ending version known!");
+
+        // Prove the data relative to the end of its epoch (rather than to the
+        // highest known ledger info), which is how the transaction backups prove it.
+        let proof_ledger_info = self
+            .verified_epoch_states
+            .get_epoch_ending_ledger_info(end_version)
+            .unwrap_or(highest_known_ledger_info);
+        let proof_version = proof_ledger_info.ledger_info().version();
         let data_stream = match self.driver_configuration.config.bootstrapping_mode {
             BootstrappingMode::ApplyTransactionOutputsFromGenesis => {
                 self.streaming_client
-                    .get_all_transaction_outputs(
-                        next_version,
-                        end_version,
-                        highest_known_ledger_version,
-                    )
+                    .get_all_transaction_outputs(next_version, end_version, proof_version)
                     .await?
             }
             BootstrappingMode::ExecuteTransactionsFromGenesis => {
                 self.streaming_client
-                    .get_all_transactions(
-                        next_version,
-                        end_version,
-                        highest_known_ledger_version,
-                        false,
-                    )
+                    .get_all_transactions(next_version, end_version, proof_version, false)
                     .await?
             }
             bootstrapping_mode => {
@@ -707,7 +823,7 @@ impl<
         };
         self.speculative_stream_state = Some(SpeculativeStreamState::new(
             utils::fetch_latest_epoch_state(self.storage.clone())?,
-            Some(highest_known_ledger_info),
+            Some(proof_ledger_info),
             highest_synced_version,
         ));
         self.active_data_stream = Some(data_stream);
@@ -997,6 +1113,7 @@ impl<
 
         // Verify the epoch change proofs, update our latest epoch state and
         // verify our waypoint.
+        let mut verified_ledger_infos = vec![];
         for epoch_ending_ledger_info in epoch_ending_ledger_infos {
             if let Err(error) = self.verified_epoch_states.update_verified_epoch_states(
                 &epoch_ending_ledger_info,
@@ -1009,6 +1126,27 @@ impl<
                 .await?;
                 return Err(error);
             }
+
+            // Ledger infos skipped before a trusted waypoint aren't kept
+            let version = epoch_ending_ledger_info.ledger_info().version();
+            if self
+                .verified_epoch_states
+                .get_epoch_ending_ledger_info(version)
+                .is_some()
+            {
+                verified_ledger_infos.push(epoch_ending_ledger_info);
+            }
+        }
+
+        // Persist the verified ledger infos so they survive a reboot
+        if let Err(error) = self
+            .metadata_storage
+            .update_epoch_ending_ledger_infos(&verified_ledger_infos)
+        {
+            warn!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                "Failed to persist the verified epoch ending ledger infos! Error: {:?}",
+                error
+            )));
         }
 
         // TODO(joshlind): do we want to preemptively notify certain components
@@ -15,12 +15,13 @@ use crate::{
         ConsensusNotificationHandler, ErrorNotification, ErrorNotificationListener,
         MempoolNotificationHandler,
     },
+    progress::SyncProgressTracker,
     storage_synchronizer::StorageSynchronizerInterface,
     utils,
     utils::PENDING_DATA_LOG_FREQ_SECS,
 };
-use aptos_config::config::{RoleType, StateSyncDriverConfig};
-use aptos_data_client::AptosDataClient;
+use aptos_config::config::{EpochVerificationMode, RoleType, StateSyncDriverConfig};
+use aptos_data_client::{AptosDataClient, GlobalDataSummary};
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_types::waypoint::Waypoint;
@@ -33,7 +34,10 @@ use data_streaming_service::streaming_client::{
 use event_notifications::EventSubscriptionService;
 use futures::StreamExt;
 use mempool_notifications::MempoolNotificationSender;
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
 use storage_interface::DbReader;
 use tokio::task::yield_now;
 use tokio::time::{interval, Duration};
@@ -106,6 +110,9 @@ pub struct StateSyncDriver<
     // The timestamp at which the driver started executing
     start_time: Option<SystemTime>,
 
+    // The tracker for the sync progress and estimated time remaining
+    sync_progress_tracker: SyncProgressTracker,
+
     // The interface to read from storage
     storage: Arc<dyn DbReader>,
 
@@ -162,6 +169,7 @@ impl<
             event_subscription_service,
             mempool_notification_handler,
             start_time: None,
+            sync_progress_tracker: SyncProgressTracker::default(),
             storage,
             storage_synchronizer,
         }
@@ -174,6 +182,19 @@ impl<
         )))
         .fuse();
 
+        // Report the epoch verification mode (it determines the node's security assumptions)
+        let epoch_verification_mode = self.driver_configuration.config.epoch_verification_mode;
+        metrics::set_gauge(
+            &metrics::EPOCH_VERIFICATION_MODE,
+            epoch_verification_mode.to_label(),
+            1,
+        );
+        if epoch_verification_mode == EpochVerificationMode::TrustWaypoint {
+            warn!(LogSchema::new(LogEntry::Driver).message(
+                "The epoch changes preceding the waypoint will not be verified! The waypoint is trusted."
+            ));
+        }
+
         // Start the driver
         info!(LogSchema::new(LogEntry::Driver).message("Started the state sync v2 driver!"));
         self.start_time = Some(SystemTime::now());
@@ -192,6 +213,7 @@ impl<
                     self.handle_error_notification(notification).await;
                 }
                 _ = progress_check_interval.select_next_some() => {
+                    aptos_liveness::heartbeat("state_sync_driver");
                     self.drive_progress().await;
                 }
             }
@@ -539,6 +561,45 @@ impl<
         }
     }
 
+    /// Updates the sync progress towards the highest advertised version
+    fn update_sync_progress(&mut self, global_data_summary: &GlobalDataSummary) {
+        let target_version = match global_data_summary
+            .advertised_data
+            .highest_synced_ledger_info()
+        {
+            Some(ledger_info) => ledger_info.ledger_info().version(),
+            None => return,
+        };
+        let synced_version = match utils::fetch_latest_synced_version(self.storage.clone()) {
+            Ok(synced_version) => synced_version,
+            Err(error) => {
+                warn!(LogSchema::new(LogEntry::Driver)
+                    .error(&error)
+                    .message("Unable to fetch the latest synced version!"));
+                return;
+            }
+        };
+
+        let mode = if self.check_if_consensus_executing() {
+            ExecutingComponent::Consensus.get_label()
+        } else if self.bootstrapper.is_bootstrapped() {
+            self.driver_configuration
+                .config
+                .continuous_syncing_mode
+                .to_label()
+        } else {
+            self.driver_configuration
+                .config
+                .bootstrapping_mode
+                .to_label()
+        };
+
+        self.sync_progress_tracker
+            .update_synced_version(synced_version, Instant::now());
+        self.sync_progress_tracker
+            .update_metrics(synced_version, target_version, mode);
+    }
+
     /// Checks that state sync is making progress
     async fn drive_progress(&mut self) {
         // Fetch the global data summary and verify we have active peers
@@ -550,6 +611,9 @@ impl<
             return self.check_auto_bootstrapping().await;
         }
 
+        // Update the sync progress metrics
+        self.update_sync_progress(&global_data_summary);
+
         // Check the progress of any sync requests
         if let Err(error) = self.check_sync_request_progress().await {
             error!(LogSchema::new(LogEntry::Driver)
@@ -12,7 +12,7 @@ use crate::{
     storage_synchronizer::StorageSynchronizer,
 };
 use aptos_config::config::NodeConfig;
-use aptos_data_client::aptosnet::AptosNetDataClient;
+use aptos_data_client::AptosDataClient;
 use aptos_infallible::Mutex;
 use aptos_types::{move_resource::MoveStorage, waypoint::Waypoint};
 use consensus_notifications::ConsensusNotificationListener;
@@ -38,6 +38,7 @@ impl DriverFactory {
     /// Creates and spawns a new state sync driver
     pub fn create_and_spawn_driver<
         ChunkExecutor: ChunkExecutorTrait + 'static,
+        DataClient: AptosDataClient + Send + Clone + 'static,
         MempoolNotifier: MempoolNotificationSender + 'static,
         MetadataStorage: MetadataStorageInterface + Clone + Send + Sync + 'static,
     >(
@@ -50,7 +51,7 @@ impl DriverFactory {
         metadata_storage: MetadataStorage,
         consensus_listener: ConsensusNotificationListener,
         mut event_subscription_service: EventSubscriptionService,
-        aptos_data_client: AptosNetDataClient,
+        aptos_data_client: DataClient,
         streaming_service_client: StreamingServiceClient,
     ) -> Self {
         // Notify subscribers of the initial on-chain config values
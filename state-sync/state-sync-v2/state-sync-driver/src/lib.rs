@@ -13,6 +13,7 @@ mod logging;
 pub mod metadata_storage;
 pub mod metrics;
 mod notification_handlers;
+mod progress;
 mod storage_synchronizer;
 mod utils;
 
@@ -11,10 +11,10 @@ use aptos_types::ledger_info::LedgerInfoWithSignatures;
 use schemadb::{
     define_schema,
     schema::{KeyCodec, ValueCodec},
-    ColumnFamilyName, Options, SchemaBatch, DB,
+    ColumnFamilyName, Options, ReadOptions, SchemaBatch, DB,
 };
 use serde::{Deserialize, Serialize};
-use std::{path::Path, sync::Arc, time::Instant};
+use std::{collections::BTreeMap, path::Path, sync::Arc, time::Instant};
 
 /// The metadata storage interface required by state sync. This enables
 /// state sync to handle failures and reboots during critical parts
@@ -47,6 +47,17 @@ pub trait MetadataStorageInterface {
         last_persisted_state_value_index: u64,
         snapshot_sync_completed: bool,
     ) -> Result<(), Error>;
+
+    /// Returns all epoch ending ledger infos that were previously fetched and
+    /// verified by the bootstrapper (ordered by epoch).
+    fn get_epoch_ending_ledger_infos(&self) -> Result<Vec<LedgerInfoWithSignatures>, Error>;
+
+    /// Persists the given epoch ending ledger infos (once they've been verified),
+    /// so that they don't need to be fetched again after a reboot.
+    fn update_epoch_ending_ledger_infos(
+        &self,
+        epoch_ending_ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<(), Error>;
 }
 
 /// The name of the state sync db file
@@ -106,10 +117,13 @@ impl PersistentMetadataStorage {
                     ))
                 })?;
         match maybe_metadata_value {
-            Some(metadata_value) => {
-                let MetadataValue::StateSnapshotSync(snapshot_progress) = metadata_value;
+            Some(MetadataValue::StateSnapshotSync(snapshot_progress)) => {
                 Ok(Some(snapshot_progress))
             }
+            Some(metadata_value) => Err(Error::StorageError(format!(
+                "Found an unexpected metadata value for key: {:?}. Value: {:?}",
+                metadata_key, metadata_value
+            ))),
             None => Ok(None),
         }
     }
@@ -142,16 +156,26 @@ impl PersistentMetadataStorage {
         &self,
         metadata_key: MetadataKey,
         metadata_value: MetadataValue,
+    ) -> Result<(), Error> {
+        self.commit_key_values(vec![(metadata_key, metadata_value)])
+    }
+
+    /// Write the key value pairs to the database (atomically)
+    fn commit_key_values(
+        &self,
+        metadata_key_values: Vec<(MetadataKey, MetadataValue)>,
     ) -> Result<(), Error> {
         // Create the schema batch
         let batch = SchemaBatch::new();
-        batch
-            .put::<MetadataSchema>(&metadata_key, &metadata_value)
-            .map_err(|error| {
-                Error::StorageError(format!(
-                    "Failed to batch put the metadata key and value. Key: {:?}, Value: {:?}. Error: {:?}", metadata_key, metadata_value, error
-                ))
-            })?;
+        for (metadata_key, metadata_value) in metadata_key_values {
+            batch
+                .put::<MetadataSchema>(&metadata_key, &metadata_value)
+                .map_err(|error| {
+                    Error::StorageError(format!(
+                        "Failed to batch put the metadata key and value. Key: {:?}, Value: {:?}. Error: {:?}", metadata_key, metadata_value, error
+                    ))
+                })?;
+        }
 
         // Write the schema batch to the database
         self.database.write_schemas(batch).map_err(|error| {
@@ -210,6 +234,54 @@ impl MetadataStorageInterface for PersistentMetadataStorage {
         // Insert the new key/value pair
         self.commit_key_value(metadata_key, metadata_value)
     }
+
+    fn get_epoch_ending_ledger_infos(&self) -> Result<Vec<LedgerInfoWithSignatures>, Error> {
+        let mut iterator = self
+            .database
+            .iter::<MetadataSchema>(ReadOptions::default())
+            .map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to create a metadata iterator. Error: {:?}",
+                    error
+                ))
+            })?;
+        iterator.seek_to_first();
+
+        // Collect the epoch ending ledger infos (the keys are not stored in epoch order)
+        let mut epoch_ending_ledger_infos = BTreeMap::new();
+        for result in iterator {
+            let (metadata_key, metadata_value) = result.map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to read a metadata key and value. Error: {:?}",
+                    error
+                ))
+            })?;
+            if let (
+                MetadataKey::EpochEndingLedgerInfo(epoch),
+                MetadataValue::EpochEndingLedgerInfo(ledger_info),
+            ) = (metadata_key, metadata_value)
+            {
+                epoch_ending_ledger_infos.insert(epoch, ledger_info);
+            }
+        }
+        Ok(epoch_ending_ledger_infos.into_values().collect())
+    }
+
+    fn update_epoch_ending_ledger_infos(
+        &self,
+        epoch_ending_ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<(), Error> {
+        let metadata_key_values = epoch_ending_ledger_infos
+            .iter()
+            .map(|ledger_info| {
+                (
+                    MetadataKey::EpochEndingLedgerInfo(ledger_info.ledger_info().epoch()),
+                    MetadataValue::EpochEndingLedgerInfo(ledger_info.clone()),
+                )
+            })
+            .collect();
+        self.commit_key_values(metadata_key_values)
+    }
 }
 
 /// A simple struct for recording the progress of a state snapshot sync
@@ -237,7 +309,8 @@ pub mod database_schema {
     #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
     #[repr(u8)]
     pub enum MetadataKey {
-        StateSnapshotSync, // A state snapshot sync that was started
+        StateSnapshotSync,          // A state snapshot sync that was started
+        EpochEndingLedgerInfo(u64), // A verified ledger info that ends the given epoch
     }
 
     /// A metadata value that can be inserted into the database
@@ -245,6 +318,7 @@ pub mod database_schema {
     #[repr(u8)]
     pub enum MetadataValue {
         StateSnapshotSync(StateSnapshotProgress), // A state snapshot sync progress marker
+        EpochEndingLedgerInfo(LedgerInfoWithSignatures), // A verified epoch ending ledger info
     }
 
     impl KeyCodec<MetadataSchema> for MetadataKey {
@@ -15,6 +15,9 @@ pub const STORAGE_SYNCHRONIZER_PENDING_DATA: &str = "storage_synchronizer_pendin
 pub const STORAGE_SYNCHRONIZER_APPLY_CHUNK: &str = "apply_chunk";
 pub const STORAGE_SYNCHRONIZER_EXECUTE_CHUNK: &str = "execute_chunk";
 pub const STORAGE_SYNCHRONIZER_COMMIT_CHUNK: &str = "commit_chunk";
+pub const SYNC_PROGRESS_TARGET_VERSION: &str = "target_version";
+pub const SYNC_PROGRESS_VERSIONS_PER_SEC: &str = "versions_per_sec";
+pub const SYNC_PROGRESS_ESTIMATED_SECS_REMAINING: &str = "estimated_secs_remaining";
 
 /// An enum representing the component currently executing
 pub enum ExecutingComponent {
@@ -136,6 +139,36 @@ pub static STORAGE_SYNCHRONIZER_OPERATIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Gauge for the epoch verification mode used when bootstrapping (set to 1)
+pub static EPOCH_VERIFICATION_MODE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_state_sync_epoch_verification_mode",
+        "The epoch verification mode used by state sync when bootstrapping",
+        &["mode"]
+    )
+    .unwrap()
+});
+
+/// Gauges tracking the sync progress towards the highest advertised version
+pub static SYNC_PROGRESS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_state_sync_progress",
+        "Gauges related to the sync progress towards the highest advertised version",
+        &["label"]
+    )
+    .unwrap()
+});
+
+/// Gauge for the current syncing mode (the active mode is set to 1)
+pub static SYNC_MODE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_state_sync_mode",
+        "The current syncing mode of state sync (the active mode is set to 1)",
+        &["mode"]
+    )
+    .unwrap()
+});
+
 /// Increments the given counter with the provided label values.
 pub fn increment_counter(counter: &Lazy<IntCounterVec>, label: &str) {
     counter.with_label_values(&[label]).inc();
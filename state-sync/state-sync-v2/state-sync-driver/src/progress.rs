@@ -0,0 +1,121 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metrics;
+use aptos_types::transaction::Version;
+use std::time::{Duration, Instant};
+
+// The minimum time between two samples of the synced version
+const MIN_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+// The weight of the latest sample in the moving average of the sync rate
+const SYNC_RATE_AVERAGE_WEIGHT: f64 = 0.5;
+
+// The longest time remaining that is estimated. A stalled sync decays the
+// rate towards zero, and the estimate towards infinity.
+const MAX_ESTIMATED_TIME_REMAINING: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Tracks the progress of state sync towards the highest advertised version,
+/// and estimates how long it will take to catch up.
+#[derive(Default)]
+pub struct SyncProgressTracker {
+    // The synced version and the time it was sampled at
+    last_sample: Option<(Version, Instant)>,
+
+    // The moving average of the number of versions synced per second
+    versions_per_sec: Option<f64>,
+
+    // The label of the last reported syncing mode
+    last_mode: Option<&'static str>,
+}
+
+impl SyncProgressTracker {
+    /// Updates the sync rate with the given synced version (if enough time
+    /// has passed since the last sample)
+    pub fn update_synced_version(&mut self, synced_version: Version, now: Instant) {
+        match self.last_sample {
+            Some((last_version, last_time)) => {
+                let elapsed = now.saturating_duration_since(last_time);
+                if elapsed < MIN_SAMPLE_INTERVAL {
+                    return;
+                }
+
+                let versions_synced = synced_version.saturating_sub(last_version);
+                let sample_rate = versions_synced as f64 / elapsed.as_secs_f64();
+                self.versions_per_sec = Some(match self.versions_per_sec {
+                    Some(versions_per_sec) => {
+                        versions_per_sec * (1.0 - SYNC_RATE_AVERAGE_WEIGHT)
+                            + sample_rate * SYNC_RATE_AVERAGE_WEIGHT
+                    }
+                    None => sample_rate,
+                });
+                self.last_sample = Some((synced_version, now));
+            }
+            None => self.last_sample = Some((synced_version, now)),
+        }
+    }
+
+    /// Returns the number of versions synced per second (if known)
+    pub fn versions_per_sec(&self) -> Option<f64> {
+        self.versions_per_sec
+    }
+
+    /// Returns the estimated time to sync to the target version. Returns `None`
+    /// if the sync rate is unknown, or if too little progress is being made to
+    /// tell (i.e., the estimate exceeds `MAX_ESTIMATED_TIME_REMAINING`).
+    pub fn estimated_time_remaining(
+        &self,
+        synced_version: Version,
+        target_version: Version,
+    ) -> Option<Duration> {
+        let versions_remaining = target_version.saturating_sub(synced_version);
+        if versions_remaining == 0 {
+            return Some(Duration::ZERO);
+        }
+
+        let versions_per_sec = self.versions_per_sec?;
+        let secs_remaining = versions_remaining as f64 / versions_per_sec;
+        // This also rejects the NaN and infinite estimates of a zero rate
+        if !(0.0..=MAX_ESTIMATED_TIME_REMAINING.as_secs_f64()).contains(&secs_remaining) {
+            return None;
+        }
+        Some(Duration::from_secs_f64(secs_remaining))
+    }
+
+    /// Updates the sync progress metrics with the given versions and syncing mode
+    pub fn update_metrics(
+        &mut self,
+        synced_version: Version,
+        target_version: Version,
+        mode: &'static str,
+    ) {
+        metrics::set_gauge(
+            &metrics::SYNC_PROGRESS,
+            metrics::SYNC_PROGRESS_TARGET_VERSION,
+            target_version,
+        );
+        metrics::set_gauge(
+            &metrics::SYNC_PROGRESS,
+            metrics::SYNC_PROGRESS_VERSIONS_PER_SEC,
+            self.versions_per_sec.unwrap_or_default() as u64,
+        );
+
+        // Unknown estimates are reported as -1
+        let estimated_secs_remaining = self
+            .estimated_time_remaining(synced_version, target_version)
+            .map(|time_remaining| time_remaining.as_secs() as i64)
+            .unwrap_or(-1);
+        metrics::SYNC_PROGRESS
+            .with_label_values(&[metrics::SYNC_PROGRESS_ESTIMATED_SECS_REMAINING])
+            .set(estimated_secs_remaining);
+
+        // Mark the current mode as the active one
+        if self.last_mode != Some(mode) {
+            if let Some(last_mode) = self.last_mode {
+                metrics::set_gauge(&metrics::SYNC_MODE, last_mode, 0);
+            }
+            metrics::set_gauge(&metrics::SYNC_MODE, mode, 1);
+            self.last_mode = Some(mode);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    bootstrapper::{Bootstrapper, GENESIS_TRANSACTION_VERSION},
+    bootstrapper::{Bootstrapper, VerifiedEpochStates, GENESIS_TRANSACTION_VERSION},
     driver::DriverConfiguration,
     error::Error,
     tests::{
@@ -12,15 +12,16 @@ use crate::{
         },
         utils::{
             create_data_stream_listener, create_empty_epoch_state, create_epoch_ending_ledger_info,
-            create_full_node_driver_configuration, create_global_summary,
+            create_epoch_state, create_full_node_driver_configuration, create_global_summary,
             create_output_list_with_proof, create_random_epoch_ending_ledger_info,
             create_transaction_info, create_transaction_list_with_proof,
         },
     },
 };
-use aptos_config::config::BootstrappingMode;
+use aptos_config::config::{BootstrappingMode, EpochVerificationMode};
 use aptos_data_client::GlobalDataSummary;
 use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
     transaction::{TransactionOutputListWithProof, Version},
     waypoint::Waypoint,
 };
@@ -413,6 +414,44 @@ async fn test_data_stream_transaction_outputs() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn test_data_stream_transaction_outputs_epoch_proof() {
+    // Create test data
+    let epoch_ending_version = 45;
+    let highest_version = 1000;
+    let highest_ledger_info = create_random_epoch_ending_ledger_info(highest_version, 1);
+
+    // Create a driver configuration with a genesis waypoint and output syncing
+    let mut driver_configuration = create_full_node_driver_configuration();
+    driver_configuration.config.bootstrapping_mode =
+        BootstrappingMode::ApplyTransactionOutputsFromGenesis;
+
+    // Create the mock streaming client and expect the outputs of the first epoch
+    // to be proven relative to its epoch ending ledger info (not the highest one)
+    let mut mock_streaming_client = create_mock_streaming_client();
+    let (_notification_sender, data_stream_listener) = create_data_stream_listener();
+    mock_streaming_client
+        .expect_get_all_transaction_outputs()
+        .times(1)
+        .with(eq(1), eq(epoch_ending_version), eq(epoch_ending_version))
+        .return_once(move |_, _, _| Ok(data_stream_listener));
+
+    // Create the bootstrapper
+    let mut bootstrapper = create_bootstrapper(driver_configuration, mock_streaming_client, true);
+
+    // Insert an epoch ending ledger info into the verified states of the bootstrapper
+    manipulate_verified_epoch_states(&mut bootstrapper, true, true, Some(epoch_ending_version));
+
+    // Create a global data summary that advertises a higher ledger info
+    let mut global_data_summary = create_global_summary(1);
+    global_data_summary.advertised_data.synced_ledger_infos = vec![highest_ledger_info];
+
+    // Drive progress to initialize the transaction output stream
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 async fn test_fetch_epoch_ending_ledger_infos() {
     // Create a driver configuration with a genesis waypoint and a stream timeout of 1 second
@@ -875,6 +914,85 @@ async fn test_waypoint_mismatch() {
     assert_matches!(error, Error::VerificationError(_));
 }
 
+#[test]
+fn test_trusted_waypoint() {
+    // Create a waypoint at the end of epoch 2
+    let waypoint_ledger_info = create_random_epoch_ending_ledger_info(20, 2);
+    let waypoint = Waypoint::new_any(waypoint_ledger_info.ledger_info());
+
+    // Create the epoch ending ledger infos preceding the waypoint. These
+    // can't be verified against the latest epoch state (it's for epoch 10).
+    let latest_epoch_state = create_epoch_state(10);
+    let preceding_ledger_info = create_random_epoch_ending_ledger_info(10, 1);
+
+    // Verify the preceding ledger info fails verification if all epochs are verified
+    let mut verified_epoch_states = VerifiedEpochStates::new(
+        latest_epoch_state.clone(),
+        EpochVerificationMode::VerifyAllEpochs,
+    );
+    let error = verified_epoch_states
+        .update_verified_epoch_states(&preceding_ledger_info, &waypoint)
+        .unwrap_err();
+    assert_matches!(error, Error::VerificationError(_));
+
+    // Verify the preceding ledger info is skipped if the waypoint is trusted
+    let mut verified_epoch_states =
+        VerifiedEpochStates::new(latest_epoch_state, EpochVerificationMode::TrustWaypoint);
+    verified_epoch_states
+        .update_verified_epoch_states(&preceding_ledger_info, &waypoint)
+        .unwrap();
+    assert!(!verified_epoch_states.verified_waypoint());
+    assert_none!(verified_epoch_states.get_highest_known_ledger_info());
+
+    // Verify an invalid ledger info at the waypoint version is rejected
+    let invalid_ledger_info = create_random_epoch_ending_ledger_info(20, 2);
+    let error = verified_epoch_states
+        .update_verified_epoch_states(&invalid_ledger_info, &waypoint)
+        .unwrap_err();
+    assert_matches!(error, Error::VerificationError(_));
+
+    // Verify the waypoint ledger info is trusted
+    verified_epoch_states
+        .update_verified_epoch_states(&waypoint_ledger_info, &waypoint)
+        .unwrap();
+    assert!(verified_epoch_states.verified_waypoint());
+    assert_eq!(
+        verified_epoch_states.all_epoch_ending_ledger_infos(),
+        vec![waypoint_ledger_info]
+    );
+}
+
+#[test]
+fn test_restore_epoch_ending_ledger_infos() {
+    // Create a waypoint at the end of epoch 1
+    let waypoint_ledger_info = create_random_epoch_ending_ledger_info(20, 1);
+    let waypoint = Waypoint::new_any(waypoint_ledger_info.ledger_info());
+
+    // Create the persisted ledger infos: one for an epoch that was already
+    // synced, one at the waypoint, and one that fails verification.
+    let synced_ledger_info = create_random_epoch_ending_ledger_info(10, 0);
+    let invalid_ledger_info = create_random_epoch_ending_ledger_info(30, 2);
+    let persisted_ledger_infos = vec![
+        synced_ledger_info,
+        waypoint_ledger_info.clone(),
+        invalid_ledger_info,
+    ];
+
+    // Restore the ledger infos and verify only the waypoint ledger info is kept
+    let mut verified_epoch_states = VerifiedEpochStates::new(
+        create_epoch_state(1),
+        EpochVerificationMode::VerifyAllEpochs,
+    );
+    let num_restored_ledger_infos =
+        verified_epoch_states.restore_epoch_ending_ledger_infos(persisted_ledger_infos, &waypoint);
+    assert_eq!(num_restored_ledger_infos, 1);
+    assert!(verified_epoch_states.verified_waypoint());
+    assert_eq!(
+        verified_epoch_states.get_highest_known_ledger_info(),
+        Some(waypoint_ledger_info)
+    );
+}
+
 #[tokio::test]
 async fn test_waypoint_must_be_verified() {
     // Create a driver configuration with a genesis waypoint and a stream timeout of 1 second
@@ -961,6 +1079,7 @@ fn create_bootstrapper(
     metadata_storage
         .expect_previous_snapshot_sync_target()
         .returning(|| Ok(None));
+    expect_epoch_ending_ledger_info_storage(&mut metadata_storage, vec![]);
 
     // Create the mock db reader with only genesis loaded
     let mut mock_database_reader = create_mock_db_reader();
@@ -987,13 +1106,16 @@ fn create_bootstrapper(
 fn create_bootstrapper_with_storage(
     driver_configuration: DriverConfiguration,
     mock_streaming_client: MockStreamingClient,
-    mock_metadata_storage: MockMetadataStorage,
+    mut mock_metadata_storage: MockMetadataStorage,
     latest_synced_version: Version,
     expect_reset_executor: bool,
 ) -> Bootstrapper<MockMetadataStorage, MockStorageSynchronizer, MockStreamingClient> {
     // Initialize the logger for tests
     aptos_logger::Logger::init_for_testing();
 
+    // Nothing was persisted before the bootstrapper was created
+    expect_epoch_ending_ledger_info_storage(&mut mock_metadata_storage, vec![]);
+
     // Create the mock storage synchronizer
     let mock_storage_synchronizer = create_ready_storage_synchronizer(expect_reset_executor);
 
@@ -1018,6 +1140,19 @@ fn create_bootstrapper_with_storage(
     )
 }
 
+/// Sets the expectations for the persisted epoch ending ledger infos
+fn expect_epoch_ending_ledger_info_storage(
+    metadata_storage: &mut MockMetadataStorage,
+    persisted_ledger_infos: Vec<LedgerInfoWithSignatures>,
+) {
+    metadata_storage
+        .expect_get_epoch_ending_ledger_infos()
+        .return_once(move || Ok(persisted_ledger_infos));
+    metadata_storage
+        .expect_update_epoch_ending_ledger_infos()
+        .returning(|_| Ok(()));
+}
+
 /// Drives progress for the given bootstrapper. If `until_bootstrapped`
 /// is true this method will continue to drive the bootstrapper until
 /// bootstrapping is complete.
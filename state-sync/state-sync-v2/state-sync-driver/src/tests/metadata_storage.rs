@@ -6,7 +6,10 @@ use crate::{
         database_schema::{MetadataKey, MetadataSchema, MetadataValue},
         MetadataStorageInterface, PersistentMetadataStorage, StateSnapshotProgress,
     },
-    tests::utils::{create_epoch_ending_ledger_info, create_ledger_info_at_version},
+    tests::utils::{
+        create_epoch_ending_ledger_info, create_ledger_info_at_version,
+        create_random_epoch_ending_ledger_info,
+    },
 };
 use aptos_temppath::TempPath;
 use claims::{assert_err, assert_none};
@@ -89,6 +92,55 @@ fn test_create_then_open() {
     );
 }
 
+#[test]
+fn test_epoch_ending_ledger_infos() {
+    // Create a new metadata storage and verify no ledger infos are found
+    let tmp_dir = TempPath::new();
+    let metadata_storage = PersistentMetadataStorage::new(tmp_dir.path());
+    assert!(metadata_storage
+        .get_epoch_ending_ledger_infos()
+        .unwrap()
+        .is_empty());
+
+    // Persist a state snapshot progress and several epoch ending ledger infos (out of order)
+    let target_ledger_info = create_ledger_info_at_version(12345);
+    metadata_storage
+        .update_last_persisted_state_value_index(&target_ledger_info, 100, false)
+        .unwrap();
+    let ledger_info_epoch_1 = create_random_epoch_ending_ledger_info(100, 1);
+    let ledger_info_epoch_2 = create_random_epoch_ending_ledger_info(200, 2);
+    let ledger_info_epoch_300 = create_random_epoch_ending_ledger_info(30000, 300);
+    metadata_storage
+        .update_epoch_ending_ledger_infos(&[ledger_info_epoch_300.clone()])
+        .unwrap();
+    metadata_storage
+        .update_epoch_ending_ledger_infos(&[
+            ledger_info_epoch_1.clone(),
+            ledger_info_epoch_2.clone(),
+        ])
+        .unwrap();
+
+    // Drop the handle to the storage (mimic a reboot)
+    drop(metadata_storage);
+
+    // Reopen the storage and verify the ledger infos are ordered by epoch
+    let metadata_storage = PersistentMetadataStorage::new(tmp_dir.path());
+    assert_eq!(
+        metadata_storage.get_epoch_ending_ledger_infos().unwrap(),
+        vec![
+            ledger_info_epoch_1,
+            ledger_info_epoch_2,
+            ledger_info_epoch_300
+        ]
+    );
+
+    // Verify the state snapshot progress is unaffected
+    assert_eq!(
+        Some(target_ledger_info),
+        metadata_storage.previous_snapshot_sync_target().unwrap()
+    );
+}
+
 #[test]
 fn test_metadata_schema_encode_decode() {
     assert_encode_decode::<MetadataSchema>(
@@ -99,6 +151,10 @@ fn test_metadata_schema_encode_decode() {
             snapshot_sync_completed: false,
         }),
     );
+    assert_encode_decode::<MetadataSchema>(
+        &MetadataKey::EpochEndingLedgerInfo(10),
+        &MetadataValue::EpochEndingLedgerInfo(create_epoch_ending_ledger_info()),
+    );
 }
 
 #[test]
@@ -327,6 +327,13 @@ mock! {
             last_persisted_state_value_index: u64,
             snapshot_sync_completed: bool,
         ) -> Result<(), Error>;
+
+        fn get_epoch_ending_ledger_infos(&self) -> Result<Vec<LedgerInfoWithSignatures>, Error>;
+
+        fn update_epoch_ending_ledger_infos(
+            &self,
+            epoch_ending_ledger_infos: &[LedgerInfoWithSignatures],
+        ) -> Result<(), Error>;
     }
 
     impl Clone for MetadataStorage {
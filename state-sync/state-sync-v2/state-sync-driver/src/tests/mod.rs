@@ -7,5 +7,6 @@ mod driver;
 mod driver_factory;
 mod metadata_storage;
 mod mocks;
+mod progress;
 mod storage_synchronizer;
 mod utils;
@@ -0,0 +1,63 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::progress::SyncProgressTracker;
+use claims::{assert_none, assert_some_eq};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_sync_rate_and_estimate() {
+    // Create a progress tracker and verify the rate is unknown
+    let mut progress_tracker = SyncProgressTracker::default();
+    let start_time = Instant::now();
+    progress_tracker.update_synced_version(1000, start_time);
+    assert_none!(progress_tracker.versions_per_sec());
+    assert_none!(progress_tracker.estimated_time_remaining(1000, 2000));
+
+    // Verify samples taken too soon are ignored
+    progress_tracker.update_synced_version(1500, start_time + Duration::from_secs(1));
+    assert_none!(progress_tracker.versions_per_sec());
+
+    // Sync 100 versions per second and verify the estimate
+    progress_tracker.update_synced_version(2000, start_time + Duration::from_secs(10));
+    assert_some_eq!(progress_tracker.versions_per_sec(), 100.0);
+    assert_some_eq!(
+        progress_tracker.estimated_time_remaining(2000, 5000),
+        Duration::from_secs(30)
+    );
+
+    // Verify the estimate is zero once the target is reached
+    assert_some_eq!(
+        progress_tracker.estimated_time_remaining(5000, 5000),
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn test_stalled_sync() {
+    // Create a progress tracker that makes no progress
+    let mut progress_tracker = SyncProgressTracker::default();
+    let start_time = Instant::now();
+    progress_tracker.update_synced_version(1000, start_time);
+    progress_tracker.update_synced_version(1000, start_time + Duration::from_secs(10));
+
+    // Verify no estimate is given
+    assert_some_eq!(progress_tracker.versions_per_sec(), 0.0);
+    assert_none!(progress_tracker.estimated_time_remaining(1000, 2000));
+}
+
+#[test]
+fn test_decaying_sync_rate() {
+    // Create a progress tracker that syncs slower and slower
+    let mut progress_tracker = SyncProgressTracker::default();
+    let start_time = Instant::now();
+    progress_tracker.update_synced_version(0, start_time);
+    progress_tracker.update_synced_version(1, start_time + Duration::from_secs(10));
+    for sample in 2..2000 {
+        progress_tracker.update_synced_version(1, start_time + Duration::from_secs(10 * sample));
+    }
+
+    // Verify the decayed rate gives no estimate, rather than an overflow
+    assert!(progress_tracker.versions_per_sec().unwrap() < f64::MIN_POSITIVE);
+    assert_none!(progress_tracker.estimated_time_remaining(1, u64::MAX));
+}
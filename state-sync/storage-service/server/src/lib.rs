@@ -7,10 +7,10 @@ use crate::metrics::increment_network_frame_overflow;
 use crate::{
     logging::{LogEntry, LogSchema},
     metrics::{increment_counter, start_timer, LRU_CACHE_HIT, LRU_CACHE_PROBE},
-    network::{ResponseSender, StorageServiceNetworkEvents},
+    network::{BandwidthLimiter, ResponseSender, StorageServiceNetworkEvents},
 };
 use ::network::ProtocolId;
-use aptos_config::config::StorageServiceConfig;
+use aptos_config::{config::StorageServiceConfig, network_id::NetworkId};
 use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
 use aptos_time_service::{TimeService, TimeServiceTrait};
@@ -224,6 +224,9 @@ pub struct StorageServiceServer<T> {
     // from the cached storage summary because these responses should
     // never change while the storage summary changes over time.
     lru_storage_cache: Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>,
+
+    // The limiter for the bandwidth used by responses
+    bandwidth_limiter: Arc<BandwidthLimiter>,
 }
 
 impl<T: StorageReaderInterface> StorageServiceServer<T> {
@@ -232,6 +235,7 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         executor: Handle,
         storage: T,
         time_service: TimeService,
+        network_id: NetworkId,
         network_requests: StorageServiceNetworkEvents,
     ) -> Self {
         let bounded_executor =
@@ -241,6 +245,11 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         let lru_storage_cache = Arc::new(Mutex::new(LruCache::new(
             config.max_lru_cache_size as usize,
         )));
+        let bandwidth_limiter = Arc::new(BandwidthLimiter::new(
+            &config,
+            network_id,
+            time_service.clone(),
+        ));
 
         Self {
             config,
@@ -251,9 +260,15 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
             cached_storage_server_summary,
             data_subscriptions,
             lru_storage_cache,
+            bandwidth_limiter,
         }
     }
 
+    /// Returns the limiter for the bandwidth used by responses, e.g. to update its limits
+    pub fn bandwidth_limiter(&self) -> Arc<BandwidthLimiter> {
+        self.bandwidth_limiter.clone()
+    }
+
     /// Spawns a non-terminating task that refreshes the cached storage server summary
     async fn spawn_storage_summary_refresher(&mut self) {
         let cached_storage_server_summary = self.cached_storage_server_summary.clone();
@@ -366,7 +381,7 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         // Handle the storage requests
         while let Some(request) = self.network_requests.next().await {
             // Log the request
-            let (peer, protocol, request, response_sender) = request;
+            let (peer, protocol, request, mut response_sender) = request;
             trace!(LogSchema::new(LogEntry::ReceivedStorageRequest)
                 .request(&request)
                 .message(&format!(
@@ -374,6 +389,12 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
                     peer, protocol,
                 )));
 
+            // Wait for the bandwidth used by previous responses before handling
+            // the request, and charge the response to the limiter. This also
+            // applies to the response of a data subscription, whenever it is sent.
+            self.bandwidth_limiter.wait_for_bandwidth().await;
+            response_sender.set_bandwidth_limiter(self.bandwidth_limiter.clone());
+
             // All handler methods are currently CPU-bound and synchronous
             // I/O-bound, so we want to spawn on the blocking thread pool to
             // avoid starving other async tasks on the same runtime.
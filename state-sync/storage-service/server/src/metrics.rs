@@ -11,6 +11,16 @@ use once_cell::sync::Lazy;
 pub const LRU_CACHE_HIT: &str = "lru_cache_hit";
 pub const LRU_CACHE_PROBE: &str = "lru_cache_probe";
 
+/// Bytes sent and seconds throttled by the bandwidth limiter
+pub static BANDWIDTH_LIMITER: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_storage_service_server_bandwidth_limiter",
+        "Bytes sent and seconds throttled by the storage server bandwidth limiter",
+        &["network", "metric"]
+    )
+    .unwrap()
+});
+
 /// Counter for lru cache events in the storage service (server-side)
 pub static LRU_CACHE_EVENT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::metrics;
-use aptos_config::config::StorageServiceConfig;
+use aptos_config::{config::StorageServiceConfig, network_id::NetworkId};
+use aptos_infallible::{Mutex, MutexGuard};
+use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::PeerId;
 use bytes::Bytes;
 use channel::{aptos_channel, message_queues::QueueStyle};
@@ -18,12 +20,16 @@ use network::{
 };
 use std::{
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 use storage_service_types::requests::StorageServiceRequest;
 use storage_service_types::responses::StorageServiceResponse;
 use storage_service_types::{Result, StorageServiceMessage};
 
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
 pub fn network_endpoint_config(storage_config: StorageServiceConfig) -> AppConfig {
     let max_network_channel_size = storage_config.max_network_channel_size as usize;
     AppConfig::service(
@@ -85,11 +91,20 @@ impl Stream for StorageServiceNetworkEvents {
 /// Provides a more strongly typed interface around the raw RPC response channel.
 pub struct ResponseSender {
     response_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    bandwidth_limiter: Option<Arc<BandwidthLimiter>>,
 }
 
 impl ResponseSender {
     pub fn new(response_tx: oneshot::Sender<Result<Bytes, RpcError>>) -> Self {
-        Self { response_tx }
+        Self {
+            response_tx,
+            bandwidth_limiter: None,
+        }
+    }
+
+    /// Charges the bytes of the response to the given bandwidth limiter
+    pub fn set_bandwidth_limiter(&mut self, bandwidth_limiter: Arc<BandwidthLimiter>) {
+        self.bandwidth_limiter = Some(bandwidth_limiter);
     }
 
     pub fn send(self, response: Result<StorageServiceResponse>) {
@@ -97,6 +112,114 @@ impl ResponseSender {
         let result = bcs::to_bytes(&msg)
             .map(Bytes::from)
             .map_err(RpcError::BcsError);
+        if let (Some(bandwidth_limiter), Ok(bytes)) = (&self.bandwidth_limiter, &result) {
+            bandwidth_limiter.charge(bytes.len());
+        }
         let _ = self.response_tx.send(result);
     }
 }
+
+/// Limits the bandwidth used by the responses sent on a network.
+///
+/// The size of a response is only known once its request has been handled,
+/// so responses are charged to the limiter as they're sent, which can leave
+/// the limiter in debt. Requests then wait (asynchronously) for the debt to
+/// be paid off before they are handled.
+pub struct BandwidthLimiter {
+    network_id: NetworkId,
+    time_service: TimeService,
+    state: Mutex<BandwidthLimiterState>,
+}
+
+struct BandwidthLimiterState {
+    bytes_per_sec: Option<u64>, // The limit, or `None` if the bandwidth is unlimited
+    available_bytes: i128,      // The bytes that can be sent, negative when in debt
+    last_refill_time: Instant,
+}
+
+impl BandwidthLimiter {
+    pub fn new(
+        storage_config: &StorageServiceConfig,
+        network_id: NetworkId,
+        time_service: TimeService,
+    ) -> Self {
+        let bytes_per_sec = storage_config
+            .outbound_bandwidth_limits
+            .bytes_per_sec(&network_id);
+        let state = BandwidthLimiterState {
+            bytes_per_sec,
+            available_bytes: bytes_per_sec.unwrap_or(0) as i128,
+            last_refill_time: time_service.now(),
+        };
+        Self {
+            network_id,
+            time_service,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Applies the limits of a reloaded config
+    pub fn update_limits(&self, storage_config: &StorageServiceConfig) {
+        let bytes_per_sec = storage_config
+            .outbound_bandwidth_limits
+            .bytes_per_sec(&self.network_id);
+        let mut state = self.refilled_state();
+        state.bytes_per_sec = bytes_per_sec;
+        state.available_bytes = match bytes_per_sec {
+            Some(bytes_per_sec) => state.available_bytes.min(bytes_per_sec as i128),
+            None => 0,
+        };
+    }
+
+    /// Charges the bytes of a sent response
+    pub fn charge(&self, num_bytes: usize) {
+        let mut state = self.refilled_state();
+        if state.bytes_per_sec.is_some() {
+            state.available_bytes -= num_bytes as i128;
+        }
+        metrics::BANDWIDTH_LIMITER
+            .with_label_values(&[&self.network_id.to_string(), "sent_bytes"])
+            .observe(num_bytes as f64);
+    }
+
+    /// Returns how long to wait until the debt of the sent responses is paid
+    /// off, or `None` if it already is
+    pub fn delay(&self) -> Option<Duration> {
+        let state = self.refilled_state();
+        match state.bytes_per_sec {
+            Some(bytes_per_sec) if state.available_bytes < 0 => {
+                let debt_nanos = (-state.available_bytes) as u128 * NANOS_PER_SEC;
+                let delay_nanos = (debt_nanos + bytes_per_sec as u128 - 1) / bytes_per_sec as u128;
+                Some(Duration::from_nanos(delay_nanos as u64))
+            }
+            _ => None,
+        }
+    }
+
+    /// Waits until the debt of the sent responses is paid off
+    pub async fn wait_for_bandwidth(&self) {
+        while let Some(delay) = self.delay() {
+            metrics::BANDWIDTH_LIMITER
+                .with_label_values(&[&self.network_id.to_string(), "throttled_secs"])
+                .observe(delay.as_secs_f64());
+            self.time_service.sleep(delay).await;
+        }
+    }
+
+    /// Locks the state, after refilling it with the bytes allowed since the
+    /// last refill. At most a second's worth of bytes is kept for bursts.
+    fn refilled_state(&self) -> MutexGuard<'_, BandwidthLimiterState> {
+        let mut state = self.state.lock();
+        let now = self.time_service.now();
+        if let Some(bytes_per_sec) = state.bytes_per_sec {
+            let elapsed_nanos = now.duration_since(state.last_refill_time).as_nanos();
+            let refill = elapsed_nanos * bytes_per_sec as u128 / NANOS_PER_SEC;
+            state.available_bytes = state
+                .available_bytes
+                .saturating_add(refill as i128)
+                .min(bytes_per_sec as i128);
+        }
+        state.last_refill_time = now;
+        state
+    }
+}
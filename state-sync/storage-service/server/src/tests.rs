@@ -3,10 +3,16 @@
 
 #![forbid(unsafe_code)]
 
-use crate::{network::StorageServiceNetworkEvents, StorageReader, StorageServiceServer};
+use crate::{
+    network::{BandwidthLimiter, StorageServiceNetworkEvents},
+    StorageReader, StorageServiceServer,
+};
 use anyhow::{format_err, Result};
 use aptos_bitvec::BitVec;
-use aptos_config::config::StorageServiceConfig;
+use aptos_config::{
+    config::{BandwidthLimitsConfig, StorageServiceConfig},
+    network_id::NetworkId,
+};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
 use aptos_logger::Level;
 use aptos_time_service::{MockTimeService, TimeService};
@@ -70,8 +76,93 @@ use tokio::time::timeout;
 
 /// Various test constants for storage
 const MAX_RESPONSE_TIMEOUT_SECS: u64 = 40;
+const MIB: u64 = 1024 * 1024;
 const PROTOCOL_VERSION: u64 = 1;
 
+#[test]
+fn test_bandwidth_limits_bytes_per_sec() {
+    // The validator limit covers the validator and VFN networks
+    let limits = BandwidthLimitsConfig {
+        validator_network_mib_per_sec: Some(2),
+        public_network_mib_per_sec: Some(1),
+    };
+    assert_eq!(limits.bytes_per_sec(&NetworkId::Validator), Some(2 * MIB));
+    assert_eq!(limits.bytes_per_sec(&NetworkId::Vfn), Some(2 * MIB));
+    assert_eq!(limits.bytes_per_sec(&NetworkId::Public), Some(MIB));
+
+    // Without a limit the bandwidth is unlimited
+    let limits = BandwidthLimitsConfig::default();
+    assert_none!(limits.bytes_per_sec(&NetworkId::Validator));
+    assert_none!(limits.bytes_per_sec(&NetworkId::Public));
+}
+
+#[test]
+fn test_bandwidth_limiter_delay() {
+    // Create a limiter of 1 MiB per second
+    let time_service = TimeService::mock();
+    let mock_time = time_service.clone().into_mock();
+    let config = bandwidth_limited_config(Some(1), None);
+    let limiter = BandwidthLimiter::new(&config, NetworkId::Validator, time_service);
+
+    // A second's worth of bytes can be sent at once
+    limiter.charge(MIB as usize);
+    assert_none!(limiter.delay());
+
+    // Beyond that, requests are delayed until the debt is paid off
+    limiter.charge(MIB as usize / 2);
+    assert_eq!(limiter.delay(), Some(Duration::from_millis(500)));
+    mock_time.advance_ms(250);
+    assert_eq!(limiter.delay(), Some(Duration::from_millis(250)));
+    mock_time.advance_ms(250);
+    assert_none!(limiter.delay());
+
+    // Idle time only refills a second's worth of bytes
+    mock_time.advance_secs(10);
+    limiter.charge(2 * MIB as usize);
+    assert_eq!(limiter.delay(), Some(Duration::from_secs(1)));
+}
+
+#[test]
+fn test_bandwidth_limiter_network_limits() {
+    // Only limit the validator and VFN networks
+    let time_service = TimeService::mock();
+    let config = bandwidth_limited_config(Some(1), None);
+    let vfn_limiter = BandwidthLimiter::new(&config, NetworkId::Vfn, time_service.clone());
+    let public_limiter = BandwidthLimiter::new(&config, NetworkId::Public, time_service);
+
+    vfn_limiter.charge(2 * MIB as usize);
+    assert_eq!(vfn_limiter.delay(), Some(Duration::from_secs(1)));
+    public_limiter.charge(10 * MIB as usize);
+    assert_none!(public_limiter.delay());
+
+    // Reload the config to only limit the public network
+    let config = bandwidth_limited_config(None, Some(1));
+    vfn_limiter.update_limits(&config);
+    public_limiter.update_limits(&config);
+    assert_none!(vfn_limiter.delay());
+    public_limiter.charge(2 * MIB as usize);
+    assert_eq!(public_limiter.delay(), Some(Duration::from_secs(2)));
+}
+
+#[tokio::test]
+async fn test_bandwidth_limiter_wait() {
+    // Create a limiter of 1 MiB per second, half a second in debt
+    let time_service = TimeService::mock();
+    let mock_time = time_service.clone().into_mock();
+    let config = bandwidth_limited_config(Some(1), None);
+    let limiter = BandwidthLimiter::new(&config, NetworkId::Validator, time_service);
+    limiter.charge(3 * MIB as usize / 2);
+
+    // The wait only finishes once the debt is paid off
+    let wait = limiter.wait_for_bandwidth();
+    futures::pin_mut!(wait);
+    assert!(futures::poll!(wait.as_mut()).is_pending());
+    mock_time.advance_ms_async(250).await;
+    assert!(futures::poll!(wait.as_mut()).is_pending());
+    mock_time.advance_ms_async(250).await;
+    assert!(futures::poll!(wait.as_mut()).is_ready());
+}
+
 #[tokio::test]
 async fn test_cachable_requests_compression() {
     // Create test data
@@ -1415,6 +1506,7 @@ impl MockClient {
             executor,
             storage,
             mock_time_service.clone(),
+            NetworkId::Validator,
             network_requests,
         );
 
@@ -1935,6 +2027,20 @@ fn create_state_keys_and_values(
         .collect()
 }
 
+/// Creates a storage service config with the given bandwidth limits (in MiB per second)
+fn bandwidth_limited_config(
+    validator_network_mib_per_sec: Option<u64>,
+    public_network_mib_per_sec: Option<u64>,
+) -> StorageServiceConfig {
+    StorageServiceConfig {
+        outbound_bandwidth_limits: BandwidthLimitsConfig {
+            validator_network_mib_per_sec,
+            public_network_mib_per_sec,
+        },
+        ..Default::default()
+    }
+}
+
 /// Creates a test ledger info with signatures
 fn create_test_ledger_info_with_sigs(epoch: u64, version: u64) -> LedgerInfoWithSignatures {
     // Create a mock ledger info with signatures
@@ -102,6 +102,8 @@ fn test_pruner_config() {
         );
         assert_eq!(state_pruner.is_pruner_enabled(), enable);
         assert_eq!(state_pruner.get_prune_window(), 20);
+        state_pruner.set_prune_window(10);
+        assert_eq!(state_pruner.get_prune_window(), 10);
 
         let ledger_pruner = LedgerPrunerManager::new(
             Arc::clone(&aptos_db.ledger_db),
@@ -115,6 +117,8 @@ fn test_pruner_config() {
         );
         assert_eq!(ledger_pruner.is_pruner_enabled(), enable);
         assert_eq!(ledger_pruner.get_prune_window(), 100);
+        ledger_pruner.set_prune_window(50);
+        assert_eq!(ledger_pruner.get_prune_window(), 50);
     }
 }
 
@@ -129,6 +129,9 @@ const MAX_LIMIT: u64 = 10000;
 // TODO: Either implement an iteration API to allow a very old client to loop through a long history
 // or guarantee that there is always a recent enough waypoint and client knows to boot from there.
 const MAX_NUM_EPOCH_ENDING_LEDGER_INFO: usize = 100;
+
+// How many versions the indexer catches up on at a time
+const INDEXER_CATCH_UP_BATCH_SIZE: Version = 10000;
 static ROCKSDB_PROPERTY_MAP: Lazy<HashMap<&str, String>> = Lazy::new(|| {
     [
         "rocksdb.num-immutable-mem-table",
@@ -256,6 +259,8 @@ pub struct AptosDB {
     _rocksdb_property_reporter: RocksdbPropertyReporter,
     ledger_commit_lock: std::sync::Mutex<()>,
     indexer: Option<Indexer>,
+    // Held while indexing, so the indexer resumes between commits
+    indexer_paused: Mutex<bool>,
 }
 
 impl AptosDB {
@@ -306,6 +311,7 @@ impl AptosDB {
             ),
             ledger_commit_lock: std::sync::Mutex::new(()),
             indexer: None,
+            indexer_paused: Mutex::new(false),
         }
     }
 
@@ -404,11 +410,13 @@ impl AptosDB {
             let resolver = state_view.as_move_resolver();
             let annotator = MoveValueAnnotator::new(&resolver);
 
-            const BATCH_SIZE: Version = 10000;
             let mut next_version = indexer.next_version();
             while next_version < ledger_next_version {
                 info!(next_version = next_version, "AptosDB Indexer catching up. ",);
-                let end_version = std::cmp::min(ledger_next_version, next_version + BATCH_SIZE);
+                let end_version = std::cmp::min(
+                    ledger_next_version,
+                    next_version + INDEXER_CATCH_UP_BATCH_SIZE,
+                );
                 let write_sets = self
                     .transaction_store
                     .get_write_sets(next_version, end_version)?;
@@ -670,6 +678,173 @@ impl AptosDB {
         Ok(())
     }
 
+    // ================================== Admin APIs ====================================
+
+    /// Sets the target of the enabled pruners right away, instead of waiting for enough versions
+    /// to be committed. Each pruner prunes up to the latest version minus its prune window, and the
+    /// state merkle pruners up to the latest snapshot minus theirs. Returns the latest version.
+    pub fn trigger_pruning(&self) -> Result<Version> {
+        let latest_version = self.get_latest_version()?;
+        if self.ledger_pruner.is_pruner_enabled() {
+            self.ledger_pruner
+                .set_pruner_target_db_version(latest_version);
+        }
+
+        // The state merkle pruners can't go past the latest persisted snapshot.
+        if let Some((snapshot_version, _)) =
+            self.state_store.get_state_snapshot_before(Version::MAX)?
+        {
+            let state_db = &self.state_store.state_db;
+            if state_db.state_pruner.is_pruner_enabled() {
+                state_db
+                    .state_pruner
+                    .set_pruner_target_db_version(snapshot_version);
+            }
+            if state_db.epoch_snapshot_pruner.is_pruner_enabled() {
+                state_db
+                    .epoch_snapshot_pruner
+                    .set_pruner_target_db_version(snapshot_version);
+            }
+        }
+        info!(
+            latest_version = latest_version,
+            "Triggered the AptosDB pruners."
+        );
+        Ok(latest_version)
+    }
+
+    /// Changes the prune windows of the ledger and state merkle pruners, and prunes up to the new
+    /// windows right away.
+    pub fn set_prune_windows(
+        &self,
+        ledger_prune_window: Version,
+        state_merkle_prune_window: Version,
+    ) -> Result<()> {
+        self.ledger_pruner.set_prune_window(ledger_prune_window);
+        self.state_store
+            .state_db
+            .state_pruner
+            .set_prune_window(state_merkle_prune_window);
+        info!(
+            ledger_prune_window = ledger_prune_window,
+            state_merkle_prune_window = state_merkle_prune_window,
+            "Changed the AptosDB prune windows."
+        );
+        self.trigger_pruning()?;
+        Ok(())
+    }
+
+    /// Stops indexing the committed transactions until `resume_indexer`. The tables created in
+    /// the meantime are unknown to the indexer.
+    pub fn pause_indexer(&self) {
+        if self.indexer.is_some() {
+            *self.indexer_paused.lock() = true;
+            info!("Paused the AptosDB Indexer.");
+        }
+    }
+
+    /// Indexes the transactions committed while the indexer was paused, then those committed
+    /// from now on. The indexer catches up while the commits go on, and they only wait for it to
+    /// index the last batch. It fails, and the indexer stays paused, if the ledger pruner pruned
+    /// the write sets it needs.
+    pub fn resume_indexer(&self) -> Result<()> {
+        let indexer = match &self.indexer {
+            Some(indexer) => indexer,
+            None => return Ok(()),
+        };
+        if !*self.indexer_paused.lock() {
+            return Ok(());
+        }
+
+        let mut next_version = indexer.next_version();
+        while self.get_ledger_next_version()?.saturating_sub(next_version)
+            > INDEXER_CATCH_UP_BATCH_SIZE
+        {
+            next_version = self.catch_up_indexer(
+                indexer,
+                next_version,
+                next_version + INDEXER_CATCH_UP_BATCH_SIZE,
+            )?;
+        }
+
+        let mut paused = self.indexer_paused.lock();
+        if !*paused {
+            return Ok(());
+        }
+        let ledger_next_version = self.get_ledger_next_version()?;
+        while next_version < ledger_next_version {
+            next_version = self.catch_up_indexer(
+                indexer,
+                next_version,
+                std::cmp::min(
+                    ledger_next_version,
+                    next_version + INDEXER_CATCH_UP_BATCH_SIZE,
+                ),
+            )?;
+        }
+        *paused = false;
+        info!(next_version = next_version, "Resumed the AptosDB Indexer.");
+        Ok(())
+    }
+
+    fn get_ledger_next_version(&self) -> Result<Version> {
+        Ok(self
+            .get_latest_transaction_info_option()?
+            .map_or(0, |(v, _)| v + 1))
+    }
+
+    /// Indexes the write sets of `[next_version, end_version)`, returning `end_version`
+    fn catch_up_indexer(
+        &self,
+        indexer: &Indexer,
+        next_version: Version,
+        end_version: Version,
+    ) -> Result<Version> {
+        self.error_if_ledger_pruned("Write set", next_version)?;
+        let write_sets = self
+            .transaction_store
+            .get_write_sets(next_version, end_version)?;
+        let write_sets_ref: Vec<_> = write_sets.iter().collect();
+        indexer.index(self.state_store.clone(), next_version, &write_sets_ref)?;
+        Ok(end_version)
+    }
+
+    /// Compacts all the column families of the ledger and state merkle DBs, which blocks until
+    /// it's done.
+    pub fn compact(&self) -> Result<()> {
+        let start = Instant::now();
+        for cf_name in ledger_db_column_families() {
+            self.ledger_db.compact_cf(cf_name)?;
+        }
+        for cf_name in state_merkle_db_column_families() {
+            self.state_merkle_db.compact_cf(cf_name)?;
+        }
+        info!(
+            time_ms = %start.elapsed().as_millis(),
+            "Compacted AptosDB."
+        );
+        Ok(())
+    }
+
+    /// Commits the state snapshot buffered in memory and flushes the memtables of the ledger and
+    /// state merkle DBs, so that the next start recovers quickly. Meant to be called before the
+    /// node shuts down.
+    pub fn flush(&self) -> Result<()> {
+        let start = Instant::now();
+        self.state_store.buffered_state().lock().sync_commit();
+        for cf_name in ledger_db_column_families() {
+            self.ledger_db.flush_cf(cf_name)?;
+        }
+        for cf_name in state_merkle_db_column_families() {
+            self.state_merkle_db.flush_cf(cf_name)?;
+        }
+        info!(
+            time_ms = %start.elapsed().as_millis(),
+            "Flushed AptosDB."
+        );
+        Ok(())
+    }
+
     // ================================== Private APIs ==================================
     fn get_events_by_event_key(
         &self,
@@ -1630,7 +1805,9 @@ impl DbWriter for AptosDB {
 
             // Note: this must happen after txns have been saved to db because types can be newly
             // created in this same chunk of transactions.
-            if let Some(indexer) = &self.indexer {
+            // The transactions committed while the indexer is paused are indexed once it resumes.
+            let indexer_paused = self.indexer_paused.lock();
+            if let Some(indexer) = self.indexer.as_ref().filter(|_| !*indexer_paused) {
                 let _timer = OTHER_TIMERS_SECONDS
                     .with_label_values(&["indexer_index"])
                     .start_timer();
@@ -11,9 +11,12 @@ use crate::pruner::ledger_pruner_worker::LedgerPrunerWorker;
 use crate::pruner::ledger_store::ledger_store_pruner::LedgerPruner;
 use crate::pruner::pruner_manager::PrunerManager;
 use crate::{pruner_utils, StateStore};
-use aptos_types::transaction::Version;
+use aptos_types::transaction::{AtomicVersion, Version};
 use schemadb::DB;
-use std::{sync::Arc, thread::JoinHandle};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    thread::JoinHandle,
+};
 
 /// The `PrunerManager` for `LedgerPruner`.
 #[derive(Debug)]
@@ -21,7 +24,7 @@ pub(crate) struct LedgerPrunerManager {
     pruner_enabled: bool,
     /// DB version window, which dictates how many version of other stores like transaction, ledger
     /// info, events etc to keep.
-    prune_window: Version,
+    prune_window: AtomicVersion,
     /// Ledger pruner. Is always initialized regardless if the pruner is enabled to keep tracks
     /// of the min_readable_version.
     pruner: Arc<LedgerPruner>,
@@ -54,7 +57,16 @@ impl PrunerManager for LedgerPrunerManager {
     }
 
     fn get_prune_window(&self) -> Version {
-        self.prune_window
+        self.prune_window.load(Ordering::Relaxed)
+    }
+
+    fn set_prune_window(&self, prune_window: Version) {
+        self.prune_window.store(prune_window, Ordering::Relaxed);
+        if self.pruner_enabled {
+            PRUNER_WINDOW
+                .with_label_values(&["ledger_pruner"])
+                .set(prune_window as i64);
+        }
     }
 
     fn get_min_readable_version(&self) -> Version {
@@ -65,7 +77,7 @@ impl PrunerManager for LedgerPrunerManager {
         let min_version = self.get_min_readable_version();
         if self.is_pruner_enabled() {
             let adjusted_window = self
-                .prune_window
+                .get_prune_window()
                 .saturating_sub(self.user_pruning_window_offset);
             let adjusted_cutoff = self.latest_version.lock().saturating_sub(adjusted_window);
             std::cmp::max(min_version, adjusted_cutoff)
@@ -94,7 +106,7 @@ impl PrunerManager for LedgerPrunerManager {
         assert!(self.pruner_enabled);
         self.pruner_worker
             .as_ref()
-            .set_target_db_version(latest_version.saturating_sub(self.prune_window));
+            .set_target_db_version(latest_version.saturating_sub(self.get_prune_window()));
     }
 }
 
@@ -139,7 +151,7 @@ impl LedgerPrunerManager {
 
         Self {
             pruner_enabled: ledger_pruner_config.enable,
-            prune_window: ledger_pruner_config.prune_window,
+            prune_window: AtomicVersion::new(ledger_pruner_config.prune_window),
             pruner: ledger_pruner,
             pruner_worker: ledger_pruner_worker,
             worker_thread: ledger_pruner_worker_thread,
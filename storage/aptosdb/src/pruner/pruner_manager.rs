@@ -20,6 +20,9 @@ pub trait PrunerManager: Debug + Sync {
 
     fn get_prune_window(&self) -> Version;
 
+    /// Changes the prune window, which takes effect the next time the pruner target is set.
+    fn set_prune_window(&self, prune_window: Version);
+
     fn get_min_viable_version(&self) -> Version;
 
     fn get_min_readable_version(&self) -> Version;
@@ -11,10 +11,13 @@ use aptos_infallible::Mutex;
 
 use crate::pruner::pruner_manager::PrunerManager;
 use aptos_jellyfish_merkle::StaleNodeIndex;
-use aptos_types::transaction::Version;
+use aptos_types::transaction::{AtomicVersion, Version};
 use schemadb::schema::KeyCodec;
 use schemadb::DB;
-use std::{sync::Arc, thread::JoinHandle};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    thread::JoinHandle,
+};
 
 use crate::pruner::db_pruner::DBPruner;
 use crate::pruner::state_pruner_worker::StatePrunerWorker;
@@ -36,7 +39,7 @@ where
     pruner_enabled: bool,
     /// DB version window, which dictates how many versions of state store
     /// to keep.
-    prune_window: Version,
+    prune_window: AtomicVersion,
     /// State pruner. Is always initialized regardless if the pruner is enabled to keep tracks
     /// of the min_readable_version.
     pruner: Arc<StateMerklePruner<S>>,
@@ -68,7 +71,16 @@ where
     }
 
     fn get_prune_window(&self) -> Version {
-        self.prune_window
+        self.prune_window.load(Ordering::Relaxed)
+    }
+
+    fn set_prune_window(&self, prune_window: Version) {
+        self.prune_window.store(prune_window, Ordering::Relaxed);
+        if self.pruner_enabled {
+            PRUNER_WINDOW
+                .with_label_values(&[S::name()])
+                .set(prune_window as i64);
+        }
     }
 
     fn get_min_readable_version(&self) -> Version {
@@ -94,7 +106,7 @@ where
         assert!(self.pruner_enabled);
         self.pruner_worker
             .as_ref()
-            .set_target_db_version(latest_version.saturating_sub(self.prune_window));
+            .set_target_db_version(latest_version.saturating_sub(self.get_prune_window()));
     }
 }
 
@@ -134,7 +146,7 @@ where
         let min_readable_version = pruner.as_ref().min_readable_version();
         Self {
             pruner_enabled: config.enable,
-            prune_window: config.prune_window,
+            prune_window: AtomicVersion::new(config.prune_window),
             pruner,
             pruner_worker,
             worker_thread,
@@ -127,13 +127,13 @@ impl EpochEndingBackupController {
         format!("epoch_ending_{}-", self.start_epoch)
     }
 
-    fn manifest_name() -> &'static ShellSafeName {
+    pub(crate) fn manifest_name() -> &'static ShellSafeName {
         static NAME: Lazy<ShellSafeName> =
             Lazy::new(|| ShellSafeName::from_str("epoch_ending.manifest").unwrap());
         &NAME
     }
 
-    fn chunk_name(first_epoch: u64) -> ShellSafeName {
+    pub(crate) fn chunk_name(first_epoch: u64) -> ShellSafeName {
         format!("{}-.chunk", first_epoch).try_into().unwrap()
     }
 
@@ -70,7 +70,7 @@ impl StateSnapshotBackupController {
     }
 
     async fn run_impl(mut self) -> Result<FileHandle> {
-        self.version = Some(self.get_version_for_epoch_ending(self.epoch).await?);
+        self.version = Some(Self::get_version_for_epoch_ending(&self.client, self.epoch).await?);
         let backup_handle = self
             .storage
             .create_backup_with_random_suffix(&self.backup_name())
@@ -147,23 +147,23 @@ impl StateSnapshotBackupController {
         format!("state_epoch_{}_ver_{}", self.epoch, self.version())
     }
 
-    fn manifest_name() -> &'static ShellSafeName {
+    pub(crate) fn manifest_name() -> &'static ShellSafeName {
         static NAME: Lazy<ShellSafeName> =
             Lazy::new(|| ShellSafeName::from_str("state.manifest").unwrap());
         &NAME
     }
 
-    fn proof_name() -> &'static ShellSafeName {
+    pub(crate) fn proof_name() -> &'static ShellSafeName {
         static NAME: Lazy<ShellSafeName> =
             Lazy::new(|| ShellSafeName::from_str("state.proof").unwrap());
         &NAME
     }
 
-    fn chunk_name(first_idx: usize) -> ShellSafeName {
+    pub(crate) fn chunk_name(first_idx: usize) -> ShellSafeName {
         format!("{}-.chunk", first_idx).try_into().unwrap()
     }
 
-    fn chunk_proof_name(first_idx: usize, last_idx: usize) -> ShellSafeName {
+    pub(crate) fn chunk_proof_name(first_idx: usize, last_idx: usize) -> ShellSafeName {
         format!("{}-{}.proof", first_idx, last_idx)
             .try_into()
             .unwrap()
@@ -174,9 +174,12 @@ impl StateSnapshotBackupController {
         Ok(key.hash())
     }
 
-    async fn get_version_for_epoch_ending(&self, epoch: u64) -> Result<u64> {
+    pub(crate) async fn get_version_for_epoch_ending(
+        client: &BackupServiceClient,
+        epoch: u64,
+    ) -> Result<u64> {
         let ledger_info: LedgerInfoWithSignatures = bcs::from_bytes(
-            self.client
+            client
                 .get_epoch_ending_ledger_infos(epoch, epoch + 1)
                 .await?
                 .read_record_bytes()
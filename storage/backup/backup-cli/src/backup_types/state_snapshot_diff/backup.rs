@@ -0,0 +1,371 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        state_snapshot::{backup::StateSnapshotBackupController, manifest::StateSnapshotBackup},
+        state_snapshot_diff::manifest::{StateSnapshotDiffBackup, StateSnapshotDiffChunk},
+    },
+    metadata::Metadata,
+    storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
+    utils::{
+        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
+        should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_logger::prelude::*;
+use aptos_types::{
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{SparseMerkleRangeProof, TransactionInfoWithProof},
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{Transaction, TransactionInfo, Version},
+    write_set::{TransactionWrite, WriteSet},
+};
+use clap::Parser;
+use once_cell::sync::Lazy;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[derive(Parser)]
+pub struct StateSnapshotDiffBackupOpt {
+    #[clap(
+        long = "state-snapshot-epoch",
+        help = "Epoch at the end of which a differential state snapshot is to be taken."
+    )]
+    pub epoch: u64,
+    #[clap(
+        long = "base-state-manifest",
+        help = "Manifest of the full state snapshot to take the differential state snapshot \
+        against. It must be in the same backup storage."
+    )]
+    pub base_manifest: FileHandle,
+}
+
+/// Backs up the state changes since a full state snapshot.
+///
+/// The changes are collected from the write sets of the transactions since the base, and held in
+/// memory until they are written out.
+pub struct StateSnapshotDiffBackupController {
+    epoch: u64,
+    base_manifest: FileHandle,
+    max_chunk_size: usize,
+    client: Arc<BackupServiceClient>,
+    storage: Arc<dyn BackupStorage>,
+}
+
+impl StateSnapshotDiffBackupController {
+    pub fn new(
+        opt: StateSnapshotDiffBackupOpt,
+        global_opt: GlobalBackupOpt,
+        client: Arc<BackupServiceClient>,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            epoch: opt.epoch,
+            base_manifest: opt.base_manifest,
+            max_chunk_size: global_opt.max_chunk_size,
+            client,
+            storage,
+        }
+    }
+
+    pub async fn run(self) -> Result<FileHandle> {
+        info!(
+            "Differential state snapshot backup started, for epoch {}, base: {}.",
+            self.epoch, self.base_manifest,
+        );
+        let ret = self
+            .run_impl()
+            .await
+            .map_err(|e| anyhow!("Differential state snapshot backup failed: {}", e))?;
+        info!(
+            "Differential state snapshot backup succeeded. Manifest: {}",
+            ret
+        );
+        Ok(ret)
+    }
+
+    async fn run_impl(self) -> Result<FileHandle> {
+        let base: StateSnapshotBackup = self.storage.load_json_file(&self.base_manifest).await?;
+        let version =
+            StateSnapshotBackupController::get_version_for_epoch_ending(&self.client, self.epoch)
+                .await?;
+        ensure!(
+            base.version <= version,
+            "Base state snapshot at version {} is newer than version {}.",
+            base.version,
+            version,
+        );
+        let changes = self.get_state_changes(base.version, version).await?;
+        info!(
+            num_changes = changes.len(),
+            "Collected state changes since the base."
+        );
+
+        let backup_handle = self
+            .storage
+            .create_backup_with_random_suffix(&format!(
+                "state_diff_epoch_{}_ver_{}_base_{}",
+                self.epoch, version, base.version
+            ))
+            .await?;
+
+        // Chunk boundaries of the base still in the state are where the restore verifies the
+        // layered state, plus a boundary after each `max_chunk_size` of changes, so the chunks
+        // restored are bounded in size even if the changes are dense.
+        let mut boundaries: BTreeSet<HashValue> = base
+            .chunks
+            .iter()
+            .map(|chunk| chunk.last_key)
+            .filter(|key| !matches!(changes.get(key), Some((_, None))))
+            .collect();
+        let mut chunks = Vec::new();
+        let mut chunk_bytes = Vec::new();
+        let mut chunk_first_key = None;
+        let mut prev_key = None;
+        let mut bytes_since_boundary = 0;
+        for (key_hash, (key, value)) in &changes {
+            let record_bytes = bcs::to_bytes(&(key, value))?;
+            if should_cut_chunk(&chunk_bytes, &record_bytes, self.max_chunk_size) {
+                chunks.push(
+                    self.write_chunk(
+                        &backup_handle,
+                        chunks.len(),
+                        &chunk_bytes,
+                        chunk_first_key.take().unwrap(),
+                        prev_key.unwrap(),
+                    )
+                    .await?,
+                );
+                chunk_bytes = vec![];
+            }
+            chunk_first_key.get_or_insert(*key_hash);
+            prev_key = Some(*key_hash);
+            chunk_bytes.extend(&(record_bytes.len() as u32).to_be_bytes());
+            chunk_bytes.extend(&record_bytes);
+
+            bytes_since_boundary += record_bytes.len();
+            // Deleted keys can't be proven.
+            if value.is_some() && bytes_since_boundary >= self.max_chunk_size {
+                boundaries.insert(*key_hash);
+                bytes_since_boundary = 0;
+            }
+        }
+        if !chunk_bytes.is_empty() {
+            chunks.push(
+                self.write_chunk(
+                    &backup_handle,
+                    chunks.len(),
+                    &chunk_bytes,
+                    chunk_first_key.unwrap(),
+                    prev_key.unwrap(),
+                )
+                .await?,
+            );
+        }
+
+        // The last proof must be of the rightmost key in the state, to cover all of it.
+        let base_rightmost_key = base
+            .chunks
+            .last()
+            .ok_or_else(|| anyhow!("Base state snapshot is empty."))?
+            .last_key;
+        let changes_rightmost_key = changes
+            .iter()
+            .rev()
+            .find(|(_, (_, value))| value.is_some())
+            .map(|(key_hash, _)| *key_hash);
+        let rightmost_key = if boundaries.contains(&base_rightmost_key) {
+            changes_rightmost_key.map_or(base_rightmost_key, |key| key.max(base_rightmost_key))
+        } else {
+            changes_rightmost_key
+                .filter(|key| *key > base_rightmost_key)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "The rightmost state key of the base state snapshot is deleted, and the \
+                        new rightmost one is unknown. Take a full state snapshot instead."
+                    )
+                })?
+        };
+        boundaries.insert(rightmost_key);
+
+        let range_proofs = self
+            .write_range_proofs(&backup_handle, boundaries, version)
+            .await?;
+        self.write_manifest(&backup_handle, base, version, chunks, range_proofs)
+            .await
+    }
+}
+
+impl StateSnapshotDiffBackupController {
+    fn manifest_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("state_diff.manifest").unwrap());
+        &NAME
+    }
+
+    fn proof_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("state_diff.proof").unwrap());
+        &NAME
+    }
+
+    fn range_proofs_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("state_diff.range_proofs").unwrap());
+        &NAME
+    }
+
+    fn chunk_name(chunk_idx: usize) -> ShellSafeName {
+        format!("{}.chunk", chunk_idx).try_into().unwrap()
+    }
+
+    /// Returns the latest value (`None` if deleted) of each state key written in versions
+    /// (`base_version`, `version`], by key hash.
+    async fn get_state_changes(
+        &self,
+        base_version: Version,
+        version: Version,
+    ) -> Result<BTreeMap<HashValue, (StateKey, Option<StateValue>)>> {
+        let mut changes = BTreeMap::new();
+        let num_transactions = (version - base_version) as usize;
+        if num_transactions == 0 {
+            return Ok(changes);
+        }
+
+        let mut transactions_file = self
+            .client
+            .get_transactions(base_version + 1, num_transactions)
+            .await?;
+        let mut next_version = base_version + 1;
+        while let Some(record_bytes) = transactions_file.read_record_bytes().await? {
+            let (_, _, _, write_set): (Transaction, TransactionInfo, Vec<ContractEvent>, WriteSet) =
+                bcs::from_bytes(&record_bytes)?;
+            for (key, write_op) in write_set {
+                let value = write_op.extract_raw_bytes().map(StateValue::from);
+                changes.insert(key.hash(), (key, value));
+            }
+            next_version += 1;
+        }
+        ensure!(
+            next_version == version + 1,
+            "Server did not return all transactions requested. Expecting last version {}, got {}",
+            version,
+            next_version - 1,
+        );
+
+        Ok(changes)
+    }
+
+    async fn write_chunk(
+        &self,
+        backup_handle: &BackupHandleRef,
+        chunk_idx: usize,
+        chunk_bytes: &[u8],
+        first_key: HashValue,
+        last_key: HashValue,
+    ) -> Result<StateSnapshotDiffChunk> {
+        let (chunk_handle, mut chunk_file) = self
+            .storage
+            .create_for_write(backup_handle, &Self::chunk_name(chunk_idx))
+            .await?;
+        chunk_file.write_all(chunk_bytes).await?;
+        chunk_file.shutdown().await?;
+        info!(chunk = chunk_idx, "Chunk written.");
+
+        Ok(StateSnapshotDiffChunk {
+            first_key,
+            last_key,
+            blobs: chunk_handle,
+        })
+    }
+
+    async fn write_range_proofs(
+        &self,
+        backup_handle: &BackupHandleRef,
+        keys: BTreeSet<HashValue>,
+        version: Version,
+    ) -> Result<FileHandle> {
+        let mut range_proofs = Vec::with_capacity(keys.len());
+        for key in keys {
+            let mut proof_bytes = Vec::new();
+            self.client
+                .get_account_range_proof(key, version)
+                .await?
+                .read_to_end(&mut proof_bytes)
+                .await?;
+            let proof: SparseMerkleRangeProof = bcs::from_bytes(&proof_bytes)?;
+            range_proofs.push((key, proof));
+        }
+
+        let (range_proofs_handle, mut range_proofs_file) = self
+            .storage
+            .create_for_write(backup_handle, Self::range_proofs_name())
+            .await?;
+        range_proofs_file
+            .write_all(&bcs::to_bytes(&range_proofs)?)
+            .await?;
+        range_proofs_file.shutdown().await?;
+        Ok(range_proofs_handle)
+    }
+
+    async fn write_manifest(
+        &self,
+        backup_handle: &BackupHandleRef,
+        base: StateSnapshotBackup,
+        version: Version,
+        chunks: Vec<StateSnapshotDiffChunk>,
+        range_proofs: FileHandle,
+    ) -> Result<FileHandle> {
+        let proof_bytes = self.client.get_state_root_proof(version).await?;
+        let (txn_info, _): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+            bcs::from_bytes(&proof_bytes)?;
+
+        let (proof_handle, mut proof_file) = self
+            .storage
+            .create_for_write(backup_handle, Self::proof_name())
+            .await?;
+        proof_file.write_all(&proof_bytes).await?;
+        proof_file.shutdown().await?;
+
+        let manifest = StateSnapshotDiffBackup {
+            base_version: base.version,
+            base_manifest: self.base_manifest.clone(),
+            version,
+            epoch: self.epoch,
+            root_hash: txn_info.transaction_info().ensure_state_checkpoint_hash()?,
+            chunks,
+            range_proofs,
+            proof: proof_handle,
+        };
+
+        let (manifest_handle, mut manifest_file) = self
+            .storage
+            .create_for_write(backup_handle, Self::manifest_name())
+            .await?;
+        manifest_file
+            .write_all(&serde_json::to_vec(&manifest)?)
+            .await?;
+        manifest_file.shutdown().await?;
+
+        let metadata = Metadata::new_state_snapshot_diff_backup(
+            base.version,
+            self.epoch,
+            version,
+            manifest_handle.clone(),
+        );
+        self.storage
+            .save_metadata_line(&metadata.name(), &metadata.to_text_line()?)
+            .await?;
+
+        Ok(manifest_handle)
+    }
+}
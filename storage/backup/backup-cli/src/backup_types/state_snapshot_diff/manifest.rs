@@ -0,0 +1,48 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::storage::FileHandle;
+use aptos_crypto::HashValue;
+use aptos_types::transaction::Version;
+use serde::{Deserialize, Serialize};
+
+/// A chunk of a differential state snapshot manifest, representing the state changes in the key
+/// range [`first_key`, `last_key`] (right side inclusive).
+#[derive(Deserialize, Serialize)]
+pub struct StateSnapshotDiffChunk {
+    /// key of the first state change in this chunk.
+    pub first_key: HashValue,
+    /// key of the last state change in this chunk.
+    pub last_key: HashValue,
+    /// Repeated `len(record) + record` where `record` is BCS serialized tuple
+    /// `(key, Option<state_value>)`, the value being `None` if the key is deleted.
+    pub blobs: FileHandle,
+}
+
+/// Differential state snapshot backup manifest, representing the state changes between a full
+/// state snapshot (the base) and the specified version.
+#[derive(Deserialize, Serialize)]
+pub struct StateSnapshotDiffBackup {
+    /// Version of the base state snapshot.
+    pub base_version: Version,
+    /// Manifest of the base state snapshot, which is a `StateSnapshotBackup`.
+    pub base_manifest: FileHandle,
+    /// Version at which this state snapshot is taken.
+    pub version: Version,
+    /// Epoch in which this state snapshot is taken.
+    pub epoch: u64,
+    /// Hash of the state tree root.
+    pub root_hash: HashValue,
+    /// All state changes since the base in chunks.
+    pub chunks: Vec<StateSnapshotDiffChunk>,
+    /// BCS serialized `Vec<(HashValue, SparseMerkleRangeProof)>`, sorted by the key.
+    ///
+    /// Each proof proves the state values up until its key (inclusive) add up to `root_hash`.
+    /// The keys are chosen among the base chunk boundaries and the changes, so that the state,
+    /// with the changes layered over the base, can be restored in chunks of bounded sizes.
+    pub range_proofs: FileHandle,
+    /// BCS serialized
+    /// `Tuple(TransactionInfoWithProof, LedgerInfoWithSignatures)`, same as that of a
+    /// `StateSnapshotBackup`.
+    pub proof: FileHandle,
+}
@@ -0,0 +1,281 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::restore::EpochHistory,
+        state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+        state_snapshot_diff::manifest::{StateSnapshotDiffBackup, StateSnapshotDiffChunk},
+    },
+    metrics::{restore::STATE_SNAPSHOT_VERSION, verify::VERIFY_STATE_SNAPSHOT_VERSION},
+    storage::{BackupStorage, FileHandle},
+    utils::{
+        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, GlobalRestoreOptions,
+        RestoreRunMode,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{SparseMerkleRangeProof, TransactionInfoWithProof},
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::Version,
+};
+use clap::Parser;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use serde::de::DeserializeOwned;
+use std::{cmp::Ordering, sync::Arc};
+use storage_interface::StateSnapshotReceiver;
+use tokio::time::Instant;
+
+#[derive(Parser)]
+pub struct StateSnapshotDiffRestoreOpt {
+    #[clap(long = "state-diff-manifest")]
+    pub manifest_handle: FileHandle,
+}
+
+/// Restores the state at the version of a differential state snapshot, by layering its changes
+/// over its base state snapshot.
+///
+/// The base and the changes are merged in key order, and the merged state is verified in chunks
+/// against the range proofs in the differential state snapshot.
+pub struct StateSnapshotDiffRestoreController {
+    storage: Arc<dyn BackupStorage>,
+    run_mode: Arc<RestoreRunMode>,
+    manifest_handle: FileHandle,
+    /// Global "target_version" for the entire restore process, if the differential state
+    /// snapshot is newer than this, nothing will be done.
+    target_version: Version,
+    epoch_history: Option<Arc<EpochHistory>>,
+    concurrent_downloads: usize,
+}
+
+type Record = (HashValue, StateKey, Option<StateValue>);
+
+impl StateSnapshotDiffRestoreController {
+    pub fn new(
+        opt: StateSnapshotDiffRestoreOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+        epoch_history: Option<Arc<EpochHistory>>,
+    ) -> Self {
+        Self {
+            storage,
+            run_mode: global_opt.run_mode,
+            manifest_handle: opt.manifest_handle,
+            target_version: global_opt.target_version,
+            epoch_history,
+            concurrent_downloads: global_opt.concurrent_downloads,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let name = self.name();
+        let start = Instant::now();
+        info!("{} started. Manifest: {}", name, self.manifest_handle);
+        self.run_impl()
+            .await
+            .map_err(|e| anyhow!("{} failed: {}", name, e))?;
+        info!(time = start.elapsed().as_secs(), "{} succeeded.", name);
+        Ok(())
+    }
+}
+
+impl StateSnapshotDiffRestoreController {
+    fn name(&self) -> String {
+        format!("differential state snapshot {}", self.run_mode.name())
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let manifest: StateSnapshotDiffBackup =
+            self.storage.load_json_file(&self.manifest_handle).await?;
+        if manifest.version > self.target_version {
+            warn!(
+                "Trying to restore state snapshot to version {}, which is newer than the target version {}, skipping.",
+                manifest.version,
+                self.target_version,
+            );
+            return Ok(());
+        }
+
+        let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+            self.storage.load_bcs_file(&manifest.proof).await?;
+        txn_info_with_proof.verify(li.ledger_info(), manifest.version)?;
+        let state_root_hash = txn_info_with_proof
+            .transaction_info()
+            .ensure_state_checkpoint_hash()?;
+        ensure!(
+            state_root_hash == manifest.root_hash,
+            "Root hash mismatch with that in proof. root hash: {}, expected: {}",
+            manifest.root_hash,
+            state_root_hash,
+        );
+        if let Some(epoch_history) = self.epoch_history.as_ref() {
+            epoch_history.verify_ledger_info(&li)?;
+        }
+
+        // The base doesn't need to be verified, since the layered state is verified against the
+        // root hash above.
+        let base: StateSnapshotBackup =
+            self.storage.load_json_file(&manifest.base_manifest).await?;
+        ensure!(
+            base.version == manifest.base_version,
+            "Base state snapshot version mismatch. In base manifest: {}, expected: {}",
+            base.version,
+            manifest.base_version,
+        );
+        let range_proofs: Vec<(HashValue, SparseMerkleRangeProof)> =
+            self.storage.load_bcs_file(&manifest.range_proofs).await?;
+
+        let receiver = Arc::new(Mutex::new(Some(
+            self.run_mode
+                .get_state_restore_receiver(manifest.version, manifest.root_hash)?,
+        )));
+        if self.run_mode.is_verify() {
+            VERIFY_STATE_SNAPSHOT_VERSION.set(manifest.version as i64);
+        } else {
+            STATE_SNAPSHOT_VERSION.set(manifest.version as i64);
+        }
+
+        let resume_point = receiver.lock().as_mut().unwrap().previous_key_hash()?;
+        if let Some(resume_point) = resume_point {
+            info!(
+                resume_point = resume_point.to_hex(),
+                "Resumed differential state snapshot restore."
+            );
+        }
+        let is_restored = |key: &HashValue| resume_point.map_or(false, |point| *key <= point);
+
+        let mut base_records = self.records_stream(
+            base.chunks
+                .into_iter()
+                .filter(|chunk| !is_restored(&chunk.last_key))
+                .map(|chunk: StateSnapshotChunk| chunk.blobs)
+                .collect(),
+            |(key, value): (StateKey, StateValue)| (key, Some(value)),
+        );
+        let mut diff_records = self.records_stream(
+            manifest
+                .chunks
+                .into_iter()
+                .filter(|chunk| !is_restored(&chunk.last_key))
+                .map(|chunk: StateSnapshotDiffChunk| chunk.blobs)
+                .collect(),
+            |record: (StateKey, Option<StateValue>)| record,
+        );
+        let mut range_proofs = range_proofs
+            .into_iter()
+            .filter(|(key, _)| !is_restored(key))
+            .peekable();
+
+        let mut next_base = base_records.try_next().await?;
+        let mut next_diff = diff_records.try_next().await?;
+        let mut chunk = Vec::new();
+        let mut num_chunks = 0;
+        loop {
+            let order = match (&next_base, &next_diff) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(base_record), Some(diff_record)) => base_record.0.cmp(&diff_record.0),
+            };
+            let (key_hash, key, value) = match order {
+                Ordering::Less => next_record(&mut next_base, &mut base_records).await?,
+                Ordering::Greater => next_record(&mut next_diff, &mut diff_records).await?,
+                // Changes take precedence over the base.
+                Ordering::Equal => {
+                    next_record(&mut next_base, &mut base_records).await?;
+                    next_record(&mut next_diff, &mut diff_records).await?
+                }
+            };
+            if is_restored(&key_hash) {
+                continue;
+            }
+            let is_deleted = value.is_none();
+            if let Some(value) = value {
+                chunk.push((key, value));
+            }
+
+            if range_proofs.peek().map(|(key, _)| *key) == Some(key_hash) {
+                ensure!(!is_deleted, "Range proof for deleted key {}.", key_hash);
+                let (_, proof) = range_proofs.next().unwrap();
+                let chunk = std::mem::take(&mut chunk);
+                let receiver = receiver.clone();
+                tokio::task::spawn_blocking(move || {
+                    receiver.lock().as_mut().unwrap().add_chunk(chunk, proof)
+                })
+                .await??;
+                num_chunks += 1;
+                info!(
+                    chunk = num_chunks,
+                    last_key = key_hash.to_hex(),
+                    "State chunk added."
+                );
+            }
+        }
+        ensure!(
+            chunk.is_empty() && range_proofs.peek().is_none(),
+            "Range proofs don't cover all state values.",
+        );
+
+        tokio::task::spawn_blocking(move || receiver.lock().take().unwrap().finish()).await??;
+        self.run_mode.finish();
+        Ok(())
+    }
+
+    /// Streams the records in the chunk files in order, with their key hashes.
+    fn records_stream<T: DeserializeOwned + Send + 'static>(
+        &self,
+        file_handles: Vec<FileHandle>,
+        to_record: fn(T) -> (StateKey, Option<StateValue>),
+    ) -> BoxStream<'static, Result<Record>> {
+        let storage = self.storage.clone();
+        stream::iter(file_handles)
+            .map(move |file_handle| {
+                let storage = storage.clone();
+                async move {
+                    tokio::spawn(
+                        async move { Self::read_records::<T>(&storage, file_handle).await },
+                    )
+                    .await?
+                }
+            })
+            .buffered(self.concurrent_downloads)
+            .map_ok(move |records| {
+                stream::iter(records.into_iter().map(move |record| {
+                    let (key, value) = to_record(record);
+                    Ok((key.hash(), key, value))
+                }))
+            })
+            .try_flatten()
+            .boxed()
+    }
+
+    async fn read_records<T: DeserializeOwned>(
+        storage: &Arc<dyn BackupStorage>,
+        file_handle: FileHandle,
+    ) -> Result<Vec<T>> {
+        let mut file = storage.open_for_read(&file_handle).await?;
+        let mut records = vec![];
+        while let Some(record_bytes) = file.read_record_bytes().await? {
+            records.push(bcs::from_bytes(&record_bytes)?);
+        }
+        Ok(records)
+    }
+}
+
+/// Takes the peeked record, and peeks the next one.
+async fn next_record(
+    next: &mut Option<Record>,
+    records: &mut BoxStream<'static, Result<Record>>,
+) -> Result<Record> {
+    let record = next.take().expect("Peeked record must exist.");
+    *next = records.try_next().await?;
+    Ok(record)
+}
@@ -12,9 +12,17 @@ use backup_cli::{
     backup_types::{
         epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        state_snapshot_diff::backup::{
+            StateSnapshotDiffBackupController, StateSnapshotDiffBackupOpt,
+        },
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
-    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        catalog::{CatalogService, CatalogServiceOpt},
+        export::{ExportCoordinator, ExportOpt},
+        replicate::{ReplicationCoordinator, ReplicationOpt},
+    },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::StorageOpt,
     utils::{
@@ -33,6 +41,27 @@ enum Command {
         about = "Long running process backing up the chain continuously."
     )]
     Coordinator(CoordinatorCommand),
+    #[clap(
+        subcommand,
+        about = "Catalog of the backups in one or more backup storages."
+    )]
+    Catalog(CatalogCommand),
+    #[clap(
+        about = "Copy the backups in one storage to another, e.g. to keep a copy in another \
+        region. Every file is verified after it's copied, and backups already in the destination \
+        are skipped, so an interrupted replication can be resumed by running it again."
+    )]
+    Replicate(ReplicationOpt),
+    #[clap(
+        about = "Export the transactions, events and balance changes in the transaction backups \
+        as newline delimited JSON, e.g. to load into a data warehouse."
+    )]
+    Export {
+        #[clap(flatten)]
+        opt: ExportOpt,
+        #[clap(subcommand)]
+        storage: StorageOpt,
+    },
 }
 
 #[derive(Parser)]
@@ -57,6 +86,11 @@ enum OneShotQueryType {
         about = "Queries the latest epoch and versions of the existing backups in the storage."
     )]
     BackupStorageState(OneShotQueryBackupStorageStateOpt),
+    #[clap(
+        about = "Queries all backups in the storage and the newest point they can be restored \
+        to, in JSON."
+    )]
+    BackupCatalog(OneShotQueryBackupStorageStateOpt),
 }
 
 #[derive(Parser)]
@@ -101,6 +135,12 @@ enum BackupType {
         #[clap(subcommand)]
         storage: StorageOpt,
     },
+    StateSnapshotDiff {
+        #[clap(flatten)]
+        opt: StateSnapshotDiffBackupOpt,
+        #[clap(subcommand)]
+        storage: StorageOpt,
+    },
     Transaction {
         #[clap(flatten)]
         opt: TransactionBackupOpt,
@@ -133,6 +173,16 @@ struct CoordinatorRunOpt {
     storage: StorageOpt,
 }
 
+#[derive(Parser)]
+enum CatalogCommand {
+    #[clap(
+        about = "Serve a catalog of the backups in the storages over HTTP, refreshing it \
+    periodically. GET /catalog lists the backups in all storages, /catalog/<name> those in one \
+    storage, and /newest_restorable_point the newest point that can be restored to."
+    )]
+    Serve(CatalogServiceOpt),
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     main_impl().await.map_err(|e| {
@@ -167,6 +217,15 @@ async fn main_impl() -> Result<()> {
                     .await?;
                     println!("{}", view.get_storage_state()?)
                 }
+                OneShotQueryType::BackupCatalog(opt) => {
+                    let view = cache::sync_and_load(
+                        &opt.metadata_cache,
+                        opt.storage.init_storage().await?,
+                        opt.concurrent_downloads.get(),
+                    )
+                    .await?;
+                    println!("{}", serde_json::to_string_pretty(&view.get_catalog()?)?)
+                }
             },
             OneShotCommand::Backup(opt) => {
                 let client = Arc::new(BackupServiceClient::new_with_opt(opt.client));
@@ -193,6 +252,16 @@ async fn main_impl() -> Result<()> {
                         .run()
                         .await?;
                     }
+                    BackupType::StateSnapshotDiff { opt, storage } => {
+                        StateSnapshotDiffBackupController::new(
+                            opt,
+                            global_opt,
+                            client,
+                            storage.init_storage().await?,
+                        )
+                        .run()
+                        .await?;
+                    }
                     BackupType::Transaction { opt, storage } => {
                         TransactionBackupController::new(
                             opt,
@@ -218,6 +287,30 @@ async fn main_impl() -> Result<()> {
                 .await?;
             }
         },
+        Command::Replicate(opt) => {
+            let summary = ReplicationCoordinator::new_with_opt(opt)
+                .await?
+                .run()
+                .await?;
+            println!(
+                "Replicated {} backups, skipped {} already in the destination.",
+                summary.num_replicated, summary.num_skipped
+            );
+        }
+        Command::Export { opt, storage } => {
+            let summary = ExportCoordinator::new(opt, storage.init_storage().await?)
+                .run()
+                .await?;
+            println!(
+                "Exported {} transactions, {} events and {} balance changes.",
+                summary.num_transactions, summary.num_events, summary.num_balance_changes
+            );
+        }
+        Command::Catalog(catalog_cmd) => match catalog_cmd {
+            CatalogCommand::Serve(opt) => {
+                CatalogService::new_with_opt(opt).await?.run().await?;
+            }
+        },
     }
     Ok(())
 }
@@ -8,6 +8,9 @@ use backup_cli::{
     backup_types::{
         epoch_ending::restore::{EpochEndingRestoreController, EpochEndingRestoreOpt},
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        state_snapshot_diff::restore::{
+            StateSnapshotDiffRestoreController, StateSnapshotDiffRestoreOpt,
+        },
         transaction::restore::{TransactionRestoreController, TransactionRestoreOpt},
     },
     coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
@@ -40,6 +43,12 @@ enum RestoreType {
         #[clap(subcommand)]
         storage: StorageOpt,
     },
+    StateSnapshotDiff {
+        #[clap(flatten)]
+        opt: StateSnapshotDiffRestoreOpt,
+        #[clap(subcommand)]
+        storage: StorageOpt,
+    },
     Transaction {
         #[clap(flatten)]
         opt: TransactionRestoreOpt,
@@ -86,6 +95,16 @@ async fn main_impl() -> Result<()> {
             .run()
             .await?;
         }
+        RestoreType::StateSnapshotDiff { opt, storage } => {
+            StateSnapshotDiffRestoreController::new(
+                opt,
+                global_opt,
+                storage.init_storage().await?,
+                None, /* epoch_history */
+            )
+            .run()
+            .await?;
+        }
         RestoreType::Transaction { opt, storage } => {
             TransactionRestoreController::new(
                 opt,
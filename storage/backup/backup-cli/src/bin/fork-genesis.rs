@@ -0,0 +1,235 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Builds the genesis of a new network carrying the state of an existing one at a chosen version,
+//! with a fresh validator set, see `aptos_genesis::fork`. The state is read from the DB of a node,
+//! or from a state snapshot backup, which is restored into a new DB first so that the chunks are
+//! verified against the root hash in the proof of the backup. The signatures on the ledger info
+//! of the proof are not verified.
+
+use anyhow::{anyhow, ensure, Result};
+use aptos_config::config::{
+    BUFFERED_STATE_TARGET_ITEMS, DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+    NO_OP_STORAGE_PRUNER_CONFIG,
+};
+use aptos_genesis::fork;
+use aptos_logger::{prelude::*, Level, Logger};
+use aptos_types::{chain_id::ChainId, state_store::state_key::StateKey, transaction::Version};
+use aptosdb::{AptosDB, GetRestoreHandler};
+use backup_cli::{
+    backup_types::state_snapshot::{
+        manifest::StateSnapshotBackup,
+        restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+    },
+    storage::{FileHandle, StorageOpt},
+    utils::{
+        storage_ext::BackupStorageExt, ConcurrentDownloadsOpt, GlobalRestoreOptions,
+        RestoreRunMode, RocksdbOpt,
+    },
+};
+use clap::Parser;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{collections::HashMap, io::Write, num::NonZeroUsize, path::PathBuf, sync::Arc};
+use storage_interface::DbReader;
+
+/// Number of state values read from the DB at a time
+const STATE_CHUNK_SIZE: usize = 10_000;
+
+#[derive(Parser)]
+struct Opt {
+    #[clap(
+        long = "output-dir",
+        parse(from_os_str),
+        help = "Where the genesis, the waypoint, the mint key and the configs of the new \
+        validators are written."
+    )]
+    output_dir: PathBuf,
+    #[clap(long, default_value = "1")]
+    num_validators: NonZeroUsize,
+    #[clap(
+        long,
+        default_value = "testing",
+        help = "The chain id of the new network, which should differ from that of the forked one."
+    )]
+    chain_id: ChainId,
+    #[clap(
+        long,
+        help = "The stake of each new validator, at least the minimum stake of the forked network. \
+        [Defaults to that minimum]"
+    )]
+    stake_amount: Option<u64>,
+    #[clap(subcommand)]
+    source: Source,
+}
+
+#[derive(Parser)]
+enum Source {
+    #[clap(about = "Fork the state in the DB of a node, opened read only.")]
+    Db {
+        #[clap(long = "db-dir", parse(from_os_str))]
+        db_dir: PathBuf,
+        #[clap(
+            long,
+            help = "The version of the forked state. [Defaults to the latest state checkpoint]"
+        )]
+        version: Option<Version>,
+        #[clap(flatten)]
+        rocksdb_opt: RocksdbOpt,
+    },
+    #[clap(about = "Fork the state in a state snapshot backup, restored into a new DB first.")]
+    Backup {
+        #[clap(long = "state-manifest")]
+        manifest_handle: FileHandle,
+        #[clap(
+            long = "target-db-dir",
+            parse(from_os_str),
+            help = "Where the state snapshot is restored."
+        )]
+        db_dir: PathBuf,
+        #[clap(flatten)]
+        rocksdb_opt: RocksdbOpt,
+        #[clap(flatten)]
+        concurrent_downloads: ConcurrentDownloadsOpt,
+        #[clap(subcommand)]
+        storage: StorageOpt,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    main_impl().await.map_err(|e| {
+        error!("main_impl() failed: {}", e);
+        e
+    })
+}
+
+async fn main_impl() -> Result<()> {
+    Logger::new().level(Level::Info).read_env().init();
+
+    let opt = Opt::from_args();
+    let (db, version) = match opt.source {
+        Source::Db {
+            db_dir,
+            version,
+            rocksdb_opt,
+        } => {
+            let db = open_db(db_dir, true /* read_only */, rocksdb_opt)?;
+            let version = match version {
+                Some(version) => version,
+                None => db
+                    .get_latest_state_checkpoint_version()?
+                    .ok_or_else(|| anyhow!("No state checkpoint in the DB."))?,
+            };
+            (db, version)
+        }
+        Source::Backup {
+            manifest_handle,
+            db_dir,
+            rocksdb_opt,
+            concurrent_downloads,
+            storage,
+        } => {
+            let storage = storage.init_storage().await?;
+            let manifest: StateSnapshotBackup = storage.load_json_file(&manifest_handle).await?;
+            let db = open_db(db_dir, false /* read_only */, rocksdb_opt)?;
+            let global_opt = GlobalRestoreOptions {
+                target_version: manifest.version,
+                trusted_waypoints: Arc::new(HashMap::new()),
+                run_mode: Arc::new(RestoreRunMode::Restore {
+                    restore_handler: db.get_restore_handler(),
+                }),
+                concurrent_downloads: concurrent_downloads.get(),
+                replay_concurrency_level: 1,
+            };
+            StateSnapshotRestoreController::new(
+                StateSnapshotRestoreOpt {
+                    manifest_handle,
+                    version: manifest.version,
+                },
+                global_opt,
+                storage,
+                None, /* epoch_history */
+            )
+            .run()
+            .await?;
+            (db, manifest.version)
+        }
+    };
+
+    let state = read_state(db.as_ref(), version)?;
+    info!(
+        version = version,
+        state_values = state.len(),
+        "Forked state read."
+    );
+
+    // The staking config is that of the forked network, which drops the validators staking less
+    let stake_amount = match (opt.stake_amount, fork::forked_minimum_stake(&state)?) {
+        (Some(stake_amount), _) => stake_amount,
+        (None, minimum_stake) => minimum_stake.unwrap_or_default().max(1),
+    };
+
+    std::fs::create_dir_all(&opt.output_dir)?;
+    let (root_key, genesis, waypoint, validators) = aptos_genesis::builder::Builder::new(
+        &opt.output_dir,
+        cached_packages::head_release_bundle().clone(),
+    )?
+    .with_num_validators(opt.num_validators)
+    .with_chain_id(opt.chain_id)
+    .with_init_config(Some(Arc::new(move |_, _, genesis_stake_amount| {
+        *genesis_stake_amount = stake_amount
+    })))
+    .with_forked_genesis_state(state)
+    .build(StdRng::from_entropy())?;
+
+    let mint_key_path = opt.output_dir.join("mint.key");
+    std::fs::File::create(&mint_key_path)?.write_all(&bcs::to_bytes(&root_key)?)?;
+    std::fs::File::create(opt.output_dir.join("waypoint.txt"))?
+        .write_all(waypoint.to_string().as_bytes())?;
+    std::fs::File::create(opt.output_dir.join("genesis.blob"))?
+        .write_all(&bcs::to_bytes(&genesis)?)?;
+
+    println!("Forked the state at version {}:", version);
+    println!("\tChainId: {}", opt.chain_id);
+    println!("\tStake of each validator: {}", stake_amount);
+    println!("\tWaypoint: {}", waypoint);
+    println!("\tMint key path: {:?}", mint_key_path);
+    for validator in validators {
+        println!("\tValidator config: {:?}", validator.dir);
+    }
+
+    Ok(())
+}
+
+fn open_db(db_dir: PathBuf, read_only: bool, rocksdb_opt: RocksdbOpt) -> Result<Arc<AptosDB>> {
+    Ok(Arc::new(AptosDB::open(
+        db_dir,
+        read_only,
+        NO_OP_STORAGE_PRUNER_CONFIG, /* pruner config */
+        rocksdb_opt.into(),
+        false,
+        BUFFERED_STATE_TARGET_ITEMS,
+        DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+    )?))
+}
+
+fn read_state(db: &dyn DbReader, version: Version) -> Result<Vec<(StateKey, Vec<u8>)>> {
+    let num_values = db.get_state_leaf_count(version)?;
+    let mut state = Vec::with_capacity(num_values);
+    while state.len() < num_values {
+        let chunk = db.get_state_value_chunk_with_proof(version, state.len(), STATE_CHUNK_SIZE)?;
+        ensure!(
+            !chunk.raw_values.is_empty(),
+            "No state values from index {} at version {}.",
+            state.len(),
+            version,
+        );
+        state.extend(
+            chunk
+                .raw_values
+                .into_iter()
+                .map(|(state_key, state_value)| (state_key, state_value.into_bytes())),
+        );
+    }
+    Ok(state)
+}
@@ -0,0 +1,312 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    metadata,
+    metadata::{
+        cache::MetadataCacheOpt,
+        view::{BackupCatalog, RestorablePoint},
+    },
+    storage::{
+        command_adapter::{config::CommandAdapterConfig, CommandAdapter},
+        BackupStorage, ShellSafeName,
+    },
+    utils::{unix_timestamp_sec, ConcurrentDownloadsOpt},
+};
+use anyhow::{anyhow, Result};
+use aptos_infallible::RwLock;
+use aptos_logger::prelude::*;
+use clap::Parser;
+use serde::Serialize;
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc};
+use tokio::time::{interval, Duration};
+use warp::{filters::BoxedFilter, http::StatusCode, reply::Reply, Filter};
+
+static CATALOG: &str = "catalog";
+static NEWEST_RESTORABLE_POINT: &str = "newest_restorable_point";
+
+/// A backup storage to catalog, in the format of `<name>=<command adapter config path>`.
+#[derive(Debug)]
+pub struct CatalogLocation {
+    pub name: ShellSafeName,
+    pub config: PathBuf,
+}
+
+impl FromStr for CatalogLocation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, config) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expecting <name>=<config path>, got {}", s))?;
+        Ok(Self {
+            name: name.parse()?,
+            config: config.into(),
+        })
+    }
+}
+
+#[derive(Parser)]
+pub struct CatalogServiceOpt {
+    #[clap(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+    #[clap(
+        long = "location",
+        required = true,
+        help = "A backup storage to catalog, in the format of \
+        <name>=<path to the command adapter config of the storage>. Specify multiple times to \
+        catalog backups across multiple storages."
+    )]
+    pub locations: Vec<CatalogLocation>,
+    #[clap(
+        long,
+        default_value = "127.0.0.1:6187",
+        help = "Address to serve the catalog HTTP API at."
+    )]
+    pub address: SocketAddr,
+    #[clap(
+        long,
+        default_value = "300",
+        help = "Interval (in seconds) to refresh the catalog at. Metadata files already in the \
+        cache are not downloaded again on refresh."
+    )]
+    pub refresh_interval_secs: u64,
+    #[clap(flatten)]
+    pub concurrent_downloads: ConcurrentDownloadsOpt,
+}
+
+/// The catalog of the backups in one storage.
+#[derive(Clone, Default, Serialize)]
+pub struct LocationCatalog {
+    /// Unix timestamp of the last successful refresh.
+    pub refreshed_at_secs: Option<i64>,
+    pub catalog: Option<BackupCatalog>,
+    /// Error of the last refresh, if it failed.
+    pub last_refresh_error: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct LocatedRestorablePoint {
+    pub location: String,
+    #[serde(flatten)]
+    pub restorable_point: RestorablePoint,
+}
+
+type Catalogs = Arc<RwLock<BTreeMap<String, LocationCatalog>>>;
+
+struct StorageLocation {
+    name: String,
+    metadata_cache_opt: MetadataCacheOpt,
+    storage: Arc<dyn BackupStorage>,
+}
+
+/// Keeps a catalog of the backups in a set of storages up to date, and serves it over HTTP, so
+/// one can tell which backups exist where, and the newest point that can be restored, without
+/// going through the storages.
+pub struct CatalogService {
+    locations: Vec<StorageLocation>,
+    address: SocketAddr,
+    refresh_interval: Duration,
+    concurrent_downloads: usize,
+    catalogs: Catalogs,
+}
+
+impl CatalogService {
+    pub async fn new_with_opt(opt: CatalogServiceOpt) -> Result<Self> {
+        let mut locations = Vec::new();
+        for location in opt.locations {
+            let config = CommandAdapterConfig::load_from_file(&location.config).await?;
+            locations.push(StorageLocation {
+                name: location.name.to_string(),
+                // Each storage needs its own cache, since the cache is synced to the storage.
+                metadata_cache_opt: opt.metadata_cache_opt.sub_cache(&location.name),
+                storage: Arc::new(CommandAdapter::new(config)),
+            });
+        }
+
+        Ok(Self {
+            locations,
+            address: opt.address,
+            refresh_interval: Duration::from_secs(opt.refresh_interval_secs),
+            concurrent_downloads: opt.concurrent_downloads.get(),
+            catalogs: Arc::new(RwLock::new(BTreeMap::new())),
+        })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let server = warp::serve(get_routes(Arc::clone(&self.catalogs))).try_bind(self.address);
+        tokio::spawn(server);
+        info!(
+            address = self.address.to_string(),
+            "Backup catalog service started."
+        );
+
+        let mut interval = interval(self.refresh_interval);
+        loop {
+            interval.tick().await;
+            for location in &self.locations {
+                self.refresh(location).await;
+            }
+        }
+    }
+
+    async fn refresh(&self, location: &StorageLocation) {
+        let result = async {
+            metadata::cache::sync_and_load(
+                &location.metadata_cache_opt,
+                Arc::clone(&location.storage),
+                self.concurrent_downloads,
+            )
+            .await?
+            .get_catalog()
+        }
+        .await;
+
+        let mut catalogs = self.catalogs.write();
+        let location_catalog = catalogs.entry(location.name.clone()).or_default();
+        match result {
+            Ok(catalog) => {
+                location_catalog.refreshed_at_secs = Some(unix_timestamp_sec());
+                location_catalog.catalog = Some(catalog);
+                location_catalog.last_refresh_error = None;
+            }
+            Err(e) => {
+                warn!(
+                    location = location.name,
+                    error = ?e,
+                    "Failed to refresh the backup catalog."
+                );
+                location_catalog.last_refresh_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+}
+
+/// The newest restorable point among all storages, with the storage it's in.
+pub fn newest_restorable_point(
+    catalogs: &BTreeMap<String, LocationCatalog>,
+) -> Option<LocatedRestorablePoint> {
+    catalogs
+        .iter()
+        .filter_map(|(location, location_catalog)| {
+            let restorable_point = location_catalog
+                .catalog
+                .as_ref()?
+                .newest_restorable_point
+                .clone()?;
+            Some(LocatedRestorablePoint {
+                location: location.clone(),
+                restorable_point,
+            })
+        })
+        .max_by_key(|point| {
+            (
+                point.restorable_point.latest_version,
+                point.restorable_point.state_snapshot_version,
+            )
+        })
+}
+
+fn get_routes(catalogs: Catalogs) -> BoxedFilter<(impl Reply,)> {
+    // GET catalog
+    let c = Arc::clone(&catalogs);
+    let catalog = warp::path::end().map(move || warp::reply::json(&*c.read()));
+
+    // GET catalog/<location>
+    let c = Arc::clone(&catalogs);
+    let location_catalog =
+        warp::path!(String).map(move |location: String| match c.read().get(&location) {
+            Some(location_catalog) => {
+                warp::reply::with_status(warp::reply::json(location_catalog), StatusCode::OK)
+            }
+            None => warp::reply::with_status(
+                warp::reply::json(&format!("Unknown location: {}", location)),
+                StatusCode::NOT_FOUND,
+            ),
+        });
+
+    // GET newest_restorable_point
+    let c = catalogs;
+    let newest =
+        warp::path::end().map(move || warp::reply::json(&newest_restorable_point(&c.read())));
+
+    // Route by endpoint name.
+    let routes = warp::any()
+        .and(warp::path(CATALOG).and(catalog.or(location_catalog)))
+        .or(warp::path(NEWEST_RESTORABLE_POINT).and(newest));
+
+    // Serve all routes for GET only.
+    warp::get().and(routes).boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{view::MetadataView, Metadata};
+
+    fn location_catalog(metadata: Vec<Metadata>) -> LocationCatalog {
+        LocationCatalog {
+            refreshed_at_secs: Some(0),
+            catalog: Some(MetadataView::from(metadata).get_catalog().unwrap()),
+            last_refresh_error: None,
+        }
+    }
+
+    #[test]
+    fn test_newest_restorable_point() {
+        let mut catalogs = BTreeMap::new();
+
+        // A snapshot that no transaction backup reaches isn't restorable.
+        catalogs.insert(
+            "a".to_string(),
+            location_catalog(vec![
+                Metadata::new_state_snapshot_backup(5, 500, "a/s500".to_string()),
+                Metadata::new_transaction_backup(0, 99, "a/t0".to_string()),
+            ]),
+        );
+        assert_eq!(newest_restorable_point(&catalogs), None);
+
+        // Backups with gaps are still cataloged, with nothing restorable.
+        let gapped = location_catalog(vec![
+            Metadata::new_state_snapshot_backup(1, 100, "g/s100".to_string()),
+            Metadata::new_transaction_backup(0, 99, "g/t0".to_string()),
+            Metadata::new_transaction_backup(200, 299, "g/t200".to_string()),
+        ]);
+        let gapped_catalog = gapped.catalog.as_ref().unwrap();
+        assert_eq!(gapped_catalog.transaction_backups.len(), 2);
+        assert_eq!(gapped_catalog.newest_restorable_point, None);
+        catalogs.insert("g".to_string(), gapped);
+        assert_eq!(newest_restorable_point(&catalogs), None);
+
+        // Picks the newest snapshot covered by transactions, and the continuous transactions
+        // after it.
+        catalogs.insert(
+            "b".to_string(),
+            location_catalog(vec![
+                Metadata::new_state_snapshot_backup(1, 100, "b/s100".to_string()),
+                Metadata::new_state_snapshot_backup(2, 200, "b/s200".to_string()),
+                Metadata::new_transaction_backup(0, 149, "b/t0".to_string()),
+                Metadata::new_transaction_backup(150, 299, "b/t150".to_string()),
+                Metadata::new_state_snapshot_backup(9, 900, "b/s900".to_string()),
+            ]),
+        );
+        catalogs.insert(
+            "c".to_string(),
+            location_catalog(vec![
+                Metadata::new_state_snapshot_backup(1, 100, "c/s100".to_string()),
+                Metadata::new_transaction_backup(0, 199, "c/t0".to_string()),
+            ]),
+        );
+        assert_eq!(
+            newest_restorable_point(&catalogs),
+            Some(LocatedRestorablePoint {
+                location: "b".to_string(),
+                restorable_point: RestorablePoint {
+                    state_snapshot_epoch: 2,
+                    state_snapshot_version: 200,
+                    latest_version: 299,
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,557 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::transaction::manifest::{TransactionBackup, TransactionChunk},
+    metadata,
+    metadata::cache::MetadataCacheOpt,
+    storage::BackupStorage,
+    utils::{
+        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, ConcurrentDownloadsOpt,
+    },
+};
+use anyhow::{ensure, Result};
+use aptos_crypto::hash::CryptoHash;
+use aptos_logger::prelude::*;
+use aptos_types::{
+    access_path::AccessPath,
+    account_config::CoinStoreResource,
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{TransactionAccumulatorRangeProof, TransactionInfoListWithProof},
+    state_store::state_key::StateKey,
+    transaction::{
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionPayload, Version,
+    },
+    write_set::{WriteOp, WriteSet},
+};
+use clap::Parser;
+use futures::{stream, StreamExt, TryStreamExt};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{StructTag, TypeTag},
+};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{
+    fs::{create_dir_all, File},
+    io::{AsyncWriteExt, BufReader, BufWriter},
+};
+
+const TRANSACTIONS_FILE: &str = "transactions.json";
+const EVENTS_FILE: &str = "events.json";
+const BALANCE_CHANGES_FILE: &str = "balance_changes.json";
+
+#[derive(Parser)]
+pub struct ExportOpt {
+    #[clap(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+    #[clap(long, default_value = "0", help = "First version to export.")]
+    pub start_version: Version,
+    #[clap(
+        long,
+        help = "Last version to export. [Defaults to the latest version in the backups]"
+    )]
+    pub end_version: Option<Version>,
+    #[clap(
+        long,
+        parse(from_os_str),
+        help = "Directory to write the exported records to. Transactions, events and balance \
+        changes are written to transactions.json, events.json and balance_changes.json \
+        respectively, as newline delimited JSON."
+    )]
+    pub output_dir: PathBuf,
+    #[clap(flatten)]
+    pub concurrent_downloads: ConcurrentDownloadsOpt,
+}
+
+/// Exports the transactions in the backups as flattened records, so they can be loaded into a
+/// data warehouse without running a node.
+///
+/// Each chunk is verified against the proof in the backup before it's exported.
+pub struct ExportCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    metadata_cache_opt: MetadataCacheOpt,
+    start_version: Version,
+    end_version: Option<Version>,
+    output_dir: PathBuf,
+    concurrent_downloads: usize,
+}
+
+impl ExportCoordinator {
+    pub fn new(opt: ExportOpt, storage: Arc<dyn BackupStorage>) -> Self {
+        Self {
+            storage,
+            metadata_cache_opt: opt.metadata_cache_opt,
+            start_version: opt.start_version,
+            end_version: opt.end_version,
+            output_dir: opt.output_dir,
+            concurrent_downloads: opt.concurrent_downloads.get(),
+        }
+    }
+
+    pub async fn run(self) -> Result<ExportSummary> {
+        info!("Export coordinator started.");
+        let ret = self.run_impl().await;
+        match &ret {
+            Ok(summary) => info!(
+                num_transactions = summary.num_transactions,
+                num_events = summary.num_events,
+                num_balance_changes = summary.num_balance_changes,
+                "Export coordinator exiting with success."
+            ),
+            Err(e) => error!(error = ?e, "Export coordinator failed."),
+        }
+        ret
+    }
+
+    async fn run_impl(&self) -> Result<ExportSummary> {
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?;
+        let end_version = match self.end_version {
+            Some(end_version) => end_version,
+            None => match metadata_view.max_transaction_version()? {
+                Some(max_version) => max_version,
+                None => return Ok(ExportSummary::default()),
+            },
+        };
+        ensure!(
+            self.start_version <= end_version,
+            "start_version {} is larger than end_version {}.",
+            self.start_version,
+            end_version,
+        );
+        let backups = metadata_view.select_transaction_backups(self.start_version, end_version)?;
+
+        create_dir_all(&self.output_dir).await?;
+        let mut writer = RecordWriter::new(&self.output_dir).await?;
+        for backup in backups {
+            let manifest: TransactionBackup = self.storage.load_json_file(&backup.manifest).await?;
+            manifest.verify()?;
+
+            let storage = &self.storage;
+            let mut chunks = stream::iter(manifest.chunks.into_iter().filter(|chunk| {
+                chunk.last_version >= self.start_version && chunk.first_version <= end_version
+            }))
+            .map(|chunk| ExportChunk::load(chunk, storage))
+            .buffered(self.concurrent_downloads);
+            while let Some(chunk) = chunks.try_next().await? {
+                writer
+                    .write_chunk(chunk, self.start_version, end_version)
+                    .await?;
+            }
+        }
+        writer.finish().await
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ExportSummary {
+    pub num_transactions: usize,
+    pub num_events: usize,
+    pub num_balance_changes: usize,
+}
+
+struct ExportChunk {
+    first_version: Version,
+    txns: Vec<Transaction>,
+    txn_infos: Vec<TransactionInfo>,
+    event_vecs: Vec<Vec<ContractEvent>>,
+    write_sets: Vec<WriteSet>,
+}
+
+impl ExportChunk {
+    async fn load(manifest: TransactionChunk, storage: &Arc<dyn BackupStorage>) -> Result<Self> {
+        let mut file = BufReader::new(storage.open_for_read(&manifest.transactions).await?);
+        let mut txns = Vec::new();
+        let mut txn_infos = Vec::new();
+        let mut event_vecs = Vec::new();
+        let mut write_sets = Vec::new();
+        while let Some(record_bytes) = file.read_record_bytes().await? {
+            let (txn, txn_info, events, write_set) = bcs::from_bytes(&record_bytes)?;
+            txns.push(txn);
+            txn_infos.push(txn_info);
+            event_vecs.push(events);
+            write_sets.push(write_set);
+        }
+        ensure!(
+            manifest.first_version + (txns.len() as Version) == manifest.last_version + 1,
+            "Number of items in chunks doesn't match that in manifest. first_version: {}, last_version: {}, items in chunk: {}",
+            manifest.first_version,
+            manifest.last_version,
+            txns.len(),
+        );
+
+        // Same verification as the restore, plus the write sets which the restore doesn't use.
+        let (range_proof, ledger_info) = storage
+            .load_bcs_file::<(TransactionAccumulatorRangeProof, LedgerInfoWithSignatures)>(
+                &manifest.proof,
+            )
+            .await?;
+        let txn_list_with_proof = TransactionListWithProof::new(
+            txns,
+            Some(event_vecs),
+            Some(manifest.first_version),
+            TransactionInfoListWithProof::new(range_proof, txn_infos),
+        );
+        txn_list_with_proof.verify(ledger_info.ledger_info(), Some(manifest.first_version))?;
+        let txn_infos = txn_list_with_proof.proof.transaction_infos;
+        for (idx, (txn_info, write_set)) in txn_infos.iter().zip(&write_sets).enumerate() {
+            ensure!(
+                txn_info.state_change_hash() == CryptoHash::hash(write_set),
+                "Write set doesn't match the transaction info at version {}.",
+                manifest.first_version + idx as Version,
+            );
+        }
+
+        Ok(Self {
+            first_version: manifest.first_version,
+            txns: txn_list_with_proof.transactions,
+            txn_infos,
+            event_vecs: txn_list_with_proof.events.expect("unknown to be Some."),
+            write_sets,
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct TransactionRecord {
+    pub version: Version,
+    pub hash: String,
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    /// Timestamp of the block the transaction is in. Unknown for the transactions before the
+    /// first block metadata transaction exported.
+    pub timestamp_usecs: Option<u64>,
+    pub success: bool,
+    pub vm_status: String,
+    pub gas_used: u64,
+    pub sender: Option<String>,
+    pub sequence_number: Option<u64>,
+    pub max_gas_amount: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+    pub expiration_timestamp_secs: Option<u64>,
+    pub payload_type: Option<&'static str>,
+    /// Entry function called, e.g. `0x1::coin::transfer`.
+    pub function: Option<String>,
+    pub type_arguments: Vec<String>,
+    /// BCS encoded arguments, in hex.
+    pub arguments: Vec<String>,
+    pub num_events: usize,
+    pub num_write_set_changes: usize,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct EventRecord {
+    pub version: Version,
+    pub event_index: usize,
+    /// Account the event handle belongs to.
+    pub account: String,
+    pub creation_number: u64,
+    pub sequence_number: u64,
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// BCS encoded event data, in hex.
+    pub data: String,
+}
+
+/// The balance of a coin store after a transaction changed it.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct BalanceChangeRecord {
+    pub version: Version,
+    pub account: String,
+    pub coin_type: String,
+    pub balance: u64,
+    pub frozen: bool,
+}
+
+struct RecordWriter {
+    transactions: BufWriter<File>,
+    events: BufWriter<File>,
+    balance_changes: BufWriter<File>,
+    timestamp_usecs: Option<u64>,
+    summary: ExportSummary,
+}
+
+impl RecordWriter {
+    async fn new(output_dir: &Path) -> Result<Self> {
+        Ok(Self {
+            transactions: BufWriter::new(File::create(output_dir.join(TRANSACTIONS_FILE)).await?),
+            events: BufWriter::new(File::create(output_dir.join(EVENTS_FILE)).await?),
+            balance_changes: BufWriter::new(
+                File::create(output_dir.join(BALANCE_CHANGES_FILE)).await?,
+            ),
+            timestamp_usecs: None,
+            summary: ExportSummary::default(),
+        })
+    }
+
+    async fn write_chunk(
+        &mut self,
+        chunk: ExportChunk,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<()> {
+        let ExportChunk {
+            first_version,
+            txns,
+            txn_infos,
+            event_vecs,
+            write_sets,
+        } = chunk;
+        for (idx, (((txn, txn_info), events), write_set)) in txns
+            .into_iter()
+            .zip(txn_infos)
+            .zip(event_vecs)
+            .zip(write_sets)
+            .enumerate()
+        {
+            let version = first_version + idx as Version;
+            if let Transaction::BlockMetadata(block_metadata) = &txn {
+                self.timestamp_usecs = Some(block_metadata.timestamp_usecs());
+            }
+            if version < start_version {
+                continue;
+            }
+            if version > end_version {
+                break;
+            }
+
+            let txn_record = transaction_record(
+                version,
+                &txn,
+                &txn_info,
+                events.len(),
+                &write_set,
+                self.timestamp_usecs,
+            );
+            write_record(&mut self.transactions, &txn_record).await?;
+            self.summary.num_transactions += 1;
+            for event_record in event_records(version, &events) {
+                write_record(&mut self.events, &event_record).await?;
+                self.summary.num_events += 1;
+            }
+            for balance_change_record in balance_change_records(version, &write_set) {
+                write_record(&mut self.balance_changes, &balance_change_record).await?;
+                self.summary.num_balance_changes += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<ExportSummary> {
+        self.transactions.shutdown().await?;
+        self.events.shutdown().await?;
+        self.balance_changes.shutdown().await?;
+        Ok(self.summary)
+    }
+}
+
+async fn write_record<T: Serialize>(writer: &mut BufWriter<File>, record: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    Ok(())
+}
+
+fn transaction_record(
+    version: Version,
+    txn: &Transaction,
+    txn_info: &TransactionInfo,
+    num_events: usize,
+    write_set: &WriteSet,
+    timestamp_usecs: Option<u64>,
+) -> TransactionRecord {
+    let mut record = TransactionRecord {
+        version,
+        hash: txn_info.transaction_hash().to_hex_literal(),
+        type_: match txn {
+            Transaction::UserTransaction(_) => "user",
+            Transaction::GenesisTransaction(_) => "genesis",
+            Transaction::BlockMetadata(_) => "block_metadata",
+            Transaction::StateCheckpoint(_) => "state_checkpoint",
+        },
+        timestamp_usecs,
+        success: txn_info.status().is_success(),
+        vm_status: format!("{:?}", txn_info.status()),
+        gas_used: txn_info.gas_used(),
+        sender: None,
+        sequence_number: None,
+        max_gas_amount: None,
+        gas_unit_price: None,
+        expiration_timestamp_secs: None,
+        payload_type: None,
+        function: None,
+        type_arguments: Vec::new(),
+        arguments: Vec::new(),
+        num_events,
+        num_write_set_changes: write_set.iter().count(),
+    };
+
+    if let Transaction::UserTransaction(signed_txn) = txn {
+        record.sender = Some(signed_txn.sender().to_hex_literal());
+        record.sequence_number = Some(signed_txn.sequence_number());
+        record.max_gas_amount = Some(signed_txn.max_gas_amount());
+        record.gas_unit_price = Some(signed_txn.gas_unit_price());
+        record.expiration_timestamp_secs = Some(signed_txn.expiration_timestamp_secs());
+        match signed_txn.payload() {
+            TransactionPayload::EntryFunction(entry_function) => {
+                record.payload_type = Some("entry_function");
+                record.function = Some(format!(
+                    "{}::{}",
+                    entry_function.module().short_str_lossless(),
+                    entry_function.function()
+                ));
+                record.type_arguments = type_arguments(entry_function.ty_args());
+                record.arguments = entry_function.args().iter().map(hex::encode).collect();
+            }
+            TransactionPayload::Script(script) => {
+                record.payload_type = Some("script");
+                record.type_arguments = type_arguments(script.ty_args());
+                record.arguments = script
+                    .args()
+                    .iter()
+                    .map(|arg| format!("{:?}", arg))
+                    .collect();
+            }
+            TransactionPayload::ModuleBundle(_) => {
+                record.payload_type = Some("module_bundle");
+            }
+        }
+    }
+
+    record
+}
+
+fn type_arguments(ty_args: &[TypeTag]) -> Vec<String> {
+    ty_args.iter().map(ToString::to_string).collect()
+}
+
+fn event_records(version: Version, events: &[ContractEvent]) -> Vec<EventRecord> {
+    events
+        .iter()
+        .enumerate()
+        .map(|(event_index, event)| EventRecord {
+            version,
+            event_index,
+            account: event.key().get_creator_address().to_hex_literal(),
+            creation_number: event.key().get_creation_number(),
+            sequence_number: event.sequence_number(),
+            type_: event.type_tag().to_string(),
+            data: hex::encode(event.event_data()),
+        })
+        .collect()
+}
+
+/// Balances of the coin stores written by a transaction, of any coin type.
+fn balance_change_records(version: Version, write_set: &WriteSet) -> Vec<BalanceChangeRecord> {
+    write_set
+        .iter()
+        .filter_map(|(state_key, write_op)| {
+            let access_path = match state_key {
+                StateKey::AccessPath(access_path) => access_path,
+                _ => return None,
+            };
+            let coin_type = coin_store_type(access_path)?;
+            let bytes = match write_op {
+                WriteOp::Creation(bytes) | WriteOp::Modification(bytes) => bytes,
+                WriteOp::Deletion => return None,
+            };
+            // All coin stores share the layout of the AptosCoin one.
+            let coin_store: CoinStoreResource = match bcs::from_bytes(bytes) {
+                Ok(coin_store) => coin_store,
+                Err(e) => {
+                    warn!(
+                        version = version,
+                        error = ?e,
+                        "Failed to decode coin store, skipping."
+                    );
+                    return None;
+                }
+            };
+            Some(BalanceChangeRecord {
+                version,
+                account: access_path.address.to_hex_literal(),
+                coin_type,
+                balance: coin_store.coin(),
+                frozen: coin_store.frozen(),
+            })
+        })
+        .collect()
+}
+
+/// The coin type, if the access path is of a `0x1::coin::CoinStore<CoinType>`.
+fn coin_store_type(access_path: &AccessPath) -> Option<String> {
+    let StructTag {
+        address,
+        module,
+        name,
+        type_params,
+    } = access_path.get_struct_tag()?;
+    if address != AccountAddress::ONE
+        || module.as_str() != "coin"
+        || name.as_str() != "CoinStore"
+        || type_params.len() != 1
+    {
+        return None;
+    }
+    Some(type_params[0].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::{
+        account_config::aptos_test_root_address,
+        event::{EventHandle, EventKey},
+        utility_coin::APTOS_COIN_TYPE,
+        write_set::WriteSetMut,
+    };
+    use move_core_types::{language_storage::ResourceKey, move_resource::MoveStructType};
+
+    #[test]
+    fn test_balance_change_records() {
+        let account = aptos_test_root_address();
+        let coin_store_path = AccessPath::resource_access_path(ResourceKey::new(
+            account,
+            CoinStoreResource::struct_tag(),
+        ));
+        let coin_store = CoinStoreResource::new(
+            100,
+            false,
+            EventHandle::new(EventKey::new(0, account), 0),
+            EventHandle::new(EventKey::new(1, account), 0),
+        );
+        let other_path = AccessPath::new(account, b"other".to_vec());
+        let write_set = WriteSetMut::new(vec![
+            (
+                StateKey::AccessPath(coin_store_path),
+                WriteOp::Modification(bcs::to_bytes(&coin_store).unwrap()),
+            ),
+            (
+                StateKey::AccessPath(other_path),
+                WriteOp::Modification(vec![1, 2, 3]),
+            ),
+        ])
+        .freeze()
+        .unwrap();
+
+        assert_eq!(
+            balance_change_records(7, &write_set),
+            vec![BalanceChangeRecord {
+                version: 7,
+                account: account.to_hex_literal(),
+                coin_type: APTOS_COIN_TYPE.to_string(),
+                balance: 100,
+                frozen: false,
+            }]
+        );
+    }
+}
@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup;
+pub mod catalog;
+pub mod export;
 pub mod replay_verify;
+pub mod replicate;
 pub mod restore;
 pub mod verify;
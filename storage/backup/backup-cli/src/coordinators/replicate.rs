@@ -0,0 +1,537 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::{
+            backup::EpochEndingBackupController,
+            manifest::{EpochEndingBackup, EpochEndingChunk},
+        },
+        state_snapshot::{
+            backup::StateSnapshotBackupController,
+            manifest::{StateSnapshotBackup, StateSnapshotChunk},
+        },
+        transaction::{
+            backup::TransactionBackupController,
+            manifest::{TransactionBackup, TransactionChunk},
+        },
+    },
+    metadata,
+    metadata::{
+        cache::MetadataCacheOpt, EpochEndingBackupMeta, Metadata, StateSnapshotBackupMeta,
+        TransactionBackupMeta,
+    },
+    storage::{
+        command_adapter::{config::CommandAdapterConfig, CommandAdapter},
+        BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+    },
+    utils::{storage_ext::BackupStorageExt, ConcurrentDownloadsOpt},
+};
+use anyhow::{ensure, Result};
+use aptos_crypto::HashValue;
+use aptos_logger::prelude::*;
+use clap::Parser;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::{collections::HashSet, path::PathBuf, str::FromStr, sync::Arc};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Parser)]
+pub struct ReplicationOpt {
+    #[clap(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+    #[clap(
+        long,
+        parse(from_os_str),
+        help = "Command adapter config of the storage to copy the backups from."
+    )]
+    pub source_config: PathBuf,
+    #[clap(
+        long,
+        parse(from_os_str),
+        help = "Command adapter config of the storage to copy the backups to."
+    )]
+    pub destination_config: PathBuf,
+    #[clap(flatten)]
+    pub concurrent_downloads: ConcurrentDownloadsOpt,
+}
+
+/// Copies the backups in one storage to another, through the storage APIs only, so any two
+/// storages (e.g. in different clouds or regions) can be replicated.
+///
+/// Backups already in the destination are skipped. A backup is only considered to be in the
+/// destination once its metadata is saved, which happens after all of its files are copied, so
+/// an interrupted replication resumes from the backup it was copying.
+pub struct ReplicationCoordinator {
+    source: Arc<dyn BackupStorage>,
+    destination: Arc<dyn BackupStorage>,
+    metadata_cache_opt: MetadataCacheOpt,
+    concurrent_downloads: usize,
+}
+
+impl ReplicationCoordinator {
+    pub fn new(
+        source: Arc<dyn BackupStorage>,
+        destination: Arc<dyn BackupStorage>,
+        metadata_cache_opt: MetadataCacheOpt,
+        concurrent_downloads: usize,
+    ) -> Self {
+        Self {
+            source,
+            destination,
+            metadata_cache_opt,
+            concurrent_downloads,
+        }
+    }
+
+    pub async fn new_with_opt(opt: ReplicationOpt) -> Result<Self> {
+        let source = CommandAdapterConfig::load_from_file(&opt.source_config).await?;
+        let destination = CommandAdapterConfig::load_from_file(&opt.destination_config).await?;
+        Ok(Self::new(
+            Arc::new(CommandAdapter::new(source)),
+            Arc::new(CommandAdapter::new(destination)),
+            opt.metadata_cache_opt,
+            opt.concurrent_downloads.get(),
+        ))
+    }
+
+    pub async fn run(self) -> Result<ReplicationSummary> {
+        info!("Replication coordinator started.");
+        let ret = self.run_impl().await;
+        match &ret {
+            Ok(summary) => info!(
+                num_replicated = summary.num_replicated,
+                num_skipped = summary.num_skipped,
+                "Replication coordinator exiting with success."
+            ),
+            Err(e) => error!(error = ?e, "Replication coordinator failed."),
+        }
+        ret
+    }
+
+    async fn run_impl(&self) -> Result<ReplicationSummary> {
+        // The caches are synced to different storages, so they can't be shared.
+        let source_catalog = metadata::cache::sync_and_load(
+            &self
+                .metadata_cache_opt
+                .sub_cache(&ShellSafeName::from_str("source")?),
+            Arc::clone(&self.source),
+            self.concurrent_downloads,
+        )
+        .await?
+        .get_catalog()?;
+        let destination_catalog = metadata::cache::sync_and_load(
+            &self
+                .metadata_cache_opt
+                .sub_cache(&ShellSafeName::from_str("destination")?),
+            Arc::clone(&self.destination),
+            self.concurrent_downloads,
+        )
+        .await?
+        .get_catalog()?;
+
+        // Backups are identified by their metadata names, e.g. "transaction_0-99.meta".
+        let replicated: HashSet<_> = destination_catalog
+            .epoch_ending_backups
+            .into_iter()
+            .map(|m| Metadata::EpochEndingBackup(m).name().to_string())
+            .chain(
+                destination_catalog
+                    .state_snapshot_backups
+                    .into_iter()
+                    .map(|m| Metadata::StateSnapshotBackup(m).name().to_string()),
+            )
+            .chain(
+                destination_catalog
+                    .transaction_backups
+                    .into_iter()
+                    .map(|m| Metadata::TransactionBackup(m).name().to_string()),
+            )
+            .collect();
+
+        // Epoch endings go first, since the other backups are verified against them.
+        let to_replicate = source_catalog
+            .epoch_ending_backups
+            .into_iter()
+            .map(Metadata::EpochEndingBackup)
+            .chain(
+                source_catalog
+                    .state_snapshot_backups
+                    .into_iter()
+                    .map(Metadata::StateSnapshotBackup),
+            )
+            .chain(
+                source_catalog
+                    .transaction_backups
+                    .into_iter()
+                    .map(Metadata::TransactionBackup),
+            );
+
+        let mut summary = ReplicationSummary::default();
+        for backup in to_replicate {
+            let name = backup.name();
+            if replicated.contains(name.as_str()) {
+                summary.num_skipped += 1;
+                continue;
+            }
+            info!(backup = name.as_str(), "Replicating backup.");
+            let metadata = match backup {
+                Metadata::EpochEndingBackup(m) => self.replicate_epoch_ending(m).await?,
+                Metadata::StateSnapshotBackup(m) => self.replicate_state_snapshot(m).await?,
+                Metadata::TransactionBackup(m) => self.replicate_transaction(m).await?,
+                Metadata::StateSnapshotDiffBackup(_) | Metadata::Identity(_) => {
+                    unreachable!("Not part of the catalog.")
+                }
+            };
+            // Saving the metadata marks the backup as replicated.
+            self.destination
+                .save_metadata_line(&metadata.name(), &metadata.to_text_line()?)
+                .await?;
+            summary.num_replicated += 1;
+        }
+
+        Ok(summary)
+    }
+
+    async fn replicate_epoch_ending(&self, meta: EpochEndingBackupMeta) -> Result<Metadata> {
+        let manifest: EpochEndingBackup = self.source.load_json_file(&meta.manifest).await?;
+        manifest.verify()?;
+        let backup_handle = &self
+            .destination
+            .create_backup_with_random_suffix(&format!("epoch_ending_{}-", manifest.first_epoch))
+            .await?;
+
+        let chunks = stream::iter(manifest.chunks)
+            .map(|chunk| async move {
+                Ok::<_, anyhow::Error>(EpochEndingChunk {
+                    ledger_infos: self
+                        .copy_file(
+                            backup_handle,
+                            &chunk.ledger_infos,
+                            &EpochEndingBackupController::chunk_name(chunk.first_epoch),
+                        )
+                        .await?,
+                    ..chunk
+                })
+            })
+            .buffered(self.concurrent_downloads)
+            .try_collect()
+            .await?;
+        let manifest = EpochEndingBackup { chunks, ..manifest };
+        manifest.verify()?;
+
+        let manifest_handle = self
+            .write_manifest(
+                backup_handle,
+                EpochEndingBackupController::manifest_name(),
+                &manifest,
+            )
+            .await?;
+        Ok(Metadata::new_epoch_ending_backup(
+            meta.first_epoch,
+            meta.last_epoch,
+            meta.first_version,
+            meta.last_version,
+            manifest_handle,
+        ))
+    }
+
+    async fn replicate_state_snapshot(&self, meta: StateSnapshotBackupMeta) -> Result<Metadata> {
+        let manifest: StateSnapshotBackup = self.source.load_json_file(&meta.manifest).await?;
+        let backup_handle = &self
+            .destination
+            .create_backup_with_random_suffix(&format!(
+                "state_epoch_{}_ver_{}",
+                manifest.epoch, manifest.version
+            ))
+            .await?;
+
+        let chunks = stream::iter(manifest.chunks)
+            .map(|chunk| async move {
+                let blobs = self
+                    .copy_file(
+                        backup_handle,
+                        &chunk.blobs,
+                        &StateSnapshotBackupController::chunk_name(chunk.first_idx),
+                    )
+                    .await?;
+                let proof = self
+                    .copy_file(
+                        backup_handle,
+                        &chunk.proof,
+                        &StateSnapshotBackupController::chunk_proof_name(
+                            chunk.first_idx,
+                            chunk.last_idx,
+                        ),
+                    )
+                    .await?;
+                Ok::<_, anyhow::Error>(StateSnapshotChunk {
+                    blobs,
+                    proof,
+                    ..chunk
+                })
+            })
+            .buffered(self.concurrent_downloads)
+            .try_collect()
+            .await?;
+        let proof = self
+            .copy_file(
+                backup_handle,
+                &manifest.proof,
+                StateSnapshotBackupController::proof_name(),
+            )
+            .await?;
+        let manifest = StateSnapshotBackup {
+            chunks,
+            proof,
+            ..manifest
+        };
+
+        let manifest_handle = self
+            .write_manifest(
+                backup_handle,
+                StateSnapshotBackupController::manifest_name(),
+                &manifest,
+            )
+            .await?;
+        Ok(Metadata::new_state_snapshot_backup(
+            meta.epoch,
+            meta.version,
+            manifest_handle,
+        ))
+    }
+
+    async fn replicate_transaction(&self, meta: TransactionBackupMeta) -> Result<Metadata> {
+        let manifest: TransactionBackup = self.source.load_json_file(&meta.manifest).await?;
+        manifest.verify()?;
+        let backup_handle = &self
+            .destination
+            .create_backup_with_random_suffix(&format!("transaction_{}-", manifest.first_version))
+            .await?;
+
+        let chunks = stream::iter(manifest.chunks)
+            .map(|chunk| async move {
+                let proof = self
+                    .copy_file(
+                        backup_handle,
+                        &chunk.proof,
+                        &TransactionBackupController::chunk_proof_name(
+                            chunk.first_version,
+                            chunk.last_version,
+                        ),
+                    )
+                    .await?;
+                let transactions = self
+                    .copy_file(
+                        backup_handle,
+                        &chunk.transactions,
+                        &TransactionBackupController::chunk_name(chunk.first_version),
+                    )
+                    .await?;
+                Ok::<_, anyhow::Error>(TransactionChunk {
+                    transactions,
+                    proof,
+                    ..chunk
+                })
+            })
+            .buffered(self.concurrent_downloads)
+            .try_collect()
+            .await?;
+        let manifest = TransactionBackup { chunks, ..manifest };
+        manifest.verify()?;
+
+        let manifest_handle = self
+            .write_manifest(
+                backup_handle,
+                TransactionBackupController::manifest_name(),
+                &manifest,
+            )
+            .await?;
+        Ok(Metadata::new_transaction_backup(
+            meta.first_version,
+            meta.last_version,
+            manifest_handle,
+        ))
+    }
+
+    /// Copies a file to the destination, and verifies the copy by reading it back.
+    async fn copy_file(
+        &self,
+        backup_handle: &BackupHandleRef,
+        file_handle: &FileHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<FileHandle> {
+        let bytes = self.source.read_all(file_handle).await?;
+        let new_handle = self.write_file(backup_handle, name, &bytes).await?;
+
+        let copied_bytes = self.destination.read_all(&new_handle).await?;
+        ensure!(
+            HashValue::sha3_256_of(&copied_bytes) == HashValue::sha3_256_of(&bytes),
+            "Copy of {} at {} doesn't match the original.",
+            file_handle,
+            new_handle,
+        );
+        Ok(new_handle)
+    }
+
+    async fn write_manifest<T: Serialize>(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        manifest: &T,
+    ) -> Result<FileHandle> {
+        self.write_file(backup_handle, name, &serde_json::to_vec(manifest)?)
+            .await
+    }
+
+    async fn write_file(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        bytes: &[u8],
+    ) -> Result<FileHandle> {
+        let (file_handle, mut file) = self
+            .destination
+            .create_for_write(backup_handle, name)
+            .await?;
+        file.write_all(bytes).await?;
+        file.shutdown().await?;
+        Ok(file_handle)
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ReplicationSummary {
+    pub num_replicated: usize,
+    pub num_skipped: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::local_fs::LocalFs;
+    use aptos_temppath::TempPath;
+
+    async fn write_file(
+        storage: &Arc<dyn BackupStorage>,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        bytes: &[u8],
+    ) -> FileHandle {
+        let (file_handle, mut file) = storage.create_for_write(backup_handle, name).await.unwrap();
+        file.write_all(bytes).await.unwrap();
+        file.shutdown().await.unwrap();
+        file_handle
+    }
+
+    #[tokio::test]
+    async fn test_replicate() {
+        let source_dir = TempPath::new();
+        source_dir.create_as_dir().unwrap();
+        let source: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(source_dir.path().into()));
+        let destination_dir = TempPath::new();
+        destination_dir.create_as_dir().unwrap();
+        let destination: Arc<dyn BackupStorage> =
+            Arc::new(LocalFs::new(destination_dir.path().into()));
+
+        // Write a transaction backup to the source.
+        let backup_handle = source
+            .create_backup(&ShellSafeName::from_str("transaction_0-").unwrap())
+            .await
+            .unwrap();
+        let transactions = write_file(
+            &source,
+            &backup_handle,
+            &TransactionBackupController::chunk_name(0),
+            b"transactions",
+        )
+        .await;
+        let proof = write_file(
+            &source,
+            &backup_handle,
+            &TransactionBackupController::chunk_proof_name(0, 9),
+            b"proof",
+        )
+        .await;
+        let manifest = TransactionBackup {
+            first_version: 0,
+            last_version: 9,
+            chunks: vec![TransactionChunk {
+                first_version: 0,
+                last_version: 9,
+                transactions,
+                proof,
+            }],
+        };
+        let manifest_handle = write_file(
+            &source,
+            &backup_handle,
+            TransactionBackupController::manifest_name(),
+            &serde_json::to_vec(&manifest).unwrap(),
+        )
+        .await;
+        let metadata = Metadata::new_transaction_backup(0, 9, manifest_handle);
+        source
+            .save_metadata_line(&metadata.name(), &metadata.to_text_line().unwrap())
+            .await
+            .unwrap();
+
+        let cache_dir = TempPath::new();
+        let replicate = || {
+            ReplicationCoordinator::new(
+                Arc::clone(&source),
+                Arc::clone(&destination),
+                MetadataCacheOpt::new(Some(cache_dir.path().to_path_buf())),
+                2,
+            )
+            .run()
+        };
+
+        // Replicate, and verify the copy is complete.
+        assert_eq!(
+            replicate().await.unwrap(),
+            ReplicationSummary {
+                num_replicated: 1,
+                num_skipped: 0,
+            }
+        );
+        let check_cache_dir = TempPath::new();
+        let destination_catalog = metadata::cache::sync_and_load(
+            &MetadataCacheOpt::new(Some(check_cache_dir.path().to_path_buf())),
+            Arc::clone(&destination),
+            2,
+        )
+        .await
+        .unwrap()
+        .get_catalog()
+        .unwrap();
+        assert_eq!(destination_catalog.transaction_backups.len(), 1);
+        let copied_manifest: TransactionBackup = destination
+            .load_json_file(&destination_catalog.transaction_backups[0].manifest)
+            .await
+            .unwrap();
+        assert_eq!(
+            destination
+                .read_all(&copied_manifest.chunks[0].transactions)
+                .await
+                .unwrap(),
+            b"transactions"
+        );
+        assert_eq!(
+            destination
+                .read_all(&copied_manifest.chunks[0].proof)
+                .await
+                .unwrap(),
+            b"proof"
+        );
+
+        // Replicating again skips what's already replicated.
+        assert_eq!(
+            replicate().await.unwrap(),
+            ReplicationSummary {
+                num_replicated: 0,
+                num_skipped: 1,
+            }
+        );
+    }
+}
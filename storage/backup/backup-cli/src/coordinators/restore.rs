@@ -5,21 +5,27 @@ use crate::{
     backup_types::{
         epoch_ending::restore::EpochHistoryRestoreController,
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        state_snapshot_diff::restore::{
+            StateSnapshotDiffRestoreController, StateSnapshotDiffRestoreOpt,
+        },
         transaction::restore::TransactionRestoreBatchController,
     },
     metadata,
-    metadata::{cache::MetadataCacheOpt, TransactionBackupMeta},
+    metadata::{
+        cache::MetadataCacheOpt, view::MetadataView, EpochEndingBackupMeta,
+        StateSnapshotBackupMeta, StateSnapshotDiffBackupMeta, TransactionBackupMeta,
+    },
     metrics::restore::{
         COORDINATOR_FAIL_TS, COORDINATOR_START_TS, COORDINATOR_SUCC_TS, COORDINATOR_TARGET_VERSION,
     },
     storage::BackupStorage,
     utils::{unix_timestamp_sec, GlobalRestoreOptions},
 };
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use aptos_logger::prelude::*;
 use aptos_types::transaction::Version;
 use clap::Parser;
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
 
 #[derive(Parser)]
 pub struct RestoreCoordinatorOpt {
@@ -90,18 +96,16 @@ impl RestoreCoordinator {
         // N.b.
         // The coordinator now focuses on doing one procedure, ignoring the combination of options
         // supported before:
-        //   1. a most recent state snapshot
-        //   2. a only transaction and its output, at the state snapshot version
-        //   3. the epoch history from 0 up until the latest closed epoch preceding the state
-        //      snapshot version.
+        //   1. the most recent state snapshot at or before the target version
+        //   2. the transactions from the state snapshot version up to the target version, the
+        //      ones after the state snapshot being replayed
+        //   3. the epoch history from 0 up until the latest closed epoch preceding the target
+        //      version.
         // And it does so in a resume-able way.
 
         if self.replay_all {
             bail!("--replay--all not supported in this version.");
         }
-        if self.ledger_history_start_version.is_some() {
-            bail!("--ledger-history-start-version not supported in this version.");
-        }
 
         let metadata_view = metadata::cache::sync_and_load(
             &self.metadata_cache_opt,
@@ -125,29 +129,23 @@ impl RestoreCoordinator {
             return Ok(());
         }
 
-        let state_snapshot_backup =
-            if let Some(version) = self.global_opt.run_mode.get_in_progress_state_snapshot()? {
-                info!(
-                    version = version,
-                    "Found in progress state snapshot restore",
-                );
-                metadata_view.expect_state_snapshot(version)?
-            } else {
-                let max_txn_ver = metadata_view
-                    .max_transaction_version()?
-                    .ok_or_else(|| anyhow!("No transaction backup found."))?;
-                metadata_view
-                    .select_state_snapshot(std::cmp::min(self.target_version(), max_txn_ver))?
-                    .ok_or_else(|| anyhow!("No usable state snapshot."))?
-            };
-        let version = state_snapshot_backup.version;
-        let epoch_ending_backups = metadata_view.select_epoch_ending_backups(version)?;
-        let transaction_backup = metadata_view
-            .select_transaction_backups(version, version)?
-            .pop()
-            .unwrap();
-        COORDINATOR_TARGET_VERSION.set(version as i64);
-        info!(version = version, "Restore target decided.");
+        let plan = RestorePlan::new(
+            &metadata_view,
+            self.target_version(),
+            self.ledger_history_start_version,
+            self.global_opt.run_mode.get_in_progress_state_snapshot()?,
+        )?;
+        COORDINATOR_TARGET_VERSION.set(plan.target_version as i64);
+        info!("Restore plan decided:\n{}", plan);
+        let version = plan.state_version();
+        let RestorePlan {
+            target_version: _,
+            ledger_history_start_version: _,
+            state_snapshot_backup,
+            state_snapshot_diff_backup,
+            epoch_ending_backups,
+            transaction_backups,
+        } = plan;
 
         let epoch_history = if !self.skip_epoch_endings {
             Some(Arc::new(
@@ -166,19 +164,37 @@ impl RestoreCoordinator {
             None
         };
 
-        StateSnapshotRestoreController::new(
-            StateSnapshotRestoreOpt {
-                manifest_handle: state_snapshot_backup.manifest,
-                version,
-            },
-            self.global_opt.clone(),
-            Arc::clone(&self.storage),
-            epoch_history.clone(),
-        )
-        .run()
-        .await?;
+        if let Some(state_snapshot_diff_backup) = state_snapshot_diff_backup {
+            StateSnapshotDiffRestoreController::new(
+                StateSnapshotDiffRestoreOpt {
+                    manifest_handle: state_snapshot_diff_backup.manifest,
+                },
+                self.global_opt.clone(),
+                Arc::clone(&self.storage),
+                epoch_history.clone(),
+            )
+            .run()
+            .await?;
+        } else {
+            StateSnapshotRestoreController::new(
+                StateSnapshotRestoreOpt {
+                    manifest_handle: state_snapshot_backup.manifest,
+                    version,
+                },
+                self.global_opt.clone(),
+                Arc::clone(&self.storage),
+                epoch_history.clone(),
+            )
+            .run()
+            .await?;
+        }
 
-        let txn_manifests = vec![transaction_backup.manifest];
+        // Transactions after the state snapshot are replayed, up to the target version of the
+        // global options.
+        let txn_manifests = transaction_backups
+            .into_iter()
+            .map(|backup| backup.manifest)
+            .collect();
         TransactionRestoreBatchController::new(
             self.global_opt,
             self.storage,
@@ -197,24 +213,251 @@ impl RestoreCoordinator {
     fn target_version(&self) -> Version {
         self.global_opt.target_version
     }
+}
 
-    #[allow(dead_code)]
-    fn get_actual_target_version(
-        &self,
-        transaction_backups: &[TransactionBackupMeta],
-    ) -> Result<Version> {
-        if let Some(b) = transaction_backups.last() {
-            if b.last_version > self.target_version() {
-                Ok(self.target_version())
-            } else {
-                warn!(
-                    "Can't find transaction backup containing the target version, \
-                    will restore as much as possible"
-                );
-                Ok(b.last_version)
-            }
+/// The backups to restore from, to reach a target version.
+pub struct RestorePlan {
+    pub target_version: Version,
+    pub ledger_history_start_version: Version,
+    pub state_snapshot_backup: StateSnapshotBackupMeta,
+    /// If set, the state is restored to its version by layering it over `state_snapshot_backup`.
+    pub state_snapshot_diff_backup: Option<StateSnapshotDiffBackupMeta>,
+    pub epoch_ending_backups: Vec<EpochEndingBackupMeta>,
+    pub transaction_backups: Vec<TransactionBackupMeta>,
+}
+
+impl RestorePlan {
+    /// Selects the backups to restore to `target_version`, or to the latest version in the
+    /// backups if it's `Version::MAX`.
+    pub fn new(
+        metadata_view: &MetadataView,
+        target_version: Version,
+        ledger_history_start_version: Option<Version>,
+        in_progress_state_snapshot: Option<Version>,
+    ) -> Result<Self> {
+        let max_txn_ver = metadata_view
+            .max_transaction_version()?
+            .ok_or_else(|| anyhow!("No transaction backup found."))?;
+        let target_version = if target_version == Version::MAX {
+            max_txn_ver
         } else {
-            bail!("No transaction backup found.")
+            ensure!(
+                target_version <= max_txn_ver,
+                "Target version {} is newer than the latest version in the backups, {}.",
+                target_version,
+                max_txn_ver,
+            );
+            target_version
+        };
+
+        let (state_snapshot_backup, state_snapshot_diff_backup) =
+            if let Some(version) = in_progress_state_snapshot {
+                info!(
+                    version = version,
+                    "Found in progress state snapshot restore",
+                );
+                match metadata_view.find_state_snapshot_diff(version) {
+                    Some(diff) => (
+                        metadata_view.expect_state_snapshot(diff.base_version)?,
+                        Some(diff),
+                    ),
+                    None => (metadata_view.expect_state_snapshot(version)?, None),
+                }
+            } else {
+                // Whichever of the full and the differential state snapshots is newer, to replay
+                // the fewest transactions.
+                let full = metadata_view.select_state_snapshot(target_version)?;
+                match (
+                    full,
+                    metadata_view.select_state_snapshot_diff(target_version)?,
+                ) {
+                    (full, Some((diff, base)))
+                        if full
+                            .as_ref()
+                            .map_or(true, |full| diff.version > full.version) =>
+                    {
+                        (base, Some(diff))
+                    }
+                    (Some(full), _) => (full, None),
+                    (None, _) => bail!(
+                        "No state snapshot at or before the target version {}.",
+                        target_version
+                    ),
+                }
+            };
+        let version = state_snapshot_diff_backup
+            .as_ref()
+            .map_or(state_snapshot_backup.version, |diff| diff.version);
+        ensure!(
+            version <= target_version,
+            "In progress state snapshot restore at version {} is newer than the target version {}.",
+            version,
+            target_version,
+        );
+
+        let epoch_ending_backups = metadata_view.select_epoch_ending_backups(target_version)?;
+        // The ledger history before the state snapshot is restored without replaying it, e.g.
+        // to bootstrap an archival node from genesis without downloading it from peers
+        let ledger_history_start_version = ledger_history_start_version
+            .map_or(version, |start_version| {
+                std::cmp::min(start_version, version)
+            });
+        let transaction_backups = metadata_view
+            .select_transaction_backups(ledger_history_start_version, target_version)?;
+        ensure!(
+            !transaction_backups.is_empty(),
+            "No transaction backup found at version {}.",
+            version
+        );
+
+        Ok(Self {
+            target_version,
+            ledger_history_start_version,
+            state_snapshot_backup,
+            state_snapshot_diff_backup,
+            epoch_ending_backups,
+            transaction_backups,
+        })
+    }
+
+    /// Version the state is restored to, before replaying transactions.
+    pub fn state_version(&self) -> Version {
+        self.state_snapshot_diff_backup
+            .as_ref()
+            .map_or(self.state_snapshot_backup.version, |diff| diff.version)
+    }
+}
+
+impl fmt::Display for RestorePlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "target version: {}", self.target_version)?;
+        writeln!(
+            f,
+            "state snapshot: version {} at epoch {} ({})",
+            self.state_snapshot_backup.version,
+            self.state_snapshot_backup.epoch,
+            self.state_snapshot_backup.manifest,
+        )?;
+        if let Some(diff) = &self.state_snapshot_diff_backup {
+            writeln!(
+                f,
+                "differential state snapshot: version {} at epoch {} ({})",
+                diff.version, diff.epoch, diff.manifest,
+            )?;
+        }
+        writeln!(f, "epoch endings:")?;
+        for backup in &self.epoch_ending_backups {
+            writeln!(
+                f,
+                "  epochs {}-{} ({})",
+                backup.first_epoch, backup.last_epoch, backup.manifest
+            )?;
+        }
+        writeln!(
+            f,
+            "transactions: versions {}-{} restored, {}-{} replayed",
+            self.ledger_history_start_version,
+            self.state_version(),
+            self.state_version() + 1,
+            self.target_version,
+        )?;
+        for backup in &self.transaction_backups {
+            writeln!(
+                f,
+                "  versions {}-{} ({})",
+                backup.first_version, backup.last_version, backup.manifest
+            )?;
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+
+    fn metadata_view() -> MetadataView {
+        MetadataView::from(vec![
+            Metadata::new_epoch_ending_backup(0, 1, 0, 99, "e0".to_string()),
+            Metadata::new_epoch_ending_backup(2, 3, 150, 250, "e2".to_string()),
+            Metadata::new_state_snapshot_backup(1, 100, "s100".to_string()),
+            Metadata::new_state_snapshot_backup(3, 300, "s300".to_string()),
+            Metadata::new_transaction_backup(0, 99, "t0".to_string()),
+            Metadata::new_transaction_backup(100, 199, "t100".to_string()),
+            Metadata::new_transaction_backup(200, 399, "t200".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_restore_plan() {
+        let view = metadata_view();
+
+        // Picks the latest state snapshot before the target, and replays from there.
+        let plan = RestorePlan::new(&view, 250, None, None).unwrap();
+        assert_eq!(plan.target_version, 250);
+        assert_eq!(plan.ledger_history_start_version, 100);
+        assert_eq!(plan.state_snapshot_backup.version, 100);
+        assert_eq!(plan.epoch_ending_backups.len(), 2);
+        assert_eq!(
+            plan.transaction_backups
+                .iter()
+                .map(|b| b.first_version)
+                .collect::<Vec<_>>(),
+            vec![100, 200]
+        );
+
+        // Defaults to the latest version in the backups.
+        let plan = RestorePlan::new(&view, Version::MAX, Some(0), None).unwrap();
+        assert_eq!(plan.target_version, 399);
+        assert_eq!(plan.ledger_history_start_version, 0);
+        assert_eq!(plan.state_snapshot_backup.version, 300);
+        assert_eq!(plan.transaction_backups.len(), 3);
+
+        // Resumes the in progress state snapshot restore.
+        let plan = RestorePlan::new(&view, 350, None, Some(100)).unwrap();
+        assert_eq!(plan.state_snapshot_backup.version, 100);
+
+        // Can't restore beyond the backups, or before the first state snapshot.
+        assert!(RestorePlan::new(&view, 400, None, None).is_err());
+        assert!(RestorePlan::new(&view, 50, None, None).is_err());
+        assert!(RestorePlan::new(&view, 50, None, Some(100)).is_err());
+    }
+
+    #[test]
+    fn test_restore_plan_with_state_snapshot_diff() {
+        let mut metadata = vec![
+            Metadata::new_epoch_ending_backup(0, 3, 0, 300, "e0".to_string()),
+            Metadata::new_state_snapshot_backup(1, 100, "s100".to_string()),
+            Metadata::new_state_snapshot_diff_backup(100, 2, 200, "d200".to_string()),
+            Metadata::new_state_snapshot_diff_backup(100, 3, 300, "d300".to_string()),
+            // No base.
+            Metadata::new_state_snapshot_diff_backup(150, 3, 350, "d350".to_string()),
+            Metadata::new_transaction_backup(0, 399, "t0".to_string()),
+        ];
+
+        // Picks the newest differential state snapshot with a base.
+        let view = MetadataView::from(metadata.clone());
+        let plan = RestorePlan::new(&view, 399, None, None).unwrap();
+        assert_eq!(plan.state_snapshot_backup.version, 100);
+        assert_eq!(plan.state_snapshot_diff_backup.unwrap().version, 300);
+        assert_eq!(plan.ledger_history_start_version, 300);
+        let plan = RestorePlan::new(&view, 250, None, None).unwrap();
+        assert_eq!(plan.state_version(), 200);
+
+        // Resumes the in progress differential state snapshot restore.
+        let plan = RestorePlan::new(&view, 399, None, Some(200)).unwrap();
+        assert_eq!(plan.state_version(), 200);
+
+        // Unless a newer full state snapshot exists.
+        metadata.push(Metadata::new_state_snapshot_backup(
+            3,
+            310,
+            "s310".to_string(),
+        ));
+        let view = MetadataView::from(metadata);
+        let plan = RestorePlan::new(&view, 399, None, None).unwrap();
+        assert_eq!(plan.state_snapshot_backup.version, 310);
+        assert!(plan.state_snapshot_diff_backup.is_none());
     }
 }
@@ -4,7 +4,7 @@
 use crate::{
     metadata::{view::MetadataView, Metadata},
     metrics::metadata::{NUM_META_DOWNLOAD, NUM_META_FILES, NUM_META_MISS},
-    storage::{BackupStorage, FileHandle},
+    storage::{BackupStorage, FileHandle, ShellSafeName},
     utils::{error_notes::ErrorNotes, stream::StreamX},
 };
 use anyhow::{anyhow, Context, Result};
@@ -50,6 +50,23 @@ impl MetadataCacheOpt {
     // in cache we save things other than the cached files.
     const SUB_DIR: &'static str = "cache";
 
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    /// Options for a separate cache in a sub dir of this one, e.g. to cache the metadata of
+    /// multiple backup storages.
+    pub fn sub_cache(&self, name: &ShellSafeName) -> Self {
+        Self {
+            dir: Some(
+                self.dir
+                    .clone()
+                    .unwrap_or_else(|| TEMP_METADATA_CACHE_DIR.path().to_path_buf())
+                    .join(name.as_ref()),
+            ),
+        }
+    }
+
     fn cache_dir(&self) -> PathBuf {
         self.dir
             .clone()
@@ -2,16 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::metadata::{
-    EpochEndingBackupMeta, IdentityMeta, Metadata, StateSnapshotBackupMeta, TransactionBackupMeta,
+    EpochEndingBackupMeta, IdentityMeta, Metadata, StateSnapshotBackupMeta,
+    StateSnapshotDiffBackupMeta, TransactionBackupMeta,
 };
 use anyhow::{anyhow, ensure, Result};
 use aptos_types::transaction::Version;
 use itertools::Itertools;
+use serde::Serialize;
 use std::{fmt, str::FromStr};
 
 pub struct MetadataView {
     epoch_ending_backups: Vec<EpochEndingBackupMeta>,
     state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
+    state_snapshot_diff_backups: Vec<StateSnapshotDiffBackupMeta>,
     transaction_backups: Vec<TransactionBackupMeta>,
     _identity: Option<IdentityMeta>,
 }
@@ -40,6 +43,49 @@ impl MetadataView {
         })
     }
 
+    pub fn get_catalog(&self) -> Result<BackupCatalog> {
+        // Nothing is restorable if the transaction backups have gaps, but they are still
+        // cataloged.
+        let newest_restorable_point = if self.transaction_backups_continuous() {
+            self.newest_restorable_point()?
+        } else {
+            None
+        };
+
+        Ok(BackupCatalog {
+            epoch_ending_backups: self.epoch_ending_backups.iter().sorted().cloned().collect(),
+            state_snapshot_backups: self
+                .state_snapshot_backups
+                .iter()
+                .sorted()
+                .cloned()
+                .collect(),
+            transaction_backups: self.transaction_backups.iter().sorted().cloned().collect(),
+            newest_restorable_point,
+        })
+    }
+
+    /// The newest state snapshot that can be restored (the same way the restore coordinator
+    /// selects it), and the latest version the continuous transaction backups reach from it.
+    pub fn newest_restorable_point(&self) -> Result<Option<RestorablePoint>> {
+        let max_txn_ver = match self.max_transaction_version()? {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+        let state_snapshot = match self.select_state_snapshot(max_txn_ver)? {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+        let transaction_backups =
+            self.select_transaction_backups(state_snapshot.version, Version::MAX)?;
+
+        Ok(transaction_backups.last().map(|backup| RestorablePoint {
+            state_snapshot_epoch: state_snapshot.epoch,
+            state_snapshot_version: state_snapshot.version,
+            latest_version: backup.last_version,
+        }))
+    }
+
     pub fn select_state_snapshot(
         &self,
         target_version: Version,
@@ -61,6 +107,49 @@ impl MetadataView {
             .ok_or_else(|| anyhow!("State snapshot not found at version {}", version))
     }
 
+    /// Selects the newest differential state snapshot at or before `target_version`, whose base
+    /// state snapshot exists, together with the base.
+    pub fn select_state_snapshot_diff(
+        &self,
+        target_version: Version,
+    ) -> Result<Option<(StateSnapshotDiffBackupMeta, StateSnapshotBackupMeta)>> {
+        Ok(self
+            .state_snapshot_diff_backups
+            .iter()
+            .sorted_by_key(|m| m.version)
+            .rev()
+            .filter(|m| m.version <= target_version)
+            .find_map(|m| {
+                self.state_snapshot_backups
+                    .iter()
+                    .find(|base| base.version == m.base_version)
+                    .map(|base| (m.clone(), base.clone()))
+            }))
+    }
+
+    pub fn find_state_snapshot_diff(
+        &self,
+        version: Version,
+    ) -> Option<StateSnapshotDiffBackupMeta> {
+        self.state_snapshot_diff_backups
+            .iter()
+            .find(|m| m.version == version)
+            .map(Clone::clone)
+    }
+
+    /// Whether the transaction backups cover all versions from genesis to the latest backed up
+    /// one, which is what `select_transaction_backups` checks.
+    fn transaction_backups_continuous(&self) -> bool {
+        let mut next_ver = 0;
+        for backup in self.transaction_backups.iter().sorted() {
+            if backup.first_version != next_ver {
+                return false;
+            }
+            next_ver = backup.last_version + 1;
+        }
+        true
+    }
+
     pub fn select_transaction_backups(
         &self,
         start_version: Version,
@@ -132,6 +221,7 @@ impl From<Vec<Metadata>> for MetadataView {
     fn from(metadata_vec: Vec<Metadata>) -> Self {
         let mut epoch_ending_backups = Vec::new();
         let mut state_snapshot_backups = Vec::new();
+        let mut state_snapshot_diff_backups = Vec::new();
         let mut transaction_backups = Vec::new();
         let mut identity = None;
 
@@ -139,6 +229,7 @@ impl From<Vec<Metadata>> for MetadataView {
             match meta {
                 Metadata::EpochEndingBackup(e) => epoch_ending_backups.push(e),
                 Metadata::StateSnapshotBackup(s) => state_snapshot_backups.push(s),
+                Metadata::StateSnapshotDiffBackup(s) => state_snapshot_diff_backups.push(s),
                 Metadata::TransactionBackup(t) => transaction_backups.push(t),
                 Metadata::Identity(i) => identity = Some(i),
             }
@@ -147,12 +238,30 @@ impl From<Vec<Metadata>> for MetadataView {
         Self {
             epoch_ending_backups,
             state_snapshot_backups,
+            state_snapshot_diff_backups,
             transaction_backups,
             _identity: identity,
         }
     }
 }
 
+/// All backups in a storage, ordered by version
+#[derive(Clone, Serialize)]
+pub struct BackupCatalog {
+    pub epoch_ending_backups: Vec<EpochEndingBackupMeta>,
+    pub state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
+    pub transaction_backups: Vec<TransactionBackupMeta>,
+    pub newest_restorable_point: Option<RestorablePoint>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct RestorablePoint {
+    pub state_snapshot_epoch: u64,
+    pub state_snapshot_version: Version,
+    /// Transactions are backed up continuously between the state snapshot and this version.
+    pub latest_version: Version,
+}
+
 pub struct BackupStorageState {
     pub latest_epoch_ending_epoch: Option<u64>,
     pub latest_state_snapshot_epoch: Option<u64>,
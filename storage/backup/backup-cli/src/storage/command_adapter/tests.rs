@@ -194,3 +194,14 @@ fn test_pipe_fail() {
         "echo okay | (cat; true) | cat; exec 1>&-; cat | (cat; true) | cat > /dev/null",
     ));
 }
+
+/// Make sure the sample configs stay loadable.
+#[test]
+fn test_load_sample_configs() {
+    let sample_configs_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/storage/command_adapter/sample_configs");
+    for entry in std::fs::read_dir(sample_configs_dir).unwrap() {
+        let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        CommandAdapterConfig::load_from_str(&content).unwrap();
+    }
+}
@@ -175,8 +175,8 @@ pub enum StorageOpt {
     LocalFs(LocalFsOpt),
     #[clap(
         about = "Select the CommandAdapter backup storage type, which reads shell commands with which \
-    it communicates with either a local file system or a remote cloud storage. Compression or other \
-    fitlers can be added as part of the commands. See a sample config here: \
+    it communicates with either a local file system or a remote cloud storage. Compression, \
+    encryption or other filters can be added as part of the commands. See a sample config here: \
     https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/command_adapter/sample_configs/"
     )]
     CommandAdapter(CommandAdapterOpt),
@@ -271,12 +271,18 @@ impl DB {
         })
     }
 
-    /// Flushes memtable data. This is only used for testing `get_approximate_sizes_cf` in unit
-    /// tests.
+    /// Flushes memtable data, e.g. before a shutdown so the next start doesn't replay the WAL.
     pub fn flush_cf(&self, cf_name: &str) -> Result<()> {
         Ok(self.inner.flush_cf(self.get_cf_handle(cf_name)?)?)
     }
 
+    /// Compacts the whole key range of the column family, blocking until it's done.
+    pub fn compact_cf(&self, cf_name: &str) -> Result<()> {
+        self.inner
+            .compact_range_cf(self.get_cf_handle(cf_name)?, None::<&[u8]>, None::<&[u8]>);
+        Ok(())
+    }
+
     pub fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
         self.inner
             .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
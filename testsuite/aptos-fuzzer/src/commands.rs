@@ -14,6 +14,9 @@ use std::{
     process::Command,
 };
 
+/// The name of the `cargo fuzz` target, which runs the target set in `FuzzTarget::ENV_VAR`.
+static FUZZ_RUNNER: &str = "fuzz_runner";
+
 /// Generates data for this fuzz target into the output directory. Returns the number of items
 /// generated.
 ///
@@ -57,6 +60,14 @@ pub fn make_corpus(
     Ok(idx)
 }
 
+/// Minimizes the corpus of a target by running `cargo fuzz cmin`, which only keeps the smallest
+/// items that cover all the code covered by the corpus.
+pub fn minimize_corpus(target: FuzzTarget, corpus_dir: PathBuf, args: Vec<OsString>) -> Result<()> {
+    let mut cmin_args: Vec<OsString> = vec![FUZZ_RUNNER.into(), corpus_dir.into()];
+    cmin_args.extend(args);
+    run_cargo_fuzz("cmin", target, cmin_args)
+}
+
 /// Fuzz a target by running `cargo fuzz run`.
 pub fn fuzz_target(
     target: FuzzTarget,
@@ -64,8 +75,6 @@ pub fn fuzz_target(
     artifact_dir: PathBuf,
     mut args: Vec<OsString>,
 ) -> Result<()> {
-    static FUZZ_RUNNER: &str = "fuzz_runner";
-
     // Do a bit of arg parsing -- look for a "--" and insert the target and corpus directory
     // before that.
     let dash_dash_pos = args.iter().position(|x| x == "--");
@@ -85,6 +94,11 @@ pub fn fuzz_target(
     artifact_arg.push("/");
     args.push(artifact_arg);
 
+    run_cargo_fuzz("run", target, args)
+}
+
+/// Runs a `cargo fuzz` subcommand on the fuzz runner, for the target.
+fn run_cargo_fuzz(subcommand: &str, target: FuzzTarget, args: Vec<OsString>) -> Result<()> {
     // Pass the target name in as an environment variable.
     // Use the manifest directory as the current one.
     let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").ok_or_else(|| {
@@ -93,7 +107,7 @@ pub fn fuzz_target(
 
     let status = Command::new("cargo")
         .arg("fuzz")
-        .arg("run")
+        .arg(subcommand)
         .args(args)
         .current_dir(manifest_dir)
         .env(FuzzTarget::ENV_VAR, target.name())
@@ -102,9 +116,9 @@ pub fn fuzz_target(
         // appropriate.
         .env("RUSTC_BOOTSTRAP", "1")
         .status()
-        .context("cargo fuzz run errored")?;
+        .with_context(|| format!("cargo fuzz {} errored", subcommand))?;
     if !status.success() {
-        bail!("cargo fuzz run failed with status {}", status);
+        bail!("cargo fuzz {} failed with status {}", subcommand, status);
     }
     Ok(())
 }
@@ -18,6 +18,7 @@ mod secure_storage_vault;
 mod storage;
 mod transaction;
 mod vm;
+mod vm_edge_cases;
 
 // TODO(joshlind): add a fuzzer for state sync v2!
 
@@ -87,6 +88,7 @@ static ALL_TARGETS: Lazy<BTreeMap<&'static str, Box<dyn FuzzTargetImpl>>> = Lazy
         Box::new(transaction::TwoSignedTransactions::default()),
         // VM
         Box::new(vm::CompiledModuleTarget::default()),
+        Box::new(vm_edge_cases::EdgeCaseTransactions::default()),
     ];
     targets
         .into_iter()
@@ -0,0 +1,28 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{corpus_from_strategy, fuzz_data_to_value, FuzzTargetImpl};
+use aptos_mempool::fuzzing::test_core_mempool_add_validated_transactions_impl;
+use aptos_proptest_helpers::ValueGenerator;
+use language_e2e_tests::edge_case_transactions::{
+    edge_case_transactions_strategy, run_and_assert_edge_cases,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct EdgeCaseTransactions;
+
+impl FuzzTargetImpl for EdgeCaseTransactions {
+    fn description(&self) -> &'static str {
+        "Edge case transactions validated and executed by the VM, then added to mempool"
+    }
+
+    fn generate(&self, _idx: usize, _gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(corpus_from_strategy(edge_case_transactions_strategy()))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        let transaction_gens = fuzz_data_to_value(data, edge_case_transactions_strategy());
+        let accepted = run_and_assert_edge_cases(&transaction_gens).unwrap();
+        test_core_mempool_add_validated_transactions_impl(accepted);
+    }
+}
@@ -60,6 +60,19 @@ enum Command {
         #[structopt(name = "ARGS", parse(from_os_str), allow_hyphen_values = true)]
         args: Vec<OsString>,
     },
+    /// Minimize the corpus of a target, e.g. before checking it in (must be run under `cargo run`)
+    #[structopt(name = "minimize", usage = "fuzzer minimize <TARGET> -- [ARGS]")]
+    Minimize {
+        /// Target whose corpus to minimize (use `list` to list targets)
+        #[structopt(name = "TARGET", required = true)]
+        target: FuzzTarget,
+        /// Custom directory for corpus
+        #[structopt(long = "corpus-dir", parse(from_os_str))]
+        corpus_dir: Option<PathBuf>,
+        /// Arguments for `cargo fuzz cmin`
+        #[structopt(name = "ARGS", parse(from_os_str), allow_hyphen_values = true)]
+        args: Vec<OsString>,
+    },
     /// List fuzz targets
     #[structopt(name = "list")]
     List {
@@ -143,6 +156,14 @@ fn main() {
             let artifact_dir = artifact_dir.unwrap_or_else(|| default_artifact_dir(target));
             commands::fuzz_target(target, corpus_dir, artifact_dir, args).unwrap();
         }
+        Command::Minimize {
+            target,
+            corpus_dir,
+            args,
+        } => {
+            let corpus_dir = corpus_dir.unwrap_or_else(|| default_corpus_dir(target).0);
+            commands::minimize_corpus(target, corpus_dir, args).unwrap();
+        }
         Command::List { no_desc } => {
             commands::list_targets(no_desc);
         }
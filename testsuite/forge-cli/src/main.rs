@@ -10,7 +10,7 @@ use forge::system_metrics::{MetricsThreshold, SystemMetricsThreshold};
 use forge::{ForgeConfig, Options, *};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{env, num::NonZeroUsize, process, thread, time::Duration};
+use std::{env, num::NonZeroUsize, path::PathBuf, process, thread, time::Duration};
 use structopt::StructOpt;
 use testcases::consensus_reliability_tests::ChangingWorkingQuorumTest;
 use testcases::fullnode_reboot_stress_test::FullNodeRebootStressTest;
@@ -18,6 +18,7 @@ use testcases::load_vs_perf_benchmark::LoadVsPerfBenchmark;
 use testcases::network_bandwidth_test::NetworkBandwidthTest;
 use testcases::network_loss_test::NetworkLossTest;
 use testcases::performance_with_fullnode_test::PerformanceBenchmarkWithFN;
+use testcases::soak_test::SoakTest;
 use testcases::state_sync_performance::StateSyncValidatorPerformance;
 use testcases::three_region_simulation_test::ThreeRegionSimulationTest;
 use testcases::twin_validator_test::TwinValidatorTest;
@@ -50,6 +51,24 @@ struct Args {
     suite: String,
     #[structopt(long, multiple = true)]
     changelog: Option<Vec<String>>,
+    #[structopt(
+        long,
+        help = "Store the performance results of the run in this directory, and compare them to the previous runs",
+        parse(from_os_str)
+    )]
+    results_store_dir: Option<PathBuf>,
+    #[structopt(
+        long,
+        env = "FORGE_COMMIT",
+        help = "The commit the performance results are stored for",
+        default_value = "unknown"
+    )]
+    commit: String,
+    #[structopt(
+        long,
+        help = "Fail the run when a metric regresses beyond the thresholds, instead of only annotating the report"
+    )]
+    fail_on_regression: bool,
 
     // subcommand groups
     #[structopt(flatten)]
@@ -60,6 +79,7 @@ struct Args {
 enum CliCommand {
     Test(TestCommand),
     Operator(OperatorCommand),
+    Replay(Replay),
 }
 
 #[derive(StructOpt, Debug)]
@@ -78,6 +98,16 @@ enum OperatorCommand {
 #[derive(StructOpt, Debug)]
 struct LocalSwarm {}
 
+#[derive(StructOpt, Debug)]
+struct Replay {
+    #[structopt(
+        long,
+        help = "The replay bundle collected by a failed run with --replay-dir, the test is replayed from the --suite it is in",
+        parse(from_os_str)
+    )]
+    bundle_dir: PathBuf,
+}
+
 #[derive(StructOpt, Debug)]
 struct K8sSwarm {
     #[structopt(long, help = "The kubernetes namespace to use for test")]
@@ -232,6 +262,7 @@ fn main() -> Result<()> {
                         LocalFactory::from_workspace()?,
                         &args.options,
                         args.changelog.clone(),
+                        RegressionCheck::from_args(&args)?,
                     )
                 }
                 TestCommand::K8sSwarm(k8s) => {
@@ -252,12 +283,37 @@ fn main() -> Result<()> {
                         )
                         .unwrap(),
                         &args.options,
-                        args.changelog,
+                        args.changelog.clone(),
+                        RegressionCheck::from_args(&args)?,
                     )?;
                     Ok(())
                 }
             }
         }
+        // cmd input to run a failed test again, on a local swarm with the state of the failure
+        CliCommand::Replay(replay) => {
+            let manifest = ReplayManifest::load(&replay.bundle_dir)?;
+            println!(
+                "Replaying {} which failed with: {}",
+                manifest.test_name, manifest.failure
+            );
+            for event in &manifest.timeline {
+                println!("{} {}", event.timestamp_usecs, event.event);
+            }
+
+            let mut test_suite = get_test_suite(suite_name, duration)?;
+            // Loosen all criteria, like for local runs
+            test_suite.get_success_criteria_mut().avg_tps = 400;
+            test_suite.get_success_criteria_mut().max_latency_ms = 60000;
+            run_forge(
+                duration,
+                test_suite,
+                LocalReplayFactory::new(LocalFactory::from_workspace()?, replay.bundle_dir),
+                &args.options.with_exact_filter(manifest.test_name),
+                None,
+                None,
+            )
+        }
         // cmd input for cluster operations
         CliCommand::Operator(op_cmd) => match op_cmd {
             OperatorCommand::SetNodeImageTag(set_stateful_set_image_tag_config) => {
@@ -296,12 +352,59 @@ fn main() -> Result<()> {
     }
 }
 
+/// Compares the performance results of a run to the previous runs in the store
+pub struct RegressionCheck {
+    store: ResultsStore,
+    commit: String,
+    fail_on_regression: bool,
+}
+
+impl RegressionCheck {
+    fn from_args(args: &Args) -> Result<Option<Self>> {
+        let store = match &args.results_store_dir {
+            Some(dir) => ResultsStore::new(dir.clone())?,
+            None => return Ok(None),
+        };
+        Ok(Some(Self {
+            store,
+            commit: args.commit.clone(),
+            fail_on_regression: args.fail_on_regression,
+        }))
+    }
+
+    /// Returns the annotations of the report, and fails on regressions if configured to
+    fn check(&self, report: &TestReport) -> Result<String> {
+        let comparisons = check_for_regressions(
+            report,
+            &self.store,
+            &self.commit,
+            &RegressionThresholds::default(),
+        )?;
+        let annotations = comparisons
+            .iter()
+            .map(|comparison| comparison.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        println!("Comparison to the baseline:\n{}", annotations);
+
+        let regressions = comparisons.iter().filter(|c| c.regressed).count();
+        if regressions > 0 && self.fail_on_regression {
+            return Err(format_err!(
+                "{} metrics regressed from the baseline",
+                regressions
+            ));
+        }
+        Ok(annotations)
+    }
+}
+
 pub fn run_forge<F: Factory>(
     global_duration: Duration,
     tests: ForgeConfig<'_>,
     factory: F,
     options: &Options,
     logs: Option<Vec<String>>,
+    regression_check: Option<RegressionCheck>,
 ) -> Result<()> {
     let forge = Forge::new(options, tests, global_duration, factory);
 
@@ -313,6 +416,10 @@ pub fn run_forge<F: Factory>(
 
     match forge.run() {
         Ok(report) => {
+            let regression_result = match &regression_check {
+                Some(regression_check) => regression_check.check(&report),
+                None => Ok(String::new()),
+            };
             if let Some(mut changelog) = logs {
                 if changelog.len() != 2 {
                     println!("Use: changelog <from> <to>");
@@ -320,9 +427,16 @@ pub fn run_forge<F: Factory>(
                 }
                 let to_commit = changelog.remove(1);
                 let from_commit = Some(changelog.remove(0));
-                send_changelog_message(&report.to_string(), &from_commit, &to_commit);
+                let perf_msg = match &regression_result {
+                    Ok(annotations) if !annotations.is_empty() => {
+                        format!("{}\n{}", report, annotations)
+                    }
+                    Ok(_) => report.to_string(),
+                    Err(e) => format!("{}\n{}", report, e),
+                };
+                send_changelog_message(&perf_msg, &from_commit, &to_commit);
             }
-            Ok(())
+            regression_result.map(|_| ())
         }
         Err(e) => {
             eprintln!("Failed to run tests:\n{}", e);
@@ -388,6 +502,7 @@ fn get_test_suite(suite_name: &str, duration: Duration) -> Result<ForgeConfig<'s
         // TODO(rustielin): verify each test suite
         "k8s_suite" => Ok(k8s_test_suite()),
         "chaos" => Ok(chaos_test_suite(duration)),
+        "soak" => Ok(soak_test_suite()),
         single_test => single_test_suite(single_test),
     }
 }
@@ -475,6 +590,28 @@ fn single_test_suite(test_name: &str) -> Result<ForgeConfig<'static>> {
         "network_bandwidth" => config
             .with_initial_validator_count(NonZeroUsize::new(8).unwrap())
             .with_network_tests(vec![&NetworkBandwidthTest]),
+        // Measures the impact of stragglers on a fleet that is otherwise uniform
+        "heterogeneous_validators" => config
+            .with_initial_validator_count(NonZeroUsize::new(10).unwrap())
+            .with_network_tests(vec![&PerformanceBenchmark])
+            .with_validator_override(
+                0,
+                ValidatorOverride::default()
+                    .with_cpu("4")
+                    .with_memory("8Gi"),
+            )
+            .with_validator_override(
+                1,
+                ValidatorOverride::default()
+                    .with_cpu("4")
+                    .with_memory("8Gi"),
+            )
+            .with_validator_override(
+                2,
+                ValidatorOverride::default()
+                    .with_config(serde_yaml::from_str("mempool: {capacity: 1000}").unwrap()),
+            )
+            .with_success_criteria(SuccessCriteria::new(3000, 10000, true, None, None, None)),
         "setup_test" => config
             .with_initial_fullnode_count(1)
             .with_network_tests(vec![&ForgeSetupTest]),
@@ -894,6 +1031,45 @@ fn chaos_test_suite(duration: Duration) -> ForgeConfig<'static> {
         ))
 }
 
+/// Runs the network for the whole duration, usually hours, under a mix of transaction types
+fn soak_test_suite() -> ForgeConfig<'static> {
+    ForgeConfig::default()
+        .with_initial_validator_count(NonZeroUsize::new(20).unwrap())
+        .with_initial_fullnode_count(10)
+        .with_network_tests(vec![&SoakTest {
+            sample_interval: Duration::from_secs(60),
+            max_commit_lag: Duration::from_secs(10),
+            max_mempool_size: 50000,
+            max_mempool_growing_samples: 10,
+            // Leaves room for the write sets, the events and the indices of the transactions
+            max_storage_bytes_per_txn: 4 * 1024,
+            storage_slack_bytes: 1024 * 1024 * 1024,
+        }])
+        .with_genesis_helm_config_fn(Arc::new(|helm_values| {
+            helm_values["chain"]["epoch_duration_secs"] = 3600.into();
+        }))
+        .with_emit_job(
+            EmitJobRequest::default()
+                .mode(EmitJobMode::ConstTps { tps: 1000 })
+                .transaction_mix(vec![
+                    (TransactionType::P2P, 6),
+                    (TransactionType::AccountGeneration, 3),
+                    (TransactionType::NftMintAndTransfer, 1),
+                ]),
+        )
+        .with_success_criteria(SuccessCriteria::new(
+            800,
+            10000,
+            true,
+            None,
+            None,
+            Some(StateProgressThreshold {
+                max_no_progress_secs: 30.0,
+                max_round_gap: 10,
+            }),
+        ))
+}
+
 fn changing_working_quorum_test(
     num_validators: usize,
     epoch_duration: usize,
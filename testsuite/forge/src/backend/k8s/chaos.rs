@@ -7,9 +7,13 @@ use anyhow::bail;
 use aptos_logger::info;
 use tempfile::TempDir;
 
+use aptos_sdk::types::PeerId;
+
+use super::node::APTOS_DATA_DIR;
 use crate::{
-    dump_string_to_file, K8sSwarm, Result, Swarm, SwarmChaos, SwarmNetworkBandwidth,
-    SwarmNetworkDelay, SwarmNetworkLoss, SwarmNetworkPartition, KUBECTL_BIN,
+    dump_string_to_file, K8sSwarm, NodeFailureAction, Result, Swarm, SwarmChaos, SwarmDiskFill,
+    SwarmNetEm, SwarmNetworkBandwidth, SwarmNetworkDelay, SwarmNetworkLoss, SwarmNetworkPartition,
+    SwarmNetworkPartitionGroups, SwarmNodeFailure, KUBECTL_BIN,
 };
 
 macro_rules! DELAY_NETWORK_CHAOS_TEMPLATE {
@@ -33,10 +37,37 @@ macro_rules! NETWORK_LOSS_CHAOS_TEMPLATE {
         "chaos/network_loss.yaml"
     };
 }
+macro_rules! PARTITION_GROUPS_NETWORK_CHAOS_TEMPLATE {
+    () => {
+        "chaos/network_partition_groups.yaml"
+    };
+}
+macro_rules! GROUP_LOSS_NETWORK_CHAOS_TEMPLATE {
+    () => {
+        "chaos/network_group_loss.yaml"
+    };
+}
+macro_rules! NODE_FAILURE_CHAOS_TEMPLATE {
+    () => {
+        "chaos/node_failure.yaml"
+    };
+}
+macro_rules! NODE_FAILURE_SCHEDULE_CHAOS_TEMPLATE {
+    () => {
+        "chaos/node_failure_schedule.yaml"
+    };
+}
+
+/// The file allocated by disk fills, in the data directory of the nodes
+const DISK_FILL_FILE_NAME: &str = "forge-disk-fill";
 
 impl K8sSwarm {
     /// Injects the SwarmChaos into the specified namespace
     pub fn inject_swarm_chaos(&self, chaos: &SwarmChaos) -> Result<()> {
+        if let SwarmChaos::DiskFill(disk_fill) = chaos {
+            info!("Injecting chaos: {}", disk_fill);
+            return self.fill_disks(disk_fill);
+        }
         let template = self.create_chaos_template(chaos)?;
         info!("Injecting chaos: {}", template);
         self.inject_chaos_template(template)
@@ -72,6 +103,7 @@ impl K8sSwarm {
                 }
                 Ok(())
             }
+            SwarmChaos::DiskFill(disk_fill) => self.remove_disk_fills(disk_fill),
             _ => {
                 let template = self.create_chaos_template(chaos)?;
                 self.remove_chaos_template(template)
@@ -86,31 +118,8 @@ impl K8sSwarm {
         let mut network_chaos_specs = vec![];
 
         for group_network_delay in &swarm_network_delay.group_network_delays {
-            let source_instance_labels = group_network_delay
-                .source_nodes
-                .iter()
-                .map(|node| {
-                    if let Some(v) = self.validator(*node) {
-                        v.name()
-                    } else {
-                        "invalid-node"
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(",");
-
-            let target_instance_labels = group_network_delay
-                .target_nodes
-                .iter()
-                .map(|node| {
-                    if let Some(v) = self.validator(*node) {
-                        v.name()
-                    } else {
-                        "invalid-node"
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(",");
+            let source_instance_labels = self.instance_labels(&group_network_delay.source_nodes);
+            let target_instance_labels = self.instance_labels(&group_network_delay.target_nodes);
 
             network_chaos_specs.push(format!(
                 include_str!(DELAY_NETWORK_CHAOS_TEMPLATE!()),
@@ -162,15 +171,178 @@ impl K8sSwarm {
         ))
     }
 
+    /// Partitions every pair of groups, so each group is cut off from all the others
+    fn create_network_partition_groups_template(
+        &self,
+        swarm_network_partition_groups: &SwarmNetworkPartitionGroups,
+    ) -> Result<String> {
+        let groups = &swarm_network_partition_groups.groups;
+        let mut network_chaos_specs = vec![];
+        for (i, group) in groups.iter().enumerate() {
+            for (j, target_group) in groups.iter().enumerate().skip(i + 1) {
+                network_chaos_specs.push(format!(
+                    include_str!(PARTITION_GROUPS_NETWORK_CHAOS_TEMPLATE!()),
+                    name = format!("{}-{}-{}", swarm_network_partition_groups.name, i, j),
+                    namespace = self.kube_namespace,
+                    instance_labels = self.instance_labels(group),
+                    target_instance_labels = self.instance_labels(target_group),
+                ));
+            }
+        }
+        if network_chaos_specs.is_empty() {
+            bail!("At least two groups are needed to partition them");
+        }
+        Ok(network_chaos_specs.join("\n---\n"))
+    }
+
+    /// Delays and loss can't be combined in a single NetworkChaos, each group gets one of each
+    fn create_netem_template(&self, swarm_netem: &SwarmNetEm) -> Result<String> {
+        let mut network_chaos_specs = vec![];
+
+        for group_netem in &swarm_netem.group_netems {
+            let source_instance_labels = self.instance_labels(&group_netem.source_nodes);
+            let target_instance_labels = self.instance_labels(&group_netem.target_nodes);
+
+            if group_netem.delay_latency_ms > 0 || group_netem.delay_jitter_ms > 0 {
+                network_chaos_specs.push(format!(
+                    include_str!(DELAY_NETWORK_CHAOS_TEMPLATE!()),
+                    name = format!("{}-delay", group_netem.name),
+                    namespace = self.kube_namespace,
+                    latency_ms = group_netem.delay_latency_ms,
+                    jitter_ms = group_netem.delay_jitter_ms,
+                    correlation_percentage = group_netem.delay_correlation_percentage,
+                    instance_labels = &source_instance_labels,
+                    target_instance_labels = &target_instance_labels,
+                ));
+            }
+            if group_netem.loss_percentage > 0 {
+                network_chaos_specs.push(format!(
+                    include_str!(GROUP_LOSS_NETWORK_CHAOS_TEMPLATE!()),
+                    name = format!("{}-loss", group_netem.name),
+                    namespace = self.kube_namespace,
+                    loss_percentage = group_netem.loss_percentage,
+                    correlation_percentage = group_netem.loss_correlation_percentage,
+                    instance_labels = &source_instance_labels,
+                    target_instance_labels = &target_instance_labels,
+                ));
+            }
+        }
+        if network_chaos_specs.is_empty() {
+            bail!("The NetEm chaos has neither delays nor loss");
+        }
+        Ok(network_chaos_specs.join("\n---\n"))
+    }
+
+    fn create_node_failure_template(
+        &self,
+        swarm_node_failure: &SwarmNodeFailure,
+    ) -> Result<String> {
+        let (action, duration_secs) = match swarm_node_failure.action {
+            NodeFailureAction::Kill => ("pod-kill", None),
+            NodeFailureAction::Failure { duration_secs } => ("pod-failure", Some(duration_secs)),
+        };
+        let instance_labels = self.instance_labels(&swarm_node_failure.nodes);
+        // The duration is a field of the PodChaos spec, which is nested in schedules
+        let duration = |indent: &str| {
+            duration_secs.map_or_else(String::new, |duration_secs| {
+                format!("\n{}duration: \"{}s\"", indent, duration_secs)
+            })
+        };
+
+        Ok(match swarm_node_failure.interval_secs {
+            Some(interval_secs) => format!(
+                include_str!(NODE_FAILURE_SCHEDULE_CHAOS_TEMPLATE!()),
+                name = &swarm_node_failure.name,
+                namespace = self.kube_namespace,
+                interval_secs = interval_secs,
+                action = action,
+                duration = duration("    "),
+                instance_labels = &instance_labels,
+            ),
+            None => format!(
+                include_str!(NODE_FAILURE_CHAOS_TEMPLATE!()),
+                name = &swarm_node_failure.name,
+                namespace = self.kube_namespace,
+                action = action,
+                duration = duration("  "),
+                instance_labels = &instance_labels,
+            ),
+        })
+    }
+
     fn create_chaos_template(&self, chaos: &SwarmChaos) -> Result<String> {
         match chaos {
             SwarmChaos::Delay(c) => self.create_network_delay_template(c),
             SwarmChaos::Partition(c) => self.create_network_partition_template(c),
             SwarmChaos::Bandwidth(c) => self.create_network_bandwidth_template(c),
             SwarmChaos::Loss(c) => self.create_network_loss_template(c),
+            SwarmChaos::PartitionGroups(c) => self.create_network_partition_groups_template(c),
+            SwarmChaos::NetEm(c) => self.create_netem_template(c),
+            SwarmChaos::NodeFailure(c) => self.create_node_failure_template(c),
+            SwarmChaos::DiskFill(c) => bail!("Chaos {} isn't applied with a template", c),
         }
     }
 
+    /// The instance labels of the validators, which the chaos templates select the pods with
+    fn instance_labels(&self, nodes: &[PeerId]) -> String {
+        nodes
+            .iter()
+            .map(|node| {
+                if let Some(v) = self.validator(*node) {
+                    v.name()
+                } else {
+                    "invalid-node"
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Allocates the disk fill file in the data directory of each node. The file is on the
+    /// persistent volume of the node, so it survives restarts until the chaos is removed.
+    fn fill_disks(&self, swarm_disk_fill: &SwarmDiskFill) -> Result<()> {
+        let fill_file_path = format!("{}/{}", APTOS_DATA_DIR, DISK_FILL_FILE_NAME);
+        let fill_bytes = swarm_disk_fill.fill_bytes.to_string();
+        for node in &swarm_disk_fill.nodes {
+            self.exec_in_validator(*node, &["fallocate", "-l", &fill_bytes, &fill_file_path])?;
+        }
+        Ok(())
+    }
+
+    fn remove_disk_fills(&self, swarm_disk_fill: &SwarmDiskFill) -> Result<()> {
+        let fill_file_path = format!("{}/{}", APTOS_DATA_DIR, DISK_FILL_FILE_NAME);
+        for node in &swarm_disk_fill.nodes {
+            self.exec_in_validator(*node, &["rm", "-f", &fill_file_path])?;
+        }
+        Ok(())
+    }
+
+    fn exec_in_validator(&self, node: PeerId, command: &[&str]) -> Result<()> {
+        let stateful_set_name = match self.validator_stateful_set_name(node) {
+            Some(stateful_set_name) => stateful_set_name,
+            None => bail!("Validator {} not found", node),
+        };
+        let stateful_set = format!("sts/{}", stateful_set_name);
+        let mut exec = vec![
+            "-n",
+            self.kube_namespace.as_str(),
+            "exec",
+            stateful_set.as_str(),
+            "--",
+        ];
+        exec.extend_from_slice(command);
+        info!("{:?}", exec);
+        let exec_output = Command::new(KUBECTL_BIN)
+            .stdout(Stdio::inherit())
+            .args(&exec)
+            .output()
+            .expect("failed to exec in validator");
+        if !exec_output.status.success() {
+            bail!("{}", String::from_utf8(exec_output.stderr).unwrap());
+        }
+        Ok(())
+    }
+
     /// Creates and applies the NetworkChaos CRD
     fn inject_chaos_template(&self, chaos_template: String) -> Result<()> {
         let tmp_dir = TempDir::new().expect("Could not create temp dir");
@@ -220,13 +220,19 @@ pub(crate) async fn delete_k8s_resources(client: K8sClient, kube_namespace: &str
 
 pub(crate) fn delete_all_chaos(kube_namespace: &str) -> Result<()> {
     // clear everything manually, in case there are some dangling
-    let delete_networkchaos = ["-n", kube_namespace, "delete", "networkchaos", "--all"];
+    let delete_networkchaos = [
+        "-n",
+        kube_namespace,
+        "delete",
+        "networkchaos,podchaos,schedule",
+        "--all",
+    ];
     info!("{:?}", delete_networkchaos);
     let delete_networkchaos_output = Command::new(KUBECTL_BIN)
         .stdout(Stdio::inherit())
         .args(&delete_networkchaos)
         .output()
-        .expect("failed to delete all NetworkChaos");
+        .expect("failed to delete all chaos");
     if !delete_networkchaos_output.status.success() {
         bail!(
             "{}",
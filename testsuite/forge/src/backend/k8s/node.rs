@@ -25,7 +25,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-const APTOS_DATA_DIR: &str = "/opt/aptos/data";
+pub(crate) const APTOS_DATA_DIR: &str = "/opt/aptos/data";
 
 pub struct K8sNode {
     pub(crate) name: String,
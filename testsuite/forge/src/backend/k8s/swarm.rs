@@ -120,6 +120,10 @@ impl K8sSwarm {
             .to_string()
     }
 
+    pub(crate) fn validator_stateful_set_name(&self, id: PeerId) -> Option<&str> {
+        self.validators.get(&id).map(|v| v.stateful_set_name())
+    }
+
     #[allow(dead_code)]
     fn get_kube_client(&self) -> K8sClient {
         self.kube_client.clone()
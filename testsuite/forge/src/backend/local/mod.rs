@@ -211,3 +211,51 @@ impl Factory for LocalFactory {
         Ok(Box::new(swarm))
     }
 }
+
+/// Brings back up the swarm of a replay bundle, with the versions of a `LocalFactory`, instead of
+/// launching a new swarm
+pub struct LocalReplayFactory {
+    versions: Arc<HashMap<Version, LocalVersion>>,
+    bundle_dir: PathBuf,
+}
+
+impl LocalReplayFactory {
+    pub fn new(factory: LocalFactory, bundle_dir: PathBuf) -> Self {
+        Self {
+            versions: factory.versions,
+            bundle_dir,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Factory for LocalReplayFactory {
+    fn versions<'a>(&'a self) -> Box<dyn Iterator<Item = Version> + 'a> {
+        Box::new(self.versions.keys().cloned())
+    }
+
+    async fn launch_swarm(
+        &self,
+        _rng: &mut StdRng,
+        _num_validators: NonZeroUsize,
+        _num_fullnodes: usize,
+        _version: &Version,
+        _genesis_version: &Version,
+        _genesis_config: Option<&GenesisConfig>,
+        _cleanup_duration: Duration,
+        _genesis_config_fn: Option<GenesisConfigFn>,
+        _node_config_fn: Option<NodeConfigFn>,
+    ) -> Result<Box<dyn Swarm>> {
+        // no guarding, as this code path is not used in parallel
+        let guard = ActiveNodesGuard::grab(1, Arc::new(Mutex::new(0))).await;
+
+        let (mut swarm, manifest) =
+            LocalSwarm::from_replay_bundle(&self.bundle_dir, self.versions.clone(), guard)?;
+        swarm
+            .launch_replay(&manifest)
+            .await
+            .with_context(|| format!("Swarm logs can be found here: {}", swarm.logs_location()))?;
+
+        Ok(Box::new(swarm))
+    }
+}
@@ -14,7 +14,7 @@ use state_sync_driver::metadata_storage::STATE_SYNC_DB_NAME;
 use std::{
     env,
     fs::{self, OpenOptions},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Child, Command},
     str::FromStr,
 };
@@ -83,6 +83,10 @@ impl LocalNode {
         self.directory.join("node.yaml")
     }
 
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
     pub fn log_path(&self) -> PathBuf {
         self.directory.join("log")
     }
@@ -155,6 +159,10 @@ impl LocalNode {
         self.process = None;
     }
 
+    pub fn is_running(&self) -> bool {
+        self.process.is_some()
+    }
+
     pub fn port(&self) -> u16 {
         self.config.api.address.port()
     }
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    interface::system_metrics::SystemMetricsThreshold, ChainInfo, FullNode, HealthCheckError,
-    LocalNode, LocalVersion, Node, Swarm, SwarmChaos, SwarmExt, Validator, Version,
+    copy_dir_all, interface::system_metrics::SystemMetricsThreshold, ChainInfo, FullNode,
+    HealthCheckError, LocalNode, LocalVersion, Node, NodeExt, ReplayManifest, ReplayNode,
+    ReplaySwarm, Swarm, SwarmChaos, SwarmExt, Validator, Version, REPLAY_SWARM_DIR,
 };
 use anyhow::{anyhow, bail, Result};
 use aptos_config::{
@@ -25,6 +26,7 @@ use framework::ReleaseBundle;
 use prometheus_http_query::response::PromqlResult;
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     fs, mem,
     num::NonZeroUsize,
     ops,
@@ -239,6 +241,144 @@ impl LocalSwarm {
         })
     }
 
+    /// Builds the swarm of a replay bundle, in a copy of the swarm directory of the bundle so it
+    /// can be replayed again. The nodes keep the state they had at the failure.
+    pub fn from_replay_bundle(
+        bundle_dir: &Path,
+        versions: Arc<HashMap<Version, LocalVersion>>,
+        guard: ActiveNodesGuard,
+    ) -> Result<(LocalSwarm, ReplayManifest)> {
+        let manifest = ReplayManifest::load(bundle_dir)?;
+        let replay = &manifest.swarm;
+        let dir = TempDir::new()?;
+        copy_dir_all(&bundle_dir.join(REPLAY_SWARM_DIR), dir.path())?;
+        let version = versions
+            .iter()
+            .max_by(|v1, v2| v1.0.cmp(v2.0))
+            .ok_or_else(|| anyhow!("No version to replay with"))?
+            .1
+            .clone();
+
+        let mut validators = HashMap::new();
+        let mut fullnodes = HashMap::new();
+        for node in &replay.nodes {
+            // All the paths of the configs point into the directory the swarm ran in
+            let node_dir = dir.path().join(&node.dir);
+            let config_path = node_dir.join("node.yaml");
+            let config = fs::read_to_string(&config_path)?.replace(
+                &replay.swarm_dir.display().to_string(),
+                &dir.path().display().to_string(),
+            );
+            fs::write(&config_path, config)?;
+
+            let local_node = LocalNode::new(
+                version.clone(),
+                node.name.clone(),
+                node.index,
+                node_dir,
+                None,
+            )?;
+            if node.validator {
+                validators.insert(local_node.peer_id(), local_node);
+            } else {
+                fullnodes.insert(local_node.peer_id(), local_node);
+            }
+        }
+
+        let config = validators
+            .values()
+            .next()
+            .ok_or_else(|| anyhow!("No validator in the replay bundle"))?
+            .config();
+        let genesis = config
+            .execution
+            .genesis
+            .clone()
+            .ok_or_else(|| anyhow!("No genesis in the validator config"))?;
+        let genesis_waypoint = config.base.waypoint.genesis_waypoint();
+
+        let root_key = ConfigKey::new(Ed25519PrivateKey::try_from(
+            hex::decode(&replay.root_key)?.as_slice(),
+        )?);
+        // The sequence number is fetched once the nodes are up
+        let root_account = LocalAccount::new(
+            aptos_sdk::types::account_config::aptos_test_root_address(),
+            AccountKey::from_private_key(root_key.private_key()),
+            0,
+        );
+
+        let swarm = LocalSwarm {
+            node_name_counter: replay
+                .nodes
+                .iter()
+                .map(|node| node.index + 1)
+                .max()
+                .unwrap_or(0),
+            genesis,
+            genesis_waypoint,
+            versions,
+            validators,
+            fullnodes,
+            public_networks: HashMap::new(),
+            dir: SwarmDirectory::Temporary(dir),
+            root_account,
+            chain_id: ChainId::new(replay.chain_id),
+            root_key,
+            launched: false,
+            guard,
+        };
+        Ok((swarm, manifest))
+    }
+
+    /// Starts the nodes that were running at the failure of a replay bundle, the others are left
+    /// stopped
+    pub async fn launch_replay(&mut self, manifest: &ReplayManifest) -> Result<()> {
+        if self.launched {
+            return Err(anyhow!("Swarm already launched"));
+        }
+        self.launched = true;
+
+        let running = manifest
+            .swarm
+            .nodes
+            .iter()
+            .filter(|node| node.running)
+            .collect::<Vec<_>>();
+        for node in &running {
+            self.replay_node_mut(node)?.start()?;
+        }
+        let deadline = Instant::now() + Duration::from_secs(60);
+        for node in &running {
+            self.replay_node_mut(node)?
+                .wait_until_healthy(deadline)
+                .await?;
+        }
+
+        let root_address = self.root_account.address();
+        if let Some(validator) = self.validators.values().find(|node| node.is_running()) {
+            let sequence_number = validator
+                .rest_client()
+                .get_account(root_address)
+                .await?
+                .into_inner()
+                .sequence_number;
+            *self.root_account.sequence_number_mut() = sequence_number;
+        }
+        info!("Replayed swarm launched successfully.");
+        Ok(())
+    }
+
+    fn replay_node_mut(&mut self, node: &ReplayNode) -> Result<&mut LocalNode> {
+        let nodes = if node.validator {
+            &mut self.validators
+        } else {
+            &mut self.fullnodes
+        };
+        nodes
+            .get_mut(&node.peer_id)
+            .ok_or_else(|| anyhow!("Node {} of the replay bundle not found", node.name))
+    }
+
     pub async fn launch(&mut self) -> Result<()> {
         if self.launched {
             return Err(anyhow!("Swarm already launched"));
@@ -607,6 +747,52 @@ impl Swarm for LocalSwarm {
 
         ChainInfo::new(&mut self.root_account, rest_api_url, self.chain_id)
     }
+
+    async fn collect_replay_bundle(&mut self, dir: &Path) -> Result<ReplaySwarm> {
+        let swarm_dir = self.dir.to_path_buf();
+        let mut nodes = vec![];
+        let mut stopped = vec![];
+        let all_nodes = self
+            .validators
+            .values_mut()
+            .map(|node| (true, node))
+            .chain(self.fullnodes.values_mut().map(|node| (false, node)));
+        for (validator, node) in all_nodes {
+            let running = node.is_running();
+            nodes.push(ReplayNode {
+                name: node.name().to_string(),
+                index: node.index(),
+                peer_id: node.peer_id(),
+                validator,
+                dir: node.directory().strip_prefix(&swarm_dir)?.to_path_buf(),
+                running,
+            });
+            // The databases are only consistent once the nodes are stopped
+            if running {
+                node.stop();
+                stopped.push((validator, node.peer_id()));
+            }
+        }
+        nodes.sort_by_key(|node| node.index);
+
+        let result = copy_dir_all(&swarm_dir, &dir.join(REPLAY_SWARM_DIR));
+        for (validator, peer_id) in stopped {
+            let node = if validator {
+                self.validators.get_mut(&peer_id)
+            } else {
+                self.fullnodes.get_mut(&peer_id)
+            };
+            node.expect("Stopped node must exist").start()?;
+        }
+        result?;
+
+        Ok(ReplaySwarm {
+            chain_id: self.chain_id.id(),
+            root_key: hex::encode(self.root_key.private_key().to_bytes()),
+            swarm_dir,
+            nodes,
+        })
+    }
 }
 
 #[derive(Debug)]
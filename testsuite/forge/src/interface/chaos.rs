@@ -11,6 +11,10 @@ pub enum SwarmChaos {
     Partition(SwarmNetworkPartition),
     Bandwidth(SwarmNetworkBandwidth),
     Loss(SwarmNetworkLoss),
+    PartitionGroups(SwarmNetworkPartitionGroups),
+    NetEm(SwarmNetEm),
+    NodeFailure(SwarmNodeFailure),
+    DiskFill(SwarmDiskFill),
 }
 
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
@@ -77,3 +81,90 @@ impl Display for SwarmNetworkLoss {
         )
     }
 }
+
+/// Cuts the groups of validators off from each other, the validators of a group can still reach
+/// each other
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct SwarmNetworkPartitionGroups {
+    pub name: String,
+    pub groups: Vec<Vec<PeerId>>,
+}
+
+impl Display for SwarmNetworkPartitionGroups {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Partition groups {} {:?}", self.name, self.groups)
+    }
+}
+
+/// Degrades the network between groups of validators, in one direction only
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct SwarmNetEm {
+    pub group_netems: Vec<GroupNetEm>,
+}
+
+impl Display for SwarmNetEm {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "NetEm nodes {:?}", self.group_netems)
+    }
+}
+
+/// The latency, jitter and loss of the packets the source nodes receive from the target nodes.
+/// The packets the target nodes receive from the source nodes aren't affected.
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct GroupNetEm {
+    pub name: String,
+    pub source_nodes: Vec<PeerId>,
+    pub target_nodes: Vec<PeerId>,
+    pub delay_latency_ms: u64,
+    pub delay_jitter_ms: u64,
+    pub delay_correlation_percentage: u64,
+    pub loss_percentage: u64,
+    pub loss_correlation_percentage: u64,
+}
+
+/// Kills validators, once or on a schedule.
+/// Note: restarted validators fail `Swarm::ensure_no_validator_restart`.
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct SwarmNodeFailure {
+    pub name: String,
+    pub nodes: Vec<PeerId>,
+    pub action: NodeFailureAction,
+    /// The nodes fail every interval if it's set, and only once otherwise
+    pub interval_secs: Option<u64>,
+}
+
+impl Display for SwarmNodeFailure {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?} nodes {:?}", self.action, self.nodes)?;
+        if let Some(interval_secs) = self.interval_secs {
+            write!(f, " every {}s", interval_secs)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub enum NodeFailureAction {
+    /// Kills the nodes, which are restarted right away
+    Kill,
+    /// Keeps the nodes down for the duration, then restarts them
+    Failure { duration_secs: u64 },
+}
+
+/// Fills the data disks of validators, by allocating a file of `fill_bytes` in their data
+/// directory
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct SwarmDiskFill {
+    pub nodes: Vec<PeerId>,
+    pub fill_bytes: u64,
+}
+
+impl Display for SwarmDiskFill {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Fill disks of nodes {:?} with {} bytes",
+            self.nodes, self.fill_bytes
+        )
+    }
+}
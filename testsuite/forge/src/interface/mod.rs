@@ -18,6 +18,10 @@ pub use chaos::*;
 mod node;
 pub use node::*;
 mod chain_info;
+mod replay;
+pub use replay::*;
+mod state_consistency;
+pub use state_consistency::*;
 pub mod system_metrics;
 
 pub use chain_info::*;
@@ -0,0 +1,150 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Result;
+use anyhow::Context;
+use aptos_sdk::types::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The manifest at the root of a replay bundle
+pub const REPLAY_MANIFEST_FILE: &str = "replay.json";
+/// The directory of a replay bundle holding the copy of the swarm
+pub const REPLAY_SWARM_DIR: &str = "swarm";
+
+/// What a swarm needs to be brought back up from the copy of its directory, with the state its
+/// nodes had at the failure
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplaySwarm {
+    pub chain_id: u8,
+    /// Hex encoded, to send transactions from the root account again
+    pub root_key: String,
+    /// The directory the swarm ran in, which the paths of the node configs start with
+    pub swarm_dir: PathBuf,
+    pub nodes: Vec<ReplayNode>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplayNode {
+    pub name: String,
+    pub index: usize,
+    pub peer_id: PeerId,
+    pub validator: bool,
+    /// The directory of the node, relative to the directory of the swarm
+    pub dir: PathBuf,
+    /// Whether the node was running at the failure, only these are started by a replay
+    pub running: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TimelineEvent {
+    pub timestamp_usecs: u64,
+    pub event: String,
+}
+
+impl TimelineEvent {
+    pub fn now(event: String) -> Self {
+        Self {
+            timestamp_usecs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_micros() as u64,
+            event,
+        }
+    }
+}
+
+/// Describes a replay bundle: the failed test, the swarm to bring back up, and the events of the
+/// run up to the failure. The node configs, genesis, databases and logs are in `REPLAY_SWARM_DIR`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplayManifest {
+    pub test_name: String,
+    pub failure: String,
+    pub swarm: ReplaySwarm,
+    pub timeline: Vec<TimelineEvent>,
+}
+
+impl ReplayManifest {
+    pub fn load(bundle_dir: &Path) -> Result<Self> {
+        let path = bundle_dir.join(REPLAY_MANIFEST_FILE);
+        let manifest = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read the replay manifest {:?}", path))?;
+        Ok(serde_json::from_str(&manifest)?)
+    }
+
+    pub fn save(&self, bundle_dir: &Path) -> Result<()> {
+        fs::write(
+            bundle_dir.join(REPLAY_MANIFEST_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+}
+
+/// Copies the directory `src` into `dst`, which is created
+pub fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let dst = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dst)?;
+        } else {
+            fs::copy(entry.path(), &dst)
+                .with_context(|| format!("Unable to copy {:?}", entry.path()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_copy_bundle() {
+        let swarm_dir = TempDir::new().unwrap();
+        fs::create_dir_all(swarm_dir.path().join("0/db")).unwrap();
+        fs::write(swarm_dir.path().join("0/node.yaml"), "base: {}").unwrap();
+        fs::write(swarm_dir.path().join("0/db/CURRENT"), "MANIFEST-000001").unwrap();
+
+        let bundle_dir = TempDir::new().unwrap();
+        copy_dir_all(swarm_dir.path(), &bundle_dir.path().join(REPLAY_SWARM_DIR)).unwrap();
+        let manifest = ReplayManifest {
+            test_name: "test".to_string(),
+            failure: "failure".to_string(),
+            swarm: ReplaySwarm {
+                chain_id: 4,
+                root_key: "00".to_string(),
+                swarm_dir: swarm_dir.path().to_path_buf(),
+                nodes: vec![ReplayNode {
+                    name: "0".to_string(),
+                    index: 0,
+                    peer_id: PeerId::random(),
+                    validator: true,
+                    dir: PathBuf::from("0"),
+                    running: true,
+                }],
+            },
+            timeline: vec![TimelineEvent::now("test failed".to_string())],
+        };
+        manifest.save(bundle_dir.path()).unwrap();
+
+        let loaded = ReplayManifest::load(bundle_dir.path()).unwrap();
+        assert_eq!(
+            loaded.swarm.nodes[0].peer_id,
+            manifest.swarm.nodes[0].peer_id
+        );
+        assert_eq!(loaded.timeline[0].event, "test failed");
+        let copied_dir = bundle_dir.path().join(REPLAY_SWARM_DIR).join("0");
+        assert_eq!(
+            fs::read_to_string(copied_dir.join("db/CURRENT")).unwrap(),
+            "MANIFEST-000001"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{wait_for_all_nodes_to_catchup_to_version, Result};
+use anyhow::{anyhow, bail};
+use aptos_logger::info;
+use aptos_rest_client::{aptos_api_types::TransactionData, Client as RestClient};
+use aptos_sdk::{
+    bcs, crypto::HashValue, move_types::language_storage::StructTag,
+    types::account_address::AccountAddress,
+};
+use futures::future::try_join_all;
+use std::{fmt, time::Duration};
+
+/// A resource to compare across the nodes, at every checked version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceToCheck {
+    pub address: AccountAddress,
+    pub struct_tag: StructTag,
+}
+
+impl ResourceToCheck {
+    pub fn new(address: AccountAddress, struct_tag: StructTag) -> Self {
+        Self {
+            address,
+            struct_tag,
+        }
+    }
+}
+
+/// The components of the state of a node at a version, in the order they are compared. Each
+/// component is BCS encoded, so the nodes must agree on them byte for byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateSnapshot {
+    pub version: u64,
+    pub components: Vec<(String, Vec<u8>)>,
+}
+
+impl StateSnapshot {
+    /// Pulls the components of the state of the node at the version
+    pub async fn fetch(
+        client: &RestClient,
+        version: u64,
+        resources: &[ResourceToCheck],
+    ) -> Result<Self> {
+        let txn = match client
+            .get_transaction_by_version_bcs(version)
+            .await?
+            .into_inner()
+        {
+            TransactionData::OnChain(txn) => txn,
+            TransactionData::Pending(_) => bail!("Transaction at version {} is pending", version),
+        };
+        let info = &txn.info;
+        let mut components = vec![
+            component("transaction", &txn.transaction)?,
+            component(
+                "transaction_info.transaction_hash",
+                &info.transaction_hash(),
+            )?,
+            component("transaction_info.status", info.status())?,
+            component("transaction_info.gas_used", &info.gas_used())?,
+            component("transaction_info.event_root_hash", &info.event_root_hash())?,
+            component("events", &txn.events)?,
+            component(
+                "transaction_info.state_change_hash",
+                &info.state_change_hash(),
+            )?,
+            component("write_set", &txn.changes)?,
+            component(
+                "transaction_info.state_checkpoint_hash",
+                &info.state_checkpoint_hash(),
+            )?,
+            component("accumulator_root_hash", &txn.accumulator_root_hash)?,
+        ];
+
+        for resource in resources {
+            let mut account_resources = client
+                .get_account_resources_at_version_bcs(resource.address, version)
+                .await?
+                .into_inner();
+            // A missing resource is a component of its own, so nodes disagreeing on whether the
+            // resource exists are reported too
+            components.push(component(
+                &format!("resource {}::{}", resource.address, resource.struct_tag),
+                &account_resources.remove(&resource.struct_tag),
+            )?);
+        }
+
+        Ok(Self {
+            version,
+            components,
+        })
+    }
+}
+
+fn component<T: serde::Serialize + ?Sized>(name: &str, value: &T) -> Result<(String, Vec<u8>)> {
+    Ok((name.to_string(), bcs::to_bytes(value)?))
+}
+
+/// The first component the nodes disagree on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateDivergence {
+    pub version: u64,
+    pub component: String,
+    /// The nodes grouped by the value they have for the component, identified by its hash
+    pub nodes_by_value: Vec<(HashValue, Vec<String>)>,
+}
+
+impl fmt::Display for StateDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Nodes diverge on {} at version {}:",
+            self.component, self.version
+        )?;
+        for (hash, nodes) in &self.nodes_by_value {
+            write!(f, " [{}] have {}", nodes.join(", "), hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the first component, in the order of the snapshots, that the nodes disagree on
+pub fn find_first_divergence(snapshots: &[(String, StateSnapshot)]) -> Option<StateDivergence> {
+    let num_components = snapshots
+        .iter()
+        .map(|(_, snapshot)| snapshot.components.len())
+        .max()?;
+    for idx in 0..num_components {
+        let (version, component) = snapshots.iter().find_map(|(_, snapshot)| {
+            let (name, _) = snapshot.components.get(idx)?;
+            Some((snapshot.version, name))
+        })?;
+        let mut nodes_by_value: Vec<(HashValue, Vec<String>)> = vec![];
+        for (node, snapshot) in snapshots {
+            // A node missing the component, e.g. one that was asked for other resources, has the
+            // zero hash for it
+            let hash = snapshot
+                .components
+                .get(idx)
+                .filter(|(name, _)| name == component)
+                .map_or(HashValue::zero(), |(_, bytes)| {
+                    HashValue::sha3_256_of(bytes)
+                });
+            match nodes_by_value.iter_mut().find(|(value, _)| *value == hash) {
+                Some((_, nodes)) => nodes.push(node.clone()),
+                None => nodes_by_value.push((hash, vec![node.clone()])),
+            }
+        }
+        if nodes_by_value.len() > 1 {
+            return Some(StateDivergence {
+                version,
+                component: component.clone(),
+                nodes_by_value,
+            });
+        }
+    }
+    None
+}
+
+/// Waits for all the nodes to reach the versions, then checks that they agree byte for byte on
+/// the transactions, the transaction infos, the accumulator roots and the resources at each of
+/// the versions. Fails with the first component they disagree on.
+pub async fn check_state_consistency(
+    clients: &[(String, RestClient)],
+    versions: &[u64],
+    resources: &[ResourceToCheck],
+    timeout: Duration,
+) -> Result<()> {
+    let max_version = match versions.iter().max() {
+        Some(max_version) => *max_version,
+        None => return Ok(()),
+    };
+    wait_for_all_nodes_to_catchup_to_version(clients, max_version, timeout).await?;
+
+    for version in versions {
+        let snapshots = try_join_all(clients.iter().map(|(name, client)| async move {
+            let snapshot = StateSnapshot::fetch(client, *version, resources)
+                .await
+                .map_err(|error| {
+                    anyhow!(
+                        "Unable to fetch the state of {} at {}: {}",
+                        name,
+                        version,
+                        error
+                    )
+                })?;
+            Ok::<_, anyhow::Error>((name.clone(), snapshot))
+        }))
+        .await?;
+        if let Some(divergence) = find_first_divergence(&snapshots) {
+            bail!("{}", divergence);
+        }
+    }
+    info!(
+        "State consistency check passed for {} nodes at versions {:?}",
+        clients.len(),
+        versions
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(components: &[(&str, &[u8])]) -> StateSnapshot {
+        StateSnapshot {
+            version: 10,
+            components: components
+                .iter()
+                .map(|(name, bytes)| (name.to_string(), bytes.to_vec()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_find_first_divergence() {
+        let same = snapshot(&[("transaction", b"txn"), ("write_set", b"changes")]);
+        let diverged = snapshot(&[("transaction", b"txn"), ("write_set", b"other changes")]);
+        let mut snapshots = vec![
+            ("node-0".to_string(), same.clone()),
+            ("node-1".to_string(), same.clone()),
+        ];
+        assert_eq!(find_first_divergence(&snapshots), None);
+
+        snapshots.push(("node-2".to_string(), diverged));
+        let divergence = find_first_divergence(&snapshots).unwrap();
+        assert_eq!(divergence.version, 10);
+        assert_eq!(divergence.component, "write_set");
+        assert_eq!(
+            divergence.nodes_by_value,
+            vec![
+                (
+                    HashValue::sha3_256_of(b"changes"),
+                    vec!["node-0".to_string(), "node-1".to_string()]
+                ),
+                (
+                    HashValue::sha3_256_of(b"other changes"),
+                    vec!["node-2".to_string()]
+                ),
+            ]
+        );
+
+        // A missing component diverges too
+        snapshots[0].1.components.pop();
+        assert_eq!(
+            find_first_divergence(&snapshots).unwrap().nodes_by_value[0],
+            (HashValue::zero(), vec!["node-0".to_string()])
+        );
+    }
+}
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    interface::system_metrics::SystemMetricsThreshold, AptosPublicInfo, ChainInfo, FullNode,
-    NodeExt, Result, SwarmChaos, Validator, Version,
+    check_state_consistency, interface::system_metrics::SystemMetricsThreshold, AptosPublicInfo,
+    ChainInfo, FullNode, NodeExt, ReplaySwarm, ResourceToCheck, Result, SwarmChaos, Validator,
+    Version,
 };
 use anyhow::{anyhow, bail};
 use aptos_config::config::NodeConfig;
@@ -12,7 +13,10 @@ use aptos_rest_client::Client as RestClient;
 use aptos_sdk::types::PeerId;
 use futures::future::{join_all, try_join_all};
 use prometheus_http_query::response::PromqlResult;
-use std::time::{Duration, Instant};
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 use tokio::runtime::Runtime;
 
 /// Trait used to represent a running network comprised of Validators and FullNodes
@@ -105,6 +109,12 @@ pub trait Swarm: Sync {
 
     fn chain_info_for_node(&mut self, idx: usize) -> ChainInfo<'_>;
 
+    /// Copies the state of the swarm, i.e. the node configs, genesis, databases and logs, into
+    /// `dir` so that the swarm can be brought back up with it
+    async fn collect_replay_bundle(&mut self, _dir: &Path) -> Result<ReplaySwarm> {
+        bail!("Replay bundles are not supported by this backend")
+    }
+
     fn aptos_public_info_for_node(&mut self, idx: usize) -> AptosPublicInfo<'_> {
         self.chain_info_for_node(idx).into_aptos_public_info()
     }
@@ -266,6 +276,23 @@ pub trait SwarmExt: Swarm {
             .await
     }
 
+    /// Checks that all nodes agree byte for byte on their state at the versions, see
+    /// [`check_state_consistency`]
+    async fn check_state_consistency(
+        &self,
+        versions: &[u64],
+        resources: &[ResourceToCheck],
+        timeout: Duration,
+    ) -> Result<()> {
+        check_state_consistency(
+            &self.get_all_nodes_clients_with_names(),
+            versions,
+            resources,
+            timeout,
+        )
+        .await
+    }
+
     fn get_validator_clients_with_names(&self) -> Vec<(String, RestClient)> {
         self.validators()
             .map(|node| (node.name().to_string(), node.rest_client()))
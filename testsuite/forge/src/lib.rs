@@ -19,6 +19,9 @@ pub use transaction_emitter_lib::*;
 mod report;
 pub use report::*;
 
+mod regression;
+pub use regression::*;
+
 mod github;
 pub use github::*;
 
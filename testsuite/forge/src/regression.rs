@@ -0,0 +1,323 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Stores the performance results of the runs, per test and commit, and compares each run against
+//! a rolling baseline of the previous runs to detect regressions.
+
+use crate::{ReportedMetric, Result, TestReport};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Whether an increase of a metric is an improvement or a regression
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricDirection {
+    HigherIsBetter,
+    LowerIsBetter,
+}
+
+/// The direction of the metrics that are compared, the others are only stored
+pub fn metric_direction(metric: &str) -> Option<MetricDirection> {
+    match metric {
+        "avg_tps" => Some(MetricDirection::HigherIsBetter),
+        "avg_latency" | "p50_latency" | "p90_latency" | "p99_latency" => {
+            Some(MetricDirection::LowerIsBetter)
+        }
+        _ => None,
+    }
+}
+
+/// The metrics of a test in a run
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PerfResult {
+    pub test_name: String,
+    pub commit: String,
+    pub timestamp_secs: u64,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+impl PerfResult {
+    /// Groups the metrics of a report by test
+    pub fn from_report(report: &TestReport, commit: &str) -> Vec<PerfResult> {
+        let timestamp_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let mut results: BTreeMap<&str, PerfResult> = BTreeMap::new();
+        for ReportedMetric {
+            test_name,
+            metric,
+            value,
+        } in report.metrics()
+        {
+            results
+                .entry(test_name.as_str())
+                .or_insert_with(|| PerfResult {
+                    test_name: test_name.clone(),
+                    commit: commit.to_string(),
+                    timestamp_secs,
+                    metrics: BTreeMap::new(),
+                })
+                .metrics
+                .insert(metric.clone(), *value);
+        }
+        results.into_values().collect()
+    }
+}
+
+/// Appends the results of each test to a JSON lines file of its own
+pub struct ResultsStore {
+    dir: PathBuf,
+}
+
+impl ResultsStore {
+    pub fn new(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Unable to create the results store {:?}", dir))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, test_name: &str) -> PathBuf {
+        self.dir.join(format!(
+            "{}.jsonl",
+            test_name.replace(|c: char| !c.is_alphanumeric(), "_")
+        ))
+    }
+
+    /// The results of the test, oldest first
+    pub fn load(&self, test_name: &str) -> Result<Vec<PerfResult>> {
+        let path = self.path(test_name);
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        fs::read_to_string(&path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Unable to parse a result of {:?}", path))
+            })
+            .collect()
+    }
+
+    pub fn append(&self, result: &PerfResult) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(&result.test_name))?;
+        writeln!(file, "{}", serde_json::to_string(result)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RegressionThresholds {
+    /// The number of previous runs, from other commits, the baseline is made of
+    pub baseline_runs: usize,
+    /// Below that many runs in the baseline, the runs are only stored
+    pub min_baseline_runs: usize,
+    /// A metric regresses when it is worse than the baseline mean by more than this fraction,
+    /// and the difference is statistically significant
+    pub max_regression_fraction: f64,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            baseline_runs: 10,
+            min_baseline_runs: 3,
+            max_regression_fraction: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricComparison {
+    pub test_name: String,
+    pub metric: String,
+    pub value: f64,
+    pub baseline_mean: f64,
+    pub baseline_stddev: f64,
+    /// Relative to the baseline mean, positive when the metric got worse
+    pub regression_fraction: f64,
+    /// Whether the value is outside of the 95% prediction interval of the baseline
+    pub significant: bool,
+    pub regressed: bool,
+}
+
+impl fmt::Display for MetricComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {}: {:.1} vs baseline {:.1} ± {:.1} ({:+.1}% worse{})",
+            if self.regressed { "(!) " } else { "" },
+            self.test_name,
+            self.metric,
+            self.value,
+            self.baseline_mean,
+            self.baseline_stddev,
+            self.regression_fraction * 100.0,
+            if self.significant {
+                ", significant"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// The one-sided 95% critical values of the Student's t-distribution, by degrees of freedom
+const T_CRITICAL_VALUES: [f64; 30] = [
+    6.314, 2.920, 2.353, 2.132, 2.015, 1.943, 1.895, 1.860, 1.833, 1.812, 1.796, 1.782, 1.771,
+    1.761, 1.753, 1.746, 1.740, 1.734, 1.729, 1.725, 1.721, 1.717, 1.714, 1.711, 1.708, 1.706,
+    1.703, 1.701, 1.699, 1.697,
+];
+
+fn t_critical_value(degrees_of_freedom: usize) -> f64 {
+    T_CRITICAL_VALUES
+        .get(degrees_of_freedom.saturating_sub(1))
+        .copied()
+        .unwrap_or(1.645)
+}
+
+/// Compares the metrics of a result against the latest runs of the other commits in `history`
+pub fn compare_to_baseline(
+    result: &PerfResult,
+    history: &[PerfResult],
+    thresholds: &RegressionThresholds,
+) -> Vec<MetricComparison> {
+    let baseline: Vec<&PerfResult> = history
+        .iter()
+        .rev()
+        .filter(|previous| previous.commit != result.commit)
+        .take(thresholds.baseline_runs)
+        .collect();
+
+    let mut comparisons = vec![];
+    for (metric, value) in &result.metrics {
+        let direction = match metric_direction(metric) {
+            Some(direction) => direction,
+            None => continue,
+        };
+        let values: Vec<f64> = baseline
+            .iter()
+            .filter_map(|previous| previous.metrics.get(metric).copied())
+            .collect();
+        if values.len() < thresholds.min_baseline_runs.max(2) {
+            continue;
+        }
+
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let stddev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        let worse_by = match direction {
+            MetricDirection::HigherIsBetter => mean - value,
+            MetricDirection::LowerIsBetter => value - mean,
+        };
+        let regression_fraction = if mean == 0.0 { 0.0 } else { worse_by / mean };
+        // Whether the value is unlikely to come from the same distribution as the baseline,
+        // from the prediction interval of a new observation
+        let margin = t_critical_value(values.len() - 1) * stddev * (1.0 + 1.0 / n).sqrt();
+        let significant = worse_by > margin;
+        comparisons.push(MetricComparison {
+            test_name: result.test_name.clone(),
+            metric: metric.clone(),
+            value: *value,
+            baseline_mean: mean,
+            baseline_stddev: stddev,
+            regression_fraction,
+            significant,
+            regressed: significant && regression_fraction > thresholds.max_regression_fraction,
+        });
+    }
+    comparisons
+}
+
+/// Compares the results of a report against the store, then adds them to it
+pub fn check_for_regressions(
+    report: &TestReport,
+    store: &ResultsStore,
+    commit: &str,
+    thresholds: &RegressionThresholds,
+) -> Result<Vec<MetricComparison>> {
+    let mut comparisons = vec![];
+    for result in PerfResult::from_report(report, commit) {
+        let history = store.load(&result.test_name)?;
+        comparisons.extend(compare_to_baseline(&result, &history, thresholds));
+        store.append(&result)?;
+    }
+    Ok(comparisons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(commit: &str, tps: f64, latency: f64) -> PerfResult {
+        PerfResult {
+            test_name: "perf".to_string(),
+            commit: commit.to_string(),
+            timestamp_secs: 0,
+            metrics: BTreeMap::from([
+                ("avg_tps".to_string(), tps),
+                ("avg_latency".to_string(), latency),
+                ("submitted_txn".to_string(), 1000.0),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_compare_to_baseline() {
+        let thresholds = RegressionThresholds::default();
+        let history = vec![
+            result("a", 5000.0, 1000.0),
+            result("b", 5100.0, 1020.0),
+            result("c", 4900.0, 980.0),
+            result("d", 5050.0, 1010.0),
+        ];
+
+        // Within the noise of the baseline
+        let comparisons = compare_to_baseline(&result("e", 4950.0, 1030.0), &history, &thresholds);
+        assert_eq!(comparisons.len(), 2);
+        assert!(comparisons.iter().all(|c| !c.regressed));
+
+        // TPS dropped, latency improved
+        let comparisons = compare_to_baseline(&result("e", 3000.0, 500.0), &history, &thresholds);
+        let tps = comparisons.iter().find(|c| c.metric == "avg_tps").unwrap();
+        assert!(tps.significant && tps.regressed);
+        assert!((tps.regression_fraction - 0.4).abs() < 0.01);
+        let latency = comparisons
+            .iter()
+            .find(|c| c.metric == "avg_latency")
+            .unwrap();
+        assert!(!latency.regressed);
+
+        // The runs of the same commit aren't part of the baseline
+        let thresholds = RegressionThresholds {
+            min_baseline_runs: 4,
+            ..RegressionThresholds::default()
+        };
+        let comparisons = compare_to_baseline(&result("d", 3000.0, 500.0), &history, &thresholds);
+        assert!(comparisons.is_empty());
+    }
+
+    #[test]
+    fn test_results_store() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = ResultsStore::new(dir.path().to_path_buf()).unwrap();
+        assert!(store.load("perf").unwrap().is_empty());
+        store.append(&result("a", 5000.0, 1000.0)).unwrap();
+        store.append(&result("b", 5100.0, 1020.0)).unwrap();
+        assert_eq!(
+            store.load("perf").unwrap(),
+            vec![result("a", 5000.0, 1000.0), result("b", 5100.0, 1020.0)]
+        );
+    }
+}
@@ -8,6 +8,7 @@ use transaction_emitter_lib::emitter::stats::TxnStats;
 #[derive(Default, Debug, Serialize)]
 pub struct TestReport {
     metrics: Vec<ReportedMetric>,
+    series: Vec<ReportedSeries>,
     text: String,
 }
 
@@ -18,6 +19,14 @@ pub struct ReportedMetric {
     pub value: f64,
 }
 
+/// The raw data of a trend, as (seconds since the start of the test, value) points
+#[derive(Debug, Serialize)]
+pub struct ReportedSeries {
+    pub test_name: String,
+    pub metric: String,
+    pub points: Vec<(f64, f64)>,
+}
+
 impl TestReport {
     pub fn new() -> Self {
         Default::default()
@@ -31,6 +40,23 @@ impl TestReport {
         });
     }
 
+    pub fn report_series<E: ToString, M: ToString>(
+        &mut self,
+        test: E,
+        metric: M,
+        points: Vec<(f64, f64)>,
+    ) {
+        self.series.push(ReportedSeries {
+            test_name: test.to_string(),
+            metric: metric.to_string(),
+            points,
+        });
+    }
+
+    pub fn metrics(&self) -> &[ReportedMetric] {
+        &self.metrics
+    }
+
     pub fn report_text(&mut self, text: String) {
         if !self.text.is_empty() {
             self.text.push('\n');
@@ -9,6 +9,7 @@ use std::time::Duration;
 use std::{
     io::{self, Write},
     num::NonZeroUsize,
+    path::PathBuf,
     process,
 };
 use structopt::{clap::arg_enum, StructOpt};
@@ -68,12 +69,22 @@ pub struct Options {
     /// NO-OP: unsupported option, exists for compatibility with the default test harness
     /// Show captured stdout of successful tests
     show_output: bool,
+    #[structopt(long, env = "FORGE_REPLAY_DIR", parse(from_os_str))]
+    /// Collect a replay bundle of the swarm into this directory when a test fails
+    replay_dir: Option<PathBuf>,
 }
 
 impl Options {
     pub fn from_args() -> Self {
         StructOpt::from_args()
     }
+
+    /// Only runs the test with exactly this name
+    pub fn with_exact_filter(mut self, test_name: String) -> Self {
+        self.filter = Some(test_name);
+        self.filter_exact = true;
+        self
+    }
 }
 
 arg_enum! {
@@ -118,6 +129,59 @@ pub enum InitialVersion {
 pub type NodeConfigFn = Arc<dyn Fn(&mut serde_yaml::Value) + Send + Sync>;
 pub type GenesisConfigFn = Arc<dyn Fn(&mut serde_yaml::Value) + Send + Sync>;
 
+/// Overrides of the specs of a single validator on the k8s backend, on top of the helm values of
+/// all the validators, e.g. to run a few underpowered validators or one with a tiny mempool
+#[derive(Clone, Debug, Default)]
+pub struct ValidatorOverride {
+    /// The CPU limit and request, in k8s units, e.g. "4" or "500m"
+    pub cpu: Option<String>,
+    /// The memory limit and request, in k8s units, e.g. "8Gi"
+    pub memory: Option<String>,
+    /// The size of the persistent storage, e.g. "100Gi"
+    pub storage: Option<String>,
+    /// Merged into the NodeConfig of the validator, e.g. `mempool: {capacity: 100}`
+    pub config: Option<serde_yaml::Value>,
+}
+
+impl ValidatorOverride {
+    pub fn with_cpu(mut self, cpu: &str) -> Self {
+        self.cpu = Some(cpu.to_string());
+        self
+    }
+
+    pub fn with_memory(mut self, memory: &str) -> Self {
+        self.memory = Some(memory.to_string());
+        self
+    }
+
+    pub fn with_storage(mut self, storage: &str) -> Self {
+        self.storage = Some(storage.to_string());
+        self
+    }
+
+    pub fn with_config(mut self, config: serde_yaml::Value) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Sets the override of the validator at `index` in the aptos-node helm values
+    fn apply(&self, index: usize, helm_values: &mut serde_yaml::Value) {
+        let value = &mut helm_values["validator"]["overrides"][index.to_string().as_str()];
+        for (resource, quantity) in [("cpu", &self.cpu), ("memory", &self.memory)] {
+            if let Some(quantity) = quantity {
+                value["resources"]["limits"][resource] = quantity.as_str().into();
+                value["resources"]["requests"][resource] = quantity.as_str().into();
+            }
+        }
+        if let Some(storage) = &self.storage {
+            value["storage"]["size"] = storage.as_str().into();
+        }
+        if let Some(config) = &self.config {
+            value["config"] = config.clone();
+        }
+    }
+}
+
 pub struct ForgeConfig<'cfg> {
     aptos_tests: Vec<&'cfg dyn AptosTest>,
     admin_tests: Vec<&'cfg dyn AdminTest>,
@@ -141,6 +205,9 @@ pub struct ForgeConfig<'cfg> {
     /// Optional node helm values init function
     node_helm_config_fn: Option<NodeConfigFn>,
 
+    /// Overrides of the specs of some of the validators, by index
+    validator_overrides: Vec<(usize, ValidatorOverride)>,
+
     /// Transaction workload to run on the swarm
     emit_job_request: EmitJobRequest,
 
@@ -188,6 +255,17 @@ impl<'cfg> ForgeConfig<'cfg> {
         self
     }
 
+    /// Overrides the specs of the validator at `index`, replacing any previous override of it
+    pub fn with_validator_override(
+        mut self,
+        index: usize,
+        validator_override: ValidatorOverride,
+    ) -> Self {
+        self.validator_overrides.retain(|(i, _)| *i != index);
+        self.validator_overrides.push((index, validator_override));
+        self
+    }
+
     pub fn with_initial_version(mut self, initial_version: InitialVersion) -> Self {
         self.initial_version = initial_version;
         self
@@ -221,6 +299,23 @@ impl<'cfg> ForgeConfig<'cfg> {
         &mut self.success_criteria
     }
 
+    /// The node helm values init function, followed by the validator overrides
+    fn build_node_helm_config_fn(&self) -> Option<NodeConfigFn> {
+        if self.validator_overrides.is_empty() {
+            return self.node_helm_config_fn.clone();
+        }
+        let node_helm_config_fn = self.node_helm_config_fn.clone();
+        let validator_overrides = self.validator_overrides.clone();
+        Some(Arc::new(move |helm_values| {
+            if let Some(config_fn) = &node_helm_config_fn {
+                (config_fn)(helm_values);
+            }
+            for (index, validator_override) in &validator_overrides {
+                validator_override.apply(*index, helm_values);
+            }
+        }))
+    }
+
     pub fn number_of_tests(&self) -> usize {
         self.admin_tests.len() + self.network_tests.len() + self.aptos_tests.len()
     }
@@ -265,6 +360,7 @@ impl<'cfg> Default for ForgeConfig<'cfg> {
             genesis_config: None,
             genesis_helm_config_fn: None,
             node_helm_config_fn: None,
+            validator_overrides: vec![],
             emit_job_request: EmitJobRequest::default().mode(EmitJobMode::MaxLoad {
                 mempool_backlog: 40000,
             }),
@@ -351,9 +447,11 @@ impl<'cfg, F: Factory> Forge<'cfg, F> {
                 self.tests.genesis_config.as_ref(),
                 self.global_duration + Duration::from_secs(NAMESPACE_CLEANUP_DURATION_BUFFER_SECS),
                 self.tests.genesis_helm_config_fn.clone(),
-                self.tests.node_helm_config_fn.clone(),
+                self.tests.build_node_helm_config_fn(),
             ))?;
 
+            let mut timeline = vec![TimelineEvent::now("Swarm launched".to_string())];
+
             // Run AptosTests
             for test in self.filter_tests(self.tests.aptos_tests.iter()) {
                 let mut aptos_ctx = AptosContext::new(
@@ -379,6 +477,7 @@ impl<'cfg, F: Factory> Forge<'cfg, F> {
             }
 
             for test in self.filter_tests(self.tests.network_tests.iter()) {
+                timeline.push(TimelineEvent::now(format!("{} started", test.name())));
                 let mut network_ctx = NetworkContext::new(
                     CoreContext::from_rng(&mut rng),
                     &mut *swarm,
@@ -388,6 +487,16 @@ impl<'cfg, F: Factory> Forge<'cfg, F> {
                     self.tests.success_criteria.clone(),
                 );
                 let result = run_test(|| test.run(&mut network_ctx));
+                timeline.push(TimelineEvent::now(format!("{} {}", test.name(), result)));
+                if let TestResult::FailedWithMsg(failure) = &result {
+                    self.collect_replay_bundle(
+                        &runtime,
+                        &mut *swarm,
+                        test.name(),
+                        failure,
+                        &timeline,
+                    );
+                }
                 report.report_text(result.to_string());
                 summary.handle_result(test.name().to_owned(), result)?;
             }
@@ -411,6 +520,64 @@ impl<'cfg, F: Factory> Forge<'cfg, F> {
         }
     }
 
+    /// Collects the replay bundle of a failed test into the replay dir, if any. Failing to do so
+    /// doesn't fail the run.
+    fn collect_replay_bundle(
+        &self,
+        runtime: &Runtime,
+        swarm: &mut dyn Swarm,
+        test_name: &str,
+        failure: &str,
+        timeline: &[TimelineEvent],
+    ) {
+        let replay_dir = match &self.options.replay_dir {
+            Some(replay_dir) => replay_dir,
+            None => return,
+        };
+        let bundle_dir = replay_dir.join(test_name.replace(|c: char| !c.is_alphanumeric(), "_"));
+        // The ledgers of the nodes at the failure end the timeline
+        let mut timeline = timeline.to_vec();
+        let clients = swarm
+            .validators()
+            .map(|node| (node.name().to_string(), node.rest_client()))
+            .chain(
+                swarm
+                    .full_nodes()
+                    .map(|node| (node.name().to_string(), node.rest_client())),
+            )
+            .collect::<Vec<_>>();
+        for (name, client) in clients {
+            let event = match runtime.block_on(client.get_ledger_information()) {
+                Ok(state) => format!(
+                    "{} at version {} of epoch {}",
+                    name,
+                    state.inner().version,
+                    state.inner().epoch
+                ),
+                Err(e) => format!("{} unreachable: {}", name, e),
+            };
+            timeline.push(TimelineEvent::now(event));
+        }
+        let result = runtime
+            .block_on(swarm.collect_replay_bundle(&bundle_dir))
+            .and_then(|replay_swarm| {
+                ReplayManifest {
+                    test_name: test_name.to_string(),
+                    failure: failure.to_string(),
+                    swarm: replay_swarm,
+                    timeline,
+                }
+                .save(&bundle_dir)
+            });
+        match result {
+            Ok(()) => println!("Replay bundle of {} written to {:?}", test_name, bundle_dir),
+            Err(e) => println!(
+                "Failed to collect the replay bundle of {}: {:?}",
+                test_name, e
+            ),
+        }
+    }
+
     fn filter_tests<'a, T: Test, I: Iterator<Item = T> + 'a>(
         &'a self,
         tests: I,
@@ -1,14 +1,16 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::smoke_test_environment::SwarmBuilder;
+use crate::smoke_test_environment::{ComponentKind, SwarmBuilder};
 use crate::test_utils::{assert_balance, create_and_fund_account, transfer_coins};
 use aptos_config::{
     config::{DiscoveryMethod, NodeConfig, Peer, PeerRole, HANDSHAKE_VERSION},
     network_id::NetworkId,
 };
+use aptos_rest_client::{Client as RestClient, FaucetClient};
+use aptos_sdk::types::LocalAccount;
 use aptos_types::network_address::{NetworkAddress, Protocol};
-use forge::{LocalSwarm, NodeExt, Swarm, SwarmExt};
+use forge::{LocalSwarm, Node, NodeExt, Swarm, SwarmExt};
 use std::{
     collections::HashSet,
     net::Ipv4Addr,
@@ -266,6 +268,60 @@ async fn test_private_full_node() {
     assert_balance(&validator_client, &account_1, 20).await;
 }
 
+#[tokio::test]
+async fn test_local_testnet_topology() {
+    let testnet = SwarmBuilder::new_local(1)
+        .with_num_fullnodes(1)
+        .with_num_pfns(1)
+        .with_faucet()
+        .with_aptos()
+        .build_testnet()
+        .await;
+    let endpoints = testnet.endpoints();
+    let kinds: Vec<_> = endpoints.iter().map(|endpoints| endpoints.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ComponentKind::Validator,
+            ComponentKind::ValidatorFullnode,
+            ComponentKind::PublicFullnode,
+            ComponentKind::Faucet,
+        ]
+    );
+
+    // Every node serves its REST API and its metrics
+    for endpoints in endpoints
+        .iter()
+        .filter(|endpoints| endpoints.metrics.is_some())
+    {
+        RestClient::new(endpoints.rest_api.clone())
+            .get_ledger_information()
+            .await
+            .unwrap();
+        let metrics = reqwest::get(endpoints.metrics.clone().unwrap())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(metrics.contains("aptos_"), "{}", endpoints.name);
+    }
+
+    // Accounts funded by the faucet are visible on the PFN
+    let pfn_url = testnet
+        .swarm
+        .fullnode(testnet.pfns[0])
+        .unwrap()
+        .rest_api_endpoint();
+    let faucet_url = testnet.faucet.as_ref().unwrap().endpoint.clone();
+    let account = LocalAccount::generate(&mut rand::rngs::OsRng);
+    FaucetClient::new(faucet_url, pfn_url.clone())
+        .fund(account.address(), 100)
+        .await
+        .unwrap();
+    assert_balance(&RestClient::new(pfn_url), &account, 100).await;
+}
+
 fn add_node_to_seeds(
     dest_config: &mut NodeConfig,
     seed_config: &NodeConfig,
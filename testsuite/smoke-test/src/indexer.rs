@@ -104,7 +104,7 @@ async fn test_old_indexer() {
             config.storage.enable_indexer = true;
 
             config.indexer.enabled = true;
-            config.indexer.postgres_uri = Some(get_database_url());
+            config.indexer.postgres_uri = Some(get_database_url().into());
             config.indexer.processor =
                 Some(aptos_indexer::processors::default_processor::NAME.to_string());
         }))
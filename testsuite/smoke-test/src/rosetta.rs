@@ -165,6 +165,20 @@ async fn test_block_transactions() {
         }
     );
 
+    // A block hash that doesn't match the block index should be rejected
+    rosetta_client
+        .account_balance(&AccountBalanceRequest {
+            network_identifier: NetworkIdentifier::from(chain_id),
+            account_identifier: AccountIdentifier::base_account(account_1),
+            block_identifier: Some(PartialBlockIdentifier {
+                index: Some(0),
+                hash: Some(BlockHash::new(chain_id, 1).to_string()),
+            }),
+            currencies: None,
+        })
+        .await
+        .expect_err("Mismatched block hash and index should fail");
+
     // First fund account 1 with lots more gas
     cli.fund_account(0, Some(DEFAULT_FUNDED_COINS * 10))
         .await
@@ -925,7 +939,7 @@ async fn test_block() {
     )
     .await
     .expect_err("Set voter shouldn't work with the wrong operator!");
-    let final_txn = set_voter_and_wait(
+    set_voter_and_wait(
         &rosetta_client,
         &rest_client,
         &network_identifier,
@@ -940,6 +954,52 @@ async fn test_block() {
     .await
     .expect("Set voter should work!");
 
+    // Add stake and unlock it, with the operator found by the metadata call
+    add_stake_and_wait(
+        &rosetta_client,
+        &rest_client,
+        &network_identifier,
+        private_key_3,
+        None,
+        1_000,
+        Duration::from_secs(5),
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Add stake should work!");
+    unlock_stake_and_wait(
+        &rosetta_client,
+        &rest_client,
+        &network_identifier,
+        private_key_3,
+        Some(account_id_1),
+        1_000,
+        Duration::from_secs(5),
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Unlock stake should work!");
+
+    // Anyone can distribute the unlocked stake
+    let final_txn = distribute_staking_rewards_and_wait(
+        &rosetta_client,
+        &rest_client,
+        &network_identifier,
+        private_key_1,
+        account_id_1,
+        account_id_3,
+        Duration::from_secs(5),
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Distribute staking rewards should work!");
+
     let final_block_to_check = rest_client
         .get_block_by_version(final_txn.info.version.0, false)
         .await
@@ -1464,6 +1524,71 @@ async fn parse_operations(
             OperationType::InitializeStakePool => {
                 // This is not supported in block reads
             }
+            OperationType::AddStake | OperationType::UnlockStake => {
+                if actual_successful {
+                    assert_eq!(
+                        OperationStatusType::Success,
+                        status,
+                        "Successful transaction should have successful stake operation"
+                    );
+                } else {
+                    assert_eq!(
+                        OperationStatusType::Failure,
+                        status,
+                        "Failed transaction should have failed stake operation"
+                    );
+                }
+
+                // Check that the operator and amount are the same
+                if let aptos_types::transaction::Transaction::UserTransaction(ref txn) =
+                    actual_txn.transaction
+                {
+                    if let aptos_types::transaction::TransactionPayload::EntryFunction(
+                        ref payload,
+                    ) = txn.payload()
+                    {
+                        let actual_operator_address: AccountAddress =
+                            bcs::from_bytes(payload.args().first().unwrap()).unwrap();
+                        let actual_amount: u64 =
+                            bcs::from_bytes(payload.args().get(1).unwrap()).unwrap();
+                        let metadata = operation.metadata.as_ref().unwrap();
+                        let operator = metadata
+                            .operator
+                            .as_ref()
+                            .unwrap()
+                            .account_address()
+                            .unwrap();
+                        assert_eq!(actual_operator_address, operator);
+
+                        // Unlocking can be capped to the active stake
+                        let amount = metadata.amount.unwrap().0;
+                        if operation_type == OperationType::AddStake {
+                            assert_eq!(actual_amount, amount);
+                        } else {
+                            assert!(amount <= actual_amount);
+                        }
+                    } else {
+                        panic!("Not an entry function");
+                    }
+                } else {
+                    panic!("Not a user transaction");
+                }
+            }
+            OperationType::DistributeStakingRewards => {
+                if actual_successful {
+                    assert_eq!(
+                        OperationStatusType::Success,
+                        status,
+                        "Successful transaction should have successful distribute operation"
+                    );
+                } else {
+                    assert_eq!(
+                        OperationStatusType::Failure,
+                        status,
+                        "Failed transaction should have failed distribute operation"
+                    );
+                }
+            }
         }
     }
 
@@ -1878,6 +2003,102 @@ async fn set_voter_and_wait(
         .map_err(ErrorWrapper::AfterSubmission)
 }
 
+async fn add_stake_and_wait(
+    rosetta_client: &RosettaClient,
+    rest_client: &aptos_rest_client::Client,
+    network_identifier: &NetworkIdentifier,
+    sender_key: &Ed25519PrivateKey,
+    operator: Option<AccountAddress>,
+    amount: u64,
+    txn_expiry_duration: Duration,
+    sequence_number: Option<u64>,
+    max_gas: Option<u64>,
+    gas_unit_price: Option<u64>,
+) -> Result<Box<UserTransaction>, ErrorWrapper> {
+    let expiry_time = expiry_time(txn_expiry_duration);
+    let txn_hash = rosetta_client
+        .add_stake(
+            network_identifier,
+            sender_key,
+            operator,
+            amount,
+            expiry_time.as_secs(),
+            sequence_number,
+            max_gas,
+            gas_unit_price,
+        )
+        .await
+        .map_err(ErrorWrapper::BeforeSubmission)?
+        .hash;
+    wait_for_transaction(rest_client, expiry_time, txn_hash)
+        .await
+        .map_err(ErrorWrapper::AfterSubmission)
+}
+
+async fn unlock_stake_and_wait(
+    rosetta_client: &RosettaClient,
+    rest_client: &aptos_rest_client::Client,
+    network_identifier: &NetworkIdentifier,
+    sender_key: &Ed25519PrivateKey,
+    operator: Option<AccountAddress>,
+    amount: u64,
+    txn_expiry_duration: Duration,
+    sequence_number: Option<u64>,
+    max_gas: Option<u64>,
+    gas_unit_price: Option<u64>,
+) -> Result<Box<UserTransaction>, ErrorWrapper> {
+    let expiry_time = expiry_time(txn_expiry_duration);
+    let txn_hash = rosetta_client
+        .unlock_stake(
+            network_identifier,
+            sender_key,
+            operator,
+            amount,
+            expiry_time.as_secs(),
+            sequence_number,
+            max_gas,
+            gas_unit_price,
+        )
+        .await
+        .map_err(ErrorWrapper::BeforeSubmission)?
+        .hash;
+    wait_for_transaction(rest_client, expiry_time, txn_hash)
+        .await
+        .map_err(ErrorWrapper::AfterSubmission)
+}
+
+async fn distribute_staking_rewards_and_wait(
+    rosetta_client: &RosettaClient,
+    rest_client: &aptos_rest_client::Client,
+    network_identifier: &NetworkIdentifier,
+    sender_key: &Ed25519PrivateKey,
+    operator: AccountAddress,
+    staker: AccountAddress,
+    txn_expiry_duration: Duration,
+    sequence_number: Option<u64>,
+    max_gas: Option<u64>,
+    gas_unit_price: Option<u64>,
+) -> Result<Box<UserTransaction>, ErrorWrapper> {
+    let expiry_time = expiry_time(txn_expiry_duration);
+    let txn_hash = rosetta_client
+        .distribute_staking_rewards(
+            network_identifier,
+            sender_key,
+            operator,
+            staker,
+            expiry_time.as_secs(),
+            sequence_number,
+            max_gas,
+            gas_unit_price,
+        )
+        .await
+        .map_err(ErrorWrapper::BeforeSubmission)?
+        .hash;
+    wait_for_transaction(rest_client, expiry_time, txn_hash)
+        .await
+        .map_err(ErrorWrapper::AfterSubmission)
+}
+
 async fn wait_for_transaction(
     rest_client: &aptos_rest_client::Client,
     expiry_time: Duration,
@@ -5,20 +5,27 @@ use aptos::test::CliTestFramework;
 use aptos_config::config::NodeConfig;
 use aptos_config::{keys::ConfigKey, utils::get_available_port};
 use aptos_crypto::ed25519::Ed25519PrivateKey;
-use aptos_faucet::FaucetArgs;
+use aptos_faucet::{abuse::AbuseProtectionArgs, FaucetArgs, FundingStrategy};
 use aptos_genesis::builder::{InitConfigFn, InitGenesisConfigFn};
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
-use aptos_types::{account_config::aptos_test_root_address, chain_id::ChainId};
-use forge::{ActiveNodesGuard, Node};
+use aptos_types::{account_config::aptos_test_root_address, chain_id::ChainId, PeerId};
+use forge::{ActiveNodesGuard, Node, NodeExt, Swarm};
 use forge::{Factory, LocalFactory, LocalSwarm};
 use framework::ReleaseBundle;
 use once_cell::sync::Lazy;
 use rand::rngs::OsRng;
-use std::{num::NonZeroUsize, path::PathBuf, sync::Arc};
+use reqwest::Url;
+use std::{
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::task::JoinHandle;
 
 const SWARM_BUILD_NUM_RETRIES: u8 = 3;
+const FULLNODE_HEALTHY_TIMEOUT_SECS: u64 = 60;
 
 #[derive(Clone)]
 pub struct SwarmBuilder {
@@ -29,6 +36,10 @@ pub struct SwarmBuilder {
     init_config: Option<InitConfigFn>,
     vfn_config: Option<NodeConfig>,
     init_genesis_config: Option<InitGenesisConfigFn>,
+    num_pfns: usize,
+    pfn_config: Option<NodeConfig>,
+    faucet: bool,
+    indexer_database_url: Option<String>,
 }
 
 impl SwarmBuilder {
@@ -41,6 +52,10 @@ impl SwarmBuilder {
             init_config: None,
             vfn_config: None,
             init_genesis_config: None,
+            num_pfns: 0,
+            pfn_config: None,
+            faucet: false,
+            indexer_database_url: None,
         }
     }
 
@@ -78,6 +93,30 @@ impl SwarmBuilder {
         self
     }
 
+    /// Public fullnodes, added by `build_testnet` once the validators and VFNs are up
+    pub fn with_num_pfns(mut self, num_pfns: usize) -> Self {
+        self.num_pfns = num_pfns;
+        self
+    }
+
+    pub fn with_pfn_config(mut self, config: NodeConfig) -> Self {
+        self.pfn_config = Some(config);
+        self
+    }
+
+    /// A faucet minting with the root key, added by `build_testnet`
+    pub fn with_faucet(mut self) -> Self {
+        self.faucet = true;
+        self
+    }
+
+    /// An indexer writing to the postgres database, added by `build_testnet`. It runs on a
+    /// public fullnode of its own, so it doesn't slow down the other nodes.
+    pub fn with_indexer(mut self, database_url: String) -> Self {
+        self.indexer_database_url = Some(database_url);
+        self
+    }
+
     // Gas is not enabled with this setup, it's enabled via forge instance.
     pub async fn build_inner(&mut self) -> anyhow::Result<LocalSwarm> {
         ::aptos_logger::Logger::new().init();
@@ -164,6 +203,137 @@ impl SwarmBuilder {
         );
         (swarm, tool, faucet)
     }
+
+    /// Builds the swarm, then adds the public fullnodes, the indexer and the faucet
+    pub async fn build_testnet(&mut self) -> LocalTestnet {
+        let mut swarm = self.build().await;
+        let version = swarm.versions().max().unwrap();
+        let vfns = swarm.full_nodes().map(|vfn| vfn.peer_id()).collect();
+        let pfn_config = self
+            .pfn_config
+            .clone()
+            .unwrap_or_else(NodeConfig::default_for_public_full_node);
+
+        let pfns = (0..self.num_pfns)
+            .map(|_| swarm.add_full_node(&version, pfn_config.clone()).unwrap())
+            .collect();
+        let indexer = self.indexer_database_url.as_ref().map(|database_url| {
+            let mut indexer_config = pfn_config.clone();
+            indexer_config.storage.enable_indexer = true;
+            indexer_config.indexer.enabled = true;
+            indexer_config.indexer.postgres_uri = Some(database_url.clone().into());
+            indexer_config.indexer.processor =
+                Some(aptos_indexer::processors::default_processor::NAME.to_string());
+            swarm.add_full_node(&version, indexer_config).unwrap()
+        });
+        for fullnode in swarm.full_nodes_mut() {
+            fullnode
+                .wait_until_healthy(
+                    Instant::now() + Duration::from_secs(FULLNODE_HEALTHY_TIMEOUT_SECS),
+                )
+                .await
+                .unwrap();
+        }
+
+        let faucet = if self.faucet {
+            let validator = swarm.validators().next().unwrap();
+            let port = get_available_port();
+            let handle = launch_faucet(
+                validator.rest_api_endpoint(),
+                swarm.root_key(),
+                swarm.chain_id(),
+                port,
+            );
+            let endpoint = format!("http://localhost:{}", port).parse().unwrap();
+            Some(LocalFaucet { endpoint, handle })
+        } else {
+            None
+        };
+
+        LocalTestnet {
+            swarm,
+            vfns,
+            pfns,
+            indexer,
+            faucet,
+        }
+    }
+}
+
+/// A local swarm with the other components of a network, see `SwarmBuilder::build_testnet`
+pub struct LocalTestnet {
+    pub swarm: LocalSwarm,
+    pub vfns: Vec<PeerId>,
+    pub pfns: Vec<PeerId>,
+    /// The public fullnode running the indexer
+    pub indexer: Option<PeerId>,
+    pub faucet: Option<LocalFaucet>,
+}
+
+impl LocalTestnet {
+    /// The REST and metrics endpoints of every component
+    pub fn endpoints(&self) -> Vec<ComponentEndpoints> {
+        let node_endpoints = |kind, node: &dyn Node| ComponentEndpoints {
+            kind,
+            name: node.name().to_string(),
+            rest_api: node.rest_api_endpoint(),
+            metrics: Some(node.inspection_service_endpoint().join("metrics").unwrap()),
+        };
+
+        let mut endpoints: Vec<_> = self
+            .swarm
+            .validators()
+            .map(|validator| node_endpoints(ComponentKind::Validator, validator as &dyn Node))
+            .collect();
+        for (kind, peer_ids) in [
+            (ComponentKind::ValidatorFullnode, &self.vfns),
+            (ComponentKind::PublicFullnode, &self.pfns),
+            (ComponentKind::Indexer, &self.indexer.into_iter().collect()),
+        ] {
+            for peer_id in peer_ids {
+                let fullnode = self.swarm.fullnode(*peer_id).unwrap();
+                endpoints.push(node_endpoints(kind, fullnode as &dyn Node));
+            }
+        }
+        if let Some(faucet) = &self.faucet {
+            endpoints.push(ComponentEndpoints {
+                kind: ComponentKind::Faucet,
+                name: "faucet".to_string(),
+                rest_api: faucet.endpoint.clone(),
+                metrics: None,
+            });
+        }
+        endpoints
+    }
+}
+
+pub struct LocalFaucet {
+    pub endpoint: Url,
+    handle: JoinHandle<()>,
+}
+
+impl Drop for LocalFaucet {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ComponentKind {
+    Validator,
+    ValidatorFullnode,
+    PublicFullnode,
+    Indexer,
+    Faucet,
+}
+
+#[derive(Clone, Debug)]
+pub struct ComponentEndpoints {
+    pub kind: ComponentKind,
+    pub name: String,
+    pub rest_api: Url,
+    /// The Prometheus metrics of the inspection service, the faucet has none
+    pub metrics: Option<Url>,
 }
 
 // Gas is not enabled with this setup, it's enabled via forge instance.
@@ -203,6 +373,9 @@ pub fn launch_faucet(
         chain_id,
         maximum_amount: None,
         do_not_delegate: true,
+        num_funders: 1,
+        funding_strategy: FundingStrategy::Mint,
+        abuse_protection: AbuseProtectionArgs::default(),
     };
     tokio::spawn(faucet.run())
 }
@@ -8,8 +8,14 @@ use crate::{
 use aptos_config::config::{BootstrappingMode, ContinuousSyncingMode, NodeConfig};
 use aptos_rest_client::Client as RestClient;
 use aptos_sdk::types::LocalAccount;
-use aptos_types::{account_address::AccountAddress, PeerId};
-use forge::{LocalSwarm, Node, NodeExt, Swarm, SwarmExt};
+use aptos_types::{
+    account_address::AccountAddress,
+    account_config::{CoinInfoResource, CORE_CODE_ADDRESS},
+    on_chain_config::ConfigurationResource,
+    PeerId,
+};
+use forge::{LocalSwarm, Node, NodeExt, ResourceToCheck, Swarm, SwarmExt};
+use move_core_types::move_resource::MoveStructType;
 use std::{
     sync::Arc,
     time::{Duration, Instant},
@@ -156,6 +162,47 @@ async fn test_full_node_continuous_sync_transactions() {
 }
 
 /// Creates a new full node using the given config and swarm
+#[tokio::test]
+async fn test_state_consistency_after_sync() {
+    // Create a validator swarm of 4 validator nodes, and a fullnode that syncs outputs
+    let mut swarm = new_local_swarm_with_aptos(4).await;
+    let mut vfn_config = NodeConfig::default_for_validator_full_node();
+    vfn_config.state_sync.state_sync_driver.bootstrapping_mode =
+        BootstrappingMode::ApplyTransactionOutputsFromGenesis;
+    vfn_config
+        .state_sync
+        .state_sync_driver
+        .continuous_syncing_mode = ContinuousSyncingMode::ApplyTransactionOutputs;
+    let vfn_peer_id = create_full_node(vfn_config, &mut swarm).await;
+
+    // Execute transactions and epoch changes while the fullnode is down, then let it catch up
+    test_full_node_sync(vfn_peer_id, &mut swarm, true).await;
+
+    // Verify that all nodes agree on the state from genesis to the latest version
+    let latest_version = swarm
+        .validators()
+        .next()
+        .unwrap()
+        .rest_client()
+        .get_ledger_information()
+        .await
+        .unwrap()
+        .into_inner()
+        .version;
+    let resources = vec![
+        ResourceToCheck::new(CORE_CODE_ADDRESS, CoinInfoResource::struct_tag()),
+        ResourceToCheck::new(CORE_CODE_ADDRESS, ConfigurationResource::struct_tag()),
+    ];
+    swarm
+        .check_state_consistency(
+            &[0, latest_version / 2, latest_version],
+            &resources,
+            Duration::from_secs(MAX_CATCH_UP_SECS),
+        )
+        .await
+        .unwrap();
+}
+
 async fn create_full_node(full_node_config: NodeConfig, swarm: &mut LocalSwarm) -> PeerId {
     let validator_peer_id = swarm.validators().next().unwrap().peer_id();
     let vfn_peer_id = swarm
@@ -132,7 +132,7 @@ async fn test_upgrade_flow() {
                 publish_addr,
                 "--package-dir",
                 package_path.to_str().unwrap(),
-                "--output",
+                "--output-file",
                 move_script_path.to_str().unwrap(),
             ])
             .output()
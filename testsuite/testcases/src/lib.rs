@@ -14,6 +14,7 @@ pub mod partial_nodes_down_test;
 pub mod performance_test;
 pub mod performance_with_fullnode_test;
 pub mod reconfiguration_test;
+pub mod soak_test;
 pub mod state_sync_performance;
 pub mod three_region_simulation_test;
 pub mod twin_validator_test;
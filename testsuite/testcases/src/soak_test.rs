@@ -0,0 +1,326 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::create_emitter_and_request;
+use anyhow::{anyhow, ensure, Context};
+use aptos_logger::info;
+use forge::{NetworkContext, NetworkTest, Node, NodeExt, Result, Swarm, SwarmExt, Test, TxnStats};
+use rand::SeedableRng;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::runtime::Builder;
+
+const EQUIVOCATIONS_METRIC: &str = "aptos_consensus_equivocations_count";
+const MEMPOOL_SIZE_METRIC: &str = "aptos_core_mempool_index_size";
+const STORAGE_SIZE_METRIC: &str = "aptos_rocksdb_properties";
+const SST_FILES_SIZE_PROPERTY: &str = "aptos_rocksdb_total-sst-files-size";
+
+/// Runs the network under the load of the emit job, e.g. a mix of transaction types, for the
+/// whole duration of the test, and checks the invariants below at every sample. The samples are
+/// reported as series, the raw data of the trends of the run.
+pub struct SoakTest {
+    pub sample_interval: Duration,
+    /// The maximum time between now and the timestamp of the latest ledger info of a validator
+    pub max_commit_lag: Duration,
+    /// The maximum number of transactions in the mempool of any node
+    pub max_mempool_size: i64,
+    /// The test fails once the total size of the mempools grew over that many samples in a row
+    pub max_mempool_growing_samples: usize,
+    /// The storage of a validator must not grow by more than that many bytes per committed
+    /// transaction, plus `storage_slack_bytes` for the compactions that are yet to run
+    pub max_storage_bytes_per_txn: u64,
+    pub storage_slack_bytes: u64,
+}
+
+impl Test for SoakTest {
+    fn name(&self) -> &'static str {
+        "soak test"
+    }
+}
+
+/// The state of the network at a point of the test
+#[derive(Clone, Debug)]
+struct SoakSample {
+    elapsed: Duration,
+    /// The highest ledger version of the validators
+    version: u64,
+    /// The highest commit lag of the validators
+    commit_lag: Duration,
+    /// The size of the biggest mempool, and of all the mempools
+    max_mempool_size: i64,
+    total_mempool_size: i64,
+    /// The size of the biggest storage of the validators, in bytes
+    storage_bytes: i64,
+    /// The equivocating votes and proposals observed by all the validators
+    equivocations: i64,
+}
+
+async fn mempool_size<N: Node + ?Sized>(node: &N) -> Result<i64> {
+    let size = node
+        .get_metric_with_fields_i64(
+            MEMPOOL_SIZE_METRIC,
+            HashMap::from([("index".to_string(), "system_ttl".to_string())]),
+        )
+        .await?;
+    Ok(size.unwrap_or(0))
+}
+
+async fn take_sample(swarm: &dyn Swarm, elapsed: Duration) -> Result<SoakSample> {
+    let now_usecs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_micros() as u64;
+    let mut sample = SoakSample {
+        elapsed,
+        version: 0,
+        commit_lag: Duration::ZERO,
+        max_mempool_size: 0,
+        total_mempool_size: 0,
+        storage_bytes: 0,
+        equivocations: 0,
+    };
+    for validator in swarm.validators() {
+        let state = validator
+            .rest_client()
+            .get_ledger_information()
+            .await
+            .with_context(|| format!("Unable to get the ledger of {}", validator.name()))?
+            .into_inner();
+        sample.version = sample.version.max(state.version);
+        sample.commit_lag = sample.commit_lag.max(Duration::from_micros(
+            now_usecs.saturating_sub(state.timestamp_usecs),
+        ));
+
+        // Summed over all the column families of all the databases of the validator
+        let storage_bytes = validator
+            .get_metric_with_fields_i64(
+                STORAGE_SIZE_METRIC,
+                HashMap::from([(
+                    "property_name".to_string(),
+                    SST_FILES_SIZE_PROPERTY.to_string(),
+                )]),
+            )
+            .await?;
+        sample.storage_bytes = sample.storage_bytes.max(storage_bytes.unwrap_or(0));
+        sample.equivocations += validator
+            .get_metric_with_fields_i64(EQUIVOCATIONS_METRIC, HashMap::new())
+            .await?
+            .unwrap_or(0);
+
+        let size = mempool_size(validator).await?;
+        sample.max_mempool_size = sample.max_mempool_size.max(size);
+        sample.total_mempool_size += size;
+    }
+    for full_node in swarm.full_nodes() {
+        let size = mempool_size(full_node).await?;
+        sample.max_mempool_size = sample.max_mempool_size.max(size);
+        sample.total_mempool_size += size;
+    }
+    Ok(sample)
+}
+
+impl SoakTest {
+    /// Checks the invariants on the samples so far, the last one being the latest
+    fn check_invariants(&self, samples: &[SoakSample]) -> Result<()> {
+        let (first, last) = match (samples.first(), samples.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(()),
+        };
+        ensure!(
+            last.equivocations == 0,
+            "Validators observed {} equivocations after {}s",
+            last.equivocations,
+            last.elapsed.as_secs()
+        );
+        ensure!(
+            last.commit_lag <= self.max_commit_lag,
+            "Commit lag of {}ms after {}s is above {}ms",
+            last.commit_lag.as_millis(),
+            last.elapsed.as_secs(),
+            self.max_commit_lag.as_millis()
+        );
+        ensure!(
+            last.max_mempool_size <= self.max_mempool_size,
+            "Mempool size of {} after {}s is above {}",
+            last.max_mempool_size,
+            last.elapsed.as_secs(),
+            self.max_mempool_size
+        );
+        if samples.len() > self.max_mempool_growing_samples {
+            let window = &samples[samples.len() - self.max_mempool_growing_samples - 1..];
+            ensure!(
+                !window
+                    .windows(2)
+                    .all(|w| w[1].total_mempool_size > w[0].total_mempool_size),
+                "Mempools grew over the last {} samples, to {} transactions after {}s",
+                self.max_mempool_growing_samples,
+                last.total_mempool_size,
+                last.elapsed.as_secs()
+            );
+        }
+
+        let committed_txns = last.version.saturating_sub(first.version);
+        let storage_growth = last
+            .storage_bytes
+            .saturating_sub(first.storage_bytes)
+            .max(0) as u64;
+        let max_storage_growth =
+            self.storage_slack_bytes + self.max_storage_bytes_per_txn * committed_txns;
+        ensure!(
+            storage_growth <= max_storage_growth,
+            "Storage grew by {} bytes for {} transactions after {}s, more than the {} bytes of the model",
+            storage_growth,
+            committed_txns,
+            last.elapsed.as_secs(),
+            max_storage_growth
+        );
+        Ok(())
+    }
+
+    fn report(&self, ctx: &mut NetworkContext, samples: &[SoakSample], stats: &TxnStats) {
+        let series = |value: fn(&SoakSample) -> f64| {
+            samples
+                .iter()
+                .map(|sample| (sample.elapsed.as_secs_f64(), value(sample)))
+                .collect::<Vec<_>>()
+        };
+        let name = self.name();
+        ctx.report
+            .report_series(name, "version", series(|s| s.version as f64));
+        ctx.report.report_series(
+            name,
+            "commit_lag_ms",
+            series(|s| s.commit_lag.as_millis() as f64),
+        );
+        ctx.report.report_series(
+            name,
+            "max_mempool_size",
+            series(|s| s.max_mempool_size as f64),
+        );
+        ctx.report.report_series(
+            name,
+            "total_mempool_size",
+            series(|s| s.total_mempool_size as f64),
+        );
+        ctx.report
+            .report_series(name, "storage_bytes", series(|s| s.storage_bytes as f64));
+
+        let max_commit_lag = samples
+            .iter()
+            .map(|s| s.commit_lag)
+            .max()
+            .unwrap_or_default();
+        ctx.report
+            .report_metric(name, "max_commit_lag_ms", max_commit_lag.as_millis() as f64);
+        if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+            let committed_txns = last.version.saturating_sub(first.version).max(1);
+            ctx.report.report_metric(
+                name,
+                "storage_bytes_per_txn",
+                (last.storage_bytes - first.storage_bytes) as f64 / committed_txns as f64,
+            );
+            ctx.report.report_txn_stats(
+                name.to_string(),
+                stats,
+                last.elapsed.max(Duration::from_secs(1)),
+            );
+        }
+    }
+}
+
+impl NetworkTest for SoakTest {
+    fn run<'t>(&self, ctx: &mut NetworkContext<'t>) -> Result<()> {
+        let nodes = ctx
+            .swarm()
+            .validators()
+            .map(|v| v.peer_id())
+            .chain(ctx.swarm().full_nodes().map(|v| v.peer_id()))
+            .collect::<Vec<_>>();
+        let emit_job_request = ctx.emit_job.clone();
+        let rng = SeedableRng::from_rng(ctx.core().rng())?;
+        let (mut emitter, emit_job_request) = create_emitter_and_request(
+            ctx.swarm(),
+            emit_job_request,
+            &nodes,
+            aptos_global_constants::GAS_UNIT_PRICE,
+            rng,
+        )
+        .context("create emitter")?;
+
+        let mut runtime_builder = Builder::new_multi_thread();
+        runtime_builder.disable_lifo_slot().enable_all();
+        runtime_builder.worker_threads(64);
+        let rt = runtime_builder
+            .build()
+            .map_err(|err| anyhow!("Failed to start runtime for transaction emitter. {}", err))?;
+
+        let start_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let (start_version, _) = rt
+            .block_on(ctx.swarm().get_client_with_newest_ledger_version())
+            .context("no clients replied for start version")?;
+        let job = rt
+            .block_on(emitter.start_job(ctx.swarm().chain_info().root_account, emit_job_request, 1))
+            .context("start emitter job")?;
+        info!(
+            "Soaking the network for {}s, sampling every {}s",
+            ctx.global_duration.as_secs(),
+            self.sample_interval.as_secs()
+        );
+
+        let start = Instant::now();
+        let mut samples = vec![];
+        let soak_result = loop {
+            let result = rt
+                .block_on(take_sample(ctx.swarm(), start.elapsed()))
+                .and_then(|sample| {
+                    info!("Soak test sample: {:?}", sample);
+                    samples.push(sample);
+                    self.check_invariants(&samples)
+                })
+                // Creates its own runtime, so must be called outside of ours
+                .and_then(|_| ctx.swarm().fork_check());
+            if result.is_err() {
+                break result;
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= ctx.global_duration {
+                break Ok(());
+            }
+            std::thread::sleep(self.sample_interval.min(ctx.global_duration - elapsed));
+        };
+        let actual_duration = start.elapsed();
+        let stats = rt
+            .block_on(emitter.stop_job(job))
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        // The trends are reported even when an invariant is broken, to help find out why
+        self.report(ctx, &samples, &stats);
+        soak_result.context("soak test invariants")?;
+
+        let end_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let (end_version, _) = rt
+            .block_on(ctx.swarm().get_client_with_newest_ledger_version())
+            .context("no clients replied for end version")?;
+        ctx.check_for_success(
+            &stats,
+            &actual_duration,
+            start_timestamp as i64,
+            end_timestamp as i64,
+            start_version,
+            end_version,
+        )
+        .context("check for success")?;
+
+        Ok(())
+    }
+}